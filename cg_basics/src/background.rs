@@ -0,0 +1,217 @@
+use colors::Color;
+use image::{Image, ImageBuffer};
+use math::{Point2, Vector3};
+use sampling::split;
+use traits::{Asin, Atan2, Clamp, ConvenientNumber, FloatingPoint, Half, One, Pi, Sqrt, Zero};
+
+// Backgrounds for rays that leave the scene. Screen positions run from (0, 0) in the lower left to
+// (1, 1) in the upper right corner of the image. An environment is an equirectangular image laid
+// out like the one of an environment light: the first row lies at the zenith, the center of the
+// image looks along the negative z axis.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Background<C: Color> {
+    VerticalGradient { top: C, bottom: C },
+    RadialGradient { center: C, edge: C },
+    Horizon { zenith: C, horizon: C, ground: C },
+    Environment { image: ImageBuffer<C> },
+}
+
+impl<C: Color> Background<C>
+where
+    C::ChannelType: FloatingPoint + ConvenientNumber,
+    u16: Into<C::ChannelType>,
+{
+    pub fn color_for(
+        &self,
+        direction: Vector3<C::ChannelType>,
+        screen: Point2<C::ChannelType>,
+    ) -> C {
+        let zero = C::ChannelType::zero();
+        let one = C::ChannelType::one();
+
+        match *self {
+            Background::Environment { .. } => self.environment(direction).unwrap(),
+            Background::VerticalGradient { top, bottom } => {
+                mix(bottom, top, screen.y.clamp(zero, one))
+            }
+            Background::RadialGradient { center, edge } => {
+                // The corners of the image are at a distance of one.
+                let x = screen.x - one.half();
+                let y = screen.y - one.half();
+                let distance = ((x * x + y * y) / one.half()).sqrt();
+                mix(center, edge, distance.clamp(zero, one))
+            }
+            Background::Horizon {
+                zenith,
+                horizon,
+                ground,
+            } => {
+                if direction.y >= zero {
+                    mix(horizon, zenith, direction.y.clamp(zero, one))
+                } else {
+                    mix(horizon, ground, (-direction.y).clamp(zero, one))
+                }
+            }
+        }
+    }
+
+    // The color seen along a direction by any ray that leaves the scene, not only by camera rays.
+    // None for the procedural backgrounds, which are laid out on the image.
+    pub fn environment(&self, direction: Vector3<C::ChannelType>) -> Option<C> {
+        let Background::Environment { image } = self else {
+            return None;
+        };
+        let one = C::ChannelType::one();
+        let pi = C::ChannelType::PI;
+        let size = image.size();
+
+        let (x, _) = split(
+            (direction.x.atan2(-direction.z) / pi).half() + one.half(),
+            size.x,
+        );
+        let (y, _) = split(
+            one.half() - direction.y.clamp(-one, one).asin() / pi,
+            size.y,
+        );
+        Some(image.get(Point2::new(x, y)))
+    }
+}
+
+fn mix<C: Color>(a: C, b: C, t: C::ChannelType) -> C
+where
+    C::ChannelType: FloatingPoint,
+{
+    a * (C::ChannelType::one() - t) + b * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::RGB;
+    use image::WritableImage;
+    use math::Vector2;
+
+    macro_rules! vertical_gradient {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let background = Background::VerticalGradient {
+                    top: RGB::<$type>::new(1.0, 1.0, 1.0),
+                    bottom: RGB::<$type>::new(0.0, 0.0, 0.0),
+                };
+                let direction = Vector3::new(0.0, 0.0, -1.0);
+
+                assert_eq!(
+                    background.color_for(direction, Point2::new(0.3, 1.0)),
+                    RGB::new(1.0, 1.0, 1.0)
+                );
+                assert_eq!(
+                    background.color_for(direction, Point2::new(0.3, 0.5)),
+                    RGB::new(0.5, 0.5, 0.5)
+                );
+                assert_eq!(
+                    background.color_for(direction, Point2::new(0.3, 0.0)),
+                    RGB::new(0.0, 0.0, 0.0)
+                );
+            }
+        };
+    }
+
+    vertical_gradient! { f32, vertical_gradient_f32 }
+    vertical_gradient! { f64, vertical_gradient_f64 }
+
+    macro_rules! radial_gradient {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let background = Background::RadialGradient {
+                    center: RGB::<$type>::new(1.0, 0.5, 0.0),
+                    edge: RGB::<$type>::new(0.0, 0.0, 0.0),
+                };
+                let direction = Vector3::new(0.0, 0.0, -1.0);
+
+                assert_eq!(
+                    background.color_for(direction, Point2::new(0.5, 0.5)),
+                    RGB::new(1.0, 0.5, 0.0)
+                );
+                assert_eq!(
+                    background.color_for(direction, Point2::new(1.0, 0.0)),
+                    RGB::new(0.0, 0.0, 0.0)
+                );
+            }
+        };
+    }
+
+    radial_gradient! { f32, radial_gradient_f32 }
+    radial_gradient! { f64, radial_gradient_f64 }
+
+    macro_rules! horizon_background {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let zenith = RGB::<$type>::new(0.0, 0.0, 1.0);
+                let horizon = RGB::<$type>::new(1.0, 1.0, 1.0);
+                let ground = RGB::<$type>::new(0.5, 0.25, 0.0);
+                let background = Background::Horizon {
+                    zenith,
+                    horizon,
+                    ground,
+                };
+                let screen = Point2::new(0.5, 0.5);
+
+                assert_eq!(
+                    background.color_for(Vector3::new(0.0, 1.0, 0.0), screen),
+                    zenith
+                );
+                assert_eq!(
+                    background.color_for(Vector3::new(0.0, 0.0, -1.0), screen),
+                    horizon
+                );
+                assert_eq!(
+                    background.color_for(Vector3::new(0.0, -1.0, 0.0), screen),
+                    ground
+                );
+            }
+        };
+    }
+
+    horizon_background! { f32, horizon_background_f32 }
+    horizon_background! { f64, horizon_background_f64 }
+
+    macro_rules! environment_background {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let sky = RGB::<$type>::new(0.0, 0.0, 1.0);
+                let ground = RGB::<$type>::new(0.5, 0.25, 0.0);
+                let behind = RGB::<$type>::new(1.0, 0.0, 0.0);
+                let mut image = ImageBuffer::new(Vector2::new(4, 2), sky);
+                for x in 0..4 {
+                    *image.get_mut(Point2::new(x, 1)) = ground;
+                }
+                *image.get_mut(Point2::new(0, 0)) = behind;
+                let background = Background::Environment { image };
+                let screen = Point2::new(0.5, 0.5);
+
+                let up = Vector3::new(0.0, 1.0, 0.0);
+                let down = Vector3::new(0.0, -1.0, 0.0);
+                let ahead = Vector3::<$type>::new(0.0, 0.5, -1.0).normalized();
+                let back = Vector3::<$type>::new(-0.1, 0.5, 1.0).normalized();
+                assert_eq!(background.color_for(up, screen), sky);
+                assert_eq!(background.color_for(down, screen), ground);
+                assert_eq!(background.color_for(ahead, screen), sky);
+                assert_eq!(background.environment(back), Some(behind));
+
+                let gradient = Background::VerticalGradient {
+                    top: sky,
+                    bottom: ground,
+                };
+                assert_eq!(gradient.environment(up), None);
+            }
+        };
+    }
+
+    environment_background! { f32, environment_background_f32 }
+    environment_background! { f64, environment_background_f64 }
+}
@@ -1,11 +1,94 @@
+use std::ops::{Add, Div, Mul, Sub};
+
+use math::{Point3, Vector3};
+use traits::{Abs, ConvenientNumber, FloatingPoint, One, SelfMulNumber, Sqrt, Zero};
+
+mod camera_path;
+mod cylindrical_camera;
 mod fisheye_camera;
+mod focus_pull;
+mod lens_distortion;
+mod ods_camera;
 mod orthographic_camera;
 mod perspective_camera;
+mod physical_camera;
 mod pinhole_camera;
+mod shutter;
 mod spherical_camera;
+mod stereo_camera;
 
+pub use camera_path::{CameraKeyframe, CameraPath};
+pub use cylindrical_camera::CylindricalCamera;
 pub use fisheye_camera::FisheyeCamera;
+pub use focus_pull::{FocusKeyframe, FocusPull, FocusTarget};
+pub use lens_distortion::LensDistortion;
+pub use ods_camera::OdsCamera;
 pub use orthographic_camera::OrthographicCamera;
 pub use perspective_camera::PerspectiveCamera;
+pub use physical_camera::PhysicalCamera;
 pub use pinhole_camera::PinholeCamera;
+pub use shutter::Shutter;
 pub use spherical_camera::SphericalCamera;
+pub use stereo_camera::{Eye, StereoCamera};
+
+// The gaze direction from the eye to the target and an up vector for it. An up vector that is
+// (almost) parallel to the gaze, e.g. for a camera looking straight down, leaves the orientation of
+// the camera undefined. It is replaced by the axis that is the most perpendicular to the gaze.
+pub fn gaze_and_up<T>(e: Point3<T>, target: Point3<T>, t: Vector3<T>) -> (Vector3<T>, Vector3<T>)
+where
+    T: SelfMulNumber<<T as Div>::Output> + One + Zero,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    <T as Mul>::Output: Add<Output = <T as Mul>::Output>
+        + Sub<Output = <T as Mul>::Output>
+        + Sqrt<Output = T>
+        + Zero,
+{
+    let g = target - e;
+    let w = g.normalized();
+
+    if Vector3::cross(w, t.normalized()).magnitude() > <T as Div>::Output::EPSILON.sqrt() {
+        return (g, t);
+    }
+
+    let (x, y, z) = (w.x.abs(), w.y.abs(), w.z.abs());
+    let up = if x <= y && x <= z {
+        Vector3::new(T::one(), T::zero(), T::zero())
+    } else if y <= z {
+        Vector3::new(T::zero(), T::one(), T::zero())
+    } else {
+        Vector3::new(T::zero(), T::zero(), T::one())
+    };
+
+    (g, up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! gaze_and_up {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let e = Point3::new(1.0 as $type, 2.0, 3.0);
+                let up = Vector3::new(0.0 as $type, 1.0, 0.0);
+
+                let (g, t) = gaze_and_up(e, Point3::new(1.0, 2.0, -1.0), up);
+                assert_eq!(g, Vector3::new(0.0, 0.0, -4.0));
+                assert_eq!(t, up);
+
+                // Looking straight down, the up vector is parallel to the gaze.
+                let (g, t) = gaze_and_up(e, Point3::new(1.0, -5.0, 3.0), up);
+                assert_eq!(g, Vector3::new(0.0, -7.0, 0.0));
+                assert_eq!(t, Vector3::new(1.0, 0.0, 0.0));
+
+                let (_, t) =
+                    gaze_and_up(e, Point3::new(1.0, 2.0, 0.0), Vector3::new(0.0, 0.0, 2.0));
+                assert_eq!(t, Vector3::new(1.0, 0.0, 0.0));
+            }
+        };
+    }
+
+    gaze_and_up! { f32, gaze_and_up_f32 }
+    gaze_and_up! { f64, gaze_and_up_f64 }
+}
@@ -0,0 +1,203 @@
+use std::ops::Div;
+
+use math::{Point3, Vector3};
+use traits::{ConvenientNumber, FloatingPoint, Half, One, SelfMulNumber, TotalCmp, Zero};
+
+// Where a camera is at a point in time and where it looks at.
+pub struct CameraKeyframe<T>
+where
+    T: Div,
+{
+    pub time: <T as Div>::Output,
+    pub eye_position: Point3<T>,
+    pub look_at: Point3<T>,
+    pub up_vector: Vector3<T>,
+}
+
+impl<T> CameraKeyframe<T>
+where
+    T: Div + Zero + One,
+{
+    pub fn new(
+        time: <T as Div>::Output,
+        eye_position: Point3<T>,
+        look_at: Point3<T>,
+    ) -> CameraKeyframe<T> {
+        CameraKeyframe {
+            time,
+            eye_position,
+            look_at,
+            up_vector: Vector3::new(Zero::zero(), One::one(), Zero::zero()),
+        }
+    }
+
+    pub fn with_up_vector(self, up_vector: Vector3<T>) -> CameraKeyframe<T> {
+        CameraKeyframe { up_vector, ..self }
+    }
+}
+
+impl<T> Clone for CameraKeyframe<T>
+where
+    T: Div + Copy,
+    <T as Div>::Output: Copy,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for CameraKeyframe<T>
+where
+    T: Div + Copy,
+    <T as Div>::Output: Copy,
+{
+}
+
+// The flight of a camera through keyframes, e.g. a turntable around an object or a fly-through
+// of a scene. The eye position and the target pass through the keyframes on Catmull-Rom splines,
+// so the camera moves without jerks. Before the first and after the last keyframe, the camera
+// rests there.
+pub struct CameraPath<T>
+where
+    T: Div,
+{
+    keyframes: Vec<CameraKeyframe<T>>,
+}
+
+impl<T> Clone for CameraPath<T>
+where
+    T: Div + Copy,
+    <T as Div>::Output: Copy,
+{
+    fn clone(&self) -> Self {
+        CameraPath {
+            keyframes: self.keyframes.clone(),
+        }
+    }
+}
+
+impl<T> CameraPath<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+{
+    pub fn new(keyframes: Vec<CameraKeyframe<T>>) -> CameraPath<T> {
+        assert!(!keyframes.is_empty());
+
+        let mut keyframes = keyframes;
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        CameraPath { keyframes }
+    }
+
+    pub fn keyframes(&self) -> &[CameraKeyframe<T>] {
+        &self.keyframes
+    }
+
+    // The eye position, the gaze direction and the up vector at the time.
+    pub fn pose(&self, time: <T as Div>::Output) -> (Point3<T>, Vector3<T>, Vector3<T>) {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len() - 1;
+        let next = keyframes.partition_point(|keyframe| keyframe.time <= time);
+
+        let (eye_position, look_at, up_vector) = if next == 0 {
+            let first = &keyframes[0];
+            (first.eye_position, first.look_at, first.up_vector)
+        } else if next > last {
+            let last = &keyframes[last];
+            (last.eye_position, last.look_at, last.up_vector)
+        } else {
+            let (from, to) = (&keyframes[next - 1], &keyframes[next]);
+            let s = (time - from.time) / (to.time - from.time);
+            let before = &keyframes[next.saturating_sub(2)];
+            let after = &keyframes[(next + 1).min(last)];
+
+            let spline = |position: fn(&CameraKeyframe<T>) -> Point3<T>| {
+                catmull_rom(
+                    [
+                        position(before),
+                        position(from),
+                        position(to),
+                        position(after),
+                    ],
+                    s,
+                )
+            };
+            let up_vector = from.up_vector * (<T as Div>::Output::one() - s) + to.up_vector * s;
+
+            (
+                spline(|keyframe| keyframe.eye_position),
+                spline(|keyframe| keyframe.look_at),
+                up_vector,
+            )
+        };
+
+        (eye_position, look_at - eye_position, up_vector)
+    }
+}
+
+// The point between the second and the third one of a uniform Catmull-Rom spline.
+fn catmull_rom<T>(points: [Point3<T>; 4], s: <T as Div>::Output) -> Point3<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+{
+    let one = <T as Div>::Output::one();
+    let two = one + one;
+    let three = two + one;
+    let four = two + two;
+    let s2 = s * s;
+    let s3 = s2 * s;
+
+    let w0 = (two * s2 - s3 - s).half();
+    let w2 = (four * s2 + s - three * s3).half();
+    let w3 = (s3 - s2).half();
+
+    let [p0, p1, p2, p3] = points;
+    p1 + (p0 - p1) * w0 + (p2 - p1) * w2 + (p3 - p1) * w3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! camera_path_pose {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let target = Point3::<$type>::new(0.0, 1.0, 0.0);
+                let path = CameraPath::new(vec![
+                    CameraKeyframe::new(2.0, Point3::new(0.0, 1.0, -4.0), target),
+                    CameraKeyframe::new(0.0, Point3::new(0.0, 1.0, 4.0), target),
+                    CameraKeyframe::new(1.0, Point3::new(4.0, 1.0, 0.0), target)
+                        .with_up_vector(Vector3::new(0.0, 0.0, 1.0)),
+                ]);
+                assert_eq!(path.keyframes()[1].time, 1.0);
+
+                // The keyframes are passed exactly and held before and after the path.
+                assert_eq!(
+                    path.pose(0.0),
+                    (
+                        Point3::new(0.0, 1.0, 4.0),
+                        Vector3::new(0.0, 0.0, -4.0),
+                        Vector3::new(0.0, 1.0, 0.0)
+                    )
+                );
+                assert_eq!(path.pose(-1.0), path.pose(0.0));
+                assert_eq!(path.pose(1.0).0, Point3::new(4.0, 1.0, 0.0));
+                assert_eq!(path.pose(1.0).2, Vector3::new(0.0, 0.0, 1.0));
+                assert_eq!(path.pose(5.0).0, Point3::new(0.0, 1.0, -4.0));
+
+                // Between the keyframes, the camera swings around the target.
+                let (eye_position, gaze, up_vector) = path.pose(0.5);
+                assert!(eye_position.x > 2.0 && eye_position.z > 2.0);
+                assert!((eye_position.y - 1.0).abs() < 0.0001);
+                assert!(((eye_position + gaze) - target).magnitude() < 0.0001);
+                assert_eq!(up_vector, Vector3::new(0.0, 0.5, 0.5));
+            }
+        };
+    }
+
+    camera_path_pose! { f32, camera_path_pose_f32 }
+    camera_path_pose! { f64, camera_path_pose_f64 }
+}
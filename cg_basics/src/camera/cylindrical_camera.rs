@@ -0,0 +1,69 @@
+use std::ops::{Div, Mul};
+
+use math::{Point3, Vector3};
+use traits::{ConvenientNumber, FloatingPoint, Number, SelfMulNumber, Sqrt};
+use units::angle::Radians;
+
+use super::{gaze_and_up, Shutter};
+
+// Projects the scene onto a cylinder around the eye position, for strip panoramas. The columns of
+// the image are evenly spaced angles around the up vector, up to a full turn. Unlike the rows of a
+// spherical panorama, the rows are evenly spaced heights on the cylinder, so vertical lines stay
+// straight and vertical.
+pub struct CylindricalCamera<T>
+where
+    T: Div,
+{
+    pub e: Point3<T>,
+    pub u: Vector3<<T as Div>::Output>,
+    pub v: Vector3<<T as Div>::Output>,
+    pub w: Vector3<<T as Div>::Output>,
+    pub horizontal_field_of_view: Radians<<T as Div>::Output>,
+    pub vertical_field_of_view: Radians<<T as Div>::Output>,
+    pub shutter: Shutter<<T as Div>::Output>,
+}
+
+impl<T> CylindricalCamera<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+{
+    pub fn new(
+        e: Point3<T>,
+        g: Vector3<T>,
+        t: Vector3<T>,
+        horizontal_field_of_view: Radians<<T as Div>::Output>,
+        vertical_field_of_view: Radians<<T as Div>::Output>,
+    ) -> CylindricalCamera<T> {
+        let w = -g.normalized();
+        let u = Vector3::cross(t, w).normalized();
+        let v = Vector3::cross(w, u).normalized();
+
+        CylindricalCamera {
+            e,
+            u,
+            v,
+            w,
+            horizontal_field_of_view,
+            vertical_field_of_view,
+            shutter: Shutter::default(),
+        }
+    }
+
+    // Looks from the eye at the target instead of along a gaze direction.
+    pub fn look_at(
+        e: Point3<T>,
+        target: Point3<T>,
+        t: Vector3<T>,
+        horizontal_field_of_view: Radians<<T as Div>::Output>,
+        vertical_field_of_view: Radians<<T as Div>::Output>,
+    ) -> CylindricalCamera<T> {
+        let (g, t) = gaze_and_up(e, target, t);
+        CylindricalCamera::new(e, g, t, horizontal_field_of_view, vertical_field_of_view)
+    }
+
+    pub fn with_shutter(self, shutter: Shutter<<T as Div>::Output>) -> CylindricalCamera<T> {
+        CylindricalCamera { shutter, ..self }
+    }
+}
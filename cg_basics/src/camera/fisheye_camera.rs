@@ -4,6 +4,8 @@ use math::{Point3, Vector3};
 use traits::{ConvenientNumber, FloatingPoint, Half, Number, SelfMulNumber, Sqrt};
 use units::angle::Radians;
 
+use super::{gaze_and_up, Shutter};
+
 pub struct FisheyeCamera<T>
 where
     T: Div,
@@ -13,6 +15,7 @@ where
     pub v: Vector3<<T as Div>::Output>,
     pub w: Vector3<<T as Div>::Output>,
     pub psi: Radians<<T as Div>::Output>,
+    pub shutter: Shutter<<T as Div>::Output>,
 }
 
 impl<T> FisheyeCamera<T>
@@ -37,6 +40,22 @@ where
             v,
             w,
             psi: psi.half(),
+            shutter: Shutter::default(),
         }
     }
+
+    // Looks from the eye at the target instead of along a gaze direction.
+    pub fn look_at(
+        e: Point3<T>,
+        target: Point3<T>,
+        t: Vector3<T>,
+        psi: Radians<<T as Div>::Output>,
+    ) -> FisheyeCamera<T> {
+        let (g, t) = gaze_and_up(e, target, t);
+        FisheyeCamera::new(e, g, t, psi)
+    }
+
+    pub fn with_shutter(self, shutter: Shutter<<T as Div>::Output>) -> FisheyeCamera<T> {
+        FisheyeCamera { shutter, ..self }
+    }
 }
@@ -0,0 +1,144 @@
+use std::ops::Div;
+
+use traits::{ConvenientNumber, FloatingPoint, One, SelfMulNumber, TotalCmp};
+
+// What a camera focuses on: a fixed distance or an object of the scene, looked up by its name.
+#[derive(Debug, PartialEq, Clone)]
+pub enum FocusTarget<T> {
+    Distance(T),
+    Object(String),
+}
+
+// Where the focus is at a point in time.
+pub struct FocusKeyframe<T>
+where
+    T: Div,
+{
+    pub time: <T as Div>::Output,
+    pub target: FocusTarget<T>,
+}
+
+impl<T> FocusKeyframe<T>
+where
+    T: Div,
+{
+    pub fn new(time: <T as Div>::Output, target: FocusTarget<T>) -> FocusKeyframe<T> {
+        FocusKeyframe { time, target }
+    }
+}
+
+impl<T> Clone for FocusKeyframe<T>
+where
+    T: Div + Clone,
+    <T as Div>::Output: Clone,
+{
+    fn clone(&self) -> Self {
+        FocusKeyframe {
+            time: self.time.clone(),
+            target: self.target.clone(),
+        }
+    }
+}
+
+// The focus of a camera over time, e.g. a rack focus from one object to another. Between two
+// keyframes, the focus distance eases from one target to the other like a focus puller turning
+// the ring. Before the first and after the last keyframe, the focus rests there.
+pub struct FocusPull<T>
+where
+    T: Div,
+{
+    keyframes: Vec<FocusKeyframe<T>>,
+}
+
+impl<T> Clone for FocusPull<T>
+where
+    T: Div + Clone,
+    <T as Div>::Output: Clone,
+{
+    fn clone(&self) -> Self {
+        FocusPull {
+            keyframes: self.keyframes.clone(),
+        }
+    }
+}
+
+impl<T> FocusPull<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+{
+    pub fn new(keyframes: Vec<FocusKeyframe<T>>) -> FocusPull<T> {
+        assert!(!keyframes.is_empty());
+
+        let mut keyframes = keyframes;
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        FocusPull { keyframes }
+    }
+
+    pub fn keyframes(&self) -> &[FocusKeyframe<T>] {
+        &self.keyframes
+    }
+
+    // The focus distance at the time. Objects are resolved to their distance at that time, so a
+    // focus on a moving object follows it. None if a target can not be resolved.
+    pub fn focus_distance(
+        &self,
+        time: <T as Div>::Output,
+        resolve: impl Fn(&FocusTarget<T>) -> Option<T>,
+    ) -> Option<T> {
+        let keyframes = &self.keyframes;
+        let next = keyframes.partition_point(|keyframe| keyframe.time <= time);
+
+        if next == 0 {
+            resolve(&keyframes[0].target)
+        } else if next == keyframes.len() {
+            resolve(&keyframes[next - 1].target)
+        } else {
+            let (from, to) = (&keyframes[next - 1], &keyframes[next]);
+            let s = (time - from.time) / (to.time - from.time);
+            let one = <T as Div>::Output::one();
+            let eased = s * s * (one + one + one - (one + one) * s);
+
+            let from = resolve(&from.target)?;
+            let to = resolve(&to.target)?;
+            Some(from + (to - from) * eased)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! focus_pull_focus_distance {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let pull = FocusPull::<$type>::new(vec![
+                    FocusKeyframe::new(2.0, FocusTarget::Object(String::from("ball"))),
+                    FocusKeyframe::new(0.0, FocusTarget::Distance(1.0)),
+                ]);
+                let resolve = |target: &FocusTarget<$type>| match target {
+                    FocusTarget::Distance(distance) => Some(*distance),
+                    FocusTarget::Object(name) if name == "ball" => Some(5.0),
+                    FocusTarget::Object(_) => None,
+                };
+
+                assert_eq!(pull.focus_distance(-1.0, resolve), Some(1.0));
+                assert_eq!(pull.focus_distance(0.0, resolve), Some(1.0));
+                assert_eq!(pull.focus_distance(1.0, resolve), Some(3.0));
+                assert_eq!(pull.focus_distance(3.0, resolve), Some(5.0));
+
+                // The pull starts and ends slowly.
+                assert!(pull.focus_distance(0.2, resolve).unwrap() < 1.4);
+                assert!(pull.focus_distance(1.8, resolve).unwrap() > 4.6);
+
+                assert_eq!(pull.focus_distance(1.0, |_| None), None);
+            }
+        };
+    }
+
+    focus_pull_focus_distance! { f32, focus_pull_focus_distance_f32 }
+    focus_pull_focus_distance! { f64, focus_pull_focus_distance_f64 }
+}
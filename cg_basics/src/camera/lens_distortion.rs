@@ -0,0 +1,109 @@
+use math::Point2;
+use traits::{FloatingPoint, Zero};
+
+// The distortion of a real lens after the model of Brown and Conrady, which camera calibration
+// tools commonly estimate. It moves a point of the image plane of an ideal pinhole camera at
+// distance one to where the lens actually projects it. Radial distortion bends straight lines
+// into barrels (k1 < 0) or pincushions (k1 > 0), tangential distortion is caused by a lens that
+// is not parallel to the sensor. Without any coefficients, the lens is ideal.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LensDistortion<V> {
+    pub k1: V,
+    pub k2: V,
+    pub p1: V,
+    pub p2: V,
+}
+
+impl<V: FloatingPoint> LensDistortion<V> {
+    // The number of iterations spent to undo the distortion. Plenty for the distortions of real
+    // lenses.
+    const ITERATIONS: usize = 20;
+
+    pub fn new(k1: V, k2: V, p1: V, p2: V) -> LensDistortion<V> {
+        LensDistortion { k1, k2, p1, p2 }
+    }
+
+    pub fn distort(&self, p: Point2<V>) -> Point2<V> {
+        let two = V::one() + V::one();
+        let xy = p.x * p.y;
+        let r2 = p.x * p.x + p.y * p.y;
+        let radial = V::one() + self.k1 * r2 + self.k2 * r2 * r2;
+
+        Point2::new(
+            p.x * radial + two * self.p1 * xy + self.p2 * (r2 + two * p.x * p.x),
+            p.y * radial + self.p1 * (r2 + two * p.y * p.y) + two * self.p2 * xy,
+        )
+    }
+
+    // Finds the point the lens distorts to p. p is given relative to the center of the image, in
+    // units in which the image plane is the distance away from the eye. There is no closed form,
+    // so the point is found by fixed-point iteration. Strong distortions do not reach all points
+    // of the image, e.g. the corners of a wide angle lens with barrel distortion, which leaves
+    // them without a point.
+    pub fn undistort(&self, p: Point2<V>, distance: V) -> Option<Point2<V>> {
+        if *self == LensDistortion::default() {
+            return Some(p);
+        }
+
+        let target = Point2::new(p.x / distance, p.y / distance);
+        let mut q = target;
+        for _ in 0..Self::ITERATIONS {
+            let distorted = self.distort(q);
+            q = Point2::new(q.x + target.x - distorted.x, q.y + target.y - distorted.y);
+        }
+
+        let distorted = self.distort(q);
+        let error = (target.x - distorted.x).abs() + (target.y - distorted.y).abs();
+        (error < V::EPSILON.sqrt()).then(|| Point2::new(q.x * distance, q.y * distance))
+    }
+}
+
+impl<V: Zero> Default for LensDistortion<V> {
+    fn default() -> Self {
+        LensDistortion {
+            k1: Zero::zero(),
+            k2: Zero::zero(),
+            p1: Zero::zero(),
+            p2: Zero::zero(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! lens_distortion {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let p = Point2::<$type>::new(0.3, -0.2);
+
+                let ideal = LensDistortion::<$type>::default();
+                assert_eq!(ideal.distort(p), p);
+                assert_eq!(ideal.undistort(p, 2.0), Some(p));
+
+                // Barrel distortion pulls points towards the center.
+                let barrel = LensDistortion::<$type>::new(-0.2, 0.05, 0.0, 0.0);
+                let distorted = barrel.distort(p);
+                assert!(distorted.x < p.x && distorted.x > 0.0);
+                assert!(distorted.y > p.y && distorted.y < 0.0);
+
+                let lens = LensDistortion::<$type>::new(-0.2, 0.05, 0.01, -0.005);
+                let distorted = lens.distort(p);
+                let undistorted = lens
+                    .undistort(Point2::new(distorted.x * 2.0, distorted.y * 2.0), 2.0)
+                    .unwrap();
+                assert!((undistorted.x - p.x * 2.0).abs() < 0.0001);
+                assert!((undistorted.y - p.y * 2.0).abs() < 0.0001);
+
+                // Barrel distortion this strong never reaches the corner.
+                let fisheye = LensDistortion::<$type>::new(-0.2, 0.0, 0.0, 0.0);
+                assert_eq!(fisheye.undistort(Point2::new(1.5, 1.0), 1.0), None);
+            }
+        };
+    }
+
+    lens_distortion! { f32, lens_distortion_f32 }
+    lens_distortion! { f64, lens_distortion_f64 }
+}
@@ -0,0 +1,65 @@
+use std::ops::{Div, Mul};
+
+use math::{Point3, Vector3};
+use traits::{ConvenientNumber, FloatingPoint, Number, SelfMulNumber, Sqrt};
+
+use super::{gaze_and_up, Shutter};
+
+// An omni-directional stereo camera renders 360° panoramas for both eyes, as used by VR video
+// players. The image holds two equirectangular panoramas, the one of the left eye on top of the
+// one of the right eye. The rays start on a circle with the interocular distance as diameter
+// around the eye position, where a head turned towards the direction of the ray would have its
+// eyes.
+pub struct OdsCamera<T>
+where
+    T: Div,
+{
+    pub e: Point3<T>,
+    pub u: Vector3<<T as Div>::Output>,
+    pub v: Vector3<<T as Div>::Output>,
+    pub w: Vector3<<T as Div>::Output>,
+    pub interocular_distance: T,
+    pub shutter: Shutter<<T as Div>::Output>,
+}
+
+impl<T> OdsCamera<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+{
+    pub fn new(
+        e: Point3<T>,
+        g: Vector3<T>,
+        t: Vector3<T>,
+        interocular_distance: T,
+    ) -> OdsCamera<T> {
+        let w = -g.normalized();
+        let u = Vector3::cross(t, w).normalized();
+        let v = Vector3::cross(w, u).normalized();
+
+        OdsCamera {
+            e,
+            u,
+            v,
+            w,
+            interocular_distance,
+            shutter: Shutter::default(),
+        }
+    }
+
+    // Looks from the eye at the target instead of along a gaze direction.
+    pub fn look_at(
+        e: Point3<T>,
+        target: Point3<T>,
+        t: Vector3<T>,
+        interocular_distance: T,
+    ) -> OdsCamera<T> {
+        let (g, t) = gaze_and_up(e, target, t);
+        OdsCamera::new(e, g, t, interocular_distance)
+    }
+
+    pub fn with_shutter(self, shutter: Shutter<<T as Div>::Output>) -> OdsCamera<T> {
+        OdsCamera { shutter, ..self }
+    }
+}
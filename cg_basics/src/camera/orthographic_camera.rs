@@ -3,6 +3,8 @@ use std::ops::{Div, Mul};
 use math::{Point3, Vector3};
 use traits::{ConvenientNumber, FloatingPoint, Number, SelfMulNumber, Sqrt};
 
+use super::{gaze_and_up, Shutter};
+
 pub struct OrthographicCamera<T>
 where
     T: Div,
@@ -12,6 +14,7 @@ where
     pub v: Vector3<<T as Div>::Output>,
     pub w: Vector3<<T as Div>::Output>,
     pub scale: <T as Div>::Output,
+    pub shutter: Shutter<<T as Div>::Output>,
 }
 
 impl<T> OrthographicCamera<T>
@@ -30,7 +33,29 @@ where
         let u = Vector3::cross(t, w).normalized();
         let v = Vector3::cross(w, u).normalized();
 
-        OrthographicCamera { e, u, v, w, scale }
+        OrthographicCamera {
+            e,
+            u,
+            v,
+            w,
+            scale,
+            shutter: Shutter::default(),
+        }
+    }
+
+    // Looks from the eye at the target instead of along a gaze direction.
+    pub fn look_at(
+        e: Point3<T>,
+        target: Point3<T>,
+        t: Vector3<T>,
+        scale: <T as Div>::Output,
+    ) -> OrthographicCamera<T> {
+        let (g, t) = gaze_and_up(e, target, t);
+        OrthographicCamera::new(e, g, t, scale)
+    }
+
+    pub fn with_shutter(self, shutter: Shutter<<T as Div>::Output>) -> OrthographicCamera<T> {
+        OrthographicCamera { shutter, ..self }
     }
 }
 
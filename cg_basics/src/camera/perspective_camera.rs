@@ -1,9 +1,12 @@
 use std::ops::{Div, Mul};
 
 use math::{Point3, Vector3};
+use sampling::Aperture;
 use traits::{ConvenientNumber, FloatingPoint, Half, Number, SelfMulNumber, Sqrt};
 use units::angle::Radians;
 
+use super::{gaze_and_up, LensDistortion, Shutter};
+
 pub struct PerspectiveCamera<T>
 where
     T: Div,
@@ -15,6 +18,9 @@ where
     pub vertical_field_of_view: Radians<<T as Div>::Output>,
     pub lens_radius: T,
     pub focal_length: T,
+    pub aperture: Aperture<<T as Div>::Output>,
+    pub distortion: LensDistortion<<T as Div>::Output>,
+    pub shutter: Shutter<<T as Div>::Output>,
 }
 
 impl<T> PerspectiveCamera<T>
@@ -45,6 +51,37 @@ where
             vertical_field_of_view,
             lens_radius,
             focal_length,
+            aperture: Aperture::Disc,
+            distortion: LensDistortion::default(),
+            shutter: Shutter::default(),
         }
     }
+
+    // Looks from the eye at the target instead of along a gaze direction.
+    pub fn look_at(
+        e: Point3<T>,
+        target: Point3<T>,
+        t: Vector3<T>,
+        vertical_field_of_view: Radians<<T as Div>::Output>,
+        lens_radius: T,
+        focal_length: T,
+    ) -> PerspectiveCamera<T> {
+        let (g, t) = gaze_and_up(e, target, t);
+        PerspectiveCamera::new(e, g, t, vertical_field_of_view, lens_radius, focal_length)
+    }
+
+    pub fn with_aperture(self, aperture: Aperture<<T as Div>::Output>) -> PerspectiveCamera<T> {
+        PerspectiveCamera { aperture, ..self }
+    }
+
+    pub fn with_distortion(
+        self,
+        distortion: LensDistortion<<T as Div>::Output>,
+    ) -> PerspectiveCamera<T> {
+        PerspectiveCamera { distortion, ..self }
+    }
+
+    pub fn with_shutter(self, shutter: Shutter<<T as Div>::Output>) -> PerspectiveCamera<T> {
+        PerspectiveCamera { shutter, ..self }
+    }
 }
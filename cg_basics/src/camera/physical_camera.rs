@@ -0,0 +1,150 @@
+use std::ops::{Div, Mul};
+
+use math::{Point3, Vector2, Vector3};
+use traits::{Atan, ConvenientNumber, FloatingPoint, Half, Log2, Number, One, SelfMulNumber, Sqrt};
+use units::angle::Radians;
+
+use super::{gaze_and_up, PerspectiveCamera};
+use crate::exposure::PhysicalExposure;
+
+// A camera described by the settings of a real one. The focal length and the size of the sensor
+// are in millimeters and give the field of view, the focal length and the f-number give the
+// diameter of the aperture and with it the depth of field. The f-number, the shutter speed in
+// seconds and the ISO speed give the exposure of the image. The scene is expected in meters.
+pub struct PhysicalCamera<T>
+where
+    T: Div,
+{
+    pub lens: PerspectiveCamera<T>,
+    pub focal_length: <T as Div>::Output,
+    pub sensor_size: Vector2<<T as Div>::Output>,
+    pub f_number: <T as Div>::Output,
+    pub shutter_speed: <T as Div>::Output,
+    pub iso: <T as Div>::Output,
+}
+
+impl<T> PhysicalCamera<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+    u16: Into<<T as Div>::Output>,
+{
+    // The image is sharp at the focus distance. The shutter speed defaults to 1/100 s, the ISO
+    // speed to 100.
+    pub fn new(
+        e: Point3<T>,
+        g: Vector3<T>,
+        t: Vector3<T>,
+        focal_length: <T as Div>::Output,
+        sensor_size: Vector2<<T as Div>::Output>,
+        f_number: <T as Div>::Output,
+        focus_distance: T,
+    ) -> PhysicalCamera<T> {
+        let one = <T as Div>::Output::one();
+        let hundred: <T as Div>::Output = 100u16.into();
+        let millimeter = one / 1000u16.into();
+
+        let vertical_field_of_view =
+            Radians::new((sensor_size.y.half() / focal_length).atan() * (one + one));
+        let lens_radius = T::one() * ((focal_length / f_number).half() * millimeter);
+
+        PhysicalCamera {
+            lens: PerspectiveCamera::new(
+                e,
+                g,
+                t,
+                vertical_field_of_view,
+                lens_radius,
+                focus_distance,
+            ),
+            focal_length,
+            sensor_size,
+            f_number,
+            shutter_speed: one / hundred,
+            iso: hundred,
+        }
+    }
+
+    // Looks from the eye at the target instead of along a gaze direction.
+    pub fn look_at(
+        e: Point3<T>,
+        target: Point3<T>,
+        t: Vector3<T>,
+        focal_length: <T as Div>::Output,
+        sensor_size: Vector2<<T as Div>::Output>,
+        f_number: <T as Div>::Output,
+        focus_distance: T,
+    ) -> PhysicalCamera<T> {
+        let (g, t) = gaze_and_up(e, target, t);
+        PhysicalCamera::new(e, g, t, focal_length, sensor_size, f_number, focus_distance)
+    }
+
+    pub fn with_shutter_speed(self, shutter_speed: <T as Div>::Output) -> PhysicalCamera<T> {
+        PhysicalCamera {
+            shutter_speed,
+            ..self
+        }
+    }
+
+    pub fn with_iso(self, iso: <T as Div>::Output) -> PhysicalCamera<T> {
+        PhysicalCamera { iso, ..self }
+    }
+
+    pub fn exposure(&self) -> PhysicalExposure<<T as Div>::Output> {
+        let hundred: <T as Div>::Output = 100u16.into();
+        PhysicalExposure::new(
+            (self.f_number * self.f_number / self.shutter_speed * hundred / self.iso).log2(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use traits::Tan;
+
+    macro_rules! physical_camera_settings {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A 50 mm lens on a full frame sensor at f/2.
+                let camera = PhysicalCamera::<$type>::new(
+                    Point3::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, -1.0),
+                    Vector3::new(0.0, 1.0, 0.0),
+                    50.0,
+                    Vector2::new(36.0, 24.0),
+                    2.0,
+                    3.0,
+                );
+
+                // The perspective camera keeps half of the field of view, which spans half of the
+                // sensor height at the focal length.
+                assert!((camera.lens.vertical_field_of_view.tan() - 12.0 / 50.0).abs() < 0.0001);
+                assert!((camera.lens.lens_radius - 0.0125).abs() < 0.0001);
+                assert_eq!(camera.lens.focal_length, 3.0);
+
+                // f/16, 1/100 s at ISO 100 is the exposure of the sunny 16 rule.
+                let sunny = PhysicalCamera::<$type>::new(
+                    Point3::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, -1.0),
+                    Vector3::new(0.0, 1.0, 0.0),
+                    50.0,
+                    Vector2::new(36.0, 24.0),
+                    16.0,
+                    3.0,
+                );
+                assert!((sunny.exposure().ev100 - 14.64).abs() < 0.01);
+
+                // Doubling the ISO speed or the shutter time opens up by one stop.
+                let faster = sunny.with_iso(200.0).with_shutter_speed(1.0 / 50.0);
+                assert!((faster.exposure().ev100 - 12.64).abs() < 0.01);
+            }
+        };
+    }
+
+    physical_camera_settings! { f32, physical_camera_settings_f32 }
+    physical_camera_settings! { f64, physical_camera_settings_f64 }
+}
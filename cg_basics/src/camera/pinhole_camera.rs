@@ -4,6 +4,8 @@ use math::{Point3, Vector3};
 use traits::{ConvenientNumber, FloatingPoint, Half, Number, SelfMulNumber, Sqrt};
 use units::angle::Radians;
 
+use super::{gaze_and_up, LensDistortion, Shutter};
+
 pub struct PinholeCamera<T>
 where
     T: Div,
@@ -13,6 +15,8 @@ where
     pub v: Vector3<<T as Div>::Output>,
     pub w: Vector3<<T as Div>::Output>,
     pub vertical_field_of_view: Radians<<T as Div>::Output>,
+    pub distortion: LensDistortion<<T as Div>::Output>,
+    pub shutter: Shutter<<T as Div>::Output>,
 }
 
 impl<T> PinholeCamera<T>
@@ -39,8 +43,32 @@ where
             v,
             w,
             vertical_field_of_view,
+            distortion: LensDistortion::default(),
+            shutter: Shutter::default(),
         }
     }
+
+    // Looks from the eye at the target instead of along a gaze direction.
+    pub fn look_at(
+        e: Point3<T>,
+        target: Point3<T>,
+        t: Vector3<T>,
+        vertical_field_of_view: Radians<<T as Div>::Output>,
+    ) -> PinholeCamera<T> {
+        let (g, t) = gaze_and_up(e, target, t);
+        PinholeCamera::new(e, g, t, vertical_field_of_view)
+    }
+
+    pub fn with_distortion(
+        self,
+        distortion: LensDistortion<<T as Div>::Output>,
+    ) -> PinholeCamera<T> {
+        PinholeCamera { distortion, ..self }
+    }
+
+    pub fn with_shutter(self, shutter: Shutter<<T as Div>::Output>) -> PinholeCamera<T> {
+        PinholeCamera { shutter, ..self }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,52 @@
+use traits::{Number, Zero};
+
+// The interval in which the shutter of a camera is open. Each ray is stamped with a time within
+// it, so geometry moving during the interval is blurred. By default, the shutter opens and closes
+// at time zero, which freezes all motion.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Shutter<V> {
+    pub open: V,
+    pub close: V,
+}
+
+impl<V: Number> Shutter<V> {
+    pub fn new(open: V, close: V) -> Shutter<V> {
+        Shutter { open, close }
+    }
+
+    // Maps a coordinate of the unit interval uniformly to a time in the interval.
+    pub fn time(&self, u: V) -> V {
+        self.open + (self.close - self.open) * u
+    }
+}
+
+impl<V: Zero> Default for Shutter<V> {
+    fn default() -> Self {
+        Shutter {
+            open: Zero::zero(),
+            close: Zero::zero(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! shutter_time {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let shutter = Shutter::<$type>::new(0.5, 1.5);
+                assert_eq!(shutter.time(0.0), 0.5);
+                assert_eq!(shutter.time(0.5), 1.0);
+                assert_eq!(shutter.time(1.0), 1.5);
+
+                assert_eq!(Shutter::<$type>::default().time(0.7), 0.0);
+            }
+        };
+    }
+
+    shutter_time! { f32, shutter_time_f32 }
+    shutter_time! { f64, shutter_time_f64 }
+}
@@ -4,6 +4,8 @@ use math::{Point3, Vector3};
 use traits::{ConvenientNumber, FloatingPoint, Half, Number, SelfMulNumber, Sqrt, Zero};
 use units::angle::Radians;
 
+use super::{gaze_and_up, Shutter};
+
 pub struct SphericalCamera<T>
 where
     T: Div,
@@ -13,6 +15,7 @@ where
     pub v: Vector3<<T as Div>::Output>,
     pub w: Vector3<<T as Div>::Output>,
     pub vertical_field_of_view: Radians<<T as Div>::Output>,
+    pub shutter: Shutter<<T as Div>::Output>,
 }
 
 impl<T> SphericalCamera<T>
@@ -42,6 +45,22 @@ where
             v,
             w,
             vertical_field_of_view,
+            shutter: Shutter::default(),
         }
     }
+
+    // Looks from the eye at the target instead of along a gaze direction.
+    pub fn look_at(
+        e: Point3<T>,
+        target: Point3<T>,
+        t: Vector3<T>,
+        vertical_field_of_view: Radians<<T as Div>::Output>,
+    ) -> SphericalCamera<T> {
+        let (g, t) = gaze_and_up(e, target, t);
+        SphericalCamera::new(e, g, t, vertical_field_of_view)
+    }
+
+    pub fn with_shutter(self, shutter: Shutter<<T as Div>::Output>) -> SphericalCamera<T> {
+        SphericalCamera { shutter, ..self }
+    }
 }
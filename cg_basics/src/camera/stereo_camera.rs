@@ -0,0 +1,136 @@
+use std::ops::{Div, Mul};
+
+use math::{Point3, Vector3};
+use traits::{ConvenientNumber, FloatingPoint, Half, Number, One, SelfMulNumber, Sqrt};
+use units::angle::Radians;
+
+use super::{gaze_and_up, Shutter};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+// One eye of a pair of pinhole cameras for stereoscopic images. The eyes sit the interocular
+// distance apart, to the left and the right of the eye position. Their views are shifted instead
+// of rotated towards each other, so both see the same rectangle at the convergence distance,
+// which appears at the depth of the screen.
+pub struct StereoCamera<T>
+where
+    T: Div,
+{
+    pub e: Point3<T>,
+    pub u: Vector3<<T as Div>::Output>,
+    pub v: Vector3<<T as Div>::Output>,
+    pub w: Vector3<<T as Div>::Output>,
+    pub vertical_field_of_view: Radians<<T as Div>::Output>,
+    pub interocular_distance: T,
+    pub convergence_distance: T,
+    pub eye: Eye,
+    pub shutter: Shutter<<T as Div>::Output>,
+}
+
+impl<T> StereoCamera<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+{
+    pub fn new(
+        e: Point3<T>,
+        g: Vector3<T>,
+        t: Vector3<T>,
+        vertical_field_of_view: Radians<<T as Div>::Output>,
+        interocular_distance: T,
+        convergence_distance: T,
+        eye: Eye,
+    ) -> StereoCamera<T> {
+        let w = -g.normalized();
+        let u = Vector3::cross(t, w).normalized();
+        let v = Vector3::cross(w, u).normalized();
+
+        let vertical_field_of_view = vertical_field_of_view.half();
+
+        StereoCamera {
+            e,
+            u,
+            v,
+            w,
+            vertical_field_of_view,
+            interocular_distance,
+            convergence_distance,
+            eye,
+            shutter: Shutter::default(),
+        }
+    }
+
+    // Looks from the eye at the target instead of along a gaze direction.
+    pub fn look_at(
+        e: Point3<T>,
+        target: Point3<T>,
+        t: Vector3<T>,
+        vertical_field_of_view: Radians<<T as Div>::Output>,
+        interocular_distance: T,
+        convergence_distance: T,
+        eye: Eye,
+    ) -> StereoCamera<T> {
+        let (g, t) = gaze_and_up(e, target, t);
+        StereoCamera::new(
+            e,
+            g,
+            t,
+            vertical_field_of_view,
+            interocular_distance,
+            convergence_distance,
+            eye,
+        )
+    }
+
+    pub fn with_shutter(self, shutter: Shutter<<T as Div>::Output>) -> StereoCamera<T> {
+        StereoCamera { shutter, ..self }
+    }
+
+    // The position of the eye this camera renders.
+    pub fn eye_position(&self) -> Point3<T>
+    where
+        <T as Div>::Output: Mul<T, Output = T>,
+    {
+        let offset = self.u * (self.interocular_distance * <T as Div>::Output::one().half());
+        match self.eye {
+            Eye::Left => self.e - offset,
+            Eye::Right => self.e + offset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use traits::ToRadians;
+    use units::angle::Degrees;
+
+    macro_rules! stereo_camera_eye_position {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let e = Point3::new(1 as $type, 2 as $type, 3 as $type);
+                let g = Vector3::new(0 as $type, 0 as $type, -1 as $type);
+                let t = Vector3::new(0 as $type, 1 as $type, 0 as $type);
+                let fov = Degrees::<$type>::new(90.0).to_radians();
+
+                let left = StereoCamera::new(e, g, t, fov, 0.5, 4.0, Eye::Left);
+                let right = StereoCamera::new(e, g, t, fov, 0.5, 4.0, Eye::Right);
+
+                assert_eq!(left.u, Vector3::new(1 as $type, 0 as $type, 0 as $type));
+                assert_eq!(left.vertical_field_of_view, fov.half());
+                assert_eq!(left.eye_position(), Point3::new(0.75, 2.0, 3.0));
+                assert_eq!(right.eye_position(), Point3::new(1.25, 2.0, 3.0));
+            }
+        };
+    }
+
+    stereo_camera_eye_position! { f32, stereo_camera_eye_position_f32 }
+    stereo_camera_eye_position! { f64, stereo_camera_eye_position_f64 }
+}
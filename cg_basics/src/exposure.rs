@@ -0,0 +1,251 @@
+use colors::RGB;
+use image::analyzer::LuminanceHistogram;
+use image::Image;
+use math::Point2;
+
+// Physical exposure expects scene radiance in luminance units (cd/m²). The camera is described by
+// its exposure value at ISO 100, the multiplier maps the luminance that saturates the sensor to 1.
+pub struct PhysicalExposure<T> {
+    pub ev100: T,
+}
+
+impl<T> PhysicalExposure<T> {
+    pub fn new(ev100: T) -> PhysicalExposure<T> {
+        PhysicalExposure { ev100 }
+    }
+}
+
+macro_rules! implement_physical_exposure_for {
+    ($($type: ty)*) => {$(
+        impl PhysicalExposure<$type> {
+            // Sunny 16 rule: f/16, 1/100 s at ISO 100.
+            pub const SUNNY_SIXTEEN: $type = 15.0;
+
+            // Typical luminance of the sun disc in cd/m².
+            pub const SUN_LUMINANCE: $type = 1.6e9;
+
+            pub fn daylight() -> PhysicalExposure<$type> {
+                PhysicalExposure::new(Self::SUNNY_SIXTEEN)
+            }
+
+            pub fn from_camera_settings(
+                aperture: $type,
+                shutter_time: $type,
+                iso: $type,
+            ) -> PhysicalExposure<$type> {
+                PhysicalExposure::new((aperture * aperture / shutter_time * 100.0 / iso).log2())
+            }
+
+            pub fn multiplier(&self) -> $type {
+                (1.2 * self.ev100.exp2()).recip()
+            }
+        }
+    )*}
+}
+
+implement_physical_exposure_for! { f32 f64 }
+
+// Where the light meter looks.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Metering {
+    // All pixels count the same.
+    Average,
+    // The pixels count less the farther they are from the center of the image.
+    CenterWeighted,
+    // The median of the luminance of the pixels that are not black, so a few very bright or dark
+    // pixels, e.g. of lights or the background, do not change the exposure.
+    Median,
+}
+
+// Picks the exposure from the rendered image instead of camera settings, so the first render of an
+// unknown scene is neither black nor blown out. The logarithmic average or the median of the
+// luminance of the image is mapped to the key value, middle gray by default. See Reinhard et al.,
+// "Photographic Tone Reproduction for Digital Images".
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AutoExposure<T> {
+    pub metering: Metering,
+    pub key: T,
+}
+
+macro_rules! implement_auto_exposure_for {
+    ($($type: ty)*) => {$(
+        impl AutoExposure<$type> {
+            pub const MIDDLE_GRAY: $type = 0.18;
+
+            // The standard deviation of the weights of center-weighted metering, relative to the
+            // size of the image.
+            const CENTER_SPREAD: $type = 0.25;
+
+            // The stops of the luminance histogram of median metering, in bins of 1/16 stop.
+            const HISTOGRAM_STOPS: std::ops::Range<f64> = -20.0..20.0;
+            const HISTOGRAM_BINS: usize = 640;
+
+            pub fn new(metering: Metering) -> AutoExposure<$type> {
+                AutoExposure {
+                    metering,
+                    key: Self::MIDDLE_GRAY,
+                }
+            }
+
+            pub fn with_key(self, key: $type) -> AutoExposure<$type> {
+                AutoExposure { key, ..self }
+            }
+
+            // A black image keeps its exposure.
+            pub fn multiplier(
+                &self,
+                image: &impl Image<ColorType = RGB<$type>, PointType = Point2<usize>>,
+            ) -> $type {
+                if self.metering == Metering::Median {
+                    let histogram = LuminanceHistogram::from_image(
+                        image,
+                        Self::HISTOGRAM_STOPS,
+                        Self::HISTOGRAM_BINS,
+                    );
+                    return match histogram.median() {
+                        Some(median) => self.key / median as $type,
+                        None => 1.0,
+                    };
+                }
+
+                let size = image.size();
+                let mut log_sum = 0.0;
+                let mut weight_sum = 0.0;
+                let mut lit = false;
+
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let weight = match self.metering {
+                            Metering::Average | Metering::Median => 1.0,
+                            Metering::CenterWeighted => {
+                                let dx = (x as $type + 0.5) / size.x as $type - 0.5;
+                                let dy = (y as $type + 0.5) / size.y as $type - 0.5;
+                                let spread = Self::CENTER_SPREAD;
+                                (-(dx * dx + dy * dy) / (2.0 * spread * spread)).exp()
+                            }
+                        };
+
+                        let color = image.get(Point2::new(x, y));
+                        let luminance =
+                            0.2126 * color.red + 0.7152 * color.green + 0.0722 * color.blue;
+                        lit |= luminance > 0.0;
+
+                        // The small offset keeps black pixels from dragging the average to zero.
+                        log_sum += weight * (luminance.max(0.0) + 0.0001).ln();
+                        weight_sum += weight;
+                    }
+                }
+
+                if !lit {
+                    return 1.0;
+                }
+
+                self.key / (log_sum / weight_sum).exp()
+            }
+        }
+    )*}
+}
+
+implement_auto_exposure_for! { f32 f64 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! physical_exposure_from_camera_settings {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let exposure =
+                    PhysicalExposure::<$type>::from_camera_settings(16.0, 1.0 / 100.0, 100.0);
+
+                assert!((exposure.ev100 - 14.643_856).abs() < 0.0001);
+            }
+        };
+    }
+
+    physical_exposure_from_camera_settings! { f32, physical_exposure_from_camera_settings_f32 }
+    physical_exposure_from_camera_settings! { f64, physical_exposure_from_camera_settings_f64 }
+
+    macro_rules! physical_exposure_multiplier {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let exposure = PhysicalExposure::<$type>::new(0.0);
+                assert!((exposure.multiplier() - 1.0 / 1.2).abs() < 0.0001);
+
+                let daylight = PhysicalExposure::<$type>::daylight();
+                let saturating_luminance = 1.2 * (2.0 as $type).powf(15.0);
+                assert!((daylight.multiplier() * saturating_luminance - 1.0).abs() < 0.0001);
+            }
+        };
+    }
+
+    physical_exposure_multiplier! { f32, physical_exposure_multiplier_f32 }
+    physical_exposure_multiplier! { f64, physical_exposure_multiplier_f64 }
+
+    macro_rules! auto_exposure_maps_to_key {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                use image::{ImageBuffer, WritableImage};
+                use math::Vector2;
+
+                let mut image =
+                    ImageBuffer::new(Vector2::new(3, 3), RGB::<$type>::new(2.0, 2.0, 2.0));
+                *image.get_mut(Point2::new(1, 1)) = RGB::new(8.0, 8.0, 8.0);
+
+                let average = AutoExposure::<$type>::new(Metering::Average);
+                let center_weighted = AutoExposure::<$type>::new(Metering::CenterWeighted);
+
+                let uniform =
+                    ImageBuffer::new(Vector2::new(3, 3), RGB::<$type>::new(2.0, 2.0, 2.0));
+                assert!((average.multiplier(&uniform) * 2.0 - 0.18).abs() < 0.001);
+                assert!((center_weighted.multiplier(&uniform) * 2.0 - 0.18).abs() < 0.001);
+
+                // The bright center darkens center-weighted metering more.
+                assert!(center_weighted.multiplier(&image) < average.multiplier(&image));
+                assert!(
+                    (average.with_key(0.36).multiplier(&image) - 2.0 * average.multiplier(&image))
+                        .abs()
+                        < 0.001
+                );
+
+                let black = ImageBuffer::new(Vector2::new(3, 3), RGB::<$type>::new(0.0, 0.0, 0.0));
+                assert_eq!(average.multiplier(&black), 1.0);
+            }
+        };
+    }
+
+    auto_exposure_maps_to_key! { f32, auto_exposure_maps_to_key_f32 }
+    auto_exposure_maps_to_key! { f64, auto_exposure_maps_to_key_f64 }
+
+    macro_rules! median_metering_ignores_highlights {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                use image::{ImageBuffer, WritableImage};
+                use math::Vector2;
+
+                let mut image =
+                    ImageBuffer::new(Vector2::new(3, 3), RGB::<$type>::new(2.0, 2.0, 2.0));
+                *image.get_mut(Point2::new(1, 1)) = RGB::new(1000.0, 1000.0, 1000.0);
+
+                let median = AutoExposure::<$type>::new(Metering::Median);
+                let average = AutoExposure::<$type>::new(Metering::Average);
+
+                assert!((median.multiplier(&image) * 2.0 - 0.18).abs() < 0.01);
+                assert!(average.multiplier(&image) < median.multiplier(&image));
+
+                *image.get_mut(Point2::new(0, 0)) = RGB::new(0.0, 0.0, 0.0);
+                assert!((median.multiplier(&image) * 2.0 - 0.18).abs() < 0.01);
+
+                let black = ImageBuffer::new(Vector2::new(3, 3), RGB::<$type>::new(0.0, 0.0, 0.0));
+                assert_eq!(median.multiplier(&black), 1.0);
+            }
+        };
+    }
+
+    median_metering_ignores_highlights! { f32, median_metering_ignores_highlights_f32 }
+    median_metering_ignores_highlights! { f64, median_metering_ignores_highlights_f64 }
+}
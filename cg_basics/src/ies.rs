@@ -0,0 +1,325 @@
+use std::str::FromStr;
+
+use math::Vector3;
+use traits::{ConvenientNumber, FloatingPoint, One, Zero};
+
+#[derive(Debug, PartialEq)]
+pub enum DecodingError {
+    MissingTilt,
+    UnsupportedTilt(String),
+    UnsupportedPhotometricType(String),
+    UnexpectedEndOfData,
+    InvalidNumber(String),
+    InvalidAngles,
+}
+
+// The luminous intensity distribution of a fixture, read from an IESNA LM-63 file. Only type C
+// photometry is supported, which is what is used for almost all architectural fixtures. The
+// vertical angle is measured from the nadir, the horizontal angle around it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct IesProfile<V> {
+    vertical_angles: Vec<V>,
+    horizontal_angles: Vec<V>,
+    // One row of vertical samples per horizontal angle, in candela.
+    candela: Vec<Vec<V>>,
+    maximum: V,
+}
+
+impl<V> IesProfile<V>
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    pub fn vertical_angles(&self) -> &[V] {
+        &self.vertical_angles
+    }
+
+    pub fn horizontal_angles(&self) -> &[V] {
+        &self.horizontal_angles
+    }
+
+    // The highest intensity of the profile in candela.
+    pub fn maximum(&self) -> V {
+        self.maximum
+    }
+
+    // The intensity for angles in degrees relative to the maximum of the profile.
+    pub fn intensity(&self, vertical: V, horizontal: V) -> V {
+        let zero = V::zero();
+        if self.maximum <= zero {
+            return zero;
+        }
+
+        let horizontal = self.fold_horizontal(horizontal);
+        let (h0, h1, fh) = match interpolation(&self.horizontal_angles, horizontal) {
+            Some(interpolation) => interpolation,
+            None => (0, 0, zero),
+        };
+        let (v0, v1, fv) = match interpolation(&self.vertical_angles, vertical) {
+            Some(interpolation) => interpolation,
+            None => return zero,
+        };
+
+        let one = V::one();
+        let row = |h: usize| self.candela[h][v0] * (one - fv) + self.candela[h][v1] * fv;
+
+        (row(h0) * (one - fh) + row(h1) * fh) / self.maximum
+    }
+
+    // The relative intensity towards a normalized direction leaving the fixture, which points its
+    // nadir along the axis. The horizontal angle 0 lies in the plane of the axis and the x axis,
+    // or the y axis if the fixture points along x.
+    pub fn intensity_towards(&self, direction: Vector3<V>, axis: Vector3<V>) -> V {
+        let helper: Vector3<V> = if axis.x.abs() > axis.y.abs() {
+            Vector3::new(Zero::zero(), One::one(), Zero::zero())
+        } else {
+            Vector3::new(One::one(), Zero::zero(), Zero::zero())
+        };
+        let v = Vector3::cross(axis, helper).normalized();
+        let u = Vector3::cross(v, axis);
+
+        let one = V::one();
+        let degrees = |radians: V| radians * 180u16.into() / V::PI;
+        let vertical = degrees(direction.dot(axis).clamp(-one, one).acos());
+        let horizontal = degrees(direction.dot(v).atan2(direction.dot(u)));
+
+        self.intensity(vertical, horizontal)
+    }
+
+    // Profiles only store the part of the horizontal angles that is needed because of their
+    // symmetry, the last angle tells which one it is.
+    fn fold_horizontal(&self, horizontal: V) -> V {
+        let last = self.horizontal_angles[self.horizontal_angles.len() - 1];
+        let full: V = 360u16.into();
+        let half: V = 180u16.into();
+        let quarter: V = 90u16.into();
+
+        let mut h = horizontal % full;
+        if h < V::zero() {
+            h += full;
+        }
+        if last <= half && h > half {
+            h = full - h;
+        }
+        if last <= quarter && h > quarter {
+            h = half - h;
+        }
+        h
+    }
+}
+
+// Decodes the text of an IES file.
+pub fn decode<V>(data: &str) -> Result<IesProfile<V>, DecodingError>
+where
+    V: FloatingPoint + ConvenientNumber + FromStr,
+{
+    let mut lines = data.lines();
+
+    // The header and the keywords are not needed, the photometric data follows the tilt line.
+    let tilt = loop {
+        match lines.next() {
+            Some(line) => {
+                if let Some(tilt) = line.trim().strip_prefix("TILT=") {
+                    break tilt.trim().to_string();
+                }
+            }
+            None => return Err(DecodingError::MissingTilt),
+        }
+    };
+
+    let rest: Vec<&str> = lines.collect();
+    let mut tokens = rest
+        .iter()
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+        .filter(|token| !token.is_empty());
+
+    match tilt.as_str() {
+        "NONE" => {}
+        "INCLUDE" => {
+            // The tilt only matters for lamps that change their output with their orientation.
+            let _geometry = next_number::<V>(&mut tokens)?;
+            let count = next_count(&mut tokens)?;
+            for _ in 0..2 * count {
+                next_number::<V>(&mut tokens)?;
+            }
+        }
+        tilt => return Err(DecodingError::UnsupportedTilt(tilt.to_string())),
+    }
+
+    let _lamps = next_number::<V>(&mut tokens)?;
+    let _lumens = next_number::<V>(&mut tokens)?;
+    let multiplier = next_number::<V>(&mut tokens)?;
+    let vertical_count = next_count(&mut tokens)?;
+    let horizontal_count = next_count(&mut tokens)?;
+    let photometric_type = next_count(&mut tokens)?;
+    if photometric_type != 1 {
+        return Err(DecodingError::UnsupportedPhotometricType(
+            photometric_type.to_string(),
+        ));
+    }
+    // Units, width, length, height, ballast factor, future use and input watts.
+    for _ in 0..7 {
+        next_number::<V>(&mut tokens)?;
+    }
+
+    let mut read = |count: usize| -> Result<Vec<V>, DecodingError> {
+        (0..count).map(|_| next_number(&mut tokens)).collect()
+    };
+
+    let vertical_angles = read(vertical_count)?;
+    let horizontal_angles = read(horizontal_count)?;
+    let mut candela = Vec::with_capacity(horizontal_count);
+    for _ in 0..horizontal_count {
+        candela.push(
+            read(vertical_count)?
+                .into_iter()
+                .map(|c| c * multiplier)
+                .collect::<Vec<V>>(),
+        );
+    }
+
+    let is_ascending = |angles: &[V]| !angles.is_empty() && angles.windows(2).all(|w| w[0] < w[1]);
+    if !is_ascending(&vertical_angles) || !is_ascending(&horizontal_angles) {
+        return Err(DecodingError::InvalidAngles);
+    }
+
+    let maximum = candela
+        .iter()
+        .flatten()
+        .fold(V::zero(), |m, c| if *c > m { *c } else { m });
+
+    Ok(IesProfile {
+        vertical_angles,
+        horizontal_angles,
+        candela,
+        maximum,
+    })
+}
+
+fn next_number<'a, V: FromStr>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<V, DecodingError> {
+    match tokens.next() {
+        Some(token) => match token.parse() {
+            Ok(number) => Ok(number),
+            Err(_) => Err(DecodingError::InvalidNumber(token.to_string())),
+        },
+        None => Err(DecodingError::UnexpectedEndOfData),
+    }
+}
+
+fn next_count<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<usize, DecodingError> {
+    let number: f64 = next_number(tokens)?;
+    if number < 0.0 || number.fract() != 0.0 {
+        return Err(DecodingError::InvalidNumber(number.to_string()));
+    }
+    Ok(number as usize)
+}
+
+// The two angles enclosing a value and the position between them, or None if the value is
+// outside of the angles.
+fn interpolation<V>(angles: &[V], value: V) -> Option<(usize, usize, V)>
+where
+    V: FloatingPoint + ConvenientNumber,
+{
+    let last = angles.len() - 1;
+    if value < angles[0] || value > angles[last] {
+        return None;
+    }
+    if last == 0 {
+        return Some((0, 0, V::zero()));
+    }
+
+    let upper = angles.partition_point(|a| *a <= value).clamp(1, last);
+    let lower = upper - 1;
+    let f = (value - angles[lower]) / (angles[upper] - angles[lower]);
+
+    Some((lower, upper, f.clamp(V::zero(), V::one())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A rotationally symmetric downlight that sends 1000 cd straight down and half of it at 45
+    // degrees.
+    const DOWNLIGHT: &str = "IESNA:LM-63-2002\n\
+        [TEST] downlight\n\
+        TILT=NONE\n\
+        1 -1 2.0 3 1 1 2 0.1 0.1 0.0\n\
+        1.0 1.0 20\n\
+        0 45 90\n\
+        0\n\
+        500 250 0\n";
+
+    macro_rules! decode_downlight {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let profile = decode::<$type>(DOWNLIGHT).unwrap();
+
+                assert_eq!(profile.vertical_angles(), &[0.0, 45.0, 90.0]);
+                assert_eq!(profile.horizontal_angles(), &[0.0]);
+                assert_eq!(profile.maximum(), 1000.0);
+
+                assert_eq!(profile.intensity(0.0, 0.0), 1.0);
+                assert_eq!(profile.intensity(22.5, 123.0), 0.75);
+                assert_eq!(profile.intensity(45.0, -10.0), 0.5);
+                assert_eq!(profile.intensity(135.0, 0.0), 0.0);
+
+                let down = Vector3::new(0.0, -1.0, 0.0);
+                let side = Vector3::new(1.0 as $type, -1.0, 0.0).normalized();
+                assert!((profile.intensity_towards(down, down) - 1.0).abs() < 0.0001);
+                assert!((profile.intensity_towards(side, down) - 0.5).abs() < 0.0001);
+            }
+        };
+    }
+
+    decode_downlight! { f32, decode_downlight_f32 }
+    decode_downlight! { f64, decode_downlight_f64 }
+
+    macro_rules! quadrant_symmetric_profile {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let data = "TILT=NONE\n\
+                    1 -1 1 2 2 1 2 0 0 0\n\
+                    1 1 10\n\
+                    0 90\n\
+                    0 90\n\
+                    100 100\n\
+                    200 0\n";
+                let profile = decode::<$type>(data).unwrap();
+
+                assert_eq!(profile.intensity(90.0, 0.0), 0.5);
+                assert_eq!(profile.intensity(90.0, 90.0), 0.0);
+                assert_eq!(profile.intensity(90.0, 270.0), 0.0);
+                assert_eq!(profile.intensity(90.0, 180.0), 0.5);
+                assert_eq!(profile.intensity(90.0, 135.0), 0.25);
+            }
+        };
+    }
+
+    quadrant_symmetric_profile! { f32, quadrant_symmetric_profile_f32 }
+    quadrant_symmetric_profile! { f64, quadrant_symmetric_profile_f64 }
+
+    #[test]
+    fn decode_rejects_invalid_data() {
+        assert_eq!(
+            decode::<f64>("IESNA:LM-63-2002\n").err(),
+            Some(DecodingError::MissingTilt)
+        );
+        assert_eq!(
+            decode::<f64>("TILT=lamp.tlt\n").err(),
+            Some(DecodingError::UnsupportedTilt("lamp.tlt".to_string()))
+        );
+        assert_eq!(
+            decode::<f64>("TILT=NONE\n1 -1 1 2 1 1 2 0 0 0\n1 1 10\n0 90\n0\n100\n").err(),
+            Some(DecodingError::UnexpectedEndOfData)
+        );
+        assert_eq!(
+            decode::<f64>("TILT=NONE\n1 -1 1 2 1 2 2 0 0 0\n1 1 10\n0 90\n0\n100 0\n").err(),
+            Some(DecodingError::UnsupportedPhotometricType("2".to_string()))
+        );
+    }
+}
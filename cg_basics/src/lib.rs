@@ -1,4 +1,12 @@
+pub mod background;
 pub mod camera;
+pub mod exposure;
+pub mod ies;
 pub mod light;
 pub mod material;
+pub mod microfacet;
+pub mod prefiltered_environment;
 pub mod scene_graph;
+pub mod sky;
+pub mod spherical_harmonics;
+pub mod volume;
@@ -1,8 +1,22 @@
 use std::ops::Div;
 
-use math::{Point3, Vector3};
+use colors::RGB;
+use image::{Image, ImageBuffer, WritableImage};
+use math::geometry::triangle::Triangle3Mesh;
+use math::{Point2, Point3, Vector2, Vector3};
+use sampling::{split, Distribution2D, Triangle3MeshSampler};
+use traits::{
+    Abs, Asin, Atan2, Clamp, ConvenientNumber, Cos, FloatingPoint, Half, Number, One, Pi, Powf,
+    SelfMulNumber, Sin, Tan, Zero,
+};
 use units::angle::Radians;
 use units::length::Length;
+use units::photometry::{Candela, Lumen};
+use units::radiometry::{Watt, WattPerSteradian};
+
+use crate::ies::IesProfile;
+use crate::prefiltered_environment::PrefilteredEnvironment;
+use crate::spherical_harmonics::SphericalHarmonics;
 
 pub struct DirectionalLight<T, C>
 where
@@ -10,6 +24,9 @@ where
 {
     pub color: C,
     pub direction: Vector3<<T as Div>::Output>,
+    pub shadow_bias: Option<<T as Div>::Output>,
+    pub name: Option<String>,
+    pub group: Option<String>,
 }
 
 impl<T, C> DirectionalLight<T, C>
@@ -17,18 +34,227 @@ where
     T: Div,
 {
     pub fn new(color: C, direction: Vector3<<T as Div>::Output>) -> DirectionalLight<T, C> {
-        DirectionalLight { color, direction }
+        DirectionalLight {
+            color,
+            direction,
+            shadow_bias: None,
+            name: None,
+            group: None,
+        }
+    }
+
+    pub fn with_shadow_bias(self, shadow_bias: <T as Div>::Output) -> DirectionalLight<T, C> {
+        DirectionalLight {
+            shadow_bias: Some(shadow_bias),
+            ..self
+        }
+    }
+
+    pub fn with_name(self, name: String) -> DirectionalLight<T, C> {
+        DirectionalLight {
+            name: Some(name),
+            ..self
+        }
+    }
+
+    pub fn with_group(self, group: String) -> DirectionalLight<T, C> {
+        DirectionalLight {
+            group: Some(group),
+            ..self
+        }
     }
 }
 
-pub struct PointLight<T, C> {
+// How the intensity of a light decreases with the distance d to it.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Falloff {
+    None,
+    // 1 / d². Distances below a hundredth of the length unit are clamped to avoid the
+    // singularity at the light.
+    InverseSquare,
+    // 1 / d², faded out smoothly to zero at the radius with the window (1 - (d / radius)⁴)², see
+    // Karis, "Real Shading in Unreal Engine 4".
+    Smooth,
+}
+
+// The intensity of a point or spot light in physical units. The renderer works with photometric
+// quantities: the color of these lights is their luminous intensity in candela, so surfaces end up
+// with a luminance in cd/m² as expected by the physical exposure.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LightIntensity<V> {
+    Watt(Watt<V>),
+    WattPerSteradian(WattPerSteradian<V>),
+    Lumen(Lumen<V>),
+    Candela(Candela<V>),
+}
+
+impl<V> LightIntensity<V>
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    // The luminous intensity of a light that spreads its power evenly over a solid angle in
+    // steradian.
+    pub fn to_candela(self, solid_angle: V) -> Candela<V> {
+        match self {
+            LightIntensity::Watt(power) => power.to_lumen().per_solid_angle(solid_angle),
+            LightIntensity::WattPerSteradian(intensity) => intensity.to_candela(),
+            LightIntensity::Lumen(flux) => flux.per_solid_angle(solid_angle),
+            LightIntensity::Candela(intensity) => intensity,
+        }
+    }
+
+    // Scales the tint so its luminance matches the intensity. A black tint is taken as white.
+    pub fn color(self, tint: RGB<V>, solid_angle: V) -> RGB<V> {
+        let ten_thousand: V = 10000u16.into();
+        let luminance =
+            (tint.red * 2126u16.into() + tint.green * 7152u16.into() + tint.blue * 722u16.into())
+                / ten_thousand;
+        let candela = self.to_candela(solid_angle) / Candela::one();
+
+        if luminance > V::zero() {
+            tint * (candela / luminance)
+        } else {
+            RGB::new(candela, candela, candela)
+        }
+    }
+}
+
+pub struct PointLight<T, C>
+where
+    T: Div,
+{
     pub color: C,
     pub position: Point3<T>,
+    pub shadow_bias: Option<<T as Div>::Output>,
+    pub name: Option<String>,
+    pub group: Option<String>,
+    pub falloff: Falloff,
+    // Surfaces further away than the radius, in units of the length type, receive no light.
+    pub radius: Option<<T as Div>::Output>,
+    // The nadir of the profile points down the negative y axis, like a fixture on a ceiling.
+    pub profile: Option<IesProfile<<T as Div>::Output>>,
 }
 
-impl<T, C> PointLight<T, C> {
+impl<T, C> PointLight<T, C>
+where
+    T: Div,
+{
     pub fn new(color: C, position: Point3<T>) -> PointLight<T, C> {
-        PointLight { color, position }
+        PointLight {
+            color,
+            position,
+            shadow_bias: None,
+            name: None,
+            group: None,
+            falloff: Falloff::None,
+            radius: None,
+            profile: None,
+        }
+    }
+
+    pub fn with_shadow_bias(self, shadow_bias: <T as Div>::Output) -> PointLight<T, C> {
+        PointLight {
+            shadow_bias: Some(shadow_bias),
+            ..self
+        }
+    }
+
+    pub fn with_name(self, name: String) -> PointLight<T, C> {
+        PointLight {
+            name: Some(name),
+            ..self
+        }
+    }
+
+    pub fn with_group(self, group: String) -> PointLight<T, C> {
+        PointLight {
+            group: Some(group),
+            ..self
+        }
+    }
+
+    pub fn with_falloff(self, falloff: Falloff) -> PointLight<T, C> {
+        PointLight { falloff, ..self }
+    }
+
+    pub fn with_radius(self, radius: <T as Div>::Output) -> PointLight<T, C> {
+        PointLight {
+            radius: Some(radius),
+            ..self
+        }
+    }
+
+    pub fn with_profile(self, profile: IesProfile<<T as Div>::Output>) -> PointLight<T, C> {
+        PointLight {
+            profile: Some(profile),
+            ..self
+        }
+    }
+
+    // The fraction of the intensity that leaves the light in a normalized direction.
+    pub fn profile_intensity(&self, direction: Vector3<<T as Div>::Output>) -> <T as Div>::Output
+    where
+        <T as Div>::Output: FloatingPoint + ConvenientNumber,
+        u16: Into<<T as Div>::Output>,
+    {
+        let zero = <T as Div>::Output::zero();
+        let one = <T as Div>::Output::one();
+
+        match &self.profile {
+            Some(profile) => profile.intensity_towards(direction, Vector3::new(zero, -one, zero)),
+            None => one,
+        }
+    }
+
+    // The factor the color of the light is scaled with at the given distance, in units of the
+    // length type.
+    pub fn attenuation(&self, distance: <T as Div>::Output) -> <T as Div>::Output
+    where
+        <T as Div>::Output: FloatingPoint + ConvenientNumber,
+        u16: Into<<T as Div>::Output>,
+    {
+        let zero = <T as Div>::Output::zero();
+        let one = <T as Div>::Output::one();
+
+        if let Some(radius) = self.radius {
+            if distance >= radius {
+                return zero;
+            }
+        }
+
+        let minimum: <T as Div>::Output = one / 10000u16.into();
+        let square = distance * distance;
+        let inverse_square = one / if square > minimum { square } else { minimum };
+
+        match (self.falloff, self.radius) {
+            (Falloff::None, _) => one,
+            (Falloff::InverseSquare, _) | (Falloff::Smooth, None) => inverse_square,
+            (Falloff::Smooth, Some(radius)) => {
+                let x = distance / radius;
+                let window = (one - x * x * x * x).clamp(zero, one);
+                inverse_square * window * window
+            }
+        }
+    }
+}
+
+impl<T> PointLight<T, RGB<<T as Div>::Output>>
+where
+    T: Div,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    u16: Into<<T as Div>::Output>,
+{
+    // The color becomes the tint of the light, which emits in all directions.
+    pub fn with_intensity(
+        self,
+        intensity: LightIntensity<<T as Div>::Output>,
+    ) -> PointLight<T, RGB<<T as Div>::Output>> {
+        let four: <T as Div>::Output = 4u16.into();
+        PointLight {
+            color: intensity.color(self.color, four * <T as Div>::Output::PI),
+            ..self
+        }
     }
 }
 
@@ -40,11 +266,21 @@ where
     pub position: Point3<T>,
     pub direction: Vector3<<T as Div>::Output>,
     pub angle: Radians<<T as Div>::Output>,
+    // Inside of the inner angle the spot has its full intensity, towards the angle it fades out.
+    pub inner_angle: Option<Radians<<T as Div>::Output>>,
+    pub falloff_exponent: <T as Div>::Output,
+    // The nadir of the profile points along the direction of the spot.
+    pub profile: Option<IesProfile<<T as Div>::Output>>,
+    pub gobo: Option<Box<dyn Image<ColorType = C, PointType = Point2<<T as Div>::Output>>>>,
+    pub shadow_bias: Option<<T as Div>::Output>,
+    pub name: Option<String>,
+    pub group: Option<String>,
 }
 
 impl<T, C> SpotLight<T, C>
 where
     T: Div,
+    <T as Div>::Output: One,
 {
     pub fn new(
         color: C,
@@ -57,10 +293,559 @@ where
             position,
             direction,
             angle,
+            inner_angle: None,
+            falloff_exponent: One::one(),
+            profile: None,
+            gobo: None,
+            shadow_bias: None,
+            name: None,
+            group: None,
+        }
+    }
+
+    pub fn with_inner_angle(self, inner_angle: Radians<<T as Div>::Output>) -> SpotLight<T, C> {
+        SpotLight {
+            inner_angle: Some(inner_angle),
+            ..self
+        }
+    }
+
+    pub fn with_falloff_exponent(self, falloff_exponent: <T as Div>::Output) -> SpotLight<T, C> {
+        SpotLight {
+            falloff_exponent,
+            ..self
+        }
+    }
+
+    pub fn with_profile(self, profile: IesProfile<<T as Div>::Output>) -> SpotLight<T, C> {
+        SpotLight {
+            profile: Some(profile),
+            ..self
+        }
+    }
+
+    pub fn with_gobo(
+        self,
+        gobo: Box<dyn Image<ColorType = C, PointType = Point2<<T as Div>::Output>>>,
+    ) -> SpotLight<T, C> {
+        SpotLight {
+            gobo: Some(gobo),
+            ..self
+        }
+    }
+
+    pub fn with_shadow_bias(self, shadow_bias: <T as Div>::Output) -> SpotLight<T, C> {
+        SpotLight {
+            shadow_bias: Some(shadow_bias),
+            ..self
+        }
+    }
+
+    pub fn with_name(self, name: String) -> SpotLight<T, C> {
+        SpotLight {
+            name: Some(name),
+            ..self
+        }
+    }
+
+    pub fn with_group(self, group: String) -> SpotLight<T, C> {
+        SpotLight {
+            group: Some(group),
+            ..self
         }
     }
 }
 
+impl<T, C> SpotLight<T, C>
+where
+    T: Div,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+{
+    // The fraction of the intensity that leaves the light in a normalized direction. Between the
+    // inner angle and the angle, the cosine of the angle to the axis is interpolated and raised to
+    // the falloff exponent. Without an inner angle, the edge of the spot is hard.
+    pub fn cone_attenuation(&self, direction: Vector3<<T as Div>::Output>) -> <T as Div>::Output {
+        let zero = <T as Div>::Output::zero();
+        let one = <T as Div>::Output::one();
+
+        let cos_angle = direction.dot(self.direction);
+        let cos_outer = self.angle.cos();
+
+        match self.inner_angle {
+            Some(inner_angle) if inner_angle.cos() > cos_outer => {
+                let t =
+                    ((cos_angle - cos_outer) / (inner_angle.cos() - cos_outer)).clamp(zero, one);
+                t.powf(self.falloff_exponent)
+            }
+            _ if cos_angle > cos_outer => one,
+            _ => zero,
+        }
+    }
+
+    // The fraction of the intensity that leaves the light in a normalized direction.
+    pub fn profile_intensity(&self, direction: Vector3<<T as Div>::Output>) -> <T as Div>::Output
+    where
+        u16: Into<<T as Div>::Output>,
+    {
+        match &self.profile {
+            Some(profile) => profile.intensity_towards(direction, self.direction),
+            None => <T as Div>::Output::one(),
+        }
+    }
+
+    // Maps a normalized direction leaving the light onto the square that encloses the cone's
+    // cross section. The cone axis ends up at (0.5, 0.5), the cone border touches the edges.
+    pub fn cone_coordinates(
+        &self,
+        direction: Vector3<<T as Div>::Output>,
+    ) -> Point2<<T as Div>::Output> {
+        let w = self.direction;
+        let helper: Vector3<<T as Div>::Output> = if w.x.abs() > w.y.abs() {
+            Vector3::new(Zero::zero(), One::one(), Zero::zero())
+        } else {
+            Vector3::new(One::one(), Zero::zero(), Zero::zero())
+        };
+        let u = Vector3::cross(helper, w).normalized();
+        let v = Vector3::cross(w, u);
+
+        let extent = direction.dot(w) * self.angle.tan();
+        let one = <T as Div>::Output::one();
+
+        Point2::new(
+            (direction.dot(u) / extent + one).half(),
+            (direction.dot(v) / extent + one).half(),
+        )
+    }
+}
+
+impl<T> SpotLight<T, RGB<<T as Div>::Output>>
+where
+    T: Div,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    u16: Into<<T as Div>::Output>,
+{
+    // The color becomes the tint of the light, which emits into the solid angle of its cone.
+    pub fn with_intensity(
+        self,
+        intensity: LightIntensity<<T as Div>::Output>,
+    ) -> SpotLight<T, RGB<<T as Div>::Output>> {
+        let one = <T as Div>::Output::one();
+        let pi = <T as Div>::Output::PI;
+        let solid_angle = (pi + pi) * (one - self.angle.cos());
+        SpotLight {
+            color: intensity.color(self.color, solid_angle),
+            ..self
+        }
+    }
+}
+
+// A one-sided rectangle that emits light to the side its normal a x b points to.
+pub struct AreaLight<T, C>
+where
+    T: Div,
+{
+    pub color: C,
+    pub corner: Point3<T>,
+    pub a: Vector3<T>,
+    pub b: Vector3<T>,
+    pub shadow_bias: Option<<T as Div>::Output>,
+    pub name: Option<String>,
+    pub group: Option<String>,
+}
+
+impl<T, C> AreaLight<T, C>
+where
+    T: Div,
+{
+    pub fn new(color: C, corner: Point3<T>, a: Vector3<T>, b: Vector3<T>) -> AreaLight<T, C> {
+        AreaLight {
+            color,
+            corner,
+            a,
+            b,
+            shadow_bias: None,
+            name: None,
+            group: None,
+        }
+    }
+
+    pub fn with_shadow_bias(self, shadow_bias: <T as Div>::Output) -> AreaLight<T, C> {
+        AreaLight {
+            shadow_bias: Some(shadow_bias),
+            ..self
+        }
+    }
+
+    pub fn with_name(self, name: String) -> AreaLight<T, C> {
+        AreaLight {
+            name: Some(name),
+            ..self
+        }
+    }
+
+    pub fn with_group(self, group: String) -> AreaLight<T, C> {
+        AreaLight {
+            group: Some(group),
+            ..self
+        }
+    }
+}
+
+// A light bulb. Shadow rays are distributed over the solid angle the sphere covers as seen from
+// the shaded point.
+pub struct SphereLight<T, C>
+where
+    T: Div,
+{
+    pub color: C,
+    pub position: Point3<T>,
+    pub radius: T,
+    pub shadow_bias: Option<<T as Div>::Output>,
+    pub name: Option<String>,
+    pub group: Option<String>,
+}
+
+impl<T, C> SphereLight<T, C>
+where
+    T: Div,
+{
+    pub fn new(color: C, position: Point3<T>, radius: T) -> SphereLight<T, C> {
+        SphereLight {
+            color,
+            position,
+            radius,
+            shadow_bias: None,
+            name: None,
+            group: None,
+        }
+    }
+
+    pub fn with_shadow_bias(self, shadow_bias: <T as Div>::Output) -> SphereLight<T, C> {
+        SphereLight {
+            shadow_bias: Some(shadow_bias),
+            ..self
+        }
+    }
+
+    pub fn with_name(self, name: String) -> SphereLight<T, C> {
+        SphereLight {
+            name: Some(name),
+            ..self
+        }
+    }
+
+    pub fn with_group(self, group: String) -> SphereLight<T, C> {
+        SphereLight {
+            group: Some(group),
+            ..self
+        }
+    }
+}
+
+// A one-sided light in the shape of a triangle mesh. It emits light to the side the normals of
+// the faces point to.
+pub struct MeshLight<T, C>
+where
+    T: Div,
+{
+    pub color: C,
+    pub shadow_bias: Option<<T as Div>::Output>,
+    pub name: Option<String>,
+    pub group: Option<String>,
+    sampler: Triangle3MeshSampler<T>,
+}
+
+impl<T, C> MeshLight<T, C>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    u16: Into<<T as Div>::Output>,
+{
+    pub fn new(color: C, mesh: Triangle3Mesh<T>) -> MeshLight<T, C> {
+        MeshLight {
+            color,
+            shadow_bias: None,
+            name: None,
+            group: None,
+            sampler: Triangle3MeshSampler::new(mesh),
+        }
+    }
+
+    pub fn with_shadow_bias(self, shadow_bias: <T as Div>::Output) -> MeshLight<T, C> {
+        MeshLight {
+            shadow_bias: Some(shadow_bias),
+            ..self
+        }
+    }
+
+    pub fn with_name(self, name: String) -> MeshLight<T, C> {
+        MeshLight {
+            name: Some(name),
+            ..self
+        }
+    }
+
+    pub fn with_group(self, group: String) -> MeshLight<T, C> {
+        MeshLight {
+            group: Some(group),
+            ..self
+        }
+    }
+
+    pub fn sampler(&self) -> &Triangle3MeshSampler<T> {
+        &self.sampler
+    }
+}
+
+// Light arriving from infinitely far away, given as an equirectangular image. The first row of
+// the image lies at the zenith, the center of the image looks along the negative z axis.
+pub struct EnvironmentLight<T>
+where
+    T: Div,
+    <T as Div>::Output: Number,
+{
+    pub intensity: <T as Div>::Output,
+    pub shadow_bias: Option<<T as Div>::Output>,
+    pub name: Option<String>,
+    pub group: Option<String>,
+    image: ImageBuffer<RGB<<T as Div>::Output>>,
+    irradiance: ImageBuffer<RGB<<T as Div>::Output>>,
+    specular: PrefilteredEnvironment<T>,
+    distribution: Distribution2D<<T as Div>::Output>,
+}
+
+impl<T> EnvironmentLight<T>
+where
+    T: Div,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    u16: Into<<T as Div>::Output>,
+{
+    pub fn new(image: ImageBuffer<RGB<<T as Div>::Output>>) -> EnvironmentLight<T> {
+        let distribution = Self::luminance_distribution(&image);
+        let irradiance = Self::irradiance_map(&image);
+        let specular = PrefilteredEnvironment::new(&image);
+
+        EnvironmentLight {
+            intensity: One::one(),
+            shadow_bias: None,
+            name: None,
+            group: None,
+            image,
+            irradiance,
+            specular,
+            distribution,
+        }
+    }
+
+    pub fn with_intensity(self, intensity: <T as Div>::Output) -> EnvironmentLight<T> {
+        EnvironmentLight { intensity, ..self }
+    }
+
+    pub fn with_shadow_bias(self, shadow_bias: <T as Div>::Output) -> EnvironmentLight<T> {
+        EnvironmentLight {
+            shadow_bias: Some(shadow_bias),
+            ..self
+        }
+    }
+
+    pub fn with_name(self, name: String) -> EnvironmentLight<T> {
+        EnvironmentLight {
+            name: Some(name),
+            ..self
+        }
+    }
+
+    pub fn with_group(self, group: String) -> EnvironmentLight<T> {
+        EnvironmentLight {
+            group: Some(group),
+            ..self
+        }
+    }
+
+    pub fn image(&self) -> &ImageBuffer<RGB<<T as Div>::Output>> {
+        &self.image
+    }
+
+    pub fn spherical_harmonics(&self) -> SphericalHarmonics<<T as Div>::Output>
+    where
+        <T as Div>::Output: From<f32>,
+    {
+        let mut spherical_harmonics = SphericalHarmonics::project(&self.image);
+        for coefficient in spherical_harmonics.coefficients.iter_mut() {
+            *coefficient = *coefficient * self.intensity;
+        }
+        spherical_harmonics
+    }
+
+    // Texture coordinates of a normalized direction, v grows towards the zenith.
+    pub fn coordinates(direction: Vector3<<T as Div>::Output>) -> Point2<<T as Div>::Output> {
+        let one = <T as Div>::Output::one();
+        let pi = <T as Div>::Output::PI;
+
+        Point2::new(
+            (direction.x.atan2(-direction.z) / pi).half() + one.half(),
+            direction.y.clamp(-one, one).asin() / pi + one.half(),
+        )
+    }
+
+    pub fn direction(coordinates: Point2<<T as Div>::Output>) -> Vector3<<T as Div>::Output> {
+        let one = <T as Div>::Output::one();
+        let pi = <T as Div>::Output::PI;
+
+        let phi = (coordinates.x - one.half()) * (pi + pi);
+        let latitude = (coordinates.y - one.half()) * pi;
+
+        Vector3::new(
+            latitude.cos() * phi.sin(),
+            latitude.sin(),
+            -latitude.cos() * phi.cos(),
+        )
+    }
+
+    pub fn radiance(&self, direction: Vector3<<T as Div>::Output>) -> RGB<<T as Div>::Output> {
+        let coordinates = Self::coordinates(direction);
+        let size = self.image.size();
+        let one = <T as Div>::Output::one();
+
+        let (x, _) = split(coordinates.x, size.x);
+        let (y, _) = split(one - coordinates.y, size.y);
+
+        self.image.get(Point2::new(x, y)) * self.intensity
+    }
+
+    // The cosine weighted integral of the radiance over the hemisphere around the normal, divided
+    // by pi. A uniform environment therefore yields its own radiance.
+    pub fn irradiance(&self, normal: Vector3<<T as Div>::Output>) -> RGB<<T as Div>::Output> {
+        let coordinates = Self::coordinates(normal);
+        let size = self.irradiance.size();
+        let one = <T as Div>::Output::one();
+
+        let (x0, fx) = split(coordinates.x - (one / to_value(size.x)).half(), size.x);
+        let (y0, fy) = split(
+            one - coordinates.y - (one / to_value(size.y)).half(),
+            size.y,
+        );
+        let x1 = (x0 + 1) % size.x;
+        let y1 = (y0 + 1).min(size.y - 1);
+
+        let top = self.irradiance.get(Point2::new(x0, y0)) * (one - fx)
+            + self.irradiance.get(Point2::new(x1, y0)) * fx;
+        let bottom = self.irradiance.get(Point2::new(x0, y1)) * (one - fx)
+            + self.irradiance.get(Point2::new(x1, y1)) * fx;
+
+        (top * (one - fy) + bottom * fy) * self.intensity
+    }
+
+    // The radiance a glossy surface reflects into a normalized direction, for a roughness between
+    // zero (a mirror) and one.
+    pub fn specular(
+        &self,
+        direction: Vector3<<T as Div>::Output>,
+        roughness: <T as Div>::Output,
+    ) -> RGB<<T as Div>::Output> {
+        self.specular.radiance(direction, roughness) * self.intensity
+    }
+
+    // Maps a point of the unit square onto a direction. Bright parts of the environment receive
+    // proportionally more samples.
+    pub fn sample_direction(
+        &self,
+        sample: Point2<<T as Div>::Output>,
+    ) -> Vector3<<T as Div>::Output> {
+        let (p, _) = self.distribution.sample_continuous(sample);
+
+        Self::direction(Point2::new(p.x, <T as Div>::Output::one() - p.y))
+    }
+
+    fn luminance_distribution(
+        image: &ImageBuffer<RGB<<T as Div>::Output>>,
+    ) -> Distribution2D<<T as Div>::Output> {
+        let size = image.size();
+        let ten_thousand: <T as Div>::Output = 10000u16.into();
+        let red: <T as Div>::Output = 2126u16.into();
+        let green: <T as Div>::Output = 7152u16.into();
+        let blue: <T as Div>::Output = 722u16.into();
+
+        let mut weights = Vec::with_capacity(size.x * size.y);
+        for y in 0..size.y {
+            let solid_angle = Self::latitude_of_row(y, size.y).cos();
+            weights.extend((0..size.x).map(|x| {
+                let c = image.get(Point2::new(x, y));
+                (c.red * red + c.green * green + c.blue * blue) / ten_thousand * solid_angle
+            }));
+        }
+
+        Distribution2D::new(&weights, size)
+    }
+
+    fn irradiance_map(
+        image: &ImageBuffer<RGB<<T as Div>::Output>>,
+    ) -> ImageBuffer<RGB<<T as Div>::Output>> {
+        let zero = <T as Div>::Output::zero();
+        let one = <T as Div>::Output::one();
+        let pi = <T as Div>::Output::PI;
+
+        // The radiance is first averaged down to a coarse grid, the irradiance varies slowly.
+        let size = image.size();
+        let coarse_size = Vector2::new(size.x.min(64), size.y.min(32));
+        let mut coarse = Vec::with_capacity(coarse_size.x * coarse_size.y);
+        for cy in 0..coarse_size.y {
+            for cx in 0..coarse_size.x {
+                let mut sum = RGB::new(zero, zero, zero);
+                let mut count = zero;
+                for y in (cy * size.y / coarse_size.y)..((cy + 1) * size.y / coarse_size.y) {
+                    for x in (cx * size.x / coarse_size.x)..((cx + 1) * size.x / coarse_size.x) {
+                        sum += image.get(Point2::new(x, y));
+                        count += one;
+                    }
+                }
+
+                let latitude = Self::latitude_of_row(cy, coarse_size.y);
+                let direction = Self::direction(Point2::new(
+                    (to_value(cx) + one.half()) / to_value(coarse_size.x),
+                    latitude / pi + one.half(),
+                ));
+                let solid_angle = latitude.cos() * (pi + pi) * pi
+                    / (to_value(coarse_size.x) * to_value(coarse_size.y));
+
+                coarse.push((direction, sum * (solid_angle / count / pi)));
+            }
+        }
+
+        let irradiance_size = Vector2::new(32, 16);
+        let mut irradiance = ImageBuffer::new(irradiance_size, RGB::new(zero, zero, zero));
+        for y in 0..irradiance_size.y {
+            for x in 0..irradiance_size.x {
+                let normal = Self::direction(Point2::new(
+                    (to_value(x) + one.half()) / to_value(irradiance_size.x),
+                    Self::latitude_of_row(y, irradiance_size.y) / pi + one.half(),
+                ));
+
+                *irradiance.get_mut(Point2::new(x, y)) = coarse
+                    .iter()
+                    .filter(|(direction, _)| direction.dot(normal) > zero)
+                    .fold(RGB::new(zero, zero, zero), |e, (direction, radiance)| {
+                        e + *radiance * direction.dot(normal)
+                    });
+            }
+        }
+
+        irradiance
+    }
+
+    pub(crate) fn latitude_of_row(y: usize, rows: usize) -> <T as Div>::Output {
+        let one = <T as Div>::Output::one();
+        (one.half() - (to_value(y) + one.half()) / to_value(rows)) * <T as Div>::Output::PI
+    }
+}
+
+fn to_value<V>(value: usize) -> V
+where
+    u16: Into<V>,
+{
+    (value as u16).into()
+}
+
 pub struct AmbientLight<C> {
     pub color: C,
 }
@@ -75,11 +860,42 @@ pub struct AmbientOcclusionLight<T: Length, C> {
     pub color: C,
     pub e: T::ValueType,
     pub distance: T,
+    pub shadow_bias: Option<T::ValueType>,
+    pub name: Option<String>,
+    pub group: Option<String>,
 }
 
 impl<T: Length, C> AmbientOcclusionLight<T, C> {
     pub fn new(color: C, e: T::ValueType, distance: T) -> AmbientOcclusionLight<T, C> {
-        AmbientOcclusionLight { color, e, distance }
+        AmbientOcclusionLight {
+            color,
+            e,
+            distance,
+            shadow_bias: None,
+            name: None,
+            group: None,
+        }
+    }
+
+    pub fn with_shadow_bias(self, shadow_bias: T::ValueType) -> AmbientOcclusionLight<T, C> {
+        AmbientOcclusionLight {
+            shadow_bias: Some(shadow_bias),
+            ..self
+        }
+    }
+
+    pub fn with_name(self, name: String) -> AmbientOcclusionLight<T, C> {
+        AmbientOcclusionLight {
+            name: Some(name),
+            ..self
+        }
+    }
+
+    pub fn with_group(self, group: String) -> AmbientOcclusionLight<T, C> {
+        AmbientOcclusionLight {
+            group: Some(group),
+            ..self
+        }
     }
 }
 
@@ -87,7 +903,6 @@ impl<T: Length, C> AmbientOcclusionLight<T, C> {
 mod tests {
     use super::*;
 
-    use colors::RGB;
     use math::Vector3;
     use units::length::Meter;
 
@@ -127,6 +942,77 @@ mod tests {
     new_point_light! { f32, new_point_light_f32 }
     new_point_light! { f64, new_point_light_f64 }
 
+    macro_rules! point_light_attenuation {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let light = PointLight::<$type, RGB<$type>>::new(
+                    RGB::new(1.0, 1.0, 1.0),
+                    Point3::new(0.0, 0.0, 0.0),
+                );
+                assert_eq!(light.attenuation(4.0), 1.0);
+
+                let light = light.with_falloff(Falloff::InverseSquare);
+                assert_eq!(light.attenuation(4.0), 1.0 / 16.0);
+                assert_eq!(light.attenuation(0.0), 10000.0);
+
+                let light = light.with_radius(8.0);
+                assert_eq!(light.attenuation(4.0), 1.0 / 16.0);
+                assert_eq!(light.attenuation(8.0), 0.0);
+
+                let light = light.with_falloff(Falloff::Smooth);
+                assert_eq!(
+                    light.attenuation(4.0),
+                    1.0 / 16.0 * (15.0 / 16.0) * (15.0 / 16.0)
+                );
+                assert_eq!(light.attenuation(9.0), 0.0);
+            }
+        };
+    }
+
+    point_light_attenuation! { f32, point_light_attenuation_f32 }
+    point_light_attenuation! { f64, point_light_attenuation_f64 }
+
+    macro_rules! light_intensity_in_physical_units {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let sphere = 4.0 * <$type>::PI;
+                let candela = |intensity: LightIntensity<$type>| {
+                    intensity.to_candela(sphere) / Candela::new(1.0)
+                };
+
+                assert_eq!(candela(LightIntensity::Candela(Candela::new(100.0))), 100.0);
+                assert_eq!(
+                    candela(LightIntensity::WattPerSteradian(WattPerSteradian::new(2.0))),
+                    1366.0
+                );
+                assert!((candela(LightIntensity::Lumen(Lumen::new(800.0))) - 63.662).abs() < 0.001);
+                assert!((candela(LightIntensity::Watt(Watt::new(1.0))) - 54.352).abs() < 0.001);
+
+                let light = PointLight::<$type, RGB<$type>>::new(
+                    RGB::new(2.0, 0.0, 0.0),
+                    Point3::new(0.0, 0.0, 0.0),
+                )
+                .with_intensity(LightIntensity::Candela(Candela::new(100.0)));
+                assert!((light.color.red * 0.2126 - 100.0).abs() < 0.001);
+                assert_eq!(light.color.green, 0.0);
+
+                let light = SpotLight::<$type, RGB<$type>>::new(
+                    RGB::new(0.0, 0.0, 0.0),
+                    Point3::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, -1.0, 0.0),
+                    Radians::new(<$type>::PI / 3.0),
+                )
+                .with_intensity(LightIntensity::Lumen(Lumen::new(<$type>::PI)));
+                assert!((light.color.green - 1.0).abs() < 0.001);
+            }
+        };
+    }
+
+    light_intensity_in_physical_units! { f32, light_intensity_in_physical_units_f32 }
+    light_intensity_in_physical_units! { f64, light_intensity_in_physical_units_f64 }
+
     macro_rules! new_spot_light {
         ($type: ty, $name: ident) => {
             #[test]
@@ -158,4 +1044,200 @@ mod tests {
 
     new_spot_light! { f32, new_spot_light_f32 }
     new_spot_light! { f64, new_spot_light_f64 }
+
+    macro_rules! spot_light_cone_attenuation {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let light = SpotLight::<Meter<$type>, RGB<$type>>::new(
+                    RGB::new(1.0, 1.0, 1.0),
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    Vector3::new(0.0, 0.0, -1.0),
+                    Radians::new(<$type>::PI / 3.0),
+                );
+                let at = |degrees: $type| {
+                    let radians = degrees.to_radians();
+                    Vector3::new(radians.sin(), 0.0, -radians.cos())
+                };
+
+                assert_eq!(light.cone_attenuation(at(45.0)), 1.0);
+                assert_eq!(light.cone_attenuation(at(70.0)), 0.0);
+
+                let light = light
+                    .with_inner_angle(Radians::new(<$type>::PI / 6.0))
+                    .with_falloff_exponent(2.0);
+                let t = ((45.0 as $type).to_radians().cos() - 0.5) / ((0.75 as $type).sqrt() - 0.5);
+
+                assert_eq!(light.cone_attenuation(at(0.0)), 1.0);
+                assert!((light.cone_attenuation(at(45.0)) - t * t).abs() < 0.0001);
+                assert_eq!(light.cone_attenuation(at(70.0)), 0.0);
+            }
+        };
+    }
+
+    spot_light_cone_attenuation! { f32, spot_light_cone_attenuation_f32 }
+    spot_light_cone_attenuation! { f64, spot_light_cone_attenuation_f64 }
+
+    macro_rules! new_area_light {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let color = RGB::new(0.0, 0.5, 1.0);
+                let corner = Point3::new(
+                    Meter::<$type>::new(1.0),
+                    Meter::<$type>::new(4.0),
+                    Meter::<$type>::new(3.0),
+                );
+                let a = Vector3::new(
+                    Meter::<$type>::new(2.0),
+                    Meter::<$type>::new(0.0),
+                    Meter::<$type>::new(0.0),
+                );
+                let b = Vector3::new(
+                    Meter::<$type>::new(0.0),
+                    Meter::<$type>::new(0.0),
+                    Meter::<$type>::new(1.0),
+                );
+
+                let light = AreaLight::<Meter<$type>, RGB<$type>>::new(color, corner, a, b);
+
+                assert_eq!(color, light.color);
+                assert_eq!(corner, light.corner);
+                assert_eq!(a, light.a);
+                assert_eq!(b, light.b);
+                assert_eq!(None, light.shadow_bias);
+            }
+        };
+    }
+
+    new_area_light! { f32, new_area_light_f32 }
+    new_area_light! { f64, new_area_light_f64 }
+
+    macro_rules! new_sphere_light {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let color = RGB::new(0.0, 0.5, 1.0);
+                let position = Point3::new(
+                    Meter::<$type>::new(1.0),
+                    Meter::<$type>::new(4.0),
+                    Meter::<$type>::new(3.0),
+                );
+                let radius = Meter::<$type>::new(0.5);
+
+                let light = SphereLight::<Meter<$type>, RGB<$type>>::new(color, position, radius);
+
+                assert_eq!(color, light.color);
+                assert_eq!(position, light.position);
+                assert_eq!(radius, light.radius);
+                assert_eq!(None, light.shadow_bias);
+            }
+        };
+    }
+
+    new_sphere_light! { f32, new_sphere_light_f32 }
+    new_sphere_light! { f64, new_sphere_light_f64 }
+
+    macro_rules! spot_light_cone_coordinates {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let color = RGB::new(0.0, 0.5, 1.0);
+                let position = Point3::new(
+                    Meter::<$type>::new(0.0),
+                    Meter::<$type>::new(4.0),
+                    Meter::<$type>::new(0.0),
+                );
+                let direction = Vector3::new(0 as $type, -1 as $type, 0 as $type);
+                let angle = Radians::new(<$type>::PI / 4.0);
+
+                let light =
+                    SpotLight::<Meter<$type>, RGB<$type>>::new(color, position, direction, angle);
+
+                let center = light.cone_coordinates(direction);
+                assert_eq!(center, Point2::new(0.5, 0.5));
+
+                let border = light.cone_coordinates(
+                    Vector3::new(1 as $type, -1 as $type, 0 as $type).normalized(),
+                );
+                let distance_to_center = (border - center).magnitude();
+                assert!((distance_to_center - 0.5).abs() < 0.0001);
+            }
+        };
+    }
+
+    spot_light_cone_coordinates! { f32, spot_light_cone_coordinates_f32 }
+    spot_light_cone_coordinates! { f64, spot_light_cone_coordinates_f64 }
+
+    macro_rules! environment_light_coordinates {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let forward = Vector3::new(0 as $type, 0 as $type, -1 as $type);
+                let center = EnvironmentLight::<Meter<$type>>::coordinates(forward);
+                assert_eq!(center, Point2::new(0.5, 0.5));
+
+                let up = EnvironmentLight::<Meter<$type>>::coordinates(Vector3::new(
+                    0 as $type, 1 as $type, 0 as $type,
+                ));
+                assert_eq!(up.y, 1.0);
+
+                let direction = Vector3::new(1 as $type, 2 as $type, 3 as $type).normalized();
+                let coordinates = EnvironmentLight::<Meter<$type>>::coordinates(direction);
+                let round_trip = EnvironmentLight::<Meter<$type>>::direction(coordinates);
+                assert!((round_trip - direction).magnitude() < 0.0001);
+            }
+        };
+    }
+
+    environment_light_coordinates! { f32, environment_light_coordinates_f32 }
+    environment_light_coordinates! { f64, environment_light_coordinates_f64 }
+
+    macro_rules! uniform_environment_light {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let color = RGB::<$type>::new(0.25, 0.5, 1.0);
+                let light = EnvironmentLight::<Meter<$type>>::new(ImageBuffer::new(
+                    Vector2::new(64, 32),
+                    color,
+                ))
+                .with_intensity(2.0);
+
+                let normal = Vector3::new(0 as $type, 1 as $type, 0 as $type);
+                assert_eq!(light.radiance(normal), color * 2.0);
+
+                let irradiance = light.irradiance(Vector3::new(1 as $type, 0.5, 0.25).normalized());
+                assert!((irradiance.red - 0.5).abs() < 0.01);
+                assert!((irradiance.green - 1.0).abs() < 0.01);
+                assert!((irradiance.blue - 2.0).abs() < 0.01);
+            }
+        };
+    }
+
+    uniform_environment_light! { f32, uniform_environment_light_f32 }
+    uniform_environment_light! { f64, uniform_environment_light_f64 }
+
+    macro_rules! environment_light_samples_bright_pixels {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut image =
+                    ImageBuffer::new(Vector2::new(16, 8), RGB::<$type>::new(0.0, 0.0, 0.0));
+                *image.get_mut(Point2::new(12, 2)) = RGB::new(10.0, 10.0, 10.0);
+                let light = EnvironmentLight::<Meter<$type>>::new(image);
+
+                for i in 0..10 {
+                    for j in 0..10 {
+                        let sample = Point2::new(i as $type / 10.0, j as $type / 10.0);
+                        let direction = light.sample_direction(sample);
+                        assert_eq!(light.radiance(direction), RGB::new(10.0, 10.0, 10.0));
+                    }
+                }
+            }
+        };
+    }
+
+    environment_light_samples_bright_pixels! { f32, environment_light_samples_bright_pixels_f32 }
+    environment_light_samples_bright_pixels! { f64, environment_light_samples_bright_pixels_f64 }
 }
@@ -1,5 +1,8 @@
 use colors::Color;
 use image::Image;
+use traits::{ConvenientNumber, FloatingPoint};
+
+use crate::microfacet::DirectionalAlbedo;
 
 pub struct UnshadedMaterial<I: Image> {
     pub texture: I,
@@ -11,6 +14,18 @@ impl<I: Image> UnshadedMaterial<I> {
     }
 }
 
+// A surface that glows in a single color. Geometry with this material can be registered as a
+// light source as well.
+pub struct EmissiveMaterial<C> {
+    pub color: C,
+}
+
+impl<C> EmissiveMaterial<C> {
+    pub fn new(color: C) -> EmissiveMaterial<C> {
+        EmissiveMaterial { color }
+    }
+}
+
 pub struct LambertMaterial<I: Image> {
     pub texture: I,
 }
@@ -40,3 +55,120 @@ impl<I: Image> PhongMaterial<I> {
         }
     }
 }
+
+pub struct PlasticMaterial<I: Image> {
+    pub diffuse_texture: I,
+    pub specular_texture: I,
+    pub exponent: <<I as Image>::ColorType as Color>::ChannelType,
+}
+
+impl<I: Image> PlasticMaterial<I> {
+    pub fn new(
+        diffuse_texture: I,
+        specular_texture: I,
+        exponent: <<I as Image>::ColorType as Color>::ChannelType,
+    ) -> PlasticMaterial<I> {
+        PlasticMaterial {
+            diffuse_texture,
+            specular_texture,
+            exponent,
+        }
+    }
+}
+
+// A rough conductor after the GGX microfacet model. The texture holds the reflectance at normal
+// incidence, the roughness ranges from a mirror at zero to a matte surface at one. The light that
+// bounces between the microfacets several times is added back with the directional albedo, so
+// rough metals do not turn dark.
+pub struct MetalMaterial<I: Image> {
+    pub texture: I,
+    pub roughness: <<I as Image>::ColorType as Color>::ChannelType,
+    pub albedo: DirectionalAlbedo<<<I as Image>::ColorType as Color>::ChannelType>,
+}
+
+impl<I: Image> MetalMaterial<I>
+where
+    <<I as Image>::ColorType as Color>::ChannelType: FloatingPoint + ConvenientNumber,
+    u16: Into<<<I as Image>::ColorType as Color>::ChannelType>,
+{
+    pub fn new(
+        texture: I,
+        roughness: <<I as Image>::ColorType as Color>::ChannelType,
+    ) -> MetalMaterial<I> {
+        MetalMaterial {
+            texture,
+            roughness,
+            albedo: DirectionalAlbedo::new(roughness),
+        }
+    }
+}
+
+// A clear material like glass or water, which reflects and refracts the scene behind it. The
+// transmittance tints the refracted light every time it passes the surface. Only renderers that
+// trace secondary rays can show it.
+pub struct DielectricMaterial<C: Color> {
+    pub transmittance: C,
+    pub index_of_refraction: C::ChannelType,
+}
+
+impl<C: Color> DielectricMaterial<C> {
+    pub fn new(transmittance: C, index_of_refraction: C::ChannelType) -> DielectricMaterial<C> {
+        DielectricMaterial {
+            transmittance,
+            index_of_refraction,
+        }
+    }
+}
+
+pub struct ReflectiveMaterial<M, I: Image> {
+    pub material: M,
+    pub environment: I,
+    pub reflectance: <I as Image>::ColorType,
+}
+
+impl<M, I: Image> ReflectiveMaterial<M, I> {
+    pub fn new(
+        material: M,
+        environment: I,
+        reflectance: <I as Image>::ColorType,
+    ) -> ReflectiveMaterial<M, I> {
+        ReflectiveMaterial {
+            material,
+            environment,
+            reflectance,
+        }
+    }
+}
+
+// The parameters a single instance overrides on a material it shares with other instances. The
+// tint multiplies the color of the surface, the emission strength scales its glow.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct InstanceAttributes<C: Color> {
+    pub tint: C,
+    pub emission_strength: C::ChannelType,
+}
+
+impl<C: Color> InstanceAttributes<C> {
+    pub fn new(tint: C, emission_strength: C::ChannelType) -> InstanceAttributes<C> {
+        InstanceAttributes {
+            tint,
+            emission_strength,
+        }
+    }
+}
+
+// A shared material as one instance sees it. Only the attributes are stored per instance, the
+// material itself is not copied.
+pub struct InstanceMaterial<M, C: Color> {
+    pub material: M,
+    pub attributes: InstanceAttributes<C>,
+}
+
+impl<M, C: Color> InstanceMaterial<M, C> {
+    pub fn new(material: M, attributes: InstanceAttributes<C>) -> InstanceMaterial<M, C> {
+        InstanceMaterial {
+            material,
+            attributes,
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use sampling::split;
+use traits::{ConvenientNumber, FloatingPoint};
+
+use crate::prefiltered_environment::radical_inverse;
+
+// The cosines between the normal and the view direction the directional albedo is tabulated for,
+// evenly spaced from grazing to perpendicular.
+const ENTRIES: usize = 32;
+
+// The half vectors each entry of the table integrates over.
+const SAMPLES: usize = 512;
+
+// The GGX (Trowbridge-Reitz) distribution of microfacet normals for the cosine between a half
+// vector and the normal.
+pub fn ggx_distribution<V>(n_dot_h: V, alpha: V) -> V
+where
+    V: FloatingPoint + ConvenientNumber,
+{
+    let alpha2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (alpha2 - V::one()) + V::one();
+    alpha2 / (V::PI * d * d)
+}
+
+// The separable Smith term for GGX: the fraction of microfacets that is neither hidden from the
+// view nor from the light.
+pub fn smith_masking<V>(n_dot_v: V, n_dot_l: V, alpha: V) -> V
+where
+    V: FloatingPoint + ConvenientNumber,
+{
+    smith_g1(n_dot_v, alpha) * smith_g1(n_dot_l, alpha)
+}
+
+fn smith_g1<V>(n_dot_x: V, alpha: V) -> V
+where
+    V: FloatingPoint + ConvenientNumber,
+{
+    let alpha2 = alpha * alpha;
+    let two = V::one() + V::one();
+    two * n_dot_x / (n_dot_x + (alpha2 + (V::one() - alpha2) * n_dot_x * n_dot_x).sqrt())
+}
+
+// The share of light a perfectly reflecting GGX surface of a roughness reflects after a single
+// bounce on its microfacets, for the cosine between the normal and the view direction. The rest
+// bounces between the microfacets before it leaves the surface, which a single scattering model
+// loses, so rough surfaces become too dark. The table is integrated once per roughness with
+// importance sampled half vectors.
+pub struct DirectionalAlbedo<V> {
+    values: Vec<V>,
+}
+
+impl<V> DirectionalAlbedo<V>
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    pub fn new(roughness: V) -> DirectionalAlbedo<V> {
+        let one = V::one();
+        let zero = V::zero();
+        let alpha = ggx_alpha(roughness);
+        let alpha2 = alpha * alpha;
+
+        let values = (0..ENTRIES)
+            .map(|i| {
+                let n_dot_v = (to_value::<V>(i) / to_value(ENTRIES - 1)).max(one / to_value(64));
+                let (v_x, v_z) = ((one - n_dot_v * n_dot_v).sqrt(), n_dot_v);
+
+                let mut sum = zero;
+                for s in 0..SAMPLES {
+                    let phi = (V::PI + V::PI) * to_value(s) / to_value(SAMPLES);
+                    let xi = radical_inverse::<V>(s);
+                    let cos_theta = ((one - xi) / (one + (alpha2 - one) * xi)).sqrt();
+                    let sin_theta = (one - cos_theta * cos_theta).max(zero).sqrt();
+
+                    // The light direction is the view direction mirrored at the half vector.
+                    let v_dot_h = v_x * sin_theta * phi.cos() + v_z * cos_theta;
+                    let n_dot_l = (v_dot_h + v_dot_h) * cos_theta - n_dot_v;
+                    if n_dot_l > zero && v_dot_h > zero {
+                        sum += smith_masking(n_dot_v, n_dot_l, alpha) * v_dot_h
+                            / (cos_theta * n_dot_v);
+                    }
+                }
+
+                sum / to_value(SAMPLES)
+            })
+            .collect();
+
+        DirectionalAlbedo { values }
+    }
+
+    pub fn get(&self, n_dot_v: V) -> V {
+        let (i, f) = split(n_dot_v.clamp(V::zero(), V::one()), ENTRIES - 1);
+        self.values[i] * (V::one() - f) + self.values[i + 1] * f
+    }
+
+    // The factor a single scattering reflection of a surface with a reflectance at normal
+    // incidence has to be scaled by to account for the light scattered multiple times (Turquin,
+    // "Practical multiple scattering compensation for microfacet models").
+    pub fn multiple_scattering(&self, n_dot_v: V, reflectance: V) -> V {
+        V::one() + reflectance * (V::one() / self.get(n_dot_v) - V::one())
+    }
+}
+
+// The width of the GGX lobe for a perceptual roughness between zero and one. A perfectly smooth
+// surface is a delta distribution, so the width is kept a little above zero.
+pub fn ggx_alpha<V>(roughness: V) -> V
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    let roughness = roughness.clamp(V::zero(), V::one());
+    (roughness * roughness).max(V::one() / to_value(1000))
+}
+
+fn to_value<V>(value: usize) -> V
+where
+    u16: Into<V>,
+{
+    (value as u16).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! directional_albedo {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A brute force integration of the reflected light over the hemisphere.
+                let reference = |n_dot_v: $type, roughness: $type| {
+                    let alpha = ggx_alpha(roughness);
+                    let v = (0.0, (1.0 - n_dot_v * n_dot_v).sqrt(), n_dot_v);
+                    let steps = 1000;
+                    let mut sum = 0.0;
+                    for i in 0..steps {
+                        let theta = (i as $type + 0.5) / steps as $type
+                            * std::f64::consts::FRAC_PI_2 as $type;
+                        for j in 0..steps {
+                            let phi = (j as $type + 0.5) / steps as $type
+                                * std::f64::consts::TAU as $type;
+                            let l = (
+                                theta.sin() * phi.cos(),
+                                theta.sin() * phi.sin(),
+                                theta.cos(),
+                            );
+                            let h = (v.0 + l.0, v.1 + l.1, v.2 + l.2);
+                            let length = (h.0 * h.0 + h.1 * h.1 + h.2 * h.2).sqrt();
+                            let n_dot_h = h.2 / length;
+                            let brdf = ggx_distribution(n_dot_h, alpha)
+                                * smith_masking(n_dot_v, l.2, alpha)
+                                / (4.0 * n_dot_v * l.2);
+                            let solid_angle = theta.sin()
+                                * std::f64::consts::FRAC_PI_2 as $type
+                                * std::f64::consts::TAU as $type
+                                / (steps * steps) as $type;
+                            sum += brdf * l.2 * solid_angle;
+                        }
+                    }
+                    sum
+                };
+
+                let rough = DirectionalAlbedo::<$type>::new(1.0);
+                for n_dot_v in [0.25, 0.5, 1.0] {
+                    let expected = reference(n_dot_v, 1.0);
+                    assert!(
+                        (rough.get(n_dot_v) - expected).abs() < 0.01,
+                        "{} {}",
+                        rough.get(n_dot_v),
+                        expected
+                    );
+                }
+
+                // A smooth surface reflects almost everything at once, a rough one loses a
+                // noticeable part that the compensation restores.
+                let smooth = DirectionalAlbedo::<$type>::new(0.1);
+                assert!(smooth.get(1.0) > 0.99);
+                assert!(rough.get(0.5) < 0.8);
+                assert!(
+                    (rough.get(0.5) * rough.multiple_scattering(0.5, 1.0) - 1.0).abs() < 0.0001
+                );
+                assert_eq!(rough.multiple_scattering(0.5, 0.0), 1.0);
+            }
+        };
+    }
+
+    directional_albedo! { f32, directional_albedo_f32 }
+    directional_albedo! { f64, directional_albedo_f64 }
+}
@@ -0,0 +1,299 @@
+use std::ops::Div;
+
+use colors::RGB;
+use image::{Image, ImageBuffer, WritableImage};
+use math::{Point2, Vector2, Vector3};
+use sampling::split;
+use traits::{
+    Abs, Clamp, ConvenientNumber, Cos, FloatingPoint, Half, Log2, Max, Number, One, Pi, Sin, Sqrt,
+    Zero,
+};
+
+use crate::light::EnvironmentLight;
+
+// The number of roughness levels, from a mirror to a roughness of one.
+const LEVELS: usize = 6;
+
+// The size of the first convolved level. Rough reflections are blurry, so a small map is enough.
+const BASE_SIZE: Vector2<usize> = Vector2 { x: 256, y: 128 };
+
+// The directions of the GGX lobe each texel of a convolved level integrates over.
+const SAMPLES: usize = 64;
+
+// Glossy reflections of an environment for a fast preview, after the split sum approximation of
+// Unreal Engine 4. Each level of the chain holds the environment convolved with the GGX lobe of a
+// roughness, a lookup blends the two levels around the roughness of a surface. The view
+// direction is assumed to be the normal, so the lobe does not stretch at grazing angles, which is
+// close to a traced reflection of distant light everywhere else.
+pub struct PrefilteredEnvironment<T>
+where
+    T: Div,
+    <T as Div>::Output: Number,
+{
+    levels: Vec<ImageBuffer<RGB<<T as Div>::Output>>>,
+}
+
+impl<T> PrefilteredEnvironment<T>
+where
+    T: Div,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    u16: Into<<T as Div>::Output>,
+{
+    pub fn new(image: &ImageBuffer<RGB<<T as Div>::Output>>) -> PrefilteredEnvironment<T> {
+        let mips = mip_chain(image);
+        let size = image.size();
+
+        let mut levels = vec![mips[0].clone()];
+        let mut level_size = Vector2::new(
+            (size.x / 2).clamp(1, BASE_SIZE.x),
+            (size.y / 2).clamp(1, BASE_SIZE.y),
+        );
+        for level in 1..LEVELS {
+            let roughness = to_value::<<T as Div>::Output>(level) / to_value(LEVELS - 1);
+            levels.push(Self::convolve(&mips, level_size, roughness));
+            level_size = Vector2::new((level_size.x / 2).max(1), (level_size.y / 2).max(1));
+        }
+
+        PrefilteredEnvironment { levels }
+    }
+
+    pub fn levels(&self) -> &[ImageBuffer<RGB<<T as Div>::Output>>] {
+        &self.levels
+    }
+
+    // The radiance reflected into a normalized direction by a surface of a roughness between zero
+    // (a mirror) and one.
+    pub fn radiance(
+        &self,
+        direction: Vector3<<T as Div>::Output>,
+        roughness: <T as Div>::Output,
+    ) -> RGB<<T as Div>::Output> {
+        let one = <T as Div>::Output::one();
+        let coordinates = EnvironmentLight::<T>::coordinates(direction);
+
+        let (level, f) = split(roughness.clamp(Zero::zero(), one), LEVELS - 1);
+        bilinear(&self.levels[level], coordinates) * (one - f)
+            + bilinear(&self.levels[level + 1], coordinates) * f
+    }
+
+    // Integrates the lobe by importance sampling. Each sample reads a mip level whose texels
+    // cover about the solid angle of the sample, so a few samples do not miss small bright spots.
+    fn convolve(
+        mips: &[ImageBuffer<RGB<<T as Div>::Output>>],
+        size: Vector2<usize>,
+        roughness: <T as Div>::Output,
+    ) -> ImageBuffer<RGB<<T as Div>::Output>> {
+        let zero = <T as Div>::Output::zero();
+        let one = <T as Div>::Output::one();
+        let two = one + one;
+        let pi = <T as Div>::Output::PI;
+
+        let alpha = roughness * roughness;
+        let alpha2 = alpha * alpha;
+        let source_size = mips[0].size();
+        let texel_solid_angle = two * two * pi
+            / (to_value::<<T as Div>::Output>(source_size.x) * to_value(source_size.y));
+
+        let mut level = ImageBuffer::new(size, RGB::new(zero, zero, zero));
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let n = EnvironmentLight::<T>::direction(Point2::new(
+                    (to_value::<<T as Div>::Output>(x) + one.half()) / to_value(size.x),
+                    one - (to_value::<<T as Div>::Output>(y) + one.half()) / to_value(size.y),
+                ));
+                let up = if n.y.abs() < one - one / to_value(1000) {
+                    Vector3::new(zero, one, zero)
+                } else {
+                    Vector3::new(one, zero, zero)
+                };
+                let tangent = Vector3::cross(up, n).normalized();
+                let bitangent = Vector3::cross(n, tangent);
+
+                let mut sum = RGB::new(zero, zero, zero);
+                let mut weight = zero;
+                for i in 0..SAMPLES {
+                    let phi = (pi + pi) * to_value(i) / to_value(SAMPLES);
+                    let xi = radical_inverse::<<T as Div>::Output>(i);
+                    let cos_theta = ((one - xi) / (one + (alpha2 - one) * xi)).sqrt();
+                    let sin_theta = (one - cos_theta * cos_theta).max(zero).sqrt();
+
+                    let h = tangent * (sin_theta * phi.cos())
+                        + bitangent * (sin_theta * phi.sin())
+                        + n * cos_theta;
+                    let l = h * (two * n.dot(h)) - n;
+                    let n_dot_l = n.dot(l);
+                    if n_dot_l <= zero {
+                        continue;
+                    }
+
+                    // With the view along the normal, the density of l is D(h) / 4.
+                    let d = cos_theta * cos_theta * (alpha2 - one) + one;
+                    let pdf = alpha2 / (pi * d * d) / (two * two);
+                    let sample_solid_angle = one / (to_value::<<T as Div>::Output>(SAMPLES) * pdf);
+                    let mip = ((sample_solid_angle / texel_solid_angle).log2().half() + one)
+                        .clamp(zero, to_value(mips.len() - 1));
+                    let (mip, _) = split(mip / to_value(mips.len()), mips.len());
+
+                    sum += bilinear(&mips[mip], EnvironmentLight::<T>::coordinates(l)) * n_dot_l;
+                    weight += n_dot_l;
+                }
+
+                *level.get_mut(Point2::new(x, y)) = sum * (one / weight);
+            }
+        }
+
+        level
+    }
+}
+
+// The image followed by ever smaller copies of it, each averaging 2x2 texels of the one before.
+fn mip_chain<V>(image: &ImageBuffer<RGB<V>>) -> Vec<ImageBuffer<RGB<V>>>
+where
+    V: FloatingPoint + ConvenientNumber,
+{
+    let zero = V::zero();
+    let quarter = (V::one().half()).half();
+
+    let mut mips = vec![image.clone()];
+    loop {
+        let source = &mips[mips.len() - 1];
+        let size = source.size();
+        if size.x < 2 || size.y < 2 {
+            break;
+        }
+
+        let mip_size = Vector2::new(size.x / 2, size.y / 2);
+        let mut mip = ImageBuffer::new(mip_size, RGB::new(zero, zero, zero));
+        for y in 0..mip_size.y {
+            for x in 0..mip_size.x {
+                *mip.get_mut(Point2::new(x, y)) = (source.get(Point2::new(2 * x, 2 * y))
+                    + source.get(Point2::new(2 * x + 1, 2 * y))
+                    + source.get(Point2::new(2 * x, 2 * y + 1))
+                    + source.get(Point2::new(2 * x + 1, 2 * y + 1)))
+                    * quarter;
+            }
+        }
+        mips.push(mip);
+    }
+
+    mips
+}
+
+// Interpolates between the four texels around texture coordinates of an equirectangular image.
+fn bilinear<V>(image: &ImageBuffer<RGB<V>>, coordinates: Point2<V>) -> RGB<V>
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    let size = image.size();
+    let one = V::one();
+
+    let (x0, fx) = split(coordinates.x - (one / to_value(size.x)).half(), size.x);
+    let (y0, fy) = split(
+        one - coordinates.y - (one / to_value(size.y)).half(),
+        size.y,
+    );
+    let x1 = (x0 + 1) % size.x;
+    let y1 = (y0 + 1).min(size.y - 1);
+
+    let top = image.get(Point2::new(x0, y0)) * (one - fx) + image.get(Point2::new(x1, y0)) * fx;
+    let bottom = image.get(Point2::new(x0, y1)) * (one - fx) + image.get(Point2::new(x1, y1)) * fx;
+
+    top * (one - fy) + bottom * fy
+}
+
+// The Van der Corput sequence, the second coordinate of the Hammersley point set.
+pub(crate) fn radical_inverse<V>(i: usize) -> V
+where
+    V: FloatingPoint,
+    u16: Into<V>,
+{
+    let denominator: V = 256u16.into();
+    let reversed: V = (i as u16).reverse_bits().into();
+    reversed / denominator / denominator
+}
+
+fn to_value<V>(value: usize) -> V
+where
+    u16: Into<V>,
+{
+    (value as u16).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use units::length::Meter;
+
+    macro_rules! prefiltered_environment_radiance {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A bright sky over a dark ground.
+                let size = Vector2::new(64, 32);
+                let mut image = ImageBuffer::new(size, RGB::<$type>::new(0.0, 0.0, 0.0));
+                for y in 0..size.y / 2 {
+                    for x in 0..size.x {
+                        *image.get_mut(Point2::new(x, y)) = RGB::new(1.0, 1.0, 1.0);
+                    }
+                }
+                let environment = PrefilteredEnvironment::<Meter<$type>>::new(&image);
+                assert_eq!(environment.levels().len(), LEVELS);
+
+                let up = Vector3::new(0.0, 1.0, 0.0);
+                let down = Vector3::new(0.0, -1.0, 0.0);
+                let horizon = Vector3::new(0.0, 0.0, -1.0);
+
+                // A mirror sees the environment itself.
+                assert_eq!(environment.radiance(up, 0.0), RGB::new(1.0, 1.0, 1.0));
+                assert_eq!(environment.radiance(down, 0.0), RGB::new(0.0, 0.0, 0.0));
+
+                // A rough surface sees a blend of both halves towards the horizon, but the lobe
+                // around the zenith stays in the sky.
+                let rough = environment.radiance(horizon, 1.0).red;
+                assert!((rough - 0.5).abs() < 0.1, "{}", rough);
+                assert!(environment.radiance(up, 0.5).red > 0.95);
+                assert!(environment.radiance(down, 0.5).red < 0.05);
+
+                // For an intermediate roughness the lookup matches a brute force integration of
+                // the lobe over all texels of the environment.
+                let roughness: $type = 0.6;
+                let alpha2 = roughness.powi(4);
+                let n = Vector3::<$type>::new(1.0, 0.3, 0.0).normalized();
+                let mut sum = 0.0;
+                let mut weight = 0.0;
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let l = EnvironmentLight::<Meter<$type>>::direction(Point2::new(
+                            (x as $type + 0.5) / size.x as $type,
+                            1.0 - (y as $type + 0.5) / size.y as $type,
+                        ));
+                        let n_dot_l = n.dot(l);
+                        if n_dot_l <= 0.0 {
+                            continue;
+                        }
+                        let h = (n + l).normalized();
+                        let n_dot_h = n.dot(h);
+                        let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+                        // The lobe times the solid angle of the texel.
+                        let w = alpha2 / (d * d) * (1.0 - l.y * l.y).sqrt() * n_dot_l;
+                        sum += image.get(Point2::new(x, y)).red * w;
+                        weight += w;
+                    }
+                }
+                let reference = sum / weight;
+                let prefiltered = environment.radiance(n, roughness).red;
+                assert!(
+                    (prefiltered - reference).abs() < 0.03,
+                    "{} {}",
+                    prefiltered,
+                    reference
+                );
+            }
+        };
+    }
+
+    prefiltered_environment_radiance! { f32, prefiltered_environment_radiance_f32 }
+    prefiltered_environment_radiance! { f64, prefiltered_environment_radiance_f64 }
+}
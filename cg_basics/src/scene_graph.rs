@@ -1,13 +1,22 @@
 use std::collections::HashMap;
 
-pub struct Scene3<C, L, CAM, G> {
+use colors::Color;
+use math::transform::Transform3;
+use math::Vector3;
+
+use crate::background::Background;
+use crate::volume::VolumeRegion;
+
+pub struct Scene3<C: Color, L, CAM, G> {
     pub bg_color: C,
+    pub background: Option<Background<C>>,
     pub lights: Vec<L>,
     pub cameras: HashMap<String, CAM>,
     pub geometries: Vec<G>,
+    pub volumes: Vec<VolumeRegion<C>>,
 }
 
-impl<C, L, CAM, G> Scene3<C, L, CAM, G> {
+impl<C: Color, L, CAM, G> Scene3<C, L, CAM, G> {
     pub fn new(
         bg_color: C,
         lights: Vec<L>,
@@ -16,24 +25,151 @@ impl<C, L, CAM, G> Scene3<C, L, CAM, G> {
     ) -> Scene3<C, L, CAM, G> {
         Scene3 {
             bg_color,
+            background: None,
             lights,
             cameras,
             geometries,
+            volumes: Vec::new(),
         }
     }
+
+    // Replaces the background color for rays that miss all geometries.
+    pub fn with_background(self, background: Background<C>) -> Scene3<C, L, CAM, G> {
+        Scene3 {
+            background: Some(background),
+            ..self
+        }
+    }
+}
+// Which lights illuminate a geometry, given by the names of the lights. Lights without a name are
+// only excluded by a list of included lights.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum LightLinks {
+    #[default]
+    All,
+    Include(Vec<String>),
+    Exclude(Vec<String>),
 }
+
+impl LightLinks {
+    pub fn includes(&self, light: Option<&str>) -> bool {
+        match (self, light) {
+            (LightLinks::All, _) => true,
+            (LightLinks::Include(names), Some(light)) => names.iter().any(|name| name == light),
+            (LightLinks::Include(_), None) => false,
+            (LightLinks::Exclude(names), Some(light)) => names.iter().all(|name| name != light),
+            (LightLinks::Exclude(_), None) => true,
+        }
+    }
+}
+
 pub struct RenderableGeometry<G, M, T> {
     pub geometry: G,
     pub material: M,
-    pub transform: T,
+    pub transform: Transform3<T>,
+    pub shadow_bias: Option<T>,
+    pub epsilon: Option<T>,
+    pub light_links: LightLinks,
+    pub motion: Option<Vector3<T>>,
+    pub name: Option<String>,
 }
 
 impl<G, M, T> RenderableGeometry<G, M, T> {
-    pub fn new(geometry: G, material: M, transform: T) -> RenderableGeometry<G, M, T> {
+    pub fn new(geometry: G, material: M, transform: Transform3<T>) -> RenderableGeometry<G, M, T> {
         RenderableGeometry {
             geometry,
             material,
             transform,
+            shadow_bias: None,
+            epsilon: None,
+            light_links: LightLinks::All,
+            motion: None,
+            name: None,
+        }
+    }
+
+    // Shadow rays ignore hits on this geometry that are closer than the bias. Overrides the bias
+    // of the light and the global shadow tolerance of the renderer.
+    pub fn with_shadow_bias(self, shadow_bias: T) -> RenderableGeometry<G, M, T> {
+        RenderableGeometry {
+            shadow_bias: Some(shadow_bias),
+            ..self
+        }
+    }
+
+    // The precision of the intersections with this geometry, for scenes that mix huge and tiny
+    // objects. Camera rays ignore hits on the geometry closer than the epsilon, and shadow rays
+    // leaving its surface use it instead of the global shadow tolerance.
+    pub fn with_epsilon(self, epsilon: T) -> RenderableGeometry<G, M, T> {
+        RenderableGeometry {
+            epsilon: Some(epsilon),
+            ..self
+        }
+    }
+
+    pub fn with_light_links(self, light_links: LightLinks) -> RenderableGeometry<G, M, T> {
+        RenderableGeometry {
+            light_links,
+            ..self
+        }
+    }
+
+    // The name other parts of the scene refer to the geometry by, e.g. a camera that focuses on it.
+    pub fn with_name(self, name: &str) -> RenderableGeometry<G, M, T> {
+        RenderableGeometry {
+            name: Some(name.to_string()),
+            ..self
+        }
+    }
+
+    // Moves the geometry along the motion per unit of time. The transform is its placement at time
+    // zero.
+    pub fn with_motion(self, motion: Vector3<T>) -> RenderableGeometry<G, M, T> {
+        RenderableGeometry {
+            motion: Some(motion),
+            ..self
+        }
+    }
+}
+
+// A triangle mesh whose vertices are given in world coordinates.
+pub struct RenderableMesh<G, M, T> {
+    pub mesh: G,
+    pub material: M,
+    pub shadow_bias: Option<T>,
+    pub epsilon: Option<T>,
+    pub light_links: LightLinks,
+}
+
+impl<G, M, T> RenderableMesh<G, M, T> {
+    pub fn new(mesh: G, material: M) -> RenderableMesh<G, M, T> {
+        RenderableMesh {
+            mesh,
+            material,
+            shadow_bias: None,
+            epsilon: None,
+            light_links: LightLinks::All,
+        }
+    }
+
+    pub fn with_shadow_bias(self, shadow_bias: T) -> RenderableMesh<G, M, T> {
+        RenderableMesh {
+            shadow_bias: Some(shadow_bias),
+            ..self
+        }
+    }
+
+    pub fn with_epsilon(self, epsilon: T) -> RenderableMesh<G, M, T> {
+        RenderableMesh {
+            epsilon: Some(epsilon),
+            ..self
+        }
+    }
+
+    pub fn with_light_links(self, light_links: LightLinks) -> RenderableMesh<G, M, T> {
+        RenderableMesh {
+            light_links,
+            ..self
         }
     }
 }
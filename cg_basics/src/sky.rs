@@ -0,0 +1,263 @@
+use std::ops::Div;
+
+use colors::RGB;
+use image::{ImageBuffer, WritableImage};
+use math::{Point2, Vector2, Vector3};
+use sampling::split;
+use traits::{ConvenientNumber, Exp, FloatingPoint, Zero};
+
+use crate::exposure::PhysicalExposure;
+use crate::light::EnvironmentLight;
+
+// The sun covers about half a degree of the sky, which is a solid angle of 6.8e-5 sr.
+const SUN_SOLID_ANGLE: f32 = 6.8e-5;
+
+// The clear sky model of Preetham, Shirley and Smits, "A Practical Analytic Model for Daylight".
+// The turbidity describes the haziness of the atmosphere, 2 is a very clear sky, 10 is hazy.
+// The model does not include the disc of the sun itself, it is added to the environment light.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PreethamSky<V> {
+    pub sun_direction: Vector3<V>,
+    pub turbidity: V,
+}
+
+impl<V> PreethamSky<V>
+where
+    V: FloatingPoint + ConvenientNumber + Exp<Output = V> + From<f32>,
+    u16: Into<V>,
+{
+    pub fn new(sun_direction: Vector3<V>, turbidity: V) -> PreethamSky<V> {
+        PreethamSky {
+            sun_direction,
+            turbidity,
+        }
+    }
+
+    // Linear sRGB radiance for a normalized direction, with the luminance in cd/m² as the
+    // physical exposure expects, so a clear sky ends up around middle gray at EV 15. The model
+    // gives it in kcd/m². Directions below the horizon get the color of the horizon.
+    pub fn radiance(&self, direction: Vector3<V>) -> RGB<V> {
+        let c = |value: f32| -> V { value.into() };
+        let one = V::one();
+
+        let cos_theta = direction.y.clamp(c(0.001), one);
+        let direction = Vector3::new(direction.x, cos_theta, direction.z).normalized();
+        let cos_gamma = direction.dot(self.sun_direction).clamp(-one, one);
+
+        let [luminance, x, y] = self.perez(cos_theta, cos_gamma.acos());
+        let luminance = luminance * c(1000.0);
+
+        let big_x = x / y * luminance;
+        let big_z = (one - x - y) / y * luminance;
+
+        RGB::new(
+            c(3.2406) * big_x - c(1.5372) * luminance - c(0.4986) * big_z,
+            c(-0.9689) * big_x + c(1.8758) * luminance + c(0.0415) * big_z,
+            c(0.0557) * big_x - c(0.2040) * luminance + c(1.0570) * big_z,
+        )
+    }
+
+    pub fn environment_light<T>(&self, size: Vector2<usize>) -> EnvironmentLight<T>
+    where
+        T: Div<Output = V>,
+    {
+        let zero = V::zero();
+        let one = V::one();
+        let to_value = |value: usize| -> V { (value as u16).into() };
+
+        let mut image = ImageBuffer::new(size, RGB::new(zero, zero, zero));
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let direction = EnvironmentLight::<T>::direction(Point2::new(
+                    (to_value(x) + one.half()) / to_value(size.x),
+                    one - (to_value(y) + one.half()) / to_value(size.y),
+                ));
+                *image.get_mut(Point2::new(x, y)) = self.radiance(direction);
+            }
+        }
+
+        // The sun is much smaller than a pixel, so the light of its disc is spread over the solid
+        // angle of the pixel it lies in.
+        if self.sun_direction.y > zero {
+            let coordinates = EnvironmentLight::<T>::coordinates(self.sun_direction);
+            let (x, _) = split(coordinates.x, size.x);
+            let (y, _) = split(one - coordinates.y, size.y);
+            let latitude = EnvironmentLight::<T>::latitude_of_row(y, size.y);
+            let pixel_solid_angle =
+                (V::PI + V::PI) * V::PI * latitude.cos() / (to_value(size.x) * to_value(size.y));
+            let luminance = V::from(PhysicalExposure::<f32>::SUN_LUMINANCE)
+                * V::from(SUN_SOLID_ANGLE)
+                / pixel_solid_angle;
+            *image.get_mut(Point2::new(x, y)) += RGB::new(luminance, luminance, luminance);
+        }
+
+        EnvironmentLight::new(image)
+    }
+
+    // Luminance and chromaticity for a direction with the given angles to the zenith and to
+    // the sun.
+    fn perez(&self, cos_theta: V, gamma: V) -> [V; 3] {
+        let c = |value: f32| -> V { value.into() };
+        let one = V::one();
+        let t = self.turbidity;
+
+        let cos_theta_sun = self.sun_direction.y.clamp(Zero::zero(), one);
+        let theta_sun = cos_theta_sun.acos();
+
+        let chi = (c(4.0 / 9.0) - t / c(120.0)) * (V::PI - theta_sun - theta_sun);
+        let zenith_luminance = (c(4.0453) * t - c(4.9710)) * chi.tan() - c(0.2155) * t + c(2.4192);
+
+        let polynomial = |a: [f32; 4], b: [f32; 4], d: [f32; 4]| {
+            let cubic = |k: [f32; 4]| {
+                ((c(k[0]) * theta_sun + c(k[1])) * theta_sun + c(k[2])) * theta_sun + c(k[3])
+            };
+            t * t * cubic(a) + t * cubic(b) + cubic(d)
+        };
+        let zenith_x = polynomial(
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        );
+        let zenith_y = polynomial(
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        );
+
+        let distribution = |coefficients: [[f32; 2]; 5]| {
+            let [a, b, cc, d, e] = coefficients.map(|[m, n]| c(m) * t + c(n));
+            let f = |cos_theta: V, gamma: V| {
+                let cos_gamma = gamma.cos();
+                (one + a * (b / cos_theta).exp())
+                    * (one + cc * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+            };
+            f(cos_theta, gamma) / f(one, theta_sun)
+        };
+
+        [
+            zenith_luminance
+                * distribution([
+                    [0.1787, -1.4630],
+                    [-0.3554, 0.4275],
+                    [-0.0227, 5.3251],
+                    [0.1206, -2.5771],
+                    [-0.0670, 0.3703],
+                ]),
+            zenith_x
+                * distribution([
+                    [-0.0193, -0.2592],
+                    [-0.0665, 0.0008],
+                    [-0.0004, 0.2125],
+                    [-0.0641, -0.8989],
+                    [-0.0033, 0.0452],
+                ]),
+            zenith_y
+                * distribution([
+                    [-0.0167, -0.2608],
+                    [-0.0950, 0.0092],
+                    [-0.0079, 0.2102],
+                    [-0.0441, -1.6537],
+                    [-0.0109, 0.0529],
+                ]),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::Image;
+    use traits::Pi;
+    use units::length::Meter;
+
+    macro_rules! sky_luminance_at_zenith {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let sun = Vector3::new(1.0 as $type, 1.0, 0.0).normalized();
+                let sky = PreethamSky::new(sun, 2.5 as $type);
+
+                // Zenith luminance of the model for a sun at 45 degrees, in cd/m².
+                let chi = (4.0 as $type / 9.0 - 2.5 / 120.0) * (<$type>::PI / 2.0);
+                let expected =
+                    ((4.0453 * 2.5 - 4.9710) * chi.tan() - 0.2155 * 2.5 + 2.4192) * 1000.0;
+
+                let zenith = sky.radiance(Vector3::new(0.0, 1.0, 0.0));
+                let luminance = 0.2126 * zenith.red + 0.7152 * zenith.green + 0.0722 * zenith.blue;
+
+                assert!((luminance / expected - 1.0).abs() < 0.01);
+                assert!(zenith.blue > zenith.red);
+
+                // A clear sky is exposed for by the sunny 16 rule.
+                let exposed = luminance * PhysicalExposure::<$type>::daylight().multiplier();
+                assert!((exposed / 0.18).log2().abs() < 0.5);
+            }
+        };
+    }
+
+    sky_luminance_at_zenith! { f32, sky_luminance_at_zenith_f32 }
+    sky_luminance_at_zenith! { f64, sky_luminance_at_zenith_f64 }
+
+    macro_rules! sky_is_brighter_towards_the_sun {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let sun = Vector3::new(1.0 as $type, 0.5, 0.0).normalized();
+                let sky = PreethamSky::new(sun, 3.0 as $type);
+
+                let towards = sky.radiance(Vector3::new(1.0 as $type, 0.6, 0.0).normalized());
+                let away = sky.radiance(Vector3::new(-1.0 as $type, 0.6, 0.0).normalized());
+
+                assert!(towards.red > away.red);
+                assert!(towards.green > away.green);
+                assert!(towards.blue > away.blue);
+
+                let light = sky.environment_light::<Meter<$type>>(Vector2::new(32, 16));
+                assert_eq!(light.image().size(), Vector2::new(32, 16));
+            }
+        };
+    }
+
+    sky_is_brighter_towards_the_sun! { f32, sky_is_brighter_towards_the_sun_f32 }
+    sky_is_brighter_towards_the_sun! { f64, sky_is_brighter_towards_the_sun_f64 }
+
+    macro_rules! sun_disc_lies_in_one_pixel {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let sun = Vector3::new(1.0 as $type, 1.0, 0.0).normalized();
+                let light = PreethamSky::new(sun, 2.5 as $type)
+                    .environment_light::<Meter<$type>>(Vector2::new(64, 32));
+                let image = light.image();
+
+                let (mut brightest, mut position) = (0.0, Point2::new(0, 0));
+                for y in 0..32 {
+                    for x in 0..64 {
+                        let color = image.get(Point2::new(x, y));
+                        if color.green > brightest {
+                            (brightest, position) = (color.green, Point2::new(x, y));
+                        }
+                    }
+                }
+
+                // The pixel sends the light of the sun, far more than the sky around it.
+                let latitude = EnvironmentLight::<Meter<$type>>::latitude_of_row(position.y, 32);
+                let solid_angle = 2.0 * <$type>::PI * <$type>::PI * latitude.cos() / (64.0 * 32.0);
+                let expected = PhysicalExposure::<$type>::SUN_LUMINANCE * SUN_SOLID_ANGLE as $type;
+                assert!((brightest * solid_angle / expected - 1.0).abs() < 0.01);
+
+                // The pixels are 5.625 degrees wide, so the sun lies within 4 degrees of the
+                // center of its pixel.
+                let center = EnvironmentLight::<Meter<$type>>::direction(Point2::new(
+                    (position.x as $type + 0.5) / 64.0,
+                    latitude / <$type>::PI + 0.5,
+                ));
+                assert!(center.dot(sun).acos().to_degrees() < 4.0);
+            }
+        };
+    }
+
+    sun_disc_lies_in_one_pixel! { f32, sun_disc_lies_in_one_pixel_f32 }
+    sun_disc_lies_in_one_pixel! { f64, sun_disc_lies_in_one_pixel_f64 }
+}
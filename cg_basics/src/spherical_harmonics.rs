@@ -0,0 +1,197 @@
+use colors::RGB;
+use image::{Image, ImageBuffer};
+use math::{Point2, Vector3};
+use traits::{ConvenientNumber, FloatingPoint, Zero};
+
+use crate::light::EnvironmentLight;
+
+// The first nine real spherical harmonics (bands 0 to 2) of a spherical function. Nine
+// coefficients are enough to represent the irradiance of an environment with an error of a few
+// percent, see Ramamoorthi and Hanrahan, "An Efficient Representation for Irradiance Environment
+// Maps".
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SphericalHarmonics<V> {
+    pub coefficients: [RGB<V>; 9],
+}
+
+impl<V> SphericalHarmonics<V>
+where
+    V: FloatingPoint + ConvenientNumber + From<f32>,
+    u16: Into<V>,
+{
+    pub fn new() -> SphericalHarmonics<V> {
+        SphericalHarmonics {
+            coefficients: [RGB::new(Zero::zero(), Zero::zero(), Zero::zero()); 9],
+        }
+    }
+
+    // Projects an equirectangular environment map with the layout of the environment light.
+    pub fn project(image: &ImageBuffer<RGB<V>>) -> SphericalHarmonics<V> {
+        let size = image.size();
+        let one = V::one();
+        let pi = V::PI;
+        let to_value = |value: usize| -> V { (value as u16).into() };
+
+        let mut spherical_harmonics = SphericalHarmonics::new();
+        for y in 0..size.y {
+            let v = one - (to_value(y) + one.half()) / to_value(size.y);
+            let latitude = (v - one.half()) * pi;
+            let solid_angle =
+                latitude.cos() * (pi + pi) * pi / (to_value(size.x) * to_value(size.y));
+
+            for x in 0..size.x {
+                let u = (to_value(x) + one.half()) / to_value(size.x);
+                let direction = EnvironmentLight::<V>::direction(Point2::new(u, v));
+                spherical_harmonics.add_sample(
+                    direction,
+                    image.get(Point2::new(x, y)),
+                    solid_angle,
+                );
+            }
+        }
+
+        spherical_harmonics
+    }
+
+    // Adds the radiance arriving from a direction, weighted by the solid angle it stands for.
+    pub fn add_sample(&mut self, direction: Vector3<V>, radiance: RGB<V>, weight: V) {
+        for (coefficient, basis) in self.coefficients.iter_mut().zip(basis(direction)) {
+            *coefficient += radiance * (basis * weight);
+        }
+    }
+
+    pub fn radiance(&self, direction: Vector3<V>) -> RGB<V> {
+        self.evaluate(direction, [V::one(); 3])
+    }
+
+    // The cosine weighted integral of the radiance over the hemisphere around the normal, divided
+    // by pi, like the irradiance of the environment light.
+    pub fn irradiance(&self, normal: Vector3<V>) -> RGB<V> {
+        let c = |value: f32| -> V { value.into() };
+        self.evaluate(normal, [V::one(), c(2.0 / 3.0), c(0.25)])
+    }
+
+    fn evaluate(&self, direction: Vector3<V>, band_factors: [V; 3]) -> RGB<V> {
+        const BANDS: [usize; 9] = [0, 1, 1, 1, 2, 2, 2, 2, 2];
+
+        self.coefficients
+            .iter()
+            .zip(basis(direction))
+            .zip(BANDS)
+            .fold(
+                RGB::new(Zero::zero(), Zero::zero(), Zero::zero()),
+                |sum, ((coefficient, basis), band)| {
+                    sum + *coefficient * (basis * band_factors[band])
+                },
+            )
+    }
+}
+
+impl<V> Default for SphericalHarmonics<V>
+where
+    V: FloatingPoint + ConvenientNumber + From<f32>,
+    u16: Into<V>,
+{
+    fn default() -> Self {
+        SphericalHarmonics::new()
+    }
+}
+
+// The basis functions for a normalized direction, ordered by band and then from m = -l to m = l.
+pub fn basis<V>(direction: Vector3<V>) -> [V; 9]
+where
+    V: FloatingPoint + From<f32>,
+{
+    let c = |value: f32| -> V { value.into() };
+    let Vector3 { x, y, z } = direction;
+
+    [
+        c(0.282095),
+        c(0.488603) * y,
+        c(0.488603) * z,
+        c(0.488603) * x,
+        c(1.092548) * x * y,
+        c(1.092548) * y * z,
+        c(0.315392) * (c(3.0) * z * z - V::one()),
+        c(1.092548) * x * z,
+        c(0.546274) * (x * x - y * y),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::WritableImage;
+    use math::Vector2;
+
+    macro_rules! project_uniform_environment {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let color = RGB::new(0.5 as $type, 1.0, 2.0);
+                let image = ImageBuffer::new(Vector2::new(64, 32), color);
+
+                let spherical_harmonics = SphericalHarmonics::project(&image);
+
+                for direction in [
+                    Vector3::new(0.0 as $type, 1.0, 0.0),
+                    Vector3::new(0.0, -1.0, 0.0),
+                    Vector3::new(1.0, 0.0, 0.0),
+                    Vector3::new(0.6, 0.0, -0.8),
+                ] {
+                    let irradiance = spherical_harmonics.irradiance(direction);
+                    let radiance = spherical_harmonics.radiance(direction);
+                    for (actual, expected) in [
+                        (irradiance.red, color.red),
+                        (irradiance.green, color.green),
+                        (irradiance.blue, color.blue),
+                        (radiance.blue, color.blue),
+                    ] {
+                        assert!((actual - expected).abs() < 0.01);
+                    }
+                }
+            }
+        };
+    }
+
+    project_uniform_environment! { f32, project_uniform_environment_f32 }
+    project_uniform_environment! { f64, project_uniform_environment_f64 }
+
+    macro_rules! irradiance_of_linear_environment {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // The radiance 1 + y only has components in the first two bands. The clamped
+                // cosine scales the linear band by 2/3, so the irradiance is pi * (1 + 2/3 n.y).
+                let size = Vector2::new(64, 32);
+                let mut image = ImageBuffer::new(size, RGB::new(0.0 as $type, 0.0, 0.0));
+                for y in 0..size.y {
+                    let v = 1.0 - (y as $type + 0.5) / size.y as $type;
+                    for x in 0..size.x {
+                        let u = (x as $type + 0.5) / size.x as $type;
+                        let d = EnvironmentLight::<$type>::direction(Point2::new(u, v));
+                        *image.get_mut(Point2::new(x, y)) = RGB::new(1.0 + d.y, 1.0 + d.y, 1.0);
+                    }
+                }
+
+                let spherical_harmonics = SphericalHarmonics::project(&image);
+
+                let up = spherical_harmonics.irradiance(Vector3::new(0.0, 1.0, 0.0));
+                let down = spherical_harmonics.irradiance(Vector3::new(0.0, -1.0, 0.0));
+                let side = spherical_harmonics.irradiance(Vector3::new(0.0, 0.0, 1.0));
+
+                assert!((up.red - 5.0 / 3.0).abs() < 0.01);
+                assert!((down.red - 1.0 / 3.0).abs() < 0.01);
+                assert!((side.red - 1.0).abs() < 0.01);
+                assert!((up.blue - 1.0).abs() < 0.01);
+
+                let radiance = spherical_harmonics.radiance(Vector3::new(0.0, 0.6, 0.8));
+                assert!((radiance.green - 1.6).abs() < 0.01);
+            }
+        };
+    }
+
+    irradiance_of_linear_environment! { f32, irradiance_of_linear_environment_f32 }
+    irradiance_of_linear_environment! { f64, irradiance_of_linear_environment_f64 }
+}
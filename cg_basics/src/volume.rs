@@ -0,0 +1,220 @@
+use colors::Color;
+use math::noise::Noise;
+use math::{Point3, Vector3};
+use traits::{Clamp, ConvenientNumber, FloatingPoint, Half, Max, Min, One, Sqrt, Zero};
+
+type NoiseContainer<V> = Box<dyn Noise<V> + Send + Sync>;
+
+// A participating medium filling an axis aligned box, like fog or smoke. Light is absorbed and
+// scattered along the way through it. The density is the extinction per unit of length and the
+// albedo the share of the extinguished light that is scattered instead of absorbed. A noise
+// modulates the density between zero and its full value, where the noise is sampled with a
+// frequency per unit. Integrators march through the box with steps of a fixed length.
+pub struct VolumeRegion<C: Color> {
+    pub min: Point3<C::ChannelType>,
+    pub max: Point3<C::ChannelType>,
+    pub density: C::ChannelType,
+    pub albedo: C,
+    pub anisotropy: C::ChannelType,
+    pub step: C::ChannelType,
+    pub noise: Option<(NoiseContainer<C::ChannelType>, C::ChannelType)>,
+}
+
+impl<C: Color> VolumeRegion<C>
+where
+    C::ChannelType: FloatingPoint + ConvenientNumber,
+    u16: Into<C::ChannelType>,
+{
+    // The box is given by two opposite corners. The steps default to a sixty-fourth of its
+    // diagonal.
+    pub fn new(
+        a: Point3<C::ChannelType>,
+        b: Point3<C::ChannelType>,
+        density: C::ChannelType,
+        albedo: C,
+    ) -> VolumeRegion<C> {
+        let min = Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+        let max = Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+        VolumeRegion {
+            min,
+            max,
+            density,
+            albedo,
+            anisotropy: Zero::zero(),
+            step: (max - min).magnitude() / 64u16.into(),
+            noise: None,
+        }
+    }
+
+    // The anisotropy of the Henyey-Greenstein phase function, between -1 for light scattered back
+    // and 1 for light scattered forward. Zero scatters evenly into all directions.
+    pub fn with_anisotropy(self, anisotropy: C::ChannelType) -> VolumeRegion<C> {
+        VolumeRegion { anisotropy, ..self }
+    }
+
+    pub fn with_step(self, step: C::ChannelType) -> VolumeRegion<C> {
+        VolumeRegion { step, ..self }
+    }
+
+    pub fn with_noise(
+        self,
+        noise: NoiseContainer<C::ChannelType>,
+        frequency: C::ChannelType,
+    ) -> VolumeRegion<C> {
+        VolumeRegion {
+            noise: Some((noise, frequency)),
+            ..self
+        }
+    }
+
+    // The part of a ray between its origin and a parameter that lies inside the box, given by
+    // the parameters where it enters and leaves.
+    pub fn segment(
+        &self,
+        origin: Point3<C::ChannelType>,
+        direction: Vector3<C::ChannelType>,
+        t_max: C::ChannelType,
+    ) -> Option<(C::ChannelType, C::ChannelType)> {
+        let mut near = C::ChannelType::zero();
+        let mut far = t_max;
+        for (o, d, min, max) in [
+            (origin.x, direction.x, self.min.x, self.max.x),
+            (origin.y, direction.y, self.min.y, self.max.y),
+            (origin.z, direction.z, self.min.z, self.max.z),
+        ] {
+            if d == Zero::zero() {
+                if o < min || o > max {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (min - o) / d;
+            let t2 = (max - o) / d;
+            near = near.max(t1.min(t2));
+            far = far.min(t1.max(t2));
+        }
+        if near < far {
+            Some((near, far))
+        } else {
+            None
+        }
+    }
+
+    // The extinction per unit of length at a point inside the box.
+    pub fn density_at(&self, p: Point3<C::ChannelType>) -> C::ChannelType {
+        match &self.noise {
+            Some((noise, frequency)) => {
+                let one = C::ChannelType::one();
+                let value = noise.value(Point3::new(
+                    p.x * *frequency,
+                    p.y * *frequency,
+                    p.z * *frequency,
+                ));
+                self.density * (value + one).half().clamp(Zero::zero(), one)
+            }
+            None => self.density,
+        }
+    }
+
+    // The share of the light scattered by an angle, given by its cosine, relative to a medium
+    // that scatters evenly into all directions.
+    pub fn phase(&self, cos_theta: C::ChannelType) -> C::ChannelType {
+        let one = C::ChannelType::one();
+        let g = self.anisotropy;
+        let x = one + g * g - (g + g) * cos_theta;
+        (one - g * g) / (x * x.sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::RGB;
+
+    struct Constant;
+
+    impl<V: Copy + Zero> Noise<V> for Constant {
+        fn value_and_gradient(&self, _p: Point3<V>) -> (V, Vector3<V>) {
+            (V::zero(), Vector3::new(V::zero(), V::zero(), V::zero()))
+        }
+    }
+
+    macro_rules! volume_segment {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let volume = VolumeRegion::new(
+                    Point3::<$type>::new(1.0, 1.0, 1.0),
+                    Point3::new(-1.0, -1.0, -1.0),
+                    1.0,
+                    RGB::new(1.0, 1.0, 1.0),
+                );
+
+                let origin = Point3::new(0.0, 0.0, 5.0);
+                let direction = Vector3::new(0.0, 0.0, -2.0);
+                assert_eq!(volume.segment(origin, direction, 10.0), Some((2.0, 3.0)));
+                assert_eq!(volume.segment(origin, direction, 2.5), Some((2.0, 2.5)));
+                assert_eq!(volume.segment(origin, direction, 1.0), None);
+                assert_eq!(volume.segment(origin, -direction, 10.0), None);
+
+                let inside = Point3::new(0.5, 0.0, 0.0);
+                let direction = Vector3::new(1.0, 0.0, 0.0);
+                assert_eq!(volume.segment(inside, direction, 10.0), Some((0.0, 0.5)));
+                let outside = Point3::new(0.0, 2.0, 0.0);
+                assert_eq!(volume.segment(outside, direction, 10.0), None);
+            }
+        };
+    }
+
+    volume_segment! { f32, volume_segment_f32 }
+    volume_segment! { f64, volume_segment_f64 }
+
+    macro_rules! volume_density {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let volume = VolumeRegion::new(
+                    Point3::<$type>::new(0.0, 0.0, 0.0),
+                    Point3::new(1.0, 1.0, 1.0),
+                    0.5,
+                    RGB::new(1.0, 1.0, 1.0),
+                );
+                let p = Point3::new(0.5, 0.5, 0.5);
+
+                assert_eq!(volume.density_at(p), 0.5);
+                // A noise of zero lies halfway between no density and the full one.
+                let volume = volume.with_noise(Box::new(Constant), 2.0);
+                assert_eq!(volume.density_at(p), 0.25);
+            }
+        };
+    }
+
+    volume_density! { f32, volume_density_f32 }
+    volume_density! { f64, volume_density_f64 }
+
+    macro_rules! volume_phase {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let volume = VolumeRegion::new(
+                    Point3::<$type>::new(0.0, 0.0, 0.0),
+                    Point3::new(1.0, 1.0, 1.0),
+                    1.0,
+                    RGB::new(1.0, 1.0, 1.0),
+                );
+
+                assert_eq!(volume.phase(1.0), 1.0);
+                assert_eq!(volume.phase(-1.0), 1.0);
+
+                let volume = volume.with_anisotropy(0.5);
+                assert!(volume.phase(1.0) > 1.0);
+                assert!(volume.phase(-1.0) < 1.0);
+                assert!((volume.phase(1.0) - 6.0).abs() < 1e-5);
+            }
+        };
+    }
+
+    volume_phase! { f32, volume_phase_f32 }
+    volume_phase! { f64, volume_phase_f64 }
+}
@@ -1,12 +1,13 @@
 use std::hash::{Hash, Hasher};
 use std::iter::Sum;
-use std::ops::{Add, Index, Mul};
+use std::ops::{Add, Index, Mul, Sub};
 
 use super::Color;
 
-use traits::Number;
+use traits::{Number, F16};
 
 create_color_type! { Gray, [value] }
+implement_half_float_conversions! { Gray, [value] }
 
 impl<T> Index<usize> for Gray<T> {
     type Output = T;
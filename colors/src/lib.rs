@@ -13,12 +13,25 @@ pub trait Color:
     + Copy
     + PartialEq
     + Index<usize, Output = Self::ChannelType>
+    + Send
+    + Sync
 {
     type ChannelType: Number;
 
     fn clamped(self, min: Self, max: Self) -> Self
     where
         <Self as Color>::ChannelType: PartialOrd;
+
+    // The largest of the channels, e.g. to tell whether a color is too dark to matter.
+    fn max_channel(self) -> Self::ChannelType
+    where
+        <Self as Color>::ChannelType: PartialOrd;
+
+    // A color with the same value in every channel, e.g. white for one.
+    fn uniform(value: Self::ChannelType) -> Self;
+
+    // The color with a function applied to every channel.
+    fn map(self, f: impl Fn(Self::ChannelType) -> Self::ChannelType) -> Self;
 }
 
 #[macro_export]
@@ -61,6 +74,25 @@ macro_rules! create_color_type {
                 )+
                 $name::new( $($channel,)+ )
             }
+
+            fn max_channel(self) -> T where T: PartialOrd {
+                let channels = [ $( self.$channel, )+ ];
+                let mut max = channels[0];
+                for channel in channels {
+                    if channel > max {
+                        max = channel;
+                    }
+                }
+                max
+            }
+
+            fn uniform(value: T) -> $name<T> {
+                $name { $($channel: value,)+ }
+            }
+
+            fn map(self, f: impl Fn(T) -> T) -> $name<T> {
+                $name { $($channel: f(self.$channel),)+ }
+            }
         }
 
         impl<T: Add<U> , U> Add<$name<U>> for $name<T> {
@@ -71,6 +103,13 @@ macro_rules! create_color_type {
             }
         }
 
+        impl<T: Sub<U> , U> Sub<$name<U>> for $name<T> {
+            type Output = $name<<T as Sub<U>>::Output>;
+
+            fn sub(self, rhs: $name<U>) -> Self::Output {
+                $name::new( $( self.$channel - rhs.$channel, )* )
+            }
+        }
 
         impl<T: Mul<Output=T> + Copy> Mul<T> for $name<T> {
             type Output = $name<T>;
@@ -111,6 +150,37 @@ macro_rules! create_color_type {
     }
 }
 
+// Converts colors with 32 or 64 bit float channels to 16 bit float channels, e.g. to store images
+// in less memory, and back.
+#[macro_export]
+macro_rules! implement_half_float_conversions {
+    ($name: ident, [$($channel: ident)+]) => {
+        impl From<$name<f32>> for $name<F16> {
+            fn from(color: $name<f32>) -> $name<F16> {
+                $name::new( $( F16::from_f32(color.$channel), )+ )
+            }
+        }
+
+        impl From<$name<f64>> for $name<F16> {
+            fn from(color: $name<f64>) -> $name<F16> {
+                $name::new( $( F16::from_f32(color.$channel as f32), )+ )
+            }
+        }
+
+        impl From<$name<F16>> for $name<f32> {
+            fn from(color: $name<F16>) -> $name<f32> {
+                $name::new( $( color.$channel.to_f32(), )+ )
+            }
+        }
+
+        impl From<$name<F16>> for $name<f64> {
+            fn from(color: $name<F16>) -> $name<f64> {
+                $name::new( $( color.$channel.to_f32() as f64, )+ )
+            }
+        }
+    }
+}
+
 pub mod gray;
 pub mod rgb;
 pub mod rgba;
@@ -1,14 +1,15 @@
 use std::hash::{Hash, Hasher};
 use std::iter::Sum;
-use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul};
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, Sub};
 
 use super::Color;
 use super::YCbCr;
 use super::RGBA;
 
-use traits::Number;
+use traits::{Number, F16};
 
 create_color_type! { RGB, [red green blue] }
+implement_half_float_conversions! { RGB, [red green blue] }
 
 impl From<YCbCr<u8>> for RGB<u8> {
     fn from(ycbcr: YCbCr<u8>) -> RGB<u8> {
@@ -63,6 +64,23 @@ macro_rules! rgb_from_floating_to_integral {
 rgb_from_floating_to_integral! { f32, [u8, u16, i8, i16] }
 rgb_from_floating_to_integral! { f64, [u8, u16, i8, i16] }
 
+macro_rules! rgb_from_integral_to_floating {
+    ($integral: ty, [$($floating: ty),+]) => {
+        $(
+        impl From<RGB<$integral>> for RGB<$floating> {
+            fn from(rgb: RGB<$integral>) -> RGB<$floating> {
+                RGB::new( rgb.red as $floating / <$integral>::MAX as $floating,
+                          rgb.green as $floating / <$integral>::MAX as $floating,
+                          rgb.blue as $floating / <$integral>::MAX as $floating
+                )
+            }
+        })+
+    }
+}
+
+rgb_from_integral_to_floating! { u8, [f32, f64] }
+rgb_from_integral_to_floating! { u16, [f32, f64] }
+
 // To test
 
 impl<T: AddAssign> AddAssign for RGB<T> {
@@ -189,6 +207,39 @@ mod tests {
     clamped_rgb! { f32, clamped_rgb_f32 }
     clamped_rgb! { f64, clamped_rgb_f64 }
 
+    macro_rules! max_channel_rgb {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                assert_eq!(
+                    RGB::new(3 as $type, 1 as $type, 2 as $type).max_channel(),
+                    3 as $type
+                );
+                assert_eq!(
+                    RGB::new(1 as $type, 3 as $type, 2 as $type).max_channel(),
+                    3 as $type
+                );
+                assert_eq!(
+                    RGB::new(1 as $type, 2 as $type, 3 as $type).max_channel(),
+                    3 as $type
+                );
+            }
+        };
+    }
+
+    max_channel_rgb! { u8, max_channel_rgb_u8 }
+    max_channel_rgb! { u16, max_channel_rgb_u16 }
+    max_channel_rgb! { u32, max_channel_rgb_u32 }
+    max_channel_rgb! { u64, max_channel_rgb_u64 }
+    max_channel_rgb! { u128, max_channel_rgb_u128 }
+    max_channel_rgb! { i8, max_channel_rgb_i8 }
+    max_channel_rgb! { i16, max_channel_rgb_i16 }
+    max_channel_rgb! { i32, max_channel_rgb_i32 }
+    max_channel_rgb! { i64, max_channel_rgb_i64 }
+    max_channel_rgb! { i128, max_channel_rgb_i128 }
+    max_channel_rgb! { f32, max_channel_rgb_f32 }
+    max_channel_rgb! { f64, max_channel_rgb_f64 }
+
     macro_rules! rgb_from_ycbcr {
         ($type: ty, $name: ident) => {
             #[test]
@@ -308,4 +359,43 @@ mod tests {
     integral_from_floating! { f64, u16, rgb_u16_from_rgb_f64 }
     integral_from_floating! { f64, i8, rgb_i8_from_rgb_f64 }
     integral_from_floating! { f64, i16, rgb_i16_from_rgb_f64 }
+
+    macro_rules! floating_from_integral {
+        ($integral:ty, $floating:ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let a = RGB::<$integral>::new(0, <$integral>::MAX / 4, <$integral>::MAX);
+
+                let b = RGB::<$floating>::from(a);
+
+                assert_eq!(b.red, 0.0);
+                assert!((b.green - 0.25).abs() < 0.01);
+                assert_eq!(b.blue, 1.0);
+            }
+        };
+    }
+
+    floating_from_integral! { u8, f32, rgb_f32_from_rgb_u8 }
+    floating_from_integral! { u8, f64, rgb_f64_from_rgb_u8 }
+    floating_from_integral! { u16, f32, rgb_f32_from_rgb_u16 }
+    floating_from_integral! { u16, f64, rgb_f64_from_rgb_u16 }
+
+    macro_rules! half_float_from_floating {
+        ($floating:ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let a = RGB::<$floating>::new(0.5, 1.0 / 3.0, 70000.0);
+
+                let b = RGB::<F16>::from(a);
+
+                assert_eq!(b.red.to_bits(), 0x3800);
+                assert_eq!(b.green.to_bits(), 0x3555);
+                assert_eq!(b.blue.to_bits(), 0x7c00);
+                assert_eq!(RGB::<$floating>::from(b).red, 0.5);
+            }
+        };
+    }
+
+    half_float_from_floating! { f32, rgb_f16_from_rgb_f32 }
+    half_float_from_floating! { f64, rgb_f16_from_rgb_f64 }
 }
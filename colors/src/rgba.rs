@@ -1,12 +1,13 @@
 use std::hash::{Hash, Hasher};
 use std::iter::Sum;
-use std::ops::{Add, Index, Mul};
+use std::ops::{Add, Index, Mul, Sub};
 
 use super::{Color, RGB};
 
-use traits::Number;
+use traits::{Number, F16};
 
 create_color_type! { RGBA, [red green blue alpha] }
+implement_half_float_conversions! { RGBA, [red green blue alpha] }
 
 impl<T> Index<usize> for RGBA<T> {
     type Output = T;
@@ -0,0 +1,241 @@
+use std::ops::Mul;
+use std::sync::Arc;
+
+use crate::camera::RaytracingCamera;
+use crate::integrator::{self, Driver, Integrator};
+use crate::light::Light;
+use crate::metrics::Metrics;
+use crate::whitted_ray_tracer::Tracer;
+use crate::Renderable;
+use cg_basics::light::AmbientOcclusionLight;
+use cg_basics::scene_graph::Scene3;
+use colors::Color;
+use image::ImageBuffer;
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
+use random::{RandomNumberGenerator, WichmannHillPRNG};
+use sampling::{AdaptiveSampling, PatternMapping, SamplingPattern, SamplingPatternSet};
+use traits::{ConvenientNumber, FloatingPoint, One, Sqrt, Zero};
+use units::length::Length;
+
+type SceneType<T, C> =
+    Scene3<C, Box<dyn Light<T, C>>, Box<dyn RaytracingCamera<T>>, Box<dyn Renderable<T, C>>>;
+
+// Renders the ambient occlusion of the scene alone, as for a clay render. Every camera sample
+// casts one ray of an ambient occlusion light from the surface it hits, and the pixel is as
+// bright as the share of these rays that leave within the distance without hitting anything. The
+// materials and lights of the scene are ignored, and samples that miss the scene are white.
+pub struct AmbientOcclusionRenderer<T: Length> {
+    driver: Driver<T>,
+    distance: T,
+}
+
+impl<T: Length> AmbientOcclusionRenderer<T>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    T::AreaType: Sqrt<Output = T>,
+    u16: Into<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+    SamplingPattern<Point2<T::ValueType>>: PatternMapping<T::ValueType>,
+{
+    // Geometries farther from a surface than the distance do not occlude it.
+    pub fn new(
+        sampling_patterns: SamplingPatternSet<Point2<T::ValueType>>,
+        shadow_tolerance: T::ValueType,
+        distance: T,
+    ) -> AmbientOcclusionRenderer<T> {
+        AmbientOcclusionRenderer {
+            driver: Driver::new(sampling_patterns, shadow_tolerance),
+            distance,
+        }
+    }
+
+    pub fn with_threads(self, threads: usize) -> AmbientOcclusionRenderer<T> {
+        AmbientOcclusionRenderer {
+            driver: Driver {
+                threads: threads.max(1),
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    // Draws further patterns for a pixel until its noise is low enough.
+    pub fn with_adaptive_sampling(
+        self,
+        adaptive_sampling: AdaptiveSampling<T::ValueType>,
+    ) -> AmbientOcclusionRenderer<T> {
+        AmbientOcclusionRenderer {
+            driver: Driver {
+                adaptive_sampling: Some(adaptive_sampling),
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    pub fn with_metrics(self, metrics: Arc<Metrics>) -> AmbientOcclusionRenderer<T> {
+        AmbientOcclusionRenderer {
+            driver: Driver {
+                metrics,
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.driver.metrics)
+    }
+
+    pub fn render<C: Color<ChannelType = T::ValueType>>(
+        self,
+        scene: SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> ImageBuffer<C> {
+        self.render_pass(&scene, camera_id, size, seed)
+    }
+
+    // Renders the image without consuming the renderer or the scene, e.g. for one of several
+    // passes with different seeds.
+    pub fn render_pass<C: Color<ChannelType = T::ValueType>>(
+        &self,
+        scene: &SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> ImageBuffer<C> {
+        integrator::render_pass(self, scene, camera_id, size, seed)
+    }
+}
+
+impl<T: Length, C: Color<ChannelType = T::ValueType>> Integrator<T, C>
+    for AmbientOcclusionRenderer<T>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    T::AreaType: Sqrt<Output = T>,
+    u16: Into<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+    SamplingPattern<Point2<T::ValueType>>: PatternMapping<T::ValueType>,
+{
+    fn driver(&self) -> &Driver<T> {
+        &self.driver
+    }
+
+    // White where a ray of an ambient occlusion light leaves the surface the camera ray hits
+    // within the distance, and where the camera ray misses the scene.
+    fn trace_camera_ray(
+        &self,
+        tracer: &Tracer<T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        _camera_background: Option<C>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C {
+        let one = T::ValueType::one();
+        let visible = match tracer.closest_hit(r, Zero::zero()) {
+            Some((sp, _, geometry)) => {
+                let probe = AmbientOcclusionLight::new(C::uniform(one), one, self.distance);
+                let probe_pattern = self.driver.sampling_patterns.draw_pattern(rnd);
+                tracer.illuminates(sp, geometry.as_ref(), &probe, probe_pattern, rnd)
+            }
+            None => true,
+        };
+        if visible {
+            C::uniform(one)
+        } else {
+            C::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use cg_basics::camera::PinholeCamera;
+    use cg_basics::material::LambertMaterial;
+    use cg_basics::scene_graph::RenderableGeometry;
+    use colors::RGB;
+    use image::{Image, SingleColorImage};
+    use math::geometry::ImplicitPlane3;
+    use math::transform::Transform3;
+    use math::{Normal3, Point3, Vector3};
+    use sampling::RegularPatternGenerator;
+    use traits::ToRadians;
+    use units::angle::Degrees;
+    use units::length::Meter;
+
+    macro_rules! ambient_occlusion_renderer {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // The camera looks down on a floor, under a ceiling that is close by if there is
+                // one.
+                let render = |ceiling: bool, distance: $type| {
+                    let plane = |height: $type, normal: $type| {
+                        ImplicitPlane3::new(
+                            Point3::new(Meter::new(0.0), Meter::new(height), Meter::new(0.0)),
+                            Normal3::new(0.0, normal, 0.0),
+                            Vector3::new(1.0, 0.0, 0.0),
+                        )
+                    };
+                    let material = || {
+                        LambertMaterial::new(SingleColorImage::new(
+                            RGB::<$type>::new(0.5, 0.5, 0.5),
+                            Vector2::new(1.0, 1.0),
+                        ))
+                    };
+
+                    let mut geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> =
+                        vec![Box::new(RenderableGeometry::new(
+                            plane(0.0, 1.0),
+                            material(),
+                            Transform3::<$type>::ident(),
+                        ))];
+                    if ceiling {
+                        geometries.push(Box::new(RenderableGeometry::new(
+                            plane(0.1, -1.0),
+                            material(),
+                            Transform3::<$type>::ident(),
+                        )));
+                    }
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(0.05), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                            Degrees::<$type>::new(1.0).to_radians(),
+                        )),
+                    );
+
+                    let scene = Scene3::new(RGB::new(0.0, 0.0, 0.0), vec![], cameras, geometries);
+
+                    let image = AmbientOcclusionRenderer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::regular_pattern(4, 4),
+                        0.0001,
+                        Meter::new(distance),
+                    )
+                    .render(scene, "main", Vector2::new(1, 1), 0);
+                    image.get(Point2::new(0, 0))
+                };
+
+                assert_eq!(render(false, 1.0), RGB::new(1.0, 1.0, 1.0));
+                assert_eq!(render(true, 100.0), RGB::new(0.0, 0.0, 0.0));
+
+                // The ceiling is out of reach.
+                assert_eq!(render(true, 0.05), RGB::new(1.0, 1.0, 1.0));
+            }
+        };
+    }
+
+    ambient_occlusion_renderer! { f32, ambient_occlusion_renderer_f32 }
+    ambient_occlusion_renderer! { f64, ambient_occlusion_renderer_f64 }
+}
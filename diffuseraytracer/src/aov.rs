@@ -0,0 +1,332 @@
+use cg_basics::scene_graph::Scene3;
+use colors::{Color, Gray, RGB};
+use image::{ImageBuffer, WritableImage};
+use math::{Point2, Vector2};
+use random::WichmannHillPRNG;
+use sampling::SamplingPattern;
+use traits::{ConvenientNumber, FloatingPoint, Half, One, Zero};
+use units::length::Length;
+
+use crate::camera::RaytracingCamera;
+use crate::contours::SurfaceSample;
+use crate::diffuse_ray_tracer::render_tiles;
+use crate::light::Light;
+use crate::Renderable;
+
+type SceneType<T, C> =
+    Scene3<C, Box<dyn Light<T, C>>, Box<dyn RaytracingCamera<T>>, Box<dyn Renderable<T, C>>>;
+
+// An auxiliary output that is written next to the image, for compositing or to guide a denoiser.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Aov {
+    Normal,
+    Depth,
+    Albedo,
+    ObjectId,
+    // One mask for each named geometry.
+    Masks,
+}
+
+impl Aov {
+    pub fn from_name(name: &str) -> Option<Aov> {
+        match name {
+            "normal" => Some(Aov::Normal),
+            "depth" => Some(Aov::Depth),
+            "albedo" => Some(Aov::Albedo),
+            "id" => Some(Aov::ObjectId),
+            "masks" => Some(Aov::Masks),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Albedo => "albedo",
+            Aov::ObjectId => "id",
+            Aov::Masks => "masks",
+        }
+    }
+}
+
+// The surfaces seen through the centers of the pixels, from which the auxiliary outputs are
+// taken. There is a single ray per pixel, so edges are not antialiased and the values are never
+// mixed up, e.g. the ids of two geometries. Pixels that see the background are black.
+pub struct Aovs<C: Color> {
+    surfaces: Vec<Option<(SurfaceSample<C::ChannelType>, C)>>,
+    names: Vec<Option<String>>,
+    size: Vector2<usize>,
+}
+
+impl<C: Color> Aovs<C>
+where
+    C::ChannelType: FloatingPoint + ConvenientNumber,
+    u16: Into<C::ChannelType>,
+{
+    pub fn render<T: Length<ValueType = C::ChannelType>>(
+        scene: &SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+        threads: usize,
+    ) -> Aovs<C> {
+        Aovs {
+            surfaces: trace_surfaces(scene, camera_id, size, seed, threads, 16),
+            names: scene
+                .geometries
+                .iter()
+                .map(|geometry| geometry.name().map(String::from))
+                .collect(),
+            size,
+        }
+    }
+
+    // The world space normal, with x, y and z in red, green and blue.
+    pub fn normal(&self) -> ImageBuffer<RGB<C::ChannelType>> {
+        self.image(|(surface, _)| RGB::new(surface.normal.x, surface.normal.y, surface.normal.z))
+    }
+
+    // The distance from the camera.
+    pub fn depth(&self) -> ImageBuffer<Gray<C::ChannelType>> {
+        self.image(|(surface, _)| Gray::new(surface.depth))
+    }
+
+    pub fn albedo(&self) -> ImageBuffer<C> {
+        self.image(|(_, albedo)| *albedo)
+    }
+
+    // Every geometry in a color of its own.
+    pub fn object_id(&self) -> ImageBuffer<RGB<C::ChannelType>> {
+        self.image(|(surface, _)| id_color(surface.geometry))
+    }
+
+    // White where the geometries of a name are seen.
+    pub fn mask(&self, name: &str) -> ImageBuffer<Gray<C::ChannelType>> {
+        self.image(|(surface, _)| {
+            if self.names[surface.geometry].as_deref() == Some(name) {
+                Gray::new(One::one())
+            } else {
+                Gray::new(Zero::zero())
+            }
+        })
+    }
+
+    // The surface seen through a pixel, if any.
+    pub fn surface(&self, p: Point2<usize>) -> Option<&SurfaceSample<C::ChannelType>> {
+        self.surfaces[p.y * self.size.x + p.x]
+            .as_ref()
+            .map(|(surface, _)| surface)
+    }
+
+    // The names of the geometries, each once.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .names
+            .iter()
+            .flatten()
+            .map(|name| name.as_str())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn image<D: Color>(
+        &self,
+        value: impl Fn(&(SurfaceSample<C::ChannelType>, C)) -> D,
+    ) -> ImageBuffer<D> {
+        let mut image = ImageBuffer::new(self.size, D::default());
+        for y in 0..self.size.y {
+            for x in 0..self.size.x {
+                if let Some(surface) = &self.surfaces[y * self.size.x + x] {
+                    *image.get_mut(Point2::new(x, y)) = value(surface);
+                }
+            }
+        }
+        image
+    }
+}
+
+// Neighboring indices get very different colors from Knuth's multiplicative hash.
+fn id_color<V>(index: usize) -> RGB<V>
+where
+    V: FloatingPoint,
+    u16: Into<V>,
+{
+    let h = (index as u32).wrapping_add(1).wrapping_mul(2654435761);
+    let channel = |shift: u32| Into::<V>::into(((h >> shift) & 0xff) as u16) / 255u16.into();
+    RGB::new(channel(24), channel(16), channel(8))
+}
+
+// The surface and its albedo for every pixel, traced like the image is in tiles.
+pub(crate) fn trace_surfaces<T: Length, C>(
+    scene: &SceneType<T, C>,
+    camera_id: &str,
+    size: Vector2<usize>,
+    seed: u128,
+    threads: usize,
+    tile_size: usize,
+) -> Vec<Option<(SurfaceSample<T::ValueType>, C)>>
+where
+    C: Color<ChannelType = T::ValueType>,
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    u16: Into<T::ValueType>,
+{
+    let camera = scene.cameras[camera_id].as_ref();
+    let mut surfaces = vec![None; size.x * size.y];
+    for (index, surface) in render_tiles(threads, tile_size, size, None, |origin, extent| {
+        let mut traced = Vec::with_capacity(extent.x * extent.y);
+        for y in origin.y..(origin.y + extent.y) {
+            for x in origin.x..(origin.x + extent.x) {
+                let index = y * size.x + x;
+                let mut rnd = WichmannHillPRNG::for_index(seed, index as u128);
+                traced.push((
+                    index,
+                    trace_surface(scene, camera, Point2::new(x, y), size, &mut rnd),
+                ));
+            }
+        }
+        traced
+    })
+    .into_iter()
+    .flatten()
+    {
+        surfaces[index] = surface;
+    }
+    surfaces
+}
+
+// Finds the surface seen through the center of a pixel. The ray starts in the center of the
+// lens and at the middle of the shutter interval, so the surfaces are not blurred.
+fn trace_surface<T: Length, C>(
+    scene: &SceneType<T, C>,
+    camera: &dyn RaytracingCamera<T>,
+    p: Point2<usize>,
+    size: Vector2<usize>,
+    rnd: &mut WichmannHillPRNG,
+) -> Option<(SurfaceSample<T::ValueType>, C)>
+where
+    C: Color<ChannelType = T::ValueType>,
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    u16: Into<T::ValueType>,
+{
+    let float_size = Vector2::<T::ValueType>::new((size.x as u16).into(), (size.y as u16).into());
+    let half = T::ValueType::one().half();
+    let center = Point2::<T::ValueType>::new(
+        Into::<T::ValueType>::into(p.x as u16) + half,
+        Into::<T::ValueType>::into((size.y - p.y - 1) as u16) + half,
+    );
+
+    let lens_center = SamplingPattern::new(vec![Point2::new(half, half)]);
+    let r = camera.ray_for(float_size, center, &lens_center, rnd)?;
+    let time = camera.shutter().time(half);
+    let length = (r.direction / T::one()).magnitude();
+
+    scene
+        .geometries
+        .iter()
+        .enumerate()
+        .flat_map(|(index, g)| {
+            let epsilon = g.epsilon().unwrap_or(Zero::zero());
+            g.intersect_at(r, time)
+                .into_iter()
+                .filter(move |(t, _, _)| *t > epsilon)
+                .map(move |(t, sp, material)| (t, sp, material, index))
+        })
+        .min_by(|(t1, _, _, _), (t2, _, _, _)| t1.partial_cmp(t2).unwrap())
+        .map(|(t, sp, material, geometry)| {
+            (
+                SurfaceSample {
+                    depth: t * length,
+                    normal: sp.n.as_vector().normalized(),
+                    geometry,
+                },
+                material.albedo(sp),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use cg_basics::camera::PinholeCamera;
+    use cg_basics::material::LambertMaterial;
+    use cg_basics::scene_graph::RenderableGeometry;
+    use image::{Image, SingleColorImage};
+    use math::geometry::ImplicitNSphere;
+    use math::transform::Transform3;
+    use math::{Point3, Vector3};
+    use traits::ToRadians;
+    use units::angle::Degrees;
+    use units::length::Meter;
+
+    macro_rules! aovs_of_sphere {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A sphere in front of the camera, which fills the center pixel but not the
+                // corners.
+                let sphere = ImplicitNSphere::new(
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    Meter::new(1.0),
+                );
+                let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> =
+                    vec![Box::new(
+                        RenderableGeometry::new(
+                            sphere,
+                            LambertMaterial::new(SingleColorImage::new(
+                                RGB::<$type>::new(0.25, 0.5, 0.75),
+                                Vector2::new(1.0, 1.0),
+                            )),
+                            Transform3::<$type>::ident().translate(0.0, 0.0, -5.0),
+                        )
+                        .with_name("ball"),
+                    )];
+
+                let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                    HashMap::new();
+                cameras.insert(
+                    String::from("main"),
+                    Box::new(PinholeCamera::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                        Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                        Degrees::<$type>::new(45.0).to_radians(),
+                    )),
+                );
+
+                let scene: SceneType<Meter<$type>, RGB<$type>> =
+                    Scene3::new(RGB::default(), vec![], cameras, geometries);
+                let aovs = Aovs::render(&scene, "main", Vector2::new(3, 3), 0, 1);
+
+                let center = Point2::new(1, 1);
+                let corner = Point2::new(0, 0);
+
+                let depth = aovs.depth();
+                assert!((depth.get(center).value - 4.0).abs() < 0.0001);
+                assert_eq!(depth.get(corner).value, 0.0);
+
+                let normal = aovs.normal().get(center);
+                assert!((normal.blue - 1.0).abs() < 0.0001, "{:?}", normal);
+
+                assert_eq!(aovs.albedo().get(center), RGB::new(0.25, 0.5, 0.75));
+                assert_eq!(aovs.albedo().get(corner), RGB::default());
+
+                assert_eq!(aovs.object_id().get(center), id_color(0));
+                assert_ne!(id_color::<$type>(0), id_color(1));
+
+                assert_eq!(aovs.names(), vec!["ball"]);
+                assert_eq!(aovs.mask("ball").get(center).value, 1.0);
+                assert_eq!(aovs.mask("ball").get(corner).value, 0.0);
+                assert_eq!(aovs.mask("other").get(center).value, 0.0);
+            }
+        };
+    }
+
+    aovs_of_sphere! { f32, aovs_of_sphere_f32 }
+    aovs_of_sphere! { f64, aovs_of_sphere_f64 }
+}
@@ -0,0 +1,854 @@
+use std::sync::Arc;
+
+use crate::camera::RaytracingCamera;
+use crate::integrator::{self, Driver, Integrator};
+use crate::light::{area_density, Light};
+use crate::material::Material;
+use crate::metrics::Metrics;
+use crate::whitted_ray_tracer::Tracer;
+use crate::Renderable;
+use cg_basics::scene_graph::Scene3;
+use colors::Color;
+use image::ImageBuffer;
+use math::geometry::{ParametricLine, SurfacePoint};
+use math::{Point2, Point3, Vector2, Vector3};
+use random::{RandomNumberGenerator, WichmannHillPRNG};
+use sampling::{AdaptiveSampling, SamplingPatternSet};
+use traits::{Abs, ConvenientNumber, FloatingPoint, One, Sqrt, Zero};
+use units::length::Length;
+
+type SceneType<T, C> =
+    Scene3<C, Box<dyn Light<T, C>>, Box<dyn RaytracingCamera<T>>, Box<dyn Renderable<T, C>>>;
+
+// A bidirectional path tracer. Besides the path from the camera, every sample traces a path from
+// one of the lights, and each vertex of the camera path is connected to each vertex of the light
+// path with a shadow ray. Light that reaches the camera through a small opening is hard to find
+// for the camera paths alone, while the light paths pass the opening on their own. The many ways
+// to find the same path are weighted by the power heuristic, so each of them counts where it is
+// most likely to find the path. The light samples of the path tracer are one of these ways and
+// everything else works the same, so both converge to the same image. Light paths start at lights
+// with a position, begin with the light reflected by the first surface they hit, and end at
+// mirrors and clear surfaces, whose light is found by the camera paths.
+pub struct BidirectionalPathTracer<T: Length> {
+    driver: Driver<T>,
+    max_depth: usize,
+    roulette_depth: usize,
+    packets: bool,
+}
+
+// A point where a path from the camera or from a light hit a surface.
+struct Vertex<'a, T: Length, C> {
+    sp: SurfacePoint<T>,
+    material: &'a dyn Material<T, ColorType = C>,
+    // The direction the path arrived in.
+    d: Vector3<T>,
+    // On the camera path the share of the light leaving the vertex that reaches the camera, on the
+    // light path the light arriving at the vertex, divided by the densities of drawing the path.
+    weight: C,
+    // The density per area of drawing the vertex from the end its path started at, and from the
+    // other end. Both are zero behind mirrors and clear surfaces.
+    density: T::ValueType,
+    reverse_density: T::ValueType,
+    // The path continued into a single direction from the vertex.
+    specular: bool,
+}
+
+struct LightPath<'a, T: Length, C> {
+    // The index of the light the path started at.
+    light: usize,
+    vertices: Vec<Vertex<'a, T, C>>,
+}
+
+impl<T: Length> BidirectionalPathTracer<T>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    u16: Into<T::ValueType>,
+{
+    pub fn new(
+        sampling_patterns: SamplingPatternSet<Point2<T::ValueType>>,
+        shadow_tolerance: T::ValueType,
+    ) -> BidirectionalPathTracer<T> {
+        BidirectionalPathTracer {
+            driver: Driver::new(sampling_patterns, shadow_tolerance),
+            max_depth: 8,
+            roulette_depth: 3,
+            packets: false,
+        }
+    }
+
+    pub fn with_threads(self, threads: usize) -> BidirectionalPathTracer<T> {
+        BidirectionalPathTracer {
+            driver: Driver {
+                threads: threads.max(1),
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    // The number of bounces after the camera ray. Zero renders the direct lighting only.
+    pub fn with_max_depth(self, max_depth: usize) -> BidirectionalPathTracer<T> {
+        BidirectionalPathTracer { max_depth, ..self }
+    }
+
+    // The number of bounces every path survives before Russian roulette may end it.
+    pub fn with_roulette_depth(self, roulette_depth: usize) -> BidirectionalPathTracer<T> {
+        BidirectionalPathTracer {
+            roulette_depth,
+            ..self
+        }
+    }
+
+    // Draws further patterns for a pixel until its noise is low enough.
+    pub fn with_adaptive_sampling(
+        self,
+        adaptive_sampling: AdaptiveSampling<T::ValueType>,
+    ) -> BidirectionalPathTracer<T> {
+        BidirectionalPathTracer {
+            driver: Driver {
+                adaptive_sampling: Some(adaptive_sampling),
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    // Tests the connections of a camera vertex to the light path with packets of shadow rays.
+    pub fn with_packets(self) -> BidirectionalPathTracer<T> {
+        BidirectionalPathTracer {
+            packets: true,
+            ..self
+        }
+    }
+
+    pub fn with_metrics(self, metrics: Arc<Metrics>) -> BidirectionalPathTracer<T> {
+        BidirectionalPathTracer {
+            driver: Driver {
+                metrics,
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.driver.metrics)
+    }
+
+    pub fn render<C: Color<ChannelType = T::ValueType>>(
+        self,
+        scene: SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> ImageBuffer<C>
+    where
+        T::AreaType: Sqrt<Output = T>,
+        WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+    {
+        self.render_pass(&scene, camera_id, size, seed)
+    }
+
+    // Renders the image without consuming the renderer or the scene, e.g. for one of several
+    // passes with different seeds.
+    pub fn render_pass<C: Color<ChannelType = T::ValueType>>(
+        &self,
+        scene: &SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> ImageBuffer<C>
+    where
+        T::AreaType: Sqrt<Output = T>,
+        WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+    {
+        integrator::render_pass(self, scene, camera_id, size, seed)
+    }
+
+    // The light arriving along a camera ray, gathered by a camera path and its connections to a
+    // light path. The procedural background of the scene is only seen by the camera ray.
+    fn trace_paths<C: Color<ChannelType = T::ValueType>>(
+        &self,
+        tracer: &Tracer<T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        camera_background: Option<C>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C
+    where
+        T::AreaType: Sqrt<Output = T>,
+    {
+        let zero = T::ValueType::zero();
+        let one = T::ValueType::one();
+        let max_survival = one - one / 20u16.into();
+
+        let light_count = self.light_count(tracer);
+        let light_path = self.trace_light_path(tracer, rnd);
+
+        let mut color = C::default();
+        let mut throughput = C::uniform(one);
+        let mut r = r;
+        let mut camera_path: Vec<Vertex<T, C>> = Vec::with_capacity(self.max_depth + 1);
+        // The camera draws the first vertex for every way to find a path, so its density cancels.
+        let mut direction_density = one;
+        // The camera sees light sources directly, like the mirrors and clear surfaces do.
+        let mut specular = true;
+
+        for depth in 0..=self.max_depth {
+            let tolerance = if depth == 0 {
+                Zero::zero()
+            } else {
+                self.driver.shadow_tolerance
+            };
+            let Some((sp, material, geometry)) = tracer.closest_hit(r, tolerance) else {
+                match (depth, camera_background) {
+                    (0, Some(background)) => color = background,
+                    _ if specular => color = color + throughput * tracer.background(r),
+                    _ => {}
+                }
+                break;
+            };
+
+            if let Some(emission) = material.emission() {
+                if specular {
+                    color = color + throughput * emission;
+                }
+                break;
+            }
+
+            camera_path.push(Vertex {
+                sp,
+                material,
+                d: r.direction,
+                weight: throughput,
+                density: match depth {
+                    0 => one,
+                    _ => area_density(direction_density, r.origin, sp),
+                },
+                reverse_density: zero,
+                specular: false,
+            });
+
+            for light in tracer
+                .scene
+                .lights
+                .iter()
+                .filter(|light| !light.is_indirect())
+                .filter(|light| geometry.illuminated_by(light.name()))
+            {
+                let light_pattern = self.driver.sampling_patterns.draw_pattern(rnd);
+                if !tracer.illuminates(sp, geometry.as_ref(), light.as_ref(), light_pattern, rnd) {
+                    continue;
+                }
+                let (diffuse, glossy) =
+                    material.diffuse_and_specular_for(sp, r.direction, vec![light.as_ref()]);
+                color = color
+                    + throughput
+                        * (diffuse + glossy)
+                        * light_sample_weight(light.as_ref(), light_count, &camera_path);
+            }
+
+            if let Some(light_path) = &light_path {
+                let (targets, colors): (Vec<_>, Vec<_>) = (1..=light_path
+                    .vertices
+                    .len()
+                    .min(self.max_depth + 1 - camera_path.len()))
+                    .filter_map(|length| connect(tracer, &camera_path, light_path, length))
+                    .unzip();
+                let visible = if self.packets {
+                    tracer.connections(sp.p, &targets)
+                } else {
+                    targets
+                        .iter()
+                        .map(|to| tracer.connects(sp.p, *to))
+                        .collect()
+                };
+                for (connection, visible) in colors.into_iter().zip(visible) {
+                    if visible {
+                        color = color + connection;
+                    }
+                }
+            }
+
+            if depth == self.max_depth {
+                break;
+            }
+
+            let sample = *self
+                .driver
+                .sampling_patterns
+                .draw_pattern(rnd)
+                .draw_point(rnd);
+            let Some(scattering) = material.scatter(sp, r.direction, sample) else {
+                break;
+            };
+            throughput = throughput * scattering.weight;
+            specular = scattering.specular;
+
+            let last = camera_path.len() - 1;
+            camera_path[last].specular = scattering.specular;
+            direction_density = match scattering.specular {
+                true => zero,
+                false => density_of(material, sp, r.direction, scattering.direction),
+            };
+            if last > 0 && !scattering.specular {
+                let previous = camera_path[last - 1].sp;
+                camera_path[last - 1].reverse_density = area_density(
+                    density_of(
+                        material,
+                        sp,
+                        -scattering.direction * T::one(),
+                        (previous.p - sp.p).normalized(),
+                    ),
+                    sp.p,
+                    previous,
+                );
+            }
+
+            // Paths that carry little light are likely to end, the survivors carry their share.
+            if depth + 1 >= self.roulette_depth {
+                let survival = if throughput.max_channel() < max_survival {
+                    throughput.max_channel()
+                } else {
+                    max_survival
+                };
+                if self
+                    .driver
+                    .sampling_patterns
+                    .draw_pattern(rnd)
+                    .draw_point(rnd)
+                    .x
+                    >= survival
+                {
+                    break;
+                }
+                throughput = throughput * (one / survival);
+            }
+
+            r = ParametricLine::new(sp.p, scattering.direction * T::one());
+        }
+
+        color
+    }
+
+    // The lights a light path may start at, each drawn with the same probability.
+    fn light_count<C: Color>(&self, tracer: &Tracer<T, C>) -> usize {
+        tracer
+            .scene
+            .lights
+            .iter()
+            .filter(|light| !light.is_indirect())
+            .count()
+    }
+
+    // A path from a randomly drawn light, with the light it started at. The first vertex is lit
+    // by the light like by a light sample of the camera path, so its weight is only divided by the
+    // density of finding the vertex.
+    fn trace_light_path<'a, C: Color<ChannelType = T::ValueType>>(
+        &self,
+        tracer: &Tracer<'a, T, C>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> Option<LightPath<'a, T, C>>
+    where
+        T::AreaType: Sqrt<Output = T>,
+    {
+        let zero = T::ValueType::zero();
+        let one = T::ValueType::one();
+
+        let light_count = self.light_count(tracer);
+        if light_count == 0 || self.max_depth == 0 {
+            return None;
+        }
+        let index: usize = rnd.next_random();
+        let (light_index, light) = tracer
+            .scene
+            .lights
+            .iter()
+            .enumerate()
+            .filter(|(_, light)| !light.is_indirect())
+            .nth(index % light_count)?;
+        let selection = one / (light_count as u16).into();
+
+        let sample = *self
+            .driver
+            .sampling_patterns
+            .draw_pattern(rnd)
+            .draw_point(rnd);
+        let light_pattern = self.driver.sampling_patterns.draw_pattern(rnd);
+        let (r, direction_density) = light.emit(sample, light_pattern, rnd)?;
+        let (sp, material, geometry) = tracer.closest_hit(r, self.driver.shadow_tolerance)?;
+        if material.emission().is_some()
+            || !geometry.illuminated_by(light.name())
+            || light.direction_from(sp).dot(sp.n.as_vector()) <= zero
+        {
+            return None;
+        }
+
+        // Like for a light sample, the light arrives from the position it is shaded from.
+        let mut vertices = Vec::with_capacity(self.max_depth);
+        vertices.push(Vertex {
+            sp,
+            material,
+            d: -light.direction_from(sp) * T::one(),
+            weight: C::uniform(one / (selection * area_density(direction_density, r.origin, sp))),
+            density: selection * light.emission_density(sp).unwrap_or(zero),
+            reverse_density: zero,
+            specular: false,
+        });
+
+        while vertices.len() < self.max_depth {
+            let last = vertices.len() - 1;
+            let vertex = &vertices[last];
+            let sample = *self
+                .driver
+                .sampling_patterns
+                .draw_pattern(rnd)
+                .draw_point(rnd);
+            let scattering = match vertex.material.scatter(vertex.sp, vertex.d, sample) {
+                Some(scattering) if !scattering.specular => scattering,
+                _ => {
+                    vertices[last].specular = true;
+                    break;
+                }
+            };
+            let direction_density =
+                density_of(vertex.material, vertex.sp, vertex.d, scattering.direction);
+            if direction_density <= zero {
+                break;
+            }
+
+            let weight = if last == 0 {
+                let (diffuse, glossy) = vertex.material.diffuse_and_specular_for(
+                    vertex.sp,
+                    -scattering.direction * T::one(),
+                    vec![light.as_ref()],
+                );
+                vertex.weight
+                    * (diffuse + glossy)
+                    * (scattering
+                        .direction
+                        .dot(vertex.sp.n.as_vector().normalized())
+                        .abs()
+                        / direction_density)
+            } else {
+                vertex.weight * scattering.weight
+            };
+
+            let r = ParametricLine::new(vertex.sp.p, scattering.direction * T::one());
+            let Some((sp, material, _)) = tracer.closest_hit(r, self.driver.shadow_tolerance)
+            else {
+                break;
+            };
+            if material.emission().is_some() {
+                break;
+            }
+
+            if last > 0 {
+                let previous = vertices[last - 1].sp;
+                vertices[last - 1].reverse_density = area_density(
+                    density_of(
+                        vertex.material,
+                        vertex.sp,
+                        -scattering.direction * T::one(),
+                        (previous.p - vertex.sp.p).normalized(),
+                    ),
+                    vertex.sp.p,
+                    previous,
+                );
+            }
+            let density = area_density(direction_density, r.origin, sp);
+            vertices.push(Vertex {
+                sp,
+                material,
+                d: r.direction,
+                weight,
+                density,
+                reverse_density: zero,
+                specular: false,
+            });
+        }
+
+        Some(LightPath {
+            light: light_index,
+            vertices,
+        })
+    }
+}
+
+impl<T: Length, C: Color<ChannelType = T::ValueType>> Integrator<T, C>
+    for BidirectionalPathTracer<T>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    T::AreaType: Sqrt<Output = T>,
+    u16: Into<T::ValueType>,
+{
+    fn driver(&self) -> &Driver<T> {
+        &self.driver
+    }
+
+    fn trace_camera_ray(
+        &self,
+        tracer: &Tracer<T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        camera_background: Option<C>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C {
+        self.trace_paths(tracer, r, camera_background, rnd)
+    }
+}
+
+// The light of a vertex of a light path that reaches the camera through the last vertex of a
+// camera path, weighted against the other ways to find the same path, together with the vertex of
+// the light path. It only counts if nothing lies between the two vertices. None if the connection
+// carries no light.
+fn connect<T: Length, C: Color<ChannelType = T::ValueType>>(
+    tracer: &Tracer<T, C>,
+    camera_path: &[Vertex<T, C>],
+    light_path: &LightPath<T, C>,
+    length: usize,
+) -> Option<(Point3<T>, C)>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    T::AreaType: Sqrt<Output = T>,
+{
+    let zero = T::ValueType::zero();
+    let one = T::ValueType::one();
+
+    let light = &tracer.scene.lights[light_path.light];
+    let light_path = &light_path.vertices[..length];
+    let camera_vertex = &camera_path[camera_path.len() - 1];
+    let light_vertex = &light_path[light_path.len() - 1];
+    let distance = (light_vertex.sp.p - camera_vertex.sp.p).magnitude() / T::one();
+    let direction = (light_vertex.sp.p - camera_vertex.sp.p).normalized();
+
+    let (camera_share, camera_density) =
+        camera_vertex
+            .material
+            .scattering_for(camera_vertex.sp, camera_vertex.d, direction)?;
+    // The first vertex of the light path reflects the light like for a light sample.
+    let (light_share, light_density) = if light_path.len() == 1 {
+        let (diffuse, glossy) = light_vertex.material.diffuse_and_specular_for(
+            light_vertex.sp,
+            direction * T::one(),
+            vec![light.as_ref()],
+        );
+        (
+            (diffuse + glossy)
+                * direction
+                    .dot(light_vertex.sp.n.as_vector().normalized())
+                    .abs(),
+            density_of(
+                light_vertex.material,
+                light_vertex.sp,
+                light_vertex.d,
+                -direction,
+            ),
+        )
+    } else {
+        light_vertex
+            .material
+            .scattering_for(light_vertex.sp, light_vertex.d, -direction)?
+    };
+
+    let color = camera_vertex.weight
+        * camera_share
+        * light_share
+        * light_vertex.weight
+        * (one / (distance * distance));
+    if color.max_channel() <= zero {
+        return None;
+    }
+
+    // The densities of the vertices next to the connection depend on it.
+    let mut densities = densities_of(camera_path);
+    let eye_vertices = camera_path.len();
+    densities[eye_vertices - 1].1 =
+        area_density(light_density, light_vertex.sp.p, camera_vertex.sp);
+    if eye_vertices > 1 {
+        let previous = camera_path[eye_vertices - 2].sp;
+        densities[eye_vertices - 2].1 = area_density(
+            density_of(
+                camera_vertex.material,
+                camera_vertex.sp,
+                -direction * T::one(),
+                (previous.p - camera_vertex.sp.p).normalized(),
+            ),
+            camera_vertex.sp.p,
+            previous,
+        );
+    }
+    densities.extend(
+        light_path
+            .iter()
+            .rev()
+            .map(|vertex| (vertex.reverse_density, vertex.density, false)),
+    );
+    densities[eye_vertices].0 = area_density(camera_density, camera_vertex.sp.p, light_vertex.sp);
+    if light_path.len() > 1 {
+        let previous = light_path[light_path.len() - 2].sp;
+        densities[eye_vertices + 1].0 = area_density(
+            density_of(
+                light_vertex.material,
+                light_vertex.sp,
+                direction * T::one(),
+                (previous.p - light_vertex.sp.p).normalized(),
+            ),
+            light_vertex.sp.p,
+            previous,
+        );
+    }
+
+    Some((
+        light_vertex.sp.p,
+        color * power_heuristic(&densities, eye_vertices),
+    ))
+}
+
+// The weight of a light sample at the last vertex of a camera path, against finding the light
+// with a light path.
+fn light_sample_weight<T: Length, C: Color>(
+    light: &dyn Light<T, C>,
+    light_count: usize,
+    camera_path: &[Vertex<T, C>],
+) -> T::ValueType
+where
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    T::AreaType: Sqrt<Output = T>,
+    u16: Into<T::ValueType>,
+{
+    let zero = T::ValueType::zero();
+    let one = T::ValueType::one();
+
+    let mut densities = densities_of(camera_path);
+    let eye_vertices = camera_path.len();
+    let vertex = &camera_path[eye_vertices - 1];
+    densities[eye_vertices - 1].1 =
+        one / (light_count as u16).into() * light.emission_density(vertex.sp).unwrap_or(zero);
+    if eye_vertices > 1 {
+        let previous = camera_path[eye_vertices - 2].sp;
+        densities[eye_vertices - 2].1 = area_density(
+            density_of(
+                vertex.material,
+                vertex.sp,
+                -light.direction_from(vertex.sp) * T::one(),
+                (previous.p - vertex.sp.p).normalized(),
+            ),
+            vertex.sp.p,
+            previous,
+        );
+    }
+
+    power_heuristic(&densities, eye_vertices)
+}
+
+// The densities of drawing the vertices of a camera path from the camera and from the light, and
+// whether the path continued into a single direction.
+fn densities_of<T: Length, C>(
+    camera_path: &[Vertex<T, C>],
+) -> Vec<(T::ValueType, T::ValueType, bool)> {
+    camera_path
+        .iter()
+        .map(|vertex| (vertex.density, vertex.reverse_density, vertex.specular))
+        .collect()
+}
+
+fn density_of<T: Length, C: Color>(
+    material: &dyn Material<T, ColorType = C>,
+    sp: SurfacePoint<T>,
+    d: Vector3<T>,
+    direction: Vector3<T::ValueType>,
+) -> T::ValueType
+where
+    T::ValueType: Zero,
+{
+    material
+        .scattering_for(sp, d, direction)
+        .map_or(Zero::zero(), |(_, density)| density)
+}
+
+// The power heuristic for a path split after a number of vertices found from the camera, the rest
+// found from the light. The vertices hold the densities of drawing them from the camera and from
+// the light, and whether the path continued into a single direction there. The path could have
+// been split after any other vertex, except next to such a direction, and the densities of the
+// vertices between both splits tell how likely that was in comparison.
+fn power_heuristic<V: FloatingPoint>(vertices: &[(V, V, bool)], eye_vertices: usize) -> V {
+    let zero = V::zero();
+    let one = V::one();
+
+    let mut sum = one;
+    let mut ratio = one;
+    for split in (1..eye_vertices).rev() {
+        let (camera_density, light_density, _) = vertices[split];
+        if vertices[split - 1].2 || camera_density <= zero {
+            break;
+        }
+        ratio = ratio * light_density / camera_density;
+        sum += ratio * ratio;
+    }
+
+    let mut ratio = one;
+    for &(camera_density, light_density, _) in &vertices[eye_vertices..] {
+        if light_density <= zero {
+            return zero;
+        }
+        ratio = ratio * camera_density / light_density;
+        sum += ratio * ratio;
+    }
+
+    one / sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use crate::path_tracer::PathTracer;
+    use cg_basics::camera::PinholeCamera;
+    use cg_basics::light::PointLight;
+    use cg_basics::material::LambertMaterial;
+    use cg_basics::scene_graph::{LightLinks, RenderableGeometry};
+    use colors::RGB;
+    use image::{Image, SingleColorImage};
+    use math::geometry::ImplicitPlane3;
+    use math::transform::Transform3;
+    use math::Normal3;
+    use sampling::JitteredPatternGenerator;
+    use traits::ToRadians;
+    use units::angle::Degrees;
+    use units::length::Meter;
+
+    macro_rules! bidirectional_path_tracer_indirect_light {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A gray floor under a gray ceiling with a lamp in between. The floor does not
+                // see the lamp and only receives the light the ceiling reflects, which the light
+                // paths find as well as the camera paths.
+                let scene = || {
+                    let floor = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let ceiling = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(2.0), Meter::new(0.0)),
+                        Normal3::new(0.0, -1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let gray = || {
+                        LambertMaterial::new(SingleColorImage::new(
+                            RGB::<$type>::new(0.5, 0.5, 0.5),
+                            Vector2::new(1.0, 1.0),
+                        ))
+                    };
+
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(
+                            RenderableGeometry::new(floor, gray(), Transform3::<$type>::ident())
+                                .with_light_links(LightLinks::Exclude(vec![String::from("lamp")])),
+                        ),
+                        Box::new(RenderableGeometry::new(
+                            ceiling,
+                            gray(),
+                            Transform3::<$type>::ident(),
+                        )),
+                    ];
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> = vec![Box::new(
+                        PointLight::new(
+                            RGB::new(1.0, 1.0, 1.0),
+                            Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                        )
+                        .with_name(String::from("lamp")),
+                    )];
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                            Degrees::<$type>::new(1.0).to_radians(),
+                        )),
+                    );
+
+                    Scene3::new(RGB::new(0.0, 0.0, 0.0), lights, cameras, geometries)
+                };
+                let patterns = || {
+                    SamplingPatternSet::<Point2<$type>>::jittered_patterns(
+                        4,
+                        32,
+                        32,
+                        &mut WichmannHillPRNG::from_seed(7),
+                    )
+                };
+                let render = |max_depth: usize| {
+                    let image = BidirectionalPathTracer::<Meter<$type>>::new(patterns(), 0.0001)
+                        .with_max_depth(max_depth)
+                        .with_roulette_depth(10)
+                        .render(scene(), "main", Vector2::new(1, 1), 0);
+                    image.get(Point2::new(0, 0)).red
+                };
+
+                assert_eq!(render(0), 0.0);
+
+                // The light reflected by the ceiling, integrated over the hemisphere of the
+                // floor.
+                let rendered = render(1);
+                assert!((rendered - 0.1182).abs() < 0.005, "{}", rendered);
+
+                // Longer paths converge to the image of the path tracer.
+                let reference = PathTracer::<Meter<$type>>::new(patterns(), 0.0001)
+                    .with_max_depth(4)
+                    .with_roulette_depth(10)
+                    .render(scene(), "main", Vector2::new(1, 1), 0)
+                    .get(Point2::new(0, 0))
+                    .red;
+                let rendered = render(4);
+                assert!(
+                    (rendered - reference).abs() < 0.01,
+                    "{} {}",
+                    rendered,
+                    reference
+                );
+
+                // Packets of shadow rays find the same connections.
+                let packed = BidirectionalPathTracer::<Meter<$type>>::new(patterns(), 0.0001)
+                    .with_max_depth(4)
+                    .with_roulette_depth(10)
+                    .with_packets()
+                    .render(scene(), "main", Vector2::new(1, 1), 0)
+                    .get(Point2::new(0, 0))
+                    .red;
+                assert!(
+                    (packed - rendered).abs() < 0.0001,
+                    "{} {}",
+                    packed,
+                    rendered
+                );
+            }
+        };
+    }
+
+    bidirectional_path_tracer_indirect_light! { f32, bidirectional_path_tracer_indirect_light_f32 }
+    bidirectional_path_tracer_indirect_light! { f64, bidirectional_path_tracer_indirect_light_f64 }
+
+    #[test]
+    fn power_heuristic_sums_to_one() {
+        // Three vertices with different densities from either end, none of them a mirror. The
+        // weights of all three ways to split the path add up to one.
+        let vertices = [(1.0, 0.5, false), (0.25, 2.0, false), (3.0, 0.75, false)];
+        let sum: f64 = (1..=3).map(|split| power_heuristic(&vertices, split)).sum();
+        assert!((sum - 1.0).abs() < 1e-12, "{}", sum);
+
+        // The path can not be split next to a mirror, which leaves the other ways.
+        let vertices = [(1.0, 0.5, true), (0.25, 2.0, false), (3.0, 0.75, false)];
+        assert_eq!(
+            power_heuristic(&vertices, 2) + power_heuristic(&vertices, 3),
+            1.0
+        );
+    }
+}
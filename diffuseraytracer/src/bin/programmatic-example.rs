@@ -14,11 +14,11 @@ use image::SingleColorImage;
 use math::geometry::{AxisAlignedBox, ImplicitNSphere, ImplicitPlane3, Triangle3};
 use math::transform::Transform3;
 use math::{Normal3, Point2, Point3, Vector2, Vector3};
-use random::WichmannHillPRNG;
 use sampling::{RegularPatternGenerator, SamplingPatternSet};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufWriter, Write};
+use std::thread;
 use traits::ToRadians;
 use units::angle::Degrees;
 use units::length::Meter;
@@ -124,14 +124,15 @@ fn main() {
     let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<f64>>>> = HashMap::new();
     cameras.insert(String::from("main"), cam);
 
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
     let diffuse_ray_tracer =
-        DiffuseRayTracer::new(SamplingPatternSet::regular_pattern(1, 1), 0.0001);
+        DiffuseRayTracer::new(SamplingPatternSet::regular_pattern(1, 1), 0.0001)
+            .with_threads(threads);
 
     let scene = Scene3::new(RGB::new(0.0, 0.0, 0.0), lights, cameras, geometries);
 
-    let rnd = WichmannHillPRNG::new_random();
-
-    let rendered_image = diffuse_ray_tracer.render(scene, "main", size, rnd);
+    let rendered_image = diffuse_ray_tracer.render(scene, "main", size, 0);
 
     let image_data = rendered_image
         .clamp_color(RGB::new(0.0, 0.0, 0.0), RGB::new(1.0, 1.0, 1.0))
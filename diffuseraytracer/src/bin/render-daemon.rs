@@ -0,0 +1,286 @@
+use std::env;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use diffuseraytracer::job_queue::{parse_progress, renderer_failure, Job, JobQueue, JobState};
+
+// How often the daemon looks for new jobs and for changes to the running one.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// The part of the output of the renderer that is kept to find the progress and the reason of a
+// failure.
+const OUTPUT_TAIL: usize = 4096;
+
+enum Action {
+    Run(Option<PathBuf>),
+    Submit(i32, Vec<String>),
+    Pause(u64),
+    Resume(u64),
+    Status,
+}
+
+struct Configuration {
+    queue: PathBuf,
+    action: Action,
+}
+
+fn usage() -> String {
+    String::from(
+        "Usage: render-daemon QUEUE_DIRECTORY COMMAND\n\
+         \n\
+         Commands:\n\
+         \x20 run [--renderer PATH]           Works on the queue until it is stopped.\n\
+         \x20 submit [--priority N] ARGS...   Queues a render with the arguments of the renderer.\n\
+         \x20 pause ID                        Pauses a queued or running job.\n\
+         \x20 resume ID                       Queues a paused or failed job again.\n\
+         \x20 status                          Lists all jobs.",
+    )
+}
+
+fn parse_id(id: Option<String>) -> Result<u64, String> {
+    match id {
+        Some(id) => match id.parse::<u64>() {
+            Ok(id) => Ok(id),
+            Err(m) => Err(format!("Failed to parse job id '{}': {}.", id, m)),
+        },
+        None => Err(String::from("Job id is missing.")),
+    }
+}
+
+fn parse_configuration(mut args: impl Iterator<Item = String>) -> Result<Configuration, String> {
+    args.next();
+
+    let queue = match args.next() {
+        Some(queue) => PathBuf::from(queue),
+        None => return Err(usage()),
+    };
+
+    let action = match args.next().as_deref() {
+        Some("run") => {
+            let mut renderer = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--renderer" => match args.next() {
+                        Some(path) => renderer = Some(PathBuf::from(path)),
+                        None => return Err(String::from("Path of the renderer is missing.")),
+                    },
+                    arg => return Err(format!("Unknown parameter '{}'.", arg)),
+                }
+            }
+            Action::Run(renderer)
+        }
+        Some("submit") => {
+            let mut arguments: Vec<String> = args.collect();
+            let mut priority = 0;
+            if arguments.first().map(|arg| arg.as_str()) == Some("--priority") {
+                if arguments.len() < 2 {
+                    return Err(String::from("Priority is missing."));
+                }
+                priority = match arguments[1].parse::<i32>() {
+                    Ok(priority) => priority,
+                    Err(m) => return Err(format!("Failed to parse priority: {}.", m)),
+                };
+                arguments.drain(..2);
+            }
+            if arguments.is_empty() {
+                return Err(String::from("Arguments of the renderer are missing."));
+            }
+            Action::Submit(priority, arguments)
+        }
+        Some("pause") => Action::Pause(parse_id(args.next())?),
+        Some("resume") => Action::Resume(parse_id(args.next())?),
+        Some("status") => Action::Status,
+        Some(command) => return Err(format!("Unknown command '{}'.\n\n{}", command, usage())),
+        None => return Err(usage()),
+    };
+
+    Ok(Configuration { queue, action })
+}
+
+// The renderer is expected next to the daemon, unless another one is given.
+fn renderer_path(renderer: Option<PathBuf>) -> Result<PathBuf, String> {
+    if let Some(renderer) = renderer {
+        return Ok(renderer);
+    }
+    match env::current_exe() {
+        Ok(exe) => Ok(exe.with_file_name("diffuseraytracer")),
+        Err(m) => Err(format!("Failed to find the renderer: {}", m)),
+    }
+}
+
+fn spawn_renderer(renderer: &Path, job: &Job) -> std::io::Result<Child> {
+    Command::new(renderer)
+        .args(&job.arguments)
+        .arg("--progress")
+        .current_dir(&job.directory)
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+}
+
+// Collects the end of the error output of the renderer while it runs.
+fn collect_output(mut child: Child) -> (Child, Arc<Mutex<String>>) {
+    let output = Arc::new(Mutex::new(String::new()));
+    if let Some(mut stderr) = child.stderr.take() {
+        let output = Arc::clone(&output);
+        thread::spawn(move || {
+            let mut buffer = [0u8; 256];
+            while let Ok(n) = stderr.read(&mut buffer) {
+                if n == 0 {
+                    break;
+                }
+                let mut output = output.lock().unwrap();
+                output.push_str(&String::from_utf8_lossy(&buffer[..n]));
+                if output.len() > OUTPUT_TAIL {
+                    let mut start = output.len() - OUTPUT_TAIL;
+                    while !output.is_char_boundary(start) {
+                        start += 1;
+                    }
+                    output.drain(..start);
+                }
+            }
+        });
+    }
+    (child, output)
+}
+
+// Renders a job until it is done, fails or is paused from another process.
+fn run_job(queue: &JobQueue, renderer: &Path, mut job: Job) -> std::io::Result<()> {
+    job.state = JobState::Running;
+    job.progress = 0.0;
+    job.message = None;
+    queue.save(&job)?;
+    println!("Running job {}.", job.id);
+
+    let child = match spawn_renderer(renderer, &job) {
+        Ok(child) => child,
+        Err(m) => {
+            job.state = JobState::Failed;
+            job.message = Some(format!("Failed to start {}: {}", renderer.display(), m));
+            println!("Job {} failed.", job.id);
+            return queue.save(&job);
+        }
+    };
+    let (mut child, output) = collect_output(child);
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        if queue.get(job.id)?.state == JobState::Paused {
+            let _ = child.kill();
+            let _ = child.wait();
+            println!("Paused job {}.", job.id);
+            return Ok(());
+        }
+
+        let status = child.try_wait()?;
+        let output = output.lock().unwrap().clone();
+        let progress = parse_progress(&output);
+        job.progress = progress.unwrap_or(0.0);
+
+        match status {
+            None => {
+                // Another process may have paused the job since it was read, which is noticed
+                // in the next round.
+                if queue.get(job.id)?.state == JobState::Running {
+                    queue.save(&job)?;
+                }
+            }
+            // The renderer reports errors on stderr without a status, so a job only counts as
+            // done when the last progress bar is full.
+            Some(status) => {
+                if status.success() && progress == Some(1.0) {
+                    job.state = JobState::Done;
+                    println!("Finished job {}.", job.id);
+                } else {
+                    job.state = JobState::Failed;
+                    job.message = renderer_failure(&output)
+                        .or(Some(format!("Renderer exited with {}.", status)));
+                    println!("Job {} failed.", job.id);
+                }
+                return queue.save(&job);
+            }
+        }
+    }
+}
+
+// Jobs that were running when the daemon stopped, e.g. because the machine was shut down or
+// hibernated, are rendered again from the start.
+fn run(queue: &JobQueue, renderer: Option<PathBuf>) -> Result<(), String> {
+    let renderer = renderer_path(renderer)?;
+    let error = |m: std::io::Error| format!("Failed to access job queue: {}", m);
+
+    for job in queue.recover().map_err(error)? {
+        println!("Requeued interrupted job {}.", job.id);
+    }
+
+    loop {
+        match queue.next().map_err(error)? {
+            Some(job) => run_job(queue, &renderer, job).map_err(error)?,
+            None => thread::sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+fn print_status(queue: &JobQueue) -> std::io::Result<()> {
+    for job in queue.jobs()? {
+        print!(
+            "{:>4}  {:<8} {:>3}  {:5.1}%  {}",
+            job.id,
+            job.state,
+            job.priority,
+            job.progress * 100.0,
+            job.arguments.join(" ")
+        );
+        if let Some(message) = job.message {
+            print!("  ({})", message);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn main() {
+    let config = match parse_configuration(env::args()) {
+        Ok(config) => config,
+        Err(m) => {
+            eprintln!("{}", m);
+            return;
+        }
+    };
+
+    let queue = match JobQueue::open(&config.queue) {
+        Ok(queue) => queue,
+        Err(m) => {
+            eprintln!("Failed to open job queue: {}", m);
+            return;
+        }
+    };
+
+    let result = match config.action {
+        Action::Run(renderer) => run(&queue, renderer),
+        Action::Submit(priority, arguments) => env::current_dir()
+            .and_then(|directory| queue.submit(priority, &directory, arguments))
+            .map(|job| println!("Submitted job {}.", job.id))
+            .map_err(|m| format!("Failed to submit job: {}", m)),
+        Action::Pause(id) => queue
+            .pause(id)
+            .map(|job| println!("Paused job {}.", job.id))
+            .map_err(|m| format!("Failed to pause job: {}", m)),
+        Action::Resume(id) => queue
+            .resume(id)
+            .map(|job| println!("Resumed job {}.", job.id))
+            .map_err(|m| format!("Failed to resume job: {}", m)),
+        Action::Status => print_status(&queue).map_err(|m| format!("Failed to read jobs: {}", m)),
+    };
+
+    if let Err(m) = result {
+        eprintln!("{}", m);
+    }
+}
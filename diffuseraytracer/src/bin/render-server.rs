@@ -0,0 +1,572 @@
+use std::env;
+use std::fs;
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::time::Duration;
+
+use diffuseraytracer::http::{Request, Response};
+use diffuseraytracer::job_queue::renderer_failure;
+use diffuseraytracer::parser::assets::AssetChecksums;
+
+// How long the server waits for a client to send its request, so a stalled client does not block
+// the renders of the others.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+// A value of an option of the renderer.
+enum Value {
+    Unsigned,
+    // A finite number that is not negative.
+    Number,
+    // A finite number that may be negative.
+    Signed,
+    // A name of the scene, e.g. of a camera.
+    Name,
+    Choice(&'static [&'static str]),
+}
+
+impl Value {
+    fn accepts(&self, value: &str) -> bool {
+        let number = value.parse::<f64>().is_ok_and(f64::is_finite);
+        match self {
+            Value::Unsigned => value.parse::<usize>().is_ok(),
+            Value::Number => number && !value.starts_with('-'),
+            Value::Signed => number,
+            Value::Name => value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            Value::Choice(choices) => choices.contains(&value),
+        }
+    }
+}
+
+// The options of the renderer a client may pass, each with the values it can take. Others are
+// left out, since the server decides which files are read and written and where the renderer
+// connects to, and only the image is sent back.
+const OPTIONS: [(&str, &[&[Value]]); 33] = [
+    ("size", &[&[Value::Unsigned, Value::Unsigned]]),
+    (
+        "crop",
+        &[&[
+            Value::Unsigned,
+            Value::Unsigned,
+            Value::Unsigned,
+            Value::Unsigned,
+        ]],
+    ),
+    ("cropped", &[&[]]),
+    (
+        "sampling",
+        &[
+            &[
+                Value::Choice(&["Regular"]),
+                Value::Unsigned,
+                Value::Unsigned,
+            ],
+            &[
+                Value::Choice(&["Random", "NRooks"]),
+                Value::Unsigned,
+                Value::Unsigned,
+            ],
+            &[
+                Value::Choice(&["Jittered", "MultiJittered"]),
+                Value::Unsigned,
+                Value::Unsigned,
+                Value::Unsigned,
+            ],
+            &[Value::Choice(&["Hammersley"]), Value::Unsigned],
+        ],
+    ),
+    (
+        "adaptive",
+        &[&[Value::Unsigned, Value::Unsigned, Value::Number]],
+    ),
+    ("seed", &[&[Value::Unsigned]]),
+    ("threads", &[&[Value::Unsigned]]),
+    ("filter", &[&[Value::Choice(&["box", "gaussian", "sinc"])]]),
+    ("camera", &[&[Value::Name]]),
+    ("physical-exposure", &[&[]]),
+    ("ev100", &[&[Value::Signed]]),
+    (
+        "auto-exposure",
+        &[&[Value::Choice(&["average", "center-weighted", "median"])]],
+    ),
+    ("contours", &[&[]]),
+    ("contour-interval", &[&[Value::Number]]),
+    ("crease-angle", &[&[Value::Number]]),
+    ("anaglyph", &[&[]]),
+    ("time", &[&[Value::Signed]]),
+    ("fps", &[&[Value::Number]]),
+    ("shadows", &[&[]]),
+    ("linear-output", &[&[]]),
+    ("stats", &[&[]]),
+    ("packets", &[&[]]),
+    ("sample-heatmap", &[&[]]),
+    (
+        "tone-map",
+        &[
+            &[Value::Choice(&["reinhard", "aces"])],
+            &[Value::Choice(&["exposure"]), Value::Signed],
+            &[Value::Choice(&["gamma"]), Value::Number],
+        ],
+    ),
+    ("pixel-art", &[&[Value::Unsigned]]),
+    ("palette", &[&[Value::Choice(&["gameboy", "cga", "pico8"])]]),
+    (
+        "integrator",
+        &[&[Value::Choice(&["diffuse", "whitted", "path", "bdpt", "ao"])]],
+    ),
+    ("max-depth", &[&[Value::Unsigned]]),
+    ("ao-distance", &[&[Value::Number]]),
+    ("gizmos", &[&[Value::Number]]),
+    ("denoise", &[&[]]),
+    ("progressive", &[&[Value::Unsigned]]),
+    ("alpha", &[&[]]),
+];
+
+// The last image the server rendered, with the arguments of its render and the checksums of the
+// scene and the assets it refers to. A client asking for the same render again, e.g. a preview
+// polling while an asset is edited, is answered with it until one of the files changes.
+struct LastRender {
+    arguments: Vec<String>,
+    checksums: AssetChecksums,
+    image: Vec<u8>,
+}
+
+impl LastRender {
+    fn image_for(&self, arguments: &[String], checksums: &AssetChecksums) -> Option<&[u8]> {
+        if self.arguments == arguments && checksums.changed(&self.checksums).is_empty() {
+            Some(&self.image)
+        } else {
+            None
+        }
+    }
+}
+
+struct Configuration {
+    address: String,
+    renderer: Option<PathBuf>,
+    // The working directory of the renderer, where the assets of the scenes are looked up.
+    assets: PathBuf,
+}
+
+fn usage() -> String {
+    String::from(
+        "Usage: render-server ADDRESS [--renderer PATH] [--assets DIRECTORY]\n\
+         \n\
+         Waits for scenes on the address, e.g. 127.0.0.1:8080, and renders them one at a time.\n\
+         Relative names of assets in the scenes are looked up in the assets directory, which is\n\
+         the working directory by default. A render that is asked for again is answered with the\n\
+         last image, as long as the scene and its assets are unchanged.",
+    )
+}
+
+fn api() -> String {
+    String::from(
+        "POST /render?OPTION=VALUE&...   Renders the scene in the body and answers with the\n\
+         \x20                               image in farbfeld. The options are those of the\n\
+         \x20                               renderer that change the image, without the dashes,\n\
+         \x20                               with spaces between values, e.g.\n\
+         \x20                               size=320+240&integrator=path&packets.\n\
+         GET /                           Shows this help.",
+    )
+}
+
+fn parse_configuration(mut args: impl Iterator<Item = String>) -> Result<Configuration, String> {
+    args.next();
+
+    let address = match args.next() {
+        Some(address) if !address.starts_with("--") => address,
+        _ => return Err(usage()),
+    };
+
+    let mut renderer = None;
+    let mut assets = PathBuf::from(".");
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--renderer" => match args.next() {
+                Some(path) => renderer = Some(PathBuf::from(path)),
+                None => return Err(String::from("Path of the renderer is missing.")),
+            },
+            "--assets" => match args.next() {
+                Some(directory) => assets = PathBuf::from(directory),
+                None => return Err(String::from("Assets directory is missing.")),
+            },
+            arg => return Err(format!("Unknown parameter '{}'.", arg)),
+        }
+    }
+
+    Ok(Configuration {
+        address,
+        renderer,
+        assets,
+    })
+}
+
+// The renderer is expected next to the server, unless another one is given.
+fn renderer_path(renderer: Option<PathBuf>) -> Result<PathBuf, String> {
+    if let Some(renderer) = renderer {
+        return Ok(renderer);
+    }
+    match env::current_exe() {
+        Ok(exe) => Ok(exe.with_file_name("diffuseraytracer")),
+        Err(m) => Err(format!("Failed to find the renderer: {}", m)),
+    }
+}
+
+// The arguments of the renderer for the parameters of a query, e.g. --size 320 240 for
+// size=320+240. Every value must be one the option takes, so no further options can be passed
+// within them.
+fn renderer_arguments(query: &[(String, String)]) -> Result<Vec<String>, String> {
+    let mut arguments = Vec::new();
+    for (name, value) in query {
+        let Some((_, forms)) = OPTIONS.iter().find(|(option, _)| option == name) else {
+            return Err(format!(
+                "The option '{}' can not be passed to the server.",
+                name
+            ));
+        };
+        let values: Vec<&str> = value.split_whitespace().collect();
+        let valid = forms.iter().any(|form| {
+            form.len() == values.len()
+                && form.iter().zip(&values).all(|(v, value)| v.accepts(value))
+        });
+        if !valid {
+            return Err(format!(
+                "The value '{}' is not valid for the option '{}'.",
+                value, name
+            ));
+        }
+        arguments.push(format!("--{}", name));
+        arguments.extend(values.into_iter().map(String::from));
+    }
+    Ok(arguments)
+}
+
+// Renders the scene in a directory of its own, which holds the scene and the image the renderer
+// writes. The scene is only rendered again if it, one of its assets or the arguments differ from
+// the last render.
+fn render(
+    scene: &[u8],
+    arguments: &[String],
+    renderer: &Path,
+    assets: &Path,
+    directory: &Path,
+    last_render: &mut Option<LastRender>,
+) -> Response {
+    let scene_path = directory.join("scene.scene");
+    let output_path = directory.join("out.ff");
+    if let Err(m) = fs::create_dir_all(directory).and_then(|_| fs::write(&scene_path, scene)) {
+        return Response::text(500, &format!("Failed to store the scene: {}", m));
+    }
+
+    // The renderer looks the assets up next to the scene and then in its working directory. A
+    // scene whose files can not be read is not kept, the renderer tells what is wrong with it.
+    let checksums =
+        AssetChecksums::of_scene(&scene_path.to_string_lossy(), &[assets.to_path_buf()]).ok();
+    let cached = checksums.as_ref().and_then(|checksums| {
+        last_render
+            .as_ref()
+            .and_then(|last_render| last_render.image_for(arguments, checksums))
+    });
+    if let Some(image) = cached {
+        return Response::new(200, "image/x-farbfeld", image.to_vec());
+    }
+
+    let output = Command::new(renderer)
+        .arg(&scene_path)
+        .args(arguments)
+        .arg("-O")
+        .arg(&output_path)
+        .current_dir(assets)
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output();
+    let output = match output {
+        Ok(output) => output,
+        Err(m) => {
+            return Response::text(
+                500,
+                &format!("Failed to start {}: {}", renderer.display(), m),
+            );
+        }
+    };
+
+    // The renderer reports errors on stderr without a status, so a render only succeeded if it
+    // wrote the image.
+    match fs::read(&output_path) {
+        Ok(image) if output.status.success() => {
+            *last_render = checksums.map(|checksums| LastRender {
+                arguments: arguments.to_vec(),
+                checksums,
+                image: image.clone(),
+            });
+            Response::new(200, "image/x-farbfeld", image)
+        }
+        _ => {
+            let message = renderer_failure(&String::from_utf8_lossy(&output.stderr))
+                .unwrap_or(format!("Renderer exited with {}.", output.status));
+            Response::text(422, &message)
+        }
+    }
+}
+
+fn respond(
+    request: &Request,
+    renderer: &Path,
+    assets: &Path,
+    last_render: &mut Option<LastRender>,
+) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => Response::text(200, &api()),
+        ("POST", "/render") => {
+            let arguments = match renderer_arguments(&request.query) {
+                Ok(arguments) => arguments,
+                Err(m) => return Response::text(400, &m),
+            };
+            // The requests are answered one after another, so they share the directory and the
+            // scene keeps its path for the checksums.
+            let directory = env::temp_dir().join(format!("render-server-{}", process::id()));
+            let response = render(
+                &request.body,
+                &arguments,
+                renderer,
+                assets,
+                &directory,
+                last_render,
+            );
+            let _ = fs::remove_dir_all(&directory);
+            response
+        }
+        (_, "/" | "/render") => Response::text(405, "Method not allowed."),
+        _ => Response::text(404, "Not found."),
+    }
+}
+
+fn handle(
+    stream: TcpStream,
+    renderer: &Path,
+    assets: &Path,
+    id: u64,
+    last_render: &mut Option<LastRender>,
+) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let response = match Request::read_from(&mut BufReader::new(&stream)) {
+        Ok(request) => {
+            let response = respond(&request, renderer, assets, last_render);
+            println!(
+                "{} {} {} {}",
+                id, request.method, request.path, response.status
+            );
+            response
+        }
+        Err(m) => Response::text(400, &format!("Malformed request: {}", m)),
+    };
+    response.write_to(&mut &stream)
+}
+
+fn run(config: Configuration) -> Result<(), String> {
+    let renderer = renderer_path(config.renderer)?;
+    let listener = match TcpListener::bind(&config.address) {
+        Ok(listener) => listener,
+        Err(m) => return Err(format!("Failed to listen on {}: {}", config.address, m)),
+    };
+    println!("Listening on {}.", config.address);
+
+    // A render uses all cores, so the requests are answered one after another.
+    let mut last_render = None;
+    for (id, stream) in listener.incoming().enumerate() {
+        let result = stream.and_then(|stream| {
+            handle(
+                stream,
+                &renderer,
+                &config.assets,
+                id as u64,
+                &mut last_render,
+            )
+        });
+        if let Err(m) = result {
+            eprintln!("Failed to answer request {}: {}", id, m);
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let result = parse_configuration(env::args()).and_then(run);
+    if let Err(m) = result {
+        eprintln!("{}", m);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(parameters: &[(&str, &str)]) -> Vec<(String, String)> {
+        parameters
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn pass_options_with_valid_values() {
+        let arguments = renderer_arguments(&query(&[
+            ("size", "320 240"),
+            ("integrator", "path"),
+            ("packets", ""),
+            ("ev100", "-2.5"),
+            ("tone-map", "gamma 2.2"),
+            ("sampling", "Jittered 1 4 4"),
+            ("camera", "top_view"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            arguments,
+            [
+                "--size",
+                "320",
+                "240",
+                "--integrator",
+                "path",
+                "--packets",
+                "--ev100",
+                "-2.5",
+                "--tone-map",
+                "gamma",
+                "2.2",
+                "--sampling",
+                "Jittered",
+                "1",
+                "4",
+                "4",
+                "--camera",
+                "top_view"
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_injected_options() {
+        for parameters in [
+            [("size", "8 8 --pack /tmp/pwnpack")],
+            [("size", "8 8 -O /tmp/image.ff")],
+            [("integrator", "path --checkpoint /tmp/checkpoint")],
+            [("camera", "--worker")],
+            [("camera", "../main")],
+            [("packets", "--workers render1:7878")],
+            [("ao-distance", "-1")],
+            [("fps", "NaN")],
+            [("size", "8")],
+            [("tone-map", "gamma")],
+            [("pack", "/tmp/pwnpack")],
+            [("-O", "/tmp/image.ff")],
+            [("--checkpoint", "/tmp/checkpoint")],
+            [("I", "/")],
+            [("lpe", "../diffuse C<RD>L")],
+        ] {
+            assert!(
+                renderer_arguments(&query(&parameters)).is_err(),
+                "{:?} was passed.",
+                parameters
+            );
+        }
+    }
+
+    #[test]
+    fn render_again_when_an_asset_changed() {
+        let root = env::temp_dir().join("render_again_when_an_asset_changed");
+        let _ = fs::remove_dir_all(&root);
+        let assets = root.join("assets");
+        let directory = root.join("render");
+        fs::create_dir_all(&assets).unwrap();
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(assets.join("sky.hdr"), "sky").unwrap();
+
+        // The renderer is missing, so every render that is not answered from the last one fails.
+        let renderer = root.join("diffuseraytracer");
+        let scene = b"environment_light { image: sky.hdr }\n";
+        let arguments = vec![String::from("--size"), String::from("8"), String::from("8")];
+        fs::write(directory.join("scene.scene"), scene).unwrap();
+        let checksums = AssetChecksums::of_scene(
+            directory.join("scene.scene").to_str().unwrap(),
+            std::slice::from_ref(&assets),
+        )
+        .unwrap();
+        let mut last_render = Some(LastRender {
+            arguments: arguments.clone(),
+            checksums,
+            image: b"image".to_vec(),
+        });
+        let mut render = |scene: &[u8], arguments: &[String]| {
+            render(
+                scene,
+                arguments,
+                &renderer,
+                &assets,
+                &directory,
+                &mut last_render,
+            )
+        };
+
+        let response = render(scene, &arguments);
+        assert_eq!((response.status, response.body), (200, b"image".to_vec()));
+        assert_eq!(render(scene, &arguments[..1]).status, 500);
+        assert_eq!(render(b"sphere { }", &arguments).status, 500);
+
+        // The scene is rendered again, once an asset has other content.
+        fs::write(assets.join("sky.hdr"), "cloudy sky").unwrap();
+        assert_eq!(render(scene, &arguments).status, 500);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    // A stand-in for the renderer, which fails on the first line of a file the scene includes
+    // like the renderer does.
+    #[cfg(unix)]
+    #[test]
+    fn answer_without_the_content_of_included_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = env::temp_dir().join("answer_without_the_content_of_included_files");
+        let _ = fs::remove_dir_all(&root);
+        let assets = root.join("assets");
+        fs::create_dir_all(&assets).unwrap();
+        fs::write(
+            assets.join("secret.scene"),
+            "root:x:0:0:root:/root:/bin/bash\n",
+        )
+        .unwrap();
+
+        let renderer = root.join("diffuseraytracer");
+        fs::write(
+            &renderer,
+            "#!/bin/sh\n\
+             line=$(head -n 1 secret.scene)\n\
+             printf '[##..]  50.0%%\\n' >&2\n\
+             printf 'Failed to parse passed scene file. Error was: UnsupportedElement(\"%s\")\\n' \"$line\" >&2\n\
+             printf ' --> secret.scene:1:1\\n  |\\n1 | %s\\n  | ^^^^\\n' \"$line\" >&2\n",
+        )
+        .unwrap();
+        fs::set_permissions(&renderer, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let response = render(
+            b"include secret.scene\n",
+            &[],
+            &renderer,
+            &assets,
+            &root.join("render"),
+            &mut None,
+        );
+        assert_eq!(response.status, 422);
+        assert_eq!(
+            String::from_utf8(response.body).unwrap(),
+            "Failed to parse passed scene file. Error was: UnsupportedElement\n \
+             --> secret.scene:1:1\n"
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,10 +1,14 @@
 use std::ops::Div;
 
+use cg_basics::camera::Shutter;
+use cg_basics::exposure::PhysicalExposure;
 use math::geometry::ParametricLine;
 use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
 use sampling::SamplingPattern;
+use traits::{One, Zero};
 
-pub trait RaytracingCamera<T>
+pub trait RaytracingCamera<T>: Sync
 where
     T: Div,
 {
@@ -13,11 +17,78 @@ where
         size: Vector2<<T as Div>::Output>,
         p: Point2<<T as Div>::Output>,
         pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
     ) -> Option<ParametricLine<Point3<T>, Vector3<T>>>;
+
+    // The solid angle the image covers around p, relative to the rest of the image. Samples are
+    // weighted by it when they are combined into pixels, so a pixel of a panoramic projection is
+    // not dominated by the part of it that shows the least of the scene.
+    fn solid_angle(
+        &self,
+        _size: Vector2<<T as Div>::Output>,
+        _p: Point2<<T as Div>::Output>,
+    ) -> <T as Div>::Output
+    where
+        <T as Div>::Output: One,
+    {
+        One::one()
+    }
+
+    // The interval the time of the camera rays is drawn from.
+    fn shutter(&self) -> Shutter<<T as Div>::Output>
+    where
+        <T as Div>::Output: Zero,
+    {
+        Shutter::default()
+    }
+
+    // The exposure of the image, if the camera is set up like a real one.
+    fn exposure(&self) -> Option<PhysicalExposure<<T as Div>::Output>> {
+        None
+    }
+
+    // Moves the camera to the time of a frame of an animation. Most cameras stand still.
+    fn set_time(&mut self, _time: <T as Div>::Output) {}
+
+    // The focus distance that shows p sharp. None if the camera has no focus.
+    fn focus_distance_to(&self, _p: Point3<T>) -> Option<T> {
+        None
+    }
+
+    // The ray along the optical axis, e.g. to focus on what the camera looks at. None if the camera
+    // has no focus.
+    fn central_ray(&self) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        None
+    }
+
+    // Focuses the camera at a distance in front of the lens. Cameras without a focus ignore it.
+    fn set_focus_distance(&mut self, _focus_distance: T) {}
+
+    // Moves a focus that changes over time to the time of a frame. The positions of objects the
+    // camera focuses on are looked up by their names.
+    fn pull_focus(
+        &mut self,
+        _time: <T as Div>::Output,
+        _locate: &dyn Fn(&str) -> Option<Point3<T>>,
+    ) {
+    }
 }
 
+mod animated_camera;
+mod cropped_camera;
+mod cylindrical_camera;
 mod fisheye_camera;
+mod focus_pulled_camera;
+mod frame_camera;
+mod ods_camera;
 mod orthographic_camera;
 mod perspective_camera;
+mod physical_camera;
 mod pinhole_camera;
 mod spherical_camera;
+mod stereo_camera;
+
+pub use animated_camera::AnimatedCamera;
+pub use cropped_camera::{CropWindow, CroppedCamera};
+pub use focus_pulled_camera::FocusPulledCamera;
+pub use frame_camera::FrameCamera;
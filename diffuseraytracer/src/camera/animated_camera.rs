@@ -0,0 +1,244 @@
+use std::ops::{Div, Mul};
+
+use cg_basics::camera::{CameraPath, Shutter};
+use cg_basics::exposure::PhysicalExposure;
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
+use sampling::SamplingPattern;
+use traits::{ConvenientNumber, FloatingPoint, Number, SelfMulNumber, Sqrt, Zero};
+
+use crate::camera::RaytracingCamera;
+
+// Moves a camera along a path. The camera is placed relative to the pose on the path: a camera in
+// the origin that looks along the negative z axis with y up sees exactly what the path looks at,
+// any other camera is carried along like a camera mounted on a rig, e.g. the eyes of a stereo
+// camera.
+pub struct AnimatedCamera<T>
+where
+    T: Div,
+{
+    pub camera: Box<dyn RaytracingCamera<T>>,
+    pub path: CameraPath<T>,
+    time: <T as Div>::Output,
+}
+
+impl<T> AnimatedCamera<T>
+where
+    T: Div,
+    <T as Div>::Output: Zero,
+{
+    pub fn new(camera: Box<dyn RaytracingCamera<T>>, path: CameraPath<T>) -> AnimatedCamera<T> {
+        AnimatedCamera {
+            camera,
+            path,
+            time: Zero::zero(),
+        }
+    }
+}
+
+impl<T> RaytracingCamera<T> for AnimatedCamera<T>
+where
+    T: SelfMulNumber<<T as Div>::Output> + ConvenientNumber + Sync,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+{
+    fn ray_for(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+        pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        let ray = self.camera.ray_for(size, p, pattern, rnd)?;
+        Some(from_rig(&self.path, self.time, ray))
+    }
+
+    fn solid_angle(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+    ) -> <T as Div>::Output {
+        self.camera.solid_angle(size, p)
+    }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.camera.shutter()
+    }
+
+    fn exposure(&self) -> Option<PhysicalExposure<<T as Div>::Output>> {
+        self.camera.exposure()
+    }
+
+    fn set_time(&mut self, time: <T as Div>::Output) {
+        self.time = time;
+    }
+
+    fn focus_distance_to(&self, p: Point3<T>) -> Option<T> {
+        self.camera
+            .focus_distance_to(to_rig(&self.path, self.time, p))
+    }
+
+    fn central_ray(&self) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        let ray = self.camera.central_ray()?;
+        Some(from_rig(&self.path, self.time, ray))
+    }
+
+    fn set_focus_distance(&mut self, focus_distance: T) {
+        self.camera.set_focus_distance(focus_distance);
+    }
+
+    fn pull_focus(&mut self, time: <T as Div>::Output, locate: &dyn Fn(&str) -> Option<Point3<T>>) {
+        let (path, pose_time) = (&self.path, self.time);
+        self.camera.pull_focus(time, &|name| {
+            locate(name).map(|p| to_rig(path, pose_time, p))
+        });
+    }
+}
+
+// The eye position and the axes u, v and w of a camera.
+type Frame<T> = (
+    Point3<T>,
+    Vector3<<T as Div>::Output>,
+    Vector3<<T as Div>::Output>,
+    Vector3<<T as Div>::Output>,
+);
+
+// The frame of the camera on the path at a time.
+fn rig_frame<T>(path: &CameraPath<T>, time: <T as Div>::Output) -> Frame<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+{
+    let (e, g, t) = path.pose(time);
+    let w = -g.normalized();
+    let u = Vector3::cross(t, w).normalized();
+    let v = Vector3::cross(w, u).normalized();
+    (e, u, v, w)
+}
+
+// Where a ray of the rig goes in the scene.
+fn from_rig<T>(
+    path: &CameraPath<T>,
+    time: <T as Div>::Output,
+    ray: ParametricLine<Point3<T>, Vector3<T>>,
+) -> ParametricLine<Point3<T>, Vector3<T>>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+{
+    let (e, u, v, w) = rig_frame(path, time);
+
+    let o = ray.origin;
+    let d = ray.direction;
+
+    ParametricLine::new(e + u * o.x + v * o.y + w * o.z, u * d.x + v * d.y + w * d.z)
+}
+
+// Where a point of the scene is relative to the camera on the path, i.e. in the space the rig is
+// built in.
+fn to_rig<T>(path: &CameraPath<T>, time: <T as Div>::Output, p: Point3<T>) -> Point3<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+{
+    let (e, u, v, w) = rig_frame(path, time);
+    let d = (p - e) / T::one();
+    Point3::new(
+        d.dot(u) * T::one(),
+        d.dot(v) * T::one(),
+        d.dot(w) * T::one(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cg_basics::camera::{CameraKeyframe, PinholeCamera};
+    use sampling::{RegularPatternGenerator, SamplingPatternSet};
+    use traits::ToRadians;
+    use units::angle::Degrees;
+
+    macro_rules! animated_camera_ray_for {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let pinhole = |e: Point3<$type>| {
+                    PinholeCamera::new(
+                        e,
+                        Vector3::new(0.0, 0.0, -1.0),
+                        Vector3::new(0.0, 1.0, 0.0),
+                        Degrees::<$type>::new(90.0).to_radians(),
+                    )
+                    .with_shutter(Shutter::new(0.0, 0.5))
+                };
+                let path = CameraPath::new(vec![
+                    CameraKeyframe::new(
+                        0.0,
+                        Point3::new(0.0, 0.0, 5.0),
+                        Point3::new(0.0, 0.0, 0.0),
+                    ),
+                    CameraKeyframe::new(
+                        1.0,
+                        Point3::new(5.0, 0.0, 0.0),
+                        Point3::new(0.0, 0.0, 0.0),
+                    ),
+                ]);
+
+                let mut camera = AnimatedCamera::new(
+                    Box::new(pinhole(Point3::new(0.0, 0.0, 0.0))),
+                    path.clone(),
+                );
+                let mut rig =
+                    AnimatedCamera::new(Box::new(pinhole(Point3::new(0.0, 0.0, 1.0))), path);
+
+                let size = Vector2::new(640.0, 480.0);
+                let patterns = SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1);
+                let mut rnd = WichmannHillPRNG::from_seed(0);
+                let mut ray_for = |camera: &AnimatedCamera<$type>, p: Point2<$type>| {
+                    camera.ray_for(size, p, &patterns[0], &mut rnd).unwrap()
+                };
+                let close = |a: Vector3<$type>, b: Vector3<$type>| (a - b).magnitude() < 0.0001;
+
+                let center = ray_for(&camera, Point2::new(320.0, 240.0));
+                assert!(close(
+                    center.origin - Point3::new(0.0, 0.0, 5.0),
+                    Vector3::new(0.0, 0.0, 0.0)
+                ));
+                assert!(close(center.direction, Vector3::new(0.0, 0.0, -1.0)));
+                assert_eq!(camera.shutter(), Shutter::new(0.0, 0.5));
+
+                camera.set_time(1.0);
+                rig.set_time(1.0);
+
+                let center = ray_for(&camera, Point2::new(320.0, 240.0));
+                assert!(close(
+                    center.origin - Point3::new(5.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, 0.0)
+                ));
+                assert!(close(center.direction, Vector3::new(-1.0, 0.0, 0.0)));
+                // The shutter is moved to the time of the frame by the frame camera.
+                assert_eq!(camera.shutter(), Shutter::new(0.0, 0.5));
+
+                // The right edge of the image turned with the camera.
+                let right = ray_for(&camera, Point2::new(640.0, 240.0));
+                assert!(right.direction.z < -0.5);
+
+                // A camera mounted behind the eye stays behind it.
+                let center = ray_for(&rig, Point2::new(320.0, 240.0));
+                assert!(close(
+                    center.origin - Point3::new(6.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, 0.0)
+                ));
+                assert!(close(center.direction, Vector3::new(-1.0, 0.0, 0.0)));
+            }
+        };
+    }
+
+    animated_camera_ray_for! { f32, animated_camera_ray_for_f32 }
+    animated_camera_ray_for! { f64, animated_camera_ray_for_f64 }
+}
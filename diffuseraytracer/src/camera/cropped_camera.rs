@@ -0,0 +1,207 @@
+use std::ops::Div;
+
+use cg_basics::camera::Shutter;
+use cg_basics::exposure::PhysicalExposure;
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
+use sampling::SamplingPattern;
+use traits::FloatingPoint;
+
+use crate::camera::RaytracingCamera;
+
+// A rectangle of an image in pixels, from the upper left corner to the lower right one, which is
+// not part of the window.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CropWindow {
+    pub min: Point2<usize>,
+    pub max: Point2<usize>,
+}
+
+impl CropWindow {
+    pub fn new(min: Point2<usize>, max: Point2<usize>) -> CropWindow {
+        CropWindow { min, max }
+    }
+
+    pub fn size(&self) -> Vector2<usize> {
+        Vector2::new(self.max.x - self.min.x, self.max.y - self.min.y)
+    }
+}
+
+// Renders only a window of the image of a camera, e.g. to inspect a detail without rendering the
+// whole image again. The rendered image is either the window alone or the whole image, with the
+// pixels outside of the window left black.
+pub struct CroppedCamera<T>
+where
+    T: Div,
+{
+    pub camera: Box<dyn RaytracingCamera<T>>,
+    pub window: CropWindow,
+    // The size of the whole image.
+    pub size: Vector2<usize>,
+    // Whether the rendered image only holds the window.
+    pub cropped: bool,
+}
+
+impl<T> CroppedCamera<T>
+where
+    T: Div,
+    <T as Div>::Output: FloatingPoint,
+    u16: Into<<T as Div>::Output>,
+{
+    pub fn new(
+        camera: Box<dyn RaytracingCamera<T>>,
+        window: CropWindow,
+        size: Vector2<usize>,
+        cropped: bool,
+    ) -> CroppedCamera<T> {
+        CroppedCamera {
+            camera,
+            window,
+            size,
+            cropped,
+        }
+    }
+
+    fn image_size(&self) -> Vector2<<T as Div>::Output> {
+        Vector2::new((self.size.x as u16).into(), (self.size.y as u16).into())
+    }
+
+    // The position on the whole image, whose rows count up from the bottom. None outside of the
+    // window.
+    fn on_image(&self, p: Point2<<T as Div>::Output>) -> Option<Point2<<T as Div>::Output>> {
+        let value = |value: usize| -> <T as Div>::Output { (value as u16).into() };
+        let bottom = self.size.y - self.window.max.y;
+
+        if self.cropped {
+            return Some(Point2::new(
+                p.x + value(self.window.min.x),
+                p.y + value(bottom),
+            ));
+        }
+
+        let inside = p.x >= value(self.window.min.x)
+            && p.x < value(self.window.max.x)
+            && p.y >= value(bottom)
+            && p.y < value(self.size.y - self.window.min.y);
+        if inside {
+            Some(p)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> RaytracingCamera<T> for CroppedCamera<T>
+where
+    T: Div + Sync,
+    <T as Div>::Output: FloatingPoint,
+    u16: Into<<T as Div>::Output>,
+{
+    fn ray_for(
+        &self,
+        _size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+        pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        let p = self.on_image(p)?;
+        self.camera.ray_for(self.image_size(), p, pattern, rnd)
+    }
+
+    fn solid_angle(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+    ) -> <T as Div>::Output {
+        match self.on_image(p) {
+            Some(p) => self.camera.solid_angle(self.image_size(), p),
+            None => self.camera.solid_angle(size, p),
+        }
+    }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.camera.shutter()
+    }
+
+    fn exposure(&self) -> Option<PhysicalExposure<<T as Div>::Output>> {
+        self.camera.exposure()
+    }
+
+    fn set_time(&mut self, time: <T as Div>::Output) {
+        self.camera.set_time(time);
+    }
+
+    fn focus_distance_to(&self, p: Point3<T>) -> Option<T> {
+        self.camera.focus_distance_to(p)
+    }
+
+    fn central_ray(&self) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        self.camera.central_ray()
+    }
+
+    fn set_focus_distance(&mut self, focus_distance: T) {
+        self.camera.set_focus_distance(focus_distance);
+    }
+
+    fn pull_focus(&mut self, time: <T as Div>::Output, locate: &dyn Fn(&str) -> Option<Point3<T>>) {
+        self.camera.pull_focus(time, locate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cg_basics::camera::PinholeCamera;
+    use sampling::{RegularPatternGenerator, SamplingPatternSet};
+    use traits::ToRadians;
+    use units::angle::Degrees;
+
+    macro_rules! cropped_camera_window {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let lens = || {
+                    Box::new(PinholeCamera::new(
+                        Point3::new(0.0, 0.0, 0.0),
+                        Vector3::new(0.0, 0.0, -1.0),
+                        Vector3::new(0.0, 1.0, 0.0),
+                        Degrees::<$type>::new(90.0).to_radians(),
+                    ))
+                };
+                let camera = lens();
+                // The window covers the columns 10 to 29 and the rows 5 to 14 of an image of
+                // 40 by 20 pixels.
+                let window = CropWindow::new(Point2::new(10, 5), Point2::new(30, 15));
+                let size = Vector2::new(40, 20);
+                let cropped = CroppedCamera::new(lens(), window, size, true);
+                let full_frame = CroppedCamera::new(lens(), window, size, false);
+
+                let patterns = SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1);
+                let mut rnd = WichmannHillPRNG::from_seed(0);
+                let mut ray = |camera: &dyn RaytracingCamera<$type>, size: Vector2<$type>, x, y| {
+                    camera.ray_for(size, Point2::new(x, y), &patterns[0], &mut rnd)
+                };
+
+                // The lower left corner of the window, row 14 from the top.
+                let expected = ray(camera.as_ref(), Vector2::new(40.0, 20.0), 10.5, 5.5);
+                assert_eq!(ray(&cropped, Vector2::new(20.0, 10.0), 0.5, 0.5), expected);
+                assert_eq!(
+                    ray(&full_frame, Vector2::new(40.0, 20.0), 10.5, 5.5),
+                    expected
+                );
+
+                assert_eq!(ray(&full_frame, Vector2::new(40.0, 20.0), 9.5, 5.5), None);
+                assert_eq!(ray(&full_frame, Vector2::new(40.0, 20.0), 10.5, 4.5), None);
+                assert_eq!(ray(&full_frame, Vector2::new(40.0, 20.0), 30.5, 10.5), None);
+                assert_eq!(ray(&full_frame, Vector2::new(40.0, 20.0), 20.5, 15.5), None);
+
+                assert_eq!(window.size(), Vector2::new(20, 10));
+            }
+        };
+    }
+
+    cropped_camera_window! { f32, cropped_camera_window_f32 }
+    cropped_camera_window! { f64, cropped_camera_window_f64 }
+}
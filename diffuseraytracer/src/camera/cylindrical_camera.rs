@@ -0,0 +1,143 @@
+use std::ops::{Div, Mul};
+
+use cg_basics::camera::{CylindricalCamera, Shutter};
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
+use sampling::SamplingPattern;
+use traits::{
+    ConvenientNumber, Cos, FloatingPoint, Half, Number, One, SelfMulNumber, Sin, Sqrt, Tan,
+};
+use units::angle::Radians;
+
+use crate::camera::RaytracingCamera;
+
+impl<T> RaytracingCamera<T> for CylindricalCamera<T>
+where
+    T: SelfMulNumber<<T as Div>::Output> + ConvenientNumber,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+    Radians<<T as Div>::Output>: Cos<Output = <T as Div>::Output>
+        + Sin<Output = <T as Div>::Output>
+        + Tan<Output = <T as Div>::Output>,
+{
+    fn ray_for(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+        _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
+    ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        // The angle around the up vector grows to the right, the gaze direction is in the center.
+        let phi = self.horizontal_field_of_view * ((p.x - size.x.half()) / size.x);
+        let height = height(self.vertical_field_of_view, size, p);
+
+        let d = -self.w * phi.cos() + self.u * phi.sin() + self.v * height;
+
+        Some(ParametricLine::new(self.e, d.normalized() * T::one()))
+    }
+
+    // A pixel covers the same area everywhere on the cylinder, which is seen smaller and more
+    // inclined the farther it is above or below the eye.
+    fn solid_angle(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+    ) -> <T as Div>::Output {
+        let height = height(self.vertical_field_of_view, size, p);
+        let cos_psi =
+            <T as Div>::Output::one() / (<T as Div>::Output::one() + height * height).sqrt();
+
+        cos_psi * cos_psi * cos_psi
+    }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.shutter
+    }
+}
+
+// The height of a point of the image on a cylinder with a radius of one around the eye, from the
+// bottom to the top of the vertical field of view.
+fn height<V>(vertical_field_of_view: Radians<V>, size: Vector2<V>, p: Point2<V>) -> V
+where
+    V: FloatingPoint + ConvenientNumber,
+    Radians<V>: Tan<Output = V>,
+{
+    let half_height = size.y.half();
+    vertical_field_of_view.half().tan() * ((p.y - half_height) / half_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sampling::{RegularPatternGenerator, SamplingPatternSet};
+    use traits::ToRadians;
+    use units::angle::Degrees;
+
+    macro_rules! cylindrical_camera_ray_for {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let e = Point3::new(0 as $type, 1 as $type, 0 as $type);
+                let g = Vector3::new(0 as $type, 0 as $type, -1 as $type);
+                let t = Vector3::new(0 as $type, 1 as $type, 0 as $type);
+                let camera = CylindricalCamera::new(
+                    e,
+                    g,
+                    t,
+                    Degrees::<$type>::new(360.0).to_radians(),
+                    Degrees::<$type>::new(90.0).to_radians(),
+                );
+                let size = Vector2::new(800.0, 200.0);
+
+                let patterns = SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1);
+                let mut rnd = WichmannHillPRNG::from_seed(0);
+                let mut ray_for = |x: $type, y: $type| {
+                    camera
+                        .ray_for(size, Point2::new(x, y), &patterns[0], &mut rnd)
+                        .unwrap()
+                };
+                let assert_near = |a: Vector3<$type>, b: Vector3<$type>| {
+                    assert!((a - b).magnitude() < 0.0001, "{:?} != {:?}", a, b);
+                };
+
+                // The gaze direction in the center, a quarter turn for every quarter of the width
+                // and behind the camera on both edges.
+                let ray = ray_for(400.0, 100.0);
+                assert_eq!(ray.origin, e);
+                assert_near(ray.direction, Vector3::new(0.0, 0.0, -1.0));
+                assert_near(ray_for(600.0, 100.0).direction, Vector3::new(1.0, 0.0, 0.0));
+                assert_near(
+                    ray_for(200.0, 100.0).direction,
+                    Vector3::new(-1.0, 0.0, 0.0),
+                );
+                assert_near(ray_for(0.0, 100.0).direction, Vector3::new(0.0, 0.0, 1.0));
+                assert_near(ray_for(800.0, 100.0).direction, Vector3::new(0.0, 0.0, 1.0));
+
+                // The top and bottom edges are at half the vertical field of view, halfway up is
+                // at a height of half the top edge.
+                let half_sqrt = (0.5 as $type).sqrt();
+                assert_near(
+                    ray_for(400.0, 200.0).direction,
+                    Vector3::new(0.0, half_sqrt, -half_sqrt),
+                );
+                assert_near(
+                    ray_for(600.0, 0.0).direction,
+                    Vector3::new(half_sqrt, -half_sqrt, 0.0),
+                );
+                let up = ray_for(400.0, 150.0).direction;
+                assert!((up.y / -up.z - 0.5).abs() < 0.0001);
+
+                assert!((camera.solid_angle(size, Point2::new(400.0, 100.0)) - 1.0).abs() < 0.0001);
+                assert!(
+                    (camera.solid_angle(size, Point2::new(0.0, 200.0)) - half_sqrt.powi(3)).abs()
+                        < 0.0001
+                );
+            }
+        };
+    }
+
+    cylindrical_camera_ray_for! { f32, cylindrical_camera_ray_for_f32 }
+    cylindrical_camera_ray_for! { f64, cylindrical_camera_ray_for_f64 }
+}
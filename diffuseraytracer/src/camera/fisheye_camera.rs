@@ -1,10 +1,12 @@
 use std::ops::{Div, Mul};
 
-use cg_basics::camera::FisheyeCamera;
+use cg_basics::camera::{FisheyeCamera, Shutter};
 use math::geometry::ParametricLine;
 use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
 use sampling::SamplingPattern;
-use traits::{ConvenientNumber, Cos, FloatingPoint, Half, Min, Number, One, Sin};
+use traits::{ConvenientNumber, Cos, FloatingPoint, Half, Min, Number, One, Sin, Zero};
+use units::angle::Radians;
 
 use crate::camera::RaytracingCamera;
 
@@ -18,6 +20,7 @@ where
         size: Vector2<<T as Div>::Output>,
         p: Point2<<T as Div>::Output>,
         _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
     ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
         let half_size = size.half();
         let centerd_p = p - half_size;
@@ -39,4 +42,30 @@ where
             None
         }
     }
+
+    // The projection is equidistant, a ring at the angle psi from the view direction covers
+    // sin(psi) / psi times the solid angle of a ring of the same area in the center. Points
+    // outside of the circle get the weight of its rim.
+    fn solid_angle(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+    ) -> <T as Div>::Output {
+        let half_size = size.half();
+        let min_dim = half_size.x.min(half_size.y);
+        let r = ((p - half_size) / min_dim).as_vector().magnitude();
+        let r = if r > One::one() { One::one() } else { r };
+
+        let psi = self.psi * r;
+        let angle = psi / Radians::one();
+        if angle == Zero::zero() {
+            One::one()
+        } else {
+            psi.sin() / angle
+        }
+    }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.shutter
+    }
 }
@@ -0,0 +1,155 @@
+use std::ops::{Div, Mul};
+
+use cg_basics::camera::{FocusPull, FocusTarget, Shutter};
+use cg_basics::exposure::PhysicalExposure;
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
+use sampling::SamplingPattern;
+use traits::{ConvenientNumber, FloatingPoint, Number, SelfMulNumber, Sqrt};
+
+use crate::camera::RaytracingCamera;
+
+// Changes the focus of a camera over time. The focus is pulled once per frame, so an object the
+// camera focuses on stays sharp while it moves.
+pub struct FocusPulledCamera<T>
+where
+    T: Div,
+{
+    pub camera: Box<dyn RaytracingCamera<T>>,
+    pub pull: FocusPull<T>,
+}
+
+impl<T> FocusPulledCamera<T>
+where
+    T: Div,
+{
+    pub fn new(camera: Box<dyn RaytracingCamera<T>>, pull: FocusPull<T>) -> FocusPulledCamera<T> {
+        FocusPulledCamera { camera, pull }
+    }
+}
+
+impl<T> RaytracingCamera<T> for FocusPulledCamera<T>
+where
+    T: SelfMulNumber<<T as Div>::Output> + ConvenientNumber + Sync,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+{
+    fn ray_for(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+        pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        self.camera.ray_for(size, p, pattern, rnd)
+    }
+
+    fn solid_angle(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+    ) -> <T as Div>::Output {
+        self.camera.solid_angle(size, p)
+    }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.camera.shutter()
+    }
+
+    fn exposure(&self) -> Option<PhysicalExposure<<T as Div>::Output>> {
+        self.camera.exposure()
+    }
+
+    fn set_time(&mut self, time: <T as Div>::Output) {
+        self.camera.set_time(time);
+    }
+
+    fn focus_distance_to(&self, p: Point3<T>) -> Option<T> {
+        self.camera.focus_distance_to(p)
+    }
+
+    fn central_ray(&self) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        self.camera.central_ray()
+    }
+
+    fn set_focus_distance(&mut self, focus_distance: T) {
+        self.camera.set_focus_distance(focus_distance);
+    }
+
+    // Keeps the focus if an object can not be found.
+    fn pull_focus(&mut self, time: <T as Div>::Output, locate: &dyn Fn(&str) -> Option<Point3<T>>) {
+        let camera = &self.camera;
+        let focus_distance = self.pull.focus_distance(time, |target| match target {
+            FocusTarget::Distance(distance) => Some(*distance),
+            FocusTarget::Object(name) => camera.focus_distance_to(locate(name)?),
+        });
+
+        if let Some(focus_distance) = focus_distance {
+            self.camera.set_focus_distance(focus_distance);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cg_basics::camera::{FocusKeyframe, PerspectiveCamera};
+    use sampling::{RegularPatternGenerator, SamplingPatternSet};
+    use traits::ToRadians;
+    use units::angle::Degrees;
+
+    macro_rules! focus_pulled_camera_pull_focus {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let lens = PerspectiveCamera::new(
+                    Point3::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, -1.0),
+                    Vector3::new(0.0, 1.0, 0.0),
+                    Degrees::<$type>::new(90.0).to_radians(),
+                    0.5,
+                    1.0,
+                );
+                let pull = FocusPull::new(vec![
+                    FocusKeyframe::new(0.0, FocusTarget::Distance(1.0)),
+                    FocusKeyframe::new(1.0, FocusTarget::Object(String::from("ball"))),
+                ]);
+                let mut camera = FocusPulledCamera::new(Box::new(lens), pull);
+
+                let locate = |name: &str| match name {
+                    "ball" => Some(Point3::new(1.0, 0.0, -4.0)),
+                    _ => None,
+                };
+
+                let size = Vector2::new(640.0, 480.0);
+                let patterns = SamplingPatternSet::<Point2<$type>>::regular_pattern(4, 4);
+                let mut rnd = WichmannHillPRNG::from_seed(0);
+
+                // All rays through the center of the image meet in the focus.
+                let mut focused_at = |camera: &FocusPulledCamera<$type>, distance: $type| {
+                    (0..8).all(|_| {
+                        let ray = camera
+                            .ray_for(size, Point2::new(320.0, 240.0), &patterns[0], &mut rnd)
+                            .unwrap();
+                        let p = ray.origin + ray.direction * (distance / -ray.direction.z);
+                        p.x.abs() < 0.0001 && p.y.abs() < 0.0001
+                    })
+                };
+
+                camera.pull_focus(1.0, &locate);
+                assert!(focused_at(&camera, 4.0));
+
+                camera.pull_focus(0.5, &locate);
+                assert!(focused_at(&camera, 2.5));
+
+                camera.pull_focus(1.0, &|_| None);
+                assert!(focused_at(&camera, 2.5));
+            }
+        };
+    }
+
+    focus_pulled_camera_pull_focus! { f32, focus_pulled_camera_pull_focus_f32 }
+    focus_pulled_camera_pull_focus! { f64, focus_pulled_camera_pull_focus_f64 }
+}
@@ -0,0 +1,120 @@
+use std::ops::Div;
+
+use cg_basics::camera::Shutter;
+use cg_basics::exposure::PhysicalExposure;
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
+use sampling::SamplingPattern;
+use traits::{FloatingPoint, Zero};
+
+use crate::camera::RaytracingCamera;
+
+// Places a camera at the time of a frame of an animation. The shutter opens at the time of the
+// frame, so moving geometry is where it is at that time, even for a camera that stands still.
+pub struct FrameCamera<T>
+where
+    T: Div,
+{
+    pub camera: Box<dyn RaytracingCamera<T>>,
+    time: <T as Div>::Output,
+}
+
+impl<T> FrameCamera<T>
+where
+    T: Div,
+    <T as Div>::Output: Zero,
+{
+    pub fn new(camera: Box<dyn RaytracingCamera<T>>) -> FrameCamera<T> {
+        FrameCamera {
+            camera,
+            time: Zero::zero(),
+        }
+    }
+}
+
+impl<T> RaytracingCamera<T> for FrameCamera<T>
+where
+    T: Div + Sync,
+    <T as Div>::Output: FloatingPoint,
+{
+    fn ray_for(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+        pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        self.camera.ray_for(size, p, pattern, rnd)
+    }
+
+    fn solid_angle(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+    ) -> <T as Div>::Output {
+        self.camera.solid_angle(size, p)
+    }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        let shutter = self.camera.shutter();
+        Shutter::new(shutter.open + self.time, shutter.close + self.time)
+    }
+
+    fn exposure(&self) -> Option<PhysicalExposure<<T as Div>::Output>> {
+        self.camera.exposure()
+    }
+
+    fn set_time(&mut self, time: <T as Div>::Output) {
+        self.time = time;
+        self.camera.set_time(time);
+    }
+
+    fn focus_distance_to(&self, p: Point3<T>) -> Option<T> {
+        self.camera.focus_distance_to(p)
+    }
+
+    fn central_ray(&self) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        self.camera.central_ray()
+    }
+
+    fn set_focus_distance(&mut self, focus_distance: T) {
+        self.camera.set_focus_distance(focus_distance);
+    }
+
+    fn pull_focus(&mut self, time: <T as Div>::Output, locate: &dyn Fn(&str) -> Option<Point3<T>>) {
+        self.camera.pull_focus(time, locate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cg_basics::camera::PinholeCamera;
+    use traits::ToRadians;
+    use units::angle::Degrees;
+
+    macro_rules! frame_camera_shutter {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let pinhole = PinholeCamera::new(
+                    Point3::<$type>::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, -1.0),
+                    Vector3::new(0.0, 1.0, 0.0),
+                    Degrees::<$type>::new(90.0).to_radians(),
+                )
+                .with_shutter(Shutter::new(0.0, 0.5));
+                let mut camera = FrameCamera::new(Box::new(pinhole));
+
+                assert_eq!(camera.shutter(), Shutter::new(0.0, 0.5));
+                camera.set_time(2.0);
+                assert_eq!(camera.shutter(), Shutter::new(2.0, 2.5));
+            }
+        };
+    }
+
+    frame_camera_shutter! { f32, frame_camera_shutter_f32 }
+    frame_camera_shutter! { f64, frame_camera_shutter_f64 }
+}
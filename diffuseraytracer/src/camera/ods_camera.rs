@@ -0,0 +1,144 @@
+use std::ops::{Div, Mul};
+
+use cg_basics::camera::{Eye, OdsCamera, Shutter};
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
+use sampling::SamplingPattern;
+use traits::{
+    ConvenientNumber, Cos, FloatingPoint, Half, Number, One, SelfMulNumber, Sin, Sqrt, Zero,
+};
+use units::angle::{Angle, Radians};
+
+use crate::camera::RaytracingCamera;
+
+impl<T> RaytracingCamera<T> for OdsCamera<T>
+where
+    T: SelfMulNumber<<T as Div>::Output> + ConvenientNumber,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+    Radians<<T as Div>::Output>:
+        Angle + Cos<Output = <T as Div>::Output> + Sin<Output = <T as Div>::Output>,
+{
+    fn ray_for(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+        _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
+    ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        let (eye, latitude) = eye_and_latitude(size, p);
+
+        // The longitude runs from behind the camera on the left edge over the gaze direction in
+        // the center to behind the camera on the right edge.
+        let half_width = size.x.half();
+        let phi = Radians::half_turn() * ((p.x - half_width) / half_width);
+        let psi = Radians::quarter_turn() * latitude;
+
+        let forward = -self.w * phi.cos() + self.u * phi.sin();
+        let right = self.u * phi.cos() + self.w * phi.sin();
+
+        let offset = right * (self.interocular_distance * <T as Div>::Output::one().half());
+        let o = match eye {
+            Eye::Left => self.e - offset,
+            Eye::Right => self.e + offset,
+        };
+        let d = forward * psi.cos() + self.v * psi.sin();
+
+        Some(ParametricLine::new(o, d * T::one()))
+    }
+
+    // Rows of the panoramas are circles of latitude, their length shrinks with the cosine of the
+    // latitude.
+    fn solid_angle(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+    ) -> <T as Div>::Output {
+        let (_, latitude) = eye_and_latitude(size, p);
+        let weight = (Radians::quarter_turn() * latitude).cos();
+
+        if weight < Zero::zero() {
+            Zero::zero()
+        } else {
+            weight
+        }
+    }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.shutter
+    }
+}
+
+// The eye a point of the image belongs to and the latitude of the point, from -1 at the bottom to
+// 1 at the top of the panorama of the eye.
+fn eye_and_latitude<V>(size: Vector2<V>, p: Point2<V>) -> (Eye, V)
+where
+    V: FloatingPoint + ConvenientNumber,
+{
+    let half_height = size.y.half();
+    let quarter_height = half_height.half();
+    if p.y >= half_height {
+        (
+            Eye::Left,
+            (p.y - half_height - quarter_height) / quarter_height,
+        )
+    } else {
+        (Eye::Right, (p.y - quarter_height) / quarter_height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sampling::{RegularPatternGenerator, SamplingPatternSet};
+
+    macro_rules! ods_camera_ray_for {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let e = Point3::new(0 as $type, 1 as $type, 0 as $type);
+                let g = Vector3::new(0 as $type, 0 as $type, -1 as $type);
+                let t = Vector3::new(0 as $type, 1 as $type, 0 as $type);
+                let camera = OdsCamera::new(e, g, t, 0.5);
+                let size = Vector2::new(400.0, 400.0);
+
+                let patterns = SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1);
+                let mut rnd = WichmannHillPRNG::from_seed(0);
+                let mut ray_for = |x: $type, y: $type| {
+                    camera
+                        .ray_for(size, Point2::new(x, y), &patterns[0], &mut rnd)
+                        .unwrap()
+                };
+                let assert_near = |a: Vector3<$type>, b: Vector3<$type>| {
+                    assert!((a - b).magnitude() < 0.0001, "{:?} != {:?}", a, b);
+                };
+
+                // The centers of the panoramas of the left eye on top and the right eye below.
+                let left = ray_for(200.0, 300.0);
+                let right = ray_for(200.0, 100.0);
+                assert_near(left.direction, Vector3::new(0.0, 0.0, -1.0));
+                assert_near(right.direction, Vector3::new(0.0, 0.0, -1.0));
+                assert_near(left.origin - e, Vector3::new(-0.25, 0.0, 0.0));
+                assert_near(right.origin - e, Vector3::new(0.25, 0.0, 0.0));
+
+                // Turned to the right, the left eye is in front of the eye position.
+                let left = ray_for(300.0, 300.0);
+                assert_near(left.direction, Vector3::new(1.0, 0.0, 0.0));
+                assert_near(left.origin - e, Vector3::new(0.0, 0.0, -0.25));
+
+                // Behind the camera on both edges and straight up at the top.
+                assert_near(ray_for(0.0, 100.0).direction, Vector3::new(0.0, 0.0, 1.0));
+                assert_near(ray_for(400.0, 100.0).direction, Vector3::new(0.0, 0.0, 1.0));
+                assert_near(ray_for(200.0, 400.0).direction, Vector3::new(0.0, 1.0, 0.0));
+
+                assert!((camera.solid_angle(size, Point2::new(200.0, 300.0)) - 1.0).abs() < 0.0001);
+                assert!(camera.solid_angle(size, Point2::new(200.0, 200.0)).abs() < 0.0001);
+            }
+        };
+    }
+
+    ods_camera_ray_for! { f32, ods_camera_ray_for_f32 }
+    ods_camera_ray_for! { f64, ods_camera_ray_for_f64 }
+}
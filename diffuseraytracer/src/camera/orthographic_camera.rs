@@ -1,8 +1,9 @@
 use std::ops::{Add, Div, Mul, Neg, Sub};
 
-use cg_basics::camera::OrthographicCamera;
+use cg_basics::camera::{OrthographicCamera, Shutter};
 use math::geometry::ParametricLine;
 use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
 use sampling::SamplingPattern;
 use traits::{Half, One};
 
@@ -10,19 +11,27 @@ use crate::camera::RaytracingCamera;
 
 impl<T> RaytracingCamera<T> for OrthographicCamera<T>
 where
-    T: Add<Output = T> + Div + Mul<<T as Div>::Output, Output = T> + Neg<Output = T> + One + Copy,
+    T: Add<Output = T>
+        + Div
+        + Mul<<T as Div>::Output, Output = T>
+        + Neg<Output = T>
+        + One
+        + Copy
+        + Sync,
     <T as Div>::Output: Div<Output = <T as Div>::Output>
         + Half
         + Mul<T, Output = T>
         + Mul<<T as Div>::Output, Output = <T as Div>::Output>
         + Sub<Output = <T as Div>::Output>
-        + Copy,
+        + Copy
+        + Sync,
 {
     fn ray_for(
         &self,
         size: Vector2<<T as Div>::Output>,
         p: Point2<<T as Div>::Output>,
         _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
     ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
         let aspect_ratio = size.x / size.y;
 
@@ -37,6 +46,10 @@ where
 
         Some(ParametricLine::new(o, d))
     }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.shutter
+    }
 }
 
 #[cfg(test)]
@@ -67,25 +80,26 @@ mod tests {
                     ParametricLine::new(Point3::new(3 as $type, 242.0 as $type, 321 as $type), g);
 
                 let patterns = SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1);
+                let mut rnd = WichmannHillPRNG::from_seed(0);
 
                 assert_eq!(
-                    orth.ray_for(size, Point2::new(320.0, 240.0), &patterns[0]),
+                    orth.ray_for(size, Point2::new(320.0, 240.0), &patterns[0], &mut rnd),
                     Some(center)
                 );
                 assert_eq!(
-                    orth.ray_for(size, Point2::new(0.0, 480.0), &patterns[0]),
+                    orth.ray_for(size, Point2::new(0.0, 480.0), &patterns[0], &mut rnd),
                     Some(upper_left)
                 );
                 assert_eq!(
-                    orth.ray_for(size, Point2::new(0.0, 0.0), &patterns[0]),
+                    orth.ray_for(size, Point2::new(0.0, 0.0), &patterns[0], &mut rnd),
                     Some(lower_left)
                 );
                 assert_eq!(
-                    orth.ray_for(size, Point2::new(640.0, 0.0), &patterns[0]),
+                    orth.ray_for(size, Point2::new(640.0, 0.0), &patterns[0], &mut rnd),
                     Some(lower_right)
                 );
                 assert_eq!(
-                    orth.ray_for(size, Point2::new(640.0, 480.0), &patterns[0]),
+                    orth.ray_for(size, Point2::new(640.0, 480.0), &patterns[0], &mut rnd),
                     Some(upper_right)
                 );
             }
@@ -1,6 +1,6 @@
 use std::ops::{Div, Mul};
 
-use cg_basics::camera::PerspectiveCamera;
+use cg_basics::camera::{PerspectiveCamera, Shutter};
 
 use math::geometry::ParametricLine;
 use math::{Point2, Point3, Vector2, Vector3};
@@ -15,12 +15,14 @@ where
     T: SelfMulNumber<<T as Div>::Output>,
     <T as Div>::Output: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
     <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+    u16: Into<<T as Div>::Output>,
 {
     fn ray_for(
         &self,
         size: Vector2<<T as Div>::Output>,
         p: Point2<<T as Div>::Output>,
         pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
     ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
         let o = self.e;
 
@@ -28,16 +30,19 @@ where
 
         let focal_length_factor = (self.focal_length / T::one()) / unit_plane_distance;
 
+        let offset = self.distortion.undistort(
+            Point2::new(p.x - size.x.half(), p.y - size.y.half()),
+            unit_plane_distance,
+        )?;
+
         let a = -self.w * (self.focal_length / T::one());
-        let b = self.u * (p.x - size.x.half()) * focal_length_factor;
-        let c = self.v * (p.y - size.y.half()) * focal_length_factor;
+        let b = self.u * offset.x * focal_length_factor;
+        let c = self.v * offset.y * focal_length_factor;
 
         let r = a + b + c;
         let fp = o + r * T::one();
 
-        let mut rnd = WichmannHillPRNG::new_random();
-
-        let sampling_point = pattern.draw_point(&mut rnd);
+        let sampling_point = self.aperture.map(*pattern.draw_point(rnd));
         let lo = o
             + self.u * sampling_point.x * self.lens_radius
             + self.v * sampling_point.y * self.lens_radius;
@@ -46,4 +51,20 @@ where
 
         Some(ParametricLine::new(lo, direction))
     }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.shutter
+    }
+
+    fn focus_distance_to(&self, p: Point3<T>) -> Option<T> {
+        Some(((p - self.e) / T::one()).dot(-self.w) * T::one())
+    }
+
+    fn central_ray(&self) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        Some(ParametricLine::new(self.e, -self.w * T::one()))
+    }
+
+    fn set_focus_distance(&mut self, focus_distance: T) {
+        self.focal_length = focus_distance;
+    }
 }
@@ -0,0 +1,49 @@
+use std::ops::{Div, Mul};
+
+use cg_basics::camera::{PhysicalCamera, Shutter};
+use cg_basics::exposure::PhysicalExposure;
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
+use sampling::SamplingPattern;
+use traits::{ConvenientNumber, FloatingPoint, Number, SelfMulNumber, Sqrt};
+
+use crate::camera::RaytracingCamera;
+
+impl<T> RaytracingCamera<T> for PhysicalCamera<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+    u16: Into<<T as Div>::Output>,
+{
+    fn ray_for(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+        pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        self.lens.ray_for(size, p, pattern, rnd)
+    }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.lens.shutter
+    }
+
+    fn exposure(&self) -> Option<PhysicalExposure<<T as Div>::Output>> {
+        Some(PhysicalCamera::exposure(self))
+    }
+
+    fn focus_distance_to(&self, p: Point3<T>) -> Option<T> {
+        self.lens.focus_distance_to(p)
+    }
+
+    fn central_ray(&self) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        self.lens.central_ray()
+    }
+
+    fn set_focus_distance(&mut self, focus_distance: T) {
+        self.lens.set_focus_distance(focus_distance);
+    }
+}
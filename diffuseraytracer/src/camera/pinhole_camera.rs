@@ -1,8 +1,9 @@
 use std::ops::{Div, Mul};
 
-use cg_basics::camera::PinholeCamera;
+use cg_basics::camera::{PinholeCamera, Shutter};
 use math::geometry::ParametricLine;
 use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
 use sampling::SamplingPattern;
 use traits::{ConvenientNumber, FloatingPoint, Half, Number, SelfMulNumber, Sqrt, Tan};
 
@@ -19,18 +20,29 @@ where
         size: Vector2<<T as Div>::Output>,
         p: Point2<<T as Div>::Output>,
         _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
     ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
         let o = self.e;
 
-        let a = -self.w * (size.y.half() / self.vertical_field_of_view.tan());
-        let b = self.u * (p.x - size.x.half());
-        let c = self.v * (p.y - size.y.half());
+        let distance = size.y.half() / self.vertical_field_of_view.tan();
+        let offset = self.distortion.undistort(
+            Point2::new(p.x - size.x.half(), p.y - size.y.half()),
+            distance,
+        )?;
+
+        let a = -self.w * distance;
+        let b = self.u * offset.x;
+        let c = self.v * offset.y;
 
         let r = a + b + c;
         let d = r.normalized() * T::one();
 
         Some(ParametricLine::new(o, d))
     }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.shutter
+    }
 }
 
 #[cfg(test)]
@@ -77,25 +89,26 @@ mod tests {
                 );
 
                 let patterns = SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1);
+                let mut rnd = WichmannHillPRNG::from_seed(0);
 
                 assert_eq!(
-                    persp.ray_for(size, Point2::new(320.0, 240.0), &patterns[0]),
+                    persp.ray_for(size, Point2::new(320.0, 240.0), &patterns[0], &mut rnd),
                     Some(center)
                 );
                 assert_eq!(
-                    persp.ray_for(size, Point2::new(0.0, 480.0), &patterns[0]),
+                    persp.ray_for(size, Point2::new(0.0, 480.0), &patterns[0], &mut rnd),
                     Some(upper_left)
                 );
                 assert_eq!(
-                    persp.ray_for(size, Point2::new(0.0, 0.0), &patterns[0]),
+                    persp.ray_for(size, Point2::new(0.0, 0.0), &patterns[0], &mut rnd),
                     Some(lower_left)
                 );
                 assert_eq!(
-                    persp.ray_for(size, Point2::new(640.0, 0.0), &patterns[0]),
+                    persp.ray_for(size, Point2::new(640.0, 0.0), &patterns[0], &mut rnd),
                     Some(lower_right)
                 );
                 assert_eq!(
-                    persp.ray_for(size, Point2::new(640.0, 480.0), &patterns[0]),
+                    persp.ray_for(size, Point2::new(640.0, 480.0), &patterns[0], &mut rnd),
                     Some(upper_right)
                 );
             }
@@ -104,4 +117,43 @@ mod tests {
 
     pinhole_camera_ray_for! { f32, pinhole_camera_ray_for_f32 }
     pinhole_camera_ray_for! { f64, pinhole_camera_ray_for_f64 }
+
+    macro_rules! pinhole_camera_distorted_ray_for {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                use cg_basics::camera::LensDistortion;
+
+                let e = Point3::new(1 as $type, 2 as $type, 3 as $type);
+                let g = Vector3::new(0 as $type, 0 as $type, -1 as $type);
+                let t = Vector3::new(0 as $type, 1 as $type, 0 as $type);
+
+                let fov = Degrees::<$type>::new(90.0).to_radians();
+                let size = Vector2::new(640.0, 480.0);
+
+                let ideal = PinholeCamera::new(e, g, t, fov);
+                let barrel = PinholeCamera::new(e, g, t, fov)
+                    .with_distortion(LensDistortion::new(-0.05, 0.0, 0.0, 0.0));
+
+                let patterns = SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1);
+                let mut rnd = WichmannHillPRNG::from_seed(0);
+                let mut ray_for = |camera: &PinholeCamera<$type>, p: Point2<$type>| {
+                    camera.ray_for(size, p, &patterns[0], &mut rnd).unwrap()
+                };
+
+                let center = Point2::new(320.0, 240.0);
+                assert_eq!(ray_for(&barrel, center), ray_for(&ideal, center));
+
+                // The lens squeezes the edges of the image, so they show more of the scene.
+                let corner = Point2::new(560.0, 420.0);
+                let ideal_corner = ray_for(&ideal, corner).direction;
+                let barrel_corner = ray_for(&barrel, corner).direction;
+                assert!(barrel_corner.x / -barrel_corner.z > ideal_corner.x / -ideal_corner.z);
+                assert!(barrel_corner.y / -barrel_corner.z > ideal_corner.y / -ideal_corner.z);
+            }
+        };
+    }
+
+    pinhole_camera_distorted_ray_for! { f32, pinhole_camera_distorted_ray_for_f32 }
+    pinhole_camera_distorted_ray_for! { f64, pinhole_camera_distorted_ray_for_f64 }
 }
@@ -1,10 +1,11 @@
 use std::ops::{Div, Mul};
 
-use cg_basics::camera::SphericalCamera;
+use cg_basics::camera::{Shutter, SphericalCamera};
 use math::geometry::ParametricLine;
 use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
 use sampling::SamplingPattern;
-use traits::{ConvenientNumber, Cos, FloatingPoint, Half, Min, Number, Sin};
+use traits::{ConvenientNumber, Cos, FloatingPoint, Half, Min, Number, Sin, Zero};
 use units::angle::{Angle, Radians};
 
 use crate::camera::RaytracingCamera;
@@ -21,6 +22,7 @@ where
         size: Vector2<<T as Div>::Output>,
         p: Point2<<T as Div>::Output>,
         _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
     ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
         let half_size = size.half();
         let centerd_p = p - half_size;
@@ -44,4 +46,27 @@ where
 
         Some(ParametricLine::new(self.e, direction * T::one()))
     }
+
+    // Rows of the image are circles of latitude, their length shrinks with the cosine of the
+    // latitude.
+    fn solid_angle(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+    ) -> <T as Div>::Output {
+        let half_size = size.half();
+        let min_dim = half_size.x.min(half_size.y);
+        let psi = self.vertical_field_of_view * ((p.y - half_size.y) / min_dim);
+        let weight = psi.cos();
+
+        if weight < Zero::zero() {
+            Zero::zero()
+        } else {
+            weight
+        }
+    }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.shutter
+    }
 }
@@ -0,0 +1,94 @@
+use std::ops::{Div, Mul};
+
+use cg_basics::camera::{Shutter, StereoCamera};
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
+use sampling::SamplingPattern;
+use traits::{ConvenientNumber, FloatingPoint, Half, Number, SelfMulNumber, Sqrt, Tan};
+
+use crate::camera::RaytracingCamera;
+
+impl<T> RaytracingCamera<T> for StereoCamera<T>
+where
+    T: SelfMulNumber<<T as Div>::Output> + ConvenientNumber,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Mul>::Output: Number<<T as Div>::Output> + ConvenientNumber + Sqrt<Output = T>,
+{
+    fn ray_for(
+        &self,
+        size: Vector2<<T as Div>::Output>,
+        p: Point2<<T as Div>::Output>,
+        _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
+    ) -> Option<ParametricLine<Point3<T>, Vector3<T>>> {
+        let o = self.eye_position();
+
+        let distance = size.y.half() / self.vertical_field_of_view.tan();
+        let a = -self.w * distance;
+        let b = self.u * (p.x - size.x.half());
+        let c = self.v * (p.y - size.y.half());
+
+        // The point seen through p on the plane at the convergence distance, as seen from the
+        // center between both eyes.
+        let target = self.e + (a + b + c) * (self.convergence_distance / distance);
+        let d = (target - o).normalized() * T::one();
+
+        Some(ParametricLine::new(o, d))
+    }
+
+    fn shutter(&self) -> Shutter<<T as Div>::Output> {
+        self.shutter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cg_basics::camera::Eye;
+    use sampling::{RegularPatternGenerator, SamplingPatternSet};
+    use traits::ToRadians;
+    use units::angle::Degrees;
+
+    macro_rules! stereo_camera_ray_for {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let e = Point3::new(0 as $type, 1 as $type, 0 as $type);
+                let g = Vector3::new(0 as $type, 0 as $type, -1 as $type);
+                let t = Vector3::new(0 as $type, 1 as $type, 0 as $type);
+                let fov = Degrees::<$type>::new(90.0).to_radians();
+                let size = Vector2::new(640.0, 480.0);
+
+                let patterns = SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1);
+                let mut rnd = WichmannHillPRNG::from_seed(0);
+
+                let mut ray_for = |eye: Eye, p: Point2<$type>| {
+                    StereoCamera::new(e, g, t, fov, 0.5, 4.0, eye)
+                        .ray_for(size, p, &patterns[0], &mut rnd)
+                        .unwrap()
+                };
+
+                // Both eyes see the same point at the convergence distance.
+                for p in [Point2::new(320.0, 240.0), Point2::new(0.0, 480.0)] {
+                    let left = ray_for(Eye::Left, p);
+                    let right = ray_for(Eye::Right, p);
+
+                    assert_eq!(left.origin, Point3::new(-0.25, 1.0, 0.0));
+                    assert_eq!(right.origin, Point3::new(0.25, 1.0, 0.0));
+
+                    let t = -4.0 / left.direction.z;
+                    let u = -4.0 / right.direction.z;
+                    let left_target = left.origin + left.direction * t;
+                    let right_target = right.origin + right.direction * u;
+                    assert!((left_target.x - right_target.x).abs() < 0.0001);
+                    assert!((left_target.y - right_target.y).abs() < 0.0001);
+                }
+            }
+        };
+    }
+
+    stereo_camera_ray_for! { f32, stereo_camera_ray_for_f32 }
+    stereo_camera_ray_for! { f64, stereo_camera_ray_for_f64 }
+}
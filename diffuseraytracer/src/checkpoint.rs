@@ -0,0 +1,198 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use colors::{Color, RGB};
+use image::accumulation_buffer::{AccumulationBuffer, CompensatedSum};
+use image::Image;
+use math::{Point2, Vector2};
+
+const MAGIC: &[u8; 16] = b"rustracer ckpt 1";
+const HEADER_SIZE: usize = MAGIC.len() + 4 + 4 + 4 + 16 + 8;
+
+// The state of an interrupted progressive render: the passes that are done and the samples they
+// added up to. A checkpoint only fits a render of the same size and seed, so a resumed render
+// continues with the samples the interrupted one would have drawn next.
+pub struct Checkpoint<C: Color> {
+    pub seed: u128,
+    pub passes: usize,
+    pub image: AccumulationBuffer<C>,
+}
+
+// The file starts with the magic bytes, the size of a channel in bytes, the width and height, the
+// seed and the number of passes. Every pixel follows in rows from the top, with the sum, the
+// compensation of the sum and the number of samples, all in big endian.
+macro_rules! implement_checkpoint_for {
+    ($($type: ty)*) => {$(
+        impl Checkpoint<RGB<$type>> {
+            pub fn encode(&self) -> Vec<u8> {
+                let size = self.image.size();
+                let mut data = Vec::with_capacity(HEADER_SIZE + size.x * size.y * 7 * size_of::<$type>());
+                data.extend_from_slice(MAGIC);
+                data.extend_from_slice(&(size_of::<$type>() as u32).to_be_bytes());
+                data.extend_from_slice(&(size.x as u32).to_be_bytes());
+                data.extend_from_slice(&(size.y as u32).to_be_bytes());
+                data.extend_from_slice(&self.seed.to_be_bytes());
+                data.extend_from_slice(&(self.passes as u64).to_be_bytes());
+
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let p = Point2::new(x, y);
+                        let sum = self.image.sum(p);
+                        for color in [sum.value(), sum.compensation()] {
+                            for channel in [color.red, color.green, color.blue] {
+                                data.extend_from_slice(&channel.to_be_bytes());
+                            }
+                        }
+                        data.extend_from_slice(&self.image.samples(p).to_be_bytes());
+                    }
+                }
+                data
+            }
+
+            pub fn decode(data: &[u8]) -> Result<Checkpoint<RGB<$type>>, String> {
+                let length = data.len();
+                let mut data = data;
+                let mut take = |n: usize| -> Result<&[u8], String> {
+                    if data.len() < n {
+                        return Err(String::from("The checkpoint is truncated."));
+                    }
+                    let (taken, rest) = data.split_at(n);
+                    data = rest;
+                    Ok(taken)
+                };
+
+                if take(MAGIC.len())? != MAGIC {
+                    return Err(String::from("The file is not a checkpoint."));
+                }
+                let channel_size = u32::from_be_bytes(take(4)?.try_into().unwrap());
+                if channel_size as usize != size_of::<$type>() {
+                    return Err(format!(
+                        "The checkpoint has channels of {} bytes instead of {}.",
+                        channel_size,
+                        size_of::<$type>()
+                    ));
+                }
+                let width = u32::from_be_bytes(take(4)?.try_into().unwrap()) as usize;
+                let height = u32::from_be_bytes(take(4)?.try_into().unwrap()) as usize;
+                let seed = u128::from_be_bytes(take(16)?.try_into().unwrap());
+                let passes = u64::from_be_bytes(take(8)?.try_into().unwrap()) as usize;
+
+                // The size is checked against the pixels that follow before the image is
+                // allocated.
+                let pixels = width
+                    .checked_mul(height)
+                    .and_then(|pixels| pixels.checked_mul(7 * size_of::<$type>()));
+                if pixels != Some(length - HEADER_SIZE) {
+                    return Err(format!(
+                        "The checkpoint does not hold the pixels of {}x{}.",
+                        width, height
+                    ));
+                }
+
+                let mut image = AccumulationBuffer::new(Vector2::new(width, height));
+                let mut value = || -> Result<$type, String> {
+                    Ok(<$type>::from_be_bytes(
+                        take(size_of::<$type>())?.try_into().unwrap(),
+                    ))
+                };
+                for y in 0..height {
+                    for x in 0..width {
+                        let sum = RGB::new(value()?, value()?, value()?);
+                        let compensation = RGB::new(value()?, value()?, value()?);
+                        let samples = value()?;
+                        image.set(
+                            Point2::new(x, y),
+                            CompensatedSum::from_parts(sum, compensation),
+                            samples,
+                        );
+                    }
+                }
+
+                Ok(Checkpoint {
+                    seed,
+                    passes,
+                    image,
+                })
+            }
+
+            // The checkpoint is written to a temporary file first, so an interruption while
+            // saving keeps the previous one.
+            pub fn save(&self, path: &Path) -> io::Result<()> {
+                let temporary = path.with_extension("tmp");
+                fs::write(&temporary, self.encode())?;
+                fs::rename(&temporary, path)
+            }
+
+            pub fn load(path: &Path) -> Result<Checkpoint<RGB<$type>>, String> {
+                match fs::read(path) {
+                    Ok(data) => Self::decode(&data),
+                    Err(m) => Err(format!("Unable to read checkpoint {}: {}", path.display(), m)),
+                }
+            }
+        }
+    )*}
+}
+
+implement_checkpoint_for! { f32 f64 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! checkpoint_round_trip {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut image = AccumulationBuffer::<RGB<$type>>::new(Vector2::new(3, 2));
+                for i in 0..5 {
+                    image.add_sample(Point2::new(2, 1), RGB::new(0.1 * i as $type, 1.0, 1e-9));
+                }
+                image.add_sample(Point2::new(0, 0), RGB::new(0.5, 0.25, 0.125));
+
+                let checkpoint = Checkpoint {
+                    seed: 1 << 100,
+                    passes: 5,
+                    image,
+                };
+                let data = checkpoint.encode();
+                let decoded = Checkpoint::<RGB<$type>>::decode(&data).unwrap();
+
+                assert_eq!(decoded.seed, 1 << 100);
+                assert_eq!(decoded.passes, 5);
+                assert_eq!(decoded.image.size(), Vector2::new(3, 2));
+                for (x, y) in [(0, 0), (1, 0), (2, 1)] {
+                    let p = Point2::new(x, y);
+                    assert_eq!(decoded.image.sum(p), checkpoint.image.sum(p));
+                    assert_eq!(decoded.image.samples(p), checkpoint.image.samples(p));
+                }
+
+                assert!(Checkpoint::<RGB<$type>>::decode(&data[..data.len() - 1]).is_err());
+                assert!(Checkpoint::<RGB<$type>>::decode(&[&data[..], &[0]].concat()).is_err());
+
+                // A size beyond the data is rejected instead of allocated.
+                let mut huge = data.clone();
+                huge[20..28].copy_from_slice(&[0xff; 8]);
+                assert!(Checkpoint::<RGB<$type>>::decode(&huge).is_err());
+                assert!(Checkpoint::<RGB<$type>>::decode(b"not a checkpoint at all").is_err());
+            }
+        };
+    }
+
+    checkpoint_round_trip! { f32, checkpoint_round_trip_f32 }
+    checkpoint_round_trip! { f64, checkpoint_round_trip_f64 }
+
+    #[test]
+    fn checkpoint_keeps_precision() {
+        let mut image = AccumulationBuffer::<RGB<f64>>::new(Vector2::new(1, 1));
+        image.add_sample(Point2::new(0, 0), RGB::new(0.1, 0.2, 0.3));
+        let checkpoint = Checkpoint {
+            seed: 0,
+            passes: 1,
+            image,
+        };
+
+        // A checkpoint of another precision is rejected instead of misread.
+        assert!(Checkpoint::<RGB<f32>>::decode(&checkpoint.encode()).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use colors::Color;
+use image::{Image, ImageBuffer, WritableImage};
+use math::{Point2, Vector2, Vector3};
+use traits::{Cos, FloatingPoint, Floor};
+
+// How the lines of a technical illustration are drawn over a render. Silhouettes, where a
+// geometry ends in front of the background or another geometry, are always drawn. Isolines of the
+// depth are drawn at each multiple of the depth interval, creases where the normals of neighboring
+// pixels differ by more than the crease angle.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ContourStyle<C: Color> {
+    pub line_color: C,
+    pub paper_color: C,
+    pub depth_interval: Option<C::ChannelType>,
+    // In radians.
+    pub crease_angle: Option<C::ChannelType>,
+}
+
+impl<C: Color> ContourStyle<C> {
+    pub fn new(line_color: C, paper_color: C) -> ContourStyle<C> {
+        ContourStyle {
+            line_color,
+            paper_color,
+            depth_interval: None,
+            crease_angle: None,
+        }
+    }
+
+    pub fn with_depth_interval(self, depth_interval: C::ChannelType) -> ContourStyle<C> {
+        ContourStyle {
+            depth_interval: Some(depth_interval),
+            ..self
+        }
+    }
+
+    pub fn with_crease_angle(self, crease_angle: C::ChannelType) -> ContourStyle<C> {
+        ContourStyle {
+            crease_angle: Some(crease_angle),
+            ..self
+        }
+    }
+}
+
+// What the ray through the center of a pixel hits first.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SurfaceSample<V> {
+    // The distance from the camera.
+    pub depth: V,
+    pub normal: Vector3<V>,
+    // The index of the geometry in the scene.
+    pub geometry: usize,
+}
+
+pub struct Contours<C: Color> {
+    pub beauty: ImageBuffer<C>,
+    pub style: ContourStyle<C>,
+    lines: Vec<bool>,
+    size: Vector2<usize>,
+}
+
+impl<C: Color> Contours<C>
+where
+    C::ChannelType: FloatingPoint,
+{
+    pub fn new(
+        beauty: ImageBuffer<C>,
+        surfaces: &[Option<SurfaceSample<C::ChannelType>>],
+        style: ContourStyle<C>,
+    ) -> Contours<C> {
+        let size = beauty.size();
+        assert_eq!(surfaces.len(), size.x * size.y);
+
+        // A line is drawn on the pixel in front, so it does not spill onto the background.
+        let mut lines = vec![false; size.x * size.y];
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let index = y * size.x + x;
+                for neighbor in [
+                    (x + 1 < size.x, index + 1),
+                    (y + 1 < size.y, index + size.x),
+                ] {
+                    let (true, neighbor) = neighbor else {
+                        continue;
+                    };
+                    if let Some(front) = separated(surfaces[index], surfaces[neighbor], &style) {
+                        lines[if front { index } else { neighbor }] = true;
+                    }
+                }
+            }
+        }
+
+        Contours {
+            beauty,
+            style,
+            lines,
+            size,
+        }
+    }
+
+    pub fn is_line(&self, p: Point2<usize>) -> bool {
+        self.lines[p.y * self.size.x + p.x]
+    }
+
+    // The lines drawn over the render.
+    pub fn overlaid(&self) -> ImageBuffer<C> {
+        self.draw(|p| self.beauty.get(p))
+    }
+
+    // The lines alone, drawn on the paper color.
+    pub fn drawing(&self) -> ImageBuffer<C> {
+        self.draw(|_| self.style.paper_color)
+    }
+
+    fn draw(&self, background: impl Fn(Point2<usize>) -> C) -> ImageBuffer<C> {
+        let mut image_buffer = ImageBuffer::new(self.size, C::default());
+        for y in 0..self.size.y {
+            for x in 0..self.size.x {
+                let p = Point2::new(x, y);
+                *image_buffer.get_mut(p) = if self.is_line(p) {
+                    self.style.line_color
+                } else {
+                    background(p)
+                };
+            }
+        }
+        image_buffer
+    }
+}
+
+// Whether a line runs between two neighboring pixels. If so, tells if the first one is in front.
+fn separated<C: Color>(
+    a: Option<SurfaceSample<C::ChannelType>>,
+    b: Option<SurfaceSample<C::ChannelType>>,
+    style: &ContourStyle<C>,
+) -> Option<bool>
+where
+    C::ChannelType: FloatingPoint,
+{
+    match (a, b) {
+        (None, None) => None,
+        (Some(_), None) => Some(true),
+        (None, Some(_)) => Some(false),
+        (Some(a), Some(b)) => {
+            let front = a.depth <= b.depth;
+            let silhouette = a.geometry != b.geometry;
+            let isoline = style.depth_interval.is_some_and(|interval| {
+                (a.depth / interval).floor() != (b.depth / interval).floor()
+            });
+            let crease = style
+                .crease_angle
+                .is_some_and(|angle| a.normal.dot(b.normal) < angle.cos());
+
+            (silhouette || isoline || crease).then_some(front)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::RGB;
+
+    macro_rules! contours_draw_lines {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let floor = |depth: $type| {
+                    Some(SurfaceSample {
+                        depth,
+                        normal: Vector3::new(0.0, 1.0, 0.0),
+                        geometry: 0,
+                    })
+                };
+                let wall = Some(SurfaceSample {
+                    depth: 2.5,
+                    normal: Vector3::new(1.0, 0.0, 0.0),
+                    geometry: 0,
+                });
+                let sphere = Some(SurfaceSample {
+                    depth: 1.0,
+                    normal: Vector3::new(0.0, 0.0, 1.0),
+                    geometry: 1,
+                });
+
+                // A single row of pixels.
+                let surfaces = [
+                    None,
+                    floor(1.5),
+                    floor(1.8),
+                    floor(2.2),
+                    wall,
+                    sphere,
+                    floor(3.0),
+                ];
+                let beauty =
+                    || ImageBuffer::new(Vector2::new(7, 1), RGB::<$type>::new(0.5, 0.5, 0.5));
+                let line_color = RGB::new(0.0, 0.0, 0.0);
+                let paper_color = RGB::new(1.0, 1.0, 1.0);
+                let is_line = |contours: &Contours<RGB<$type>>| -> Vec<bool> {
+                    (0..7)
+                        .map(|x| contours.is_line(Point2::new(x, 0)))
+                        .collect()
+                };
+
+                let silhouettes = Contours::new(
+                    beauty(),
+                    &surfaces,
+                    ContourStyle::new(line_color, paper_color),
+                );
+                assert_eq!(
+                    is_line(&silhouettes),
+                    vec![false, true, false, false, false, true, false]
+                );
+
+                let isolines = Contours::new(
+                    beauty(),
+                    &surfaces,
+                    ContourStyle::new(line_color, paper_color).with_depth_interval(1.0),
+                );
+                assert_eq!(
+                    is_line(&isolines),
+                    vec![false, true, true, false, false, true, false]
+                );
+
+                let creases = Contours::new(
+                    beauty(),
+                    &surfaces,
+                    ContourStyle::new(line_color, paper_color)
+                        .with_crease_angle(std::f64::consts::FRAC_PI_4 as $type),
+                );
+                assert_eq!(
+                    is_line(&creases),
+                    vec![false, true, false, true, false, true, false]
+                );
+
+                assert_eq!(creases.overlaid().get(Point2::new(3, 0)), line_color);
+                assert_eq!(
+                    creases.overlaid().get(Point2::new(4, 0)),
+                    RGB::new(0.5, 0.5, 0.5)
+                );
+                assert_eq!(creases.drawing().get(Point2::new(1, 0)), line_color);
+                assert_eq!(creases.drawing().get(Point2::new(0, 0)), paper_color);
+            }
+        };
+    }
+
+    contours_draw_lines! { f32, contours_draw_lines_f32 }
+    contours_draw_lines! { f64, contours_draw_lines_f64 }
+}
@@ -0,0 +1,153 @@
+use cg_basics::scene_graph::Scene3;
+use colors::{Color, Gray};
+use image::{ImageBuffer, WritableImage};
+use math::{Point2, Vector2};
+use random::WichmannHillPRNG;
+use sampling::SamplingPatternSet;
+use traits::{ConvenientNumber, FloatingPoint, Zero};
+use units::length::Length;
+
+use crate::camera::RaytracingCamera;
+use crate::diffuse_ray_tracer::render_tiles;
+use crate::light::Light;
+use crate::Renderable;
+
+type SceneType<T, C> =
+    Scene3<C, Box<dyn Light<T, C>>, Box<dyn RaytracingCamera<T>>, Box<dyn Renderable<T, C>>>;
+
+// The fraction of the camera rays through every pixel that hit a geometry, the alpha of renders
+// that are composited over other backgrounds. The rays are spread over the pixel, the lens and the
+// shutter interval by the sampling patterns like those of the renderers, so edges, blurred
+// geometry and the edge of a fisheye are partly covered.
+pub fn render_coverage<T: Length, C>(
+    scene: &SceneType<T, C>,
+    camera_id: &str,
+    size: Vector2<usize>,
+    sampling_patterns: &SamplingPatternSet<Point2<T::ValueType>>,
+    seed: u128,
+    threads: usize,
+) -> ImageBuffer<Gray<T::ValueType>>
+where
+    C: Color<ChannelType = T::ValueType>,
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    u16: Into<T::ValueType>,
+{
+    let camera = scene.cameras[camera_id].as_ref();
+    let float_size = Vector2::<T::ValueType>::new((size.x as u16).into(), (size.y as u16).into());
+
+    let mut image = ImageBuffer::new(size, Gray::new(Zero::zero()));
+    for (p, coverage) in render_tiles(threads, 16, size, None, |origin, extent| {
+        let mut covered = Vec::with_capacity(extent.x * extent.y);
+        for y in origin.y..(origin.y + extent.y) {
+            for x in origin.x..(origin.x + extent.x) {
+                let mut rnd = WichmannHillPRNG::for_index(seed, (y * size.x + x) as u128);
+                let pixel = Point2::<T::ValueType>::new(
+                    (x as u16).into(),
+                    ((size.y - y - 1) as u16).into(),
+                );
+
+                let mut hits = T::ValueType::zero();
+                let mut weights = T::ValueType::zero();
+                let pattern = sampling_patterns.draw_pattern(&mut rnd);
+                for i in 0..pattern.len() {
+                    let sp = pixel + pattern[i].as_vector();
+                    let weight = camera.solid_angle(float_size, sp);
+                    let lens_pattern = sampling_patterns.draw_pattern(&mut rnd);
+                    let time_pattern = sampling_patterns.draw_pattern(&mut rnd);
+                    let time = camera.shutter().time(time_pattern.draw_point(&mut rnd).x);
+                    let hit = camera
+                        .ray_for(float_size, sp, lens_pattern, &mut rnd)
+                        .is_some_and(|r| {
+                            scene.geometries.iter().any(|g| {
+                                let epsilon = g.epsilon().unwrap_or(Zero::zero());
+                                g.intersects_any(r, time, epsilon, T::ValueType::INFINITY)
+                            })
+                        });
+                    if hit {
+                        hits += weight;
+                    }
+                    weights += weight;
+                }
+                covered.push((Point2::new(x, y), hits / weights));
+            }
+        }
+        covered
+    })
+    .into_iter()
+    .flatten()
+    {
+        *image.get_mut(p) = Gray::new(coverage);
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use cg_basics::camera::PinholeCamera;
+    use cg_basics::material::LambertMaterial;
+    use cg_basics::scene_graph::RenderableGeometry;
+    use colors::RGB;
+    use image::{Image, SingleColorImage};
+    use math::geometry::ImplicitNSphere;
+    use math::transform::Transform3;
+    use math::{Point3, Vector3};
+    use sampling::RegularPatternGenerator;
+    use traits::ToRadians;
+    use units::angle::Degrees;
+    use units::length::Meter;
+
+    macro_rules! coverage_of_sphere {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A sphere in front of the camera, which covers the center pixel, but not the
+                // corners, and only a part of the pixels at its edge.
+                let sphere = ImplicitNSphere::new(
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    Meter::new(1.0),
+                );
+                let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> =
+                    vec![Box::new(RenderableGeometry::new(
+                        sphere,
+                        LambertMaterial::new(SingleColorImage::new(
+                            RGB::<$type>::new(0.5, 0.5, 0.5),
+                            Vector2::new(1.0, 1.0),
+                        )),
+                        Transform3::<$type>::ident().translate(0.0, 0.0, -5.0),
+                    ))];
+
+                let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                    HashMap::new();
+                cameras.insert(
+                    String::from("main"),
+                    Box::new(PinholeCamera::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                        Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                        Degrees::<$type>::new(45.0).to_radians(),
+                    )),
+                );
+
+                let scene: SceneType<Meter<$type>, RGB<$type>> =
+                    Scene3::new(RGB::default(), vec![], cameras, geometries);
+                let patterns = SamplingPatternSet::<Point2<$type>>::regular_pattern(8, 8);
+                let coverage = render_coverage(&scene, "main", Vector2::new(9, 9), &patterns, 0, 2);
+
+                assert_eq!(coverage.get(Point2::new(4, 4)).value, 1.0);
+                assert_eq!(coverage.get(Point2::new(0, 0)).value, 0.0);
+                let edge = (0..9)
+                    .map(|x| coverage.get(Point2::new(x, 4)).value)
+                    .find(|&value| value > 0.0)
+                    .unwrap();
+                assert!(edge < 1.0, "{}", edge);
+            }
+        };
+    }
+
+    coverage_of_sphere! { f32, coverage_of_sphere_f32 }
+    coverage_of_sphere! { f64, coverage_of_sphere_f64 }
+}
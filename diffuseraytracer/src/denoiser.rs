@@ -0,0 +1,242 @@
+use colors::Color;
+use image::{Image, ImageBuffer, WritableImage};
+use math::Point2;
+use traits::{ConvenientNumber, Exp, FloatingPoint, Zero};
+
+use crate::aov::Aovs;
+use crate::contours::SurfaceSample;
+
+// Smooths the noise of a render with few samples, see Dammertz et al., "Edge-Avoiding À-Trous
+// Wavelet Transform for fast Global Illumination Filtering". Every iteration blurs with a 5x5
+// kernel whose taps are spread twice as far as in the one before. The surfaces of the auxiliary
+// outputs keep the blur from crossing edges: pixels are only mixed if their normals point in
+// similar directions, their depths are close and their colors are alike.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Denoiser<V> {
+    pub iterations: usize,
+    // The color difference at which the colors stop mixing. It is halved in every iteration.
+    pub color_sigma: V,
+    // The exponent of the cosine between two normals, the higher the sharper the edges.
+    pub normal_power: V,
+    // The depth difference relative to the depth at which the surfaces stop mixing.
+    pub depth_sigma: V,
+}
+
+impl<V> Denoiser<V>
+where
+    V: FloatingPoint + ConvenientNumber + Exp<Output = V>,
+    u16: Into<V>,
+{
+    pub fn new(iterations: usize) -> Denoiser<V> {
+        Denoiser {
+            iterations,
+            color_sigma: V::one() / 2u16.into(),
+            normal_power: 64u16.into(),
+            depth_sigma: V::one() / 10u16.into(),
+        }
+    }
+
+    pub fn with_color_sigma(self, color_sigma: V) -> Denoiser<V> {
+        Denoiser {
+            color_sigma,
+            ..self
+        }
+    }
+
+    pub fn with_normal_power(self, normal_power: V) -> Denoiser<V> {
+        Denoiser {
+            normal_power,
+            ..self
+        }
+    }
+
+    pub fn with_depth_sigma(self, depth_sigma: V) -> Denoiser<V> {
+        Denoiser {
+            depth_sigma,
+            ..self
+        }
+    }
+
+    pub fn denoise<C: Color<ChannelType = V>>(
+        &self,
+        image: &ImageBuffer<C>,
+        aovs: &Aovs<C>,
+    ) -> ImageBuffer<C> {
+        let size = image.size();
+        let kernel: [V; 5] = [1u16, 4, 6, 4, 1].map(|k| k.into() / 16u16.into());
+
+        let mut denoised = image.clone();
+        let mut color_sigma = self.color_sigma;
+        for iteration in 0..self.iterations {
+            let step = 1 << iteration;
+            let mut filtered = ImageBuffer::new(size, C::default());
+
+            for y in 0..size.y {
+                for x in 0..size.x {
+                    let p = Point2::new(x, y);
+                    let color = denoised.get(p);
+                    let surface = aovs.surface(p);
+
+                    let mut sum = C::default();
+                    let mut weights = V::zero();
+                    for (j, ky) in kernel.iter().enumerate() {
+                        for (i, kx) in kernel.iter().enumerate() {
+                            let qx = x as isize + (i as isize - 2) * step;
+                            let qy = y as isize + (j as isize - 2) * step;
+                            if qx < 0 || qy < 0 || qx >= size.x as isize || qy >= size.y as isize {
+                                continue;
+                            }
+
+                            let q = Point2::new(qx as usize, qy as usize);
+                            let other = denoised.get(q);
+                            let difference = other + color * -V::one();
+                            let difference = (difference * difference).max_channel();
+                            let weight = *kx
+                                * *ky
+                                * self.surface_weight(surface, aovs.surface(q))
+                                * (-difference / (color_sigma * color_sigma)).exp();
+
+                            sum = sum + other * weight;
+                            weights += weight;
+                        }
+                    }
+
+                    // The pixel itself always has a weight.
+                    *filtered.get_mut(p) = sum * (V::one() / weights);
+                }
+            }
+
+            denoised = filtered;
+            color_sigma /= 2u16.into();
+        }
+        denoised
+    }
+
+    // Pixels that see the background only mix with each other.
+    fn surface_weight(
+        &self,
+        surface: Option<&SurfaceSample<V>>,
+        other: Option<&SurfaceSample<V>>,
+    ) -> V {
+        match (surface, other) {
+            (Some(surface), Some(other)) => {
+                let normal = surface
+                    .normal
+                    .dot(other.normal)
+                    .max(Zero::zero())
+                    .powf(self.normal_power);
+                let depth =
+                    -(surface.depth - other.depth).abs() / (self.depth_sigma * surface.depth);
+                normal * depth.exp()
+            }
+            (None, None) => V::one(),
+            _ => Zero::zero(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use cg_basics::camera::PinholeCamera;
+    use cg_basics::material::LambertMaterial;
+    use cg_basics::scene_graph::{RenderableGeometry, Scene3};
+    use colors::RGB;
+    use image::SingleColorImage;
+    use math::geometry::ImplicitNSphere;
+    use math::transform::Transform3;
+    use math::{Point3, Vector2, Vector3};
+    use traits::ToRadians;
+    use units::angle::Degrees;
+    use units::length::Meter;
+
+    use crate::camera::RaytracingCamera;
+    use crate::Renderable;
+
+    macro_rules! denoiser_keeps_edges {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A sphere in the middle of the image on a black background.
+                let sphere = ImplicitNSphere::new(
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    Meter::new(1.0),
+                );
+                let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> =
+                    vec![Box::new(RenderableGeometry::new(
+                        sphere,
+                        LambertMaterial::new(SingleColorImage::new(
+                            RGB::<$type>::new(1.0, 1.0, 1.0),
+                            Vector2::new(1.0, 1.0),
+                        )),
+                        Transform3::<$type>::ident().translate(0.0, 0.0, -3.0),
+                    ))];
+
+                let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                    HashMap::new();
+                cameras.insert(
+                    String::from("main"),
+                    Box::new(PinholeCamera::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                        Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                        Degrees::<$type>::new(45.0).to_radians(),
+                    )),
+                );
+
+                let scene = Scene3::new(RGB::default(), vec![], cameras, geometries);
+                let size = Vector2::new(17, 17);
+                let aovs = Aovs::render(&scene, "main", size, 0, 1);
+
+                // The sphere is rendered with noise around a gray of 0.5.
+                let mut image = ImageBuffer::new(size, RGB::<$type>::default());
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let p = Point2::new(x, y);
+                        if aovs.surface(p).is_some() {
+                            let value = 0.3 + 0.1 * ((x * 3 + y * 7) % 5) as $type;
+                            *image.get_mut(p) = RGB::new(value, value, value);
+                        }
+                    }
+                }
+
+                let denoised = Denoiser::new(3).denoise(&image, &aovs);
+
+                // The noise is at least halved.
+                let deviation = |image: &ImageBuffer<RGB<$type>>| {
+                    let mut sum = 0.0;
+                    for y in 0..size.y {
+                        for x in 0..size.x {
+                            let p = Point2::new(x, y);
+                            if aovs.surface(p).is_some() {
+                                sum += (image.get(p).red - 0.5).abs();
+                            }
+                        }
+                    }
+                    sum
+                };
+                assert!(
+                    deviation(&denoised) < deviation(&image) * 0.5,
+                    "{} {}",
+                    deviation(&denoised),
+                    deviation(&image)
+                );
+
+                // Neither does the sphere bleed into the background nor the background darken
+                // the sphere.
+                assert_eq!(denoised.get(Point2::new(0, 8)), RGB::default());
+                let edge = (0..size.x)
+                    .map(|x| Point2::new(x, 8))
+                    .find(|p| aovs.surface(*p).is_some())
+                    .unwrap();
+                assert!(denoised.get(edge).red > 0.3, "{:?}", denoised.get(edge));
+            }
+        };
+    }
+
+    denoiser_keeps_edges! { f32, denoiser_keeps_edges_f32 }
+    denoiser_keeps_edges! { f64, denoiser_keeps_edges_f64 }
+}
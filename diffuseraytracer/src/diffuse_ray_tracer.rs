@@ -1,22 +1,44 @@
-use std::ops::{AddAssign, DivAssign};
+use std::cell::Cell;
+use std::ops::{DivAssign, Sub};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
 
+use crate::aov;
 use crate::camera::RaytracingCamera;
+use crate::contours::{ContourStyle, Contours, SurfaceSample};
 use crate::light::Light;
-use crate::material::Material;
-use crate::Renderable;
+use crate::light_path_expression::{EventKind, LightPathExpression, PathEvent};
+use crate::metrics::Metrics;
+use crate::whitted_ray_tracer::{Hit, Tracer};
+use crate::{Renderable, PACKET_WIDTH};
 use cg_basics::scene_graph::Scene3;
-use colors::Color;
-use image::{ImageBuffer, WritableImage};
-use math::geometry::SurfacePoint;
-use math::{Point2, Vector2};
+use colors::{Color, Gray};
+use image::accumulation_buffer::CompensatedSum;
+use image::filter::ReconstructionFilter;
+use image::{Image, ImageBuffer, WritableImage};
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
 use random::WichmannHillPRNG;
-use sampling::SamplingPatternSet;
-use traits::{One, Zero};
+use sampling::{AdaptiveSampling, SampleStatistics, SamplingPattern, SamplingPatternSet};
+use traits::{ConvenientNumber, Exp, FloatingPoint, Half, One, Sqrt, Zero};
 use units::length::Length;
 
+type SceneType<T, C> =
+    Scene3<C, Box<dyn Light<T, C>>, Box<dyn RaytracingCamera<T>>, Box<dyn Renderable<T, C>>>;
+
+// The group of the lights that are not tagged with one.
+const DEFAULT_LIGHT_GROUP: &str = "default";
+
 pub struct DiffuseRayTracer<T: Length> {
     sampling_patterns: SamplingPatternSet<Point2<T::ValueType>>,
     shadow_tolerance: T::ValueType,
+    threads: usize,
+    tile_size: usize,
+    filter: ReconstructionFilter<T::ValueType>,
+    adaptive_sampling: Option<AdaptiveSampling<T::ValueType>>,
+    packets: bool,
+    metrics: Arc<Metrics>,
 }
 
 impl<T: Length> DiffuseRayTracer<T> {
@@ -27,114 +49,2155 @@ impl<T: Length> DiffuseRayTracer<T> {
         DiffuseRayTracer {
             sampling_patterns,
             shadow_tolerance,
+            threads: 1,
+            tile_size: 16,
+            filter: ReconstructionFilter::Box,
+            adaptive_sampling: None,
+            packets: false,
+            metrics: Arc::new(Metrics::new()),
+        }
+    }
+
+    pub fn with_threads(self, threads: usize) -> DiffuseRayTracer<T> {
+        DiffuseRayTracer {
+            threads: threads.max(1),
+            ..self
+        }
+    }
+
+    pub fn with_tile_size(self, tile_size: usize) -> DiffuseRayTracer<T> {
+        DiffuseRayTracer {
+            tile_size: tile_size.max(1),
+            ..self
         }
     }
+
+    // Filters other than the box filter splat every sample into the pixels around it. This is
+    // slower, but smoothes the edges of panoramic projections, whose pixels cover very different
+    // parts of the scene.
+    pub fn with_filter(self, filter: ReconstructionFilter<T::ValueType>) -> DiffuseRayTracer<T> {
+        DiffuseRayTracer { filter, ..self }
+    }
+
+    // Draws further patterns for a pixel until its noise is low enough.
+    pub fn with_adaptive_sampling(
+        self,
+        adaptive_sampling: AdaptiveSampling<T::ValueType>,
+    ) -> DiffuseRayTracer<T> {
+        DiffuseRayTracer {
+            adaptive_sampling: Some(adaptive_sampling),
+            ..self
+        }
+    }
+
+    // Intersects the camera rays of a pattern with the scene in packets, before any of them is
+    // shaded. Shading draws its random numbers after all rays of the pattern are drawn, so area
+    // lights and lenses are noisy in another way than without packets.
+    pub fn with_packets(self) -> DiffuseRayTracer<T> {
+        DiffuseRayTracer {
+            packets: true,
+            ..self
+        }
+    }
+
+    // The metrics the renderer reports into, e.g. to show the progress from another thread.
+    pub fn with_metrics(self, metrics: Arc<Metrics>) -> DiffuseRayTracer<T> {
+        DiffuseRayTracer { metrics, ..self }
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
     pub fn render<C: Color<ChannelType = T::ValueType>>(
         self,
-        mut scene: Scene3<
-            C,
-            Box<dyn Light<T, C>>,
-            Box<dyn RaytracingCamera<T>>,
-            Box<dyn Renderable<T, C>>,
-        >,
+        scene: SceneType<T, C>,
         camera_id: &str,
         size: Vector2<usize>,
-        rnd: WichmannHillPRNG,
+        seed: u128,
     ) -> ImageBuffer<C>
     where
-        C: AddAssign + DivAssign<C::ChannelType>,
+        C: Sub<Output = C> + DivAssign<C::ChannelType>,
         C::ChannelType: Zero + One,
         u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
     {
-        let mut rnd = rnd;
+        self.render_pass(&scene, camera_id, size, seed)
+    }
 
+    // Renders the image without consuming the renderer or the scene, e.g. for one of several
+    // passes with different seeds.
+    pub fn render_pass<C>(
+        &self,
+        scene: &SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> ImageBuffer<C>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    {
         let mut image_buffer = ImageBuffer::new(size, C::default());
 
-        let camera = scene.cameras.remove(camera_id).unwrap();
+        for (p, sample) in self.render_samples(scene, camera_id, size, seed, Outputs::default()) {
+            *image_buffer.get_mut(p) = sample.combined();
+        }
+
+        image_buffer
+    }
+
+    // Renders the image split into the direct and indirect parts of the diffuse and specular
+    // reflection. Adding all components and the background yields the image returned by render.
+    pub fn render_lighting_components<C>(
+        self,
+        scene: SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> LightingComponents<C>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    {
+        let mut components = LightingComponents {
+            background: ImageBuffer::new(size, C::default()),
+            direct_diffuse: ImageBuffer::new(size, C::default()),
+            direct_specular: ImageBuffer::new(size, C::default()),
+            indirect_diffuse: ImageBuffer::new(size, C::default()),
+            indirect_specular: ImageBuffer::new(size, C::default()),
+        };
+
+        for (p, sample) in self.render_samples(&scene, camera_id, size, seed, Outputs::default()) {
+            *components.background.get_mut(p) = sample.background;
+            *components.direct_diffuse.get_mut(p) = sample.direct_diffuse;
+            *components.direct_specular.get_mut(p) = sample.direct_specular;
+            *components.indirect_diffuse.get_mut(p) = sample.indirect_diffuse;
+            *components.indirect_specular.get_mut(p) = sample.indirect_specular;
+        }
+
+        components
+    }
+
+    // Renders one image per light group in a single pass, so the groups can be rebalanced after
+    // the render. Lights without a group end up in the default group. Adding the background and
+    // all groups yields the image returned by render.
+    pub fn render_light_groups<C>(
+        self,
+        scene: SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> LightGroups<C>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    {
+        let mut names: Vec<String> = vec![];
+        for light in &scene.lights {
+            let group = light.group().unwrap_or(DEFAULT_LIGHT_GROUP);
+            if !names.iter().any(|name| name == group) {
+                names.push(group.to_string());
+            }
+        }
+
+        let mut light_groups = LightGroups {
+            background: ImageBuffer::new(size, C::default()),
+            groups: names
+                .iter()
+                .map(|name| (name.clone(), ImageBuffer::new(size, C::default())))
+                .collect(),
+        };
+
+        for (p, sample) in self.render_samples(
+            &scene,
+            camera_id,
+            size,
+            seed,
+            Outputs {
+                light_groups: &names,
+                ..Outputs::default()
+            },
+        ) {
+            *light_groups.background.get_mut(p) = sample.background + sample.reflection;
+            for ((_, image), color) in light_groups.groups.iter_mut().zip(sample.light_groups) {
+                *image.get_mut(p) = color;
+            }
+        }
+
+        light_groups
+    }
+
+    // Renders an image of the light of the paths selected by each light path expression. The
+    // paths of this renderer are the background seen by the camera, CB, glowing surfaces, CL, the
+    // surroundings seen in a reflection, CSB, and the light of each light reflected by the
+    // surface, CDL and CSL.
+    pub fn render_light_paths<C>(
+        self,
+        scene: SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+        expressions: &[LightPathExpression],
+    ) -> LightPaths<C>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    {
+        let mut light_paths = LightPaths {
+            beauty: ImageBuffer::new(size, C::default()),
+            paths: expressions
+                .iter()
+                .map(|_| ImageBuffer::new(size, C::default()))
+                .collect(),
+        };
+
+        for (p, sample) in self.render_samples(
+            &scene,
+            camera_id,
+            size,
+            seed,
+            Outputs {
+                light_paths: expressions,
+                ..Outputs::default()
+            },
+        ) {
+            *light_paths.beauty.get_mut(p) = sample.combined();
+            for (image, color) in light_paths.paths.iter_mut().zip(sample.light_paths) {
+                *image.get_mut(p) = color;
+            }
+        }
+
+        light_paths
+    }
+
+    // Renders the image together with the shadows in it, e.g. to composite the shadows of
+    // rendered objects onto a photographic backplate. The shadows are the fraction of the samples
+    // of a pixel in which a light is occluded, for each light and for all lights together.
+    // Surfaces that face away from a light are not in its shadow.
+    pub fn render_shadows<C>(
+        self,
+        scene: SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> Shadows<C>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    {
+        let black = Gray::new(Zero::zero());
+        let mut shadows = Shadows {
+            beauty: ImageBuffer::new(size, C::default()),
+            combined: ImageBuffer::new(size, black),
+            lights: scene
+                .lights
+                .iter()
+                .enumerate()
+                .map(|(index, light)| {
+                    let name = light.name().map_or(index.to_string(), String::from);
+                    (name, ImageBuffer::new(size, black))
+                })
+                .collect(),
+        };
+
+        for (p, sample) in self.render_samples(
+            &scene,
+            camera_id,
+            size,
+            seed,
+            Outputs {
+                shadows: true,
+                ..Outputs::default()
+            },
+        ) {
+            *shadows.beauty.get_mut(p) = sample.combined();
+            *shadows.combined.get_mut(p) = Gray::new(sample.shadows[0]);
+            for ((_, image), fraction) in shadows.lights.iter_mut().zip(&sample.shadows[1..]) {
+                *image.get_mut(p) = Gray::new(*fraction);
+            }
+        }
+
+        shadows
+    }
+
+    // Renders the left and the right eye of a stereo camera, which the scene holds as two cameras
+    // with the suffixes .left and .right.
+    pub fn render_stereo<C>(
+        self,
+        scene: SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> (ImageBuffer<C>, ImageBuffer<C>)
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    {
+        let render_eye = |eye: &str| {
+            let mut image_buffer = ImageBuffer::new(size, C::default());
+            let camera_id = format!("{}.{}", camera_id, eye);
+            for (p, sample) in
+                self.render_samples(&scene, &camera_id, size, seed, Outputs::default())
+            {
+                *image_buffer.get_mut(p) = sample.combined();
+            }
+            image_buffer
+        };
+
+        (render_eye("left"), render_eye("right"))
+    }
+
+    // Renders the image and finds the contours of the surfaces in it, e.g. for a technical
+    // illustration. The surfaces are found with a single ray through the center of each pixel.
+    pub fn render_contours<C>(
+        self,
+        scene: SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+        style: ContourStyle<C>,
+    ) -> Contours<C>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    {
+        let surfaces: Vec<Option<SurfaceSample<T::ValueType>>> =
+            aov::trace_surfaces(&scene, camera_id, size, seed, self.threads, self.tile_size)
+                .into_iter()
+                .map(|surface| surface.map(|(surface, _)| surface))
+                .collect();
+
+        let beauty = self.render(scene, camera_id, size, seed);
+        Contours::new(beauty, &surfaces, style)
+    }
+
+    fn render_samples<C>(
+        &self,
+        scene: &SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+        outputs: Outputs,
+    ) -> Vec<(Point2<usize>, Sample<C>)>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    {
+        let frame = &Frame {
+            scene,
+            camera: scene.cameras[camera_id].as_ref(),
+            outputs,
+        };
+
+        self.metrics.pixels_total.add((size.x * size.y) as u64);
+        let _render_time = self.metrics.render_time.start();
+
+        if self.filter == ReconstructionFilter::Box {
+            return self
+                .render_tiles(size, |origin, extent| {
+                    let mut rendered = Vec::with_capacity(extent.x * extent.y);
+                    for y in origin.y..(origin.y + extent.y) {
+                        for x in origin.x..(origin.x + extent.x) {
+                            let p = Point2::new(x, y);
+                            let mut rnd =
+                                WichmannHillPRNG::for_index(seed, (y * size.x + x) as u128);
+                            rendered.push((p, self.render_pixel(frame, p, size, &mut rnd)));
+                        }
+                    }
+                    rendered
+                })
+                .into_iter()
+                .flatten()
+                .collect();
+        }
+
+        // The samples are rendered first and then gathered by the pixels around them. Every pixel
+        // adds up its samples in a fixed order, so the image does not depend on the tiles.
+        let mut samples: Vec<Vec<FilterSample<C>>> = (0..size.x * size.y).map(|_| vec![]).collect();
+        for (index, pixel_samples) in self
+            .render_tiles(size, |origin, extent| {
+                let mut rendered = Vec::with_capacity(extent.x * extent.y);
+                for y in origin.y..(origin.y + extent.y) {
+                    for x in origin.x..(origin.x + extent.x) {
+                        rendered.push((
+                            y * size.x + x,
+                            self.render_filter_samples(frame, Point2::new(x, y), size, seed),
+                        ));
+                    }
+                }
+                rendered
+            })
+            .into_iter()
+            .flatten()
+        {
+            samples[index] = pixel_samples;
+        }
+
+        let margin = self.filter_margin();
+        let to_value = |value: usize| -> T::ValueType { (value as u16).into() };
+        let half = T::ValueType::one().half();
+        let samples = &samples;
+
+        self.render_tiles(size, |origin, extent| {
+            let mut gathered = Vec::with_capacity(extent.x * extent.y);
+            for y in origin.y..(origin.y + extent.y) {
+                for x in origin.x..(origin.x + extent.x) {
+                    let center = Point2::new(to_value(x) + half, to_value(size.y - y - 1) + half);
+                    let mut sums = LightingSample::new_sum(frame);
+                    let mut counter = T::ValueType::zero();
 
+                    for ny in y.saturating_sub(margin)..(y + margin + 1).min(size.y) {
+                        for nx in x.saturating_sub(margin)..(x + margin + 1).min(size.x) {
+                            for filter_sample in &samples[ny * size.x + nx] {
+                                let weight = self.filter.weight(filter_sample.position - center)
+                                    * filter_sample.solid_angle;
+                                if let Some(sample) = &filter_sample.sample {
+                                    sums.add(sample, weight);
+                                }
+                                counter += weight;
+                            }
+                        }
+                    }
+
+                    gathered.push((Point2::new(x, y), sums.mean(counter)));
+                }
+            }
+            gathered
+        })
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn render_tiles<R: Send>(
+        &self,
+        size: Vector2<usize>,
+        render_tile: impl Fn(Point2<usize>, Vector2<usize>) -> R + Sync,
+    ) -> Vec<R> {
+        render_tiles(
+            self.threads,
+            self.tile_size,
+            size,
+            Some(&self.metrics),
+            render_tile,
+        )
+    }
+
+    // The number of pixels next to a pixel that its samples reach with the reconstruction filter.
+    fn filter_margin(&self) -> usize
+    where
+        T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+        u16: Into<T::ValueType>,
+    {
+        let radius = self.filter.radius();
+        let mut margin: usize = 0;
+        while Into::<T::ValueType>::into(margin as u16) + T::ValueType::one().half() < radius {
+            margin += 1;
+        }
+        margin
+    }
+
+    // Renders the samples of a pixel for the reconstruction filter, which keeps them to weight
+    // them for the pixels around.
+    fn render_filter_samples<C>(
+        &self,
+        frame: &Frame<T, C>,
+        p: Point2<usize>,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> Vec<FilterSample<C>>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber,
+    {
         let float_size =
             Vector2::<T::ValueType>::new((size.x as u16).into(), (size.y as u16).into());
+        let mut rnd = WichmannHillPRNG::for_index(seed, (p.y * size.x + p.x) as u128);
+        let mut camera_rays = 0;
+        let shadow_rays = Cell::new(0);
 
-        for x in 0..size.x {
-            for y in 0..size.y {
-                let p = Point2::new(x, y);
-                let pattern = self.sampling_patterns.draw_pattern(&mut rnd);
+        let mut samples = vec![];
+        let mut statistics = SampleStatistics::new();
+        loop {
+            let positions = pixel_positions(p, size, self.sampling_patterns.draw_pattern(&mut rnd));
+            let pattern_samples =
+                self.render_samples_at(frame, &positions, float_size, &mut rnd, &shadow_rays);
+            for (position, sample) in positions.into_iter().zip(pattern_samples) {
+                match &sample {
+                    Some(sample) => {
+                        camera_rays += 1;
+                        statistics.add(sample.combined().max_channel());
+                    }
+                    None => statistics.add(Zero::zero()),
+                }
+                samples.push(FilterSample {
+                    position,
+                    solid_angle: frame.camera.solid_angle(float_size, position),
+                    sample,
+                });
+            }
 
-                let mut counter = C::ChannelType::zero();
+            if self.is_pixel_done(&statistics) {
+                break;
+            }
+        }
 
-                let color = image_buffer.get_mut(p);
+        self.report_pixel(
+            frame.scene,
+            p,
+            statistics.samples(),
+            camera_rays,
+            shadow_rays.get(),
+        );
 
-                for i in 0..pattern.len() {
-                    let sp = Point2::<T::ValueType>::new(
-                        (p.x as u16).into(),
-                        ((size.y - p.y - 1) as u16).into(),
-                    ) + pattern[i].as_vector();
+        samples
+    }
 
-                    let ray = camera.ray_for(
-                        float_size,
-                        sp,
-                        self.sampling_patterns.draw_pattern(&mut rnd),
-                    );
+    fn render_pixel<C>(
+        &self,
+        frame: &Frame<T, C>,
+        p: Point2<usize>,
+        size: Vector2<usize>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> Sample<C>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber,
+    {
+        let float_size =
+            Vector2::<T::ValueType>::new((size.x as u16).into(), (size.y as u16).into());
+
+        let mut counter = C::ChannelType::zero();
+        let mut camera_rays = 0;
+        let shadow_rays = Cell::new(0);
+
+        let mut sums = LightingSample::new_sum(frame);
+        let mut statistics = SampleStatistics::new();
+
+        loop {
+            let positions = pixel_positions(p, size, self.sampling_patterns.draw_pattern(rnd));
+            let samples = self.render_samples_at(frame, &positions, float_size, rnd, &shadow_rays);
+            for (sp, sample) in positions.into_iter().zip(samples) {
+                // Samples the camera does not see anything for, e.g. outside of the circle of a
+                // fisheye, count as black, so the edge of the projection is smooth.
+                let weight = frame.camera.solid_angle(float_size, sp);
+                match sample {
+                    Some(sample) => {
+                        camera_rays += 1;
+                        statistics.add(sample.combined().max_channel());
+                        sums.add(&sample, weight);
+                    }
+                    None => statistics.add(Zero::zero()),
+                }
+                counter += weight;
+            }
+
+            if self.is_pixel_done(&statistics) {
+                break;
+            }
+        }
+
+        self.report_pixel(
+            frame.scene,
+            p,
+            statistics.samples(),
+            camera_rays,
+            shadow_rays.get(),
+        );
+
+        sums.mean(counter)
+    }
+
+    // Without adaptive sampling, every pixel takes the samples of one pattern. The noise is
+    // measured on the combined color, so the components of a pixel share its samples.
+    fn is_pixel_done(&self, statistics: &SampleStatistics<T::ValueType>) -> bool
+    where
+        T::ValueType: FloatingPoint,
+    {
+        self.adaptive_sampling
+            .is_none_or(|adaptive| adaptive.is_done(statistics))
+    }
+
+    fn report_pixel<C>(
+        &self,
+        scene: &SceneType<T, C>,
+        p: Point2<usize>,
+        samples: usize,
+        camera_rays: u64,
+        shadow_rays: u64,
+    ) where
+        C: Color<ChannelType = T::ValueType>,
+    {
+        let geometries = scene.geometries.len() as u64;
+        self.metrics.camera_rays.add(camera_rays);
+        self.metrics.shadow_rays.add(shadow_rays);
+        self.metrics
+            .intersection_tests
+            .add((camera_rays + shadow_rays) * geometries);
+        self.metrics.pixel_done(p, samples as u64);
+    }
+
+    // Traces the camera ray through a point of the image. Returns None if the camera does not
+    // see anything there.
+    fn render_sample<C>(
+        &self,
+        frame: &Frame<T, C>,
+        sp: Point2<T::ValueType>,
+        float_size: Vector2<T::ValueType>,
+        rnd: &mut WichmannHillPRNG,
+        shadow_rays: &Cell<u64>,
+    ) -> Option<Sample<C>>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber,
+    {
+        let (r, time) = self.camera_ray(frame, sp, float_size, rnd)?;
+        let hit = frame
+            .scene
+            .geometries
+            .iter()
+            .flat_map(|g| {
+                let epsilon = g.epsilon().unwrap_or(Zero::zero());
+                g.intersect_at(r, time)
+                    .into_iter()
+                    .filter(move |(t, _, _)| *t > epsilon)
+                    .map(move |(t, sp, material)| (t, sp, material, g))
+            })
+            .min_by(|(t1, _, _, _), (t2, _, _, _)| t1.partial_cmp(t2).unwrap())
+            .map(|(_, sp, material, g)| (sp, material, g));
+
+        let screen = Point2::new(sp.x / float_size.x, sp.y / float_size.y);
+        Some(self.shade_sample(frame, screen, (r, time), hit, rnd, shadow_rays))
+    }
+
+    // The samples at several points of the image, in their order. With packets, the camera rays
+    // of all points are drawn first and intersected with the scene in packets.
+    fn render_samples_at<C>(
+        &self,
+        frame: &Frame<T, C>,
+        positions: &[Point2<T::ValueType>],
+        float_size: Vector2<T::ValueType>,
+        rnd: &mut WichmannHillPRNG,
+        shadow_rays: &Cell<u64>,
+    ) -> Vec<Option<Sample<C>>>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber,
+    {
+        if !self.packets {
+            return positions
+                .iter()
+                .map(|sp| self.render_sample(frame, *sp, float_size, rnd, shadow_rays))
+                .collect();
+        }
+
+        let camera_rays: Vec<_> = positions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, sp)| Some((index, self.camera_ray(frame, *sp, float_size, rnd)?)))
+            .collect();
+        // The camera rays are counted with the samples.
+        let rays = Cell::new(0);
+        let tracer = Tracer {
+            scene: frame.scene,
+            time: Zero::zero(),
+            shadow_tolerance: self.shadow_tolerance,
+            rays: &rays,
+            shadow_rays,
+        };
+
+        let mut samples: Vec<Option<Sample<C>>> = positions.iter().map(|_| None).collect();
+        for packet in camera_rays.chunks(PACKET_WIDTH) {
+            let rays: Vec<_> = packet.iter().map(|(_, (r, _))| *r).collect();
+            let times: Vec<_> = packet.iter().map(|(_, (_, time))| *time).collect();
+            let hits = tracer.closest_hits(&rays, &times, Zero::zero());
+            for ((index, camera_ray), hit) in packet.iter().zip(hits) {
+                let sp = positions[*index];
+                let screen = Point2::new(sp.x / float_size.x, sp.y / float_size.y);
+                samples[*index] =
+                    Some(self.shade_sample(frame, screen, *camera_ray, hit, rnd, shadow_rays));
+            }
+        }
+        samples
+    }
+
+    // The camera ray through a point of the image, with the time it is traced at.
+    fn camera_ray<C>(
+        &self,
+        frame: &Frame<T, C>,
+        sp: Point2<T::ValueType>,
+        float_size: Vector2<T::ValueType>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> Option<(ParametricLine<Point3<T>, Vector3<T>>, T::ValueType)>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber,
+    {
+        let lens_pattern = self.sampling_patterns.draw_pattern(rnd);
+        let r = frame.camera.ray_for(float_size, sp, lens_pattern, rnd)?;
+
+        // The shadow rays are cast at the time of the camera ray, so moving geometry casts its
+        // shadow where it is seen.
+        let time_pattern = self.sampling_patterns.draw_pattern(rnd);
+        let time = frame.camera.shutter().time(time_pattern.draw_point(rnd).x);
+        Some((r, time))
+    }
+
+    // Shades the closest hit of a camera ray, or the background it sees if it misses the scene.
+    // The screen position is where the ray passes the image, relative to its size.
+    fn shade_sample<'a, C>(
+        &self,
+        frame: &Frame<'a, T, C>,
+        screen: Point2<T::ValueType>,
+        (r, time): (ParametricLine<Point3<T>, Vector3<T>>, T::ValueType),
+        hit: Option<Hit<'a, T, C>>,
+        rnd: &mut WichmannHillPRNG,
+        shadow_rays: &Cell<u64>,
+    ) -> Sample<C>
+    where
+        C: Color<ChannelType = T::ValueType> + Sub<Output = C> + DivAssign<C::ChannelType>,
+        C::ChannelType: Zero + One,
+        u16: Into<T::ValueType>,
+        T::AreaType: Sqrt<Output = T>,
+        T::ValueType: FloatingPoint + ConvenientNumber,
+    {
+        let mut sample = LightingSample {
+            background: C::default(),
+            direct_diffuse: C::default(),
+            direct_specular: C::default(),
+            indirect_diffuse: C::default(),
+            indirect_specular: C::default(),
+            reflection: C::default(),
+            light_groups: vec![C::default(); frame.outputs.light_groups.len()],
+            light_paths: vec![C::default(); frame.outputs.light_paths.len()],
+            shadows: vec![Zero::zero(); shadow_count(frame)],
+        };
 
-                    if let Some(r) = ray {
-                        let mut hits: Vec<(
-                            T::ValueType,
-                            SurfacePoint<T>,
-                            &dyn Material<T, ColorType = C>,
-                        )> = scene
+        let Some((sp, material, geometry)) = hit else {
+            let direction = r.direction.normalized();
+            let background = match &frame.scene.background {
+                Some(background) => background.color_for(direction, screen),
+                None => frame
+                    .scene
+                    .lights
+                    .iter()
+                    .find_map(|light| light.background(direction))
+                    .unwrap_or(frame.scene.bg_color),
+            };
+            sample.background = background;
+
+            let path = [
+                PathEvent::new(EventKind::Camera),
+                PathEvent::new(EventKind::Background),
+            ];
+            for (expression, color) in frame
+                .outputs
+                .light_paths
+                .iter()
+                .zip(&mut sample.light_paths)
+            {
+                if expression.matches(&path) {
+                    *color = background;
+                }
+            }
+            return sample;
+        };
+
+        let mut reached = vec![false; shadow_count(frame).saturating_sub(1)];
+        let mut occluded = reached.clone();
+        let (indirect_lights, direct_lights): (Vec<_>, Vec<_>) = frame
+            .scene
+            .lights
+            .iter()
+            .enumerate()
+            .filter(|(_, light)| geometry.illuminated_by(light.name()))
+            .filter(|(index, light)| {
+                let light_pattern = self.sampling_patterns.draw_pattern(rnd);
+                let light_bias = light
+                    .shadow_bias()
+                    .unwrap_or(geometry.epsilon().unwrap_or(self.shadow_tolerance));
+                let illuminated = light.illuminates(
+                    sp,
+                    &|shadow_ray, max_distance| {
+                        shadow_rays.set(shadow_rays.get() + 1);
+                        let t_max = max_distance.map_or(T::ValueType::INFINITY, |d| d / T::one());
+                        frame
+                            .scene
                             .geometries
                             .iter()
-                            .flat_map(|g| g.intersect(r))
-                            .filter(|(t, _, _)| *t > Zero::zero())
-                            .collect();
-
-                        hits.sort_by(|(t1, _, _), (t2, _, _)| t1.partial_cmp(t2).unwrap());
-
-                        counter += C::ChannelType::one();
-
-                        if hits.is_empty() {
-                            *color += scene.bg_color;
-                        } else {
-                            let (_, sp, material) = hits.remove(0);
-                            let lights = scene
-                                .lights
-                                .iter()
-                                .filter(|light| {
-                                    light.illuminates(
-                                        sp,
-                                        &|shadow_ray, min_distance| {
-                                            let mut hits: Vec<T::ValueType> = scene
-                                                .geometries
-                                                .iter()
-                                                .flat_map(|g| g.intersect(shadow_ray))
-                                                .map(|(t, _, _)| t)
-                                                .filter(|t| *t > self.shadow_tolerance)
-                                                .filter(|t| {
-                                                    if let Some(min_d) = min_distance {
-                                                        *t < min_d / T::one()
-                                                    } else {
-                                                        true
-                                                    }
-                                                })
-                                                .collect();
-                                            hits.sort_by(|t1, t2| t1.partial_cmp(t2).unwrap());
-                                            hits.first().copied()
-                                        },
-                                        self.sampling_patterns.draw_pattern(&mut rnd),
-                                        &mut rnd,
-                                    )
-                                })
-                                .collect();
-
-                            *color += material.color_for(sp, r.direction, lights)
-                        }
+                            .filter(|g| g.casts_shadows())
+                            .any(|g| {
+                                let bias = g.shadow_bias().unwrap_or(light_bias);
+                                g.intersects_any(shadow_ray, time, bias, t_max)
+                            })
+                    },
+                    light_pattern,
+                    rnd,
+                );
+
+                // Without any occluders, a shadow can be told apart from a surface that faces
+                // away from the light.
+                if frame.outputs.shadows {
+                    occluded[*index] =
+                        !illuminated && light.illuminates(sp, &|_, _| false, light_pattern, rnd);
+                    reached[*index] = illuminated || occluded[*index];
+                }
+
+                illuminated
+            })
+            .map(|(_, light)| light.as_ref())
+            .partition(|light| light.is_indirect());
+
+        if frame.outputs.shadows {
+            let count = |flags: &[bool]| -> T::ValueType {
+                (flags.iter().filter(|flag| **flag).count() as u16).into()
+            };
+            if reached.contains(&true) {
+                sample.shadows[0] = count(&occluded) / count(&reached);
+            }
+            for (fraction, occluded) in sample.shadows[1..].iter_mut().zip(&occluded) {
+                if *occluded {
+                    *fraction = One::one();
+                }
+            }
+        }
+
+        for (group, color) in frame
+            .outputs
+            .light_groups
+            .iter()
+            .zip(sample.light_groups.iter_mut())
+        {
+            let lights = direct_lights
+                .iter()
+                .chain(indirect_lights.iter())
+                .filter(|light| light.group().unwrap_or(DEFAULT_LIGHT_GROUP) == group)
+                .copied()
+                .collect();
+            let (diffuse, specular) = material.diffuse_and_specular_for(sp, r.direction, lights);
+            *color = diffuse + specular;
+        }
+
+        sample.reflection = material.reflection_for(sp, r.direction);
+
+        if let Some(emission) = material.emission() {
+            // The camera sees a glowing surface, which does not reflect the lights.
+            let path = [
+                PathEvent::new(EventKind::Camera),
+                PathEvent::new(EventKind::Light),
+            ];
+            for (expression, color) in frame
+                .outputs
+                .light_paths
+                .iter()
+                .zip(&mut sample.light_paths)
+            {
+                if expression.matches(&path) {
+                    *color = emission;
+                }
+            }
+        } else if !frame.outputs.light_paths.is_empty() {
+            // Each light is shaded on its own, so its light can be told apart.
+            let camera = PathEvent::new(EventKind::Camera);
+            let diffuse = PathEvent::new(EventKind::Diffuse);
+            let specular = PathEvent::new(EventKind::Specular);
+            let lit: Vec<_> = direct_lights
+                .iter()
+                .chain(indirect_lights.iter())
+                .map(|light| {
+                    let (d, s) = material.diffuse_and_specular_for(sp, r.direction, vec![*light]);
+                    (PathEvent::labeled(EventKind::Light, light.name()), d, s)
+                })
+                .collect();
+
+            for (expression, color) in frame
+                .outputs
+                .light_paths
+                .iter()
+                .zip(&mut sample.light_paths)
+            {
+                if expression.matches(&[camera, specular, PathEvent::new(EventKind::Background)]) {
+                    *color = *color + sample.reflection;
+                }
+                for (light, d, s) in &lit {
+                    if expression.matches(&[camera, diffuse, *light]) {
+                        *color = *color + *d;
+                    }
+                    if expression.matches(&[camera, specular, *light]) {
+                        *color = *color + *s;
                     }
                 }
+            }
+        }
+
+        let (diffuse, specular) = material.diffuse_and_specular_for(sp, r.direction, direct_lights);
+        sample.direct_diffuse = diffuse;
+        sample.direct_specular = specular;
+
+        let (diffuse, specular) =
+            material.diffuse_and_specular_for(sp, r.direction, indirect_lights);
+        sample.indirect_diffuse = diffuse;
+        sample.indirect_specular = specular + sample.reflection;
+
+        sample
+    }
+}
+
+// The points of the image a pattern puts into a pixel, counted from the bottom left.
+fn pixel_positions<V>(
+    p: Point2<usize>,
+    size: Vector2<usize>,
+    pattern: &SamplingPattern<Point2<V>>,
+) -> Vec<Point2<V>>
+where
+    V: FloatingPoint,
+    u16: Into<V>,
+{
+    let corner = Point2::<V>::new((p.x as u16).into(), ((size.y - p.y - 1) as u16).into());
+    (0..pattern.len())
+        .map(|i| corner + pattern[i].as_vector())
+        .collect()
+}
 
-                *color /= counter;
+// Renders the tiles of the image on the worker threads and returns their results in the order
+// of the tiles. Every finished tile is reported to the metrics, if there are any. Once the render
+// is cancelled, the tiles that are not started yet are left out.
+pub(crate) fn render_tiles<R: Send>(
+    threads: usize,
+    tile_size: usize,
+    size: Vector2<usize>,
+    metrics: Option<&Metrics>,
+    render_tile: impl Fn(Point2<usize>, Vector2<usize>) -> R + Sync,
+) -> Vec<R> {
+    let tiles_x = size.x.div_ceil(tile_size);
+    let tiles_y = size.y.div_ceil(tile_size);
+    let tiles = tiles_x * tiles_y;
+    if let Some(metrics) = metrics {
+        metrics.tiles_total.add(tiles as u64);
+    }
+
+    let next_tile = AtomicUsize::new(0);
+
+    let mut rendered: Vec<(usize, R)> = thread::scope(|s| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut rendered = Vec::new();
+                    loop {
+                        let tile = next_tile.fetch_add(1, Ordering::Relaxed);
+                        if tile >= tiles || metrics.is_some_and(Metrics::is_cancelled) {
+                            break;
+                        }
+
+                        let origin =
+                            Point2::new((tile % tiles_x) * tile_size, (tile / tiles_x) * tile_size);
+                        let extent = Vector2::new(
+                            tile_size.min(size.x - origin.x),
+                            tile_size.min(size.y - origin.y),
+                        );
+
+                        rendered.push((tile, render_tile(origin, extent)));
+                        if let Some(metrics) = metrics {
+                            metrics.tile_done();
+                        }
+                    }
+                    rendered
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+
+    rendered.sort_by_key(|(tile, _)| *tile);
+    rendered.into_iter().map(|(_, result)| result).collect()
+}
+
+pub struct LightingComponents<C: Color> {
+    pub background: ImageBuffer<C>,
+    pub direct_diffuse: ImageBuffer<C>,
+    pub direct_specular: ImageBuffer<C>,
+    pub indirect_diffuse: ImageBuffer<C>,
+    pub indirect_specular: ImageBuffer<C>,
+}
+
+impl<C: Color> LightingComponents<C> {
+    pub fn combined(&self) -> ImageBuffer<C> {
+        let size = self.background.size();
+        let mut image_buffer = ImageBuffer::new(size, C::default());
+
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let p = Point2::new(x, y);
+                *image_buffer.get_mut(p) = self.background.get(p)
+                    + self.direct_diffuse.get(p)
+                    + self.direct_specular.get(p)
+                    + self.indirect_diffuse.get(p)
+                    + self.indirect_specular.get(p);
+            }
+        }
+
+        image_buffer
+    }
+}
+
+pub struct LightGroups<C: Color> {
+    // The background and the light reflected from the surroundings, which do not belong to any
+    // light.
+    pub background: ImageBuffer<C>,
+    pub groups: Vec<(String, ImageBuffer<C>)>,
+}
+
+impl<C: Color> LightGroups<C> {
+    pub fn combined(&self) -> ImageBuffer<C> {
+        let size = self.background.size();
+        let mut image_buffer = ImageBuffer::new(size, C::default());
+
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let p = Point2::new(x, y);
+                *image_buffer.get_mut(p) = self
+                    .groups
+                    .iter()
+                    .fold(self.background.get(p), |sum, (_, image)| sum + image.get(p));
+            }
+        }
+
+        image_buffer
+    }
+}
+
+pub struct LightPaths<C: Color> {
+    pub beauty: ImageBuffer<C>,
+    // One image per light path expression.
+    pub paths: Vec<ImageBuffer<C>>,
+}
+
+impl<C: Color + Sub<Output = C>> LightPaths<C> {
+    // The image without the paths selected by a light path expression.
+    pub fn excluding(&self, index: usize) -> ImageBuffer<C> {
+        let size = self.beauty.size();
+        let mut image_buffer = ImageBuffer::new(size, C::default());
+
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let p = Point2::new(x, y);
+                *image_buffer.get_mut(p) = self.beauty.get(p) - self.paths[index].get(p);
             }
         }
 
         image_buffer
     }
 }
+
+pub struct Shadows<C: Color> {
+    pub beauty: ImageBuffer<C>,
+    pub combined: ImageBuffer<Gray<C::ChannelType>>,
+    // One image per light, named after the light or its index in the scene.
+    pub lights: Vec<(String, ImageBuffer<Gray<C::ChannelType>>)>,
+}
+
+// What the pixels of a render are computed from.
+struct Frame<'a, T: Length, C: Color> {
+    scene: &'a SceneType<T, C>,
+    camera: &'a dyn RaytracingCamera<T>,
+    outputs: Outputs<'a>,
+}
+
+// The images rendered besides the beauty image.
+#[derive(Clone, Copy, Default)]
+struct Outputs<'a> {
+    light_groups: &'a [String],
+    light_paths: &'a [LightPathExpression],
+    shadows: bool,
+}
+
+// A sample that is kept until the pixels around it are reconstructed.
+struct FilterSample<C: Color> {
+    position: Point2<C::ChannelType>,
+    solid_angle: C::ChannelType,
+    sample: Option<Sample<C>>,
+}
+
+// A sample of the colors of a render.
+type Sample<C> = LightingSample<C, <C as Color>::ChannelType>;
+
+struct LightingSample<C, V> {
+    background: C,
+    direct_diffuse: C,
+    direct_specular: C,
+    indirect_diffuse: C,
+    indirect_specular: C,
+    // Light reflected from the surroundings, already contained in the indirect specular part.
+    reflection: C,
+    light_groups: Vec<C>,
+    // The contribution of the paths selected by each light path expression.
+    light_paths: Vec<C>,
+    // The fraction of the lights that are occluded, first for all lights together, then for each
+    // light of the scene.
+    shadows: Vec<V>,
+}
+
+impl<C> LightingSample<CompensatedSum<C>, C::ChannelType>
+where
+    C: Color + Sub<Output = C> + DivAssign<C::ChannelType>,
+    C::ChannelType: Zero,
+{
+    fn new_sum<T: Length>(
+        frame: &Frame<T, C>,
+    ) -> LightingSample<CompensatedSum<C>, C::ChannelType> {
+        let outputs = frame.outputs;
+        LightingSample {
+            background: CompensatedSum::new(),
+            direct_diffuse: CompensatedSum::new(),
+            direct_specular: CompensatedSum::new(),
+            indirect_diffuse: CompensatedSum::new(),
+            indirect_specular: CompensatedSum::new(),
+            reflection: CompensatedSum::new(),
+            light_groups: vec![CompensatedSum::new(); outputs.light_groups.len()],
+            light_paths: vec![CompensatedSum::new(); outputs.light_paths.len()],
+            shadows: vec![Zero::zero(); shadow_count(frame)],
+        }
+    }
+
+    fn add(&mut self, sample: &Sample<C>, weight: C::ChannelType) {
+        self.background.add(sample.background * weight);
+        self.direct_diffuse.add(sample.direct_diffuse * weight);
+        self.direct_specular.add(sample.direct_specular * weight);
+        self.indirect_diffuse.add(sample.indirect_diffuse * weight);
+        self.indirect_specular
+            .add(sample.indirect_specular * weight);
+        self.reflection.add(sample.reflection * weight);
+        for (sum, color) in self.light_groups.iter_mut().zip(&sample.light_groups) {
+            sum.add(*color * weight);
+        }
+        for (sum, color) in self.light_paths.iter_mut().zip(&sample.light_paths) {
+            sum.add(*color * weight);
+        }
+        for (sum, fraction) in self.shadows.iter_mut().zip(&sample.shadows) {
+            *sum += *fraction * weight;
+        }
+    }
+
+    // Pixels without any weight, e.g. outside of the circle of a fisheye, stay black. Negative
+    // lobes of a filter can make the weight of a pixel negative, which is treated the same.
+    fn mean(self, counter: C::ChannelType) -> Sample<C>
+    where
+        C::ChannelType: PartialOrd,
+    {
+        let mean = |sum: CompensatedSum<C>| {
+            let mut color = sum.value();
+            if counter > C::ChannelType::zero() {
+                color /= counter;
+            } else {
+                color = C::default();
+            }
+            color
+        };
+
+        LightingSample {
+            background: mean(self.background),
+            direct_diffuse: mean(self.direct_diffuse),
+            direct_specular: mean(self.direct_specular),
+            indirect_diffuse: mean(self.indirect_diffuse),
+            indirect_specular: mean(self.indirect_specular),
+            reflection: mean(self.reflection),
+            light_groups: self.light_groups.into_iter().map(mean).collect(),
+            light_paths: self.light_paths.into_iter().map(mean).collect(),
+            shadows: self
+                .shadows
+                .into_iter()
+                .map(|sum| {
+                    if counter > C::ChannelType::zero() {
+                        sum / counter
+                    } else {
+                        Zero::zero()
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+// One fraction for all lights together and one for each light, if shadows are rendered at all.
+fn shadow_count<T: Length, C: Color>(frame: &Frame<T, C>) -> usize {
+    if frame.outputs.shadows {
+        frame.scene.lights.len() + 1
+    } else {
+        0
+    }
+}
+
+impl<C: Color, V> LightingSample<C, V> {
+    fn combined(&self) -> C {
+        self.background
+            + self.direct_diffuse
+            + self.direct_specular
+            + self.indirect_diffuse
+            + self.indirect_specular
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use cg_basics::camera::{FisheyeCamera, PerspectiveCamera, PinholeCamera};
+    use cg_basics::light::{AmbientLight, AmbientOcclusionLight, EnvironmentLight, PointLight};
+    use cg_basics::material::{LambertMaterial, PhongMaterial};
+    use cg_basics::scene_graph::{LightLinks, RenderableGeometry};
+    use colors::RGB;
+    use image::generator::Checkerboard;
+    use image::{Image, SingleColorImage, WritableImage};
+    use math::geometry::{ImplicitNSphere, ImplicitPlane3};
+    use math::transform::Transform3;
+    use math::{Normal3, Point3, Vector3};
+    use sampling::{JitteredPatternGenerator, RegularPatternGenerator};
+    use traits::ToRadians;
+    use units::angle::Degrees;
+    use units::length::Meter;
+
+    macro_rules! render_is_independent_of_thread_scheduling {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let scene = || -> SceneType<Meter<$type>, RGB<$type>> {
+                    let plane = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let sphere = ImplicitNSphere::new(
+                        Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                        Meter::new(1.0),
+                    );
+
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(RenderableGeometry::new(
+                            plane,
+                            LambertMaterial::new(Checkerboard::generate(
+                                RGB::new(1.0, 1.0, 1.0),
+                                RGB::new(0.2, 0.2, 0.2),
+                            )),
+                            Transform3::<$type>::ident(),
+                        )),
+                        Box::new(RenderableGeometry::new(
+                            sphere,
+                            LambertMaterial::new(Checkerboard::generate(
+                                RGB::new(1.0, 0.0, 0.0),
+                                RGB::new(0.0, 0.0, 1.0),
+                            )),
+                            Transform3::<$type>::ident(),
+                        )),
+                    ];
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(PointLight::new(
+                            RGB::new(0.5, 0.5, 0.5),
+                            Point3::new(Meter::new(2.0), Meter::new(4.0), Meter::new(2.0)),
+                        )),
+                        Box::new(AmbientOcclusionLight::new(
+                            RGB::new(0.5, 0.5, 0.5),
+                            1.0,
+                            Meter::new(2.0),
+                        )),
+                    ];
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PerspectiveCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(4.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Degrees::<$type>::new(90.0).to_radians(),
+                            Meter::new(0.1),
+                            Meter::new(4.0),
+                        )),
+                    );
+
+                    Scene3::new(RGB::new(0.1, 0.2, 0.3), lights, cameras, geometries)
+                };
+
+                let patterns = || {
+                    SamplingPatternSet::<Point2<$type>>::jittered_patterns(
+                        8,
+                        2,
+                        2,
+                        &mut WichmannHillPRNG::from_seed(7),
+                    )
+                };
+
+                let size = Vector2::new(37, 23);
+
+                let reference = DiffuseRayTracer::<Meter<$type>>::new(patterns(), 0.0001).render(
+                    scene(),
+                    "main",
+                    size,
+                    42,
+                );
+                let threaded = DiffuseRayTracer::<Meter<$type>>::new(patterns(), 0.0001)
+                    .with_threads(4)
+                    .with_tile_size(5)
+                    .render(scene(), "main", size, 42);
+                let other_seed = DiffuseRayTracer::<Meter<$type>>::new(patterns(), 0.0001)
+                    .with_threads(3)
+                    .render(scene(), "main", size, 43);
+
+                let mut differs_for_other_seed = false;
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let p = Point2::new(x, y);
+                        assert_eq!(reference.get(p), threaded.get(p));
+                        differs_for_other_seed |= reference.get(p) != other_seed.get(p);
+                    }
+                }
+                assert!(differs_for_other_seed);
+            }
+        };
+    }
+
+    render_is_independent_of_thread_scheduling! { f32, render_is_independent_of_thread_scheduling_f32 }
+    render_is_independent_of_thread_scheduling! { f64, render_is_independent_of_thread_scheduling_f64 }
+
+    macro_rules! shadow_bias_overrides_shadow_tolerance {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A single ray from above hits the floor at the origin, the sphere blocks the light at a
+                // distance of about 1.2.
+                let render = |light_bias: Option<$type>,
+                              geometry_bias: Option<$type>,
+                              floor_epsilon: Option<$type>| {
+                    let plane = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let sphere = ImplicitNSphere::new(
+                        Point3::new(Meter::new(1.0), Meter::new(1.0), Meter::new(0.0)),
+                        Meter::new(0.2),
+                    );
+                    let material = || {
+                        LambertMaterial::new(SingleColorImage::new(
+                            RGB::<$type>::new(1.0, 1.0, 1.0),
+                            Vector2::new(1.0, 1.0),
+                        ))
+                    };
+
+                    let mut occluder =
+                        RenderableGeometry::new(sphere, material(), Transform3::<$type>::ident());
+                    if let Some(bias) = geometry_bias {
+                        occluder = occluder.with_shadow_bias(bias);
+                    }
+
+                    let mut floor =
+                        RenderableGeometry::new(plane, material(), Transform3::<$type>::ident());
+                    if let Some(epsilon) = floor_epsilon {
+                        floor = floor.with_epsilon(epsilon);
+                    }
+
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> =
+                        vec![Box::new(floor), Box::new(occluder)];
+
+                    let mut light = PointLight::<Meter<$type>, RGB<$type>>::new(
+                        RGB::new(1.0, 1.0, 1.0),
+                        Point3::new(Meter::new(3.0), Meter::new(3.0), Meter::new(0.0)),
+                    );
+                    if let Some(bias) = light_bias {
+                        light = light.with_shadow_bias(bias);
+                    }
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> =
+                        vec![Box::new(light)];
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(3.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                            Degrees::<$type>::new(1.0).to_radians(),
+                        )),
+                    );
+
+                    let scene = Scene3::new(RGB::new(0.0, 0.0, 0.0), lights, cameras, geometries);
+
+                    let image = DiffuseRayTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1),
+                        0.0001,
+                    )
+                    .render(scene, "main", Vector2::new(1, 1), 0);
+                    image.get(Point2::new(0, 0))
+                };
+
+                let shadowed = render(None, None, None);
+                let lit = render(Some(2.0), None, None);
+
+                assert_eq!(shadowed, RGB::new(0.0, 0.0, 0.0));
+                assert!(lit.red > 0.5);
+                assert_eq!(render(None, Some(2.0), None), lit);
+                assert_eq!(render(Some(2.0), Some(0.0001), None), shadowed);
+
+                // The epsilon of the floor replaces the shadow tolerance for rays leaving it.
+                assert_eq!(render(None, None, Some(2.0)), lit);
+                assert_eq!(render(Some(0.0001), None, Some(2.0)), shadowed);
+            }
+        };
+    }
+
+    shadow_bias_overrides_shadow_tolerance! { f32, shadow_bias_overrides_shadow_tolerance_f32 }
+    shadow_bias_overrides_shadow_tolerance! { f64, shadow_bias_overrides_shadow_tolerance_f64 }
+
+    macro_rules! light_links_select_lights {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let render = |light_links: LightLinks| {
+                    let plane = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let floor = RenderableGeometry::new(
+                        plane,
+                        LambertMaterial::new(SingleColorImage::new(
+                            RGB::<$type>::new(1.0, 1.0, 1.0),
+                            Vector2::new(1.0, 1.0),
+                        )),
+                        Transform3::<$type>::ident(),
+                    )
+                    .with_light_links(light_links);
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> =
+                        vec![Box::new(floor)];
+
+                    let position = Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0));
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(
+                            PointLight::new(RGB::new(1.0, 0.0, 0.0), position)
+                                .with_name(String::from("key")),
+                        ),
+                        Box::new(
+                            PointLight::new(RGB::new(0.0, 1.0, 0.0), position)
+                                .with_name(String::from("rim")),
+                        ),
+                        Box::new(PointLight::new(RGB::new(0.0, 0.0, 1.0), position)),
+                    ];
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                            Degrees::<$type>::new(1.0).to_radians(),
+                        )),
+                    );
+
+                    let scene = Scene3::new(RGB::new(0.0, 0.0, 0.0), lights, cameras, geometries);
+
+                    let image = DiffuseRayTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1),
+                        0.0001,
+                    )
+                    .render(scene, "main", Vector2::new(1, 1), 0);
+                    let color = image.get(Point2::new(0, 0));
+                    [color.red > 0.0, color.green > 0.0, color.blue > 0.0]
+                };
+
+                assert_eq!(render(LightLinks::All), [true, true, true]);
+                assert_eq!(
+                    render(LightLinks::Include(vec![String::from("rim")])),
+                    [false, true, false]
+                );
+                assert_eq!(
+                    render(LightLinks::Exclude(vec![String::from("rim")])),
+                    [true, false, true]
+                );
+            }
+        };
+    }
+
+    light_links_select_lights! { f32, light_links_select_lights_f32 }
+    light_links_select_lights! { f64, light_links_select_lights_f64 }
+
+    macro_rules! diffuse_ray_tracer_packets {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A shiny ball casting a shadow on a floor, partly cut off by the image.
+                let render = |packets: bool, filter: ReconstructionFilter<$type>| {
+                    let floor = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let ball = ImplicitNSphere::new(
+                        Point3::new(Meter::new(-0.3), Meter::new(0.5), Meter::new(0.0)),
+                        Meter::new(0.5),
+                    );
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(RenderableGeometry::new(
+                            floor,
+                            LambertMaterial::new(Checkerboard::generate(
+                                RGB::new(1.0, 1.0, 1.0),
+                                RGB::new(0.2, 0.2, 0.2),
+                            )),
+                            Transform3::<$type>::ident(),
+                        )),
+                        Box::new(RenderableGeometry::new(
+                            ball,
+                            PhongMaterial::new(
+                                SingleColorImage::new(
+                                    RGB::<$type>::new(1.0, 0.0, 0.0),
+                                    Vector2::new(1.0, 1.0),
+                                ),
+                                SingleColorImage::new(
+                                    RGB::<$type>::new(1.0, 1.0, 1.0),
+                                    Vector2::new(1.0, 1.0),
+                                ),
+                                16.0,
+                            ),
+                            Transform3::<$type>::ident(),
+                        )),
+                    ];
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> =
+                        vec![Box::new(PointLight::new(
+                            RGB::new(1.0, 1.0, 1.0),
+                            Point3::new(Meter::new(2.0), Meter::new(3.0), Meter::new(1.0)),
+                        ))];
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(3.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-0.3), Meter::new(-1.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Degrees::<$type>::new(40.0).to_radians(),
+                        )),
+                    );
+
+                    let scene = Scene3::new(RGB::new(0.5, 0.7, 1.0), lights, cameras, geometries);
+
+                    // Nine samples per pixel fill one packet and start another.
+                    let renderer = DiffuseRayTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::regular_pattern(3, 3),
+                        0.0001,
+                    )
+                    .with_filter(filter);
+                    let renderer = if packets {
+                        renderer.with_packets()
+                    } else {
+                        renderer
+                    };
+                    renderer.render(scene, "main", Vector2::new(16, 12), 0)
+                };
+
+                let filters = [
+                    ReconstructionFilter::Box,
+                    ReconstructionFilter::Gaussian {
+                        radius: 1.5,
+                        alpha: 2.0,
+                    },
+                ];
+                for filter in filters {
+                    let image = render(false, filter);
+                    let packed = render(true, filter);
+                    for y in 0..12 {
+                        for x in 0..16 {
+                            let p = Point2::new(x, y);
+                            assert_eq!(packed.get(p), image.get(p), "{:?}", p);
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    diffuse_ray_tracer_packets! { f32, diffuse_ray_tracer_packets_f32 }
+    diffuse_ray_tracer_packets! { f64, diffuse_ray_tracer_packets_f64 }
+
+    macro_rules! missed_rays_see_the_environment {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let sky = RGB::<$type>::new(0.5, 0.7, 1.0);
+                let ground = RGB::<$type>::new(0.3, 0.2, 0.1);
+
+                let render = |direction: $type| {
+                    let mut environment = ImageBuffer::new(Vector2::new(8, 4), ground);
+                    for x in 0..8 {
+                        *environment.get_mut(Point2::new(x, 0)) = sky;
+                        *environment.get_mut(Point2::new(x, 1)) = sky;
+                    }
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> =
+                        vec![Box::new(EnvironmentLight::<Meter<$type>>::new(environment))];
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(direction), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                            Degrees::<$type>::new(1.0).to_radians(),
+                        )),
+                    );
+
+                    let scene = Scene3::new(RGB::new(0.0, 0.0, 0.0), lights, cameras, vec![]);
+
+                    let image = DiffuseRayTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1),
+                        0.0001,
+                    )
+                    .render(scene, "main", Vector2::new(1, 1), 0);
+                    image.get(Point2::new(0, 0))
+                };
+
+                assert_eq!(render(1.0), sky);
+                assert_eq!(render(-1.0), ground);
+            }
+        };
+    }
+
+    missed_rays_see_the_environment! { f32, missed_rays_see_the_environment_f32 }
+    missed_rays_see_the_environment! { f64, missed_rays_see_the_environment_f64 }
+
+    macro_rules! filtered_fisheye_render {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let background = RGB::<$type>::new(0.5, 0.7, 1.0);
+
+                let scene = || -> SceneType<Meter<$type>, RGB<$type>> {
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(FisheyeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Degrees::<$type>::new(180.0).to_radians(),
+                        )),
+                    );
+
+                    Scene3::new(background, vec![], cameras, vec![])
+                };
+
+                let tracer = || {
+                    DiffuseRayTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::jittered_patterns(
+                            4,
+                            2,
+                            2,
+                            &mut WichmannHillPRNG::from_seed(3),
+                        ),
+                        0.0001,
+                    )
+                    .with_filter(ReconstructionFilter::Gaussian {
+                        radius: 1.5,
+                        alpha: 2.0,
+                    })
+                };
+
+                let size = Vector2::new(21, 21);
+                let reference = tracer().render(scene(), "main", size, 5);
+                let threaded =
+                    tracer()
+                        .with_threads(4)
+                        .with_tile_size(4)
+                        .render(scene(), "main", size, 5);
+
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let p = Point2::new(x, y);
+                        assert_eq!(reference.get(p), threaded.get(p));
+                    }
+                }
+
+                let center = reference.get(Point2::new(10, 10));
+                assert!((center.red - background.red).abs() < 0.0001);
+                assert!((center.blue - background.blue).abs() < 0.0001);
+                assert_eq!(reference.get(Point2::new(0, 0)), RGB::new(0.0, 0.0, 0.0));
+            }
+        };
+    }
+
+    filtered_fisheye_render! { f32, filtered_fisheye_render_f32 }
+    filtered_fisheye_render! { f64, filtered_fisheye_render_f64 }
+
+    macro_rules! lighting_components_add_up_to_rendered_image {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let scene = || -> SceneType<Meter<$type>, RGB<$type>> {
+                    let plane = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let sphere = ImplicitNSphere::new(
+                        Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                        Meter::new(1.0),
+                    );
+
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(RenderableGeometry::new(
+                            plane,
+                            LambertMaterial::new(Checkerboard::generate(
+                                RGB::new(1.0, 1.0, 1.0),
+                                RGB::new(0.2, 0.2, 0.2),
+                            )),
+                            Transform3::<$type>::ident(),
+                        )),
+                        Box::new(RenderableGeometry::new(
+                            sphere,
+                            PhongMaterial::new(
+                                Checkerboard::generate(
+                                    RGB::new(1.0, 0.0, 0.0),
+                                    RGB::new(0.0, 0.0, 1.0),
+                                ),
+                                Checkerboard::generate(
+                                    RGB::new(1.0, 1.0, 1.0),
+                                    RGB::new(1.0, 1.0, 1.0),
+                                ),
+                                16.0,
+                            ),
+                            Transform3::<$type>::ident(),
+                        )),
+                    ];
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(PointLight::new(
+                            RGB::new(0.5, 0.5, 0.5),
+                            Point3::new(Meter::new(2.0), Meter::new(4.0), Meter::new(2.0)),
+                        )),
+                        Box::new(AmbientLight::new(RGB::new(0.1, 0.1, 0.1))),
+                    ];
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(3.0), Meter::new(4.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-0.5), Meter::new(-1.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Degrees::<$type>::new(90.0).to_radians(),
+                        )),
+                    );
+
+                    Scene3::new(RGB::new(0.1, 0.2, 0.3), lights, cameras, geometries)
+                };
+
+                let renderer = || {
+                    DiffuseRayTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::regular_pattern(2, 2),
+                        0.0001,
+                    )
+                };
+
+                let size = Vector2::new(32, 24);
+
+                let rendered = renderer().render(scene(), "main", size, 0);
+                let components = renderer().render_lighting_components(scene(), "main", size, 0);
+                let combined = components.combined();
+
+                let mut has_specular = false;
+                let mut has_indirect = false;
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let p = Point2::new(x, y);
+                        let expected = rendered.get(p);
+                        let actual = combined.get(p);
+                        assert!((expected.red - actual.red).abs() < 0.0001);
+                        assert!((expected.green - actual.green).abs() < 0.0001);
+                        assert!((expected.blue - actual.blue).abs() < 0.0001);
+
+                        has_specular |= components.direct_specular.get(p).red > 0.0;
+                        has_indirect |= components.indirect_diffuse.get(p).red > 0.0;
+                    }
+                }
+                assert!(has_specular);
+                assert!(has_indirect);
+            }
+        };
+    }
+
+    lighting_components_add_up_to_rendered_image! { f32, lighting_components_add_up_to_rendered_image_f32 }
+    lighting_components_add_up_to_rendered_image! { f64, lighting_components_add_up_to_rendered_image_f64 }
+
+    macro_rules! light_groups_add_up_to_rendered_image {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let scene = || -> SceneType<Meter<$type>, RGB<$type>> {
+                    let plane = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> =
+                        vec![Box::new(RenderableGeometry::new(
+                            plane,
+                            LambertMaterial::new(SingleColorImage::new(
+                                RGB::new(1.0, 1.0, 1.0),
+                                Vector2::new(1.0, 1.0),
+                            )),
+                            Transform3::<$type>::ident(),
+                        ))];
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(
+                            PointLight::new(
+                                RGB::new(0.5, 0.0, 0.0),
+                                Point3::new(Meter::new(-2.0), Meter::new(2.0), Meter::new(0.0)),
+                            )
+                            .with_group(String::from("key")),
+                        ),
+                        Box::new(PointLight::new(
+                            RGB::new(0.0, 0.5, 0.0),
+                            Point3::new(Meter::new(2.0), Meter::new(2.0), Meter::new(0.0)),
+                        )),
+                        Box::new(AmbientLight::new(RGB::new(0.0, 0.0, 0.1))),
+                    ];
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(3.0), Meter::new(4.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-0.5), Meter::new(-1.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Degrees::<$type>::new(90.0).to_radians(),
+                        )),
+                    );
+
+                    Scene3::new(RGB::new(0.1, 0.2, 0.3), lights, cameras, geometries)
+                };
+
+                let renderer = || {
+                    DiffuseRayTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::regular_pattern(2, 2),
+                        0.0001,
+                    )
+                };
+
+                let size = Vector2::new(32, 24);
+
+                let rendered = renderer().render(scene(), "main", size, 0);
+                let light_groups = renderer().render_light_groups(scene(), "main", size, 0);
+                let combined = light_groups.combined();
+
+                let names: Vec<&str> = light_groups
+                    .groups
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect();
+                assert_eq!(names, vec!["key", "default"]);
+
+                let (_, key) = &light_groups.groups[0];
+                let (_, default) = &light_groups.groups[1];
+
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let p = Point2::new(x, y);
+                        let expected = rendered.get(p);
+                        let actual = combined.get(p);
+                        assert!((expected.red - actual.red).abs() < 0.0001);
+                        assert!((expected.green - actual.green).abs() < 0.0001);
+                        assert!((expected.blue - actual.blue).abs() < 0.0001);
+
+                        assert_eq!(key.get(p).green, 0.0);
+                        assert_eq!(key.get(p).blue, 0.0);
+                        assert_eq!(default.get(p).red, 0.0);
+                    }
+                }
+
+                let floor = Point2::new(16, 20);
+                assert!(key.get(floor).red > 0.0);
+                assert!(default.get(floor).green > 0.0);
+                assert!(default.get(floor).blue > 0.0);
+                assert_eq!(light_groups.background.get(floor), RGB::new(0.0, 0.0, 0.0));
+            }
+        };
+    }
+
+    light_groups_add_up_to_rendered_image! { f32, light_groups_add_up_to_rendered_image_f32 }
+    light_groups_add_up_to_rendered_image! { f64, light_groups_add_up_to_rendered_image_f64 }
+
+    macro_rules! shadows_of_occluded_lights {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let scene = || -> SceneType<Meter<$type>, RGB<$type>> {
+                    let plane = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let sphere = ImplicitNSphere::new(
+                        Point3::new(Meter::new(1.0), Meter::new(1.0), Meter::new(-1.0)),
+                        Meter::new(1.0),
+                    );
+                    let material = || {
+                        LambertMaterial::new(SingleColorImage::new(
+                            RGB::new(1.0, 1.0, 1.0),
+                            Vector2::new(1.0, 1.0),
+                        ))
+                    };
+
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(RenderableGeometry::new(
+                            plane,
+                            material(),
+                            Transform3::<$type>::ident(),
+                        )),
+                        Box::new(RenderableGeometry::new(
+                            sphere,
+                            material(),
+                            Transform3::<$type>::ident(),
+                        )),
+                    ];
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(
+                            PointLight::new(
+                                RGB::new(0.5, 0.5, 0.5),
+                                Point3::new(Meter::new(1.0), Meter::new(5.0), Meter::new(-1.0)),
+                            )
+                            .with_name(String::from("sun")),
+                        ),
+                        Box::new(AmbientLight::new(RGB::new(0.1, 0.1, 0.1))),
+                    ];
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(3.0), Meter::new(4.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-0.5), Meter::new(-1.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Degrees::<$type>::new(90.0).to_radians(),
+                        )),
+                    );
+
+                    Scene3::new(RGB::new(0.1, 0.2, 0.3), lights, cameras, geometries)
+                };
+
+                let renderer = || {
+                    DiffuseRayTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::regular_pattern(2, 2),
+                        0.0001,
+                    )
+                };
+
+                let size = Vector2::new(32, 24);
+
+                let rendered = renderer().render(scene(), "main", size, 0);
+                let shadows = renderer().render_shadows(scene(), "main", size, 0);
+
+                let names: Vec<&str> = shadows
+                    .lights
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect();
+                assert_eq!(names, vec!["sun", "1"]);
+
+                let (_, sun) = &shadows.lights[0];
+                let (_, ambient) = &shadows.lights[1];
+
+                let mut shadowed = 0;
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let p = Point2::new(x, y);
+                        assert_eq!(shadows.beauty.get(p), rendered.get(p));
+                        assert_eq!(ambient.get(p).value, 0.0);
+
+                        // Where the sun is occluded, the ambient light still reaches the floor.
+                        let fraction = sun.get(p).value;
+                        assert!((0.0..=1.0).contains(&fraction));
+                        assert!((shadows.combined.get(p).value - fraction / 2.0).abs() < 0.0001);
+                        if fraction == 1.0 {
+                            shadowed += 1;
+                        }
+                    }
+                }
+                assert!(shadowed > 0);
+
+                // The floor away from the sphere is lit.
+                assert_eq!(sun.get(Point2::new(2, 20)).value, 0.0);
+            }
+        };
+    }
+
+    shadows_of_occluded_lights! { f32, shadows_of_occluded_lights_f32 }
+    shadows_of_occluded_lights! { f64, shadows_of_occluded_lights_f64 }
+
+    macro_rules! light_paths_select_contributions {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let scene = || -> SceneType<Meter<$type>, RGB<$type>> {
+                    let plane = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> =
+                        vec![Box::new(RenderableGeometry::new(
+                            plane,
+                            LambertMaterial::new(SingleColorImage::new(
+                                RGB::new(1.0, 1.0, 1.0),
+                                Vector2::new(1.0, 1.0),
+                            )),
+                            Transform3::<$type>::ident(),
+                        ))];
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(
+                            PointLight::new(
+                                RGB::new(0.5, 0.0, 0.0),
+                                Point3::new(Meter::new(-2.0), Meter::new(2.0), Meter::new(0.0)),
+                            )
+                            .with_name(String::from("key")),
+                        ),
+                        Box::new(PointLight::new(
+                            RGB::new(0.0, 0.5, 0.0),
+                            Point3::new(Meter::new(2.0), Meter::new(2.0), Meter::new(0.0)),
+                        )),
+                        Box::new(AmbientLight::new(RGB::new(0.0, 0.0, 0.1))),
+                    ];
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(3.0), Meter::new(4.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-0.5), Meter::new(-1.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Degrees::<$type>::new(90.0).to_radians(),
+                        )),
+                    );
+
+                    Scene3::new(RGB::new(0.1, 0.2, 0.3), lights, cameras, geometries)
+                };
+
+                let expressions: Vec<LightPathExpression> = ["C.*", "CD'key'", "CB", "CSL"]
+                    .iter()
+                    .map(|e| LightPathExpression::parse(e).unwrap())
+                    .collect();
+
+                let size = Vector2::new(32, 24);
+                let light_paths = DiffuseRayTracer::<Meter<$type>>::new(
+                    SamplingPatternSet::<Point2<$type>>::regular_pattern(2, 2),
+                    0.0001,
+                )
+                .render_light_paths(scene(), "main", size, 0, &expressions);
+
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let p = Point2::new(x, y);
+                        let expected = light_paths.beauty.get(p);
+                        let actual = light_paths.paths[0].get(p);
+                        assert!((expected.red - actual.red).abs() < 0.0001);
+                        assert!((expected.green - actual.green).abs() < 0.0001);
+                        assert!((expected.blue - actual.blue).abs() < 0.0001);
+
+                        assert_eq!(light_paths.paths[1].get(p).green, 0.0);
+                        assert_eq!(light_paths.paths[1].get(p).blue, 0.0);
+                        assert_eq!(light_paths.paths[3].get(p), RGB::new(0.0, 0.0, 0.0));
+                    }
+                }
+
+                let floor = Point2::new(16, 20);
+                assert!(light_paths.paths[1].get(floor).red > 0.0);
+                assert_eq!(light_paths.paths[2].get(floor), RGB::new(0.0, 0.0, 0.0));
+                assert_eq!(
+                    light_paths.excluding(2).get(floor),
+                    light_paths.beauty.get(floor)
+                );
+
+                let sky = Point2::new(16, 0);
+                assert_eq!(light_paths.paths[2].get(sky), RGB::new(0.1, 0.2, 0.3));
+                assert_eq!(light_paths.excluding(2).get(sky), RGB::new(0.0, 0.0, 0.0));
+            }
+        };
+    }
+
+    light_paths_select_contributions! { f32, light_paths_select_contributions_f32 }
+    light_paths_select_contributions! { f64, light_paths_select_contributions_f64 }
+
+    #[test]
+    fn cancelled_render_skips_remaining_tiles() {
+        let metrics = Metrics::new();
+        let origins = render_tiles(1, 4, Vector2::new(8, 8), Some(&metrics), |origin, _| {
+            metrics.cancel();
+            origin
+        });
+
+        assert_eq!(origins, vec![Point2::new(0, 0)]);
+        assert_eq!(metrics.snapshot().tiles, 1);
+        assert_eq!(metrics.snapshot().tiles_total, 4);
+    }
+}
@@ -0,0 +1,273 @@
+use std::io::{self, Read, Write};
+
+use colors::RGB;
+use image::{Image, ImageBuffer, WritableImage};
+use math::{Point2, Vector2};
+
+use crate::camera::CropWindow;
+
+const REQUEST_MAGIC: &[u8; 16] = b"rustracer tile 1";
+const RESULT_MAGIC: &[u8; 16] = b"rustracer pix 1 ";
+// The longest argument or message that is read, so a peer can not make the other side allocate
+// arbitrary amounts of memory.
+const MAX_STRING_LENGTH: usize = 1 << 16;
+
+// A tile a coordinator asks a worker to render. The worker renders the window of the image the
+// arguments describe, with the seed of the tile. All numbers are sent in big endian, the
+// arguments as their length followed by their bytes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TileRequest {
+    pub args: Vec<String>,
+    pub window: CropWindow,
+    pub seed: u128,
+}
+
+impl TileRequest {
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let mut data = Vec::new();
+        data.extend_from_slice(REQUEST_MAGIC);
+        data.extend_from_slice(&self.seed.to_be_bytes());
+        for corner in [
+            self.window.min.x,
+            self.window.min.y,
+            self.window.max.x,
+            self.window.max.y,
+        ] {
+            data.extend_from_slice(&(corner as u32).to_be_bytes());
+        }
+        data.extend_from_slice(&(self.args.len() as u32).to_be_bytes());
+        for arg in &self.args {
+            data.extend_from_slice(&(arg.len() as u32).to_be_bytes());
+            data.extend_from_slice(arg.as_bytes());
+        }
+        writer.write_all(&data)?;
+        writer.flush()
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> io::Result<TileRequest> {
+        if read_array::<16>(reader)? != *REQUEST_MAGIC {
+            return Err(invalid_data("The request is not a tile."));
+        }
+        let seed = u128::from_be_bytes(read_array(reader)?);
+        let mut corners = [0; 4];
+        for corner in &mut corners {
+            *corner = read_u32(reader)? as usize;
+        }
+        let [x0, y0, x1, y1] = corners;
+        let args = (0..read_u32(reader)?)
+            .map(|_| read_string(reader))
+            .collect::<io::Result<Vec<String>>>()?;
+
+        Ok(TileRequest {
+            args,
+            window: CropWindow::new(Point2::new(x0, y0), Point2::new(x1, y1)),
+            seed,
+        })
+    }
+}
+
+// The answer of a worker is either the rendered tile, with the size of a channel in bytes, the
+// width and the height and the pixels in rows from the top, or the message why it could not be
+// rendered. A tile of another size than the one asked for is rejected before its pixels are read.
+macro_rules! implement_tile_transfer_for {
+    ($($type: ty)*) => {$(
+        impl TileResult for ImageBuffer<RGB<$type>> {
+            fn write_to(
+                tile: Result<&Self, &str>,
+                writer: &mut impl Write,
+            ) -> io::Result<()> {
+                let mut data = Vec::new();
+                data.extend_from_slice(RESULT_MAGIC);
+                match tile {
+                    Ok(image) => {
+                        let size = image.size();
+                        data.push(0);
+                        data.extend_from_slice(&(size_of::<$type>() as u32).to_be_bytes());
+                        data.extend_from_slice(&(size.x as u32).to_be_bytes());
+                        data.extend_from_slice(&(size.y as u32).to_be_bytes());
+                        for y in 0..size.y {
+                            for x in 0..size.x {
+                                let color = image.get(Point2::new(x, y));
+                                for channel in [color.red, color.green, color.blue] {
+                                    data.extend_from_slice(&channel.to_be_bytes());
+                                }
+                            }
+                        }
+                    }
+                    Err(message) => {
+                        data.push(1);
+                        data.extend_from_slice(&(message.len() as u32).to_be_bytes());
+                        data.extend_from_slice(message.as_bytes());
+                    }
+                }
+                writer.write_all(&data)?;
+                writer.flush()
+            }
+
+            fn read_from(
+                reader: &mut impl Read,
+                size: Vector2<usize>,
+            ) -> io::Result<Result<Self, String>> {
+                if read_array::<16>(reader)? != *RESULT_MAGIC {
+                    return Err(invalid_data("The answer is not a tile."));
+                }
+                if read_array::<1>(reader)? != [0] {
+                    return Ok(Err(read_string(reader)?));
+                }
+                let channel_size = read_u32(reader)?;
+                if channel_size as usize != size_of::<$type>() {
+                    return Err(invalid_data(&format!(
+                        "The tile has channels of {} bytes instead of {}.",
+                        channel_size,
+                        size_of::<$type>()
+                    )));
+                }
+                let width = read_u32(reader)? as usize;
+                let height = read_u32(reader)? as usize;
+                if Vector2::new(width, height) != size {
+                    return Err(invalid_data(&format!(
+                        "The tile is {}x{} pixels instead of {}x{}.",
+                        width, height, size.x, size.y
+                    )));
+                }
+
+                let mut image = ImageBuffer::new(size, RGB::default());
+                let mut value = || -> io::Result<$type> {
+                    Ok(<$type>::from_be_bytes(read_array(reader)?))
+                };
+                for y in 0..height {
+                    for x in 0..width {
+                        *image.get_mut(Point2::new(x, y)) = RGB::new(value()?, value()?, value()?);
+                    }
+                }
+                Ok(Ok(image))
+            }
+        }
+    )*}
+}
+
+pub trait TileResult: Sized {
+    fn write_to(tile: Result<&Self, &str>, writer: &mut impl Write) -> io::Result<()>;
+    fn read_from(reader: &mut impl Read, size: Vector2<usize>) -> io::Result<Result<Self, String>>;
+}
+
+implement_tile_transfer_for! { f32 f64 }
+
+// The tiles an image is split into, in rows from the top. The tiles at the right and the bottom
+// edge are cut off by the image.
+pub fn tiles(size: Vector2<usize>, tile_size: usize) -> Vec<CropWindow> {
+    let mut tiles = Vec::new();
+    for y in (0..size.y).step_by(tile_size) {
+        for x in (0..size.x).step_by(tile_size) {
+            tiles.push(CropWindow::new(
+                Point2::new(x, y),
+                Point2::new((x + tile_size).min(size.x), (y + tile_size).min(size.y)),
+            ));
+        }
+    }
+    tiles
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut data = [0; N];
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    Ok(u32::from_be_bytes(read_array(reader)?))
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    let length = read_u32(reader)? as usize;
+    if length > MAX_STRING_LENGTH {
+        return Err(invalid_data("The text is too long."));
+    }
+    let mut data = vec![0; length];
+    reader.read_exact(&mut data)?;
+    String::from_utf8(data).map_err(|_| invalid_data("The text is not UTF-8."))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_request_round_trip() {
+        let request = TileRequest {
+            args: vec![
+                String::from("scene.scene"),
+                String::from("--size"),
+                String::new(),
+            ],
+            window: CropWindow::new(Point2::new(64, 0), Point2::new(100, 64)),
+            seed: 1 << 100,
+        };
+        let mut data = Vec::new();
+        request.write_to(&mut data).unwrap();
+
+        assert_eq!(
+            TileRequest::read_from(&mut data.as_slice()).unwrap(),
+            request
+        );
+        assert!(TileRequest::read_from(&mut &data[..data.len() - 1]).is_err());
+        assert!(TileRequest::read_from(&mut &b"not a tile request"[..]).is_err());
+
+        // The length of an argument is checked before it is allocated.
+        let mut data = REQUEST_MAGIC.to_vec();
+        data.extend_from_slice(&[0; 32]);
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&u32::MAX.to_be_bytes());
+        let error = TileRequest::read_from(&mut data.as_slice()).unwrap_err();
+        assert_eq!(error.to_string(), "The text is too long.");
+    }
+
+    macro_rules! tile_result_round_trip {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut image = ImageBuffer::new(Vector2::new(3, 2), RGB::<$type>::default());
+                *image.get_mut(Point2::new(2, 1)) = RGB::new(0.5, 1.0, 1e-9);
+                *image.get_mut(Point2::new(0, 0)) = RGB::new(0.25, 0.125, 2.0);
+
+                let mut data = Vec::new();
+                ImageBuffer::write_to(Ok(&image), &mut data).unwrap();
+                let tile = ImageBuffer::<RGB<$type>>::read_from(&mut data.as_slice(), image.size())
+                    .unwrap();
+                assert_eq!(tile, Ok(image));
+
+                // A tile of another size is rejected before its pixels are read.
+                let other = Vector2::new(1 << 20, 1 << 20);
+                assert!(ImageBuffer::<RGB<$type>>::read_from(&mut data.as_slice(), other).is_err());
+
+                let mut data = Vec::new();
+                ImageBuffer::<RGB<$type>>::write_to(Err("No scene."), &mut data).unwrap();
+                let tile =
+                    ImageBuffer::<RGB<$type>>::read_from(&mut data.as_slice(), other).unwrap();
+                assert_eq!(tile, Err(String::from("No scene.")));
+            }
+        };
+    }
+
+    tile_result_round_trip! { f32, tile_result_round_trip_f32 }
+    tile_result_round_trip! { f64, tile_result_round_trip_f64 }
+
+    #[test]
+    fn tiles_cover_the_image() {
+        let tiles = tiles(Vector2::new(100, 70), 64);
+
+        assert_eq!(
+            tiles,
+            vec![
+                CropWindow::new(Point2::new(0, 0), Point2::new(64, 64)),
+                CropWindow::new(Point2::new(64, 0), Point2::new(100, 64)),
+                CropWindow::new(Point2::new(0, 64), Point2::new(64, 70)),
+                CropWindow::new(Point2::new(64, 64), Point2::new(100, 70)),
+            ]
+        );
+    }
+}
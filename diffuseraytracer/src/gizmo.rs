@@ -0,0 +1,305 @@
+use cg_basics::material::EmissiveMaterial;
+use cg_basics::scene_graph::Scene3;
+use colors::Color;
+use math::geometry::{ParametricLine, SurfacePoint};
+use math::{Point2, Point3, Vector2, Vector3};
+use random::WichmannHillPRNG;
+use sampling::SamplingPattern;
+use traits::floating_point::{Cos, Sin};
+use traits::{ConvenientNumber, FloatingPoint, Half, One, Pi, Sqrt, Zero};
+use units::length::Length;
+
+use crate::camera::RaytracingCamera;
+use crate::light::Light;
+use crate::material::Material;
+use crate::{Hits, Renderable};
+
+type SceneType<T, C> =
+    Scene3<C, Box<dyn Light<T, C>>, Box<dyn RaytracingCamera<T>>, Box<dyn Renderable<T, C>>>;
+
+// The number of edges a circle of a gizmo is made of.
+const CIRCLE_SEGMENTS: u16 = 24;
+
+// A wireframe of straight edges, drawn as thin tubes. It shows where the cameras and lights of a
+// scene are, to debug its layout, and does not cast shadows.
+pub struct Wireframe<T: Length, M> {
+    pub edges: Vec<(Point3<T>, Point3<T>)>,
+    pub radius: T,
+    pub material: M,
+}
+
+impl<T: Length, M> Wireframe<T, M> {
+    pub fn new(edges: Vec<(Point3<T>, Point3<T>)>, radius: T, material: M) -> Wireframe<T, M> {
+        Wireframe {
+            edges,
+            radius,
+            material,
+        }
+    }
+}
+
+// An edge is hit where the ray passes it closer than the radius. The tubes have no real surface,
+// so the normal always faces the ray.
+impl<T: Length, M> Renderable<T, <M as Material<T>>::ColorType> for Wireframe<T, M>
+where
+    T::ValueType: FloatingPoint,
+    T::AreaType: Sqrt<Output = T>,
+    M: Material<T>,
+    <M as Material<T>>::ColorType: Color<ChannelType = T::ValueType>,
+{
+    fn intersect(
+        &self,
+        ray: ParametricLine<Point3<T>, Vector3<T>>,
+    ) -> Hits<'_, T, <M as Material<T>>::ColorType> {
+        let zero = T::ValueType::zero();
+        let one = T::ValueType::one();
+        let radius = self.radius / T::one();
+        let d = ray.direction / T::one();
+        let n = (-d).normalized().as_normal();
+
+        self.edges
+            .iter()
+            .filter_map(|(a, b)| {
+                let e = (*b - *a) / T::one();
+                let w = (ray.origin - *a) / T::one();
+
+                // The closest points of the ray and the line through the edge, with the one on
+                // the line moved onto the edge.
+                let (dd, de, ee) = (d.dot(d), d.dot(e), e.dot(e));
+                let (dw, ew) = (d.dot(w), e.dot(w));
+                let denominator = dd * ee - de * de;
+                let mut s = if denominator > zero {
+                    (dd * ew - de * dw) / denominator
+                } else {
+                    zero
+                };
+                if s < zero {
+                    s = zero;
+                } else if s > one {
+                    s = one;
+                }
+                let t = (de * s - dw) / dd;
+
+                let gap = w + d * t - e * s;
+                if t > zero && gap.dot(gap) < radius * radius {
+                    Some((
+                        t,
+                        SurfacePoint::new(
+                            ray.origin + ray.direction * t,
+                            n,
+                            Point2::new(zero, zero),
+                        ),
+                        &self.material
+                            as &dyn Material<T, ColorType = <M as Material<T>>::ColorType>,
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn casts_shadows(&self) -> bool {
+        false
+    }
+}
+
+// The edges of the volume a camera sees, up to a distance. They follow the rays through the
+// corners of the image, so the frustum shows the distortion of the lens as well. Corners that
+// are outside of the image circle of a lens are left out.
+pub fn camera_frustum<T: Length>(
+    camera: &dyn RaytracingCamera<T>,
+    size: Vector2<T::ValueType>,
+    distance: T,
+) -> Vec<(Point3<T>, Point3<T>)>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    T::AreaType: Sqrt<Output = T>,
+{
+    let zero = T::ValueType::zero();
+    let half = T::ValueType::one().half();
+
+    // Rays through the middle of the lens.
+    let pattern = SamplingPattern::new(vec![Point2::new(half, half)]);
+    let mut rnd = WichmannHillPRNG::from_seed(0);
+    let corners: Vec<(Point3<T>, Point3<T>)> = [
+        Point2::new(zero, zero),
+        Point2::new(size.x, zero),
+        Point2::new(size.x, size.y),
+        Point2::new(zero, size.y),
+    ]
+    .into_iter()
+    .filter_map(|p| camera.ray_for(size, p, &pattern, &mut rnd))
+    .map(|ray| {
+        let direction = (ray.direction / T::one()).normalized();
+        (ray.origin, ray.origin + direction * distance)
+    })
+    .collect();
+
+    let mut edges = corners.clone();
+    for (i, (_, end)) in corners.iter().enumerate() {
+        let (_, next) = corners[(i + 1) % corners.len()];
+        edges.push((*end, next));
+    }
+    edges
+}
+
+// The edges of a circle around a center, spanned by two perpendicular radii.
+pub fn circle<T: Length>(
+    center: Point3<T>,
+    u: Vector3<T>,
+    v: Vector3<T>,
+) -> Vec<(Point3<T>, Point3<T>)>
+where
+    T::ValueType: FloatingPoint,
+    u16: Into<T::ValueType>,
+{
+    let point = |i: u16| {
+        let phi = (T::ValueType::PI + T::ValueType::PI) * i.into() / CIRCLE_SEGMENTS.into();
+        center + u * phi.cos() + v * phi.sin()
+    };
+    (0..CIRCLE_SEGMENTS)
+        .map(|i| (point(i), point(i + 1)))
+        .collect()
+}
+
+// Adds gizmos for all cameras but the one that renders the image, which would only see its
+// frustum from the inside, and for all lights that have a position. Both eyes of a stereo camera
+// count as the camera. The size is the length of the frustums, and the extent of lights without a
+// shape of their own.
+pub fn add_gizmos<T, C>(
+    scene: &mut SceneType<T, C>,
+    camera_name: &str,
+    image_size: Vector2<T::ValueType>,
+    size: T,
+    camera_color: C,
+    light_color: C,
+) where
+    T: Length + 'static,
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    T::AreaType: Sqrt<Output = T>,
+    C: Color<ChannelType = T::ValueType> + 'static,
+    u16: Into<T::ValueType>,
+{
+    // Thin enough not to hide much of the scene, thick enough to be seen from afar.
+    let radius = size / 200.into();
+
+    let camera_edges: Vec<_> = scene
+        .cameras
+        .iter()
+        .filter(|(name, _)| {
+            name.as_str() != camera_name && !name.starts_with(&format!("{}.", camera_name))
+        })
+        .flat_map(|(_, camera)| camera_frustum(camera.as_ref(), image_size, size))
+        .collect();
+    let light_edges: Vec<_> = scene
+        .lights
+        .iter()
+        .flat_map(|light| light.gizmo(size))
+        .collect();
+
+    for (edges, color) in [(camera_edges, camera_color), (light_edges, light_color)] {
+        if !edges.is_empty() {
+            let wireframe = Wireframe::new(edges, radius, EmissiveMaterial::new(color));
+            scene.geometries.push(Box::new(wireframe));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::RGB;
+    use traits::ToRadians;
+    use units::angle::Degrees;
+    use units::length::Meter;
+
+    use cg_basics::camera::PinholeCamera;
+
+    macro_rules! wireframe_intersect {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let edges = vec![(
+                    Point3::new(Meter::new(-1.0), Meter::new(0.0), Meter::new(0.0)),
+                    Point3::new(Meter::new(1.0), Meter::new(0.0), Meter::new(0.0)),
+                )];
+                let wireframe: Wireframe<Meter<$type>, _> = Wireframe::new(
+                    edges,
+                    Meter::new(0.1),
+                    EmissiveMaterial::new(RGB::new(1.0 as $type, 1.0, 0.0)),
+                );
+
+                // The ray passes the edge within the radius.
+                let ray = ParametricLine::new(
+                    Point3::new(Meter::new(0.5), Meter::new(0.05), Meter::new(5.0)),
+                    Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                );
+                let hits = wireframe.intersect(ray);
+                assert_eq!(hits.len(), 1);
+                assert!((hits[0].0 - 5.0).abs() < 0.0001);
+                assert_eq!(hits[0].1.n.as_vector(), Vector3::new(0.0, 0.0, 1.0));
+
+                // Further away than the radius, beyond the end of the edge and behind the ray.
+                let miss = |origin: Point3<Meter<$type>>| {
+                    wireframe
+                        .intersect(ParametricLine::new(origin, ray.direction))
+                        .is_empty()
+                };
+                assert!(miss(Point3::new(
+                    Meter::new(0.5),
+                    Meter::new(0.2),
+                    Meter::new(5.0)
+                )));
+                assert!(miss(Point3::new(
+                    Meter::new(1.2),
+                    Meter::new(0.0),
+                    Meter::new(5.0)
+                )));
+                assert!(miss(Point3::new(
+                    Meter::new(0.5),
+                    Meter::new(0.0),
+                    Meter::new(-5.0)
+                )));
+                assert!(!wireframe.casts_shadows());
+            }
+        };
+    }
+
+    wireframe_intersect! { f32, wireframe_intersect_f32 }
+    wireframe_intersect! { f64, wireframe_intersect_f64 }
+
+    macro_rules! camera_frustum_edges {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let e = Point3::new(Meter::new(1.0), Meter::new(2.0), Meter::new(3.0));
+                let camera = PinholeCamera::new(
+                    e,
+                    Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                    Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                    Degrees::<$type>::new(90.0).to_radians(),
+                );
+
+                let edges = camera_frustum(&camera, Vector2::new(640.0, 480.0), Meter::new(2.0));
+
+                // Four edges from the eye to the corners, and four around the far end.
+                assert_eq!(edges.len(), 8);
+                for (start, end) in &edges[..4] {
+                    assert_eq!(*start, e);
+                    let d = (*end - e) / Meter::new(1.0);
+                    assert!((d.dot(d).sqrt() - 2.0).abs() < 0.0001);
+                    assert!(d.z < 0.0);
+                }
+                for i in 0..4 {
+                    assert_eq!(edges[4 + i].0, edges[i].1);
+                    assert_eq!(edges[4 + i].1, edges[(i + 1) % 4].1);
+                }
+            }
+        };
+    }
+
+    camera_frustum_edges! { f32, camera_frustum_edges_f32 }
+    camera_frustum_edges! { f64, camera_frustum_edges_f64 }
+}
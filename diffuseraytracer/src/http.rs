@@ -0,0 +1,228 @@
+use std::io::{self, BufRead, Write};
+
+// The largest body of a request that is accepted, so a client can not exhaust the memory.
+pub const MAX_BODY_SIZE: usize = 64 * 1024 * 1024;
+
+// A request of HTTP/1.1, with the path split from the query and the query decoded into its
+// parameters in the order they are given.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    // Reads the request line, the headers and the body, whose length is given by the
+    // Content-Length header. Chunked bodies are not supported.
+    pub fn read_from(reader: &mut impl BufRead) -> io::Result<Request> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut parts = line.split_whitespace();
+        let (Some(method), Some(target), Some(_)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid_data("Malformed request line."));
+        };
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let (method, path, query) = (method.to_string(), percent_decode(path), parse_query(query));
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 {
+                return Err(invalid_data("The headers end too early."));
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            let Some((name, value)) = header.split_once(':') else {
+                return Err(invalid_data("Malformed header."));
+            };
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| invalid_data("Malformed content length."))?;
+            }
+            if name.eq_ignore_ascii_case("transfer-encoding") {
+                return Err(invalid_data("Chunked bodies are not supported."));
+            }
+        }
+        if content_length > MAX_BODY_SIZE {
+            return Err(invalid_data("The body is too large."));
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        Ok(Request {
+            method,
+            path,
+            query,
+            body,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: Vec<u8>) -> Response {
+        Response {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    pub fn text(status: u16, text: &str) -> Response {
+        let mut body = text.as_bytes().to_vec();
+        body.push(b'\n');
+        Response::new(status, "text/plain; charset=utf-8", body)
+    }
+
+    // The connection is closed after each response, so a client reads the body to the end.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason(self.status),
+            self.content_type,
+            self.body.len()
+        )?;
+        writer.write_all(&self.body)?;
+        writer.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        422 => "Unprocessable Entity",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+// The parameters of a query like size=320+240&packets, where a parameter without a value has an
+// empty one.
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|parameter| !parameter.is_empty())
+        .map(|parameter| {
+            let (name, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+            (percent_decode(name), percent_decode(value))
+        })
+        .collect()
+}
+
+// Decodes the escapes like %2F of a URL and the plus signs that stand for spaces. Malformed
+// escapes are kept as they are.
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => decoded.push(b' '),
+            b'%' if index + 2 < bytes.len() => {
+                let digits = (hex_digit(bytes[index + 1]), hex_digit(bytes[index + 2]));
+                match digits {
+                    (Some(high), Some(low)) => {
+                        decoded.push(high << 4 | low);
+                        index += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex_digit(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|digit| digit as u8)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_request() {
+        let data = b"POST /render?size=320+240&integrator=path&packets HTTP/1.1\r\n\
+                     Host: localhost\r\n\
+                     content-length: 5\r\n\
+                     \r\n\
+                     scene";
+        let request = Request::read_from(&mut &data[..]).unwrap();
+
+        assert_eq!(
+            request,
+            Request {
+                method: String::from("POST"),
+                path: String::from("/render"),
+                query: vec![
+                    (String::from("size"), String::from("320 240")),
+                    (String::from("integrator"), String::from("path")),
+                    (String::from("packets"), String::new()),
+                ],
+                body: b"scene".to_vec(),
+            }
+        );
+    }
+
+    #[test]
+    fn read_malformed_request() {
+        assert!(Request::read_from(&mut &b"GET\r\n\r\n"[..]).is_err());
+        assert!(Request::read_from(&mut &b"GET / HTTP/1.1\r\nHost\r\n\r\n"[..]).is_err());
+        assert!(Request::read_from(&mut &b"GET / HTTP/1.1\r\nHost: a\r\n"[..]).is_err());
+        assert!(
+            Request::read_from(&mut &b"POST / HTTP/1.1\r\nContent-Length: 9\r\n\r\nshort"[..])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn decode_percent_escapes() {
+        assert_eq!(percent_decode("a%20b+c%2Fd"), "a b c/d");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+    }
+
+    #[test]
+    fn write_response() {
+        let mut data = Vec::new();
+        Response::text(404, "Not here.")
+            .write_to(&mut data)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            "HTTP/1.1 404 Not Found\r\n\
+             Content-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 10\r\n\
+             Connection: close\r\n\
+             \r\n\
+             Not here.\n"
+        );
+    }
+}
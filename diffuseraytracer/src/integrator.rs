@@ -0,0 +1,240 @@
+use std::cell::Cell;
+use std::sync::Arc;
+
+use crate::camera::RaytracingCamera;
+use crate::diffuse_ray_tracer::render_tiles;
+use crate::light::Light;
+use crate::metrics::Metrics;
+use crate::whitted_ray_tracer::{Hit, Tracer};
+use crate::{Renderable, PACKET_WIDTH};
+use cg_basics::scene_graph::Scene3;
+use colors::Color;
+use image::{ImageBuffer, WritableImage};
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
+use random::{RandomNumberGenerator, WichmannHillPRNG};
+use sampling::{AdaptiveSampling, SampleStatistics, SamplingPatternSet};
+use traits::{ConvenientNumber, FloatingPoint, One, Sqrt, Zero};
+use units::length::Length;
+
+type SceneType<T, C> =
+    Scene3<C, Box<dyn Light<T, C>>, Box<dyn RaytracingCamera<T>>, Box<dyn Renderable<T, C>>>;
+
+// The settings the driver renders the image of an integrator with.
+pub(crate) struct Driver<T: Length> {
+    pub(crate) sampling_patterns: SamplingPatternSet<Point2<T::ValueType>>,
+    pub(crate) shadow_tolerance: T::ValueType,
+    pub(crate) threads: usize,
+    pub(crate) tile_size: usize,
+    pub(crate) adaptive_sampling: Option<AdaptiveSampling<T::ValueType>>,
+    pub(crate) metrics: Arc<Metrics>,
+}
+
+impl<T: Length> Driver<T> {
+    pub(crate) fn new(
+        sampling_patterns: SamplingPatternSet<Point2<T::ValueType>>,
+        shadow_tolerance: T::ValueType,
+    ) -> Driver<T> {
+        Driver {
+            sampling_patterns,
+            shadow_tolerance,
+            threads: 1,
+            tile_size: 16,
+            adaptive_sampling: None,
+            metrics: Arc::new(Metrics::new()),
+        }
+    }
+}
+
+// An integrator estimates the light arriving along a single camera ray. The driver draws the
+// camera rays of every pixel, takes further patterns while adaptive sampling asks for them, and
+// averages the samples by the solid angle the camera sees them under.
+pub(crate) trait Integrator<T: Length, C: Color<ChannelType = T::ValueType>>: Sync {
+    fn driver(&self) -> &Driver<T>;
+
+    // The light arriving along a camera ray. The camera background is the procedural background
+    // of the scene in the direction of the ray, if the scene has one.
+    fn trace_camera_ray(
+        &self,
+        tracer: &Tracer<T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        camera_background: Option<C>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C;
+
+    // Intersects the camera rays of a pattern with the scene in packets, and passes their hits to
+    // trace_camera_hit. The rays of a pattern are all drawn before the first one is traced.
+    fn packets(&self) -> bool {
+        false
+    }
+
+    // The light arriving along a camera ray whose closest hit is already known.
+    fn trace_camera_hit<'a>(
+        &self,
+        tracer: &Tracer<'a, T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        _hit: Option<Hit<'a, T, C>>,
+        camera_background: Option<C>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C {
+        self.trace_camera_ray(tracer, r, camera_background, rnd)
+    }
+}
+
+// Renders the image of an integrator without consuming it or the scene, e.g. for one of several
+// passes with different seeds.
+pub(crate) fn render_pass<T: Length, C: Color<ChannelType = T::ValueType>>(
+    integrator: &impl Integrator<T, C>,
+    scene: &SceneType<T, C>,
+    camera_id: &str,
+    size: Vector2<usize>,
+    seed: u128,
+) -> ImageBuffer<C>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    T::AreaType: Sqrt<Output = T>,
+    u16: Into<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+{
+    let driver = integrator.driver();
+    let camera = scene.cameras[camera_id].as_ref();
+
+    driver.metrics.pixels_total.add((size.x * size.y) as u64);
+    let _render_time = driver.metrics.render_time.start();
+
+    let mut image_buffer = ImageBuffer::new(size, C::default());
+    for (p, color) in render_tiles(
+        driver.threads,
+        driver.tile_size,
+        size,
+        Some(&driver.metrics),
+        |origin, extent| {
+            let mut rendered = Vec::with_capacity(extent.x * extent.y);
+            for y in origin.y..(origin.y + extent.y) {
+                for x in origin.x..(origin.x + extent.x) {
+                    let p = Point2::new(x, y);
+                    let mut rnd = WichmannHillPRNG::for_index(seed, (y * size.x + x) as u128);
+                    rendered.push((
+                        p,
+                        render_pixel(integrator, scene, camera, p, size, &mut rnd),
+                    ));
+                }
+            }
+            rendered
+        },
+    )
+    .into_iter()
+    .flatten()
+    {
+        *image_buffer.get_mut(p) = color;
+    }
+
+    image_buffer
+}
+
+fn render_pixel<T: Length, C: Color<ChannelType = T::ValueType>>(
+    integrator: &impl Integrator<T, C>,
+    scene: &SceneType<T, C>,
+    camera: &dyn RaytracingCamera<T>,
+    p: Point2<usize>,
+    size: Vector2<usize>,
+    rnd: &mut WichmannHillPRNG,
+) -> C
+where
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    T::AreaType: Sqrt<Output = T>,
+    u16: Into<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+{
+    let driver = integrator.driver();
+    let float_size = Vector2::<T::ValueType>::new((size.x as u16).into(), (size.y as u16).into());
+    let rays = Cell::new(0);
+    let shadow_rays = Cell::new(0);
+    let tracer = Tracer {
+        scene,
+        time: Zero::zero(),
+        shadow_tolerance: driver.shadow_tolerance,
+        rays: &rays,
+        shadow_rays: &shadow_rays,
+    };
+
+    let mut sum = C::default();
+    let mut counter = T::ValueType::zero();
+    // One pattern per pixel, unless adaptive sampling asks for more.
+    let mut statistics = SampleStatistics::new();
+    loop {
+        let pattern = driver.sampling_patterns.draw_pattern(rnd);
+        let mut samples = Vec::new();
+        for i in 0..pattern.len() {
+            let sp = Point2::<T::ValueType>::new(
+                (p.x as u16).into(),
+                ((size.y - p.y - 1) as u16).into(),
+            ) + pattern[i].as_vector();
+
+            // Samples the camera does not see anything for count as black.
+            let weight = camera.solid_angle(float_size, sp);
+            counter += weight;
+
+            let lens_pattern = driver.sampling_patterns.draw_pattern(rnd);
+            let Some(r) = camera.ray_for(float_size, sp, lens_pattern, rnd) else {
+                statistics.add(Zero::zero());
+                continue;
+            };
+            let time_pattern = driver.sampling_patterns.draw_pattern(rnd);
+            let time = camera.shutter().time(time_pattern.draw_point(rnd).x);
+
+            let background = scene.background.as_ref().map(|background| {
+                background.color_for(
+                    r.direction.normalized(),
+                    Point2::new(sp.x / float_size.x, sp.y / float_size.y),
+                )
+            });
+            if integrator.packets() {
+                samples.push((weight, r, time, background));
+                continue;
+            }
+
+            let tracer = Tracer { time, ..tracer };
+            let color = integrator.trace_camera_ray(&tracer, r, background, rnd);
+            statistics.add(color.max_channel());
+            sum = sum + color * weight;
+        }
+
+        for packet in samples.chunks(PACKET_WIDTH) {
+            let camera_rays: Vec<_> = packet.iter().map(|(_, r, _, _)| *r).collect();
+            let times: Vec<_> = packet.iter().map(|(_, _, time, _)| *time).collect();
+            let hits = tracer.closest_hits(&camera_rays, &times, Zero::zero());
+            for ((weight, r, time, background), hit) in packet.iter().zip(hits) {
+                let tracer = Tracer {
+                    time: *time,
+                    ..tracer
+                };
+                let color = integrator.trace_camera_hit(&tracer, *r, hit, *background, rnd);
+                statistics.add(color.max_channel());
+                sum = sum + color * *weight;
+            }
+        }
+
+        if driver
+            .adaptive_sampling
+            .is_none_or(|adaptive| adaptive.is_done(&statistics))
+        {
+            break;
+        }
+    }
+
+    let geometries = scene.geometries.len() as u64;
+    driver.metrics.camera_rays.add(rays.get());
+    driver.metrics.shadow_rays.add(shadow_rays.get());
+    driver
+        .metrics
+        .intersection_tests
+        .add((rays.get() + shadow_rays.get()) * geometries);
+    driver.metrics.pixel_done(p, statistics.samples() as u64);
+
+    if counter > Zero::zero() {
+        sum * (T::ValueType::one() / counter)
+    } else {
+        sum
+    }
+}
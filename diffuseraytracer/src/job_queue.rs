@@ -0,0 +1,394 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// The extension of the files that hold the jobs of a queue.
+const JOB_EXTENSION: &str = "job";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JobState {
+    Queued,
+    Running,
+    Paused,
+    Done,
+    Failed,
+}
+
+impl JobState {
+    fn parse(state: &str) -> Option<JobState> {
+        match state {
+            "queued" => Some(JobState::Queued),
+            "running" => Some(JobState::Running),
+            "paused" => Some(JobState::Paused),
+            "done" => Some(JobState::Done),
+            "failed" => Some(JobState::Failed),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Paused => "paused",
+            JobState::Done => "done",
+            JobState::Failed => "failed",
+        };
+        f.pad(state)
+    }
+}
+
+// A render that waits in a queue, given by the arguments of the renderer and the directory the
+// renderer runs in, so relative names of scenes and images keep working.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Job {
+    pub id: u64,
+    pub priority: i32,
+    pub directory: PathBuf,
+    pub arguments: Vec<String>,
+    pub state: JobState,
+    // From zero to one.
+    pub progress: f64,
+    // Why the job failed.
+    pub message: Option<String>,
+}
+
+impl Job {
+    // One key and value per line, the arguments in the order they are passed.
+    fn to_text(&self) -> String {
+        let mut text = format!(
+            "priority: {}\ndirectory: {}\nstate: {}\nprogress: {}\n",
+            self.priority,
+            self.directory.display(),
+            self.state,
+            self.progress
+        );
+        if let Some(message) = &self.message {
+            text.push_str(&format!("message: {}\n", message.replace('\n', " ")));
+        }
+        for argument in &self.arguments {
+            text.push_str(&format!("argument: {}\n", argument));
+        }
+        text
+    }
+
+    fn from_text(id: u64, text: &str) -> Result<Job, String> {
+        let mut job = Job {
+            id,
+            priority: 0,
+            directory: PathBuf::from("."),
+            arguments: Vec::new(),
+            state: JobState::Queued,
+            progress: 0.0,
+            message: None,
+        };
+
+        for line in text.lines().filter(|line| !line.is_empty()) {
+            let Some((key, value)) = line.split_once(": ") else {
+                return Err(format!("Malformed line '{}'.", line));
+            };
+            match key {
+                "priority" => match value.parse() {
+                    Ok(priority) => job.priority = priority,
+                    Err(m) => return Err(format!("Unable to parse priority: {}", m)),
+                },
+                "directory" => job.directory = PathBuf::from(value),
+                "state" => match JobState::parse(value) {
+                    Some(state) => job.state = state,
+                    None => return Err(format!("Unknown state '{}'.", value)),
+                },
+                "progress" => match value.parse() {
+                    Ok(progress) => job.progress = progress,
+                    Err(m) => return Err(format!("Unable to parse progress: {}", m)),
+                },
+                "message" => job.message = Some(value.to_string()),
+                "argument" => job.arguments.push(value.to_string()),
+                key => return Err(format!("Unknown key '{}'.", key)),
+            }
+        }
+
+        Ok(job)
+    }
+}
+
+// A queue of render jobs that is kept in a directory, one file per job. Everything is written
+// to disk right away, so the queue outlives the process that works on it, and several processes
+// can submit, pause and resume jobs while it runs.
+pub struct JobQueue {
+    directory: PathBuf,
+}
+
+impl JobQueue {
+    pub fn open(directory: &Path) -> io::Result<JobQueue> {
+        fs::create_dir_all(directory)?;
+        Ok(JobQueue {
+            directory: directory.to_path_buf(),
+        })
+    }
+
+    pub fn submit(
+        &self,
+        priority: i32,
+        directory: &Path,
+        arguments: Vec<String>,
+    ) -> io::Result<Job> {
+        let id = self.jobs()?.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        let job = Job {
+            id,
+            priority,
+            directory: directory.to_path_buf(),
+            arguments,
+            state: JobState::Queued,
+            progress: 0.0,
+            message: None,
+        };
+        self.save(&job)?;
+        Ok(job)
+    }
+
+    // All jobs, ordered by the time they were submitted.
+    pub fn jobs(&self) -> io::Result<Vec<Job>> {
+        let mut jobs = Vec::new();
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(JOB_EXTENSION) {
+                continue;
+            }
+            let Some(id) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+            else {
+                continue;
+            };
+            jobs.push(self.load(id, &path)?);
+        }
+        jobs.sort_by_key(|job| job.id);
+        Ok(jobs)
+    }
+
+    pub fn get(&self, id: u64) -> io::Result<Job> {
+        self.load(id, &self.path(id))
+    }
+
+    // Writes the job to a temporary file first, so a process that reads the queue at the same
+    // time never sees half of it.
+    pub fn save(&self, job: &Job) -> io::Result<()> {
+        let temporary = self
+            .directory
+            .join(format!("{}.{}.tmp", job.id, JOB_EXTENSION));
+        fs::write(&temporary, job.to_text())?;
+        fs::rename(temporary, self.path(job.id))
+    }
+
+    // The queued job with the highest priority. Jobs of the same priority are worked on in the
+    // order they were submitted.
+    pub fn next(&self) -> io::Result<Option<Job>> {
+        Ok(self
+            .jobs()?
+            .into_iter()
+            .filter(|job| job.state == JobState::Queued)
+            .min_by_key(|job| (-(job.priority as i64), job.id)))
+    }
+
+    // A paused job is skipped until it is resumed. A running job is stopped by the process that
+    // runs it, once it notices the change.
+    pub fn pause(&self, id: u64) -> io::Result<Job> {
+        self.change_state(id, &[JobState::Queued, JobState::Running], JobState::Paused)
+    }
+
+    // Queues a paused or failed job again.
+    pub fn resume(&self, id: u64) -> io::Result<Job> {
+        self.change_state(id, &[JobState::Paused, JobState::Failed], JobState::Queued)
+    }
+
+    // Jobs that were running when the process working on the queue went away are queued again.
+    pub fn recover(&self) -> io::Result<Vec<Job>> {
+        let mut recovered = Vec::new();
+        for mut job in self.jobs()? {
+            if job.state == JobState::Running {
+                job.state = JobState::Queued;
+                job.progress = 0.0;
+                self.save(&job)?;
+                recovered.push(job);
+            }
+        }
+        Ok(recovered)
+    }
+
+    fn change_state(&self, id: u64, from: &[JobState], to: JobState) -> io::Result<Job> {
+        let mut job = self.get(id)?;
+        if !from.contains(&job.state) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Job {} is {} and can not be {}.", id, job.state, to),
+            ));
+        }
+        job.state = to;
+        job.progress = 0.0;
+        job.message = None;
+        self.save(&job)?;
+        Ok(job)
+    }
+
+    fn path(&self, id: u64) -> PathBuf {
+        self.directory.join(format!("{}.{}", id, JOB_EXTENSION))
+    }
+
+    fn load(&self, id: u64, path: &Path) -> io::Result<Job> {
+        let text = fs::read_to_string(path)?;
+        Job::from_text(id, &text).map_err(|m| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), m),
+            )
+        })
+    }
+}
+
+// The progress of the last progress bar the renderer drew, like [#####.....]  50.0%.
+pub fn parse_progress(output: &str) -> Option<f64> {
+    let bar = output
+        .rsplit('\r')
+        .find(|bar| bar.trim_end().ends_with('%'))?;
+    let percent = bar.trim_end().strip_suffix('%')?.rsplit(' ').next()?;
+    percent.parse::<f64>().ok().map(|percent| percent / 100.0)
+}
+
+// The last error the renderer reported before it stopped, which tells why it failed, together with
+// where it is found in the scene:
+//
+//     Failed to parse passed scene file. Error was: SphereParsingError
+//      --> scene.scene:2:15
+//
+// The error is cut to its kind, and the snippet of the scene below the position is left out. Both
+// may quote any file the scene includes, which the client of a render server must not read.
+pub fn renderer_failure(output: &str) -> Option<String> {
+    let lines: Vec<&str> = output
+        .lines()
+        .filter(|line| {
+            let line = line.trim();
+            !line.is_empty() && !line.ends_with('%') && !line.starts_with("note:")
+        })
+        .collect();
+    let start = lines.iter().rposition(|line| !is_location(line))?;
+    let error = lines[start].split('(').next().unwrap_or_default().trim();
+    match lines[start + 1..]
+        .iter()
+        .find(|line| line.trim_start().starts_with("-->"))
+    {
+        Some(position) => Some(format!("{}\n{}", error, position)),
+        None => Some(error.to_string()),
+    }
+}
+
+// A line that points at the token an error is found at, like
+//
+//      --> scene.scene:2:15
+//       |
+//     2 |   position: 0 one 0
+//       |               ^^^
+fn is_location(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("-->")
+        || line
+            .split_once('|')
+            .is_some_and(|(number, _)| number.trim().chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    fn queue(name: &str) -> (PathBuf, JobQueue) {
+        let directory = env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&directory);
+        let queue = JobQueue::open(&directory).unwrap();
+        (directory, queue)
+    }
+
+    #[test]
+    fn job_queue_order() {
+        let (directory, queue) = queue("job_queue_order");
+        let arguments =
+            |scene: &str| vec![scene.to_string(), "--size".to_string(), "64 48".to_string()];
+
+        let low = queue
+            .submit(0, Path::new("/scenes"), arguments("a.scene"))
+            .unwrap();
+        let high = queue
+            .submit(5, Path::new("/scenes"), arguments("b.scene"))
+            .unwrap();
+        let later = queue
+            .submit(5, Path::new("/scenes"), arguments("c.scene"))
+            .unwrap();
+        assert_eq!((low.id, high.id, later.id), (1, 2, 3));
+
+        // The queue is read back from the directory.
+        let queue = JobQueue::open(&directory).unwrap();
+        assert_eq!(queue.get(2).unwrap(), high);
+        assert_eq!(queue.next().unwrap(), Some(high.clone()));
+
+        queue.pause(high.id).unwrap();
+        assert_eq!(queue.next().unwrap(), Some(later.clone()));
+        assert!(queue.pause(high.id).is_err());
+
+        queue.resume(high.id).unwrap();
+        assert_eq!(queue.next().unwrap().map(|job| job.id), Some(high.id));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn job_queue_recover() {
+        let (directory, queue) = queue("job_queue_recover");
+        let mut job = queue.submit(0, Path::new("."), vec![]).unwrap();
+        job.state = JobState::Running;
+        job.progress = 0.5;
+        queue.save(&job).unwrap();
+
+        let recovered = queue.recover().unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(queue.get(job.id).unwrap().state, JobState::Queued);
+        assert_eq!(queue.get(job.id).unwrap().progress, 0.0);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn parse_progress_bar() {
+        assert_eq!(parse_progress("\r[....]   0.0%\r[##..]  50.0%"), Some(0.5));
+        assert_eq!(parse_progress("\r[####] 100.0%\n"), Some(1.0));
+        assert_eq!(parse_progress("Failed to parse scene."), None);
+    }
+
+    #[test]
+    fn report_located_errors_of_failed_jobs() {
+        let output = "\r[##..]  50.0%\n\
+                      Failed to parse passed scene file. Error was: \
+                      UnsupportedElement(\"root:x:0:0:root:/root:/bin/bash\")\n \
+                      --> scene.scene:2:15\n  \
+                      |\n\
+                      2 |   root:x:0:0:root:/root:/bin/bash\n  \
+                      |               ^^^\n";
+        assert_eq!(
+            renderer_failure(output).unwrap(),
+            "Failed to parse passed scene file. Error was: UnsupportedElement\n \
+             --> scene.scene:2:15"
+        );
+
+        let output = "thread 'main' panicked at src/parser.rs:366:61:\n\
+                      Unable to read file: No such file or directory\n\
+                      note: run with `RUST_BACKTRACE=1` to display a backtrace\n";
+        assert_eq!(
+            renderer_failure(output).unwrap(),
+            "Unable to read file: No such file or directory"
+        );
+        assert_eq!(renderer_failure("10%\n\n"), None);
+    }
+}
@@ -1,20 +1,46 @@
+use std::cmp::Ordering;
 use std::ops::{Div, Mul};
 
 use colors::Color;
 use material::Material;
-use math::geometry::{Intersect, ParametricLine, SurfacePoint};
-use math::transform::Transform3;
-use math::{Point3, Vector3};
-use traits::{Number, Sqrt};
+use math::geometry::triangle::Triangle3Mesh;
+use math::geometry::{
+    ClosestPoint, Intersect, IntersectPacket, ParametricLine, RayPacket, SurfacePoint,
+};
+use math::{Normal3, Point3, Vector3};
+use traits::{FloatingPoint, Sqrt};
 use units::length::Length;
 
-use cg_basics::scene_graph::RenderableGeometry;
+use cg_basics::scene_graph::{RenderableGeometry, RenderableMesh};
 
+pub mod ambient_occlusion;
+pub mod aov;
+pub mod bidirectional_path_tracer;
 pub mod camera;
+pub mod checkpoint;
+pub mod contours;
+pub mod coverage;
+pub mod denoiser;
 pub mod diffuse_ray_tracer;
+pub mod distributed;
+pub mod gizmo;
+pub mod http;
+pub mod integrator;
+pub mod job_queue;
 pub mod light;
+pub mod light_path_expression;
 pub mod material;
+pub mod metrics;
 pub mod parser;
+pub mod path_tracer;
+pub mod progressive;
+pub mod scene_query;
+pub mod settings;
+pub mod whitted_ray_tracer;
+
+// The number of rays traced together in a packet. Eight lanes fill an AVX register with f32 and
+// two with f64.
+pub const PACKET_WIDTH: usize = 8;
 
 type Cylinder<T> = math::geometry::ImplicitCylinder<T>;
 type Disc<T> = math::geometry::ImplicitDisc3<T>;
@@ -23,46 +49,112 @@ type Sphere<T> = math::geometry::Sphere<T>;
 type AxisAlignedBox<T> = math::geometry::AxisAlignedBox<Point3<T>>;
 type Triangle<T> = math::geometry::Triangle3<T>;
 
-pub trait Renderable<T: Length, C: Color<ChannelType = T::ValueType>> {
-    fn intersect(
+// The ray parameters and surface points of the intersections of a ray with a geometry, together
+// with the material at each of them.
+pub type Hits<'a, T, C> = Vec<(
+    <T as Length>::ValueType,
+    SurfacePoint<T>,
+    &'a dyn Material<T, ColorType = C>,
+)>;
+
+pub trait Renderable<T: Length, C: Color<ChannelType = T::ValueType>>: Sync {
+    fn intersect(&self, ray: ParametricLine<Point3<T>, Vector3<T>>) -> Hits<'_, T, C>;
+
+    // Intersects the geometry where it is at the time of the ray. Static geometry ignores the
+    // time.
+    fn intersect_at(
         &self,
         ray: ParametricLine<Point3<T>, Vector3<T>>,
-    ) -> Vec<(
-        T::ValueType,
-        SurfacePoint<T>,
-        &dyn Material<T, ColorType = C>,
-    )>;
+        _time: T::ValueType,
+    ) -> Hits<'_, T, C> {
+        self.intersect(ray)
+    }
+
+    // The ray parameters of the closest intersections above the epsilon of a packet of rays, with
+    // infinity where a ray misses. Each ray is traced at its own time. None if the geometry has no
+    // test for packets, so the rays are intersected one by one.
+    fn intersect_packet(
+        &self,
+        _packet: &RayPacket<T::ValueType, PACKET_WIDTH>,
+        _times: [T::ValueType; PACKET_WIDTH],
+        _epsilon: T::ValueType,
+    ) -> Option<[T::ValueType; PACKET_WIDTH]> {
+        None
+    }
+
+    // Whether a ray at a time hits the geometry between two ray parameters. Shadow rays only need
+    // this, so geometry that can stop at the first hit and skip the surface points overrides it.
+    fn intersects_any(
+        &self,
+        ray: ParametricLine<Point3<T>, Vector3<T>>,
+        time: T::ValueType,
+        t_min: T::ValueType,
+        t_max: T::ValueType,
+    ) -> bool {
+        self.intersect_at(ray, time)
+            .iter()
+            .any(|(t, _, _)| *t > t_min && *t < t_max)
+    }
+
+    // The point on the surface that is the closest to a point, along with the normal there. None
+    // if the geometry does not support the query.
+    fn closest_point(&self, _p: Point3<T>) -> Option<(Point3<T>, Normal3<T::ValueType>)> {
+        None
+    }
+
+    fn shadow_bias(&self) -> Option<T::ValueType> {
+        None
+    }
+
+    fn epsilon(&self) -> Option<T::ValueType> {
+        None
+    }
+
+    fn illuminated_by(&self, _light: Option<&str>) -> bool {
+        true
+    }
+
+    fn name(&self) -> Option<&str> {
+        None
+    }
+
+    // Shadow rays pass geometry that does not cast shadows, e.g. debug geometry.
+    fn casts_shadows(&self) -> bool {
+        true
+    }
+
+    // Where the origin of the geometry is at a time. None if the geometry has no origin of its own.
+    fn position_at(&self, _time: T::ValueType) -> Option<Point3<T>> {
+        None
+    }
 }
 
 impl<G, T: Length, M> Renderable<T, <M as Material<T>>::ColorType>
-    for RenderableGeometry<G, M, Transform3<T::ValueType>>
+    for RenderableGeometry<G, M, T::ValueType>
 where
     ParametricLine<Point3<T>, Vector3<T>>:
         Intersect<G, Output = Vec<(<T as Div>::Output, SurfacePoint<T>)>>,
-    G: Copy + Clone,
+    G: ClosestPoint<T>
+        + IntersectPacket<T::ValueType, PACKET_WIDTH>
+        + IntersectPacket<T::ValueType, 1>
+        + Copy
+        + Clone
+        + Sync,
     T: Copy + Clone,
-    T::ValueType: Number + Mul<T, Output = T> + Sqrt<Output = T::ValueType>,
+    T::ValueType: FloatingPoint + Mul<T, Output = T> + Sqrt<Output = T::ValueType>,
     M: Material<T>,
     <M as Material<T>>::ColorType: Color<ChannelType = <T as Div>::Output>,
 {
     fn intersect(
         &self,
         ray: ParametricLine<Point3<T>, Vector3<T>>,
-    ) -> Vec<(
-        T::ValueType,
-        SurfacePoint<T>,
-        &dyn Material<T, ColorType = <M as Material<T>>::ColorType>,
-    )> {
+    ) -> Hits<'_, T, <M as Material<T>>::ColorType> {
         let transformed_ray = ParametricLine::new(
             self.transform.inverse * ray.origin,
             self.transform.inverse * ray.direction,
         );
 
-        let mut hits: Vec<(
-            T::ValueType,
-            SurfacePoint<T>,
-            &dyn Material<T, ColorType = <M as Material<T>>::ColorType>,
-        )> = transformed_ray
+        let mut hits: Hits<T, <M as Material<T>>::ColorType> = transformed_ray
             .intersect(self.geometry)
             .iter()
             .map(|t| {
@@ -92,6 +184,175 @@ where
 
         hits
     }
+
+    fn intersect_at(
+        &self,
+        ray: ParametricLine<Point3<T>, Vector3<T>>,
+        time: T::ValueType,
+    ) -> Hits<'_, T, <M as Material<T>>::ColorType> {
+        let Some(motion) = self.motion else {
+            return self.intersect(ray);
+        };
+
+        // Moving the ray backwards instead of the geometry keeps the ray parameters of the hits.
+        let offset = motion * time * T::one();
+        self.intersect(ParametricLine::new(ray.origin - offset, ray.direction))
+            .into_iter()
+            .map(|(t, sp, material)| (t, SurfacePoint::new(sp.p + offset, sp.n, sp.uv), material))
+            .collect()
+    }
+
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket<T::ValueType, PACKET_WIDTH>,
+        times: [T::ValueType; PACKET_WIDTH],
+        epsilon: T::ValueType,
+    ) -> Option<[T::ValueType; PACKET_WIDTH]> {
+        let packet = match self.motion {
+            Some(motion) => packet.translated(times.map(|time| -(motion * time))),
+            None => *packet,
+        };
+        self.geometry
+            .intersect_packet(&packet.transformed(self.transform.inverse), epsilon)
+    }
+
+    // A packet of a single ray, which only yields the ray parameter of the closest hit. Geometry
+    // without a test for packets falls back to the full intersection.
+    fn intersects_any(
+        &self,
+        ray: ParametricLine<Point3<T>, Vector3<T>>,
+        time: T::ValueType,
+        t_min: T::ValueType,
+        t_max: T::ValueType,
+    ) -> bool {
+        let packet = RayPacket::new([ray]);
+        let packet = match self.motion {
+            Some(motion) => packet.translated([-(motion * time)]),
+            None => packet,
+        };
+        match self
+            .geometry
+            .intersect_packet(&packet.transformed(self.transform.inverse), t_min)
+        {
+            Some([t]) => t < t_max,
+            None => self
+                .intersect_at(ray, time)
+                .iter()
+                .any(|(t, _, _)| *t > t_min && *t < t_max),
+        }
+    }
+
+    // Exact for rigid transformations and uniform scaling. A non-uniform scaling distorts the
+    // distances, so the point is only close to the closest one. Moving geometry is queried where
+    // it is at time zero.
+    fn closest_point(&self, p: Point3<T>) -> Option<(Point3<T>, Normal3<T::ValueType>)> {
+        let (c, n) = self.geometry.closest_point(self.transform.inverse * p);
+        Some((
+            self.transform.matrix * c,
+            self.transform.inverse.transposed() * n,
+        ))
+    }
+
+    fn shadow_bias(&self) -> Option<T::ValueType> {
+        self.shadow_bias
+    }
+
+    fn epsilon(&self) -> Option<T::ValueType> {
+        self.epsilon
+    }
+
+    fn illuminated_by(&self, light: Option<&str>) -> bool {
+        self.light_links.includes(light)
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn position_at(&self, time: T::ValueType) -> Option<Point3<T>> {
+        let origin = Point3::new(T::zero(), T::zero(), T::zero());
+        let position = self.transform.matrix * origin;
+        match self.motion {
+            Some(motion) => Some(position + motion * time * T::one()),
+            None => Some(position),
+        }
+    }
+}
+
+impl<T: Length, M> Renderable<T, <M as Material<T>>::ColorType>
+    for RenderableMesh<Triangle3Mesh<T>, M, T::ValueType>
+where
+    for<'a> ParametricLine<Point3<T>, Vector3<T>>:
+        Intersect<&'a Triangle3Mesh<T>, Output = Vec<(<T as Div>::Output, SurfacePoint<T>)>>,
+    Triangle<T>: ClosestPoint<T>,
+    Triangle3Mesh<T>: IntersectPacket<T::ValueType, PACKET_WIDTH>,
+    T::ValueType: FloatingPoint,
+    M: Material<T>,
+    <M as Material<T>>::ColorType: Color<ChannelType = <T as Div>::Output>,
+{
+    fn intersect(
+        &self,
+        ray: ParametricLine<Point3<T>, Vector3<T>>,
+    ) -> Hits<'_, T, <M as Material<T>>::ColorType> {
+        ray.intersect(&self.mesh)
+            .into_iter()
+            .map(|(t, sp)| {
+                (
+                    t,
+                    sp,
+                    &self.material as &dyn Material<T, ColorType = <M as Material<T>>::ColorType>,
+                )
+            })
+            .collect()
+    }
+
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket<T::ValueType, PACKET_WIDTH>,
+        _times: [T::ValueType; PACKET_WIDTH],
+        epsilon: T::ValueType,
+    ) -> Option<[T::ValueType; PACKET_WIDTH]> {
+        self.mesh.intersect_packet(packet, epsilon)
+    }
+
+    fn intersects_any(
+        &self,
+        ray: ParametricLine<Point3<T>, Vector3<T>>,
+        _time: T::ValueType,
+        t_min: T::ValueType,
+        t_max: T::ValueType,
+    ) -> bool {
+        self.mesh.intersects_any(ray, t_min, t_max)
+    }
+
+    fn closest_point(&self, p: Point3<T>) -> Option<(Point3<T>, Normal3<T::ValueType>)> {
+        let squared_distance = |c: Point3<T>| {
+            let d = (c - p) / T::one();
+            d.dot(d)
+        };
+
+        self.mesh
+            .faces()
+            .iter()
+            .map(|face| self.mesh.triangle(face).closest_point(p))
+            .min_by(|(a, _), (b, _)| {
+                squared_distance(*a)
+                    .partial_cmp(&squared_distance(*b))
+                    .unwrap_or(Ordering::Equal)
+            })
+    }
+
+    fn shadow_bias(&self) -> Option<T::ValueType> {
+        self.shadow_bias
+    }
+
+    fn epsilon(&self) -> Option<T::ValueType> {
+        self.epsilon
+    }
+
+    fn illuminated_by(&self, light: Option<&str>) -> bool {
+        self.light_links.includes(light)
+    }
 }
 
 #[cfg(test)]
@@ -101,8 +362,9 @@ mod tests {
     use std::fmt::Debug;
 
     use colors::RGB;
-    use math::{Normal3, Point2};
-    use traits::Zero;
+    use math::transform::Transform3;
+    use math::Point2;
+    use traits::{Number, Zero};
     use units::length::Meter;
 
     use crate::light::Light;
@@ -134,6 +396,17 @@ mod tests {
         }
     }
 
+    impl<T: Length, const N: usize> IntersectPacket<T::ValueType, N> for MockGeometry<T> {}
+
+    impl<T> ClosestPoint<T> for MockGeometry<T>
+    where
+        T: Length,
+    {
+        fn closest_point(&self, p: Point3<T>) -> (Point3<T>, Normal3<<T as Length>::ValueType>) {
+            (p, self.normal)
+        }
+    }
+
     #[derive(Debug, PartialEq, Clone, Copy)]
     struct MockMaterial<T: Length> {
         color: RGB<<T as Length>::ValueType>,
@@ -149,7 +422,7 @@ mod tests {
             &self,
             _sp: SurfacePoint<T>,
             _d: Vector3<T>,
-            _lights: Vec<&Box<dyn Light<T, RGB<<T as Length>::ValueType>>>>,
+            _lights: Vec<&dyn Light<T, RGB<<T as Length>::ValueType>>>,
         ) -> RGB<<T as Length>::ValueType> {
             self.color
         }
@@ -224,4 +497,90 @@ mod tests {
 
     renderable_geometry_intersect! { f32, renderable_geometry_intersect_f32 }
     renderable_geometry_intersect! { f64, renderable_geometry_intersect_f64 }
+
+    macro_rules! renderable_geometry_intersect_at {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let m: MockMaterial<Meter<$type>> = MockMaterial {
+                    color: RGB::new(0.0 as $type, 0.5 as $type, 1.0 as $type),
+                };
+                let sphere = Sphere::new(
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    Meter::new(1.0 as $type),
+                );
+                let rg = RenderableGeometry::new(sphere, m, Transform3::<$type>::ident())
+                    .with_motion(Vector3::new(2.0, 0.0, 0.0));
+
+                let ray = ParametricLine::new(
+                    Point3::new(Meter::new(3.0), Meter::new(0.0), Meter::new(5.0)),
+                    Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                );
+
+                // At time zero the sphere is still at the origin, at time 1.5 it is in front of
+                // the ray.
+                assert!(rg.intersect_at(ray, 0.0).is_empty());
+                let hits = rg.intersect_at(ray, 1.5);
+                assert_eq!(hits.len(), 2);
+                assert_eq!(hits[0].0, 4.0);
+                assert_eq!(
+                    hits[0].1.p,
+                    Point3::new(Meter::new(3.0), Meter::new(0.0), Meter::new(1.0))
+                );
+            }
+        };
+    }
+
+    renderable_geometry_intersect_at! { f32, renderable_geometry_intersect_at_f32 }
+    renderable_geometry_intersect_at! { f64, renderable_geometry_intersect_at_f64 }
+
+    macro_rules! renderable_geometry_intersects_any {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let m: MockMaterial<Meter<$type>> = MockMaterial {
+                    color: RGB::new(0.0 as $type, 0.5 as $type, 1.0 as $type),
+                };
+                let sphere = Sphere::new(
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    Meter::new(1.0 as $type),
+                );
+                let rg = RenderableGeometry::new(
+                    sphere,
+                    m,
+                    Transform3::<$type>::ident().translate(0.0, 0.0, -1.0),
+                )
+                .with_motion(Vector3::new(2.0, 0.0, 0.0));
+
+                let ray = ParametricLine::new(
+                    Point3::new(Meter::new(3.0), Meter::new(0.0), Meter::new(5.0)),
+                    Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                );
+
+                // The sphere is in front of the ray at time 1.5, from 5 to 7 along it.
+                let inf = <$type>::INFINITY;
+                assert!(!rg.intersects_any(ray, 0.0, 0.001, inf));
+                assert!(rg.intersects_any(ray, 1.5, 0.001, inf));
+                assert!(rg.intersects_any(ray, 1.5, 6.0, inf));
+                assert!(!rg.intersects_any(ray, 1.5, 0.001, 4.5));
+                assert!(!rg.intersects_any(ray, 1.5, 7.5, inf));
+
+                // Geometry without a test for packets gives the same answers.
+                let mock = RenderableGeometry::new(
+                    MockGeometry::<Meter<$type>> {
+                        t: 5.0,
+                        normal: Normal3::new(0.0, 0.0, 1.0),
+                    },
+                    m,
+                    Transform3::<$type>::ident(),
+                );
+                assert!(mock.intersects_any(ray, 0.0, 0.001, inf));
+                assert!(!mock.intersects_any(ray, 0.0, 0.001, 4.5));
+                assert!(!mock.intersects_any(ray, 0.0, 5.5, inf));
+            }
+        };
+    }
+
+    renderable_geometry_intersects_any! { f32, renderable_geometry_intersects_any_f32 }
+    renderable_geometry_intersects_any! { f64, renderable_geometry_intersects_any_f64 }
 }
@@ -2,16 +2,27 @@ use std::fmt::Debug;
 use std::ops::{Div, Mul};
 
 use cg_basics::light::{
-    AmbientLight, AmbientOcclusionLight, DirectionalLight, PointLight, SpotLight,
+    AmbientLight, AmbientOcclusionLight, AreaLight, DirectionalLight, EnvironmentLight, MeshLight,
+    PointLight, SphereLight, SpotLight,
 };
-use math::geometry::{ParametricLine, SurfacePoint};
+use cg_basics::spherical_harmonics::SphericalHarmonics;
+use colors::{Color, RGB};
+use math::geometry::{ParametricLine, SampleSurface, SurfacePoint};
 use math::{Point2, Point3, Vector3};
 use random::{RandomNumberGenerator, WichmannHillPRNG};
 use sampling::{PatternMapping, SamplingPattern};
-use traits::{Cos, FloatingPoint, SignedNumber, Sqrt, Zero};
+use traits::{
+    Abs, ConvenientNumber, Cos, FloatingPoint, Half, Max, One, Pi, SignedNumber, Sin, Sqrt, Zero,
+};
 use units::length::Length;
 
-pub trait Light<T, C>
+use crate::gizmo;
+use crate::material::tangent_frame;
+
+// A ray of light leaving a light, with the density of its direction per solid angle.
+pub type Emission<T> = (ParametricLine<Point3<T>, Vector3<T>>, <T as Div>::Output);
+
+pub trait Light<T, C>: Sync
 where
     T: Div + Copy + Debug,
     <T as Div>::Output: Copy + Debug + PartialEq,
@@ -19,21 +30,88 @@ where
     fn direction_from(&self, sp: SurfacePoint<T>) -> Vector3<<T as Div>::Output>;
     fn get_color(&self) -> C;
 
+    fn color_at(&self, _sp: SurfacePoint<T>) -> C {
+        self.get_color()
+    }
+
+    // Overrides the shadow tolerance of the renderer for shadow rays cast towards this light.
+    fn shadow_bias(&self) -> Option<<T as Div>::Output> {
+        None
+    }
+
+    // Geometries include or exclude lights by their name.
+    fn name(&self) -> Option<&str> {
+        None
+    }
+
+    // Lights of the same group are rendered into a common image if light groups are requested,
+    // so their contribution can be rebalanced after the render.
+    fn group(&self) -> Option<&str> {
+        None
+    }
+
+    // Ambient terms stand in for the light bouncing around in the scene. They are reported as
+    // indirect illumination when the lighting is split into components.
+    fn is_indirect(&self) -> bool {
+        false
+    }
+
+    // Radiance seen by rays that leave the scene without hitting any geometry.
+    fn background(&self, _direction: Vector3<<T as Div>::Output>) -> Option<C> {
+        None
+    }
+
+    // The light of the surroundings a glossy surface reflects into a normalized direction, for a
+    // roughness between zero (a mirror) and one. None for lights that only cause highlights.
+    fn reflected_radiance(
+        &self,
+        _direction: Vector3<<T as Div>::Output>,
+        _roughness: <T as Div>::Output,
+    ) -> Option<C> {
+        None
+    }
+
+    // Whether the light reaches a surface point. The shadow check tells whether anything blocks a
+    // ray before a distance along it, or anywhere along it without a distance.
     fn illuminates(
         &self,
         sp: SurfacePoint<T>,
-        shadow_check: &dyn Fn(
-            ParametricLine<Point3<T>, Vector3<T>>,
-            Option<T>,
-        ) -> Option<<T as Div>::Output>,
+        shadow_check: &dyn Fn(ParametricLine<Point3<T>, Vector3<T>>, Option<T>) -> bool,
         pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
         rnd: &mut WichmannHillPRNG,
     ) -> bool;
+
+    // Draws a ray of light leaving the light, for renderers that trace paths from the lights as
+    // well, together with the density of its direction per solid angle. The sample picks the
+    // direction, the pattern the point on lights with an area. None for lights without a
+    // position.
+    fn emit(
+        &self,
+        _sample: Point2<<T as Div>::Output>,
+        _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
+    ) -> Option<Emission<T>> {
+        None
+    }
+
+    // The density per area of an emitted ray reaching a surface point first, taken from the
+    // position the light is shaded from. Renderers weigh the paths found from the lights against
+    // the light samples with it. None for lights that emit no rays.
+    fn emission_density(&self, _sp: SurfacePoint<T>) -> Option<<T as Div>::Output> {
+        None
+    }
+
+    // The edges of a wireframe that shows where the light is, to debug the layout of a scene.
+    // Lights without a shape of their own are drawn as large as the size. Lights without a
+    // position have no gizmo.
+    fn gizmo(&self, _size: T) -> Vec<(Point3<T>, Point3<T>)> {
+        Vec::new()
+    }
 }
 
 impl<T, C> Light<T, C> for DirectionalLight<T, C>
 where
-    C: Copy,
+    C: Copy + Sync,
     T: Length,
     <T as Length>::ValueType: SignedNumber + Mul<T, Output = T>,
 {
@@ -45,31 +123,40 @@ where
         self.color
     }
 
+    fn shadow_bias(&self) -> Option<<T as Div>::Output> {
+        self.shadow_bias
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
     fn illuminates(
         &self,
         sp: SurfacePoint<T>,
-        shadow_check: &dyn Fn(
-            ParametricLine<Point3<T>, Vector3<T>>,
-            Option<T>,
-        ) -> Option<<T as Div>::Output>,
+        shadow_check: &dyn Fn(ParametricLine<Point3<T>, Vector3<T>>, Option<T>) -> bool,
         _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
         _rnd: &mut WichmannHillPRNG,
     ) -> bool {
         self.direction.dot(sp.n.as_vector()) > Zero::zero()
-            && shadow_check(
+            && !shadow_check(
                 ParametricLine::new(sp.p, self.direction_from(sp) * T::one()),
                 None,
             )
-            .is_none()
     }
 }
 
 impl<T, C> Light<T, C> for PointLight<T, C>
 where
-    C: Copy,
+    C: Color<ChannelType = <T as Div>::Output>,
     T: Length,
-    <T as Length>::ValueType: SignedNumber + Mul<T, Output = T>,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
     <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
 {
     fn direction_from(&self, sp: SurfacePoint<T>) -> Vector3<<T as Div>::Output> {
         (self.position - sp.p).normalized()
@@ -79,37 +166,84 @@ where
         self.color
     }
 
+    fn color_at(&self, sp: SurfacePoint<T>) -> C {
+        let direction = -self.direction_from(sp);
+        self.color
+            * self.attenuation((self.position - sp.p).magnitude() / T::one())
+            * self.profile_intensity(direction)
+    }
+
+    fn shadow_bias(&self) -> Option<<T as Div>::Output> {
+        self.shadow_bias
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
     fn illuminates(
         &self,
         sp: SurfacePoint<T>,
-        shadow_check: &dyn Fn(
-            ParametricLine<Point3<T>, Vector3<T>>,
-            Option<T>,
-        ) -> Option<<T as Div>::Output>,
+        shadow_check: &dyn Fn(ParametricLine<Point3<T>, Vector3<T>>, Option<T>) -> bool,
         _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
         _rnd: &mut WichmannHillPRNG,
     ) -> bool {
         if self.direction_from(sp).dot(sp.n.as_vector()) > Zero::zero() {
-            let ot = shadow_check(
+            !shadow_check(
                 ParametricLine::new(sp.p, self.direction_from(sp) * T::one()),
-                None,
-            );
-            match ot {
-                Some(t) => t > ((self.position - sp.p).magnitude() / T::one()),
-                None => true,
-            }
+                Some((self.position - sp.p).magnitude()),
+            )
         } else {
             false
         }
     }
+
+    fn emit(
+        &self,
+        sample: Point2<<T as Div>::Output>,
+        _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
+    ) -> Option<Emission<T>> {
+        let (direction, density) = uniform_direction(sample);
+        Some((
+            ParametricLine::new(self.position, direction * T::one()),
+            density,
+        ))
+    }
+
+    fn emission_density(&self, sp: SurfacePoint<T>) -> Option<<T as Div>::Output> {
+        Some(area_density(uniform_direction_density(), self.position, sp))
+    }
+
+    // Three axes through the position.
+    fn gizmo(&self, size: T) -> Vec<(Point3<T>, Point3<T>)> {
+        let one = <T as Length>::ValueType::one();
+        let zero = <T as Length>::ValueType::zero();
+        [
+            Vector3::new(one, zero, zero),
+            Vector3::new(zero, one, zero),
+            Vector3::new(zero, zero, one),
+        ]
+        .into_iter()
+        .map(|axis| {
+            let offset = axis * size * one.half();
+            (self.position - offset, self.position + offset)
+        })
+        .collect()
+    }
 }
 
 impl<T, C> Light<T, C> for SpotLight<T, C>
 where
-    C: Copy,
+    C: Color<ChannelType = <T as Div>::Output>,
     T: Length,
-    <T as Length>::ValueType: FloatingPoint + Mul<T, Output = T>,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
     <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
 {
     fn direction_from(&self, sp: SurfacePoint<T>) -> Vector3<<T as Div>::Output> {
         (self.position - sp.p).normalized()
@@ -119,13 +253,32 @@ where
         self.color
     }
 
+    fn shadow_bias(&self) -> Option<<T as Div>::Output> {
+        self.shadow_bias
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    fn color_at(&self, sp: SurfacePoint<T>) -> C {
+        let direction = -self.direction_from(sp);
+        let color =
+            self.color * (self.cone_attenuation(direction) * self.profile_intensity(direction));
+        match &self.gobo {
+            Some(gobo) => color * gobo.get(self.cone_coordinates(direction)),
+            None => color,
+        }
+    }
+
     fn illuminates(
         &self,
         sp: SurfacePoint<T>,
-        shadow_check: &dyn Fn(
-            ParametricLine<Point3<T>, Vector3<T>>,
-            Option<T>,
-        ) -> Option<<T as Div>::Output>,
+        shadow_check: &dyn Fn(ParametricLine<Point3<T>, Vector3<T>>, Option<T>) -> bool,
         _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
         _rnd: &mut WichmannHillPRNG,
     ) -> bool {
@@ -134,20 +287,442 @@ where
         if direction.dot(sp.n.as_vector()) > Zero::zero()
             && (-direction).dot(self.direction) > self.angle.cos()
         {
-            let ot = shadow_check(ParametricLine::new(sp.p, direction * T::one()), None);
-            match ot {
-                Some(t) => t > ((self.position - sp.p).magnitude() / T::one()),
-                None => true,
-            }
+            !shadow_check(
+                ParametricLine::new(sp.p, direction * T::one()),
+                Some((self.position - sp.p).magnitude()),
+            )
+        } else {
+            false
+        }
+    }
+
+    // The rays fill the cone of the spot evenly.
+    fn emit(
+        &self,
+        sample: Point2<<T as Div>::Output>,
+        _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
+    ) -> Option<Emission<T>> {
+        let zero = <T as Length>::ValueType::zero();
+        let one = <T as Length>::ValueType::one();
+        let pi = <T as Length>::ValueType::PI;
+
+        let axis = self.direction.normalized();
+        let (u, v) = tangent_frame(axis);
+        let cos_angle = self.angle.cos();
+        let cos_theta = one - sample.x * (one - cos_angle);
+        let sin_theta = (one - cos_theta * cos_theta).max(zero).sqrt();
+        let phi = (pi + pi) * sample.y;
+        let direction =
+            u * (sin_theta * phi.cos()) + v * (sin_theta * phi.sin()) + axis * cos_theta;
+
+        Some((
+            ParametricLine::new(self.position, direction * T::one()),
+            one / ((pi + pi) * (one - cos_angle)),
+        ))
+    }
+
+    fn emission_density(&self, sp: SurfacePoint<T>) -> Option<<T as Div>::Output> {
+        let one = <T as Length>::ValueType::one();
+        let pi = <T as Length>::ValueType::PI;
+
+        let cos_angle = self.angle.cos();
+        if (-self.direction_from(sp)).dot(self.direction.normalized()) <= cos_angle {
+            return Some(Zero::zero());
+        }
+        Some(area_density(
+            one / ((pi + pi) * (one - cos_angle)),
+            self.position,
+            sp,
+        ))
+    }
+
+    // The cone of the spot, with edges as long as the size.
+    fn gizmo(&self, size: T) -> Vec<(Point3<T>, Point3<T>)> {
+        let direction = self.direction.normalized();
+        let (u, v) = tangent_frame(direction);
+        let center = self.position + direction * (size * self.angle.cos());
+        let radius = size * self.angle.sin();
+        let mut edges = gizmo::circle(center, u * radius, v * radius);
+        edges.extend(
+            [u, v, -u, -v]
+                .into_iter()
+                .map(|axis| (self.position, center + axis * radius)),
+        );
+        edges
+    }
+}
+
+impl<T, C> Light<T, C> for AreaLight<T, C>
+where
+    C: Copy + Sync,
+    T: Length,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
+{
+    // Shading uses the center of the rectangle. Only the visibility test samples the area, which
+    // produces the penumbra once the samples of a pixel are averaged.
+    fn direction_from(&self, sp: SurfacePoint<T>) -> Vector3<<T as Div>::Output> {
+        let center = self.corner + (self.a + self.b) * <T as Length>::ValueType::one().half();
+        (center - sp.p).normalized()
+    }
+
+    fn get_color(&self) -> C {
+        self.color
+    }
+
+    fn shadow_bias(&self) -> Option<<T as Div>::Output> {
+        self.shadow_bias
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    fn illuminates(
+        &self,
+        sp: SurfacePoint<T>,
+        shadow_check: &dyn Fn(ParametricLine<Point3<T>, Vector3<T>>, Option<T>) -> bool,
+        pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> bool {
+        let sample = pattern.draw_point(rnd);
+        let position = self.corner + self.a * sample.x + self.b * sample.y;
+        let direction = (position - sp.p).normalized();
+        let normal = Vector3::cross(self.a.normalized(), self.b.normalized());
+
+        if direction.dot(sp.n.as_vector()) > Zero::zero() && direction.dot(normal) < Zero::zero() {
+            !shadow_check(
+                ParametricLine::new(sp.p, direction * T::one()),
+                Some((position - sp.p).magnitude()),
+            )
         } else {
             false
         }
     }
+
+    // The rays leave a point on the rectangle with a density proportional to the cosine to its
+    // normal.
+    fn emit(
+        &self,
+        sample: Point2<<T as Div>::Output>,
+        pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> Option<Emission<T>> {
+        let position = pattern.draw_point(rnd);
+        let normal = Vector3::cross(self.a.normalized(), self.b.normalized());
+        let (direction, density) = cosine_direction(normal, sample);
+        Some((
+            ParametricLine::new(
+                self.corner + self.a * position.x + self.b * position.y,
+                direction * T::one(),
+            ),
+            density,
+        ))
+    }
+
+    fn emission_density(&self, sp: SurfacePoint<T>) -> Option<<T as Div>::Output> {
+        let center = self.corner + (self.a + self.b) * <T as Length>::ValueType::one().half();
+        let normal = Vector3::cross(self.a.normalized(), self.b.normalized());
+        let cos = normal.dot(-self.direction_from(sp));
+        if cos <= Zero::zero() {
+            return Some(Zero::zero());
+        }
+        Some(area_density(cos / <T as Length>::ValueType::PI, center, sp))
+    }
+
+    // The outline of the rectangle.
+    fn gizmo(&self, _size: T) -> Vec<(Point3<T>, Point3<T>)> {
+        let corners = [
+            self.corner,
+            self.corner + self.a,
+            self.corner + self.a + self.b,
+            self.corner + self.b,
+        ];
+        (0..corners.len())
+            .map(|i| (corners[i], corners[(i + 1) % corners.len()]))
+            .collect()
+    }
+}
+
+impl<T, C> Light<T, C> for MeshLight<T, C>
+where
+    C: Copy + Sync,
+    T: Length,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
+{
+    // Like the area light, shading uses the center of the mesh and only the visibility test
+    // samples the surface.
+    fn direction_from(&self, sp: SurfacePoint<T>) -> Vector3<<T as Div>::Output> {
+        (self.sampler().center() - sp.p).normalized()
+    }
+
+    fn get_color(&self) -> C {
+        self.color
+    }
+
+    fn shadow_bias(&self) -> Option<<T as Div>::Output> {
+        self.shadow_bias
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    fn illuminates(
+        &self,
+        sp: SurfacePoint<T>,
+        shadow_check: &dyn Fn(ParametricLine<Point3<T>, Vector3<T>>, Option<T>) -> bool,
+        pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> bool {
+        let (sample, _) = self.sampler().sample_surface(*pattern.draw_point(rnd));
+        let direction = (sample.p - sp.p).normalized();
+
+        if direction.dot(sp.n.as_vector()) <= Zero::zero()
+            || direction.dot(sample.n.as_vector()) >= Zero::zero()
+        {
+            return false;
+        }
+
+        // The mesh is usually part of the scene as well. Hits on the sampled triangle itself
+        // must not count as occluders.
+        let distance = (sample.p - sp.p).magnitude() / T::one();
+        let tolerance: <T as Length>::ValueType = 1000u16.into();
+        !shadow_check(
+            ParametricLine::new(sp.p, direction * T::one()),
+            Some((distance - distance / tolerance) * T::one()),
+        )
+    }
+
+    // The rays leave a point on the mesh with a density proportional to the cosine to its normal.
+    // Seen from the center, they spread evenly into all directions.
+    fn emit(
+        &self,
+        sample: Point2<<T as Div>::Output>,
+        pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> Option<Emission<T>> {
+        let (position, _) = self.sampler().sample_surface(*pattern.draw_point(rnd));
+        let (direction, density) = cosine_direction(position.n.as_vector().normalized(), sample);
+        Some((
+            ParametricLine::new(position.p, direction * T::one()),
+            density,
+        ))
+    }
+
+    fn emission_density(&self, sp: SurfacePoint<T>) -> Option<<T as Div>::Output> {
+        Some(area_density(
+            uniform_direction_density(),
+            self.sampler().center(),
+            sp,
+        ))
+    }
+}
+
+impl<T, C> Light<T, C> for SphereLight<T, C>
+where
+    C: Copy + Sync,
+    T: Length,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
+{
+    fn direction_from(&self, sp: SurfacePoint<T>) -> Vector3<<T as Div>::Output> {
+        (self.position - sp.p).normalized()
+    }
+
+    fn get_color(&self) -> C {
+        self.color
+    }
+
+    fn shadow_bias(&self) -> Option<<T as Div>::Output> {
+        self.shadow_bias
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    // The sample is mapped uniformly onto the cone of directions that hit the sphere.
+    fn illuminates(
+        &self,
+        sp: SurfacePoint<T>,
+        shadow_check: &dyn Fn(ParametricLine<Point3<T>, Vector3<T>>, Option<T>) -> bool,
+        pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> bool {
+        let zero = <T as Length>::ValueType::zero();
+        let one = <T as Length>::ValueType::one();
+
+        let to_center = self.position - sp.p;
+        let distance = to_center.magnitude() / T::one();
+        let radius = self.radius / T::one();
+
+        if distance <= radius {
+            return true;
+        }
+
+        let w = to_center.normalized();
+        let helper = if w.x.abs() > one.half() {
+            Vector3::new(zero, one, zero)
+        } else {
+            Vector3::new(one, zero, zero)
+        };
+        let u = Vector3::cross(helper, w).normalized();
+        let v = Vector3::cross(w, u);
+
+        let sin_theta_max = radius / distance;
+        let cos_theta_max = (one - sin_theta_max * sin_theta_max).sqrt();
+
+        let sample = pattern.draw_point(rnd);
+        let cos_theta = one - sample.x * (one - cos_theta_max);
+        let sin_theta = (one - cos_theta * cos_theta).max(zero).sqrt();
+        let phi = (<T as Length>::ValueType::PI + <T as Length>::ValueType::PI) * sample.y;
+
+        let direction = u * (sin_theta * phi.cos()) + v * (sin_theta * phi.sin()) + w * cos_theta;
+
+        if direction.dot(sp.n.as_vector()) <= zero {
+            return false;
+        }
+
+        let surface_distance = distance * cos_theta
+            - (radius * radius - distance * distance * sin_theta * sin_theta)
+                .max(zero)
+                .sqrt();
+
+        !shadow_check(
+            ParametricLine::new(sp.p, direction * T::one()),
+            Some(surface_distance * T::one()),
+        )
+    }
+
+    // The rays leave the sphere straight outwards, evenly into all directions.
+    fn emit(
+        &self,
+        sample: Point2<<T as Div>::Output>,
+        _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
+    ) -> Option<Emission<T>> {
+        let (direction, density) = uniform_direction(sample);
+        Some((
+            ParametricLine::new(
+                self.position + direction * self.radius,
+                direction * T::one(),
+            ),
+            density,
+        ))
+    }
+
+    fn emission_density(&self, sp: SurfacePoint<T>) -> Option<<T as Div>::Output> {
+        Some(area_density(uniform_direction_density(), self.position, sp))
+    }
+
+    // Three great circles of the sphere.
+    fn gizmo(&self, _size: T) -> Vec<(Point3<T>, Point3<T>)> {
+        let one = <T as Length>::ValueType::one();
+        let zero = <T as Length>::ValueType::zero();
+        let x = Vector3::new(one, zero, zero) * self.radius;
+        let y = Vector3::new(zero, one, zero) * self.radius;
+        let z = Vector3::new(zero, zero, one) * self.radius;
+        let mut edges = gizmo::circle(self.position, x, y);
+        edges.extend(gizmo::circle(self.position, y, z));
+        edges.extend(gizmo::circle(self.position, z, x));
+        edges
+    }
+}
+
+impl<T> Light<T, RGB<<T as Length>::ValueType>> for EnvironmentLight<T>
+where
+    T: Length,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + Mul<T, Output = T>,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+{
+    // The irradiance already accounts for the orientation of the surface, so the light appears
+    // to come from the direction of the normal.
+    fn direction_from(&self, sp: SurfacePoint<T>) -> Vector3<<T as Div>::Output> {
+        sp.n.as_vector().normalized()
+    }
+
+    fn get_color(&self) -> RGB<<T as Length>::ValueType> {
+        self.irradiance(Vector3::new(Zero::zero(), One::one(), Zero::zero()))
+    }
+
+    fn color_at(&self, sp: SurfacePoint<T>) -> RGB<<T as Length>::ValueType> {
+        self.irradiance(sp.n.as_vector().normalized())
+    }
+
+    fn shadow_bias(&self) -> Option<<T as Div>::Output> {
+        self.shadow_bias
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    fn background(
+        &self,
+        direction: Vector3<<T as Div>::Output>,
+    ) -> Option<RGB<<T as Length>::ValueType>> {
+        Some(self.radiance(direction))
+    }
+
+    fn reflected_radiance(
+        &self,
+        direction: Vector3<<T as Div>::Output>,
+        roughness: <T as Div>::Output,
+    ) -> Option<RGB<<T as Length>::ValueType>> {
+        Some(self.specular(direction, roughness))
+    }
+
+    // Casts a single shadow ray towards a direction chosen proportionally to the brightness of
+    // the environment. Directions below the horizon of the surface are drawn again.
+    fn illuminates(
+        &self,
+        sp: SurfacePoint<T>,
+        shadow_check: &dyn Fn(ParametricLine<Point3<T>, Vector3<T>>, Option<T>) -> bool,
+        pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> bool {
+        let normal = sp.n.as_vector();
+        let mut sample = *pattern.draw_point(rnd);
+
+        for _ in 0..16 {
+            let direction = self.sample_direction(sample);
+            if direction.dot(normal) > Zero::zero() {
+                return !shadow_check(ParametricLine::new(sp.p, direction * T::one()), None);
+            }
+            sample = Point2::new(rnd.next_random(), rnd.next_random());
+        }
+
+        true
+    }
 }
 
 impl<T, C> Light<T, C> for AmbientLight<C>
 where
-    C: Copy,
+    C: Copy + Sync,
     T: Length,
     <T as Length>::ValueType: FloatingPoint + Mul<T, Output = T>,
     <T as Length>::AreaType: Sqrt<Output = T>,
@@ -156,13 +731,14 @@ where
         self.color
     }
 
+    fn is_indirect(&self) -> bool {
+        true
+    }
+
     fn illuminates(
         &self,
         _sp: SurfacePoint<T>,
-        _shadow_check: &dyn Fn(
-            ParametricLine<Point3<T>, Vector3<T>>,
-            Option<T>,
-        ) -> Option<<T as Div>::Output>,
+        _shadow_check: &dyn Fn(ParametricLine<Point3<T>, Vector3<T>>, Option<T>) -> bool,
         _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
         _rnd: &mut WichmannHillPRNG,
     ) -> bool {
@@ -174,9 +750,45 @@ where
     }
 }
 
+// A fast ambient term for an environment. The irradiance is evaluated from its spherical
+// harmonics without casting any shadow rays.
+impl<T> Light<T, RGB<<T as Length>::ValueType>> for SphericalHarmonics<<T as Length>::ValueType>
+where
+    T: Length,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + From<f32> + Mul<T, Output = T>,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
+{
+    fn direction_from(&self, sp: SurfacePoint<T>) -> Vector3<<T as Div>::Output> {
+        sp.n.as_vector().normalized()
+    }
+
+    fn get_color(&self) -> RGB<<T as Length>::ValueType> {
+        self.irradiance(Vector3::new(Zero::zero(), One::one(), Zero::zero()))
+    }
+
+    fn color_at(&self, sp: SurfacePoint<T>) -> RGB<<T as Length>::ValueType> {
+        self.irradiance(sp.n.as_vector().normalized())
+    }
+
+    fn is_indirect(&self) -> bool {
+        true
+    }
+
+    fn illuminates(
+        &self,
+        _sp: SurfacePoint<T>,
+        _shadow_check: &dyn Fn(ParametricLine<Point3<T>, Vector3<T>>, Option<T>) -> bool,
+        _pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
+        _rnd: &mut WichmannHillPRNG,
+    ) -> bool {
+        true
+    }
+}
+
 impl<T: Length, C> Light<T, C> for AmbientOcclusionLight<T, C>
 where
-    C: Copy,
+    C: Copy + Sync,
     T: Length,
     <T as Length>::ValueType: FloatingPoint + Mul<T, Output = T>,
     <T as Length>::AreaType: Sqrt<Output = T>,
@@ -187,13 +799,26 @@ where
         self.color
     }
 
+    fn is_indirect(&self) -> bool {
+        true
+    }
+
+    fn shadow_bias(&self) -> Option<<T as Div>::Output> {
+        self.shadow_bias
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
     fn illuminates(
         &self,
         sp: SurfacePoint<T>,
-        shadow_check: &dyn Fn(
-            ParametricLine<Point3<T>, Vector3<T>>,
-            Option<T>,
-        ) -> Option<<T as Div>::Output>,
+        shadow_check: &dyn Fn(ParametricLine<Point3<T>, Vector3<T>>, Option<T>) -> bool,
         pattern: &SamplingPattern<Point2<<T as Div>::Output>>,
         rnd: &mut WichmannHillPRNG,
     ) -> bool {
@@ -209,9 +834,7 @@ where
 
         let shadow_ray = ParametricLine::new(sp.p, direction);
 
-        let hits = shadow_check(shadow_ray, Some(self.distance)).is_none();
-
-        hits
+        !shadow_check(shadow_ray, Some(self.distance))
     }
 
     fn direction_from(&self, sp: SurfacePoint<T>) -> Vector3<<T as Div>::Output> {
@@ -219,11 +842,74 @@ where
     }
 }
 
+// A direction drawn evenly from the unit sphere, with its density per solid angle.
+fn uniform_direction<V>(sample: Point2<V>) -> (Vector3<V>, V)
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    let one = V::one();
+    let z = one - sample.x - sample.x;
+    let r = (one - z * z).max(V::zero()).sqrt();
+    let phi = (V::PI + V::PI) * sample.y;
+    (
+        Vector3::new(r * phi.cos(), r * phi.sin(), z),
+        uniform_direction_density(),
+    )
+}
+
+fn uniform_direction_density<V>() -> V
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    let four: V = 4u16.into();
+    V::one() / (V::PI * four)
+}
+
+// A direction drawn proportionally to its cosine to a normalized normal, with its density per
+// solid angle.
+fn cosine_direction<V>(n: Vector3<V>, sample: Point2<V>) -> (Vector3<V>, V)
+where
+    V: FloatingPoint + ConvenientNumber,
+{
+    let (tangent, bitangent) = tangent_frame(n);
+    let phi = (V::PI + V::PI) * sample.x;
+    let r = sample.y.sqrt();
+    let cos = (V::one() - sample.y).max(V::zero()).sqrt();
+    (
+        tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + n * cos,
+        cos / V::PI,
+    )
+}
+
+// Turns a density per solid angle around a position into a density per area of the surface a ray
+// from there hits first.
+pub(crate) fn area_density<T: Length>(
+    density: <T as Length>::ValueType,
+    position: Point3<T>,
+    sp: SurfacePoint<T>,
+) -> <T as Length>::ValueType
+where
+    <T as Length>::ValueType: FloatingPoint,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+{
+    let to_surface = sp.p - position;
+    let distance = to_surface.magnitude() / T::one();
+    density
+        * to_surface
+            .normalized()
+            .dot(sp.n.as_vector().normalized())
+            .abs()
+        / (distance * distance)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use colors::RGB;
+    use image::generator::Checkerboard;
     use math::{Normal3, Point2, Vector3};
     use units::angle::Radians;
     use units::length::Meter;
@@ -543,4 +1229,261 @@ mod tests {
 
     spot_light_get_color! { f32, spot_light_get_color_f32 }
     spot_light_get_color! { f64, spot_light_get_color_f64 }
+
+    macro_rules! spot_light_color_at_with_gobo {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let color = RGB::new(1.0, 0.5, 1.0);
+                let position = Point3::new(
+                    Meter::<$type>::new(0.0),
+                    Meter::<$type>::new(4.0),
+                    Meter::<$type>::new(0.0),
+                );
+                let direction = Vector3::<$type>::new(0.0, -1.0, 0.0);
+                let angle = Radians::new(0.5);
+
+                let light =
+                    SpotLight::<Meter<$type>, RGB<$type>>::new(color, position, direction, angle);
+                let gobo_light =
+                    SpotLight::<Meter<$type>, RGB<$type>>::new(color, position, direction, angle)
+                        .with_gobo(Box::new(Checkerboard::generate(
+                            RGB::new(1.0, 1.0, 1.0),
+                            RGB::new(0.0, 0.0, 0.0),
+                        )));
+
+                let sp = |x: $type, z: $type| {
+                    SurfacePoint::new(
+                        Point3::new(
+                            Meter::<$type>::new(x),
+                            Meter::<$type>::new(0.0),
+                            Meter::<$type>::new(z),
+                        ),
+                        Normal3::new(0 as $type, 1 as $type, 0 as $type),
+                        Point2::new(0 as $type, 0 as $type),
+                    )
+                };
+
+                assert_eq!(color, light.color_at(sp(0.5, 0.5)));
+                assert_eq!(color, light.color_at(sp(-0.5, 0.5)));
+
+                let lit = gobo_light.color_at(sp(0.5, 0.5));
+                let dark = gobo_light.color_at(sp(-0.5, 0.5));
+                assert_ne!(lit, dark);
+                assert!(lit == color || dark == color);
+                assert!(lit == RGB::default() || dark == RGB::default());
+            }
+        };
+    }
+
+    spot_light_color_at_with_gobo! { f32, spot_light_color_at_with_gobo_f32 }
+    spot_light_color_at_with_gobo! { f64, spot_light_color_at_with_gobo_f64 }
+
+    macro_rules! spot_light_emit {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let light = SpotLight::<Meter<$type>, RGB<$type>>::new(
+                    RGB::new(1.0, 1.0, 1.0),
+                    Point3::new(
+                        Meter::<$type>::new(0.0),
+                        Meter::<$type>::new(4.0),
+                        Meter::<$type>::new(0.0),
+                    ),
+                    Vector3::<$type>::new(0.0, -1.0, 0.0),
+                    Radians::new(0.5),
+                );
+                let pattern = SamplingPattern::new(vec![Point2::new(0.5, 0.5)]);
+                let mut rnd = WichmannHillPRNG::from_seed(1);
+
+                // The rays fill the cone evenly.
+                let cone = (2.0 * <$type>::PI * (1.0 - (0.5 as $type).cos())).recip();
+                for (x, y) in [(0.0, 0.0), (0.5, 0.25), (1.0, 1.0)] {
+                    let (ray, density) = light.emit(Point2::new(x, y), &pattern, &mut rnd).unwrap();
+                    assert_eq!(ray.origin, light.position);
+                    assert!(-ray.direction.y / Meter::new(1.0) >= (0.5 as $type).cos() - 0.0001);
+                    assert!((density - cone).abs() < 0.0001);
+                }
+
+                let sp = |x: $type| {
+                    SurfacePoint::new(
+                        Point3::new(
+                            Meter::<$type>::new(x),
+                            Meter::<$type>::new(0.0),
+                            Meter::<$type>::new(0.0),
+                        ),
+                        Normal3::new(0 as $type, 1 as $type, 0 as $type),
+                        Point2::new(0 as $type, 0 as $type),
+                    )
+                };
+
+                // Straight below the spot the density per area falls with the squared distance,
+                // outside of the cone nothing arrives.
+                let below = light.emission_density(sp(0.0)).unwrap();
+                assert!((below - cone / 16.0).abs() < 0.0001, "{}", below);
+                assert_eq!(light.emission_density(sp(4.0)), Some(0.0));
+            }
+        };
+    }
+
+    spot_light_emit! { f32, spot_light_emit_f32 }
+    spot_light_emit! { f64, spot_light_emit_f64 }
+
+    macro_rules! area_light_illuminates {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let light = AreaLight::<Meter<$type>, RGB<$type>>::new(
+                    RGB::new(1.0, 1.0, 1.0),
+                    Point3::new(
+                        Meter::<$type>::new(-1.0),
+                        Meter::<$type>::new(2.0),
+                        Meter::<$type>::new(-1.0),
+                    ),
+                    Vector3::new(
+                        Meter::<$type>::new(2.0),
+                        Meter::<$type>::new(0.0),
+                        Meter::<$type>::new(0.0),
+                    ),
+                    Vector3::new(
+                        Meter::<$type>::new(0.0),
+                        Meter::<$type>::new(0.0),
+                        Meter::<$type>::new(2.0),
+                    ),
+                );
+
+                let sp = |y: $type, normal_y: $type| {
+                    SurfacePoint::new(
+                        Point3::new(
+                            Meter::<$type>::new(0.0),
+                            Meter::<$type>::new(y),
+                            Meter::<$type>::new(0.0),
+                        ),
+                        Normal3::new(0 as $type, normal_y, 0 as $type),
+                        Point2::new(0 as $type, 0 as $type),
+                    )
+                };
+
+                // One pattern per point on a regular grid over the light, so every position is
+                // sampled exactly once.
+                let patterns: Vec<SamplingPattern<Point2<$type>>> = (0..8)
+                    .flat_map(|x| (0..8).map(move |y| (x, y)))
+                    .map(|(x, y)| {
+                        SamplingPattern::new(vec![Point2::new(
+                            (x as $type + 0.5) / 8.0,
+                            (y as $type + 0.5) / 8.0,
+                        )])
+                    })
+                    .collect();
+                let mut rnd = WichmannHillPRNG::from_seed(1);
+
+                // An occluder covers the half of the light with negative x.
+                let half_blocked =
+                    |ray: ParametricLine<Point3<Meter<$type>>, Vector3<Meter<$type>>>,
+                     _: Option<Meter<$type>>| ray.direction.x < Meter::new(0.0);
+                let unblocked = |_: ParametricLine<Point3<Meter<$type>>, Vector3<Meter<$type>>>,
+                                 _: Option<Meter<$type>>| false;
+
+                let lit = patterns
+                    .iter()
+                    .filter(|pattern| {
+                        light.illuminates(sp(0.0, 1.0), &half_blocked, pattern, &mut rnd)
+                    })
+                    .count();
+                assert_eq!(lit, 32);
+
+                assert!(patterns.iter().all(|pattern| light.illuminates(
+                    sp(0.0, 1.0),
+                    &unblocked,
+                    pattern,
+                    &mut rnd
+                )));
+                // The light only emits downwards.
+                assert!(!patterns.iter().any(|pattern| light.illuminates(
+                    sp(3.0, -1.0),
+                    &unblocked,
+                    pattern,
+                    &mut rnd
+                )));
+            }
+        };
+    }
+
+    area_light_illuminates! { f32, area_light_illuminates_f32 }
+    area_light_illuminates! { f64, area_light_illuminates_f64 }
+
+    macro_rules! sphere_light_illuminates {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let light = SphereLight::<Meter<$type>, RGB<$type>>::new(
+                    RGB::new(1.0, 1.0, 1.0),
+                    Point3::new(
+                        Meter::<$type>::new(0.0),
+                        Meter::<$type>::new(4.0),
+                        Meter::<$type>::new(0.0),
+                    ),
+                    Meter::<$type>::new(1.0),
+                );
+
+                let sp = SurfacePoint::new(
+                    Point3::new(
+                        Meter::<$type>::new(0.0),
+                        Meter::<$type>::new(0.0),
+                        Meter::<$type>::new(0.0),
+                    ),
+                    Normal3::new(0 as $type, 1 as $type, 0 as $type),
+                    Point2::new(0 as $type, 0 as $type),
+                );
+
+                let patterns: Vec<SamplingPattern<Point2<$type>>> = (0..8)
+                    .flat_map(|x| (0..8).map(move |y| (x, y)))
+                    .map(|(x, y)| {
+                        SamplingPattern::new(vec![Point2::new(
+                            (x as $type + 0.5) / 8.0,
+                            (y as $type + 0.5) / 8.0,
+                        )])
+                    })
+                    .collect();
+                let mut rnd = WichmannHillPRNG::from_seed(1);
+
+                // Every shadow ray points into the cone covered by the sphere.
+                let cos_theta_max = (1.0 as $type - 1.0 / 16.0).sqrt();
+                let inside_cone =
+                    |ray: ParametricLine<Point3<Meter<$type>>, Vector3<Meter<$type>>>,
+                     _: Option<Meter<$type>>| {
+                        let direction = ray.direction / Meter::<$type>::new(1.0);
+                        assert!(direction.y >= cos_theta_max - 0.0001);
+                        false
+                    };
+                assert!(patterns.iter().all(|pattern| light.illuminates(
+                    sp,
+                    &inside_cone,
+                    pattern,
+                    &mut rnd
+                )));
+
+                let half_blocked =
+                    |ray: ParametricLine<Point3<Meter<$type>>, Vector3<Meter<$type>>>,
+                     _: Option<Meter<$type>>| ray.direction.x < Meter::new(0.0);
+                let lit = patterns
+                    .iter()
+                    .filter(|pattern| light.illuminates(sp, &half_blocked, pattern, &mut rnd))
+                    .count();
+                assert_eq!(lit, 32);
+
+                // Geometry behind the bulb does not cast a shadow.
+                let behind = |_: ParametricLine<Point3<Meter<$type>>, Vector3<Meter<$type>>>,
+                              distance: Option<Meter<$type>>| {
+                    distance.is_none_or(|distance| distance > Meter::new(10.0))
+                };
+                assert!(patterns
+                    .iter()
+                    .all(|pattern| light.illuminates(sp, &behind, pattern, &mut rnd)));
+            }
+        };
+    }
+
+    sphere_light_illuminates! { f32, sphere_light_illuminates_f32 }
+    sphere_light_illuminates! { f64, sphere_light_illuminates_f64 }
 }
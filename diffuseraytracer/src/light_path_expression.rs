@@ -0,0 +1,318 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+// The events along the path of the light from a light source to the camera, written from the
+// camera towards the light, e.g. C D L for light that reached the camera after a diffuse
+// reflection.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EventKind {
+    Camera,
+    Diffuse,
+    Specular,
+    Light,
+    // Light from the background or the surroundings of the scene.
+    Background,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PathEvent<'a> {
+    pub kind: EventKind,
+    // The name of a light.
+    pub label: Option<&'a str>,
+}
+
+impl<'a> PathEvent<'a> {
+    pub fn new(kind: EventKind) -> PathEvent<'a> {
+        PathEvent { kind, label: None }
+    }
+
+    pub fn labeled(kind: EventKind, label: Option<&'a str>) -> PathEvent<'a> {
+        PathEvent { kind, label }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum EventPattern {
+    Any,
+    Kind(EventKind),
+    // A diffuse or a specular reflection.
+    Reflection,
+    Label(String),
+    All(Vec<EventPattern>),
+    Set {
+        patterns: Vec<EventPattern>,
+        negated: bool,
+    },
+}
+
+impl EventPattern {
+    fn matches(&self, event: &PathEvent) -> bool {
+        match self {
+            EventPattern::Any => true,
+            EventPattern::Kind(kind) => event.kind == *kind,
+            EventPattern::Reflection => {
+                event.kind == EventKind::Diffuse || event.kind == EventKind::Specular
+            }
+            EventPattern::Label(label) => event.label == Some(label.as_str()),
+            EventPattern::All(patterns) => patterns.iter().all(|p| p.matches(event)),
+            EventPattern::Set { patterns, negated } => {
+                patterns.iter().any(|p| p.matches(event)) != *negated
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum PathPattern {
+    Event(EventPattern),
+    Sequence(Vec<PathPattern>),
+    Alternative(Vec<PathPattern>),
+    Repeat {
+        pattern: Box<PathPattern>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+impl PathPattern {
+    // All positions in the path the pattern can end at if it starts at the position.
+    fn ends(&self, path: &[PathEvent], start: usize) -> Vec<usize> {
+        match self {
+            PathPattern::Event(event) => match path.get(start) {
+                Some(e) if event.matches(e) => vec![start + 1],
+                _ => vec![],
+            },
+            PathPattern::Sequence(patterns) => patterns.iter().fold(vec![start], |ends, p| {
+                let mut next: Vec<usize> = ends.iter().flat_map(|e| p.ends(path, *e)).collect();
+                next.sort();
+                next.dedup();
+                next
+            }),
+            PathPattern::Alternative(patterns) => {
+                let mut ends: Vec<usize> =
+                    patterns.iter().flat_map(|p| p.ends(path, start)).collect();
+                ends.sort();
+                ends.dedup();
+                ends
+            }
+            PathPattern::Repeat { pattern, min, max } => {
+                let mut ends = if *min == 0 { vec![start] } else { vec![] };
+                let mut current = vec![start];
+                // Repeating a pattern more often than the path has events only adds positions if
+                // the pattern matches nothing, which does not lead anywhere new.
+                for count in 1..=max.unwrap_or(path.len() + 1) {
+                    let mut next: Vec<usize> = current
+                        .iter()
+                        .flat_map(|e| pattern.ends(path, *e))
+                        .collect();
+                    next.sort();
+                    next.dedup();
+                    if next.is_empty() {
+                        break;
+                    }
+                    if count >= *min {
+                        ends.extend(&next);
+                    }
+                    current = next;
+                }
+                ends.sort();
+                ends.dedup();
+                ends
+            }
+        }
+    }
+}
+
+// A light path expression selects the paths of the light by a regular expression over their
+// events, so their contribution can be written to an image of its own. The events are
+//
+//   C  the camera
+//   D  a diffuse reflection
+//   S  a specular reflection
+//   R  any reflection
+//   L  a light
+//   B  the background
+//   .  any event
+//
+// followed by *, + or ? to repeat them. 'name' selects the light with the name. [DS] matches any
+// of the events in the brackets, [^S] any other event. Events in angle brackets must all match,
+// e.g. <L'sun'> is the light called sun. Parentheses group events and | separates alternatives.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LightPathExpression {
+    pattern: PathPattern,
+}
+
+impl LightPathExpression {
+    pub fn parse(expression: &str) -> Result<LightPathExpression, String> {
+        let mut chars = expression.chars().peekable();
+        let pattern = parse_alternative(&mut chars)?;
+        match chars.next() {
+            None => Ok(LightPathExpression { pattern }),
+            Some(c) => Err(format!(
+                "Unexpected '{}' in light path expression '{}'.",
+                c, expression
+            )),
+        }
+    }
+
+    pub fn matches(&self, path: &[PathEvent]) -> bool {
+        self.pattern.ends(path, 0).contains(&path.len())
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn parse_alternative(chars: &mut Peekable<Chars>) -> Result<PathPattern, String> {
+    let mut alternatives = vec![parse_sequence(chars)?];
+    while chars.next_if_eq(&'|').is_some() {
+        alternatives.push(parse_sequence(chars)?);
+    }
+
+    Ok(if alternatives.len() == 1 {
+        alternatives.remove(0)
+    } else {
+        PathPattern::Alternative(alternatives)
+    })
+}
+
+fn parse_sequence(chars: &mut Peekable<Chars>) -> Result<PathPattern, String> {
+    let mut sequence = vec![];
+    loop {
+        skip_whitespace(chars);
+        let pattern = match chars.peek() {
+            None | Some('|') | Some(')') => break,
+            Some('(') => {
+                chars.next();
+                let group = parse_alternative(chars)?;
+                if chars.next() != Some(')') {
+                    return Err(String::from("Missing ')' in light path expression."));
+                }
+                group
+            }
+            Some(_) => PathPattern::Event(parse_event(chars)?),
+        };
+
+        skip_whitespace(chars);
+        let (min, max) = match chars.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            _ => {
+                sequence.push(pattern);
+                continue;
+            }
+        };
+        chars.next();
+        sequence.push(PathPattern::Repeat {
+            pattern: Box::new(pattern),
+            min,
+            max,
+        });
+    }
+
+    Ok(PathPattern::Sequence(sequence))
+}
+
+fn parse_event(chars: &mut Peekable<Chars>) -> Result<EventPattern, String> {
+    match chars.next() {
+        Some('C') => Ok(EventPattern::Kind(EventKind::Camera)),
+        Some('D') => Ok(EventPattern::Kind(EventKind::Diffuse)),
+        Some('S') => Ok(EventPattern::Kind(EventKind::Specular)),
+        Some('R') => Ok(EventPattern::Reflection),
+        Some('L') => Ok(EventPattern::Kind(EventKind::Light)),
+        Some('B') => Ok(EventPattern::Kind(EventKind::Background)),
+        Some('.') => Ok(EventPattern::Any),
+        Some('\'') => {
+            let mut label = String::new();
+            loop {
+                match chars.next() {
+                    Some('\'') => break,
+                    Some(c) => label.push(c),
+                    None => {
+                        return Err(String::from(
+                            "Missing closing ' of a name in light path expression.",
+                        ))
+                    }
+                }
+            }
+            Ok(EventPattern::Label(label))
+        }
+        Some('<') => {
+            let mut patterns = vec![];
+            while chars.next_if_eq(&'>').is_none() {
+                if chars.peek().is_none() {
+                    return Err(String::from("Missing '>' in light path expression."));
+                }
+                patterns.push(parse_event(chars)?);
+            }
+            Ok(EventPattern::All(patterns))
+        }
+        Some('[') => {
+            let negated = chars.next_if_eq(&'^').is_some();
+            let mut patterns = vec![];
+            while chars.next_if_eq(&']').is_none() {
+                if chars.peek().is_none() {
+                    return Err(String::from("Missing ']' in light path expression."));
+                }
+                patterns.push(parse_event(chars)?);
+            }
+            Ok(EventPattern::Set { patterns, negated })
+        }
+        Some(c) => Err(format!("Unknown event '{}' in light path expression.", c)),
+        None => Err(String::from("Unexpected end of light path expression.")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn light_path_expression_matches_paths() {
+        let camera = PathEvent::new(EventKind::Camera);
+        let diffuse = PathEvent::new(EventKind::Diffuse);
+        let specular = PathEvent::new(EventKind::Specular);
+        let background = PathEvent::new(EventKind::Background);
+        let sun = PathEvent::labeled(EventKind::Light, Some("sun"));
+        let lamp = PathEvent::labeled(EventKind::Light, Some("lamp"));
+
+        let matches = |expression: &str, path: &[PathEvent]| {
+            LightPathExpression::parse(expression)
+                .unwrap()
+                .matches(path)
+        };
+
+        assert!(matches("CDL", &[camera, diffuse, sun]));
+        assert!(!matches("CDL", &[camera, specular, sun]));
+        assert!(matches("C<RD>*L", &[camera, diffuse, diffuse, lamp]));
+        assert!(matches("C<RD>*L", &[camera, lamp]));
+        assert!(!matches("C<RD>*L", &[camera, specular, lamp]));
+        assert!(matches("CSD+L", &[camera, specular, diffuse, diffuse, sun]));
+        assert!(!matches("CSD+L", &[camera, specular, sun]));
+        assert!(matches("CR?B", &[camera, background]));
+        assert!(matches("CR?B", &[camera, specular, background]));
+        assert!(matches("C.*", &[camera, specular, background]));
+        assert!(matches("C [DS] <L'sun'>", &[camera, specular, sun]));
+        assert!(!matches("C [DS] <L'sun'>", &[camera, specular, lamp]));
+        assert!(matches("C[^S]'lamp'", &[camera, diffuse, lamp]));
+        assert!(!matches("C[^S]'lamp'", &[camera, specular, lamp]));
+        assert!(matches("C(D|SS)L", &[camera, specular, specular, sun]));
+        assert!(!matches("C(D|SS)L", &[camera, specular, sun]));
+        assert!(matches(
+            "C(DS?)*L",
+            &[camera, diffuse, diffuse, specular, sun]
+        ));
+    }
+
+    #[test]
+    fn light_path_expression_reports_syntax_errors() {
+        assert!(LightPathExpression::parse("C(DL").is_err());
+        assert!(LightPathExpression::parse("C<RD").is_err());
+        assert!(LightPathExpression::parse("C[DS").is_err());
+        assert!(LightPathExpression::parse("C'sun").is_err());
+        assert!(LightPathExpression::parse("CXL").is_err());
+        assert!(LightPathExpression::parse("CDL)").is_err());
+    }
+}
@@ -1,22 +1,61 @@
+use cg_basics::exposure::{AutoExposure, Metering, PhysicalExposure};
 use cg_basics::scene_graph::Scene3;
-use colors::{RGB, RGBA};
-use diffuseraytracer::camera::RaytracingCamera;
+use colors::{Color, Gray, RGB, RGBA};
+use diffuseraytracer::ambient_occlusion::AmbientOcclusionRenderer;
+use diffuseraytracer::aov::{Aov, Aovs};
+use diffuseraytracer::bidirectional_path_tracer::BidirectionalPathTracer;
+use diffuseraytracer::camera::{CropWindow, CroppedCamera, FrameCamera, RaytracingCamera};
+use diffuseraytracer::checkpoint::Checkpoint;
+use diffuseraytracer::contours::ContourStyle;
+use diffuseraytracer::coverage::render_coverage;
+use diffuseraytracer::denoiser::Denoiser;
 use diffuseraytracer::diffuse_ray_tracer::DiffuseRayTracer;
+use diffuseraytracer::distributed::{self, TileRequest, TileResult};
+use diffuseraytracer::gizmo::add_gizmos;
 use diffuseraytracer::light::Light;
+use diffuseraytracer::light_path_expression::LightPathExpression;
+use diffuseraytracer::metrics::{Metrics, SampleCounts};
+use diffuseraytracer::parser::assets;
+use diffuseraytracer::parser::plugin::PluginRegistry;
+use diffuseraytracer::parser::{util, ParsingError};
+use diffuseraytracer::path_tracer::PathTracer;
+use diffuseraytracer::progressive::Progressive;
+use diffuseraytracer::whitted_ray_tracer::WhittedRayTracer;
 use diffuseraytracer::Renderable;
-use image::converter::Converter;
-use image::farbfeld::Encoder;
+use image::accumulation_buffer::AccumulationBuffer;
+use image::anaglyph::Anaglyph;
+use image::comparison;
+use image::converter::{Converter, ToneMapping};
+use image::exr::{ExrImage, PixelType, Writer as ExrWriter};
+use image::farbfeld::Writer as FarbfeldWriter;
+use image::filter::ReconstructionFilter;
+use image::hdr::Writer as HdrWriter;
+use image::pfm::Encoder as PfmEncoder;
+use image::png::Encoder as PngEncoder;
+use image::ppm::Writer as PpmWriter;
+use image::tiled_buffer::TiledBuffer;
+use image::{Image, ImageBuffer, ScanlineEncoder, WritableImage};
 use math::{Point2, Vector2};
 use random::{RandomNumberGenerator, WichmannHillPRNG};
 use sampling::{
-    HammersleyPatternGenerator, JitteredPatternGenerator, MultiJitteredPatterGenerator,
-    NRooksPatternGenerator, RandomPatternGenerator, RegularPatternGenerator, SamplingPatternSet,
+    AdaptiveSampling, HammersleyPatternGenerator, JitteredPatternGenerator,
+    MultiJitteredPatterGenerator, NRooksPatternGenerator, RandomPatternGenerator,
+    RegularPatternGenerator, SamplingPatternSet,
 };
 use units::length::Meter;
 
 use std::env;
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{self, BufWriter, Write};
+use std::net::{TcpListener, TcpStream};
+use std::ops::Range;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 type FloatingPointType = f64;
 type LengthType = Meter<FloatingPointType>;
@@ -28,12 +67,96 @@ type GeometryContainer = Box<dyn Renderable<LengthType, ColorType>>;
 
 type SceneType = Scene3<ColorType, LightContainer, CameraContainer, GeometryContainer>;
 
+enum Exposure {
+    Physical(PhysicalExposure<FloatingPointType>),
+    // Metered from the rendered image.
+    Auto(AutoExposure<FloatingPointType>),
+}
+
+// How the eyes of a stereo camera are written.
+enum StereoOutput {
+    // One file per eye.
+    Separate,
+    // A single red-cyan image.
+    Anaglyph,
+}
+
+// How the rendered colors become the written images. The tone mapping steps compress the
+// highlights before the colors are clamped and encoded to sRGB, unless linear colors are written.
+// For retro renders, the image is rendered with fewer, larger pixels and its colors may be limited
+// to a palette. EXR files hold their channels in 16 or 32 bit floats, and the auxiliary outputs as
+// further layers. The coverage of the pixels becomes the alpha channel of farbfeld, PNG and EXR
+// files.
+struct Style {
+    tone_mapping: Vec<ToneMapping<FloatingPointType>>,
+    srgb: bool,
+    pixel_size: usize,
+    palette: Option<Vec<ColorType>>,
+    exr_pixel_type: PixelType,
+    layers: Option<ExrImage>,
+    alpha: Option<ImageBuffer<Gray<FloatingPointType>>>,
+}
+
+// The algorithm that renders the image.
+enum Integrator {
+    // Direct lighting with the reflections of the materials, the default.
+    Diffuse,
+    // Traces reflection and refraction rays up to a depth.
+    Whitted { max_depth: usize },
+    // Follows paths of light bouncing around in the scene up to a depth.
+    Path { max_depth: usize },
+    // Connects the paths from the camera to paths from the lights, up to a depth.
+    Bidirectional { max_depth: usize },
+    // The ambient occlusion of the surfaces within a distance, without materials and lights.
+    AmbientOcclusion { distance: FloatingPointType },
+}
+
 struct Configuration {
     scene: SceneType,
+    scene_filenames: Vec<String>,
+    include_dirs: Vec<PathBuf>,
+    pack: Option<PathBuf>,
     camera_name: String,
     size: Vector2<usize>,
     output: String,
     sampling_patterns: SamplingPatternSet<Point2<FloatingPointType>>,
+    shadow_bias: FloatingPointType,
+    adaptive_sampling: Option<AdaptiveSampling<FloatingPointType>>,
+    seed: u128,
+    threads: usize,
+    filter: ReconstructionFilter<FloatingPointType>,
+    exposure: Option<Exposure>,
+    lighting_components: bool,
+    light_groups: bool,
+    contours: Option<ContourStyle<ColorType>>,
+    // The name of the output, the expression and whether the paths are excluded from the image
+    // instead of selected.
+    light_paths: Vec<(String, LightPathExpression, bool)>,
+    stereo: Option<StereoOutput>,
+    shadows: bool,
+    stats: bool,
+    progress: bool,
+    style: Style,
+    integrator: Integrator,
+    // Camera rays and shadow rays are traced in packets.
+    packets: bool,
+    // Writes the samples taken for each pixel in false colors next to the image.
+    sample_heatmap: bool,
+    progressive: Option<Progressive>,
+    aovs: Vec<Aov>,
+    // Writes the coverage of the pixels as alpha channel, so the image can be composited over
+    // other backgrounds.
+    alpha: bool,
+    denoiser: Option<Denoiser<FloatingPointType>>,
+    checkpoint: Option<PathBuf>,
+    // The passes of an interrupted render to continue.
+    resumed: Option<Checkpoint<ColorType>>,
+    // The addresses of the workers that render the tiles of the image, and the arguments they
+    // render them with.
+    workers: Vec<String>,
+    worker_arguments: Vec<String>,
+    // The file the tiles of the workers are assembled in, instead of in memory.
+    framebuffer_file: Option<PathBuf>,
 }
 
 fn parse_next_usize(
@@ -182,21 +305,149 @@ fn parse_sampling_pattern_set(
     }
 }
 
-fn parse_configuration(mut args: impl Iterator<Item = String>) -> Result<Configuration, String> {
-    _ = args.next();
+// How the message of a scene that can not be parsed starts.
+const SCENE_PARSING_FAILURE: &str = "Failed to parse passed scene file.";
+
+fn parse_configuration(args: impl Iterator<Item = String>) -> Result<Configuration, String> {
+    let args: Vec<String> = args.skip(1).collect();
+
+    // The seed is needed before any other argument, since random sampling patterns are derived
+    // from it.
+    let seed = match args.iter().position(|arg| arg == "--seed") {
+        Some(index) => match args.get(index + 1) {
+            Some(seed) => match seed.parse::<u128>() {
+                Ok(seed) => seed,
+                Err(m) => {
+                    return Err(format!("Unable to parse seed: {}", m));
+                }
+            },
+            None => {
+                return Err(String::from("Missing seed."));
+            }
+        },
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos(),
+    };
+
+    let frame = match args.iter().position(|arg| arg == "--frame") {
+        Some(index) => match args.get(index + 1) {
+            Some(frame) => match frame.parse::<u64>() {
+                Ok(frame) => Some(frame),
+                Err(m) => {
+                    return Err(format!("Unable to parse frame: {}", m));
+                }
+            },
+            None => {
+                return Err(String::from("Missing frame."));
+            }
+        },
+        None => None,
+    };
+
+    // Frames of an animation get their own seed, unless the noise should stay the same for every
+    // frame, e.g. for temporal denoising.
+    let seed = match frame {
+        Some(_) if args.iter().any(|arg| arg == "--static-noise") => seed,
+        Some(frame) => random::frame_seed(seed, frame),
+        None => seed,
+    };
+
+    // A resumed render continues with the seed of its checkpoint, unless another one is given.
+    let resumed = match args.iter().any(|arg| arg == "--resume") {
+        true => match args.iter().position(|arg| arg == "--checkpoint") {
+            Some(index) => match args.get(index + 1) {
+                Some(path) => Some(Checkpoint::<ColorType>::load(Path::new(path))?),
+                None => {
+                    return Err(String::from("Missing checkpoint filename."));
+                }
+            },
+            None => {
+                return Err(String::from("Resuming a render needs a checkpoint."));
+            }
+        },
+        false => None,
+    };
+    let seed = match &resumed {
+        Some(resumed) if !args.iter().any(|arg| arg == "--seed") => resumed.seed,
+        _ => seed,
+    };
+
+    // Include directories are needed before the scene is parsed.
+    let mut include_dirs: Vec<PathBuf> = Vec::new();
+    for (index, arg) in args.iter().enumerate() {
+        if arg == "-I" {
+            match args.get(index + 1) {
+                Some(directory) => include_dirs.push(PathBuf::from(directory)),
+                None => {
+                    return Err(String::from("Missing include directory."));
+                }
+            }
+        }
+    }
+
+    let worker_arguments = worker_arguments(&args);
+
+    let mut args = args.into_iter();
     let mut size = Vector2::new(640, 480);
     let mut camera_name: String = String::from("main");
-    let mut scene: Option<SceneType> = None;
+    let mut scene_filenames: Vec<String> = Vec::new();
+    let mut pack: Option<PathBuf> = None;
     let mut output: String = String::from("out.ff");
-    let mut rnd = WichmannHillPRNG::new_random();
-    let mut sampling_patterns =
-        SamplingPatternSet::<Point2<FloatingPointType>>::regular_pattern(1, 1);
+    let mut rnd = WichmannHillPRNG::from_seed(seed);
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut sampling_patterns: Option<SamplingPatternSet<Point2<FloatingPointType>>> = None;
+    let mut filter = ReconstructionFilter::Box;
+    let mut adaptive_sampling: Option<AdaptiveSampling<FloatingPointType>> = None;
+    let mut exposure: Option<Exposure> = None;
+    let mut lighting_components = false;
+    let mut light_groups = false;
+    let mut contours: Option<ContourStyle<ColorType>> = None;
+    let contour_style = |contours: Option<ContourStyle<ColorType>>| {
+        contours.unwrap_or(ContourStyle::new(
+            RGB::new(0.0, 0.0, 0.0),
+            RGB::new(1.0, 1.0, 1.0),
+        ))
+    };
+    let mut light_paths: Vec<(String, LightPathExpression, bool)> = vec![];
+    let mut stereo: Option<StereoOutput> = None;
+    let mut shadows = false;
+    let mut stats = false;
+    let mut progress = false;
+    let mut packets = false;
+    let mut sample_heatmap = false;
+    let mut time: Option<FloatingPointType> = None;
+    let mut fps: Option<FloatingPointType> = None;
+    let mut style = Style {
+        tone_mapping: vec![],
+        srgb: true,
+        pixel_size: 1,
+        palette: None,
+        exr_pixel_type: PixelType::Float,
+        layers: None,
+        alpha: None,
+    };
+    let mut integrator = Integrator::Diffuse;
+    let mut max_depth: Option<usize> = None;
+    let mut ao_distance: Option<FloatingPointType> = None;
+    let mut crop: Option<CropWindow> = None;
+    let mut cropped = false;
+    let mut gizmos: Option<FloatingPointType> = None;
+    let mut progressive: Option<Progressive> = None;
+    let mut update_interval: Option<Duration> = None;
+    let mut aovs: Vec<Aov> = vec![];
+    let mut alpha = false;
+    let mut denoiser: Option<Denoiser<FloatingPointType>> = None;
+    let mut checkpoint: Option<PathBuf> = None;
+    let mut workers: Vec<String> = vec![];
+    let mut framebuffer_file: Option<PathBuf> = None;
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--sampling" => match parse_sampling_pattern_set(&mut args, &mut rnd) {
                 Ok(patterns) => {
-                    sampling_patterns = patterns;
+                    sampling_patterns = Some(patterns);
                 }
                 Err(m) => {
                     return Err(m);
@@ -223,6 +474,104 @@ fn parse_configuration(mut args: impl Iterator<Item = String>) -> Result<Configu
 
                 size = Vector2::new(width.unwrap(), height.unwrap());
             }
+            // The window of the image that is rendered, from the upper left corner to the lower
+            // right one, which is not part of it.
+            "--crop" => {
+                let mut corners = [0; 4];
+                for (corner, name) in corners.iter_mut().zip(["x0", "y0", "x1", "y1"]) {
+                    let value = args.next();
+                    if value.is_none() {
+                        return Err(format!("Missing {} of crop window.", name));
+                    }
+                    match value.unwrap().parse::<usize>() {
+                        Ok(value) => *corner = value,
+                        Err(m) => {
+                            return Err(format!("Unable to parse {} of crop window: {}", name, m));
+                        }
+                    }
+                }
+                let [x0, y0, x1, y1] = corners;
+                crop = Some(CropWindow::new(Point2::new(x0, y0), Point2::new(x1, y1)));
+            }
+            // Writes the crop window alone instead of the whole image.
+            "--cropped" => {
+                cropped = true;
+            }
+            // Takes further samples for noisy pixels, from the minimal up to the maximal number of
+            // samples, in steps of the sampling patterns.
+            "--adaptive" => {
+                let min_samples = args.next();
+                if min_samples.is_none() {
+                    return Err(String::from("Missing minimal number of samples."));
+                }
+                let min_samples = min_samples.unwrap().parse::<usize>();
+                if let Err(m) = min_samples {
+                    return Err(format!("Unable to parse minimal number of samples: {}", m));
+                }
+
+                let max_samples = args.next();
+                if max_samples.is_none() {
+                    return Err(String::from("Missing maximal number of samples."));
+                }
+                let max_samples = max_samples.unwrap().parse::<usize>();
+                if let Err(m) = max_samples {
+                    return Err(format!("Unable to parse maximal number of samples: {}", m));
+                }
+
+                let threshold = args.next();
+                if threshold.is_none() {
+                    return Err(String::from("Missing noise threshold."));
+                }
+                let threshold = threshold.unwrap().parse::<FloatingPointType>();
+                if let Err(m) = threshold {
+                    return Err(format!("Unable to parse noise threshold: {}", m));
+                }
+
+                adaptive_sampling = Some(AdaptiveSampling::new(
+                    min_samples.unwrap(),
+                    max_samples.unwrap(),
+                    threshold.unwrap(),
+                ));
+            }
+            "--seed" | "--frame" | "-I" => {
+                _ = args.next();
+            }
+            "--static-noise" | "--resume" => {}
+            "--threads" => match args.next() {
+                Some(t) => match t.parse::<usize>() {
+                    Ok(t) => {
+                        threads = t;
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse number of threads: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing number of threads."));
+                }
+            },
+            "--filter" => match args.next() {
+                Some(f) => match f.as_str() {
+                    "box" => {
+                        filter = ReconstructionFilter::Box;
+                    }
+                    "gaussian" => {
+                        filter = ReconstructionFilter::Gaussian {
+                            radius: 1.5,
+                            alpha: 2.0,
+                        };
+                    }
+                    "sinc" => {
+                        filter = ReconstructionFilter::WindowedSinc { radius: 3.0 };
+                    }
+                    f => {
+                        return Err(format!("Unknown filter {}.", f));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing filter."));
+                }
+            },
             "--camera" => match args.next() {
                 Some(c) => {
                     camera_name = c;
@@ -231,6 +580,386 @@ fn parse_configuration(mut args: impl Iterator<Item = String>) -> Result<Configu
                     return Err(String::from("Missing camera name."));
                 }
             },
+            "--physical-exposure" => {
+                exposure = Some(Exposure::Physical(
+                    PhysicalExposure::<FloatingPointType>::daylight(),
+                ));
+            }
+            "--ev100" => match args.next() {
+                Some(ev100) => match ev100.parse::<FloatingPointType>() {
+                    Ok(ev100) => {
+                        exposure = Some(Exposure::Physical(PhysicalExposure::new(ev100)));
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse EV100: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing EV100 value."));
+                }
+            },
+            "--auto-exposure" => match args.next().as_deref() {
+                Some("average") => {
+                    exposure = Some(Exposure::Auto(AutoExposure::<FloatingPointType>::new(
+                        Metering::Average,
+                    )));
+                }
+                Some("center-weighted") => {
+                    exposure = Some(Exposure::Auto(AutoExposure::<FloatingPointType>::new(
+                        Metering::CenterWeighted,
+                    )));
+                }
+                Some("median") => {
+                    exposure = Some(Exposure::Auto(AutoExposure::<FloatingPointType>::new(
+                        Metering::Median,
+                    )));
+                }
+                Some(m) => {
+                    return Err(format!("Unknown metering {}.", m));
+                }
+                None => {
+                    return Err(String::from("Missing metering."));
+                }
+            },
+            "--lighting-components" => {
+                lighting_components = true;
+            }
+            "--light-groups" => {
+                light_groups = true;
+            }
+            "--contours" => {
+                contours = Some(contour_style(contours));
+            }
+            "--contour-interval" => match args.next() {
+                Some(interval) => match interval.parse::<FloatingPointType>() {
+                    Ok(interval) => {
+                        contours = Some(contour_style(contours).with_depth_interval(interval));
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse contour interval: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing contour interval."));
+                }
+            },
+            "--crease-angle" => match args.next() {
+                Some(angle) => match angle.parse::<FloatingPointType>() {
+                    Ok(angle) => {
+                        contours =
+                            Some(contour_style(contours).with_crease_angle(angle.to_radians()));
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse crease angle: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing crease angle."));
+                }
+            },
+            "--lpe" | "--lpe-exclude" => {
+                let Some(name) = args.next() else {
+                    return Err(String::from("Missing name of light path expression."));
+                };
+                let Some(expression) = args.next() else {
+                    return Err(String::from("Missing light path expression."));
+                };
+                light_paths.push((
+                    name,
+                    LightPathExpression::parse(&expression)?,
+                    arg == "--lpe-exclude",
+                ));
+            }
+            "--stereo" => {
+                stereo = Some(StereoOutput::Separate);
+            }
+            "--anaglyph" => {
+                stereo = Some(StereoOutput::Anaglyph);
+            }
+            "--time" => match args.next() {
+                Some(t) => match t.parse::<FloatingPointType>() {
+                    Ok(t) => {
+                        time = Some(t);
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse time: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing time."));
+                }
+            },
+            "--fps" => match args.next() {
+                Some(f) => match f.parse::<FloatingPointType>() {
+                    Ok(f) if f > 0.0 => {
+                        fps = Some(f);
+                    }
+                    Ok(_) => {
+                        return Err(String::from("Frames per second must be positive."));
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse frames per second: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing frames per second."));
+                }
+            },
+            "--shadows" => {
+                shadows = true;
+            }
+            "--linear-output" => {
+                style.srgb = false;
+            }
+            "--half-float" => {
+                style.exr_pixel_type = PixelType::Half;
+            }
+            "--stats" => {
+                stats = true;
+            }
+            "--progress" => {
+                progress = true;
+            }
+            "--packets" => {
+                packets = true;
+            }
+            "--sample-heatmap" => {
+                sample_heatmap = true;
+            }
+            // The steps are applied in the order they are given.
+            "--tone-map" => match args.next().as_deref() {
+                Some("reinhard") => {
+                    style.tone_mapping.push(ToneMapping::Reinhard);
+                }
+                Some("aces") => {
+                    style.tone_mapping.push(ToneMapping::Aces);
+                }
+                Some("exposure") => match args.next() {
+                    Some(s) => match s.parse::<FloatingPointType>() {
+                        Ok(stops) => {
+                            style.tone_mapping.push(ToneMapping::Exposure(
+                                (2.0 as FloatingPointType).powf(stops),
+                            ));
+                        }
+                        Err(m) => {
+                            return Err(format!("Unable to parse exposure stops: {}", m));
+                        }
+                    },
+                    None => {
+                        return Err(String::from("Missing exposure stops."));
+                    }
+                },
+                Some("gamma") => match args.next() {
+                    Some(g) => match g.parse::<FloatingPointType>() {
+                        Ok(g) if g > 0.0 => {
+                            style.tone_mapping.push(ToneMapping::Gamma(g));
+                        }
+                        Ok(_) => {
+                            return Err(String::from("The gamma must be positive."));
+                        }
+                        Err(m) => {
+                            return Err(format!("Unable to parse gamma: {}", m));
+                        }
+                    },
+                    None => {
+                        return Err(String::from("Missing gamma."));
+                    }
+                },
+                Some(t) => {
+                    return Err(format!("Unknown tone mapping {}.", t));
+                }
+                None => {
+                    return Err(String::from("Missing tone mapping."));
+                }
+            },
+            "--pixel-art" => match args.next() {
+                Some(p) => match p.parse::<usize>() {
+                    Ok(p) if p > 0 => {
+                        style.pixel_size = p;
+                    }
+                    Ok(_) => {
+                        return Err(String::from("The pixel size must be positive."));
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse pixel size: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing pixel size."));
+                }
+            },
+            "--palette" => match args.next() {
+                Some(p) => {
+                    style.palette = Some(parse_palette(&p)?);
+                }
+                None => {
+                    return Err(String::from("Missing palette."));
+                }
+            },
+            "--integrator" => match args.next().as_deref() {
+                Some("diffuse") => {
+                    integrator = Integrator::Diffuse;
+                }
+                Some("whitted") => {
+                    integrator = Integrator::Whitted { max_depth: 5 };
+                }
+                Some("path") => {
+                    integrator = Integrator::Path { max_depth: 8 };
+                }
+                Some("bdpt") => {
+                    integrator = Integrator::Bidirectional { max_depth: 8 };
+                }
+                Some("ao") => {
+                    integrator = Integrator::AmbientOcclusion { distance: 1.0 };
+                }
+                Some(i) => {
+                    return Err(format!("Unknown integrator {}.", i));
+                }
+                None => {
+                    return Err(String::from("Missing integrator."));
+                }
+            },
+            "--max-depth" => match args.next() {
+                Some(d) => match d.parse::<usize>() {
+                    Ok(d) => {
+                        max_depth = Some(d);
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse maximum depth: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing maximum depth."));
+                }
+            },
+            "--ao-distance" => match args.next() {
+                Some(d) => match d.parse::<FloatingPointType>() {
+                    Ok(d) if d > 0.0 => {
+                        ao_distance = Some(d);
+                    }
+                    Ok(_) => {
+                        return Err(String::from(
+                            "The ambient occlusion distance must be positive.",
+                        ));
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse ambient occlusion distance: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing ambient occlusion distance."));
+                }
+            },
+            // Shows the other cameras and the lights of the scene as wireframes of the size.
+            "--gizmos" => match args.next() {
+                Some(g) => match g.parse::<FloatingPointType>() {
+                    Ok(g) if g > 0.0 => {
+                        gizmos = Some(g);
+                    }
+                    Ok(_) => {
+                        return Err(String::from("The size of the gizmos must be positive."));
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse size of the gizmos: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing size of the gizmos."));
+                }
+            },
+            "--denoise" => {
+                denoiser = Some(Denoiser::new(5));
+            }
+            // Renders the image in passes and writes the average after each of them.
+            "--progressive" => match args.next() {
+                Some(p) => match p.parse::<usize>() {
+                    Ok(p) if p > 0 => {
+                        progressive = Some(Progressive::new(p));
+                    }
+                    Ok(_) => {
+                        return Err(String::from("The number of passes must be positive."));
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse number of passes: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing number of passes."));
+                }
+            },
+            "--update-interval" => match args.next() {
+                Some(i) => match i.parse::<FloatingPointType>() {
+                    Ok(i) if i >= 0.0 => {
+                        update_interval = Some(Duration::from_secs_f64(i));
+                    }
+                    Ok(_) => {
+                        return Err(String::from("The update interval must not be negative."));
+                    }
+                    Err(m) => {
+                        return Err(format!("Unable to parse update interval: {}", m));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing update interval."));
+                }
+            },
+            // Saves the passes of a progressive render, so an interrupted render can be resumed.
+            "--checkpoint" => match args.next() {
+                Some(filename) => {
+                    checkpoint = Some(PathBuf::from(filename));
+                }
+                None => {
+                    return Err(String::from("Missing checkpoint filename."));
+                }
+            },
+            // Writes an auxiliary output next to the image.
+            "--aov" => match args.next() {
+                Some(name) => match Aov::from_name(&name) {
+                    Some(aov) => {
+                        if !aovs.contains(&aov) {
+                            aovs.push(aov);
+                        }
+                    }
+                    None => {
+                        return Err(format!("Unknown auxiliary output {}.", name));
+                    }
+                },
+                None => {
+                    return Err(String::from("Missing auxiliary output."));
+                }
+            },
+            // Pixels where the camera sees no geometry become transparent.
+            "--alpha" => {
+                alpha = true;
+            }
+            // Renders the image on workers, given as a comma separated list of addresses like
+            // render1:7878,render2:7878.
+            "--workers" => match args.next() {
+                Some(addresses) => {
+                    workers = addresses.split(',').map(String::from).collect();
+                }
+                None => {
+                    return Err(String::from("Missing worker addresses."));
+                }
+            },
+            // Keeps only some of the tiles of the workers in memory and the others in the file,
+            // for images too large for the memory of the coordinator.
+            "--framebuffer-file" => match args.next() {
+                Some(filename) => {
+                    framebuffer_file = Some(PathBuf::from(filename));
+                }
+                None => {
+                    return Err(String::from("Missing framebuffer filename."));
+                }
+            },
+            "--pack" => match args.next() {
+                Some(directory) => {
+                    pack = Some(PathBuf::from(directory));
+                }
+                None => {
+                    return Err(String::from("Missing directory to pack the scene into."));
+                }
+            },
             "-O" => match args.next() {
                 Some(o) => {
                     output = o;
@@ -240,58 +969,1846 @@ fn parse_configuration(mut args: impl Iterator<Item = String>) -> Result<Configu
                 }
             },
 
-            filename => match diffuseraytracer::parser::parse_scene::<LengthType>(filename) {
-                Ok(s) => {
-                    scene = Some(s);
-                }
-                Err(err) => {
-                    return Err(format!(
-                        "Failed to parse passed scene file. Error was: {:?}",
-                        err
-                    ));
-                }
-            },
+            filename => {
+                scene_filenames.push(filename.to_string());
+            }
         }
     }
 
-    if scene.is_none() {
+    if scene_filenames.is_empty() {
         return Err(String::from("No scene file was passed."));
     }
 
+    // The image is rendered with one sample position per large pixel and enlarged when written.
+    if !size.x.is_multiple_of(style.pixel_size) || !size.y.is_multiple_of(style.pixel_size) {
+        return Err(String::from(
+            "The size of the image must be a multiple of the pixel size.",
+        ));
+    }
+    let size = Vector2::new(size.x / style.pixel_size, size.y / style.pixel_size);
+
+    let crop = match crop {
+        Some(window) => {
+            if window.min.x >= window.max.x
+                || window.min.y >= window.max.y
+                || window.max.x > size.x * style.pixel_size
+                || window.max.y > size.y * style.pixel_size
+            {
+                return Err(String::from(
+                    "The crop window must be a non-empty part of the image.",
+                ));
+            }
+            let corners = [window.min.x, window.min.y, window.max.x, window.max.y];
+            if corners.iter().any(|c| !c.is_multiple_of(style.pixel_size)) {
+                return Err(String::from(
+                    "The corners of the crop window must be multiples of the pixel size.",
+                ));
+            }
+            Some(CropWindow::new(
+                Point2::new(
+                    window.min.x / style.pixel_size,
+                    window.min.y / style.pixel_size,
+                ),
+                Point2::new(
+                    window.max.x / style.pixel_size,
+                    window.max.y / style.pixel_size,
+                ),
+            ))
+        }
+        None if cropped => {
+            return Err(String::from("A cropped image needs a crop window."));
+        }
+        None => None,
+    };
+
+    // Later scene files add to the earlier ones, e.g. a lighting rig for a scene.
+    let filenames: Vec<&str> = scene_filenames.iter().map(String::as_str).collect();
+    let (mut scene, settings) = match diffuseraytracer::parser::parse_scenes_and_settings::<
+        LengthType,
+    >(&filenames, &PluginRegistry::new(), &include_dirs)
+    {
+        Ok(parsed) => parsed,
+        Err(ParsingError::LocatedParsingError(location, cause)) => {
+            return Err(format!(
+                "{} Error was: {:?}\n{}",
+                SCENE_PARSING_FAILURE, cause, location
+            ));
+        }
+        Err(err) => {
+            return Err(format!("{} Error was: {:?}", SCENE_PARSING_FAILURE, err));
+        }
+    };
+
+    // Animated cameras are evaluated at the time of the frame, either passed directly or derived
+    // from the number of the frame, e.g. --frame 48 --fps 24 renders the frame at two seconds.
+    let time = match (time, fps, frame) {
+        (Some(_), Some(_), _) => {
+            return Err(String::from(
+                "Time and frames per second can not be passed at once.",
+            ));
+        }
+        (Some(time), None, _) => time,
+        (None, Some(fps), Some(frame)) => frame as FloatingPointType / fps,
+        (None, Some(_), None) => {
+            return Err(String::from("Frames per second need a frame."));
+        }
+        (None, None, _) => 0.0,
+    };
+    let locate = |name: &str, time: FloatingPointType| {
+        scene
+            .geometries
+            .iter()
+            .find(|geometry| geometry.name() == Some(name))
+            .and_then(|geometry| geometry.position_at(time))
+    };
+    scene.cameras = scene
+        .cameras
+        .drain()
+        .map(|(name, camera)| {
+            let camera: CameraContainer = Box::new(FrameCamera::new(camera));
+            (name, camera)
+        })
+        .collect();
+    for camera in scene.cameras.values_mut() {
+        camera.set_time(time);
+        // Objects are in focus where the camera sees them, in the middle of its exposure.
+        let shutter = camera.shutter();
+        let seen = (shutter.open + shutter.close) / 2.0;
+        camera.pull_focus(time, &|name| locate(name, seen));
+    }
+
+    // The frustums are placed like the cameras are at the time of the frame.
+    if let Some(gizmos) = gizmos {
+        add_gizmos(
+            &mut scene,
+            &camera_name,
+            Vector2::new(size.x as FloatingPointType, size.y as FloatingPointType),
+            LengthType::new(gizmos),
+            RGB::new(1.0, 0.8, 0.0),
+            RGB::new(0.0, 0.8, 1.0),
+        );
+    }
+
+    // Every camera renders the window, e.g. both eyes of a stereo camera.
+    let size = match crop {
+        Some(window) => {
+            scene.cameras = scene
+                .cameras
+                .drain()
+                .map(|(name, camera)| {
+                    let camera: CameraContainer =
+                        Box::new(CroppedCamera::new(camera, window, size, cropped));
+                    (name, camera)
+                })
+                .collect();
+            if cropped {
+                window.size()
+            } else {
+                size
+            }
+        }
+        None => size,
+    };
+
+    // Without an exposure on the command line, a physical camera exposes the image itself.
+    if exposure.is_none() {
+        let camera_id = match stereo {
+            Some(_) => format!("{}.left", camera_name),
+            None => camera_name.clone(),
+        };
+        exposure = scene
+            .cameras
+            .get(&camera_id)
+            .and_then(|camera| camera.exposure())
+            .map(Exposure::Physical);
+    }
+
+    if lighting_components && light_groups {
+        return Err(String::from(
+            "Lighting components and light groups can not be rendered at once.",
+        ));
+    }
+
+    if contours.is_some() && (lighting_components || light_groups) {
+        return Err(String::from(
+            "Contours can not be rendered together with lighting components or light groups.",
+        ));
+    }
+
+    if !light_paths.is_empty() && (lighting_components || light_groups || contours.is_some()) {
+        return Err(String::from(
+            "Light path expressions can not be rendered together with lighting components, light groups or contours.",
+        ));
+    }
+
+    if stereo.is_some()
+        && (lighting_components || light_groups || contours.is_some() || !light_paths.is_empty())
+    {
+        return Err(String::from(
+            "Stereo images can only be rendered without any further outputs.",
+        ));
+    }
+
+    if shadows
+        && (lighting_components
+            || light_groups
+            || contours.is_some()
+            || !light_paths.is_empty()
+            || stereo.is_some())
+    {
+        return Err(String::from(
+            "Shadows can only be rendered without any further outputs.",
+        ));
+    }
+
+    // The arguments take precedence over the settings of the scene.
+    let sampling_patterns = match (sampling_patterns, settings.samples) {
+        (Some(sampling_patterns), _) => sampling_patterns,
+        (None, Some(samples)) => {
+            SamplingPatternSet::<Point2<FloatingPointType>>::n_rooks_patterns(64, samples, &mut rnd)
+        }
+        (None, None) => SamplingPatternSet::<Point2<FloatingPointType>>::regular_pattern(1, 1),
+    };
+
+    // The settings of the scene only apply to the integrators they are meant for.
+    if let (None, Some(depth)) = (max_depth, settings.max_depth) {
+        if let Integrator::Whitted { max_depth }
+        | Integrator::Path { max_depth }
+        | Integrator::Bidirectional { max_depth } = &mut integrator
+        {
+            *max_depth = depth;
+        }
+    }
+
+    if let Some(depth) = max_depth {
+        match &mut integrator {
+            Integrator::Whitted { max_depth }
+            | Integrator::Path { max_depth }
+            | Integrator::Bidirectional { max_depth } => *max_depth = depth,
+            Integrator::Diffuse | Integrator::AmbientOcclusion { .. } => {
+                return Err(String::from(
+                    "A maximum depth needs the whitted, the path or the bdpt integrator.",
+                ));
+            }
+        }
+    }
+
+    if let Some(ao_distance) = ao_distance {
+        match &mut integrator {
+            Integrator::AmbientOcclusion { distance } => *distance = ao_distance,
+            _ => {
+                return Err(String::from(
+                    "An ambient occlusion distance needs the ao integrator.",
+                ));
+            }
+        }
+    }
+
+    if packets
+        && !matches!(
+            integrator,
+            Integrator::Diffuse | Integrator::Whitted { .. } | Integrator::Bidirectional { .. }
+        )
+    {
+        return Err(String::from(
+            "Packets of rays need the diffuse, the whitted or the bdpt integrator.",
+        ));
+    }
+
+    if !scene.volumes.is_empty()
+        && !matches!(
+            integrator,
+            Integrator::Whitted { .. } | Integrator::Path { .. }
+        )
+    {
+        return Err(String::from(
+            "Volumes need the whitted or the path integrator.",
+        ));
+    }
+
+    if !matches!(integrator, Integrator::Diffuse)
+        && (lighting_components
+            || light_groups
+            || contours.is_some()
+            || !light_paths.is_empty()
+            || stereo.is_some()
+            || shadows
+            || filter != ReconstructionFilter::Box)
+    {
+        return Err(String::from(
+            "The integrators besides diffuse only render the image with a box filter.",
+        ));
+    }
+
+    if let Some(update_interval) = update_interval {
+        match progressive {
+            Some(p) => {
+                progressive = Some(p.with_update_interval(update_interval));
+            }
+            None => {
+                return Err(String::from(
+                    "An update interval needs a progressive render.",
+                ));
+            }
+        }
+    }
+
+    if (progressive.is_some() || denoiser.is_some())
+        && (lighting_components
+            || light_groups
+            || contours.is_some()
+            || !light_paths.is_empty()
+            || stereo.is_some()
+            || shadows)
+    {
+        return Err(String::from(
+            "Progressive and denoised renders only write the image without any further outputs.",
+        ));
+    }
+
+    if sample_heatmap && adaptive_sampling.is_none() {
+        return Err(String::from("A sample heatmap needs adaptive sampling."));
+    }
+
+    if sample_heatmap
+        && (lighting_components
+            || light_groups
+            || contours.is_some()
+            || !light_paths.is_empty()
+            || stereo.is_some()
+            || shadows)
+    {
+        return Err(String::from(
+            "A sample heatmap can only be written along with the image alone.",
+        ));
+    }
+
+    if alpha && stereo.is_some() {
+        return Err(String::from("Stereo images are written without alpha."));
+    }
+
+    if alpha
+        && ["ppm", "pfm", "hdr"]
+            .iter()
+            .any(|e| has_extension(&output, e))
+    {
+        return Err(String::from(
+            "Only farbfeld, PNG and EXR files are written with alpha.",
+        ));
+    }
+
+    if checkpoint.is_some() && progressive.is_none() {
+        return Err(String::from("A checkpoint needs a progressive render."));
+    }
+
+    // The checkpoint must be of the same render, otherwise the passes would not add up.
+    if let Some(resumed) = &resumed {
+        if resumed.image.size() != size {
+            return Err(format!(
+                "The checkpoint is of an image of {}x{} pixels instead of {}x{}.",
+                resumed.image.size().x,
+                resumed.image.size().y,
+                size.x,
+                size.y
+            ));
+        }
+        if resumed.seed != seed {
+            return Err(String::from(
+                "The checkpoint is of a render with another seed.",
+            ));
+        }
+    }
+
+    // The workers only send back the image, which is merged without a filter across the tiles.
+    if !workers.is_empty()
+        && (progressive.is_some()
+            || denoiser.is_some()
+            || !aovs.is_empty()
+            || sample_heatmap
+            || stats
+            || crop.is_some()
+            || lighting_components
+            || light_groups
+            || contours.is_some()
+            || !light_paths.is_empty()
+            || stereo.is_some()
+            || shadows
+            || filter != ReconstructionFilter::Box)
+    {
+        return Err(String::from(
+            "Workers only render the whole image with a box filter without further outputs or statistics.",
+        ));
+    }
+
+    if framebuffer_file.is_some() && workers.is_empty() {
+        return Err(String::from(
+            "Only images rendered on workers are assembled in a framebuffer file.",
+        ));
+    }
+
     Ok(Configuration {
-        scene: scene.unwrap(),
+        scene,
+        scene_filenames,
+        include_dirs,
+        pack,
         camera_name,
         size,
         output,
         sampling_patterns,
+        shadow_bias: settings.shadow_bias,
+        adaptive_sampling,
+        seed,
+        threads,
+        filter,
+        exposure,
+        lighting_components,
+        light_groups,
+        contours,
+        light_paths,
+        stereo,
+        shadows,
+        stats,
+        progress,
+        style,
+        integrator,
+        packets,
+        sample_heatmap,
+        progressive,
+        aovs,
+        alpha,
+        denoiser,
+        checkpoint,
+        resumed,
+        workers,
+        worker_arguments,
+        framebuffer_file,
     })
 }
 
-fn main() {
-    match parse_configuration(env::args()) {
-        Ok(config) => {
-            let diffuse_ray_tracer =
-                DiffuseRayTracer::<LengthType>::new(config.sampling_patterns, 0.0001);
+// A palette of colors by name, or a comma separated list of hexadecimal colors like ff8000.
+fn parse_palette(palette: &str) -> Result<Vec<ColorType>, String> {
+    let palette = match palette {
+        "gameboy" => "0f380f,306230,8bac0f,9bbc0f",
+        "cga" => "000000,55ffff,ff55ff,ffffff",
+        "pico8" => "000000,1d2b53,7e2553,008751,ab5236,5f574f,c2c3c7,fff1e8,ff004d,ffa300,ffec27,00e436,29adff,83769c,ff77a8,ffccaa",
+        palette => palette,
+    };
+
+    palette
+        .split(',')
+        .map(|color| {
+            if color.len() != 6 || !color.is_ascii() {
+                return Err(format!("Unable to parse color {} of palette.", color));
+            }
+            let channel = |index: usize| match u8::from_str_radix(&color[index..index + 2], 16) {
+                Ok(value) => Ok(value as FloatingPointType / 255.0),
+                Err(m) => Err(format!("Unable to parse color {} of palette: {}", color, m)),
+            };
+            Ok(RGB::new(channel(0)?, channel(2)?, channel(4)?))
+        })
+        .collect()
+}
+
+// Renders the image alone, which is all the integrators beyond the diffuse ray tracer,
+// progressive and denoised renders support.
+fn render_traced(mut config: Configuration) {
+    let resumed = config.resumed.take();
+    let metrics = if config.sample_heatmap {
+        Arc::new(Metrics::new().with_sample_counts(config.size))
+    } else {
+        Arc::new(Metrics::new())
+    };
+    let done = AtomicBool::new(false);
 
-            let rnd = WichmannHillPRNG::new_random();
+    thread::scope(|s| {
+        if config.progress {
+            s.spawn(|| show_progress(&metrics, &done));
+        }
+
+        let (scene, camera_name, size) = (&config.scene, &config.camera_name, config.size);
+        let render_pass = traced_render_pass(&config, &metrics);
 
-            let rendered_image =
-                diffuse_ray_tracer.render(config.scene, &config.camera_name, config.size, rnd);
+        let save_checkpoint = |passes: usize, image: &AccumulationBuffer<ColorType>| {
+            let Some(path) = &config.checkpoint else {
+                return;
+            };
+            let checkpoint = Checkpoint {
+                seed: config.seed,
+                passes,
+                image: image.clone(),
+            };
+            if let Err(m) = checkpoint.save(path) {
+                eprintln!("Unable to save checkpoint {}: {}", path.display(), m);
+            }
+        };
 
-            let image_data = rendered_image
-                .clamp_color(RGB::new(0.0, 0.0, 0.0), RGB::new(1.0, 1.0, 1.0))
-                .convert_color::<RGBA<FloatingPointType>>()
-                .convert_color::<RGBA<u16>>()
-                .encode();
+        // The previews are exposed on their own, as the average is still changing.
+        let rendered_image = match config.progressive {
+            Some(progressive) => {
+                let (accumulated, passes_done) = match resumed {
+                    Some(resumed) => (resumed.image, resumed.passes),
+                    None => (AccumulationBuffer::new(size), 0),
+                };
+                let accumulated = progressive.resume(
+                    accumulated,
+                    passes_done,
+                    config.seed,
+                    &metrics,
+                    render_pass,
+                    |passes, image| {
+                        save_checkpoint(passes, image);
+                        let exposure_multiplier = exposure_multiplier(&config.exposure, image);
+                        write_image(
+                            image.to_image_buffer(),
+                            exposure_multiplier,
+                            &config.style,
+                            &config.output,
+                        );
+                    },
+                );
+                // A later render may continue with more passes.
+                save_checkpoint(progressive.passes.max(passes_done), &accumulated);
+                accumulated.to_image_buffer()
+            }
+            None => render_pass(config.seed),
+        };
 
-            let f = File::create(config.output).unwrap();
+        // The denoiser is guided by the surfaces of the auxiliary outputs.
+        let rendered_image = match config.denoiser {
+            Some(denoiser) => {
+                let aovs = Aovs::render(scene, camera_name, size, config.seed, config.threads);
+                denoiser.denoise(&rendered_image, &aovs)
+            }
+            None => rendered_image,
+        };
 
-            let mut writer = BufWriter::new(f);
+        let exposure_multiplier = exposure_multiplier(&config.exposure, &rendered_image);
+        write_image(
+            rendered_image,
+            exposure_multiplier,
+            &config.style,
+            &config.output,
+        );
 
-            let _ = writer.write_all(image_data.as_slice());
+        if let Some(sample_counts) = &metrics.sample_counts {
+            write_sample_heatmap(sample_counts, &config.style, &config.output);
         }
-        Err(m) => {
-            eprintln!("{}", m);
+
+        done.store(true, Ordering::Relaxed);
+    });
+
+    if config.stats {
+        println!("{}", metrics.snapshot());
+    }
+}
+
+// A render pass of the image with the integrator of the configuration, which renders the image
+// for a seed.
+fn traced_render_pass<'a>(
+    config: &'a Configuration,
+    metrics: &Arc<Metrics>,
+) -> Box<dyn Fn(u128) -> ImageBuffer<ColorType> + 'a> {
+    let (scene, camera_name, size) = (&config.scene, &config.camera_name, config.size);
+    match config.integrator {
+        Integrator::Diffuse => {
+            let renderer = DiffuseRayTracer::<LengthType>::new(
+                config.sampling_patterns.clone(),
+                config.shadow_bias,
+            )
+            .with_threads(config.threads)
+            .with_filter(config.filter)
+            .with_metrics(Arc::clone(metrics));
+            let renderer = match config.adaptive_sampling {
+                Some(adaptive_sampling) => renderer.with_adaptive_sampling(adaptive_sampling),
+                None => renderer,
+            };
+            let renderer = if config.packets {
+                renderer.with_packets()
+            } else {
+                renderer
+            };
+            Box::new(move |seed| renderer.render_pass(scene, camera_name, size, seed))
+        }
+        Integrator::Whitted { max_depth } => {
+            let renderer = WhittedRayTracer::<LengthType>::new(
+                config.sampling_patterns.clone(),
+                config.shadow_bias,
+            )
+            .with_threads(config.threads)
+            .with_max_depth(max_depth)
+            .with_metrics(Arc::clone(metrics));
+            let renderer = match config.adaptive_sampling {
+                Some(adaptive_sampling) => renderer.with_adaptive_sampling(adaptive_sampling),
+                None => renderer,
+            };
+            let renderer = if config.packets {
+                renderer.with_packets()
+            } else {
+                renderer
+            };
+            Box::new(move |seed| renderer.render_pass(scene, camera_name, size, seed))
+        }
+        Integrator::Path { max_depth } => {
+            let renderer =
+                PathTracer::<LengthType>::new(config.sampling_patterns.clone(), config.shadow_bias)
+                    .with_threads(config.threads)
+                    .with_max_depth(max_depth)
+                    .with_metrics(Arc::clone(metrics));
+            let renderer = match config.adaptive_sampling {
+                Some(adaptive_sampling) => renderer.with_adaptive_sampling(adaptive_sampling),
+                None => renderer,
+            };
+            Box::new(move |seed| renderer.render_pass(scene, camera_name, size, seed))
+        }
+        Integrator::Bidirectional { max_depth } => {
+            let renderer = BidirectionalPathTracer::<LengthType>::new(
+                config.sampling_patterns.clone(),
+                config.shadow_bias,
+            )
+            .with_threads(config.threads)
+            .with_max_depth(max_depth)
+            .with_metrics(Arc::clone(metrics));
+            let renderer = match config.adaptive_sampling {
+                Some(adaptive_sampling) => renderer.with_adaptive_sampling(adaptive_sampling),
+                None => renderer,
+            };
+            let renderer = if config.packets {
+                renderer.with_packets()
+            } else {
+                renderer
+            };
+            Box::new(move |seed| renderer.render_pass(scene, camera_name, size, seed))
+        }
+        Integrator::AmbientOcclusion { distance } => {
+            let renderer = AmbientOcclusionRenderer::<LengthType>::new(
+                config.sampling_patterns.clone(),
+                config.shadow_bias,
+                Meter::new(distance),
+            )
+            .with_threads(config.threads)
+            .with_metrics(Arc::clone(metrics));
+            let renderer = match config.adaptive_sampling {
+                Some(adaptive_sampling) => renderer.with_adaptive_sampling(adaptive_sampling),
+                None => renderer,
+            };
+            Box::new(move |seed| renderer.render_pass(scene, camera_name, size, seed))
+        }
+    }
+}
+
+// The exposure of the image, metered from the image itself for automatic exposure.
+fn exposure_multiplier(
+    exposure: &Option<Exposure>,
+    image: &impl Image<ColorType = ColorType, PointType = Point2<usize>>,
+) -> FloatingPointType {
+    match exposure {
+        Some(Exposure::Physical(exposure)) => exposure.multiplier(),
+        Some(Exposure::Auto(exposure)) => exposure.multiplier(image),
+        None => 1.0,
+    }
+}
+
+fn write_image(
+    image: impl Image<ColorType = ColorType, PointType = Point2<usize>>,
+    exposure_multiplier: FloatingPointType,
+    style: &Style,
+    output: &str,
+) {
+    let size = image.size();
+    let written = ImageWriter::create(output, size, style).and_then(|mut writer| {
+        writer.write_rows(&image, exposure_multiplier, style, 0..size.y)?;
+        writer.finish(style)
+    });
+    if let Err(m) = written {
+        eprintln!("Unable to write {}: {}", output, m);
+    }
+}
+
+// Writes the rows of an image to the file of the output as soon as they are rendered. Farbfeld,
+// PPM, Radiance and EXR files are written row by row, the others are encoded as a whole once every
+// row is written. The format follows the extension of the output, farbfeld unless it is another
+// one. PNG files keep the 16 bits per channel of farbfeld, so linear colors do not band in the
+// shadows, while PPM files only have 8 bits per channel, but are read by almost any viewer. PFM,
+// Radiance and EXR files hold the exposed colors as floats, before they are tone mapped and
+// clamped.
+enum ImageWriter {
+    Farbfeld(FarbfeldWriter<BufWriter<File>>),
+    Ppm(PpmWriter<BufWriter<File>>),
+    Hdr(HdrWriter<BufWriter<File>, ColorType>),
+    Exr(ExrWriter<BufWriter<File>, ColorType>),
+    AlphaExr(ExrWriter<BufWriter<File>, RGBA<FloatingPointType>>),
+    // The alpha is only encoded if there is one.
+    Png(BufWriter<File>, ImageBuffer<RGBA<u16>>),
+    Pfm(BufWriter<File>, ImageBuffer<ColorType>),
+    // The auxiliary outputs are further layers of the file.
+    LayeredExr(BufWriter<File>, ExrImage, ImageBuffer<ColorType>),
+}
+
+impl ImageWriter {
+    // The writer for an image of the size, which is larger in the file for retro renders.
+    fn create(output: &str, size: Vector2<usize>, style: &Style) -> io::Result<ImageWriter> {
+        let size = Vector2::new(size.x * style.pixel_size, size.y * style.pixel_size);
+        let file = BufWriter::new(File::create(output)?);
+        let writer = if has_extension(output, "png") {
+            ImageWriter::Png(file, ImageBuffer::new(size, RGBA::default()))
+        } else if has_extension(output, "ppm") {
+            ImageWriter::Ppm(PpmWriter::new(file, size)?)
+        } else if has_extension(output, "pfm") {
+            ImageWriter::Pfm(file, ImageBuffer::new(size, RGB::default()))
+        } else if has_extension(output, "hdr") {
+            ImageWriter::Hdr(HdrWriter::new(file, size)?)
+        } else if has_extension(output, "exr") {
+            match (&style.layers, &style.alpha) {
+                (Some(layers), _) => ImageWriter::LayeredExr(
+                    file,
+                    layers.clone(),
+                    ImageBuffer::new(size, RGB::default()),
+                ),
+                (None, Some(_)) => {
+                    ImageWriter::AlphaExr(ExrWriter::new(file, size, style.exr_pixel_type)?)
+                }
+                (None, None) => ImageWriter::Exr(ExrWriter::new(file, size, style.exr_pixel_type)?),
+            }
+        } else {
+            ImageWriter::Farbfeld(FarbfeldWriter::new(file, size)?)
+        };
+        Ok(writer)
+    }
+
+    // Writes the rows of the image, which become several rows of the file for retro renders.
+    fn write_rows(
+        &mut self,
+        image: impl Image<ColorType = ColorType, PointType = Point2<usize>>,
+        exposure_multiplier: FloatingPointType,
+        style: &Style,
+        rows: Range<usize>,
+    ) -> io::Result<()> {
+        let image = image.expose(exposure_multiplier);
+        let rows = rows.start * style.pixel_size..rows.end * style.pixel_size;
+        let upscaled = || (&image).upscale(style.pixel_size);
+        match self {
+            ImageWriter::Hdr(writer) => writer.write_rows(&upscaled(), rows),
+            ImageWriter::Exr(writer) => writer.write_rows(&upscaled(), rows),
+            ImageWriter::AlphaExr(writer) => {
+                let alpha = style
+                    .alpha
+                    .as_ref()
+                    .expect("The alpha of the image is missing.");
+                writer.write_rows(
+                    &upscaled().with_alpha(alpha.upscale(style.pixel_size)),
+                    rows,
+                )
+            }
+            ImageWriter::Pfm(_, buffer) | ImageWriter::LayeredExr(_, _, buffer) => {
+                copy_rows(&upscaled(), buffer, rows);
+                Ok(())
+            }
+            _ => {
+                let image = image
+                    .tone_map(style.tone_mapping.clone())
+                    .clamp_color(RGB::new(0.0, 0.0, 0.0), RGB::new(1.0, 1.0, 1.0));
+                if style.srgb {
+                    self.write_styled(image.encode_srgb(), style, rows)
+                } else {
+                    self.write_styled(image, style, rows)
+                }
+            }
+        }
+    }
+
+    // The colors of a palette are given like the colors of the file, so the image is quantized
+    // after the sRGB encoding.
+    fn write_styled(
+        &mut self,
+        image: impl Image<ColorType = ColorType, PointType = Point2<usize>>,
+        style: &Style,
+        rows: Range<usize>,
+    ) -> io::Result<()> {
+        let image = image.upscale(style.pixel_size);
+        match &style.palette {
+            Some(palette) => self.write_clamped(image.quantize(palette.clone()), style, rows),
+            None => self.write_clamped(image, style, rows),
+        }
+    }
+
+    // Images without alpha are opaque.
+    fn write_clamped(
+        &mut self,
+        image: impl Image<ColorType = ColorType, PointType = Point2<usize>>,
+        style: &Style,
+        rows: Range<usize>,
+    ) -> io::Result<()> {
+        match &style.alpha {
+            Some(alpha) => self.write_rgba(image.with_alpha(alpha.upscale(style.pixel_size)), rows),
+            None => self.write_rgba(image.convert_color::<RGBA<FloatingPointType>>(), rows),
+        }
+    }
+
+    fn write_rgba(
+        &mut self,
+        image: impl Image<ColorType = RGBA<FloatingPointType>, PointType = Point2<usize>>,
+        rows: Range<usize>,
+    ) -> io::Result<()> {
+        match self {
+            ImageWriter::Png(_, buffer) => {
+                copy_rows(&image.convert_color::<RGBA<u16>>(), buffer, rows);
+                Ok(())
+            }
+            ImageWriter::Ppm(writer) => writer.write_rows(
+                &image
+                    .convert_color::<ColorType>()
+                    .convert_color::<RGB<u8>>(),
+                rows,
+            ),
+            ImageWriter::Farbfeld(writer) => {
+                writer.write_rows(&image.convert_color::<RGBA<u16>>(), rows)
+            }
+            _ => unreachable!("Floating point formats are written without clamping."),
+        }
+    }
+
+    fn finish(self, style: &Style) -> io::Result<()> {
+        let mut file = match self {
+            ImageWriter::Farbfeld(writer) => writer.finish()?,
+            ImageWriter::Ppm(writer) => writer.finish()?,
+            ImageWriter::Hdr(writer) => writer.finish()?,
+            ImageWriter::Exr(writer) => writer.finish()?,
+            ImageWriter::AlphaExr(writer) => writer.finish()?,
+            ImageWriter::Png(mut file, buffer) => {
+                match style.alpha {
+                    Some(_) => file.write_all(&buffer.encode_png())?,
+                    None => file.write_all(&buffer.convert_color::<RGB<u16>>().encode_png())?,
+                }
+                file
+            }
+            ImageWriter::Pfm(mut file, buffer) => {
+                file.write_all(&buffer.encode_pfm())?;
+                file
+            }
+            ImageWriter::LayeredExr(mut file, layers, buffer) => {
+                let layers = match &style.alpha {
+                    Some(alpha) => {
+                        let alpha = alpha.upscale(style.pixel_size);
+                        layers.with_layer("", &(&buffer).with_alpha(alpha))
+                    }
+                    None => layers.with_layer("", &buffer),
+                };
+                file.write_all(&layers.encode())?;
+                file
+            }
+        };
+        file.flush()
+    }
+}
+
+fn copy_rows<C: Color>(
+    image: &impl Image<ColorType = C, PointType = Point2<usize>>,
+    buffer: &mut ImageBuffer<C>,
+    rows: Range<usize>,
+) {
+    for y in rows {
+        for x in 0..image.size().x {
+            let p = Point2::new(x, y);
+            *buffer.get_mut(p) = image.get(p);
+        }
+    }
+}
+
+fn has_extension(output: &str, extension: &str) -> bool {
+    Path::new(output)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+}
+
+fn gray_to_rgb(image: &ImageBuffer<Gray<FloatingPointType>>) -> ImageBuffer<ColorType> {
+    let size = image.size();
+    let mut image_buffer = ImageBuffer::new(size, RGB::new(0.0, 0.0, 0.0));
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let p = Point2::new(x, y);
+            let value = image.get(p).value;
+            *image_buffer.get_mut(p) = RGB::new(value, value, value);
+        }
+    }
+    image_buffer
+}
+
+// The auxiliary outputs are written like the image, but with linear colors and without a palette
+// or tone mapping, as they hold data instead of a picture. Normals are mapped from -1 to 1 onto
+// the range of the file, depths from the camera to the farthest surface.
+fn write_aovs(config: &Configuration) {
+    let aovs = Aovs::render(
+        &config.scene,
+        &config.camera_name,
+        config.size,
+        config.seed,
+        config.threads,
+    );
+    let style = Style {
+        tone_mapping: vec![],
+        srgb: false,
+        pixel_size: config.style.pixel_size,
+        palette: None,
+        exr_pixel_type: config.style.exr_pixel_type,
+        layers: None,
+        alpha: None,
+    };
+    let write = |image: ImageBuffer<ColorType>, name: &str| {
+        write_image(image, 1.0, &style, &component_output(&config.output, name));
+    };
+
+    for aov in &config.aovs {
+        match aov {
+            Aov::Normal => {
+                let mut normal = aovs.normal();
+                let size = normal.size();
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let n = normal.get_mut(Point2::new(x, y));
+                        *n = *n * 0.5 + RGB::new(0.5, 0.5, 0.5);
+                    }
+                }
+                write(normal, aov.name());
+            }
+            Aov::Depth => {
+                let mut depth = aovs.depth();
+                let size = depth.size();
+                let mut farthest: FloatingPointType = 0.0;
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        farthest = farthest.max(depth.get(Point2::new(x, y)).value);
+                    }
+                }
+                if farthest > 0.0 {
+                    for y in 0..size.y {
+                        for x in 0..size.x {
+                            depth.get_mut(Point2::new(x, y)).value /= farthest;
+                        }
+                    }
+                }
+                write(gray_to_rgb(&depth), aov.name());
+            }
+            Aov::Albedo => write(aovs.albedo(), aov.name()),
+            Aov::ObjectId => write(aovs.object_id(), aov.name()),
+            Aov::Masks => {
+                for name in aovs.names() {
+                    write(gray_to_rgb(&aovs.mask(name)), &format!("mask_{}", name));
+                }
+            }
+        }
+    }
+}
+
+// The auxiliary outputs as layers of EXR files, which keep the data as it is, e.g. normals from -1
+// to 1 and depths in meters. Masks are named like they are as files.
+fn aov_layers(config: &Configuration) -> ExrImage {
+    let aovs = Aovs::render(
+        &config.scene,
+        &config.camera_name,
+        config.size,
+        config.seed,
+        config.threads,
+    );
+    let pixel_size = config.style.pixel_size;
+    let size = Vector2::new(config.size.x * pixel_size, config.size.y * pixel_size);
+
+    let mut layers = ExrImage::new(size, config.style.exr_pixel_type);
+    for aov in &config.aovs {
+        layers = match aov {
+            Aov::Normal => layers.with_layer(aov.name(), &aovs.normal().upscale(pixel_size)),
+            Aov::Depth => layers.with_layer(aov.name(), &aovs.depth().upscale(pixel_size)),
+            Aov::Albedo => layers.with_layer(aov.name(), &aovs.albedo().upscale(pixel_size)),
+            Aov::ObjectId => layers.with_layer(aov.name(), &aovs.object_id().upscale(pixel_size)),
+            Aov::Masks => aovs.names().into_iter().fold(layers, |layers, name| {
+                layers.with_layer(
+                    &format!("mask_{}", name),
+                    &aovs.mask(name).upscale(pixel_size),
+                )
+            }),
+        };
+    }
+    layers
+}
+
+// The pixel with the fewest samples is blue, the one with the most red, with cyan, green and
+// yellow in between. The colors are relative, so the range is printed along. The heatmap is
+// written linear and without a palette, like the auxiliary outputs.
+fn write_sample_heatmap(sample_counts: &SampleCounts, style: &Style, output: &str) {
+    let size = sample_counts.size();
+    let pixels = || (0..size.y).flat_map(|y| (0..size.x).map(move |x| Point2::new(x, y)));
+    let fewest = pixels().map(|p| sample_counts.get(p)).min().unwrap_or(0);
+    let most = pixels().map(|p| sample_counts.get(p)).max().unwrap_or(0);
+
+    let ramp = [
+        RGB::new(0.0, 0.0, 1.0),
+        RGB::new(0.0, 1.0, 1.0),
+        RGB::new(0.0, 1.0, 0.0),
+        RGB::new(1.0, 1.0, 0.0),
+        RGB::new(1.0, 0.0, 0.0),
+    ];
+    let mut heatmap = ImageBuffer::new(size, ramp[0]);
+    if most > fewest {
+        for p in pixels() {
+            let t = (sample_counts.get(p) - fewest) as FloatingPointType
+                / (most - fewest) as FloatingPointType
+                * (ramp.len() - 1) as FloatingPointType;
+            let index = (t as usize).min(ramp.len() - 2);
+            let f = t - index as FloatingPointType;
+            *heatmap.get_mut(p) = ramp[index] * (1.0 - f) + ramp[index + 1] * f;
+        }
+    }
+
+    let style = Style {
+        tone_mapping: vec![],
+        srgb: false,
+        pixel_size: style.pixel_size,
+        palette: None,
+        exr_pixel_type: style.exr_pixel_type,
+        layers: None,
+        alpha: None,
+    };
+    write_image(heatmap, 1.0, &style, &component_output(output, "samples"));
+    println!("Samples per pixel: {} to {}", fewest, most);
+}
+
+// The output of an animation if no other is given.
+const FRAME_OUTPUT: &str = "out_%04d.ff";
+
+// The most digits the number of a frame is padded to with zeros.
+const MAX_FRAME_DIGITS: usize = 9;
+
+// The first and the last frame of an animation that is rendered frame by frame, e.g. --frames 1
+// 240. The output needs a placeholder for the number of the frame, so the frames do not overwrite
+// each other.
+fn parse_frame_range(args: &[String]) -> Result<Option<(u64, u64)>, String> {
+    let Some(index) = args.iter().position(|arg| arg == "--frames") else {
+        return Ok(None);
+    };
+    let frame = |offset: usize, name: &str| match args.get(index + offset) {
+        Some(frame) => match frame.parse::<u64>() {
+            Ok(frame) => Ok(frame),
+            Err(m) => Err(format!("Unable to parse {} frame: {}", name, m)),
+        },
+        None => Err(format!("Missing {} frame.", name)),
+    };
+    let first = frame(1, "first")?;
+    let last = frame(2, "last")?;
+    if last < first {
+        return Err(String::from("The last frame comes before the first one."));
+    }
+
+    if args.iter().any(|arg| arg == "--frame") {
+        return Err(String::from(
+            "A frame and a range of frames can not be passed at once.",
+        ));
+    }
+    if args.iter().any(|arg| arg == "--checkpoint") {
+        return Err(String::from(
+            "A checkpoint can not be kept for several frames.",
+        ));
+    }
+    let output = match args.iter().position(|arg| arg == "-O") {
+        Some(index) => args.get(index + 1).map_or(FRAME_OUTPUT, String::as_str),
+        None => FRAME_OUTPUT,
+    };
+    match frame_placeholder(output) {
+        None => {
+            return Err(String::from(
+                "The output of several frames needs a placeholder for the frame, e.g. out_%04d.ff.",
+            ))
+        }
+        Some((_, width, _)) if width > MAX_FRAME_DIGITS => {
+            return Err(format!(
+                "Frames can be padded to at most {} digits.",
+                MAX_FRAME_DIGITS
+            ))
+        }
+        Some(_) => {}
+    }
+
+    Ok(Some((first, last)))
+}
+
+// The arguments that render a single frame of a range.
+fn frame_arguments(args: &[String], frame: u64) -> Vec<String> {
+    let mut frame_args = Vec::with_capacity(args.len() + 2);
+    let mut output = FRAME_OUTPUT;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--frames" => {
+                args.nth(1);
+            }
+            "-O" => {
+                if let Some(o) = args.next() {
+                    output = o;
+                }
+            }
+            _ => frame_args.push(arg.clone()),
+        }
+    }
+    frame_args.push(String::from("-O"));
+    frame_args.push(frame_output(output, frame).unwrap());
+    frame_args.push(String::from("--frame"));
+    frame_args.push(frame.to_string());
+    frame_args
+}
+
+// The filename of a frame, with the number of the frame in place of a placeholder like %d, or
+// %04d for a number padded with zeros to four digits. None if there is no placeholder.
+fn frame_output(output: &str, frame: u64) -> Option<String> {
+    let (prefix, width, suffix) = frame_placeholder(output)?;
+    Some(format!(
+        "{}{:0width$}{}",
+        prefix,
+        frame,
+        suffix,
+        width = width.min(MAX_FRAME_DIGITS)
+    ))
+}
+
+// The text before and after the placeholder for the frame, and the number of digits the frame is
+// padded to.
+fn frame_placeholder(output: &str) -> Option<(&str, usize, &str)> {
+    let start = output.find('%')?;
+    let rest = &output[start + 1..];
+    let end = rest.find('d')?;
+    let digits = &rest[..end];
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    // Too many digits to count are too many to pad to.
+    let width = match digits {
+        "" => 0,
+        digits => digits.parse().unwrap_or(usize::MAX),
+    };
+    Some((&output[..start], width, &rest[end + 1..]))
+}
+
+// Inserts the name of a component in front of the extension, e.g. out.ff becomes
+// out.direct_diffuse.ff.
+fn component_output(output: &str, component: &str) -> String {
+    match output.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}.{}.{}", stem, component, extension),
+        None => format!("{}.{}", output, component),
+    }
+}
+
+// The edge length of the tiles the workers render. Larger tiles spend less time on parsing the
+// scene on the workers, smaller ones share the image more evenly between them.
+const WORKER_TILE_SIZE: usize = 128;
+
+// The tiles kept in memory when the image is assembled in a file, about 24 MiB of tiles.
+const RESIDENT_TILES: usize = 64;
+
+// The arguments the workers render their tiles with, which are the arguments of the render
+// without those that only concern the coordinator. Each tile has a seed of its own.
+fn worker_arguments(args: &[String]) -> Vec<String> {
+    let mut worker_args = Vec::with_capacity(args.len());
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--workers" | "--seed" | "--threads" | "-O" | "--framebuffer-file" => {
+                args.next();
+            }
+            "--progress" => {}
+            _ => worker_args.push(arg.clone()),
+        }
+    }
+    worker_args
+}
+
+// Waits for coordinators on the address, e.g. 0.0.0.0:7878, and renders the tiles they send,
+// e.g. diffuseraytracer --worker 0.0.0.0:7878 --threads 4. The scene files must be at the same
+// paths as on the coordinator.
+fn run_worker(args: &[String]) -> Result<(), String> {
+    let mut address: Option<&str> = None;
+    let mut threads: Option<&str> = None;
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--worker" => match args.next() {
+                Some(a) => address = Some(a),
+                None => return Err(String::from("Missing worker address.")),
+            },
+            "--threads" => match args.next() {
+                Some(t) => threads = Some(t),
+                None => return Err(String::from("Missing number of threads.")),
+            },
+            arg => {
+                return Err(format!("Unexpected argument {} for a worker.", arg));
+            }
+        }
+    }
+    let address = address.unwrap();
+
+    let listener = match TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(m) => {
+            return Err(format!("Unable to listen on {}: {}", address, m));
+        }
+    };
+    println!("Waiting for tiles on {}", address);
+
+    // A coordinator sends its tiles one after another over the same connection.
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(m) => {
+                eprintln!("Unable to accept connection: {}", m);
+                continue;
+            }
+        };
+        loop {
+            let request = match TileRequest::read_from(&mut stream) {
+                Ok(request) => request,
+                Err(m) if m.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(m) => {
+                    eprintln!("Unable to read tile: {}", m);
+                    break;
+                }
+            };
+
+            let (min, max) = (request.window.min, request.window.max);
+            let mut tile_args = vec![String::from("diffuseraytracer")];
+            tile_args.extend(request.args);
+            tile_args.push(String::from("--crop"));
+            tile_args.extend([min.x, min.y, max.x, max.y].map(|c| c.to_string()));
+            tile_args.push(String::from("--cropped"));
+            tile_args.push(String::from("--seed"));
+            tile_args.push(request.seed.to_string());
+            if let Some(threads) = threads {
+                tile_args.push(String::from("--threads"));
+                tile_args.push(threads.to_string());
+            }
+
+            // A scene that can not be read must not stop the worker for the other renders. The
+            // coordinator only learns what went wrong, the details with the paths and the content
+            // of the files stay with the worker.
+            let tile = panic::catch_unwind(|| {
+                let config = parse_configuration(tile_args.into_iter()).map_err(|m| {
+                    eprintln!("Unable to render tile: {}", m);
+                    if m.starts_with(SCENE_PARSING_FAILURE) {
+                        String::from("The worker failed to parse the scene.")
+                    } else {
+                        String::from("The worker failed to set up the render.")
+                    }
+                })?;
+                let metrics = Arc::new(Metrics::new());
+                let render_pass = traced_render_pass(&config, &metrics);
+                Ok(render_pass(config.seed))
+            })
+            .unwrap_or_else(|_| Err(String::from("The worker failed to render the tile.")));
+            let written = match tile {
+                Ok(tile) => ImageBuffer::write_to(Ok(&tile), &mut stream),
+                Err(m) => ImageBuffer::<ColorType>::write_to(Err(&m), &mut stream),
+            };
+            if let Err(m) = written {
+                eprintln!("Unable to send tile: {}", m);
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Compares an image with a reference, e.g. a render of a test scene with a golden image of it:
+// diffuseraytracer --compare reference.png image.png --min-psnr 30 --min-ssim 0.95 --diff diff.png
+// Prints the PSNR and SSIM of the linear colors and writes the absolute difference of every pixel.
+// Returns whether the image is at least as similar as required, so scripts can fail on the exit
+// code.
+fn run_compare(args: &[String]) -> Result<bool, String> {
+    let mut images: Vec<&str> = vec![];
+    let mut diff: Option<&str> = None;
+    let mut min_psnr: Option<FloatingPointType> = None;
+    let mut min_ssim: Option<FloatingPointType> = None;
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--compare" => match (args.next(), args.next()) {
+                (Some(reference), Some(image)) => images = vec![reference, image],
+                _ => return Err(String::from("Missing images to compare.")),
+            },
+            "--diff" => match args.next() {
+                Some(d) => diff = Some(d),
+                None => return Err(String::from("Missing file name of the difference image.")),
+            },
+            "--min-psnr" => match args.next().map(|p| p.parse()) {
+                Some(Ok(p)) => min_psnr = Some(p),
+                _ => return Err(String::from("Missing or invalid minimum PSNR.")),
+            },
+            "--min-ssim" => match args.next().map(|s| s.parse()) {
+                Some(Ok(s)) => min_ssim = Some(s),
+                _ => return Err(String::from("Missing or invalid minimum SSIM.")),
+            },
+            arg => {
+                return Err(format!("Unexpected argument {} for a comparison.", arg));
+            }
         }
     }
+
+    let load = |filename: &str| match util::load_image::<FloatingPointType>(filename) {
+        Ok(image) => Ok(image),
+        Err(ParsingError::ImageLoadingError(m)) => Err(format!("Unable to load {}", m)),
+        Err(m) => Err(format!("Unable to load {}: {:?}", filename, m)),
+    };
+    let reference = load(images[0])?;
+    let image = load(images[1])?;
+    if reference.size() != image.size() {
+        return Err(format!(
+            "The images have different sizes, {}x{} and {}x{}.",
+            reference.size().x,
+            reference.size().y,
+            image.size().x,
+            image.size().y
+        ));
+    }
+
+    let psnr = comparison::psnr(&reference, &image, 1.0);
+    let ssim = comparison::ssim(&reference, &image, 1.0);
+    println!("PSNR: {:.2} dB", psnr);
+    println!("SSIM: {:.4}", ssim);
+
+    if let Some(diff) = diff {
+        let style = Style {
+            tone_mapping: vec![],
+            srgb: false,
+            pixel_size: 1,
+            palette: None,
+            exr_pixel_type: PixelType::Float,
+            layers: None,
+            alpha: None,
+        };
+        write_image(
+            comparison::difference(&reference, &image),
+            1.0,
+            &style,
+            diff,
+        );
+    }
+
+    Ok(min_psnr.is_none_or(|min| psnr >= min) && min_ssim.is_none_or(|min| ssim >= min))
+}
+
+// Renders the image on the workers. Each worker gets the next tile as soon as it sent back the
+// last one, so faster machines render more of the image. The tiles of a worker that fails are
+// left to the others.
+fn render_distributed(config: Configuration) {
+    let metrics = Metrics::new();
+    let done = AtomicBool::new(false);
+    let size = config.size;
+
+    // The tiles are taken from the end, which renders the image from the top.
+    let mut tiles: Vec<(usize, CropWindow)> = distributed::tiles(size, WORKER_TILE_SIZE)
+        .into_iter()
+        .enumerate()
+        .collect();
+    tiles.reverse();
+    metrics.pixels_total.set((size.x * size.y) as u64);
+    metrics.tiles_total.set(tiles.len() as u64);
+    let tiles = Mutex::new(tiles);
+    let image = TiledBuffer::new(size, WORKER_TILE_SIZE, RGB::default());
+    let image = match &config.framebuffer_file {
+        Some(path) => match image.backed_by(path, RESIDENT_TILES) {
+            Ok(image) => image,
+            Err(m) => {
+                eprintln!("Unable to create framebuffer {}: {}", path.display(), m);
+                return;
+            }
+        },
+        None => image,
+    };
+
+    // Unless the exposure is metered from the whole image, each row of tiles is written as soon
+    // as all of its tiles are rendered.
+    let writer = match &config.exposure {
+        Some(Exposure::Auto(_)) => None,
+        _ => match ImageWriter::create(&config.output, size, &config.style) {
+            Ok(writer) => Some(writer),
+            Err(m) => {
+                eprintln!("Unable to write {}: {}", config.output, m);
+                return;
+            }
+        },
+    };
+    let tile_rows = size.y.div_ceil(WORKER_TILE_SIZE);
+    let framebuffer = Mutex::new(Framebuffer {
+        exposure_multiplier: exposure_multiplier(&config.exposure, &image),
+        image,
+        missing_tiles: vec![size.x.div_ceil(WORKER_TILE_SIZE); tile_rows],
+        written_rows: 0,
+        writer,
+        error: None,
+    });
+
+    thread::scope(|s| {
+        if config.progress {
+            s.spawn(|| show_progress(&metrics, &done));
+        }
+
+        let (config, tiles, framebuffer, metrics) = (&config, &tiles, &framebuffer, &metrics);
+        let workers: Vec<_> = config
+            .workers
+            .iter()
+            .map(|worker| {
+                s.spawn(move || {
+                    if let Err(m) = render_tiles(worker, config, tiles, framebuffer, metrics) {
+                        eprintln!("Worker {}: {}", worker, m);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            let _ = worker.join();
+        }
+
+        done.store(true, Ordering::Relaxed);
+    });
+
+    if !tiles.lock().unwrap().is_empty() {
+        eprintln!("No worker is left to render the rest of the image.");
+        return;
+    }
+
+    let framebuffer = framebuffer.into_inner().unwrap();
+    let written = match (framebuffer.error, framebuffer.writer) {
+        (Some(m), _) => Err(m),
+        (None, Some(writer)) => writer.finish(&config.style),
+        (None, None) => {
+            let exposure_multiplier = exposure_multiplier(&config.exposure, &framebuffer.image);
+            write_image(
+                framebuffer.image,
+                exposure_multiplier,
+                &config.style,
+                &config.output,
+            );
+            Ok(())
+        }
+    };
+    if let Err(m) = written {
+        eprintln!("Unable to write {}: {}", config.output, m);
+    }
+}
+
+// The image the tiles of the workers are merged into, with the number of tiles each row of tiles
+// still misses. The rows of tiles above the first incomplete one are written, if there is a
+// writer, until writing fails.
+struct Framebuffer {
+    image: TiledBuffer<ColorType>,
+    exposure_multiplier: FloatingPointType,
+    missing_tiles: Vec<usize>,
+    written_rows: usize,
+    writer: Option<ImageWriter>,
+    error: Option<io::Error>,
+}
+
+impl Framebuffer {
+    fn merge(&mut self, tile: &ImageBuffer<ColorType>, window: CropWindow, style: &Style) {
+        for y in 0..tile.size().y {
+            for x in 0..tile.size().x {
+                *self
+                    .image
+                    .get_mut(Point2::new(window.min.x + x, window.min.y + y)) =
+                    tile.get(Point2::new(x, y));
+            }
+        }
+        self.missing_tiles[window.min.y / WORKER_TILE_SIZE] -= 1;
+
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        while self.missing_tiles.get(self.written_rows) == Some(&0) {
+            let start = self.written_rows * WORKER_TILE_SIZE;
+            let rows = start..(start + WORKER_TILE_SIZE).min(self.image.size().y);
+            if let Err(m) = writer.write_rows(&self.image, self.exposure_multiplier, style, rows) {
+                self.error = Some(m);
+                self.writer = None;
+                return;
+            }
+            self.written_rows += 1;
+        }
+    }
+}
+
+// Sends tiles to a worker and merges the rendered ones into the image until no tile is left.
+fn render_tiles(
+    worker: &str,
+    config: &Configuration,
+    tiles: &Mutex<Vec<(usize, CropWindow)>>,
+    framebuffer: &Mutex<Framebuffer>,
+    metrics: &Metrics,
+) -> Result<(), String> {
+    let mut stream = TcpStream::connect(worker).map_err(|m| m.to_string())?;
+    loop {
+        let Some((index, window)) = tiles.lock().unwrap().pop() else {
+            return Ok(());
+        };
+
+        let tile = render_tile(&mut stream, config, index, window);
+        let tile = match tile {
+            Ok(tile) => tile,
+            Err(m) => {
+                tiles.lock().unwrap().push((index, window));
+                return Err(m);
+            }
+        };
+
+        framebuffer
+            .lock()
+            .unwrap()
+            .merge(&tile, window, &config.style);
+        metrics.pixels.add((tile.size().x * tile.size().y) as u64);
+        metrics.tile_done();
+    }
+}
+
+// Renders a tile on the worker behind the stream. The window is given in the pixels of the
+// image, which are larger than those of the written image for retro renders.
+fn render_tile(
+    stream: &mut TcpStream,
+    config: &Configuration,
+    index: usize,
+    window: CropWindow,
+) -> Result<ImageBuffer<ColorType>, String> {
+    let pixel_size = config.style.pixel_size;
+    let request = TileRequest {
+        args: config.worker_arguments.clone(),
+        window: CropWindow::new(
+            Point2::new(window.min.x * pixel_size, window.min.y * pixel_size),
+            Point2::new(window.max.x * pixel_size, window.max.y * pixel_size),
+        ),
+        seed: random::tile_seed(config.seed, index as u32),
+    };
+    request.write_to(stream).map_err(|m| m.to_string())?;
+
+    let size = Vector2::new(window.max.x - window.min.x, window.max.y - window.min.y);
+    ImageBuffer::read_from(stream, size).map_err(|m| m.to_string())?
+}
+
+// Redraws a progress bar on stderr until the render is done.
+// The line is padded, so the end of a longer one before is overwritten.
+fn show_progress(metrics: &Metrics, done: &AtomicBool) {
+    while !done.load(Ordering::Relaxed) {
+        eprint!("\r{:<64}", metrics.snapshot().progress_line(40));
+        thread::sleep(Duration::from_millis(200));
+    }
+    eprintln!("\r{:<64}", metrics.snapshot().progress_bar(40));
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.iter().any(|arg| arg == "--worker") {
+        if let Err(m) = run_worker(&args) {
+            eprintln!("{}", m);
+        }
+        return;
+    }
+    if args.iter().any(|arg| arg == "--compare") {
+        match run_compare(&args) {
+            Ok(true) => {}
+            Ok(false) => process::exit(1),
+            Err(m) => {
+                eprintln!("{}", m);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let frames = match parse_frame_range(&args) {
+        Ok(frames) => frames,
+        Err(m) => {
+            eprintln!("{}", m);
+            return;
+        }
+    };
+
+    match frames {
+        // Every frame is parsed on its own, so the scene is evaluated at the time of the frame.
+        Some((first, last)) => {
+            for frame in first..=last {
+                match parse_configuration(frame_arguments(&args, frame).into_iter()) {
+                    Ok(config) => render(config),
+                    Err(m) => {
+                        eprintln!("Frame {}: {}", frame, m);
+                        return;
+                    }
+                }
+            }
+        }
+        None => match parse_configuration(args.into_iter()) {
+            Ok(config) => render(config),
+            Err(m) => {
+                eprintln!("{}", m);
+            }
+        },
+    }
+}
+
+fn render(mut config: Configuration) {
+    if let Some(directory) = config.pack {
+        for scene_filename in &config.scene_filenames {
+            match assets::pack(scene_filename, &config.include_dirs, &directory) {
+                Ok(packed_scene) => println!("Packed scene to {}", packed_scene.display()),
+                Err(m) => eprintln!("Failed to pack scene: {}", m),
+            }
+        }
+        return;
+    }
+
+    if !config.aovs.is_empty() {
+        if has_extension(&config.output, "exr") {
+            config.style.layers = Some(aov_layers(&config));
+        } else {
+            write_aovs(&config);
+        }
+    }
+
+    if config.alpha {
+        config.style.alpha = Some(render_coverage(
+            &config.scene,
+            &config.camera_name,
+            config.size,
+            &config.sampling_patterns,
+            config.seed,
+            config.threads,
+        ));
+    }
+
+    if !config.workers.is_empty() {
+        render_distributed(config);
+        return;
+    }
+
+    if !matches!(config.integrator, Integrator::Diffuse)
+        || config.progressive.is_some()
+        || config.denoiser.is_some()
+        || config.sample_heatmap
+    {
+        render_traced(config);
+        return;
+    }
+
+    let diffuse_ray_tracer =
+        DiffuseRayTracer::<LengthType>::new(config.sampling_patterns, config.shadow_bias)
+            .with_threads(config.threads)
+            .with_filter(config.filter);
+    let diffuse_ray_tracer = match config.adaptive_sampling {
+        Some(adaptive_sampling) => diffuse_ray_tracer.with_adaptive_sampling(adaptive_sampling),
+        None => diffuse_ray_tracer,
+    };
+    let diffuse_ray_tracer = if config.packets {
+        diffuse_ray_tracer.with_packets()
+    } else {
+        diffuse_ray_tracer
+    };
+    let metrics = diffuse_ray_tracer.metrics();
+    let done = AtomicBool::new(false);
+
+    thread::scope(|s| {
+        if config.progress {
+            s.spawn(|| show_progress(&metrics, &done));
+        }
+
+        if config.lighting_components {
+            let components = diffuse_ray_tracer.render_lighting_components(
+                config.scene,
+                &config.camera_name,
+                config.size,
+                config.seed,
+            );
+
+            let combined = components.combined();
+            let exposure_multiplier = exposure_multiplier(&config.exposure, &combined);
+            write_image(combined, exposure_multiplier, &config.style, &config.output);
+
+            for (name, image) in [
+                ("background", components.background),
+                ("direct_diffuse", components.direct_diffuse),
+                ("direct_specular", components.direct_specular),
+                ("indirect_diffuse", components.indirect_diffuse),
+                ("indirect_specular", components.indirect_specular),
+            ] {
+                write_image(
+                    image,
+                    exposure_multiplier,
+                    &config.style,
+                    &component_output(&config.output, name),
+                );
+            }
+        } else if config.light_groups {
+            let light_groups = diffuse_ray_tracer.render_light_groups(
+                config.scene,
+                &config.camera_name,
+                config.size,
+                config.seed,
+            );
+
+            let combined = light_groups.combined();
+            let exposure_multiplier = exposure_multiplier(&config.exposure, &combined);
+            write_image(combined, exposure_multiplier, &config.style, &config.output);
+            write_image(
+                light_groups.background,
+                exposure_multiplier,
+                &config.style,
+                &component_output(&config.output, "background"),
+            );
+
+            for (name, image) in light_groups.groups {
+                write_image(
+                    image,
+                    exposure_multiplier,
+                    &config.style,
+                    &component_output(&config.output, &format!("group_{}", name)),
+                );
+            }
+        } else if let Some(stereo) = config.stereo {
+            let (left, right) = diffuse_ray_tracer.render_stereo(
+                config.scene,
+                &config.camera_name,
+                config.size,
+                config.seed,
+            );
+
+            // Both eyes get the same exposure, or the anaglyph would be tinted.
+            let exposure_multiplier = exposure_multiplier(&config.exposure, &left);
+            match stereo {
+                StereoOutput::Separate => {
+                    write_image(
+                        left,
+                        exposure_multiplier,
+                        &config.style,
+                        &component_output(&config.output, "left"),
+                    );
+                    write_image(
+                        right,
+                        exposure_multiplier,
+                        &config.style,
+                        &component_output(&config.output, "right"),
+                    );
+                }
+                StereoOutput::Anaglyph => {
+                    write_image(
+                        Anaglyph::new(left, right),
+                        exposure_multiplier,
+                        &config.style,
+                        &config.output,
+                    );
+                }
+            }
+        } else if !config.light_paths.is_empty() {
+            let expressions: Vec<LightPathExpression> = config
+                .light_paths
+                .iter()
+                .map(|(_, expression, _)| expression.clone())
+                .collect();
+            let light_paths = diffuse_ray_tracer.render_light_paths(
+                config.scene,
+                &config.camera_name,
+                config.size,
+                config.seed,
+                &expressions,
+            );
+            let exposure_multiplier = exposure_multiplier(&config.exposure, &light_paths.beauty);
+
+            let excluded: Vec<_> = config
+                .light_paths
+                .iter()
+                .enumerate()
+                .map(|(index, (_, _, exclude))| exclude.then(|| light_paths.excluding(index)))
+                .collect();
+            for ((name, _, _), (excluded, selected)) in config
+                .light_paths
+                .iter()
+                .zip(excluded.into_iter().zip(light_paths.paths))
+            {
+                write_image(
+                    excluded.unwrap_or(selected),
+                    exposure_multiplier,
+                    &config.style,
+                    &component_output(&config.output, name),
+                );
+            }
+            write_image(
+                light_paths.beauty,
+                exposure_multiplier,
+                &config.style,
+                &config.output,
+            );
+        } else if config.shadows {
+            let shadows = diffuse_ray_tracer.render_shadows(
+                config.scene,
+                &config.camera_name,
+                config.size,
+                config.seed,
+            );
+
+            let exposure_multiplier = exposure_multiplier(&config.exposure, &shadows.beauty);
+            write_image(
+                shadows.beauty,
+                exposure_multiplier,
+                &config.style,
+                &config.output,
+            );
+
+            // The shadows are mattes, which are not exposed.
+            write_image(
+                gray_to_rgb(&shadows.combined),
+                1.0,
+                &config.style,
+                &component_output(&config.output, "shadow"),
+            );
+            for (name, image) in shadows.lights {
+                write_image(
+                    gray_to_rgb(&image),
+                    1.0,
+                    &config.style,
+                    &component_output(&config.output, &format!("shadow_{}", name)),
+                );
+            }
+        } else if let Some(style) = config.contours {
+            let contours = diffuse_ray_tracer.render_contours(
+                config.scene,
+                &config.camera_name,
+                config.size,
+                config.seed,
+                style,
+            );
+
+            let exposure_multiplier = exposure_multiplier(&config.exposure, &contours.beauty);
+            write_image(
+                contours.overlaid(),
+                exposure_multiplier,
+                &config.style,
+                &config.output,
+            );
+            write_image(
+                contours.drawing(),
+                1.0,
+                &config.style,
+                &component_output(&config.output, "contours"),
+            );
+        } else {
+            let rendered_image = diffuse_ray_tracer.render(
+                config.scene,
+                &config.camera_name,
+                config.size,
+                config.seed,
+            );
+
+            let exposure_multiplier = exposure_multiplier(&config.exposure, &rendered_image);
+            write_image(
+                rendered_image,
+                exposure_multiplier,
+                &config.style,
+                &config.output,
+            );
+        }
+
+        done.store(true, Ordering::Relaxed);
+    });
+
+    if config.stats {
+        println!("{}", metrics.snapshot());
+    }
 }
@@ -1,24 +1,107 @@
 use std::ops::Deref;
+use std::sync::Arc;
 
 use crate::light::Light;
-use cg_basics::material::{LambertMaterial, PhongMaterial, UnshadedMaterial};
+use cg_basics::material::{
+    DielectricMaterial, EmissiveMaterial, InstanceMaterial, LambertMaterial, MetalMaterial,
+    PhongMaterial, PlasticMaterial, ReflectiveMaterial, UnshadedMaterial,
+};
+use cg_basics::microfacet::{ggx_alpha, ggx_distribution, smith_masking};
 use colors::Color;
 use image::Image;
 use math::geometry::SurfacePoint;
 use math::{Point2, Vector3};
-use traits::floating_point::{Max, Powf, Sqrt};
-use traits::{FloatingPoint, Zero};
+use traits::floating_point::{Asin, Atan2, Clamp, Cos, Max, Powf, Powi, Sin, Sqrt};
+use traits::{ConvenientNumber, FloatingPoint, Half, One, Pi, Zero};
 use units::length::Length;
 
-pub trait Material<T: Length> {
+// A direction a path continues in after it hit a surface, for renderers that follow the light
+// bouncing around in the scene.
+pub struct Scattering<V, C> {
+    pub direction: Vector3<V>,
+    // The share of the light arriving from the direction that the surface reflects along the
+    // path, already divided by the probability of drawing the direction.
+    pub weight: C,
+    // Mirrors and clear surfaces scatter into a single direction, which a light sample never
+    // hits. The light is only found by the path itself.
+    pub specular: bool,
+}
+
+pub trait Material<T: Length>: Send + Sync {
     type ColorType: Color;
 
     fn color_for(
         &self,
         sp: SurfacePoint<T>,
         d: Vector3<T>,
-        lights: Vec<&Box<dyn Light<T, Self::ColorType>>>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
     ) -> Self::ColorType;
+
+    // Splits the color into the part reflected by the diffuse and by the specular lobe. Together
+    // with reflection_for both parts add up to the result of color_for.
+    fn diffuse_and_specular_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> (Self::ColorType, Self::ColorType) {
+        (self.color_for(sp, d, lights), Self::ColorType::default())
+    }
+
+    // Light reflected from the surroundings that does not depend on the light sources.
+    fn reflection_for(&self, _sp: SurfacePoint<T>, _d: Vector3<T>) -> Self::ColorType {
+        Self::ColorType::default()
+    }
+
+    // The color of the surface itself without any lighting, e.g. to guide a denoiser.
+    fn albedo(&self, _sp: SurfacePoint<T>) -> Self::ColorType {
+        Self::ColorType::default()
+    }
+
+    // The radiance emitted by the surface, if it glows by itself.
+    fn emission(&self) -> Option<Self::ColorType> {
+        None
+    }
+
+    // The share of the light a perfect mirror reflection of the scene adds, for renderers that
+    // trace reflection rays. They use it instead of reflection_for.
+    fn mirror_reflectance(&self, _sp: SurfacePoint<T>) -> Option<Self::ColorType> {
+        None
+    }
+
+    // The transmittance and the index of refraction of a clear material, for renderers that trace
+    // refraction rays. The share of light reflected instead follows from the Fresnel equations.
+    fn transmission(
+        &self,
+        _sp: SurfacePoint<T>,
+    ) -> Option<(Self::ColorType, <Self::ColorType as Color>::ChannelType)> {
+        None
+    }
+
+    // Draws the direction a path continues in for a point of the unit square. None if the path
+    // ends on the surface.
+    fn scatter(
+        &self,
+        _sp: SurfacePoint<T>,
+        _d: Vector3<T>,
+        _sample: Point2<<T as Length>::ValueType>,
+    ) -> Option<Scattering<<T as Length>::ValueType, Self::ColorType>> {
+        None
+    }
+
+    // The share of the light arriving from a normalized direction that the surface reflects along
+    // the path, together with the density of scatter drawing that direction, for renderers that
+    // connect paths through directions they did not draw. The share divided by the density is the
+    // weight scatter gives the direction. None if the surface scatters no light into the
+    // direction, e.g. for mirrors and clear surfaces.
+    fn scattering_for(
+        &self,
+        _sp: SurfacePoint<T>,
+        _d: Vector3<T>,
+        _direction: Vector3<<T as Length>::ValueType>,
+    ) -> Option<(Self::ColorType, <T as Length>::ValueType)> {
+        None
+    }
 }
 
 impl<T: Length, C: Color> Material<T> for Box<dyn Material<T, ColorType = C>> {
@@ -28,10 +111,123 @@ impl<T: Length, C: Color> Material<T> for Box<dyn Material<T, ColorType = C>> {
         &self,
         sp: SurfacePoint<T>,
         d: Vector3<T>,
-        lights: Vec<&Box<dyn Light<T, Self::ColorType>>>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
     ) -> Self::ColorType {
         self.deref().color_for(sp, d, lights)
     }
+
+    fn diffuse_and_specular_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> (Self::ColorType, Self::ColorType) {
+        self.deref().diffuse_and_specular_for(sp, d, lights)
+    }
+
+    fn reflection_for(&self, sp: SurfacePoint<T>, d: Vector3<T>) -> Self::ColorType {
+        self.deref().reflection_for(sp, d)
+    }
+
+    fn albedo(&self, sp: SurfacePoint<T>) -> Self::ColorType {
+        self.deref().albedo(sp)
+    }
+
+    fn emission(&self) -> Option<Self::ColorType> {
+        self.deref().emission()
+    }
+
+    fn mirror_reflectance(&self, sp: SurfacePoint<T>) -> Option<Self::ColorType> {
+        self.deref().mirror_reflectance(sp)
+    }
+
+    fn transmission(
+        &self,
+        sp: SurfacePoint<T>,
+    ) -> Option<(Self::ColorType, <Self::ColorType as Color>::ChannelType)> {
+        self.deref().transmission(sp)
+    }
+
+    fn scatter(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        sample: Point2<<T as Length>::ValueType>,
+    ) -> Option<Scattering<<T as Length>::ValueType, Self::ColorType>> {
+        self.deref().scatter(sp, d, sample)
+    }
+
+    fn scattering_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        direction: Vector3<<T as Length>::ValueType>,
+    ) -> Option<(Self::ColorType, <T as Length>::ValueType)> {
+        self.deref().scattering_for(sp, d, direction)
+    }
+}
+
+impl<T: Length, C: Color> Material<T> for Arc<dyn Material<T, ColorType = C>> {
+    type ColorType = C;
+
+    fn color_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> Self::ColorType {
+        self.deref().color_for(sp, d, lights)
+    }
+
+    fn diffuse_and_specular_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> (Self::ColorType, Self::ColorType) {
+        self.deref().diffuse_and_specular_for(sp, d, lights)
+    }
+
+    fn reflection_for(&self, sp: SurfacePoint<T>, d: Vector3<T>) -> Self::ColorType {
+        self.deref().reflection_for(sp, d)
+    }
+
+    fn albedo(&self, sp: SurfacePoint<T>) -> Self::ColorType {
+        self.deref().albedo(sp)
+    }
+
+    fn emission(&self) -> Option<Self::ColorType> {
+        self.deref().emission()
+    }
+
+    fn mirror_reflectance(&self, sp: SurfacePoint<T>) -> Option<Self::ColorType> {
+        self.deref().mirror_reflectance(sp)
+    }
+
+    fn transmission(
+        &self,
+        sp: SurfacePoint<T>,
+    ) -> Option<(Self::ColorType, <Self::ColorType as Color>::ChannelType)> {
+        self.deref().transmission(sp)
+    }
+
+    fn scatter(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        sample: Point2<<T as Length>::ValueType>,
+    ) -> Option<Scattering<<T as Length>::ValueType, Self::ColorType>> {
+        self.deref().scatter(sp, d, sample)
+    }
+
+    fn scattering_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        direction: Vector3<<T as Length>::ValueType>,
+    ) -> Option<(Self::ColorType, <T as Length>::ValueType)> {
+        self.deref().scattering_for(sp, d, direction)
+    }
 }
 
 impl<T: Length, I: Image<PointType = Point2<<T as Length>::ValueType>>> Material<T>
@@ -43,15 +239,97 @@ impl<T: Length, I: Image<PointType = Point2<<T as Length>::ValueType>>> Material
         &self,
         sp: SurfacePoint<T>,
         _d: Vector3<T>,
-        _lights: Vec<&Box<dyn Light<T, Self::ColorType>>>,
+        _lights: Vec<&dyn Light<T, Self::ColorType>>,
     ) -> Self::ColorType {
         self.texture.get(sp.uv)
     }
+
+    fn albedo(&self, sp: SurfacePoint<T>) -> Self::ColorType {
+        self.texture.get(sp.uv)
+    }
+}
+
+impl<T: Length, C: Color> Material<T> for EmissiveMaterial<C> {
+    type ColorType = C;
+
+    fn color_for(
+        &self,
+        _sp: SurfacePoint<T>,
+        _d: Vector3<T>,
+        _lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> Self::ColorType {
+        self.color
+    }
+
+    fn albedo(&self, _sp: SurfacePoint<T>) -> Self::ColorType {
+        self.color
+    }
+
+    fn emission(&self) -> Option<Self::ColorType> {
+        Some(self.color)
+    }
+}
+
+// Clear surfaces have no color of their own, all light is reflected or refracted.
+impl<T: Length, C: Color> Material<T> for DielectricMaterial<C>
+where
+    <T as Length>::ValueType: FloatingPoint,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    C: Color<ChannelType = <T as Length>::ValueType>,
+{
+    type ColorType = C;
+
+    fn color_for(
+        &self,
+        _sp: SurfacePoint<T>,
+        _d: Vector3<T>,
+        _lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> Self::ColorType {
+        C::default()
+    }
+
+    // What is seen through the surface is tinted by the transmittance.
+    fn albedo(&self, _sp: SurfacePoint<T>) -> C {
+        self.transmittance
+    }
+
+    fn transmission(&self, _sp: SurfacePoint<T>) -> Option<(C, C::ChannelType)> {
+        Some((self.transmittance, self.index_of_refraction))
+    }
+
+    // The path is either reflected or refracted, chosen by the share of the light that goes
+    // either way.
+    fn scatter(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        sample: Point2<<T as Length>::ValueType>,
+    ) -> Option<Scattering<<T as Length>::ValueType, C>> {
+        let (reflected, refracted, fresnel) = refraction(
+            d.normalized(),
+            sp.n.as_vector().normalized(),
+            self.index_of_refraction,
+        );
+        match refracted {
+            Some(refracted) if sample.x >= fresnel => Some(Scattering {
+                direction: refracted,
+                weight: self.transmittance,
+                specular: true,
+            }),
+            _ => Some(Scattering {
+                direction: reflected,
+                weight: C::uniform(One::one()),
+                specular: true,
+            }),
+        }
+    }
 }
 
 impl<T: Length, I: Image<PointType = Point2<<T as Length>::ValueType>>> Material<T>
     for LambertMaterial<I>
 where
+    <T as Length>::ValueType: FloatingPoint,
+    <T as Length>::AreaType: Sqrt<Output = T>,
     <I as Image>::ColorType: Color<ChannelType = <T as Length>::ValueType>,
 {
     type ColorType = <I as Image>::ColorType;
@@ -60,17 +338,39 @@ where
         &self,
         sp: SurfacePoint<T>,
         _d: Vector3<T>,
-        lights: Vec<&Box<dyn Light<T, Self::ColorType>>>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
     ) -> Self::ColorType {
         lights
             .iter()
             .map(|light| {
                 self.texture.get(sp.uv)
-                    * light.get_color()
+                    * light.color_at(sp)
                     * light.direction_from(sp).dot(sp.n.as_vector())
             })
             .sum()
     }
+
+    fn albedo(&self, sp: SurfacePoint<T>) -> Self::ColorType {
+        self.texture.get(sp.uv)
+    }
+
+    fn scatter(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        sample: Point2<<T as Length>::ValueType>,
+    ) -> Option<Scattering<<T as Length>::ValueType, Self::ColorType>> {
+        Some(diffuse_scattering(sp, d, self.texture.get(sp.uv), sample))
+    }
+
+    fn scattering_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        direction: Vector3<<T as Length>::ValueType>,
+    ) -> Option<(Self::ColorType, <T as Length>::ValueType)> {
+        diffuse_scattering_for(sp, d, self.texture.get(sp.uv), direction)
+    }
 }
 
 impl<T: Length, I: Image<PointType = Point2<<T as Length>::ValueType>>> Material<T>
@@ -86,23 +386,918 @@ where
         &self,
         sp: SurfacePoint<T>,
         d: Vector3<T>,
-        lights: Vec<&Box<dyn Light<T, Self::ColorType>>>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> Self::ColorType {
+        let (diffuse, specular) = self.diffuse_and_specular_for(sp, d, lights);
+        diffuse + specular
+    }
+
+    fn diffuse_and_specular_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> (Self::ColorType, Self::ColorType) {
+        let diffuse = lights
+            .iter()
+            .map(|light| {
+                self.diffuse_texture.get(sp.uv)
+                    * light.color_at(sp)
+                    * light.direction_from(sp).dot(sp.n.as_vector())
+            })
+            .sum();
+        let r = d.normalized().reflect_on(sp.n).normalized();
+        let roughness = roughness_of(self.exponent);
+        let specular = lights
+            .iter()
+            .map(|light| match light.reflected_radiance(r, roughness) {
+                Some(radiance) => self.specular_texture.get(sp.uv) * radiance,
+                None => {
+                    let reflected_light = light.direction_from(sp).reflect_on(sp.n).normalized();
+                    self.specular_texture.get(sp.uv)
+                        * light.color_at(sp)
+                        * reflected_light
+                            .dot(d.normalized())
+                            .max(Zero::zero())
+                            .powf(self.exponent)
+                }
+            })
+            .sum();
+        (diffuse, specular)
+    }
+    // Paths continue from the diffuse part only, the highlights are left to the lights.
+    fn albedo(&self, sp: SurfacePoint<T>) -> Self::ColorType {
+        self.diffuse_texture.get(sp.uv)
+    }
+
+    fn scatter(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        sample: Point2<<T as Length>::ValueType>,
+    ) -> Option<Scattering<<T as Length>::ValueType, Self::ColorType>> {
+        Some(diffuse_scattering(
+            sp,
+            d,
+            self.diffuse_texture.get(sp.uv),
+            sample,
+        ))
+    }
+
+    fn scattering_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        direction: Vector3<<T as Length>::ValueType>,
+    ) -> Option<(Self::ColorType, <T as Length>::ValueType)> {
+        diffuse_scattering_for(sp, d, self.diffuse_texture.get(sp.uv), direction)
+    }
+}
+
+// Ashikhmin-Shirley style blend of a diffuse substrate and a glossy coat. The coat reflects more
+// light at grazing angles (Schlick's Fresnel approximation) and the substrate receives only what
+// the coat lets through. Like the other materials the terms are scaled by pi, so a white
+// substrate under a white light is as bright as a white lambertian surface.
+impl<T: Length, I: Image<PointType = Point2<<T as Length>::ValueType>>> Material<T>
+    for PlasticMaterial<I>
+where
+    <T as Length>::ValueType: FloatingPoint + Sqrt<Output = <T as Length>::ValueType>,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    <I as Image>::ColorType: Color<ChannelType = <T as Length>::ValueType>,
+{
+    type ColorType = <I as Image>::ColorType;
+
+    fn color_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
     ) -> Self::ColorType {
+        let (diffuse, specular) = self.diffuse_and_specular_for(sp, d, lights);
+        diffuse + specular
+    }
+
+    fn diffuse_and_specular_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> (Self::ColorType, Self::ColorType) {
+        let one = <T as Length>::ValueType::one();
+        let two = one + one;
+        let four = two * two;
+        let eight = four * two;
+        let diffuse_normalization = (eight * four - four) / (eight * four - eight - one);
+
+        let n = sp.n.as_vector();
+        let v = -d.normalized();
+        let n_dot_v = n.dot(v).max(Zero::zero());
+
+        let diffuse = self.diffuse_texture.get(sp.uv);
+        let specular = self.specular_texture.get(sp.uv);
+        let r = d.normalized().reflect_on(sp.n).normalized();
+        let roughness = roughness_of(self.exponent);
+
         lights
             .iter()
             .map(|light| {
-                let diffuse_term = self.diffuse_texture.get(sp.uv)
-                    * light.get_color()
-                    * light.direction_from(sp).dot(sp.n.as_vector());
-                let reflected_light = light.direction_from(sp).reflect_on(sp.n).normalized();
-                let specular_term = self.specular_texture.get(sp.uv)
-                    * light.get_color()
-                    * reflected_light
-                        .dot(d.normalized())
-                        .max(Zero::zero())
-                        .powf(self.exponent);
-                diffuse_term + specular_term
+                if let Some(radiance) = light.reflected_radiance(r, roughness) {
+                    let schlick = (one - n_dot_v).powi(5);
+                    let fresnel = specular * radiance * (one - schlick) + radiance * schlick;
+                    return (diffuse * light.color_at(sp) * (one - schlick), fresnel);
+                }
+
+                let l = light.direction_from(sp);
+                let h = (l + v).normalized();
+                let n_dot_l = n.dot(l);
+                let h_dot_l = h.dot(l).max(Zero::zero());
+                let light_color = light.color_at(sp);
+
+                let substrate = diffuse_normalization
+                    * (one - (one - n_dot_l / two).powi(5))
+                    * (one - (one - n_dot_v / two).powi(5))
+                    * n_dot_l;
+                let diffuse_term = diffuse * light_color * substrate
+                    + diffuse * specular * light_color * -substrate;
+
+                let schlick = (one - h_dot_l).powi(5);
+                let fresnel = specular * light_color * (one - schlick) + light_color * schlick;
+                let denominator = h_dot_l * n_dot_l.max(n_dot_v);
+                let coat = if denominator > Zero::zero() {
+                    (self.exponent + one) / eight * n.dot(h).max(Zero::zero()).powf(self.exponent)
+                        / denominator
+                        * n_dot_l
+                } else {
+                    Zero::zero()
+                };
+                let specular_term = fresnel * coat;
+
+                (diffuse_term, specular_term)
             })
-            .sum()
+            .fold(
+                (Self::ColorType::default(), Self::ColorType::default()),
+                |(diffuse_sum, specular_sum), (diffuse_term, specular_term)| {
+                    (diffuse_sum + diffuse_term, specular_sum + specular_term)
+                },
+            )
+    }
+    // Paths continue from the substrate only, the coat is left to the lights.
+    fn albedo(&self, sp: SurfacePoint<T>) -> Self::ColorType {
+        self.diffuse_texture.get(sp.uv)
+    }
+
+    fn scatter(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        sample: Point2<<T as Length>::ValueType>,
+    ) -> Option<Scattering<<T as Length>::ValueType, Self::ColorType>> {
+        Some(diffuse_scattering(
+            sp,
+            d,
+            self.diffuse_texture.get(sp.uv),
+            sample,
+        ))
+    }
+
+    fn scattering_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        direction: Vector3<<T as Length>::ValueType>,
+    ) -> Option<(Self::ColorType, <T as Length>::ValueType)> {
+        diffuse_scattering_for(sp, d, self.diffuse_texture.get(sp.uv), direction)
+    }
+}
+
+// Cook-Torrance reflection with the GGX distribution, the separable Smith term and Schlick's
+// Fresnel approximation, scaled by pi like the other materials. For lights that provide a
+// prefiltered environment, the reflection is looked up there and weighted by the directional
+// albedo instead.
+impl<T: Length, I: Image<PointType = Point2<<T as Length>::ValueType>>> Material<T>
+    for MetalMaterial<I>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    <I as Image>::ColorType: Color<ChannelType = <T as Length>::ValueType>,
+    u16: Into<<T as Length>::ValueType>,
+{
+    type ColorType = <I as Image>::ColorType;
+
+    fn color_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> Self::ColorType {
+        let (diffuse, specular) = self.diffuse_and_specular_for(sp, d, lights);
+        diffuse + specular
+    }
+
+    fn diffuse_and_specular_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> (Self::ColorType, Self::ColorType) {
+        let one = <T as Length>::ValueType::one();
+        let four = one + one + one + one;
+
+        // The normals of scaled geometry are not normalized, the narrow lobe of a smooth metal
+        // would turn into a ring.
+        let n = sp.n.as_vector().normalized();
+        let v = -d.normalized();
+        let n_dot_v = n.dot(v).max(Zero::zero());
+        let r = n * (n_dot_v + n_dot_v) - v;
+        let alpha = ggx_alpha(self.roughness);
+
+        let reflectance = self.texture.get(sp.uv);
+        let albedo = self.albedo.get(n_dot_v);
+        let compensation = one / albedo - one;
+
+        let specular = lights
+            .iter()
+            .map(|light| {
+                let single_scattering = match light.reflected_radiance(r, self.roughness) {
+                    Some(radiance) => {
+                        let schlick = (one - n_dot_v).powi(5);
+                        (reflectance * radiance * (one - schlick) + radiance * schlick) * albedo
+                    }
+                    None => {
+                        let l = light.direction_from(sp);
+                        let h = (l + v).normalized();
+                        let n_dot_l = n.dot(l);
+                        if n_dot_l <= Zero::zero() || n_dot_v <= Zero::zero() {
+                            return Self::ColorType::default();
+                        }
+
+                        let light_color = light.color_at(sp);
+                        let schlick = (one - h.dot(l).max(Zero::zero())).powi(5);
+                        let fresnel =
+                            reflectance * light_color * (one - schlick) + light_color * schlick;
+                        fresnel
+                            * (ggx_distribution(n.dot(h).max(Zero::zero()), alpha)
+                                * smith_masking(n_dot_v, n_dot_l, alpha)
+                                / (four * n_dot_v)
+                                * <T as Length>::ValueType::PI)
+                    }
+                };
+
+                // Light that bounces between the microfacets more than once loses a part of it on
+                // every bounce, so the compensation is scaled by the reflectance.
+                single_scattering + single_scattering * reflectance * compensation
+            })
+            .sum();
+
+        (Self::ColorType::default(), specular)
+    }
+    // Draws half vectors proportionally to the GGX distribution and mirrors the path on them.
+    fn albedo(&self, sp: SurfacePoint<T>) -> Self::ColorType {
+        self.texture.get(sp.uv)
     }
+
+    fn scatter(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        sample: Point2<<T as Length>::ValueType>,
+    ) -> Option<Scattering<<T as Length>::ValueType, Self::ColorType>> {
+        let zero = <T as Length>::ValueType::zero();
+        let one = <T as Length>::ValueType::one();
+        let pi = <T as Length>::ValueType::PI;
+
+        let v = -d.normalized();
+        let n = facing(sp.n.as_vector().normalized(), v);
+        let alpha = ggx_alpha(self.roughness);
+        let alpha2 = alpha * alpha;
+
+        let cos_theta = ((one - sample.y) / (one + (alpha2 - one) * sample.y)).sqrt();
+        let sin_theta = (one - cos_theta * cos_theta).max(zero).sqrt();
+        let phi = (pi + pi) * sample.x;
+        let (tangent, bitangent) = tangent_frame(n);
+        let h =
+            tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + n * cos_theta;
+
+        let n_dot_v = n.dot(v);
+        let v_dot_h = v.dot(h);
+        let l = h * (v_dot_h + v_dot_h) - v;
+        let n_dot_l = n.dot(l);
+        if n_dot_v <= zero || n_dot_l <= zero || v_dot_h <= zero {
+            return None;
+        }
+
+        // The distribution cancels with the probability of the half vector.
+        let reflectance = self.texture.get(sp.uv);
+        let schlick = (one - v_dot_h).powi(5);
+        let fresnel = reflectance * (one - schlick) + Self::ColorType::uniform(schlick);
+        let single_scattering =
+            fresnel * (smith_masking(n_dot_v, n_dot_l, alpha) * v_dot_h / (n_dot_v * cos_theta));
+        let compensation = one / self.albedo.get(n_dot_v) - one;
+
+        Some(Scattering {
+            direction: l,
+            weight: single_scattering + single_scattering * reflectance * compensation,
+            specular: false,
+        })
+    }
+
+    // The density of the half vector is the distribution times its cosine to the normal, mirroring
+    // the path on it divides it by four times the cosine between the half vector and the path.
+    fn scattering_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        direction: Vector3<<T as Length>::ValueType>,
+    ) -> Option<(Self::ColorType, <T as Length>::ValueType)> {
+        let zero = <T as Length>::ValueType::zero();
+        let one = <T as Length>::ValueType::one();
+        let four: <T as Length>::ValueType = 4u16.into();
+
+        let v = -d.normalized();
+        let n = facing(sp.n.as_vector().normalized(), v);
+        let h = (v + direction).normalized();
+        let n_dot_v = n.dot(v);
+        let n_dot_l = n.dot(direction);
+        let n_dot_h = n.dot(h);
+        let v_dot_h = v.dot(h);
+        if n_dot_v <= zero || n_dot_l <= zero || v_dot_h <= zero {
+            return None;
+        }
+
+        let alpha = ggx_alpha(self.roughness);
+        let distribution = ggx_distribution(n_dot_h, alpha);
+        let reflectance = self.texture.get(sp.uv);
+        let schlick = (one - v_dot_h).powi(5);
+        let fresnel = reflectance * (one - schlick) + Self::ColorType::uniform(schlick);
+        let single_scattering =
+            fresnel * (smith_masking(n_dot_v, n_dot_l, alpha) * distribution / (four * n_dot_v));
+        let compensation = one / self.albedo.get(n_dot_v) - one;
+
+        Some((
+            single_scattering + single_scattering * reflectance * compensation,
+            distribution * n_dot_h / (four * v_dot_h),
+        ))
+    }
+}
+
+// A path leaving a lambertian surface into a direction drawn proportionally to the cosine to the
+// normal, which cancels with the cosine of the light arriving from there.
+fn diffuse_scattering<T: Length, C>(
+    sp: SurfacePoint<T>,
+    d: Vector3<T>,
+    albedo: C,
+    sample: Point2<<T as Length>::ValueType>,
+) -> Scattering<<T as Length>::ValueType, C>
+where
+    <T as Length>::ValueType: FloatingPoint,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+{
+    let zero = <T as Length>::ValueType::zero();
+    let one = <T as Length>::ValueType::one();
+    let pi = <T as Length>::ValueType::PI;
+
+    let n = facing(sp.n.as_vector().normalized(), -d.normalized());
+    let (tangent, bitangent) = tangent_frame(n);
+    let phi = (pi + pi) * sample.x;
+    let r = sample.y.sqrt();
+
+    Scattering {
+        direction: tangent * (r * phi.cos())
+            + bitangent * (r * phi.sin())
+            + n * (one - sample.y).max(zero).sqrt(),
+        weight: albedo,
+        specular: false,
+    }
+}
+
+// The density of diffuse_scattering is the cosine divided by pi, as is the share of a lambertian
+// surface, so their ratio is the albedo.
+fn diffuse_scattering_for<T: Length, C>(
+    sp: SurfacePoint<T>,
+    d: Vector3<T>,
+    albedo: C,
+    direction: Vector3<<T as Length>::ValueType>,
+) -> Option<(C, <T as Length>::ValueType)>
+where
+    <T as Length>::ValueType: FloatingPoint,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    C: Color<ChannelType = <T as Length>::ValueType>,
+{
+    let n = facing(sp.n.as_vector().normalized(), -d.normalized());
+    let cos = n.dot(direction);
+    if cos <= Zero::zero() {
+        return None;
+    }
+
+    let density = cos / <T as Length>::ValueType::PI;
+    Some((albedo * density, density))
+}
+
+// The normal turned to the side of the surface a direction points to.
+fn facing<V: FloatingPoint>(n: Vector3<V>, direction: Vector3<V>) -> Vector3<V> {
+    if n.dot(direction) < V::zero() {
+        -n
+    } else {
+        n
+    }
+}
+
+// Two directions perpendicular to a normalized normal and to each other.
+pub(crate) fn tangent_frame<V: FloatingPoint>(n: Vector3<V>) -> (Vector3<V>, Vector3<V>) {
+    let axis = if n.x.abs() > n.y.abs() {
+        Vector3::new(V::zero(), V::one(), V::zero())
+    } else {
+        Vector3::new(V::one(), V::zero(), V::zero())
+    };
+    let tangent = Vector3::cross(axis, n).normalized();
+    (tangent, Vector3::cross(n, tangent))
+}
+
+// How a normalized direction passes a clear surface with a normalized normal, from either side:
+// the reflected direction, the refracted direction unless all light is reflected beyond the
+// critical angle, and the share of the reflected light after Schlick's approximation of the
+// Fresnel equations.
+pub(crate) fn refraction<V: FloatingPoint>(
+    d: Vector3<V>,
+    n: Vector3<V>,
+    index_of_refraction: V,
+) -> (Vector3<V>, Option<Vector3<V>>, V) {
+    let one = V::one();
+    let reflected = d.reflect_on(n.as_normal());
+
+    // Rays hit the inside of clear geometry on their way out.
+    let cos_i = -d.dot(n);
+    let entering = cos_i >= V::zero();
+    let (n, cos_i, eta) = if entering {
+        (n, cos_i, one / index_of_refraction)
+    } else {
+        (-n, -cos_i, index_of_refraction)
+    };
+
+    let k = one - eta * eta * (one - cos_i * cos_i);
+    if k < V::zero() {
+        return (reflected, None, one);
+    }
+
+    let cos_t = k.sqrt();
+    let r0 = (one - index_of_refraction) / (one + index_of_refraction);
+    let r0 = r0 * r0;
+    let cos = if entering { cos_i } else { cos_t };
+    let fresnel = r0 + (one - r0) * (one - cos).powi(5);
+
+    (
+        reflected,
+        Some(d * eta + n * (eta * cos_i - cos_t)),
+        fresnel,
+    )
+}
+
+// The roughness of the GGX lobe that is about as wide as a Phong lobe with the exponent, for
+// the prefiltered reflections of environment lights.
+fn roughness_of<V>(exponent: V) -> V
+where
+    V: FloatingPoint + Sqrt<Output = V>,
+{
+    let two = V::one() + V::one();
+    (two / (exponent + two)).sqrt().sqrt()
+}
+
+// Mirrors the surroundings stored in a latitude-longitude environment map on top of another
+// material. The reflection ray only looks up the environment map and never hits the scene, which
+// is a lot cheaper than tracing it.
+impl<T: Length, M, I: Image<PointType = Point2<<T as Length>::ValueType>>> Material<T>
+    for ReflectiveMaterial<M, I>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    M: Material<T, ColorType = <I as Image>::ColorType>,
+    <I as Image>::ColorType: Color<ChannelType = <T as Length>::ValueType>,
+{
+    type ColorType = <I as Image>::ColorType;
+
+    fn color_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> Self::ColorType {
+        self.material.color_for(sp, d, lights) + self.reflection_for(sp, d)
+    }
+
+    fn diffuse_and_specular_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> (Self::ColorType, Self::ColorType) {
+        self.material.diffuse_and_specular_for(sp, d, lights)
+    }
+
+    fn albedo(&self, sp: SurfacePoint<T>) -> Self::ColorType {
+        self.material.albedo(sp)
+    }
+
+    fn reflection_for(&self, sp: SurfacePoint<T>, d: Vector3<T>) -> Self::ColorType {
+        let one = <T as Length>::ValueType::one();
+        let r = d.normalized().reflect_on(sp.n).normalized();
+
+        let u = (r.x.atan2(-r.z) / <T as Length>::ValueType::PI).half() + one.half();
+        let v = r.y.clamp(-one, one).asin() / <T as Length>::ValueType::PI + one.half();
+
+        self.material.reflection_for(sp, d)
+            + self.environment.get(Point2::new(u, v)) * self.reflectance
+    }
+
+    fn mirror_reflectance(&self, _sp: SurfacePoint<T>) -> Option<Self::ColorType> {
+        Some(self.reflectance)
+    }
+
+    fn transmission(
+        &self,
+        sp: SurfacePoint<T>,
+    ) -> Option<(Self::ColorType, <Self::ColorType as Color>::ChannelType)> {
+        self.material.transmission(sp)
+    }
+
+    // The path follows the mirror or the material below with the same probability.
+    fn scatter(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        sample: Point2<<T as Length>::ValueType>,
+    ) -> Option<Scattering<<T as Length>::ValueType, Self::ColorType>> {
+        let one = <T as Length>::ValueType::one();
+        let two = one + one;
+        if sample.x < one.half() {
+            return Some(Scattering {
+                direction: d
+                    .normalized()
+                    .reflect_on(sp.n.as_vector().normalized().as_normal()),
+                weight: self.reflectance * two,
+                specular: true,
+            });
+        }
+
+        self.material
+            .scatter(sp, d, Point2::new(sample.x * two - one, sample.y))
+            .map(|scattering| Scattering {
+                weight: scattering.weight * two,
+                ..scattering
+            })
+    }
+
+    // Only the material below is drawn half of the time, the mirror has no share in other
+    // directions.
+    fn scattering_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        direction: Vector3<<T as Length>::ValueType>,
+    ) -> Option<(Self::ColorType, <T as Length>::ValueType)> {
+        self.material
+            .scattering_for(sp, d, direction)
+            .map(|(share, density)| (share, density.half()))
+    }
+}
+
+// The tint colors everything the surface reflects and emits itself, but neither mirror reflections
+// nor the light passing through it, so tinted glass and mirrors stay clear.
+impl<T: Length, M, C: Color> Material<T> for InstanceMaterial<M, C>
+where
+    M: Material<T, ColorType = C>,
+{
+    type ColorType = C;
+
+    // Glowing materials show their emission as their color, so the strength scales it as well.
+    fn color_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> Self::ColorType {
+        let color = self.material.color_for(sp, d, lights) * self.attributes.tint;
+        match self.material.emission() {
+            Some(_) => color * self.attributes.emission_strength,
+            None => color,
+        }
+    }
+
+    fn diffuse_and_specular_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        lights: Vec<&dyn Light<T, Self::ColorType>>,
+    ) -> (Self::ColorType, Self::ColorType) {
+        let (diffuse, specular) = self.material.diffuse_and_specular_for(sp, d, lights);
+        (
+            diffuse * self.attributes.tint,
+            specular * self.attributes.tint,
+        )
+    }
+
+    fn reflection_for(&self, sp: SurfacePoint<T>, d: Vector3<T>) -> Self::ColorType {
+        self.material.reflection_for(sp, d) * self.attributes.tint
+    }
+
+    fn albedo(&self, sp: SurfacePoint<T>) -> Self::ColorType {
+        self.material.albedo(sp) * self.attributes.tint
+    }
+
+    fn emission(&self) -> Option<Self::ColorType> {
+        self.material
+            .emission()
+            .map(|color| color * self.attributes.tint * self.attributes.emission_strength)
+    }
+
+    fn mirror_reflectance(&self, sp: SurfacePoint<T>) -> Option<Self::ColorType> {
+        self.material.mirror_reflectance(sp)
+    }
+
+    fn transmission(
+        &self,
+        sp: SurfacePoint<T>,
+    ) -> Option<(Self::ColorType, <Self::ColorType as Color>::ChannelType)> {
+        self.material.transmission(sp)
+    }
+
+    fn scatter(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        sample: Point2<<T as Length>::ValueType>,
+    ) -> Option<Scattering<<T as Length>::ValueType, Self::ColorType>> {
+        self.material
+            .scatter(sp, d, sample)
+            .map(|scattering| match scattering.specular {
+                true => scattering,
+                false => Scattering {
+                    weight: scattering.weight * self.attributes.tint,
+                    ..scattering
+                },
+            })
+    }
+
+    fn scattering_for(
+        &self,
+        sp: SurfacePoint<T>,
+        d: Vector3<T>,
+        direction: Vector3<<T as Length>::ValueType>,
+    ) -> Option<(Self::ColorType, <T as Length>::ValueType)> {
+        self.material
+            .scattering_for(sp, d, direction)
+            .map(|(share, density)| (share * self.attributes.tint, density))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cg_basics::light::DirectionalLight;
+    use colors::RGB;
+    use image::texture::ImageTexture;
+    use image::{ImageBuffer, SingleColorImage, WritableImage};
+    use math::{Normal3, Point3, Vector2};
+    use units::length::Meter;
+
+    macro_rules! plastic_material_lobes {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let plastic = |diffuse: RGB<$type>, specular: $type| {
+                    PlasticMaterial::new(
+                        SingleColorImage::new(diffuse, Vector2::new(1.0, 1.0)),
+                        SingleColorImage::new(
+                            RGB::new(specular, specular, specular),
+                            Vector2::new(1.0, 1.0),
+                        ),
+                        20.0,
+                    )
+                };
+                let sp = SurfacePoint {
+                    p: Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    n: Normal3::new(0.0, 1.0, 0.0),
+                    uv: Point2::new(0.5, 0.5),
+                };
+                // A white light shining from l onto a floor seen from v.
+                let lobes =
+                    |material: &PlasticMaterial<SingleColorImage<RGB<$type>, Vector2<$type>>>,
+                     l: Vector3<$type>,
+                     v: Vector3<$type>| {
+                        let light: Box<dyn Light<Meter<$type>, RGB<$type>>> = Box::new(
+                            DirectionalLight::new(RGB::new(1.0, 1.0, 1.0), -l.normalized()),
+                        );
+                        let v = v.normalized();
+                        let d = Vector3::new(Meter::new(-v.x), Meter::new(-v.y), Meter::new(-v.z));
+                        let lobes = material.diffuse_and_specular_for(sp, d, vec![light.as_ref()]);
+                        assert_eq!(
+                            material.color_for(sp, d, vec![light.as_ref()]),
+                            lobes.0 + lobes.1
+                        );
+                        lobes
+                    };
+
+                let l = Vector3::new(1.0, 1.0, 0.0);
+                let mirror = Vector3::new(-1.0, 1.0, 0.0);
+                let red = plastic(RGB::new(1.0, 0.0, 0.0), 0.04);
+
+                // The substrate colors the diffuse lobe, the clear coat reflects the light as it is.
+                let (diffuse, specular) = lobes(&red, l, mirror);
+                assert!(diffuse.red > 0.0);
+                assert_eq!((diffuse.green, diffuse.blue), (0.0, 0.0));
+                assert!(specular.red > 0.0);
+                assert_eq!(specular.red, specular.green);
+                assert_eq!(specular.red, specular.blue);
+
+                // The highlight is brightest in the mirror direction.
+                let (_, off_mirror) = lobes(&red, l, Vector3::new(-1.0, 3.0, 0.0));
+                assert!(off_mirror.red < specular.red);
+
+                // The coat reflects more at grazing angles.
+                let (_, head_on) = lobes(
+                    &red,
+                    Vector3::new(0.0, 1.0, 0.0),
+                    Vector3::new(0.0, 1.0, 0.0),
+                );
+                let (_, grazing) = lobes(
+                    &red,
+                    Vector3::new(1.0, 0.1, 0.0),
+                    Vector3::new(-1.0, 0.1, 0.0),
+                );
+                assert!(grazing.red > head_on.red);
+
+                // No light reaches the substrate below a coat that reflects everything.
+                let (diffuse, specular) = lobes(&plastic(RGB::new(1.0, 1.0, 1.0), 1.0), l, mirror);
+                assert_eq!(diffuse, RGB::new(0.0, 0.0, 0.0));
+                assert!(specular.red > 0.0);
+            }
+        };
+    }
+
+    plastic_material_lobes! { f32, plastic_material_lobes_f32 }
+    plastic_material_lobes! { f64, plastic_material_lobes_f64 }
+
+    macro_rules! plastic_material_conserves_energy {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let sp = SurfacePoint {
+                    p: Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    n: Normal3::new(0.0, 1.0, 0.0),
+                    uv: Point2::new(0.5, 0.5),
+                };
+                let pi = <$type>::PI;
+                let steps = 128;
+
+                for exponent in [1.0, 10.0, 100.0] {
+                    for specular in [0.0, 0.04, 1.0] {
+                        let material = PlasticMaterial::new(
+                            SingleColorImage::new(
+                                RGB::<$type>::new(1.0, 1.0, 1.0),
+                                Vector2::new(1.0, 1.0),
+                            ),
+                            SingleColorImage::new(
+                                RGB::new(specular, specular, specular),
+                                Vector2::new(1.0, 1.0),
+                            ),
+                            exponent,
+                        );
+                        for n_dot_v in [1.0, 0.7, 0.2] {
+                            let d = Vector3::new(
+                                Meter::new((1.0 - n_dot_v * n_dot_v).sqrt()),
+                                Meter::new(-n_dot_v),
+                                Meter::new(0.0),
+                            );
+
+                            // The light reflected towards the viewer from a white sky, integrated
+                            // over the hemisphere in equal steps of the solid angle. The colors
+                            // are scaled by pi, like the radiance of a lambertian surface.
+                            let mut albedo = RGB::<$type>::new(0.0, 0.0, 0.0);
+                            for i in 0..steps {
+                                for j in 0..steps {
+                                    let cos = (i as $type + 0.5) / steps as $type;
+                                    let sin = (1.0 - cos * cos).sqrt();
+                                    let phi = 2.0 * pi * (j as $type + 0.5) / steps as $type;
+                                    let l = Vector3::new(sin * phi.cos(), cos, sin * phi.sin());
+                                    let light: Box<dyn Light<Meter<$type>, RGB<$type>>> = Box::new(
+                                        DirectionalLight::new(RGB::new(1.0, 1.0, 1.0), -l),
+                                    );
+                                    albedo += material.color_for(sp, d, vec![light.as_ref()]);
+                                }
+                            }
+                            let albedo = albedo * (2.0 / (steps * steps) as $type);
+                            assert!(albedo.red > 0.2, "{} {} {}", exponent, specular, n_dot_v);
+                            assert!(albedo.red <= 1.0, "{} {} {}", exponent, specular, n_dot_v);
+                            assert_eq!(albedo.red, albedo.green);
+                            assert_eq!(albedo.red, albedo.blue);
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    plastic_material_conserves_energy! { f32, plastic_material_conserves_energy_f32 }
+    plastic_material_conserves_energy! { f64, plastic_material_conserves_energy_f64 }
+
+    // A mirror on a red lambertian surface, which reflects an environment map of four by two
+    // pixels. Only the pixel centered at u 0.625 and v 0.75 is white, which lies between x and -z,
+    // 45 degrees above the horizon.
+    macro_rules! reflective_material {
+        ($type: ty) => {{
+            let mut environment = ImageBuffer::new(Vector2::new(4, 2), RGB::<$type>::default());
+            *environment.get_mut(Point2::new(2, 0)) = RGB::new(1.0, 1.0, 1.0);
+            ReflectiveMaterial::new(
+                LambertMaterial::new(SingleColorImage::new(
+                    RGB::<$type>::new(0.8, 0.0, 0.0),
+                    Vector2::new(1.0, 1.0),
+                )),
+                ImageTexture::new(environment),
+                RGB::new(0.2, 0.3, 0.4),
+            )
+        }};
+    }
+
+    macro_rules! reflective_material_mirrors_environment {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let material = reflective_material!($type);
+                let sp = SurfacePoint {
+                    p: Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    n: Normal3::new(0.0, 1.0, 0.0),
+                    uv: Point2::new(0.5, 0.5),
+                };
+                let half_sqrt2 = <$type>::sqrt(2.0) / 2.0;
+                let view = |x: $type, z: $type| {
+                    Vector3::new(Meter::new(x), Meter::new(-half_sqrt2), Meter::new(z))
+                };
+
+                // Looking down towards x and -z, the mirror shows the white pixel.
+                let reflection = material.reflection_for(sp, view(0.5, -0.5));
+                assert!((reflection.red - 0.2).abs() < 0.0001);
+                assert!((reflection.green - 0.3).abs() < 0.0001);
+                assert!((reflection.blue - 0.4).abs() < 0.0001);
+
+                // Any other pixel is black.
+                for (x, z) in [(-0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)] {
+                    let reflection = material.reflection_for(sp, view(x, z));
+                    assert!(reflection.red.abs() < 0.0001);
+                    assert!(reflection.green.abs() < 0.0001);
+                    assert!(reflection.blue.abs() < 0.0001);
+                }
+            }
+        };
+    }
+
+    reflective_material_mirrors_environment! { f32, reflective_material_mirrors_environment_f32 }
+    reflective_material_mirrors_environment! { f64, reflective_material_mirrors_environment_f64 }
+
+    macro_rules! reflective_material_scatters_into_both_lobes {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let material = reflective_material!($type);
+                let sp = SurfacePoint {
+                    p: Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    n: Normal3::new(0.0, 1.0, 0.0),
+                    uv: Point2::new(0.5, 0.5),
+                };
+                let d = Vector3::new(Meter::new(0.6), Meter::new(-0.8), Meter::new(0.0));
+
+                // The weights of the mirror average to the reflectance, those of the surface
+                // below to its albedo.
+                let steps = 16;
+                let mut mirror = RGB::<$type>::default();
+                let mut diffuse = RGB::<$type>::default();
+                for i in 0..steps {
+                    for j in 0..steps {
+                        let sample = Point2::new(
+                            (i as $type + 0.5) / steps as $type,
+                            (j as $type + 0.5) / steps as $type,
+                        );
+                        let scattering = material.scatter(sp, d, sample).unwrap();
+                        if scattering.specular {
+                            assert_eq!(scattering.direction, Vector3::new(0.6, 0.8, 0.0));
+                            mirror += scattering.weight;
+                        } else {
+                            assert!(scattering.direction.y > 0.0);
+                            diffuse += scattering.weight;
+                        }
+                    }
+                }
+                let samples = (steps * steps) as $type;
+                let close = |a: RGB<$type>, b: RGB<$type>| {
+                    (a.red - b.red).abs() < 0.0001
+                        && (a.green - b.green).abs() < 0.0001
+                        && (a.blue - b.blue).abs() < 0.0001
+                };
+                assert!(close(mirror * (1.0 / samples), RGB::new(0.2, 0.3, 0.4)));
+                assert!(close(diffuse * (1.0 / samples), RGB::new(0.8, 0.0, 0.0)));
+            }
+        };
+    }
+
+    reflective_material_scatters_into_both_lobes! { f32, reflective_material_scatters_into_both_lobes_f32 }
+    reflective_material_scatters_into_both_lobes! { f64, reflective_material_scatters_into_both_lobes_f64 }
 }
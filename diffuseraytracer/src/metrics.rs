@@ -0,0 +1,465 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use math::{Point2, Vector2};
+
+// A counter that can be increased from several threads at once. Workers should add up their
+// counts locally and report them in batches, e.g. once per pixel, to keep the atomic operations
+// off the hot path.
+#[derive(Debug, Default)]
+pub struct Counter {
+    value: AtomicU64,
+}
+
+impl Counter {
+    pub fn new() -> Counter {
+        Counter {
+            value: AtomicU64::new(0),
+        }
+    }
+
+    pub fn add(&self, amount: u64) {
+        self.value.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    pub fn set(&self, value: u64) {
+        self.value.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+// Sums up the time spent in a section of code over all threads.
+#[derive(Debug, Default)]
+pub struct Timer {
+    nanoseconds: AtomicU64,
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer {
+            nanoseconds: AtomicU64::new(0),
+        }
+    }
+
+    // The time until the returned guard is dropped is added to the timer.
+    pub fn start(&self) -> TimerGuard<'_> {
+        TimerGuard {
+            timer: self,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn add(&self, duration: Duration) {
+        self.nanoseconds
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn total(&self) -> Duration {
+        Duration::from_nanos(self.nanoseconds.load(Ordering::Relaxed))
+    }
+}
+
+pub struct TimerGuard<'a> {
+    timer: &'a Timer,
+    start: Instant,
+}
+
+impl Drop for TimerGuard<'_> {
+    fn drop(&mut self) {
+        self.timer.add(self.start.elapsed());
+    }
+}
+
+// The samples taken for each pixel of an image, e.g. to show where adaptive sampling spends its
+// effort. The counts of several passes add up.
+#[derive(Debug)]
+pub struct SampleCounts {
+    size: Vector2<usize>,
+    counts: Vec<AtomicU64>,
+}
+
+impl SampleCounts {
+    pub fn new(size: Vector2<usize>) -> SampleCounts {
+        SampleCounts {
+            size,
+            counts: (0..size.x * size.y).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    pub fn size(&self) -> Vector2<usize> {
+        self.size
+    }
+
+    pub fn add(&self, p: Point2<usize>, samples: u64) {
+        self.counts[p.y * self.size.x + p.x].fetch_add(samples, Ordering::Relaxed);
+    }
+
+    pub fn get(&self, p: Point2<usize>) -> u64 {
+        self.counts[p.y * self.size.x + p.x].load(Ordering::Relaxed)
+    }
+}
+
+// Stops a render that runs on other threads, e.g. when the user of an application aborts it. The
+// renderers check it before every tile and every pass, so they stop soon and return what they
+// rendered so far, with the remaining tiles left empty. Clones share their state, so a clone that
+// is kept cancels the render of the metrics it is given to.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// Called with the current numbers whenever a tile is done, e.g. to show the progress of a render
+// in an application. It is called on the worker threads, so it should return quickly.
+pub type ProgressCallback = Box<dyn Fn(&Statistics) + Send + Sync>;
+
+// The counters renderers report into while they are working. It is shared between the worker
+// threads and whoever presents the numbers, e.g. the statistics printed after a render or a
+// progress bar that polls it.
+pub struct Metrics {
+    pub pixels: Counter,
+    pub pixels_total: Counter,
+    pub tiles: Counter,
+    pub tiles_total: Counter,
+    pub samples: Counter,
+    pub camera_rays: Counter,
+    pub shadow_rays: Counter,
+    pub intersection_tests: Counter,
+    pub render_time: Timer,
+    pub sample_counts: Option<SampleCounts>,
+    started: Instant,
+    progress_callback: Option<ProgressCallback>,
+    cancellation: CancellationToken,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics {
+            pixels: Counter::new(),
+            pixels_total: Counter::new(),
+            tiles: Counter::new(),
+            tiles_total: Counter::new(),
+            samples: Counter::new(),
+            camera_rays: Counter::new(),
+            shadow_rays: Counter::new(),
+            intersection_tests: Counter::new(),
+            render_time: Timer::new(),
+            sample_counts: None,
+            started: Instant::now(),
+            progress_callback: None,
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    pub fn with_progress_callback(self, progress_callback: ProgressCallback) -> Metrics {
+        Metrics {
+            progress_callback: Some(progress_callback),
+            ..self
+        }
+    }
+
+    pub fn with_cancellation(self, cancellation: CancellationToken) -> Metrics {
+        Metrics {
+            cancellation,
+            ..self
+        }
+    }
+
+    // Counts the samples of each pixel of an image of the size, besides the total.
+    pub fn with_sample_counts(self, size: Vector2<usize>) -> Metrics {
+        Metrics {
+            sample_counts: Some(SampleCounts::new(size)),
+            ..self
+        }
+    }
+
+    // Counts a finished pixel along with the samples taken for it.
+    pub fn pixel_done(&self, p: Point2<usize>, samples: u64) {
+        self.samples.add(samples);
+        if let Some(sample_counts) = &self.sample_counts {
+            sample_counts.add(p, samples);
+        }
+        self.pixels.increment();
+    }
+
+    // Counts a finished tile and tells the progress callback.
+    pub fn tile_done(&self) {
+        self.tiles.increment();
+        if let Some(progress_callback) = &self.progress_callback {
+            progress_callback(&self.snapshot());
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancellation.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    // A copy of the current numbers. Counters that are updated while the copy is taken may be
+    // off by the updates of a single batch.
+    pub fn snapshot(&self) -> Statistics {
+        Statistics {
+            pixels: self.pixels.get(),
+            pixels_total: self.pixels_total.get(),
+            tiles: self.tiles.get(),
+            tiles_total: self.tiles_total.get(),
+            samples: self.samples.get(),
+            camera_rays: self.camera_rays.get(),
+            shadow_rays: self.shadow_rays.get(),
+            intersection_tests: self.intersection_tests.get(),
+            render_time: self.render_time.total(),
+            elapsed: self.started.elapsed(),
+        }
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Metrics {
+        Metrics::new()
+    }
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Metrics")
+            .field("statistics", &self.snapshot())
+            .field("progress_callback", &self.progress_callback.is_some())
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Statistics {
+    pub pixels: u64,
+    pub pixels_total: u64,
+    pub tiles: u64,
+    pub tiles_total: u64,
+    pub samples: u64,
+    pub camera_rays: u64,
+    pub shadow_rays: u64,
+    pub intersection_tests: u64,
+    // The time summed over all threads.
+    pub render_time: Duration,
+    // The wall clock time since the metrics were created.
+    pub elapsed: Duration,
+}
+
+impl Statistics {
+    // The finished part of the render between 0 and 1.
+    pub fn progress(&self) -> f64 {
+        if self.pixels_total == 0 {
+            0.0
+        } else {
+            self.pixels as f64 / self.pixels_total as f64
+        }
+    }
+
+    // The time left, assuming the rest of the render is as fast as the part that is done. None
+    // until there is any progress.
+    pub fn remaining(&self) -> Option<Duration> {
+        let progress = self.progress().clamp(0.0, 1.0);
+        if progress == 0.0 {
+            None
+        } else {
+            Some(self.elapsed.mul_f64((1.0 - progress) / progress))
+        }
+    }
+
+    pub fn rays_per_second(&self) -> f64 {
+        let seconds = self.render_time.as_secs_f64();
+        if seconds == 0.0 {
+            0.0
+        } else {
+            (self.camera_rays + self.shadow_rays) as f64 / seconds
+        }
+    }
+
+    // A single line bar like [#####.....]  50.0%.
+    pub fn progress_bar(&self, width: usize) -> String {
+        let progress = self.progress().clamp(0.0, 1.0);
+        let filled = (progress * width as f64).round() as usize;
+        format!(
+            "[{}{}] {:5.1}%",
+            "#".repeat(filled),
+            ".".repeat(width - filled),
+            progress * 100.0
+        )
+    }
+
+    // The progress bar followed by the time left, like [#####.....]  50.0% 0:01:05 left.
+    pub fn progress_line(&self, width: usize) -> String {
+        match self.remaining() {
+            Some(remaining) => {
+                let seconds = remaining.as_secs_f64().round() as u64;
+                format!(
+                    "{} {}:{:02}:{:02} left",
+                    self.progress_bar(width),
+                    seconds / 3600,
+                    seconds / 60 % 60,
+                    seconds % 60
+                )
+            }
+            None => self.progress_bar(width),
+        }
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Pixels:             {}/{}",
+            self.pixels, self.pixels_total
+        )?;
+        writeln!(f, "Tiles:              {}/{}", self.tiles, self.tiles_total)?;
+        writeln!(f, "Samples:            {}", self.samples)?;
+        writeln!(f, "Camera rays:        {}", self.camera_rays)?;
+        writeln!(f, "Shadow rays:        {}", self.shadow_rays)?;
+        writeln!(f, "Intersection tests: {}", self.intersection_tests)?;
+        writeln!(
+            f,
+            "Render time:        {:.3} s",
+            self.render_time.as_secs_f64()
+        )?;
+        write!(f, "Rays per second:    {:.0}", self.rays_per_second())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn metrics_are_collected_from_several_threads() {
+        let metrics = Metrics::new();
+        metrics.pixels_total.set(400);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let _guard = metrics.render_time.start();
+                    for _ in 0..100 {
+                        metrics.pixels.increment();
+                        metrics.camera_rays.add(4);
+                    }
+                });
+            }
+        });
+
+        let statistics = metrics.snapshot();
+        assert_eq!(statistics.pixels, 400);
+        assert_eq!(statistics.camera_rays, 1600);
+        assert_eq!(statistics.shadow_rays, 0);
+        assert_eq!(statistics.progress(), 1.0);
+        assert!(statistics.render_time > Duration::ZERO);
+    }
+
+    #[test]
+    fn sample_counts_per_pixel() {
+        let metrics = Metrics::new().with_sample_counts(Vector2::new(3, 2));
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    metrics.pixel_done(Point2::new(2, 1), 16);
+                    metrics.pixel_done(Point2::new(0, 0), 4);
+                });
+            }
+        });
+
+        let sample_counts = metrics.sample_counts.as_ref().unwrap();
+        assert_eq!(sample_counts.get(Point2::new(2, 1)), 64);
+        assert_eq!(sample_counts.get(Point2::new(0, 0)), 16);
+        assert_eq!(sample_counts.get(Point2::new(1, 0)), 0);
+        assert_eq!(metrics.snapshot().samples, 80);
+        assert_eq!(metrics.snapshot().pixels, 8);
+
+        // Without sample counts, only the totals are kept.
+        let metrics = Metrics::new();
+        metrics.pixel_done(Point2::new(5, 5), 4);
+        assert!(metrics.sample_counts.is_none());
+        assert_eq!(metrics.snapshot().samples, 4);
+    }
+
+    #[test]
+    fn progress_bar() {
+        let statistics = Statistics {
+            pixels: 1,
+            pixels_total: 4,
+            ..Statistics::default()
+        };
+
+        assert_eq!(statistics.progress_bar(8), "[##......]  25.0%");
+        assert_eq!(Statistics::default().progress_bar(2), "[..]   0.0%");
+    }
+
+    #[test]
+    fn progress_line_shows_time_left() {
+        let statistics = Statistics {
+            pixels: 1,
+            pixels_total: 4,
+            elapsed: Duration::from_secs(1250),
+            ..Statistics::default()
+        };
+
+        assert_eq!(statistics.remaining(), Some(Duration::from_secs(3750)));
+        assert_eq!(statistics.progress_line(4), "[#...]  25.0% 1:02:30 left");
+        assert_eq!(Statistics::default().remaining(), None);
+        assert_eq!(Statistics::default().progress_line(2), "[..]   0.0%");
+    }
+
+    #[test]
+    fn progress_callback_is_called_for_every_tile() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = std::sync::Mutex::new(sender);
+        let metrics =
+            Metrics::new().with_progress_callback(Box::new(move |statistics: &Statistics| {
+                sender.lock().unwrap().send(statistics.tiles).unwrap();
+            }));
+        metrics.tiles_total.set(2);
+
+        metrics.tile_done();
+        metrics.tile_done();
+
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn cancellation_is_shared_with_clones() {
+        let cancellation = CancellationToken::new();
+        let metrics = Metrics::new().with_cancellation(cancellation.clone());
+        assert!(!metrics.is_cancelled());
+
+        cancellation.cancel();
+        assert!(metrics.is_cancelled());
+        assert!(!Metrics::new().is_cancelled());
+    }
+}
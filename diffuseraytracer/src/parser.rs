@@ -1,58 +1,99 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fmt::Debug;
 use std::fs;
 use std::ops::Div;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
 
-use crate::camera::RaytracingCamera;
+use crate::camera::{AnimatedCamera, FocusPulledCamera, RaytracingCamera};
 use crate::light::Light;
 use crate::material::Material;
+use crate::settings::RenderSettings;
 use crate::{AxisAlignedBox, Cylinder, Disc, Plane, Renderable, Sphere, Triangle};
+use cg_basics::background::Background;
 use cg_basics::camera::{
-    FisheyeCamera, OrthographicCamera, PerspectiveCamera, PinholeCamera, SphericalCamera,
+    CameraPath, CylindricalCamera, FisheyeCamera, FocusPull, FocusTarget, OdsCamera,
+    OrthographicCamera, PerspectiveCamera, PhysicalCamera, PinholeCamera, SphericalCamera,
+    StereoCamera,
+};
+use cg_basics::light::{
+    AmbientLight, AmbientOcclusionLight, AreaLight, EnvironmentLight, MeshLight, PointLight,
+    SphereLight, SpotLight,
 };
-use cg_basics::light::{AmbientLight, AmbientOcclusionLight, PointLight, SpotLight};
-use cg_basics::scene_graph::RenderableGeometry;
 use cg_basics::scene_graph::Scene3;
+use cg_basics::scene_graph::{RenderableGeometry, RenderableMesh};
+use cg_basics::sky::PreethamSky;
+use cg_basics::volume::VolumeRegion;
 use colors::RGB;
-use math::transform::Transform3;
-use math::{Normal3, Orthonormal3, Point2};
+use image::Image;
+use math::geometry::triangle::Triangle3Mesh;
+use math::{Normal3, Orthonormal3, Point2, Vector2};
 use random::{RandomNumberGenerator, WichmannHillPRNG};
 use sampling::{PatternMapping, SamplingPattern};
 use traits::{
-    ConvenientNumber, Cos, FloatingPoint, Number, SelfMulNumber, SignedNumber, Sin, Sqrt, Zero,
+    ConvenientNumber, Cos, Exp, FloatingPoint, Number, SelfMulNumber, SignedNumber, Sin, Sqrt,
+    TotalCmp, Zero,
 };
 use units::angle::{Angle, Radians};
 use units::length::Length;
 
+pub mod assets;
+mod background;
 mod camera;
 mod geometry;
+pub mod include;
 mod light;
+pub mod location;
 mod material;
 mod misc;
+pub mod plugin;
+mod settings;
 mod texture;
-mod util;
+mod tokenizer;
+mod toml;
+pub mod util;
+mod volume;
+
+pub use material::parse_material;
+
+use location::{locate, Location, TrackedTokens};
+use plugin::{MaterialFactory, PluginRegistry};
 
-type MaterialType<T> = Box<dyn Material<T, ColorType = RGB<<T as Length>::ValueType>>>;
+pub type MaterialType<T> = Arc<dyn Material<T, ColorType = RGB<<T as Length>::ValueType>>>;
+pub type LightType<T> = Box<dyn Light<T, RGB<<T as Length>::ValueType>>>;
+pub type RenderableType<T> = Box<dyn Renderable<T, RGB<<T as Length>::ValueType>>>;
+pub type SceneType<T> = Scene3<
+    RGB<<T as Length>::ValueType>,
+    LightType<T>,
+    Box<dyn RaytracingCamera<T>>,
+    RenderableType<T>,
+>;
+pub type TextureType<T> = Box<dyn Image<ColorType = RGB<T>, PointType = Point2<T>>>;
+
+// The sky is baked into an environment map of this size, which is enough for the smooth
+// gradients of the model.
+const SKY_RESOLUTION: Vector2<usize> = Vector2 { x: 256, y: 128 };
 
 type RenderableAxisAlignedBox<T> =
-    RenderableGeometry<AxisAlignedBox<T>, MaterialType<T>, Transform3<<T as Length>::ValueType>>;
+    RenderableGeometry<AxisAlignedBox<T>, MaterialType<T>, <T as Length>::ValueType>;
 type RenderableCylinder<T> =
-    RenderableGeometry<Cylinder<T>, MaterialType<T>, Transform3<<T as Length>::ValueType>>;
-type RenderableDisc<T> =
-    RenderableGeometry<Disc<T>, MaterialType<T>, Transform3<<T as Length>::ValueType>>;
-type RenderablePlane<T> =
-    RenderableGeometry<Plane<T>, MaterialType<T>, Transform3<<T as Length>::ValueType>>;
-type RenderableSphere<T> =
-    RenderableGeometry<Sphere<T>, MaterialType<T>, Transform3<<T as Length>::ValueType>>;
+    RenderableGeometry<Cylinder<T>, MaterialType<T>, <T as Length>::ValueType>;
+type RenderableDisc<T> = RenderableGeometry<Disc<T>, MaterialType<T>, <T as Length>::ValueType>;
+type RenderablePlane<T> = RenderableGeometry<Plane<T>, MaterialType<T>, <T as Length>::ValueType>;
+type RenderableSphere<T> = RenderableGeometry<Sphere<T>, MaterialType<T>, <T as Length>::ValueType>;
 type RenderableTriangle<T> =
-    RenderableGeometry<Triangle<T>, MaterialType<T>, Transform3<<T as Length>::ValueType>>;
+    RenderableGeometry<Triangle<T>, MaterialType<T>, <T as Length>::ValueType>;
+type RenderableTriangleMesh<T> =
+    RenderableMesh<Triangle3Mesh<T>, MaterialType<T>, <T as Length>::ValueType>;
 
 #[derive(Debug)]
 pub enum ParsingError {
     UnexpectedEndOfTokens,
+    UnterminatedComment,
     NumberParsingError(&'static str),
+    NonFiniteNumber(String),
 
     ColorParsingError(Box<ParsingError>),
     Point2ParsingError(Box<ParsingError>),
@@ -66,15 +107,27 @@ pub enum ParsingError {
     },
     TextureParsingError(Box<ParsingError>),
     UnsupportedTexture(String),
+    BackgroundParsingError(Box<ParsingError>),
+    UnsupportedBackground(String),
     SingleColorTextureParsingError(Box<ParsingError>),
     CheckerboardTextureParsingError(Box<ParsingError>),
     GridTextureParsingError(Box<ParsingError>),
+    NoiseTextureParsingError(Box<ParsingError>),
+    ImageTextureParsingError(Box<ParsingError>),
 
     UnshadedMaterialParsingError(Box<ParsingError>),
     LambertMaterialParsingError(Box<ParsingError>),
     PhongMaterialParsingError(Box<ParsingError>),
+    PlasticMaterialParsingError(Box<ParsingError>),
+    MetalMaterialParsingError(Box<ParsingError>),
+    DielectricMaterialParsingError(Box<ParsingError>),
+    ReflectiveMaterialParsingError(Box<ParsingError>),
+    EmissiveMaterialParsingError(Box<ParsingError>),
     MaterialParsingError(Box<ParsingError>),
+    MaterialLibraryParsingError(Box<ParsingError>),
+    InstanceAttributesParsingError(Box<ParsingError>),
     UnsupportedMaterial(String),
+    DuplicateMaterial(String),
 
     DiscParsingError(Box<ParsingError>),
     SphereParsingError(Box<ParsingError>),
@@ -82,39 +135,215 @@ pub enum ParsingError {
     PlaneParsingError(Box<ParsingError>),
     BoxParsingError(Box<ParsingError>),
     TriangleParsingError(Box<ParsingError>),
+    MeshParsingError(Box<ParsingError>),
 
     PinholeCameraParsingError(Box<ParsingError>),
     PerspectiveCameraParsingError(Box<ParsingError>),
+    PhysicalCameraParsingError(Box<ParsingError>),
     FisheyeCameraParsingError(Box<ParsingError>),
     OrthographicCameraParsingError(Box<ParsingError>),
     SphericalCameraParsingError(Box<ParsingError>),
+    CylindricalCameraParsingError(Box<ParsingError>),
+    StereoCameraParsingError(Box<ParsingError>),
+    OdsCameraParsingError(Box<ParsingError>),
+    CameraPathParsingError(Box<ParsingError>),
+    FocusPullParsingError(Box<ParsingError>),
 
     PointLightParsingError(Box<ParsingError>),
     SpotLightParsingError(Box<ParsingError>),
+    AreaLightParsingError(Box<ParsingError>),
+    SphereLightParsingError(Box<ParsingError>),
+    EnvironmentLightParsingError(Box<ParsingError>),
+    SkyParsingError(Box<ParsingError>),
     AmbientOcclusionLightParsingError(Box<ParsingError>),
 
+    VolumeParsingError(Box<ParsingError>),
+
+    SettingsParsingError(Box<ParsingError>),
+
     MissingElement(&'static str),
     UnsupportedElement(String),
+    UnknownObject(String),
+    ImageLoadingError(String),
+    ProfileLoadingError(String),
+    IncludeLoadingError(String),
+    IncludeCycle(String),
+    IncludeOutsideDirectory(String),
     SceneParsingError(Box<ParsingError>),
+
+    PluginParsingError(&'static str, Box<ParsingError>),
+
+    TomlParsingError(&'static str),
+
+    LocatedParsingError(Location, Box<ParsingError>),
 }
 
-trait FromTokens: Sized {
+pub trait FromTokens: Sized {
     type Err;
 
     fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err>;
 }
 
+pub trait FromTokensWithMaterials<T: Length>: Sized {
+    fn from_tokens<'a>(
+        tokens: &mut impl Iterator<Item = &'a str>,
+        materials: &MaterialLibrary<T>,
+    ) -> Result<Self, ParsingError>;
+}
+
+// The named materials of a scene together with the material plugins that can be constructed by
+// name.
+pub struct MaterialLibrary<T: Length> {
+    materials: HashMap<String, MaterialType<T>>,
+    plugins: HashMap<&'static str, MaterialFactory<T>>,
+}
+
+impl<T: Length> MaterialLibrary<T> {
+    fn new(plugins: &PluginRegistry<T>) -> MaterialLibrary<T> {
+        MaterialLibrary {
+            materials: HashMap::new(),
+            plugins: plugins.materials(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<MaterialType<T>> {
+        self.materials.get(name).cloned()
+    }
+
+    pub fn insert(&mut self, name: String, material: MaterialType<T>) {
+        self.materials.insert(name, material);
+    }
+
+    pub fn plugin(&self, name: &str) -> Option<MaterialFactory<T>> {
+        self.plugins.get(name).copied()
+    }
+}
+
 pub fn parse_scene<T: Length + SignedNumber<T::ValueType> + ConvenientNumber + 'static>(
     filename: &str,
-) -> Result<
-    Scene3<
-        RGB<<T as Length>::ValueType>,
-        Box<dyn Light<T, RGB<<T as Length>::ValueType>>>,
-        Box<dyn RaytracingCamera<T>>,
-        Box<dyn Renderable<T, RGB<T::ValueType>>>,
-    >,
-    ParsingError,
->
+) -> Result<SceneType<T>, ParsingError>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <<T as Length>::ValueType as FromStr>::Err: Error,
+    <T as Length>::AreaType: Sqrt<Output = T>
+        + SelfMulNumber<T::ValueType>
+        + SignedNumber<T::ValueType>
+        + ConvenientNumber,
+    <T as Length>::SecondMomentOfAreaType:
+        Number<T::ValueType> + Sqrt<Output = <T as Length>::AreaType> + ConvenientNumber,
+    <T as FromStr>::Err: Error,
+    Normal3<<T as Length>::ValueType>: Orthonormal3,
+    Radians<<T as Div>::Output>:
+        Angle + Cos<Output = <T as Div>::Output> + Sin<Output = <T as Div>::Output>,
+    SamplingPattern<Point2<T::ValueType>>: PatternMapping<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+    <T as Length>::ValueType: From<f32> + Exp<Output = <T as Length>::ValueType>,
+    u16: Into<<T as Length>::ValueType>,
+{
+    parse_scene_with_plugins(filename, &PluginRegistry::new())
+}
+
+pub fn parse_scene_with_plugins<
+    T: Length + SignedNumber<T::ValueType> + ConvenientNumber + 'static,
+>(
+    filename: &str,
+    plugins: &PluginRegistry<T>,
+) -> Result<SceneType<T>, ParsingError>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <<T as Length>::ValueType as FromStr>::Err: Error,
+    <T as Length>::AreaType: Sqrt<Output = T>
+        + SelfMulNumber<T::ValueType>
+        + SignedNumber<T::ValueType>
+        + ConvenientNumber,
+    <T as Length>::SecondMomentOfAreaType:
+        Number<T::ValueType> + Sqrt<Output = <T as Length>::AreaType> + ConvenientNumber,
+    <T as FromStr>::Err: Error,
+    Normal3<<T as Length>::ValueType>: Orthonormal3,
+    Radians<<T as Div>::Output>:
+        Angle + Cos<Output = <T as Div>::Output> + Sin<Output = <T as Div>::Output>,
+    SamplingPattern<Point2<T::ValueType>>: PatternMapping<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+    <T as Length>::ValueType: From<f32> + Exp<Output = <T as Length>::ValueType>,
+    u16: Into<<T as Length>::ValueType>,
+{
+    parse_scene_with_include_dirs(filename, plugins, &[])
+}
+
+// Files the scene refers to are looked up next to the scene and then in the include directories.
+pub fn parse_scene_with_include_dirs<
+    T: Length + SignedNumber<T::ValueType> + ConvenientNumber + 'static,
+>(
+    filename: &str,
+    plugins: &PluginRegistry<T>,
+    include_dirs: &[PathBuf],
+) -> Result<SceneType<T>, ParsingError>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <<T as Length>::ValueType as FromStr>::Err: Error,
+    <T as Length>::AreaType: Sqrt<Output = T>
+        + SelfMulNumber<T::ValueType>
+        + SignedNumber<T::ValueType>
+        + ConvenientNumber,
+    <T as Length>::SecondMomentOfAreaType:
+        Number<T::ValueType> + Sqrt<Output = <T as Length>::AreaType> + ConvenientNumber,
+    <T as FromStr>::Err: Error,
+    Normal3<<T as Length>::ValueType>: Orthonormal3,
+    Radians<<T as Div>::Output>:
+        Angle + Cos<Output = <T as Div>::Output> + Sin<Output = <T as Div>::Output>,
+    SamplingPattern<Point2<T::ValueType>>: PatternMapping<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+    <T as Length>::ValueType: From<f32> + Exp<Output = <T as Length>::ValueType>,
+    u16: Into<<T as Length>::ValueType>,
+{
+    parse_scenes_with_include_dirs(&[filename], plugins, include_dirs)
+}
+
+// Merges several scene files into one scene, e.g. to reuse a lighting rig for many scenes.
+// Geometries and lights of all files are rendered together, cameras with the same id and the
+// background are replaced by later files. The named materials of all files are collected before
+// the geometries are parsed, so later files can override the materials of earlier ones. Within a
+// file, every name is defined once.
+pub fn parse_scenes_with_include_dirs<
+    T: Length + SignedNumber<T::ValueType> + ConvenientNumber + 'static,
+>(
+    filenames: &[&str],
+    plugins: &PluginRegistry<T>,
+    include_dirs: &[PathBuf],
+) -> Result<SceneType<T>, ParsingError>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <<T as Length>::ValueType as FromStr>::Err: Error,
+    <T as Length>::AreaType: Sqrt<Output = T>
+        + SelfMulNumber<T::ValueType>
+        + SignedNumber<T::ValueType>
+        + ConvenientNumber,
+    <T as Length>::SecondMomentOfAreaType:
+        Number<T::ValueType> + Sqrt<Output = <T as Length>::AreaType> + ConvenientNumber,
+    <T as FromStr>::Err: Error,
+    Normal3<<T as Length>::ValueType>: Orthonormal3,
+    Radians<<T as Div>::Output>:
+        Angle + Cos<Output = <T as Div>::Output> + Sin<Output = <T as Div>::Output>,
+    SamplingPattern<Point2<T::ValueType>>: PatternMapping<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+    <T as Length>::ValueType: From<f32> + Exp<Output = <T as Length>::ValueType>,
+    u16: Into<<T as Length>::ValueType>,
+{
+    match parse_scenes_and_settings(filenames, plugins, include_dirs) {
+        Ok((scene, _)) => Ok(scene),
+        Err(cause) => Err(cause),
+    }
+}
+
+// Like parse_scenes_with_include_dirs, together with the settings of the scene files. Like the
+// materials, the settings blocks of all files are collected before the rest of the scene.
+pub fn parse_scenes_and_settings<
+    T: Length + SignedNumber<T::ValueType> + ConvenientNumber + 'static,
+>(
+    filenames: &[&str],
+    plugins: &PluginRegistry<T>,
+    include_dirs: &[PathBuf],
+) -> Result<(SceneType<T>, RenderSettings<T::ValueType>), ParsingError>
 where
     <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
     <<T as Length>::ValueType as FromStr>::Err: Error,
@@ -130,159 +359,914 @@ where
         Angle + Cos<Output = <T as Div>::Output> + Sin<Output = <T as Div>::Output>,
     SamplingPattern<Point2<T::ValueType>>: PatternMapping<T::ValueType>,
     WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+    <T as Length>::ValueType: From<f32> + Exp<Output = <T as Length>::ValueType>,
+    u16: Into<<T as Length>::ValueType>,
 {
-    let file_content = fs::read_to_string(filename).expect("Unable to read file");
+    // Errors are located at the token they are found at.
+    let files: Vec<_> = filenames
+        .iter()
+        .map(|filename| {
+            let file_content = fs::read_to_string(filename).expect("Unable to read file");
+            include::scene_tokens(filename, &file_content, include_dirs)
+        })
+        .collect::<Result<_, _>>()?;
 
-    let mut tokens = file_content
-        .split(&[' ', '\t', '\n'])
-        .filter(|token| !token.is_empty());
+    let mut materials = MaterialLibrary::new(plugins);
+    let mut settings = RenderSettings::new();
+    for file in &files {
+        let mut tokens = file.iter();
+        let mut depth: usize = 0;
+        let mut names = HashSet::new();
+        while let Some(token) = tokens.next() {
+            match token {
+                "{" => depth += 1,
+                "}" => depth = depth.saturating_sub(1),
+                "materials" if depth == 0 => {
+                    if let Err(cause) =
+                        material::parse_material_library(&mut tokens, &mut materials, &mut names)
+                    {
+                        return Err(tokens.locate(ParsingError::SceneParsingError(Box::new(cause))));
+                    }
+                }
+                "settings" if depth == 0 => {
+                    if let Err(cause) = settings::parse_settings(&mut tokens, &mut settings) {
+                        return Err(tokens.locate(ParsingError::SceneParsingError(Box::new(cause))));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
 
-    let mut geometries: Vec<Box<dyn Renderable<T, RGB<T::ValueType>>>> = Vec::new();
-    let mut lights: Vec<Box<dyn Light<T, RGB<<T as Length>::ValueType>>>> = Vec::new();
-    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<T>>> = HashMap::new();
-    let mut background_color: RGB<<T as Length>::ValueType> =
-        RGB::new(Zero::zero(), Zero::zero(), Zero::zero());
+    let mut scene = Scene3::new(
+        RGB::new(Zero::zero(), Zero::zero(), Zero::zero()),
+        Vec::new(),
+        HashMap::new(),
+        Vec::new(),
+    );
+    // Cameras that focus on an object are focused once all geometries are known. Errors are
+    // located at the camera.
+    let mut auto_focus: Vec<(String, String, Option<Location>)> = Vec::new();
+    for file in &files {
+        let mut tokens = file.iter();
+        if let Err(cause) = parse_elements(
+            &mut tokens,
+            &materials,
+            plugins,
+            &mut scene,
+            &mut auto_focus,
+        ) {
+            return Err(tokens.locate(cause));
+        }
+    }
+    for (id, name, location) in auto_focus {
+        if let Err(cause) = focus_on(&mut scene, &id, &name) {
+            return Err(locate(
+                ParsingError::SceneParsingError(Box::new(
+                    ParsingError::PerspectiveCameraParsingError(Box::new(cause)),
+                )),
+                location,
+            ));
+        }
+    }
 
+    Ok((scene, settings))
+}
+
+fn parse_elements<T: Length + SignedNumber<T::ValueType> + ConvenientNumber + 'static>(
+    tokens: &mut TrackedTokens<'_>,
+    materials: &MaterialLibrary<T>,
+    plugins: &PluginRegistry<T>,
+    scene: &mut SceneType<T>,
+    auto_focus: &mut Vec<(String, String, Option<Location>)>,
+) -> Result<(), ParsingError>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <<T as Length>::ValueType as FromStr>::Err: Error,
+    <T as Length>::AreaType: Sqrt<Output = T>
+        + SelfMulNumber<T::ValueType>
+        + SignedNumber<T::ValueType>
+        + ConvenientNumber,
+    <T as Length>::SecondMomentOfAreaType:
+        Number<T::ValueType> + Sqrt<Output = <T as Length>::AreaType> + ConvenientNumber,
+    <T as FromStr>::Err: Error,
+    Normal3<<T as Length>::ValueType>: Orthonormal3,
+    Radians<<T as Div>::Output>:
+        Angle + Cos<Output = <T as Div>::Output> + Sin<Output = <T as Div>::Output>,
+    SamplingPattern<Point2<T::ValueType>>: PatternMapping<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+    <T as Length>::ValueType: From<f32> + Exp<Output = <T as Length>::ValueType>,
+    u16: Into<<T as Length>::ValueType>,
+{
     while let Some(token) = tokens.next() {
+        // Where the element starts, to locate errors that are found after all elements are parsed.
+        let element = tokens.taken() - 1;
         match token {
-            "sphere" => match RenderableSphere::<T>::from_tokens(&mut tokens) {
+            "sphere" => match RenderableSphere::<T>::from_tokens(tokens, materials) {
                 Ok(sphere) => {
-                    geometries.push(Box::new(sphere));
+                    scene.geometries.push(Box::new(sphere));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            "cylinder" => match RenderableCylinder::<T>::from_tokens(&mut tokens) {
+            "cylinder" => match RenderableCylinder::<T>::from_tokens(tokens, materials) {
                 Ok(cylinder) => {
-                    geometries.push(Box::new(cylinder));
+                    scene.geometries.push(Box::new(cylinder));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            "disc" => match RenderableDisc::<T>::from_tokens(&mut tokens) {
+            "disc" => match RenderableDisc::<T>::from_tokens(tokens, materials) {
                 Ok(disc) => {
-                    geometries.push(Box::new(disc));
+                    scene.geometries.push(Box::new(disc));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
 
-            "plane" => match RenderablePlane::<T>::from_tokens(&mut tokens) {
+            "plane" => match RenderablePlane::<T>::from_tokens(tokens, materials) {
                 Ok(plane) => {
-                    geometries.push(Box::new(plane));
+                    scene.geometries.push(Box::new(plane));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            "box" => match RenderableAxisAlignedBox::<T>::from_tokens(&mut tokens) {
+            "box" => match RenderableAxisAlignedBox::<T>::from_tokens(tokens, materials) {
                 Ok(aab) => {
-                    geometries.push(Box::new(aab));
+                    scene.geometries.push(Box::new(aab));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            "triangle" => match RenderableTriangle::<T>::from_tokens(&mut tokens) {
+            "triangle" => match RenderableTriangle::<T>::from_tokens(tokens, materials) {
                 Ok(triangle) => {
-                    geometries.push(Box::new(triangle));
+                    scene.geometries.push(Box::new(triangle));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "mesh" => match RenderableTriangleMesh::<T>::from_tokens(tokens, materials) {
+                Ok(mesh) => {
+                    // Glowing meshes light the scene as well.
+                    if let Some(color) = mesh.material.emission() {
+                        let mut mesh_light = MeshLight::new(color, mesh.mesh.clone());
+                        if let Some(shadow_bias) = mesh.shadow_bias {
+                            mesh_light = mesh_light.with_shadow_bias(shadow_bias);
+                        }
+                        scene.lights.push(Box::new(mesh_light));
+                    }
+                    scene.geometries.push(Box::new(mesh));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            "pinhole_camera" => match <(String, PinholeCamera<T>)>::from_tokens(&mut tokens) {
+            // The materials and settings of all files have been collected before.
+            "materials" | "settings" => {
+                if let Err(cause) = util::skip_block(tokens) {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            }
+            "pinhole_camera" => match <(String, PinholeCamera<T>)>::from_tokens(tokens) {
                 Ok((id, camera)) => {
-                    cameras.insert(id, Box::new(camera));
+                    scene.cameras.insert(id, Box::new(camera));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
             "perspective_camera" => {
-                match <(String, PerspectiveCamera<T>)>::from_tokens(&mut tokens) {
-                    Ok((id, camera)) => {
-                        cameras.insert(id, Box::new(camera));
+                match <(String, PerspectiveCamera<T>, Option<String>)>::from_tokens(tokens) {
+                    Ok((id, camera, focus_on)) => {
+                        if let Some(name) = focus_on {
+                            auto_focus.push((id.clone(), name, tokens.location(element)));
+                        }
+                        scene.cameras.insert(id, Box::new(camera));
                     }
                     Err(cause) => {
                         return Err(ParsingError::SceneParsingError(Box::new(cause)));
                     }
                 }
             }
-            "orthographic_camera" => {
-                match <(String, OrthographicCamera<T>)>::from_tokens(&mut tokens) {
-                    Ok((id, camera)) => {
-                        cameras.insert(id, Box::new(camera));
-                    }
-                    Err(cause) => {
-                        return Err(ParsingError::SceneParsingError(Box::new(cause)));
-                    }
+            "physical_camera" => match <(String, PhysicalCamera<T>)>::from_tokens(tokens) {
+                Ok((id, camera)) => {
+                    scene.cameras.insert(id, Box::new(camera));
                 }
-            }
-            "fisheye_camera" => match <(String, FisheyeCamera<T>)>::from_tokens(&mut tokens) {
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "orthographic_camera" => match <(String, OrthographicCamera<T>)>::from_tokens(tokens) {
+                Ok((id, camera)) => {
+                    scene.cameras.insert(id, Box::new(camera));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "fisheye_camera" => match <(String, FisheyeCamera<T>)>::from_tokens(tokens) {
                 Ok((id, camera)) => {
-                    cameras.insert(id, Box::new(camera));
+                    scene.cameras.insert(id, Box::new(camera));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            "spherical_camera" => match <(String, SphericalCamera<T>)>::from_tokens(&mut tokens) {
+            "spherical_camera" => match <(String, SphericalCamera<T>)>::from_tokens(tokens) {
                 Ok((id, camera)) => {
-                    cameras.insert(id, Box::new(camera));
+                    scene.cameras.insert(id, Box::new(camera));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            "point_light" => match PointLight::from_tokens(&mut tokens) {
+            "cylindrical_camera" => match <(String, CylindricalCamera<T>)>::from_tokens(tokens) {
+                Ok((id, camera)) => {
+                    scene.cameras.insert(id, Box::new(camera));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "ods_camera" => match <(String, OdsCamera<T>)>::from_tokens(tokens) {
+                Ok((id, camera)) => {
+                    scene.cameras.insert(id, Box::new(camera));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            // The eyes are added as two cameras, e.g. main.left and main.right.
+            "stereo_camera" => match <(String, [StereoCamera<T>; 2])>::from_tokens(tokens) {
+                Ok((id, [left, right])) => {
+                    scene.cameras.insert(format!("{}.left", id), Box::new(left));
+                    scene
+                        .cameras
+                        .insert(format!("{}.right", id), Box::new(right));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            // Moves the camera with the id along the path, or both eyes of a stereo camera.
+            "camera_path" => match <(String, CameraPath<T>)>::from_tokens(tokens) {
+                Ok((id, path)) => {
+                    let ids = camera_ids(scene, id);
+                    if ids.is_empty() {
+                        return Err(ParsingError::SceneParsingError(Box::new(
+                            ParsingError::CameraPathParsingError(Box::new(
+                                ParsingError::MissingElement("camera"),
+                            )),
+                        )));
+                    }
+                    for id in ids {
+                        let camera = scene.cameras.remove(&id).unwrap();
+                        scene
+                            .cameras
+                            .insert(id, Box::new(AnimatedCamera::new(camera, path.clone())));
+                    }
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "focus_pull" => match <(String, FocusPull<T>)>::from_tokens(tokens) {
+                Ok((id, pull)) => {
+                    let ids = camera_ids(scene, id);
+                    if ids.is_empty() {
+                        return Err(ParsingError::SceneParsingError(Box::new(
+                            ParsingError::FocusPullParsingError(Box::new(
+                                ParsingError::MissingElement("camera"),
+                            )),
+                        )));
+                    }
+                    // The objects are looked up by their names for every frame, so they have to
+                    // be declared before the focus pull, like the camera.
+                    for keyframe in pull.keyframes() {
+                        if let FocusTarget::Object(name) = &keyframe.target {
+                            if !scene
+                                .geometries
+                                .iter()
+                                .any(|geometry| geometry.name() == Some(name))
+                            {
+                                return Err(ParsingError::SceneParsingError(Box::new(
+                                    ParsingError::FocusPullParsingError(Box::new(
+                                        ParsingError::UnknownObject(name.clone()),
+                                    )),
+                                )));
+                            }
+                        }
+                    }
+                    for id in ids {
+                        let camera = scene.cameras.remove(&id).unwrap();
+                        scene
+                            .cameras
+                            .insert(id, Box::new(FocusPulledCamera::new(camera, pull.clone())));
+                    }
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "point_light" => match PointLight::from_tokens(tokens) {
                 Ok(point_light) => {
-                    lights.push(Box::new(point_light));
+                    scene.lights.push(Box::new(point_light));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            "ambient_occlusion_light" => match AmbientOcclusionLight::from_tokens(&mut tokens) {
+            "ambient_occlusion_light" => match AmbientOcclusionLight::from_tokens(tokens) {
                 Ok(ambient_occlusion_light) => {
-                    lights.push(Box::new(ambient_occlusion_light));
+                    scene.lights.push(Box::new(ambient_occlusion_light));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            "spot_light" => match SpotLight::from_tokens(&mut tokens) {
+            "spot_light" => match SpotLight::from_tokens(tokens) {
                 Ok(spot_light) => {
-                    lights.push(Box::new(spot_light));
+                    scene.lights.push(Box::new(spot_light));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "area_light" => match AreaLight::from_tokens(tokens) {
+                Ok(area_light) => {
+                    scene.lights.push(Box::new(area_light));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "sphere_light" => match SphereLight::from_tokens(tokens) {
+                Ok(sphere_light) => {
+                    scene.lights.push(Box::new(sphere_light));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "environment_light" => match EnvironmentLight::from_tokens(tokens) {
+                Ok(environment_light) => {
+                    scene.lights.push(Box::new(environment_light));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "ambient_environment_light" => match EnvironmentLight::<T>::from_tokens(tokens) {
+                Ok(environment_light) => {
+                    scene
+                        .lights
+                        .push(Box::new(environment_light.spherical_harmonics()));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "sky" => match PreethamSky::from_tokens(tokens) {
+                Ok(sky) => {
+                    scene
+                        .lights
+                        .push(Box::new(sky.environment_light::<T>(SKY_RESOLUTION)));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            "background_color:" => match RGB::from_tokens(&mut tokens) {
+            "volume" => match VolumeRegion::from_tokens(tokens) {
+                Ok(volume) => {
+                    scene.volumes.push(volume);
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "background_color:" => match RGB::from_tokens(tokens) {
                 Ok(bg) => {
-                    background_color = bg;
+                    scene.bg_color = bg;
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            "ambient_light:" => match RGB::from_tokens(&mut tokens) {
+            "background:" => match Background::from_tokens(tokens) {
+                Ok(bg) => {
+                    scene.background = Some(bg);
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                }
+            },
+            "ambient_light:" => match RGB::from_tokens(tokens) {
                 Ok(ambient) => {
-                    lights.push(Box::new(AmbientLight::new(ambient)));
+                    scene.lights.push(Box::new(AmbientLight::new(ambient)));
                 }
                 Err(cause) => {
                     return Err(ParsingError::SceneParsingError(Box::new(cause)));
                 }
             },
-            &_ => {
-                return Err(ParsingError::UnsupportedElement(token.to_string()));
+            name => {
+                if let Some(construct) = plugins.geometry(name) {
+                    match construct(tokens, materials) {
+                        Ok(geometry) => {
+                            scene.geometries.push(geometry);
+                        }
+                        Err(cause) => {
+                            return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                        }
+                    }
+                } else if let Some(construct) = plugins.light(name) {
+                    match construct(tokens) {
+                        Ok(light) => {
+                            scene.lights.push(light);
+                        }
+                        Err(cause) => {
+                            return Err(ParsingError::SceneParsingError(Box::new(cause)));
+                        }
+                    }
+                } else {
+                    return Err(ParsingError::UnsupportedElement(name.to_string()));
+                }
             }
         }
     }
 
-    Ok(Scene3::new(background_color, lights, cameras, geometries))
+    Ok(())
+}
+
+// Focuses a camera on the point where its central ray hits an object. If the ray misses the
+// object, the camera focuses on the origin of the object instead.
+fn focus_on<T: Length>(scene: &mut SceneType<T>, id: &str, name: &str) -> Result<(), ParsingError>
+where
+    <T as Length>::ValueType: FloatingPoint,
+{
+    let Some(geometry) = scene
+        .geometries
+        .iter()
+        .find(|geometry| geometry.name() == Some(name))
+    else {
+        return Err(ParsingError::UnknownObject(name.to_string()));
+    };
+    // A later scene file may have replaced the camera.
+    let Some(camera) = scene.cameras.get_mut(id) else {
+        return Ok(());
+    };
+    let Some(ray) = camera.central_ray() else {
+        return Ok(());
+    };
+
+    let hit = geometry
+        .intersect(ray)
+        .into_iter()
+        .filter(|(t, _, _)| *t > Zero::zero())
+        .min_by(|(a, _, _), (b, _, _)| a.total_cmp(b))
+        .map(|(_, sp, _)| sp.p);
+    let focus_distance = hit
+        .or_else(|| geometry.position_at(Zero::zero()))
+        .and_then(|p| camera.focus_distance_to(p));
+    if let Some(focus_distance) = focus_distance {
+        camera.set_focus_distance(focus_distance);
+    }
+    Ok(())
+}
+
+// Whether a scene is written in TOML, by the extension of its file.
+pub fn is_toml(filename: &str) -> bool {
+    Path::new(filename)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("toml"))
+}
+
+// The cameras an element refers to by an id: the camera with the id or both eyes of a stereo
+// camera.
+fn camera_ids<T: Length>(scene: &SceneType<T>, id: String) -> Vec<String> {
+    if scene.cameras.contains_key(&id) {
+        return vec![id];
+    }
+    [format!("{}.left", id), format!("{}.right", id)]
+        .into_iter()
+        .filter(|eye| scene.cameras.contains_key(eye))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    use math::geometry::ParametricLine;
+    use math::{Point3, Vector3};
+    use sampling::{RegularPatternGenerator, SamplingPatternSet};
+    use units::length::Meter;
+
+    macro_rules! reject_non_finite_numbers {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let material = "material: lambert_material { texture: single_color_texture { color: 1 1 1 } }";
+                // Each element is cut off after the invalid number, parsing has to fail there.
+                let elements = [
+                    "background_color: 0 NaN 0".to_string(),
+                    "ambient_light: inf 0 0".to_string(),
+                    "background: vertical_gradient { top: 0 0 -inf".to_string(),
+                    format!("sphere {{ {} position: 0 1e400 0", material),
+                    format!("cylinder {{ {} scale: NaN 1 1", material),
+                    format!("disc {{ {} rotation: 0 0 nan", material),
+                    format!("plane {{ {} shadow_bias: inf", material),
+                    format!("box {{ {} position: Infinity 0 0", material),
+                    format!("triangle {{ {} na: 0 NaN 1", material),
+                    format!("triangle {{ {} uva: NaN 0", material),
+                    format!("mesh {{ {} vertices: 1 0 0 NaN", material),
+                    format!("sphere {{ {} attributes: {{ tint: 1 NaN 1", material),
+                    format!("disc {{ {} attributes: {{ emission_strength: inf", material),
+                    "sphere { material: emissive_material { color: NaN 1 1".to_string(),
+                    "sphere { material: unshaded_material { texture: checkerboard_texture { a: inf 1 1".to_string(),
+                    "sphere { material: phong_material { exponent: NaN".to_string(),
+                    "sphere { material: plastic_material { diffuse_texture: grid_texture { width: inf".to_string(),
+                    "sphere { material: metal_material { roughness: NaN".to_string(),
+                    "sphere { material: dielectric_material { index_of_refraction: NaN".to_string(),
+                    "sphere { material: reflective_material { reflectance: NaN 0 0".to_string(),
+                    "sphere { material: lambert_material { texture: noise_texture { frequency: inf".to_string(),
+                    "pinhole_camera { field_of_view: NaN".to_string(),
+                    "pinhole_camera { radial_distortion: -0.1 NaN".to_string(),
+                    "perspective_camera { lens_radius: inf".to_string(),
+                    "physical_camera { f_number: NaN".to_string(),
+                    "orthographic_camera { scale: NaN".to_string(),
+                    "fisheye_camera { psi: -inf".to_string(),
+                    "spherical_camera { eye_position: NaN 0 0".to_string(),
+                    "cylindrical_camera { vertical_field_of_view: NaN".to_string(),
+                    "ods_camera { interocular_distance: NaN".to_string(),
+                    "camera_path { keyframe: { time: NaN".to_string(),
+                    "focus_pull { keyframe: { focus_distance: NaN".to_string(),
+                    "point_light { radius: NaN".to_string(),
+                    "point_light { intensity: inf lm".to_string(),
+                    "spot_light { angle: NaN".to_string(),
+                    "area_light { corner: 0 inf 0".to_string(),
+                    "sphere_light { radius: inf".to_string(),
+                    "environment_light { intensity: NaN".to_string(),
+                    "sky { turbidity: inf".to_string(),
+                    "ambient_occlusion_light { distance: NaN".to_string(),
+                ];
+
+                let filename = env::temp_dir().join(concat!(stringify!($name), ".scene"));
+                for element in elements {
+                    fs::write(&filename, &element).unwrap();
+                    let error = parse_scene::<Meter<$type>>(filename.to_str().unwrap())
+                        .err()
+                        .unwrap();
+                    assert!(
+                        format!("{:?}", error).contains("NonFiniteNumber"),
+                        "{} was accepted: {:?}",
+                        element,
+                        error
+                    );
+                }
+                fs::remove_file(filename).unwrap();
+            }
+        };
+    }
+
+    reject_non_finite_numbers! { f32, reject_non_finite_numbers_f32 }
+    reject_non_finite_numbers! { f64, reject_non_finite_numbers_f64 }
+
+    #[test]
+    fn reject_numbers_too_large_for_f32() {
+        let filename = env::temp_dir().join("reject_numbers_too_large_for_f32.scene");
+        fs::write(&filename, "ambient_light: 1e39 0 0").unwrap();
+
+        let error = parse_scene::<Meter<f32>>(filename.to_str().unwrap())
+            .err()
+            .unwrap();
+        assert!(format!("{:?}", error).contains("NonFiniteNumber(\"1e39\")"));
+        assert!(parse_scene::<Meter<f64>>(filename.to_str().unwrap()).is_ok());
+        fs::remove_file(filename).unwrap();
+    }
+
+    macro_rules! merge_scene_files {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let directory = env::temp_dir();
+                let scene = directory.join(concat!(stringify!($name), "_scene.scene"));
+                let rig = directory.join(concat!(stringify!($name), "_rig.scene"));
+                fs::write(
+                    &scene,
+                    "background_color: 0.1 0.1 0.1\n\
+                    materials { red: unshaded_material { texture: single_color_texture { color: 1 0 0 } } }\n\
+                    sphere { material: red }\n\
+                    point_light { position: 0 5 0 }\n\
+                    pinhole_camera { id: main eye_position: 0 0 5 }\n",
+                )
+                .unwrap();
+                fs::write(
+                    &rig,
+                    "materials { red: unshaded_material { texture: single_color_texture { color: 0 0 1 } } }\n\
+                    point_light { position: 5 5 0 }\n\
+                    pinhole_camera { id: main eye_position: 0 0 10 }\n\
+                    pinhole_camera { id: side eye_position: 10 0 0 }\n",
+                )
+                .unwrap();
+
+                let filenames = [scene.to_str().unwrap(), rig.to_str().unwrap()];
+                let merged = parse_scenes_with_include_dirs::<Meter<$type>>(
+                    &filenames,
+                    &PluginRegistry::new(),
+                    &[],
+                )
+                .unwrap();
+
+                assert_eq!(merged.geometries.len(), 1);
+                assert_eq!(merged.lights.len(), 2);
+                assert_eq!(merged.cameras.len(), 2);
+                assert_eq!(merged.bg_color, RGB::new(0.1, 0.1, 0.1));
+
+                let ray = ParametricLine::new(
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(5.0)),
+                    Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                );
+                let color = |scene: &SceneType<Meter<$type>>| {
+                    let (_, sp, material) = scene.geometries[0].intersect(ray)[0];
+                    material.color_for(sp, ray.direction, vec![])
+                };
+                assert_eq!(color(&merged), RGB::new(0.0, 0.0, 1.0));
+
+                let single = parse_scene::<Meter<$type>>(filenames[0]).unwrap();
+                assert_eq!(color(&single), RGB::new(1.0, 0.0, 0.0));
+                assert_eq!(single.cameras.len(), 1);
+
+                fs::remove_file(scene).unwrap();
+                fs::remove_file(rig).unwrap();
+            }
+        };
+    }
+
+    merge_scene_files! { f32, merge_scene_files_f32 }
+    merge_scene_files! { f64, merge_scene_files_f64 }
+
+    macro_rules! material_library {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let filename = env::temp_dir().join(concat!(stringify!($name), ".scene"));
+                fs::write(
+                    &filename,
+                    "materials {\n\
+                        red: unshaded_material { texture: single_color_texture { color: 1 0 0 } }\n\
+                        blue: unshaded_material { texture: single_color_texture { color: 0 0 1 } }\n\
+                    }\n\
+                    sphere { material: blue }\n",
+                )
+                .unwrap();
+                let scene = parse_scene::<Meter<$type>>(filename.to_str().unwrap()).unwrap();
+
+                let ray = ParametricLine::new(
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(5.0)),
+                    Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                );
+                let (_, sp, material) = scene.geometries[0].intersect(ray)[0];
+                assert_eq!(material.color_for(sp, ray.direction, vec![]), RGB::new(0.0, 0.0, 1.0));
+
+                // A name must not be defined twice.
+                fs::write(
+                    &filename,
+                    "materials {\n\
+                        blue: unshaded_material { texture: single_color_texture { color: 0 0 1 } }\n\
+                        blue: unshaded_material { texture: single_color_texture { color: 1 0 0 } }\n\
+                    }\n\
+                    sphere { material: blue }\n",
+                )
+                .unwrap();
+                let error = parse_scene::<Meter<$type>>(filename.to_str().unwrap())
+                    .err()
+                    .unwrap();
+                assert!(format!("{:?}", error).contains("DuplicateMaterial(\"blue\")"));
+
+                fs::remove_file(filename).unwrap();
+            }
+        };
+    }
+
+    material_library! { f32, material_library_f32 }
+    material_library! { f64, material_library_f64 }
+
+    macro_rules! override_instance_attributes {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let filename = env::temp_dir().join(concat!(stringify!($name), ".scene"));
+                fs::write(
+                    &filename,
+                    "materials {\n\
+                        white: unshaded_material { texture: single_color_texture { color: 1 1 1 } }\n\
+                        glow: emissive_material { color: 0.5 0.5 0.5 }\n\
+                    }\n\
+                    sphere { material: white }\n\
+                    sphere { material: white attributes: { tint: 1 0.5 0 } }\n\
+                    mesh {\n\
+                        vertices: 3 -1 -1 -2 1 -1 -2 0 1 -2\n\
+                        faces: 1 0 1 2\n\
+                        material: glow\n\
+                        attributes: { emission_strength: 4 }\n\
+                    }\n",
+                )
+                .unwrap();
+                let scene = parse_scene::<Meter<$type>>(filename.to_str().unwrap()).unwrap();
+
+                let ray = ParametricLine::new(
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(5.0)),
+                    Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                );
+                let material = |index: usize| scene.geometries[index].intersect(ray)[0];
+
+                // Both spheres share the material, only the second one is tinted.
+                let (_, sp, white) = material(0);
+                assert_eq!(white.color_for(sp, ray.direction, vec![]), RGB::new(1.0, 1.0, 1.0));
+                let (_, sp, tinted) = material(1);
+                assert_eq!(tinted.color_for(sp, ray.direction, vec![]), RGB::new(1.0, 0.5, 0.0));
+
+                // The glowing mesh is registered as a light with the stronger emission.
+                let (_, _, glow) = material(2);
+                assert_eq!(glow.emission(), Some(RGB::new(2.0, 2.0, 2.0)));
+                assert_eq!(scene.lights.len(), 1);
+
+                fs::remove_file(filename).unwrap();
+            }
+        };
+    }
+
+    override_instance_attributes! { f32, override_instance_attributes_f32 }
+    override_instance_attributes! { f64, override_instance_attributes_f64 }
+
+    macro_rules! focus_on_named_object {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let filename = env::temp_dir().join(concat!(stringify!($name), ".scene"));
+                let element = "perspective_camera { id: main field_of_view: 90 lens_radius: 0.5 focus_on: ball }";
+                fs::write(
+                    &filename,
+                    format!(
+                        "{} sphere {{ name: ball position: 0 0 -5 material: emissive_material {{ color: 1 1 1 }} }}",
+                        element
+                    ),
+                )
+                .unwrap();
+                let scene = parse_scene::<Meter<$type>>(filename.to_str().unwrap()).unwrap();
+
+                // The rays through the center of the image meet on the front of the sphere.
+                let camera = &scene.cameras["main"];
+                let size = Vector2::new(640.0, 480.0);
+                let patterns = SamplingPatternSet::<Point2<$type>>::regular_pattern(4, 4);
+                let mut rnd = WichmannHillPRNG::from_seed(0);
+                for _ in 0..8 {
+                    let ray = camera
+                        .ray_for(size, Point2::new(320.0, 240.0), &patterns[0], &mut rnd)
+                        .unwrap();
+                    let p = ray.origin + ray.direction * (4.0 / -(ray.direction.z / Meter::new(1.0)));
+                    assert!((p.x / Meter::new(1.0)).abs() < 0.0001);
+                    assert!((p.y / Meter::new(1.0)).abs() < 0.0001);
+                }
+
+                fs::write(&filename, element).unwrap();
+                let error = parse_scene::<Meter<$type>>(filename.to_str().unwrap())
+                    .err()
+                    .unwrap();
+                assert!(format!("{:?}", error).contains("UnknownObject(\"ball\")"));
+
+                fs::remove_file(filename).unwrap();
+            }
+        };
+    }
+
+    focus_on_named_object! { f32, focus_on_named_object_f32 }
+    focus_on_named_object! { f64, focus_on_named_object_f64 }
+
+    macro_rules! environment_background {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let filename = env::temp_dir().join(concat!(stringify!($name), ".scene"));
+                let image = concat!(env!("CARGO_MANIFEST_DIR"), "/example-environment.hdr");
+                fs::write(
+                    &filename,
+                    format!("background: environment {{ image: {} }}", image),
+                )
+                .unwrap();
+                let scene = parse_scene::<Meter<$type>>(filename.to_str().unwrap()).unwrap();
+
+                let background = scene.background.unwrap();
+                let up = Vector3::new(0.0, 1.0, 0.0);
+                assert!(background.environment(up).is_some());
+
+                fs::write(&filename, "background: environment { }").unwrap();
+                let error = parse_scene::<Meter<$type>>(filename.to_str().unwrap())
+                    .err()
+                    .unwrap();
+                assert!(format!("{:?}", error).contains("MissingElement(\"image\")"));
+
+                fs::remove_file(filename).unwrap();
+            }
+        };
+    }
+
+    environment_background! { f32, environment_background_f32 }
+    environment_background! { f64, environment_background_f64 }
+
+    macro_rules! volume_region {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let filename = env::temp_dir().join(concat!(stringify!($name), ".scene"));
+                fs::write(
+                    &filename,
+                    "volume { a: 1 2 3 b: -1 0 1 density: 0.5 anisotropy: 0.2 noise: simplex }",
+                )
+                .unwrap();
+                let scene = parse_scene::<Meter<$type>>(filename.to_str().unwrap()).unwrap();
+
+                assert_eq!(scene.volumes.len(), 1);
+                let volume = &scene.volumes[0];
+                assert_eq!(volume.min, Point3::new(-1.0, 0.0, 1.0));
+                assert_eq!(volume.max, Point3::new(1.0, 2.0, 3.0));
+                assert_eq!(volume.density, 0.5);
+                assert_eq!(volume.anisotropy, 0.2);
+                assert!(volume.noise.is_some());
+
+                fs::write(&filename, "volume { a: 1 2 3 b: -1 0 1 }").unwrap();
+                let error = parse_scene::<Meter<$type>>(filename.to_str().unwrap())
+                    .err()
+                    .unwrap();
+                assert!(format!("{:?}", error).contains("MissingElement(\"density\")"));
+
+                fs::remove_file(filename).unwrap();
+            }
+        };
+    }
+
+    volume_region! { f32, volume_region_f32 }
+    volume_region! { f64, volume_region_f64 }
+
+    macro_rules! locate_errors {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let location = |content: &str| {
+                    let filename = env::temp_dir().join(concat!(stringify!($name), ".scene"));
+                    fs::write(&filename, content).unwrap();
+                    let error = parse_scene::<Meter<$type>>(filename.to_str().unwrap())
+                        .err()
+                        .unwrap();
+                    fs::remove_file(&filename).unwrap();
+                    match error {
+                        ParsingError::LocatedParsingError(location, _) => location,
+                        error => panic!("The error {:?} is not located.", error),
+                    }
+                };
+
+                let error = location("// A sphere.\nsphere {\n    position: 0 one 0\n}\n");
+                assert_eq!((error.line, error.column), (3, 17));
+                assert_eq!(error.token, "one");
+                assert_eq!(error.snippet, "    position: 0 one 0");
+
+                let error = location("sphere { radius: 1 }");
+                assert_eq!((error.line, error.column), (1, 10));
+
+                let error = location("materials { red: shiny_material { } }");
+                assert_eq!((error.line, error.column), (1, 18));
+
+                let error = location(
+                    "materials {\n\
+                         red: emissive_material { color: 1 0 0 }\n\
+                         red: emissive_material { color: 0 0 1 }\n\
+                     }",
+                );
+                assert_eq!((error.line, error.column), (3, 1));
+                assert_eq!(error.token, "red:");
+
+                let error = location("ambient_light: 1 1 1\nbackground_color: 0 0");
+                assert_eq!((error.line, error.column), (2, 21));
+
+                // The object to focus on is looked up after all elements are parsed.
+                let error = location(
+                    "point_light { position: 0 5 0 }\n\
+                     perspective_camera { id: main focus_on: ball }",
+                );
+                assert_eq!((error.line, error.column), (2, 1));
+                assert_eq!(error.token, "perspective_camera");
+            }
+        };
+    }
+
+    locate_errors! { f32, locate_errors_f32 }
+    locate_errors! { f64, locate_errors_f64 }
 }
@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::parser::include::{included_name, scene_tokens, INCLUDE};
+use crate::parser::tokenizer::Tokenizer;
+
+// Keys whose value names a file, e.g. the image of an environment light or the profile of a spot
+// light.
+const ASSET_KEYS: [&str; 2] = ["image:", "profile:"];
+
+// The directory assets are copied to when packing, if they can not keep the name they have in the
+// scene.
+const PACKED_ASSETS: &str = "assets";
+
+// Finds the files a scene refers to. Relative names are looked up next to the scene first, then in
+// the include directories in the given order and at last relative to the working directory.
+pub struct AssetResolver {
+    search_paths: Vec<PathBuf>,
+}
+
+impl AssetResolver {
+    pub fn new(scene: &str, include_dirs: &[PathBuf]) -> AssetResolver {
+        let mut search_paths = Vec::with_capacity(include_dirs.len() + 1);
+        if let Some(directory) = Path::new(scene).parent() {
+            search_paths.push(directory.to_path_buf());
+        }
+        search_paths.extend(include_dirs.iter().cloned());
+
+        AssetResolver { search_paths }
+    }
+
+    pub fn resolve(&self, name: &str) -> Option<PathBuf> {
+        let path = Path::new(name);
+        if path.is_absolute() {
+            return if path.is_file() {
+                Some(path.to_path_buf())
+            } else {
+                None
+            };
+        }
+
+        self.search_paths
+            .iter()
+            .map(|directory| directory.join(path))
+            .chain([path.to_path_buf()])
+            .find(|candidate| candidate.is_file())
+    }
+
+    // The tokens of a scene with the names of all assets that can be found replaced by their
+    // paths. Names that can not be found are kept, so loading them reports the name of the scene.
+    pub fn resolve_tokens<'a>(&self, tokens: impl Iterator<Item = &'a str>) -> Vec<String> {
+        let mut resolved = Vec::new();
+        let mut previous = "";
+        for token in tokens {
+            let path = if ASSET_KEYS.contains(&previous) {
+                self.resolve(token)
+            } else {
+                None
+            };
+            match path {
+                Some(path) => resolved.push(path.to_string_lossy().into_owned()),
+                None => resolved.push(token.to_string()),
+            }
+            previous = token;
+        }
+        resolved
+    }
+}
+
+// The content hashes of a scene and the assets it refers to. Comparing them with the hashes of an
+// earlier load tells which files have to be loaded again, independent of modification times.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AssetChecksums {
+    checksums: HashMap<PathBuf, u64>,
+}
+
+impl AssetChecksums {
+    pub fn new() -> AssetChecksums {
+        AssetChecksums {
+            checksums: HashMap::new(),
+        }
+    }
+
+    // Hashes the scene, the files it includes and all of their assets that can be found.
+    pub fn of_scene(scene: &str, include_dirs: &[PathBuf]) -> io::Result<AssetChecksums> {
+        let mut checksums = AssetChecksums::new();
+        checksums.insert_scene(Path::new(scene), include_dirs, &mut Vec::new())?;
+        Ok(checksums)
+    }
+
+    // Every file is hashed once, even if it is included several times or in a cycle.
+    fn insert_scene(
+        &mut self,
+        scene: &Path,
+        include_dirs: &[PathBuf],
+        visited: &mut Vec<PathBuf>,
+    ) -> io::Result<()> {
+        let canonical = fs::canonicalize(scene)?;
+        if visited.contains(&canonical) {
+            return Ok(());
+        }
+        visited.push(canonical);
+
+        let content = fs::read_to_string(scene)?;
+        let resolver = AssetResolver::new(&scene.to_string_lossy(), include_dirs);
+        self.insert(scene.to_path_buf(), content.as_bytes());
+        for path in asset_names(&content)
+            .into_iter()
+            .filter_map(|name| resolver.resolve(name))
+        {
+            let data = fs::read(&path)?;
+            self.insert(path, &data);
+        }
+        for path in included_names(&content)
+            .into_iter()
+            .filter_map(|name| resolver.resolve(name))
+        {
+            self.insert_scene(&path, include_dirs, visited)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn insert(&mut self, path: PathBuf, data: &[u8]) {
+        self.checksums.insert(path, checksum(data));
+    }
+
+    pub fn get(&self, path: &Path) -> Option<u64> {
+        self.checksums.get(path).copied()
+    }
+
+    // The files whose content differs from an earlier load, including files that were added or
+    // are gone, sorted by path.
+    pub fn changed(&self, previous: &AssetChecksums) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = self
+            .checksums
+            .iter()
+            .filter(|(path, checksum)| previous.checksums.get(*path) != Some(*checksum))
+            .map(|(path, _)| path.clone())
+            .chain(
+                previous
+                    .checksums
+                    .keys()
+                    .filter(|path| !self.checksums.contains_key(*path))
+                    .cloned(),
+            )
+            .collect();
+        changed.sort();
+        changed
+    }
+}
+
+// The 64 bit FNV-1a hash of the data. It is not cryptographically secure, but fast and good
+// enough to notice that a file was edited.
+pub fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// The names of all assets a scene refers to, in the order they appear.
+pub fn asset_names(content: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut previous = "";
+    for token in Tokenizer::new(content).map(|token| token.text) {
+        if ASSET_KEYS.contains(&previous) && !names.contains(&token) {
+            names.push(token);
+        }
+        previous = token;
+    }
+    names
+}
+
+// The names of all files a scene includes, in the order they appear.
+pub fn included_names(content: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut previous = "";
+    for token in Tokenizer::new(content).map(|token| token.text) {
+        if previous == INCLUDE && !names.contains(&included_name(token)) {
+            names.push(included_name(token));
+        }
+        previous = token;
+    }
+    names
+}
+
+// Copies a scene and all assets it refers to into a directory, so it can be moved to another
+// machine. Included files are spliced into the packed scene and TOML scenes are translated, so the
+// packed scene is a single scene file, without the comments. Assets in the directory of the scene
+// keep their path relative to it, all others are copied to the assets directory. Returns the path
+// of the packed scene.
+pub fn pack(scene: &str, include_dirs: &[PathBuf], directory: &Path) -> io::Result<PathBuf> {
+    let content = fs::read_to_string(scene)?;
+    let tokens = scene_tokens(scene, &content, include_dirs).map_err(|error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to read the scene: {:?}", error),
+        )
+    })?;
+
+    // The names of the assets are resolved to their paths in the tokens.
+    let mut sources: Vec<PathBuf> = Vec::new();
+    let mut previous = "";
+    for token in tokens.tokens() {
+        if ASSET_KEYS.contains(&previous) {
+            let source = fs::canonicalize(token).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Asset {} not found.", token),
+                )
+            })?;
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+        previous = token;
+    }
+
+    // The assets that keep their path are named first, so the others can not take their names.
+    let scene_directory = match Path::new(scene).parent() {
+        Some(parent) if parent != Path::new("") => fs::canonicalize(parent)?,
+        _ => fs::canonicalize(".")?,
+    };
+    let mut packed_names: Vec<(&Path, String)> = sources
+        .iter()
+        .filter_map(|source| {
+            let relative = source.strip_prefix(&scene_directory).ok()?;
+            Some((source.as_path(), relative.to_string_lossy().into_owned()))
+        })
+        .collect();
+    for source in &sources {
+        if packed_names.iter().any(|(path, _)| *path == source) {
+            continue;
+        }
+        let file_name = source.file_name().unwrap_or_default().to_string_lossy();
+        let mut packed_name = format!("{}/{}", PACKED_ASSETS, file_name);
+        let mut counter = 1;
+        while packed_names.iter().any(|(_, name)| *name == packed_name) {
+            packed_name = format!("{}/{}-{}", PACKED_ASSETS, counter, file_name);
+            counter += 1;
+        }
+        packed_names.push((source, packed_name));
+    }
+
+    for (source, packed_name) in &packed_names {
+        let destination = directory.join(packed_name);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(source, &destination)?;
+    }
+
+    // The tokens keep the lines they are written on, indented by the blocks they are in.
+    let mut packed = String::new();
+    let mut line = None;
+    let mut depth = 0usize;
+    let mut previous = "";
+    for (index, token) in tokens.tokens().iter().enumerate() {
+        if token.contains(char::is_whitespace) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The value '{}' can not be written to a scene file.", token),
+            ));
+        }
+        let token = if ASSET_KEYS.contains(&previous) {
+            let source = fs::canonicalize(token)?;
+            packed_names
+                .iter()
+                .find(|(path, _)| *path == source)
+                .map_or(token.as_str(), |(_, packed_name)| packed_name.as_str())
+        } else {
+            token.as_str()
+        };
+        if token == "}" {
+            depth = depth.saturating_sub(1);
+        }
+        if line.is_some() {
+            if line == tokens.line(index) {
+                packed.push(' ');
+            } else {
+                packed.push('\n');
+                packed.push_str(&"    ".repeat(depth));
+            }
+        }
+        packed.push_str(token);
+        if token == "{" {
+            depth += 1;
+        }
+        line = tokens.line(index);
+        previous = token;
+    }
+    packed.push('\n');
+
+    let scene_name = Path::new(scene).with_extension("scene");
+    let packed_scene = directory.join(scene_name.file_name().unwrap_or_default());
+    fs::create_dir_all(directory)?;
+    fs::write(&packed_scene, packed)?;
+
+    Ok(packed_scene)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    #[test]
+    fn pack_scene_with_assets() {
+        let root = env::temp_dir().join("pack_scene_with_assets");
+        let _ = fs::remove_dir_all(&root);
+        let scenes = root.join("scenes");
+        let include = root.join("include");
+        let packed = root.join("packed");
+        fs::create_dir_all(scenes.join("profiles")).unwrap();
+        fs::create_dir_all(&include).unwrap();
+
+        fs::write(scenes.join("profiles/spot.ies"), "spot").unwrap();
+        fs::write(include.join("sky.hdr"), "sky").unwrap();
+        let absolute = include.join("bulb.ies");
+        fs::write(&absolute, "bulb").unwrap();
+
+        let scene = scenes.join("test.scene");
+        let content = format!(
+            "spot_light {{ profile: profiles/spot.ies }}\n\
+             // environment_light {{ image: old.hdr }}\n\
+             point_light {{ profile: {} }}\n\
+             environment_light {{ image: sky.hdr }}\n",
+            absolute.to_str().unwrap()
+        );
+        fs::write(&scene, &content).unwrap();
+        let scene = scene.to_str().unwrap();
+
+        let resolver = AssetResolver::new(scene, std::slice::from_ref(&include));
+        assert_eq!(resolver.resolve("sky.hdr"), Some(include.join("sky.hdr")));
+        assert_eq!(
+            resolver.resolve("profiles/spot.ies"),
+            Some(scenes.join("profiles/spot.ies"))
+        );
+        assert_eq!(resolver.resolve("missing.hdr"), None);
+        assert_eq!(asset_names(&content).len(), 3);
+
+        let packed_scene = pack(scene, std::slice::from_ref(&include), &packed).unwrap();
+        let packed_content = fs::read_to_string(packed_scene).unwrap();
+        assert_eq!(
+            packed_content,
+            "spot_light { profile: profiles/spot.ies }\n\
+             point_light { profile: assets/bulb.ies }\n\
+             environment_light { image: assets/sky.hdr }\n"
+        );
+        assert_eq!(
+            fs::read_to_string(packed.join("profiles/spot.ies")).unwrap(),
+            "spot"
+        );
+        assert_eq!(
+            fs::read_to_string(packed.join("assets/bulb.ies")).unwrap(),
+            "bulb"
+        );
+        assert_eq!(
+            fs::read_to_string(packed.join("assets/sky.hdr")).unwrap(),
+            "sky"
+        );
+
+        assert!(pack(scene, &[], &root.join("incomplete")).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn pack_included_files_and_toml_scenes() {
+        let root = env::temp_dir().join("pack_included_files_and_toml_scenes");
+        let _ = fs::remove_dir_all(&root);
+        let scenes = root.join("scenes");
+        let include = root.join("include");
+        let packed = root.join("packed");
+        fs::create_dir_all(scenes.join("parts")).unwrap();
+        fs::create_dir_all(scenes.join("assets")).unwrap();
+        fs::create_dir_all(&include).unwrap();
+
+        fs::write(scenes.join("parts/spot.ies"), "spot").unwrap();
+        fs::write(scenes.join("assets/sky.hdr"), "sky").unwrap();
+        fs::write(include.join("sky.hdr"), "cloudy sky").unwrap();
+        fs::write(
+            scenes.join("parts/set.scene"),
+            "spot_light {\n    profile: spot.ies\n}\n",
+        )
+        .unwrap();
+
+        // The sky of the include directory can not take the name of the sky next to the scene.
+        let scene = scenes.join("test.scene");
+        fs::write(
+            &scene,
+            "// The set.\n\
+             include parts/set.scene\n\
+             environment_light { image: sky.hdr }\n\
+             environment_light { image: assets/sky.hdr }\n",
+        )
+        .unwrap();
+        let packed_scene = pack(
+            scene.to_str().unwrap(),
+            std::slice::from_ref(&include),
+            &packed,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(packed_scene).unwrap(),
+            "spot_light {\n    \
+             profile: parts/spot.ies\n\
+             }\n\
+             environment_light { image: assets/1-sky.hdr }\n\
+             environment_light { image: assets/sky.hdr }\n"
+        );
+        assert_eq!(
+            fs::read_to_string(packed.join("parts/spot.ies")).unwrap(),
+            "spot"
+        );
+        assert_eq!(
+            fs::read_to_string(packed.join("assets/sky.hdr")).unwrap(),
+            "sky"
+        );
+        assert_eq!(
+            fs::read_to_string(packed.join("assets/1-sky.hdr")).unwrap(),
+            "cloudy sky"
+        );
+
+        let scene = scenes.join("lights.toml");
+        fs::write(
+            &scene,
+            "[[environment_light]]\nimage = \"parts/../assets/sky.hdr\"\n",
+        )
+        .unwrap();
+        let packed_scene = pack(scene.to_str().unwrap(), &[], &packed).unwrap();
+        assert_eq!(packed_scene, packed.join("lights.scene"));
+        assert_eq!(
+            fs::read_to_string(packed_scene).unwrap(),
+            "environment_light {\n    image: assets/sky.hdr\n}\n"
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn asset_checksums_detect_changes() {
+        assert_eq!(checksum(b""), 0xcbf29ce484222325);
+        assert_eq!(checksum(b"a"), 0xaf63dc4c8601ec8c);
+
+        let root = env::temp_dir().join("asset_checksums_detect_changes");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let scene = root.join("test.scene");
+        let sky = root.join("sky.hdr");
+        let spot = root.join("spot.ies");
+        fs::write(&scene, "a { image: sky.hdr }\nb { profile: spot.ies }\n").unwrap();
+        fs::write(&sky, "sky").unwrap();
+        fs::write(&spot, "spot").unwrap();
+        let scene = scene.to_str().unwrap();
+
+        let before = AssetChecksums::of_scene(scene, &[]).unwrap();
+        assert_eq!(before.get(&sky), Some(checksum(b"sky")));
+        assert!(before.changed(&before).is_empty());
+
+        fs::write(&spot, "spot").unwrap();
+        fs::write(&sky, "cloudy sky").unwrap();
+        let after = AssetChecksums::of_scene(scene, &[]).unwrap();
+        assert_eq!(after.changed(&before), vec![sky.clone()]);
+
+        fs::write(scene, "a { image: sky.hdr }\n").unwrap();
+        let after = AssetChecksums::of_scene(scene, &[]).unwrap();
+        assert_eq!(
+            after.changed(&before),
+            vec![sky.clone(), spot.clone(), PathBuf::from(scene)]
+        );
+
+        // Included files and their assets are hashed, even if they include the scene again.
+        let rig = root.join("rig.scene");
+        fs::write(scene, "a { image: sky.hdr }\ninclude rig.scene\n").unwrap();
+        fs::write(&rig, "include test.scene\nb { profile: spot.ies }\n").unwrap();
+        let before = AssetChecksums::of_scene(scene, &[]).unwrap();
+        assert_eq!(before.get(&spot), Some(checksum(b"spot")));
+        fs::write(&rig, "include \"test.scene\"\nb { profile: spot.ies }\n").unwrap();
+        let after = AssetChecksums::of_scene(scene, &[]).unwrap();
+        assert_eq!(after.changed(&before), vec![rig.clone()]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
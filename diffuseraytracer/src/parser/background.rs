@@ -0,0 +1,119 @@
+use std::error::Error;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use cg_basics::background::Background;
+use colors::RGB;
+use image::ImageBuffer;
+use traits::{FloatingPoint, Number};
+
+use crate::parser::util;
+use crate::parser::{FromTokens, ParsingError};
+
+impl<T: FloatingPoint + From<f32>> FromTokens for Background<RGB<T>>
+where
+    <T as FromStr>::Err: Error + Debug,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        match tokens.next() {
+            Some("vertical_gradient") => {
+                match parse_colors(tokens, ["top:", "bottom:"], "top:, bottom:, }") {
+                    Ok([top, bottom]) => Ok(Background::VerticalGradient { top, bottom }),
+                    Err(cause) => Err(ParsingError::BackgroundParsingError(Box::new(cause))),
+                }
+            }
+            Some("radial_gradient") => {
+                match parse_colors(tokens, ["center:", "edge:"], "center:, edge:, }") {
+                    Ok([center, edge]) => Ok(Background::RadialGradient { center, edge }),
+                    Err(cause) => Err(ParsingError::BackgroundParsingError(Box::new(cause))),
+                }
+            }
+            Some("horizon_gradient") => match parse_colors(
+                tokens,
+                ["zenith:", "horizon:", "ground:"],
+                "zenith:, horizon:, ground:, }",
+            ) {
+                Ok([zenith, horizon, ground]) => Ok(Background::Horizon {
+                    zenith,
+                    horizon,
+                    ground,
+                }),
+                Err(cause) => Err(ParsingError::BackgroundParsingError(Box::new(cause))),
+            },
+            Some("environment") => match parse_environment(tokens) {
+                Ok(image) => Ok(Background::Environment { image }),
+                Err(cause) => Err(ParsingError::BackgroundParsingError(Box::new(cause))),
+            },
+            Some(background) => Err(ParsingError::UnsupportedBackground(background.to_string())),
+            None => Err(ParsingError::UnexpectedEndOfTokens),
+        }
+    }
+}
+
+// Parses a block with the equirectangular image of an environment.
+fn parse_environment<'a, T: FloatingPoint + From<f32>>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<ImageBuffer<RGB<T>>, ParsingError> {
+    util::check_next_token(tokens, "{")?;
+
+    let mut image = None;
+    while let Some(token) = tokens.next() {
+        match token {
+            "}" => break,
+            "image:" => match tokens.next() {
+                Some(filename) => image = Some(util::load_image(filename)?),
+                None => return Err(ParsingError::UnexpectedEndOfTokens),
+            },
+            _ => {
+                return Err(ParsingError::UnexpectedToken {
+                    expected: "image:, }",
+                    found: token.to_string(),
+                });
+            }
+        }
+    }
+
+    image.ok_or(ParsingError::MissingElement("image"))
+}
+
+// Parses a block that assigns a color to each of the given keys.
+fn parse_colors<'a, T: FromStr + Number, const N: usize>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    keys: [&'static str; N],
+    expected: &'static str,
+) -> Result<[RGB<T>; N], ParsingError>
+where
+    <T as FromStr>::Err: Error + Debug,
+{
+    util::check_next_token(tokens, "{")?;
+
+    let mut colors: [Option<RGB<T>>; N] = [None; N];
+
+    while let Some(token) = tokens.next() {
+        if token == "}" {
+            break;
+        }
+
+        match keys.iter().position(|key| *key == token) {
+            Some(index) => {
+                colors[index] = Some(RGB::from_tokens(tokens)?);
+            }
+            None => {
+                return Err(ParsingError::UnexpectedToken {
+                    expected,
+                    found: token.to_string(),
+                });
+            }
+        }
+    }
+
+    for (key, color) in keys.iter().zip(colors.iter()) {
+        if color.is_none() {
+            return Err(ParsingError::MissingElement(key.trim_end_matches(':')));
+        }
+    }
+
+    Ok(colors.map(|color| color.unwrap()))
+}
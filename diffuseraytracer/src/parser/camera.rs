@@ -3,12 +3,15 @@ use std::fmt::Debug;
 use std::str::FromStr;
 
 use cg_basics::camera::{
-    FisheyeCamera, OrthographicCamera, PerspectiveCamera, PinholeCamera, SphericalCamera,
+    gaze_and_up, CameraKeyframe, CameraPath, CylindricalCamera, Eye, FisheyeCamera, FocusKeyframe,
+    FocusPull, FocusTarget, LensDistortion, OdsCamera, OrthographicCamera, PerspectiveCamera,
+    PhysicalCamera, PinholeCamera, Shutter, SphericalCamera, StereoCamera,
 };
-use math::{Point3, Vector3};
+use math::{Point3, Vector2, Vector3};
+use sampling::Aperture;
 use traits::floating_point::ToRadians;
 use traits::{ConvenientNumber, FloatingPoint, One, SignedNumber, Sqrt, Zero};
-use units::angle::Degrees;
+use units::angle::{Degrees, Radians};
 use units::length::Length;
 
 use crate::parser::util;
@@ -32,8 +35,12 @@ where
         let mut eye_position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
         let mut gaze_direction: Vector3<T> = Vector3::new(Zero::zero(), Zero::zero(), -T::one());
         let mut up_vector: Vector3<T> = Vector3::new(Zero::zero(), One::one(), Zero::zero());
+        let mut look_at: Option<Point3<T>> = None;
         let mut field_of_view: Degrees<<T as Length>::ValueType> = Degrees::new(Zero::zero());
 
+        let mut distortion: LensDistortion<<T as Length>::ValueType> = LensDistortion::default();
+        let mut shutter: Shutter<<T as Length>::ValueType> = Shutter::default();
+
         while let Some(token) = tokens.next() {
             match token {
                 "id:" => match tokens.next() {
@@ -60,6 +67,14 @@ where
                         return Err(ParsingError::PinholeCameraParsingError(Box::new(cause)));
                     }
                 },
+                "look_at:" => match Point3::from_tokens(tokens) {
+                    Ok(target) => {
+                        look_at = Some(target);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PinholeCameraParsingError(Box::new(cause)));
+                    }
+                },
                 "up_vector:" => match Vector3::from_tokens(tokens) {
                     Ok(vec) => {
                         up_vector = vec;
@@ -69,30 +84,72 @@ where
                     }
                 },
                 "field_of_view:" => match tokens.next() {
-                    Some(fov_string) => match fov_string.parse() {
-                        Ok(fov) => field_of_view = fov,
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse field of number.",
-                            ));
+                    Some(fov_string) => {
+                        match util::parse_token(fov_string, "Unable to parse field of number.") {
+                            Ok(fov) => field_of_view = fov,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
                         }
-                    },
+                    }
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
                 },
+                "radial_distortion:" => {
+                    match (util::parse_number(tokens), util::parse_number(tokens)) {
+                        (Ok(k1), Ok(k2)) => {
+                            distortion.k1 = k1;
+                            distortion.k2 = k2;
+                        }
+                        (Err(cause), _) | (_, Err(cause)) => {
+                            return Err(ParsingError::PinholeCameraParsingError(Box::new(cause)));
+                        }
+                    }
+                }
+                "tangential_distortion:" => {
+                    match (util::parse_number(tokens), util::parse_number(tokens)) {
+                        (Ok(p1), Ok(p2)) => {
+                            distortion.p1 = p1;
+                            distortion.p2 = p2;
+                        }
+                        (Err(cause), _) | (_, Err(cause)) => {
+                            return Err(ParsingError::PinholeCameraParsingError(Box::new(cause)));
+                        }
+                    }
+                }
+                "shutter_open:" => match util::parse_number(tokens) {
+                    Ok(open) => {
+                        shutter.open = open;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PinholeCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "shutter_close:" => match util::parse_number(tokens) {
+                    Ok(close) => {
+                        shutter.close = close;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PinholeCameraParsingError(Box::new(cause)));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
                         expected:
-                            "id:, eye_position:, gaze_direction:, up_vector:, field_of_view:, }",
+                            "id:, eye_position:, gaze_direction:, look_at:, up_vector:, field_of_view:, radial_distortion:, tangential_distortion:, shutter_open:, shutter_close:, }",
                         found: token.to_string(),
                     });
                 }
             }
         }
+        if let Some(target) = look_at {
+            (gaze_direction, up_vector) = gaze_and_up(eye_position, target, up_vector);
+        }
+
         Ok((
             id.to_string(),
             PinholeCamera::new(
@@ -100,12 +157,17 @@ where
                 gaze_direction,
                 up_vector,
                 field_of_view.to_radians(),
-            ),
+            )
+            .with_distortion(distortion)
+            .with_shutter(shutter),
         ))
     }
 }
 
-impl<T: Length + SignedNumber<T::ValueType>> FromTokens for (String, PerspectiveCamera<T>)
+// The camera comes with the name of the object it focuses on, which is resolved once the whole
+// scene is known.
+impl<T: Length + SignedNumber<T::ValueType>> FromTokens
+    for (String, PerspectiveCamera<T>, Option<String>)
 where
     <T as Length>::AreaType: Sqrt<Output = T> + ConvenientNumber,
     <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
@@ -123,9 +185,16 @@ where
         let mut eye_position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
         let mut gaze_direction: Vector3<T> = Vector3::new(Zero::zero(), Zero::zero(), -T::one());
         let mut up_vector: Vector3<T> = Vector3::new(Zero::zero(), One::one(), Zero::zero());
+        let mut look_at: Option<Point3<T>> = None;
         let mut field_of_view: Degrees<<T as Length>::ValueType> = Degrees::new(Zero::zero());
         let mut lens_radius = T::one();
         let mut focal_length = T::one();
+        let mut focus_on: Option<String> = None;
+        let mut aperture_blades: Option<usize> = None;
+        let mut aperture_rotation: Degrees<<T as Length>::ValueType> = Degrees::new(Zero::zero());
+
+        let mut distortion: LensDistortion<<T as Length>::ValueType> = LensDistortion::default();
+        let mut shutter: Shutter<<T as Length>::ValueType> = Shutter::default();
 
         while let Some(token) = tokens.next() {
             match token {
@@ -153,6 +222,14 @@ where
                         return Err(ParsingError::PerspectiveCameraParsingError(Box::new(cause)));
                     }
                 },
+                "look_at:" => match Point3::from_tokens(tokens) {
+                    Ok(target) => {
+                        look_at = Some(target);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PerspectiveCameraParsingError(Box::new(cause)));
+                    }
+                },
                 "up_vector:" => match Vector3::from_tokens(tokens) {
                     Ok(vec) => {
                         up_vector = vec;
@@ -162,70 +239,352 @@ where
                     }
                 },
                 "field_of_view:" => match tokens.next() {
-                    Some(fov_string) => match fov_string.parse() {
-                        Ok(fov) => field_of_view = fov,
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse field of view.",
-                            ));
+                    Some(fov_string) => {
+                        match util::parse_token(fov_string, "Unable to parse field of view.") {
+                            Ok(fov) => field_of_view = fov,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
                         }
-                    },
+                    }
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
                 },
                 "lens_radius:" => match tokens.next() {
-                    Some(lens_radius_string) => match lens_radius_string.parse() {
-                        Ok(lr) => lens_radius = lr,
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse lens radius.",
-                            ));
+                    Some(lens_radius_string) => {
+                        match util::parse_token(lens_radius_string, "Unable to parse lens radius.")
+                        {
+                            Ok(lr) => lens_radius = lr,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
                         }
-                    },
+                    }
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
                 },
                 "focal_length:" => match tokens.next() {
-                    Some(focal_length_string) => match focal_length_string.parse() {
+                    Some(focal_length_string) => match util::parse_token(
+                        focal_length_string,
+                        "Unable to parse folcal length.",
+                    ) {
                         Ok(fl) => focal_length = fl,
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse folcal length.",
-                            ));
+                        Err(cause) => {
+                            return Err(cause);
                         }
                     },
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
                 },
+                "focus_on:" => match tokens.next() {
+                    Some(name) => {
+                        focus_on = Some(name.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "aperture_blades:" => match util::parse_number(tokens) {
+                    Ok(blades) => {
+                        if blades < 3 {
+                            return Err(ParsingError::PerspectiveCameraParsingError(Box::new(
+                                ParsingError::NumberParsingError(
+                                    "An aperture needs at least three blades.",
+                                ),
+                            )));
+                        }
+                        aperture_blades = Some(blades);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PerspectiveCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "aperture_rotation:" => match util::parse_number(tokens) {
+                    Ok(rotation) => {
+                        aperture_rotation = rotation;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PerspectiveCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "radial_distortion:" => {
+                    match (util::parse_number(tokens), util::parse_number(tokens)) {
+                        (Ok(k1), Ok(k2)) => {
+                            distortion.k1 = k1;
+                            distortion.k2 = k2;
+                        }
+                        (Err(cause), _) | (_, Err(cause)) => {
+                            return Err(ParsingError::PerspectiveCameraParsingError(Box::new(
+                                cause,
+                            )));
+                        }
+                    }
+                }
+                "tangential_distortion:" => {
+                    match (util::parse_number(tokens), util::parse_number(tokens)) {
+                        (Ok(p1), Ok(p2)) => {
+                            distortion.p1 = p1;
+                            distortion.p2 = p2;
+                        }
+                        (Err(cause), _) | (_, Err(cause)) => {
+                            return Err(ParsingError::PerspectiveCameraParsingError(Box::new(
+                                cause,
+                            )));
+                        }
+                    }
+                }
+                "shutter_open:" => match util::parse_number(tokens) {
+                    Ok(open) => {
+                        shutter.open = open;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PerspectiveCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "shutter_close:" => match util::parse_number(tokens) {
+                    Ok(close) => {
+                        shutter.close = close;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PerspectiveCameraParsingError(Box::new(cause)));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
                         expected:
-                            "id:, eye_position:, gaze_direction:, up_vector:, field_of_view:, lens_radius, focal_length }",
+                            "id:, eye_position:, gaze_direction:, look_at:, up_vector:, field_of_view:, lens_radius, focal_length, focus_on:, aperture_blades:, aperture_rotation:, radial_distortion:, tangential_distortion:, shutter_open:, shutter_close:, }",
                         found: token.to_string(),
                     });
                 }
             }
         }
+        if let Some(target) = look_at {
+            (gaze_direction, up_vector) = gaze_and_up(eye_position, target, up_vector);
+        }
+
+        let mut camera = PerspectiveCamera::new(
+            eye_position,
+            gaze_direction,
+            up_vector,
+            field_of_view.to_radians(),
+            lens_radius,
+            focal_length,
+        );
+
+        // Without blades the aperture is a disc.
+        if let Some(blades) = aperture_blades {
+            camera = camera.with_aperture(Aperture::Polygon {
+                blades,
+                rotation: aperture_rotation.to_radians() / Radians::one(),
+            });
+        }
+
         Ok((
             id.to_string(),
-            PerspectiveCamera::new(
-                eye_position,
-                gaze_direction,
-                up_vector,
-                field_of_view.to_radians(),
-                lens_radius,
-                focal_length,
-            ),
+            camera.with_distortion(distortion).with_shutter(shutter),
+            focus_on,
         ))
     }
 }
 
+impl<T: Length + SignedNumber<T::ValueType>> FromTokens for (String, PhysicalCamera<T>)
+where
+    <T as Length>::AreaType: Sqrt<Output = T> + ConvenientNumber,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <T as FromStr>::Err: Error + Debug,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+    u16: Into<<T as Length>::ValueType>,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+        }
+
+        let mut id = "main";
+        let mut eye_position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut gaze_direction: Vector3<T> = Vector3::new(Zero::zero(), Zero::zero(), -T::one());
+        let mut up_vector: Vector3<T> = Vector3::new(Zero::zero(), One::one(), Zero::zero());
+        let mut look_at: Option<Point3<T>> = None;
+
+        // A normal lens on a full frame sensor, exposed for daylight by the sunny 16 rule.
+        let mut focal_length: <T as Length>::ValueType = 50u16.into();
+        let mut sensor_size: Vector2<<T as Length>::ValueType> =
+            Vector2::new(36u16.into(), 24u16.into());
+        let mut f_number: <T as Length>::ValueType = 16u16.into();
+        let mut focus_distance = T::one();
+        let mut shutter_speed: <T as Length>::ValueType =
+            <T as Length>::ValueType::one() / 100u16.into();
+        let mut iso: <T as Length>::ValueType = 100u16.into();
+
+        let mut shutter: Shutter<<T as Length>::ValueType> = Shutter::default();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "id:" => match tokens.next() {
+                    Some(parsed_id) => {
+                        id = parsed_id;
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "eye_position:" => match Point3::from_tokens(tokens) {
+                    Ok(value) => {
+                        eye_position = value;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "gaze_direction:" => match Vector3::from_tokens(tokens) {
+                    Ok(value) => {
+                        gaze_direction = value;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "look_at:" => match Point3::from_tokens(tokens) {
+                    Ok(target) => {
+                        look_at = Some(target);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "up_vector:" => match Vector3::from_tokens(tokens) {
+                    Ok(value) => {
+                        up_vector = value;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "focal_length:" => match util::parse_number(tokens) {
+                    Ok(length) => {
+                        focal_length = length;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "sensor_size:" => match (util::parse_number(tokens), util::parse_number(tokens)) {
+                    (Ok(width), Ok(height)) => {
+                        sensor_size = Vector2::new(width, height);
+                    }
+                    (Err(cause), _) | (_, Err(cause)) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "f_number:" => match util::parse_number(tokens) {
+                    Ok(number) => {
+                        f_number = number;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "focus_distance:" => match util::parse_number(tokens) {
+                    Ok(distance) => {
+                        focus_distance = distance;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "shutter_speed:" => match util::parse_number(tokens) {
+                    Ok(speed) => {
+                        shutter_speed = speed;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "iso:" => match util::parse_number(tokens) {
+                    Ok(speed) => {
+                        iso = speed;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "shutter_open:" => match util::parse_number(tokens) {
+                    Ok(open) => {
+                        shutter.open = open;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "shutter_close:" => match util::parse_number(tokens) {
+                    Ok(close) => {
+                        shutter.close = close;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PhysicalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected:
+                            "id:, eye_position:, gaze_direction:, look_at:, up_vector:, focal_length:, sensor_size:, f_number:, focus_distance:, shutter_speed:, iso:, shutter_open:, shutter_close:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        let zero = <T as Length>::ValueType::zero();
+        if [
+            focal_length,
+            sensor_size.x,
+            sensor_size.y,
+            f_number,
+            shutter_speed,
+            iso,
+        ]
+        .iter()
+        .any(|setting| *setting <= zero)
+        {
+            return Err(ParsingError::PhysicalCameraParsingError(Box::new(
+                ParsingError::NumberParsingError(
+                    "The settings of a physical camera must be positive.",
+                ),
+            )));
+        }
+
+        if let Some(target) = look_at {
+            (gaze_direction, up_vector) = gaze_and_up(eye_position, target, up_vector);
+        }
+
+        let mut camera = PhysicalCamera::new(
+            eye_position,
+            gaze_direction,
+            up_vector,
+            focal_length,
+            sensor_size,
+            f_number,
+            focus_distance,
+        )
+        .with_shutter_speed(shutter_speed)
+        .with_iso(iso);
+        camera.lens = camera.lens.with_shutter(shutter);
+
+        Ok((id.to_string(), camera))
+    }
+}
+
 impl<T: Length + SignedNumber<T::ValueType>> FromTokens for (String, OrthographicCamera<T>)
 where
     <T as Length>::AreaType: Sqrt<Output = T> + ConvenientNumber,
@@ -245,8 +604,11 @@ where
         let mut eye_position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
         let mut gaze_direction: Vector3<T> = Vector3::new(Zero::zero(), Zero::zero(), -T::one());
         let mut up_vector: Vector3<T> = Vector3::new(Zero::zero(), One::one(), Zero::zero());
+        let mut look_at: Option<Point3<T>> = None;
         let mut scale: <T as Length>::ValueType = One::one();
 
+        let mut shutter: Shutter<<T as Length>::ValueType> = Shutter::default();
+
         while let Some(token) = tokens.next() {
             match token {
                 "id:" => match tokens.next() {
@@ -277,6 +639,16 @@ where
                         )));
                     }
                 },
+                "look_at:" => match Point3::from_tokens(tokens) {
+                    Ok(target) => {
+                        look_at = Some(target);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::OrthographicCameraParsingError(Box::new(
+                            cause,
+                        )));
+                    }
+                },
                 "up_vector:" => match Vector3::from_tokens(tokens) {
                     Ok(vec) => {
                         up_vector = vec;
@@ -288,33 +660,58 @@ where
                     }
                 },
                 "scale:" => match tokens.next() {
-                    Some(fov_string) => match fov_string.parse() {
-                        Ok(s) => scale = s,
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse field of number.",
-                            ));
+                    Some(fov_string) => {
+                        match util::parse_token(fov_string, "Unable to parse field of number.") {
+                            Ok(s) => scale = s,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
                         }
-                    },
+                    }
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
                 },
+                "shutter_open:" => match util::parse_number(tokens) {
+                    Ok(open) => {
+                        shutter.open = open;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::OrthographicCameraParsingError(Box::new(
+                            cause,
+                        )));
+                    }
+                },
+                "shutter_close:" => match util::parse_number(tokens) {
+                    Ok(close) => {
+                        shutter.close = close;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::OrthographicCameraParsingError(Box::new(
+                            cause,
+                        )));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
                         expected:
-                            "id:, eye_position:, gaze_direction:, up_vector:, field_of_view:, }",
+                            "id:, eye_position:, gaze_direction:, look_at:, up_vector:, field_of_view:, shutter_open:, shutter_close:, }",
                         found: token.to_string(),
                     });
                 }
             }
         }
+        if let Some(target) = look_at {
+            (gaze_direction, up_vector) = gaze_and_up(eye_position, target, up_vector);
+        }
+
         Ok((
             id.to_string(),
-            OrthographicCamera::new(eye_position, gaze_direction, up_vector, scale),
+            OrthographicCamera::new(eye_position, gaze_direction, up_vector, scale)
+                .with_shutter(shutter),
         ))
     }
 }
@@ -337,8 +734,11 @@ where
         let mut eye_position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
         let mut gaze_direction: Vector3<T> = Vector3::new(Zero::zero(), Zero::zero(), -T::one());
         let mut up_vector: Vector3<T> = Vector3::new(Zero::zero(), One::one(), Zero::zero());
+        let mut look_at: Option<Point3<T>> = None;
         let mut psi: Degrees<<T as Length>::ValueType> = Degrees::new(Zero::zero());
 
+        let mut shutter: Shutter<<T as Length>::ValueType> = Shutter::default();
+
         while let Some(token) = tokens.next() {
             match token {
                 "id:" => match tokens.next() {
@@ -365,6 +765,14 @@ where
                         return Err(ParsingError::FisheyeCameraParsingError(Box::new(cause)));
                     }
                 },
+                "look_at:" => match Point3::from_tokens(tokens) {
+                    Ok(target) => {
+                        look_at = Some(target);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::FisheyeCameraParsingError(Box::new(cause)));
+                    }
+                },
                 "up_vector:" => match Vector3::from_tokens(tokens) {
                     Ok(vec) => {
                         up_vector = vec;
@@ -374,32 +782,53 @@ where
                     }
                 },
                 "psi:" => match tokens.next() {
-                    Some(psi_string) => match psi_string.parse() {
-                        Ok(p) => psi = p,
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse field of number.",
-                            ));
+                    Some(psi_string) => {
+                        match util::parse_token(psi_string, "Unable to parse field of number.") {
+                            Ok(p) => psi = p,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
                         }
-                    },
+                    }
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
                 },
+                "shutter_open:" => match util::parse_number(tokens) {
+                    Ok(open) => {
+                        shutter.open = open;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::FisheyeCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "shutter_close:" => match util::parse_number(tokens) {
+                    Ok(close) => {
+                        shutter.close = close;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::FisheyeCameraParsingError(Box::new(cause)));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
-                        expected: "id:, eye_position:, gaze_direction:, up_vector:, psi:, }",
+                        expected: "id:, eye_position:, gaze_direction:, look_at:, up_vector:, psi:, shutter_open:, shutter_close:, }",
                         found: token.to_string(),
                     });
                 }
             }
         }
+        if let Some(target) = look_at {
+            (gaze_direction, up_vector) = gaze_and_up(eye_position, target, up_vector);
+        }
+
         Ok((
             id.to_string(),
-            FisheyeCamera::new(eye_position, gaze_direction, up_vector, psi.to_radians()),
+            FisheyeCamera::new(eye_position, gaze_direction, up_vector, psi.to_radians())
+                .with_shutter(shutter),
         ))
     }
 }
@@ -422,8 +851,11 @@ where
         let mut eye_position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
         let mut gaze_direction: Vector3<T> = Vector3::new(Zero::zero(), Zero::zero(), -T::one());
         let mut up_vector: Vector3<T> = Vector3::new(Zero::zero(), One::one(), Zero::zero());
+        let mut look_at: Option<Point3<T>> = None;
         let mut field_of_view: Degrees<<T as Length>::ValueType> = Degrees::new(Zero::zero());
 
+        let mut shutter: Shutter<<T as Length>::ValueType> = Shutter::default();
+
         while let Some(token) = tokens.next() {
             match token {
                 "id:" => match tokens.next() {
@@ -450,6 +882,14 @@ where
                         return Err(ParsingError::SphericalCameraParsingError(Box::new(cause)));
                     }
                 },
+                "look_at:" => match Point3::from_tokens(tokens) {
+                    Ok(target) => {
+                        look_at = Some(target);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphericalCameraParsingError(Box::new(cause)));
+                    }
+                },
                 "up_vector:" => match Vector3::from_tokens(tokens) {
                     Ok(vec) => {
                         up_vector = vec;
@@ -459,30 +899,50 @@ where
                     }
                 },
                 "field_of_view:" => match tokens.next() {
-                    Some(fov_string) => match fov_string.parse() {
-                        Ok(fov) => field_of_view = fov,
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse field of number.",
-                            ));
+                    Some(fov_string) => {
+                        match util::parse_token(fov_string, "Unable to parse field of number.") {
+                            Ok(fov) => field_of_view = fov,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
                         }
-                    },
+                    }
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
                 },
+                "shutter_open:" => match util::parse_number(tokens) {
+                    Ok(open) => {
+                        shutter.open = open;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphericalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "shutter_close:" => match util::parse_number(tokens) {
+                    Ok(close) => {
+                        shutter.close = close;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphericalCameraParsingError(Box::new(cause)));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
                         expected:
-                            "id:, eye_position:, gaze_direction:, up_vector:, field_of_view:, }",
+                            "id:, eye_position:, gaze_direction:, look_at:, up_vector:, field_of_view:, shutter_open:, shutter_close:, }",
                         found: token.to_string(),
                     });
                 }
             }
         }
+        if let Some(target) = look_at {
+            (gaze_direction, up_vector) = gaze_and_up(eye_position, target, up_vector);
+        }
+
         Ok((
             id.to_string(),
             SphericalCamera::new(
@@ -490,7 +950,675 @@ where
                 gaze_direction,
                 up_vector,
                 field_of_view.to_radians(),
-            ),
+            )
+            .with_shutter(shutter),
         ))
     }
 }
+
+impl<T: Length + SignedNumber<T::ValueType>> FromTokens for (String, CylindricalCamera<T>)
+where
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <T as FromStr>::Err: Error + Debug,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+    u16: Into<<T as Length>::ValueType>,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::CylindricalCameraParsingError(Box::new(cause)));
+        }
+
+        let mut id = "main";
+        let mut eye_position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut gaze_direction: Vector3<T> = Vector3::new(Zero::zero(), Zero::zero(), -T::one());
+        let mut up_vector: Vector3<T> = Vector3::new(Zero::zero(), One::one(), Zero::zero());
+        let mut look_at: Option<Point3<T>> = None;
+        let mut horizontal_field_of_view: Degrees<<T as Length>::ValueType> =
+            Degrees::new(360u16.into());
+        let mut vertical_field_of_view: Degrees<<T as Length>::ValueType> =
+            Degrees::new(90u16.into());
+
+        let mut shutter: Shutter<<T as Length>::ValueType> = Shutter::default();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "id:" => match tokens.next() {
+                    Some(parsed_id) => {
+                        id = parsed_id;
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "eye_position:" => match Point3::from_tokens(tokens) {
+                    Ok(pos) => {
+                        eye_position = pos;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylindricalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "gaze_direction:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        gaze_direction = vec;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylindricalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "look_at:" => match Point3::from_tokens(tokens) {
+                    Ok(target) => {
+                        look_at = Some(target);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylindricalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "up_vector:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        up_vector = vec;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylindricalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "horizontal_field_of_view:" => match tokens.next() {
+                    Some(fov_string) => {
+                        match util::parse_token(fov_string, "Unable to parse field of number.") {
+                            Ok(fov) => horizontal_field_of_view = fov,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
+                        }
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "vertical_field_of_view:" => match tokens.next() {
+                    Some(fov_string) => {
+                        match util::parse_token(fov_string, "Unable to parse field of number.") {
+                            Ok(fov) => vertical_field_of_view = fov,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
+                        }
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "shutter_open:" => match util::parse_number(tokens) {
+                    Ok(open) => {
+                        shutter.open = open;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylindricalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "shutter_close:" => match util::parse_number(tokens) {
+                    Ok(close) => {
+                        shutter.close = close;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylindricalCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected:
+                            "id:, eye_position:, gaze_direction:, look_at:, up_vector:, horizontal_field_of_view:, vertical_field_of_view:, shutter_open:, shutter_close:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+        if let Some(target) = look_at {
+            (gaze_direction, up_vector) = gaze_and_up(eye_position, target, up_vector);
+        }
+
+        Ok((
+            id.to_string(),
+            CylindricalCamera::new(
+                eye_position,
+                gaze_direction,
+                up_vector,
+                horizontal_field_of_view.to_radians(),
+                vertical_field_of_view.to_radians(),
+            )
+            .with_shutter(shutter),
+        ))
+    }
+}
+
+impl<T: Length + SignedNumber<T::ValueType>> FromTokens for (String, OdsCamera<T>)
+where
+    <T as Length>::AreaType: Sqrt<Output = T> + ConvenientNumber,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <T as FromStr>::Err: Error + Debug,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::OdsCameraParsingError(Box::new(cause)));
+        }
+
+        let mut id = "main";
+        let mut eye_position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut gaze_direction: Vector3<T> = Vector3::new(Zero::zero(), Zero::zero(), -T::one());
+        let mut up_vector: Vector3<T> = Vector3::new(Zero::zero(), One::one(), Zero::zero());
+        let mut look_at: Option<Point3<T>> = None;
+        let mut interocular_distance: T = Zero::zero();
+
+        let mut shutter: Shutter<<T as Length>::ValueType> = Shutter::default();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "id:" => match tokens.next() {
+                    Some(parsed_id) => {
+                        id = parsed_id;
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "eye_position:" => match Point3::from_tokens(tokens) {
+                    Ok(pos) => {
+                        eye_position = pos;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::OdsCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "gaze_direction:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        gaze_direction = vec;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::OdsCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "look_at:" => match Point3::from_tokens(tokens) {
+                    Ok(target) => {
+                        look_at = Some(target);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::OdsCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "up_vector:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        up_vector = vec;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::OdsCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "interocular_distance:" => match tokens.next() {
+                    Some(distance_string) => {
+                        match util::parse_token(
+                            distance_string,
+                            "Unable to parse interocular distance.",
+                        ) {
+                            Ok(distance) => interocular_distance = distance,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
+                        }
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "shutter_open:" => match util::parse_number(tokens) {
+                    Ok(open) => {
+                        shutter.open = open;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::OdsCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "shutter_close:" => match util::parse_number(tokens) {
+                    Ok(close) => {
+                        shutter.close = close;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::OdsCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected:
+                            "id:, eye_position:, gaze_direction:, look_at:, up_vector:, interocular_distance:, shutter_open:, shutter_close:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+        if let Some(target) = look_at {
+            (gaze_direction, up_vector) = gaze_and_up(eye_position, target, up_vector);
+        }
+
+        Ok((
+            id.to_string(),
+            OdsCamera::new(
+                eye_position,
+                gaze_direction,
+                up_vector,
+                interocular_distance,
+            )
+            .with_shutter(shutter),
+        ))
+    }
+}
+
+impl<T: Length + SignedNumber<T::ValueType>> FromTokens for (String, [StereoCamera<T>; 2])
+where
+    <T as Length>::AreaType: Sqrt<Output = T> + ConvenientNumber,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <T as FromStr>::Err: Error + Debug,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::StereoCameraParsingError(Box::new(cause)));
+        }
+
+        let mut id = "main";
+        let mut eye_position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut gaze_direction: Vector3<T> = Vector3::new(Zero::zero(), Zero::zero(), -T::one());
+        let mut up_vector: Vector3<T> = Vector3::new(Zero::zero(), One::one(), Zero::zero());
+        let mut look_at: Option<Point3<T>> = None;
+        let mut field_of_view: Degrees<<T as Length>::ValueType> = Degrees::new(Zero::zero());
+        let mut interocular_distance: T = Zero::zero();
+        let mut convergence_distance = T::one();
+
+        let mut shutter: Shutter<<T as Length>::ValueType> = Shutter::default();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "id:" => match tokens.next() {
+                    Some(parsed_id) => {
+                        id = parsed_id;
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "eye_position:" => match Point3::from_tokens(tokens) {
+                    Ok(pos) => {
+                        eye_position = pos;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::StereoCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "gaze_direction:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        gaze_direction = vec;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::StereoCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "look_at:" => match Point3::from_tokens(tokens) {
+                    Ok(target) => {
+                        look_at = Some(target);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::StereoCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "up_vector:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        up_vector = vec;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::StereoCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "field_of_view:" => match tokens.next() {
+                    Some(fov_string) => {
+                        match util::parse_token(fov_string, "Unable to parse field of number.") {
+                            Ok(fov) => field_of_view = fov,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
+                        }
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "interocular_distance:" => match tokens.next() {
+                    Some(distance_string) => {
+                        match util::parse_token(
+                            distance_string,
+                            "Unable to parse interocular distance.",
+                        ) {
+                            Ok(distance) => interocular_distance = distance,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
+                        }
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "convergence_distance:" => match tokens.next() {
+                    Some(distance_string) => {
+                        match util::parse_token(
+                            distance_string,
+                            "Unable to parse convergence distance.",
+                        ) {
+                            Ok(distance) => convergence_distance = distance,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
+                        }
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "shutter_open:" => match util::parse_number(tokens) {
+                    Ok(open) => {
+                        shutter.open = open;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::StereoCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "shutter_close:" => match util::parse_number(tokens) {
+                    Ok(close) => {
+                        shutter.close = close;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::StereoCameraParsingError(Box::new(cause)));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected:
+                            "id:, eye_position:, gaze_direction:, look_at:, up_vector:, field_of_view:, interocular_distance:, convergence_distance:, shutter_open:, shutter_close:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+        if let Some(target) = look_at {
+            (gaze_direction, up_vector) = gaze_and_up(eye_position, target, up_vector);
+        }
+
+        let camera = |eye| {
+            StereoCamera::new(
+                eye_position,
+                gaze_direction,
+                up_vector,
+                field_of_view.to_radians(),
+                interocular_distance,
+                convergence_distance,
+                eye,
+            )
+            .with_shutter(shutter)
+        };
+
+        Ok((id.to_string(), [camera(Eye::Left), camera(Eye::Right)]))
+    }
+}
+
+impl<T: Length + SignedNumber<T::ValueType>> FromTokens for (String, CameraPath<T>)
+where
+    <T as Length>::AreaType: Sqrt<Output = T> + ConvenientNumber,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <T as FromStr>::Err: Error + Debug,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::CameraPathParsingError(Box::new(cause)));
+        }
+
+        let mut camera = "main";
+        let mut keyframes: Vec<CameraKeyframe<T>> = Vec::new();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "camera:" => match tokens.next() {
+                    Some(parsed_camera) => {
+                        camera = parsed_camera;
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "keyframe:" => match CameraKeyframe::from_tokens(tokens) {
+                    Ok(keyframe) => {
+                        keyframes.push(keyframe);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CameraPathParsingError(Box::new(cause)));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "camera:, keyframe:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+        if keyframes.is_empty() {
+            return Err(ParsingError::CameraPathParsingError(Box::new(
+                ParsingError::MissingElement("keyframe:"),
+            )));
+        }
+
+        Ok((camera.to_string(), CameraPath::new(keyframes)))
+    }
+}
+
+impl<T: Length + SignedNumber<T::ValueType>> FromTokens for CameraKeyframe<T>
+where
+    <T as Length>::AreaType: Sqrt<Output = T> + ConvenientNumber,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <T as FromStr>::Err: Error + Debug,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        util::check_next_token(tokens, "{")?;
+
+        let mut time: <T as Length>::ValueType = Zero::zero();
+        let mut eye_position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut look_at: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), -T::one());
+        let mut up_vector: Vector3<T> = Vector3::new(Zero::zero(), One::one(), Zero::zero());
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "time:" => match util::parse_number(tokens) {
+                    Ok(value) => {
+                        time = value;
+                    }
+                    Err(cause) => {
+                        return Err(cause);
+                    }
+                },
+                "eye_position:" => match Point3::from_tokens(tokens) {
+                    Ok(value) => {
+                        eye_position = value;
+                    }
+                    Err(cause) => {
+                        return Err(cause);
+                    }
+                },
+                "look_at:" => match Point3::from_tokens(tokens) {
+                    Ok(value) => {
+                        look_at = value;
+                    }
+                    Err(cause) => {
+                        return Err(cause);
+                    }
+                },
+                "up_vector:" => match Vector3::from_tokens(tokens) {
+                    Ok(value) => {
+                        up_vector = value;
+                    }
+                    Err(cause) => {
+                        return Err(cause);
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "time:, eye_position:, look_at:, up_vector:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+        let (_, up_vector) = gaze_and_up(eye_position, look_at, up_vector);
+
+        Ok(CameraKeyframe::new(time, eye_position, look_at).with_up_vector(up_vector))
+    }
+}
+
+impl<T: Length + SignedNumber<T::ValueType>> FromTokens for (String, FocusPull<T>)
+where
+    <T as Length>::AreaType: Sqrt<Output = T> + ConvenientNumber,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <T as FromStr>::Err: Error + Debug,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::FocusPullParsingError(Box::new(cause)));
+        }
+
+        let mut camera = "main";
+        let mut keyframes: Vec<FocusKeyframe<T>> = Vec::new();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "camera:" => match tokens.next() {
+                    Some(parsed_camera) => {
+                        camera = parsed_camera;
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "keyframe:" => match FocusKeyframe::from_tokens(tokens) {
+                    Ok(keyframe) => {
+                        keyframes.push(keyframe);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::FocusPullParsingError(Box::new(cause)));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "camera:, keyframe:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+        if keyframes.is_empty() {
+            return Err(ParsingError::FocusPullParsingError(Box::new(
+                ParsingError::MissingElement("keyframe:"),
+            )));
+        }
+
+        Ok((camera.to_string(), FocusPull::new(keyframes)))
+    }
+}
+
+impl<T: Length + SignedNumber<T::ValueType>> FromTokens for FocusKeyframe<T>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
+    <T as FromStr>::Err: Error + Debug,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        util::check_next_token(tokens, "{")?;
+
+        let mut time: <T as Length>::ValueType = Zero::zero();
+        let mut target: Option<FocusTarget<T>> = None;
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "time:" => match util::parse_number(tokens) {
+                    Ok(value) => {
+                        time = value;
+                    }
+                    Err(cause) => {
+                        return Err(cause);
+                    }
+                },
+                "focus_distance:" => match util::parse_number(tokens) {
+                    Ok(value) => {
+                        target = Some(FocusTarget::Distance(value));
+                    }
+                    Err(cause) => {
+                        return Err(cause);
+                    }
+                },
+                "focus_on:" => match tokens.next() {
+                    Some(name) => {
+                        target = Some(FocusTarget::Object(name.to_string()));
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "time:, focus_distance:, focus_on:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        match target {
+            Some(target) => Ok(FocusKeyframe::new(time, target)),
+            None => Err(ParsingError::MissingElement("focus_distance: or focus_on:")),
+        }
+    }
+}
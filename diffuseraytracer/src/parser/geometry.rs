@@ -2,10 +2,11 @@ use std::error::Error;
 use std::fmt::Debug;
 use std::str::FromStr;
 
-use crate::material::Material;
 use crate::{AxisAlignedBox, Cylinder, Disc, Plane, Sphere, Triangle};
-use cg_basics::scene_graph::RenderableGeometry;
+use cg_basics::material::InstanceAttributes;
+use cg_basics::scene_graph::{LightLinks, RenderableGeometry, RenderableMesh};
 use colors::RGB;
+use math::geometry::triangle::{Face3, Triangle3Mesh};
 use math::transform::Transform3;
 use math::{Normal3, Point2, Point3, Vector3};
 use traits::{ConvenientNumber, FloatingPoint, One, SignedNumber, Sqrt, Zero};
@@ -13,28 +14,31 @@ use units::angle::Degrees;
 use units::length::Length;
 
 use crate::parser::{
-    FromTokens, ParsingError, RenderableAxisAlignedBox, RenderableCylinder, RenderableDisc,
-    RenderablePlane, RenderableSphere, RenderableTriangle,
+    FromTokens, FromTokensWithMaterials, MaterialLibrary, MaterialType, ParsingError,
+    RenderableAxisAlignedBox, RenderableCylinder, RenderableDisc, RenderablePlane,
+    RenderableSphere, RenderableTriangle, RenderableTriangleMesh,
 };
 
 use crate::parser::{material, util};
 
-impl<T: Length> FromTokens for RenderableTriangle<T>
+impl<T: Length + 'static> FromTokensWithMaterials<T> for RenderableTriangle<T>
 where
-    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + 'static,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
     <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
     <T as Length>::AreaType: Sqrt<Output = T>,
     <T as FromStr>::Err: Error,
+    u16: Into<<T as Length>::ValueType>,
 {
-    type Err = ParsingError;
-
-    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+    fn from_tokens<'a>(
+        tokens: &mut impl Iterator<Item = &'a str>,
+        materials: &MaterialLibrary<T>,
+    ) -> Result<Self, ParsingError> {
         if let Err(cause) = util::check_next_token(tokens, "{") {
             return Err(ParsingError::TriangleParsingError(Box::new(cause)));
         }
 
-        let mut material: Option<Box<dyn Material<T, ColorType = RGB<<T as Length>::ValueType>>>> =
-            None;
+        let mut material: Option<MaterialType<T>> = None;
+        let mut attributes: Option<InstanceAttributes<RGB<T::ValueType>>> = None;
         let transform = Transform3::ident();
 
         let mut position: Vector3<T::ValueType> =
@@ -42,6 +46,11 @@ where
         let mut scale: Vector3<T::ValueType> = Vector3::new(One::one(), One::one(), One::one());
         let mut rotation: Vector3<Degrees<T::ValueType>> =
             Vector3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut shadow_bias: Option<T::ValueType> = None;
+        let mut epsilon: Option<T::ValueType> = None;
+        let mut motion: Option<Vector3<T::ValueType>> = None;
+        let mut light_links = LightLinks::All;
+        let mut name: Option<&str> = None;
 
         let mut a: Option<Point3<T>> = None;
         let mut b: Option<Point3<T>> = None;
@@ -130,7 +139,23 @@ where
                     }
                 },
 
-                "material:" => match material::parse_material(tokens) {
+                "name:" => match tokens.next() {
+                    Some(parsed_name) => {
+                        name = Some(parsed_name);
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "attributes:" => match InstanceAttributes::from_tokens(tokens) {
+                    Ok(parsed_attributes) => {
+                        attributes = Some(parsed_attributes);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::TriangleParsingError(Box::new(cause)));
+                    }
+                },
+                "material:" => match material::parse_material(tokens, materials) {
                     Ok(mat) => {
                         material = Some(mat);
                     }
@@ -162,12 +187,52 @@ where
                         return Err(ParsingError::TriangleParsingError(Box::new(cause)));
                     }
                 },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::TriangleParsingError(Box::new(cause)));
+                    }
+                },
+                "motion:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        motion = Some(vec);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::TriangleParsingError(Box::new(cause)));
+                    }
+                },
+                "epsilon:" => match util::parse_number(tokens) {
+                    Ok(value) => {
+                        epsilon = Some(value);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::TriangleParsingError(Box::new(cause)));
+                    }
+                },
+                "include_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Include(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::TriangleParsingError(Box::new(cause)));
+                    }
+                },
+                "exclude_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Exclude(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::TriangleParsingError(Box::new(cause)));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
-                        expected: "material:, position:, scale:, rotation:, }",
+                        expected: "material:, attributes:, position:, scale:, rotation:, shadow_bias:, epsilon:, motion:, include_lights:, exclude_lights:, name:, }",
                         found: token.to_string(),
                     });
                 }
@@ -217,9 +282,9 @@ where
             uvc.unwrap(),
         );
 
-        let triangle_geometry = RenderableGeometry::new(
+        let mut triangle_geometry = RenderableGeometry::new(
             triangle,
-            material.unwrap(),
+            material::with_attributes(material.unwrap(), attributes),
             transform
                 .translate(position.x, position.y, position.z)
                 .rotate_z(rotation.z)
@@ -228,25 +293,42 @@ where
                 .scale(scale.x, scale.y, scale.z),
         );
 
+        if let Some(shadow_bias) = shadow_bias {
+            triangle_geometry = triangle_geometry.with_shadow_bias(shadow_bias);
+        }
+        if let Some(epsilon) = epsilon {
+            triangle_geometry = triangle_geometry.with_epsilon(epsilon);
+        }
+        if let Some(motion) = motion {
+            triangle_geometry = triangle_geometry.with_motion(motion);
+        }
+        triangle_geometry = triangle_geometry.with_light_links(light_links);
+        if let Some(name) = name {
+            triangle_geometry = triangle_geometry.with_name(name);
+        }
+
         Ok(triangle_geometry)
     }
 }
 
-impl<T: Length + SignedNumber<T::ValueType>> FromTokens for RenderableAxisAlignedBox<T>
+impl<T: Length + SignedNumber<T::ValueType> + 'static> FromTokensWithMaterials<T>
+    for RenderableAxisAlignedBox<T>
 where
-    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + 'static,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
     <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
     <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
 {
-    type Err = ParsingError;
-
-    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+    fn from_tokens<'a>(
+        tokens: &mut impl Iterator<Item = &'a str>,
+        materials: &MaterialLibrary<T>,
+    ) -> Result<Self, ParsingError> {
         if let Err(cause) = util::check_next_token(tokens, "{") {
             return Err(ParsingError::BoxParsingError(Box::new(cause)));
         }
 
-        let mut material: Option<Box<dyn Material<T, ColorType = RGB<<T as Length>::ValueType>>>> =
-            None;
+        let mut material: Option<MaterialType<T>> = None;
+        let mut attributes: Option<InstanceAttributes<RGB<T::ValueType>>> = None;
         let transform = Transform3::ident();
 
         let mut position: Vector3<T::ValueType> =
@@ -254,10 +336,31 @@ where
         let mut scale: Vector3<T::ValueType> = Vector3::new(One::one(), One::one(), One::one());
         let mut rotation: Vector3<Degrees<T::ValueType>> =
             Vector3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut shadow_bias: Option<T::ValueType> = None;
+        let mut epsilon: Option<T::ValueType> = None;
+        let mut motion: Option<Vector3<T::ValueType>> = None;
+        let mut light_links = LightLinks::All;
+        let mut name: Option<&str> = None;
 
         while let Some(token) = tokens.next() {
             match token {
-                "material:" => match material::parse_material(tokens) {
+                "name:" => match tokens.next() {
+                    Some(parsed_name) => {
+                        name = Some(parsed_name);
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "attributes:" => match InstanceAttributes::from_tokens(tokens) {
+                    Ok(parsed_attributes) => {
+                        attributes = Some(parsed_attributes);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::BoxParsingError(Box::new(cause)));
+                    }
+                },
+                "material:" => match material::parse_material(tokens, materials) {
                     Ok(mat) => {
                         material = Some(mat);
                     }
@@ -289,12 +392,52 @@ where
                         return Err(ParsingError::BoxParsingError(Box::new(cause)));
                     }
                 },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::BoxParsingError(Box::new(cause)));
+                    }
+                },
+                "motion:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        motion = Some(vec);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::BoxParsingError(Box::new(cause)));
+                    }
+                },
+                "epsilon:" => match util::parse_number(tokens) {
+                    Ok(value) => {
+                        epsilon = Some(value);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::BoxParsingError(Box::new(cause)));
+                    }
+                },
+                "include_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Include(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::BoxParsingError(Box::new(cause)));
+                    }
+                },
+                "exclude_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Exclude(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::BoxParsingError(Box::new(cause)));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
-                        expected: "material:, position:, scale:, rotation:, }",
+                        expected: "material:, attributes:, position:, scale:, rotation:, shadow_bias:, epsilon:, motion:, include_lights:, exclude_lights:, name:, }",
                         found: token.to_string(),
                     });
                 }
@@ -310,9 +453,9 @@ where
             Point3::new(T::one(), T::one(), T::one()),
         );
 
-        let aab_geometry = RenderableGeometry::new(
+        let mut aab_geometry = RenderableGeometry::new(
             aab,
-            material.unwrap(),
+            material::with_attributes(material.unwrap(), attributes),
             transform
                 .translate(position.x, position.y, position.z)
                 .rotate_z(rotation.z)
@@ -321,25 +464,41 @@ where
                 .scale(scale.x, scale.y, scale.z),
         );
 
+        if let Some(shadow_bias) = shadow_bias {
+            aab_geometry = aab_geometry.with_shadow_bias(shadow_bias);
+        }
+        if let Some(epsilon) = epsilon {
+            aab_geometry = aab_geometry.with_epsilon(epsilon);
+        }
+        if let Some(motion) = motion {
+            aab_geometry = aab_geometry.with_motion(motion);
+        }
+        aab_geometry = aab_geometry.with_light_links(light_links);
+        if let Some(name) = name {
+            aab_geometry = aab_geometry.with_name(name);
+        }
+
         Ok(aab_geometry)
     }
 }
 
-impl<T: Length> FromTokens for RenderableDisc<T>
+impl<T: Length + 'static> FromTokensWithMaterials<T> for RenderableDisc<T>
 where
-    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + 'static,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
     <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
     <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
 {
-    type Err = ParsingError;
-
-    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+    fn from_tokens<'a>(
+        tokens: &mut impl Iterator<Item = &'a str>,
+        materials: &MaterialLibrary<T>,
+    ) -> Result<Self, ParsingError> {
         if let Err(cause) = util::check_next_token(tokens, "{") {
             return Err(ParsingError::PlaneParsingError(Box::new(cause)));
         }
 
-        let mut material: Option<Box<dyn Material<T, ColorType = RGB<<T as Length>::ValueType>>>> =
-            None;
+        let mut material: Option<MaterialType<T>> = None;
+        let mut attributes: Option<InstanceAttributes<RGB<T::ValueType>>> = None;
         let transform = Transform3::ident();
 
         let mut position: Vector3<T::ValueType> =
@@ -347,10 +506,31 @@ where
         let mut scale: Vector3<T::ValueType> = Vector3::new(One::one(), One::one(), One::one());
         let mut rotation: Vector3<Degrees<T::ValueType>> =
             Vector3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut shadow_bias: Option<T::ValueType> = None;
+        let mut epsilon: Option<T::ValueType> = None;
+        let mut motion: Option<Vector3<T::ValueType>> = None;
+        let mut light_links = LightLinks::All;
+        let mut name: Option<&str> = None;
 
         while let Some(token) = tokens.next() {
             match token {
-                "material:" => match material::parse_material(tokens) {
+                "name:" => match tokens.next() {
+                    Some(parsed_name) => {
+                        name = Some(parsed_name);
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "attributes:" => match InstanceAttributes::from_tokens(tokens) {
+                    Ok(parsed_attributes) => {
+                        attributes = Some(parsed_attributes);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::DiscParsingError(Box::new(cause)));
+                    }
+                },
+                "material:" => match material::parse_material(tokens, materials) {
                     Ok(mat) => {
                         material = Some(mat);
                     }
@@ -382,12 +562,53 @@ where
                         return Err(ParsingError::DiscParsingError(Box::new(cause)));
                     }
                 },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::DiscParsingError(Box::new(cause)));
+                    }
+                },
+                "motion:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        motion = Some(vec);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::DiscParsingError(Box::new(cause)));
+                    }
+                },
+                "epsilon:" => match util::parse_number(tokens) {
+                    Ok(value) => {
+                        epsilon = Some(value);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::DiscParsingError(Box::new(cause)));
+                    }
+                },
+                "include_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Include(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::DiscParsingError(Box::new(cause)));
+                    }
+                },
+                "exclude_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Exclude(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::DiscParsingError(Box::new(cause)));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
-                        expected: "radius:, material:, position:, scale:, rotation:, }",
+                        expected:
+                            "radius:, material:, attributes:, position:, scale:, rotation:, shadow_bias:, epsilon:, motion:, include_lights:, exclude_lights:, name:, }",
                         found: token.to_string(),
                     });
                 }
@@ -405,9 +626,9 @@ where
             One::one(),
         );
 
-        let disc_geometry = RenderableGeometry::new(
+        let mut disc_geometry = RenderableGeometry::new(
             disc,
-            material.unwrap(),
+            material::with_attributes(material.unwrap(), attributes),
             transform
                 .translate(position.x, position.y, position.z)
                 .rotate_z(rotation.z)
@@ -416,25 +637,41 @@ where
                 .scale(scale.x, scale.y, scale.z),
         );
 
+        if let Some(shadow_bias) = shadow_bias {
+            disc_geometry = disc_geometry.with_shadow_bias(shadow_bias);
+        }
+        if let Some(epsilon) = epsilon {
+            disc_geometry = disc_geometry.with_epsilon(epsilon);
+        }
+        if let Some(motion) = motion {
+            disc_geometry = disc_geometry.with_motion(motion);
+        }
+        disc_geometry = disc_geometry.with_light_links(light_links);
+        if let Some(name) = name {
+            disc_geometry = disc_geometry.with_name(name);
+        }
+
         Ok(disc_geometry)
     }
 }
 
-impl<T: Length> FromTokens for RenderablePlane<T>
+impl<T: Length + 'static> FromTokensWithMaterials<T> for RenderablePlane<T>
 where
-    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + 'static,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
     <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
     <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
 {
-    type Err = ParsingError;
-
-    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+    fn from_tokens<'a>(
+        tokens: &mut impl Iterator<Item = &'a str>,
+        materials: &MaterialLibrary<T>,
+    ) -> Result<Self, ParsingError> {
         if let Err(cause) = util::check_next_token(tokens, "{") {
             return Err(ParsingError::PlaneParsingError(Box::new(cause)));
         }
 
-        let mut material: Option<Box<dyn Material<T, ColorType = RGB<<T as Length>::ValueType>>>> =
-            None;
+        let mut material: Option<MaterialType<T>> = None;
+        let mut attributes: Option<InstanceAttributes<RGB<T::ValueType>>> = None;
         let transform = Transform3::ident();
 
         let mut position: Vector3<T::ValueType> =
@@ -442,10 +679,31 @@ where
         let mut scale: Vector3<T::ValueType> = Vector3::new(One::one(), One::one(), One::one());
         let mut rotation: Vector3<Degrees<T::ValueType>> =
             Vector3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut shadow_bias: Option<T::ValueType> = None;
+        let mut epsilon: Option<T::ValueType> = None;
+        let mut motion: Option<Vector3<T::ValueType>> = None;
+        let mut light_links = LightLinks::All;
+        let mut name: Option<&str> = None;
 
         while let Some(token) = tokens.next() {
             match token {
-                "material:" => match material::parse_material(tokens) {
+                "name:" => match tokens.next() {
+                    Some(parsed_name) => {
+                        name = Some(parsed_name);
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "attributes:" => match InstanceAttributes::from_tokens(tokens) {
+                    Ok(parsed_attributes) => {
+                        attributes = Some(parsed_attributes);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PlaneParsingError(Box::new(cause)));
+                    }
+                },
+                "material:" => match material::parse_material(tokens, materials) {
                     Ok(mat) => {
                         material = Some(mat);
                     }
@@ -477,12 +735,52 @@ where
                         return Err(ParsingError::PlaneParsingError(Box::new(cause)));
                     }
                 },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PlaneParsingError(Box::new(cause)));
+                    }
+                },
+                "motion:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        motion = Some(vec);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PlaneParsingError(Box::new(cause)));
+                    }
+                },
+                "epsilon:" => match util::parse_number(tokens) {
+                    Ok(value) => {
+                        epsilon = Some(value);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PlaneParsingError(Box::new(cause)));
+                    }
+                },
+                "include_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Include(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PlaneParsingError(Box::new(cause)));
+                    }
+                },
+                "exclude_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Exclude(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PlaneParsingError(Box::new(cause)));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
-                        expected: "material:, position:, scale:, rotation:, }",
+                        expected: "material:, attributes:, position:, scale:, rotation:, shadow_bias:, epsilon:, motion:, include_lights:, exclude_lights:, name:, }",
                         found: token.to_string(),
                     });
                 }
@@ -499,9 +797,9 @@ where
             Vector3::new(One::one(), Zero::zero(), Zero::zero()),
         );
 
-        let plane_geometry = RenderableGeometry::new(
+        let mut plane_geometry = RenderableGeometry::new(
             plane,
-            material.unwrap(),
+            material::with_attributes(material.unwrap(), attributes),
             transform
                 .translate(position.x, position.y, position.z)
                 .rotate_z(rotation.z)
@@ -510,25 +808,41 @@ where
                 .scale(scale.x, scale.y, scale.z),
         );
 
+        if let Some(shadow_bias) = shadow_bias {
+            plane_geometry = plane_geometry.with_shadow_bias(shadow_bias);
+        }
+        if let Some(epsilon) = epsilon {
+            plane_geometry = plane_geometry.with_epsilon(epsilon);
+        }
+        if let Some(motion) = motion {
+            plane_geometry = plane_geometry.with_motion(motion);
+        }
+        plane_geometry = plane_geometry.with_light_links(light_links);
+        if let Some(name) = name {
+            plane_geometry = plane_geometry.with_name(name);
+        }
+
         Ok(plane_geometry)
     }
 }
 
-impl<T: Length> FromTokens for RenderableSphere<T>
+impl<T: Length + 'static> FromTokensWithMaterials<T> for RenderableSphere<T>
 where
-    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + 'static,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
     <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
     <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
 {
-    type Err = ParsingError;
-
-    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+    fn from_tokens<'a>(
+        tokens: &mut impl Iterator<Item = &'a str>,
+        materials: &MaterialLibrary<T>,
+    ) -> Result<Self, ParsingError> {
         if let Err(cause) = util::check_next_token(tokens, "{") {
             return Err(ParsingError::SphereParsingError(Box::new(cause)));
         }
 
-        let mut material: Option<Box<dyn Material<T, ColorType = RGB<<T as Length>::ValueType>>>> =
-            None;
+        let mut material: Option<MaterialType<T>> = None;
+        let mut attributes: Option<InstanceAttributes<RGB<T::ValueType>>> = None;
         let transform = Transform3::ident();
 
         let mut position: Vector3<T::ValueType> =
@@ -536,10 +850,31 @@ where
         let mut scale: Vector3<T::ValueType> = Vector3::new(One::one(), One::one(), One::one());
         let mut rotation: Vector3<Degrees<T::ValueType>> =
             Vector3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut shadow_bias: Option<T::ValueType> = None;
+        let mut epsilon: Option<T::ValueType> = None;
+        let mut motion: Option<Vector3<T::ValueType>> = None;
+        let mut light_links = LightLinks::All;
+        let mut name: Option<&str> = None;
 
         while let Some(token) = tokens.next() {
             match token {
-                "material:" => match material::parse_material(tokens) {
+                "name:" => match tokens.next() {
+                    Some(parsed_name) => {
+                        name = Some(parsed_name);
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "attributes:" => match InstanceAttributes::from_tokens(tokens) {
+                    Ok(parsed_attributes) => {
+                        attributes = Some(parsed_attributes);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphereParsingError(Box::new(cause)));
+                    }
+                },
+                "material:" => match material::parse_material(tokens, materials) {
                     Ok(mat) => {
                         material = Some(mat);
                     }
@@ -571,12 +906,52 @@ where
                         return Err(ParsingError::SphereParsingError(Box::new(cause)));
                     }
                 },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphereParsingError(Box::new(cause)));
+                    }
+                },
+                "motion:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        motion = Some(vec);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphereParsingError(Box::new(cause)));
+                    }
+                },
+                "epsilon:" => match util::parse_number(tokens) {
+                    Ok(value) => {
+                        epsilon = Some(value);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphereParsingError(Box::new(cause)));
+                    }
+                },
+                "include_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Include(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphereParsingError(Box::new(cause)));
+                    }
+                },
+                "exclude_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Exclude(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphereParsingError(Box::new(cause)));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
-                        expected: "material:, position:, scale:, rotation:, }",
+                        expected: "material:, attributes:, position:, scale:, rotation:, shadow_bias:, epsilon:, motion:, include_lights:, exclude_lights:, name:, }",
                         found: token.to_string(),
                     });
                 }
@@ -591,9 +966,9 @@ where
             Point3::new(Zero::zero(), Zero::zero(), Zero::zero()),
             One::one(),
         );
-        let sphere_geometry = RenderableGeometry::new(
+        let mut sphere_geometry = RenderableGeometry::new(
             sphere,
-            material.unwrap(),
+            material::with_attributes(material.unwrap(), attributes),
             transform
                 .translate(position.x, position.y, position.z)
                 .rotate_z(rotation.z)
@@ -602,25 +977,41 @@ where
                 .scale(scale.x, scale.y, scale.z),
         );
 
+        if let Some(shadow_bias) = shadow_bias {
+            sphere_geometry = sphere_geometry.with_shadow_bias(shadow_bias);
+        }
+        if let Some(epsilon) = epsilon {
+            sphere_geometry = sphere_geometry.with_epsilon(epsilon);
+        }
+        if let Some(motion) = motion {
+            sphere_geometry = sphere_geometry.with_motion(motion);
+        }
+        sphere_geometry = sphere_geometry.with_light_links(light_links);
+        if let Some(name) = name {
+            sphere_geometry = sphere_geometry.with_name(name);
+        }
+
         Ok(sphere_geometry)
     }
 }
 
-impl<T: Length> FromTokens for RenderableCylinder<T>
+impl<T: Length + 'static> FromTokensWithMaterials<T> for RenderableCylinder<T>
 where
-    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + 'static,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
     <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
     <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
 {
-    type Err = ParsingError;
-
-    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+    fn from_tokens<'a>(
+        tokens: &mut impl Iterator<Item = &'a str>,
+        materials: &MaterialLibrary<T>,
+    ) -> Result<Self, ParsingError> {
         if let Err(cause) = util::check_next_token(tokens, "{") {
             return Err(ParsingError::SphereParsingError(Box::new(cause)));
         }
 
-        let mut material: Option<Box<dyn Material<T, ColorType = RGB<<T as Length>::ValueType>>>> =
-            None;
+        let mut material: Option<MaterialType<T>> = None;
+        let mut attributes: Option<InstanceAttributes<RGB<T::ValueType>>> = None;
         let transform = Transform3::ident();
 
         let mut position: Vector3<T::ValueType> =
@@ -628,10 +1019,31 @@ where
         let mut scale: Vector3<T::ValueType> = Vector3::new(One::one(), One::one(), One::one());
         let mut rotation: Vector3<Degrees<T::ValueType>> =
             Vector3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut shadow_bias: Option<T::ValueType> = None;
+        let mut epsilon: Option<T::ValueType> = None;
+        let mut motion: Option<Vector3<T::ValueType>> = None;
+        let mut light_links = LightLinks::All;
+        let mut name: Option<&str> = None;
 
         while let Some(token) = tokens.next() {
             match token {
-                "material:" => match material::parse_material(tokens) {
+                "name:" => match tokens.next() {
+                    Some(parsed_name) => {
+                        name = Some(parsed_name);
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "attributes:" => match InstanceAttributes::from_tokens(tokens) {
+                    Ok(parsed_attributes) => {
+                        attributes = Some(parsed_attributes);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylinderParsingError(Box::new(cause)));
+                    }
+                },
+                "material:" => match material::parse_material(tokens, materials) {
                     Ok(mat) => {
                         material = Some(mat);
                     }
@@ -663,12 +1075,52 @@ where
                         return Err(ParsingError::CylinderParsingError(Box::new(cause)));
                     }
                 },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylinderParsingError(Box::new(cause)));
+                    }
+                },
+                "motion:" => match Vector3::from_tokens(tokens) {
+                    Ok(vec) => {
+                        motion = Some(vec);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylinderParsingError(Box::new(cause)));
+                    }
+                },
+                "epsilon:" => match util::parse_number(tokens) {
+                    Ok(value) => {
+                        epsilon = Some(value);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylinderParsingError(Box::new(cause)));
+                    }
+                },
+                "include_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Include(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylinderParsingError(Box::new(cause)));
+                    }
+                },
+                "exclude_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Exclude(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::CylinderParsingError(Box::new(cause)));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
-                        expected: "material:, position:, scale:, rotation:, }",
+                        expected: "material:, attributes:, position:, scale:, rotation:, shadow_bias:, epsilon:, motion:, include_lights:, exclude_lights:, name:, }",
                         found: token.to_string(),
                     });
                 }
@@ -684,9 +1136,9 @@ where
             One::one(),
             One::one(),
         );
-        let cylinder_geometry = RenderableGeometry::new(
+        let mut cylinder_geometry = RenderableGeometry::new(
             cylinder,
-            material.unwrap(),
+            material::with_attributes(material.unwrap(), attributes),
             transform
                 .translate(position.x, position.y, position.z)
                 .rotate_z(rotation.z)
@@ -695,6 +1147,193 @@ where
                 .scale(scale.x, scale.y, scale.z),
         );
 
+        if let Some(shadow_bias) = shadow_bias {
+            cylinder_geometry = cylinder_geometry.with_shadow_bias(shadow_bias);
+        }
+        if let Some(epsilon) = epsilon {
+            cylinder_geometry = cylinder_geometry.with_epsilon(epsilon);
+        }
+        if let Some(motion) = motion {
+            cylinder_geometry = cylinder_geometry.with_motion(motion);
+        }
+        cylinder_geometry = cylinder_geometry.with_light_links(light_links);
+        if let Some(name) = name {
+            cylinder_geometry = cylinder_geometry.with_name(name);
+        }
+
         Ok(cylinder_geometry)
     }
 }
+
+// The vertices of a mesh are given in world coordinates, each face is flat shaded.
+//
+// mesh {
+//     vertices: 3 0.0 0.0 0.0 1.0 0.0 0.0 0.0 1.0 0.0
+//     faces: 1 0 1 2
+//     material: ...
+// }
+impl<T: Length + 'static> FromTokensWithMaterials<T> for RenderableTriangleMesh<T>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    <T as FromStr>::Err: Error,
+    u16: Into<<T as Length>::ValueType>,
+{
+    fn from_tokens<'a>(
+        tokens: &mut impl Iterator<Item = &'a str>,
+        materials: &MaterialLibrary<T>,
+    ) -> Result<Self, ParsingError> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::MeshParsingError(Box::new(cause)));
+        }
+
+        let mut material: Option<MaterialType<T>> = None;
+        let mut attributes: Option<InstanceAttributes<RGB<T::ValueType>>> = None;
+        let mut shadow_bias: Option<T::ValueType> = None;
+        let mut epsilon: Option<T::ValueType> = None;
+        let mut light_links = LightLinks::All;
+        let mut vertices: Vec<Point3<T>> = Vec::new();
+        let mut faces: Vec<[usize; 3]> = Vec::new();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "vertices:" => {
+                    let count: usize = match util::parse_number(tokens) {
+                        Ok(count) => count,
+                        Err(cause) => {
+                            return Err(ParsingError::MeshParsingError(Box::new(cause)));
+                        }
+                    };
+                    for _ in 0..count {
+                        match Point3::from_tokens(tokens) {
+                            Ok(point) => vertices.push(point),
+                            Err(cause) => {
+                                return Err(ParsingError::MeshParsingError(Box::new(cause)));
+                            }
+                        }
+                    }
+                }
+                "faces:" => {
+                    let count: usize = match util::parse_number(tokens) {
+                        Ok(count) => count,
+                        Err(cause) => {
+                            return Err(ParsingError::MeshParsingError(Box::new(cause)));
+                        }
+                    };
+                    for _ in 0..count {
+                        let mut face = [0; 3];
+                        for index in face.iter_mut() {
+                            match util::parse_number(tokens) {
+                                Ok(i) => *index = i,
+                                Err(cause) => {
+                                    return Err(ParsingError::MeshParsingError(Box::new(cause)));
+                                }
+                            }
+                        }
+                        faces.push(face);
+                    }
+                }
+                "attributes:" => match InstanceAttributes::from_tokens(tokens) {
+                    Ok(parsed_attributes) => {
+                        attributes = Some(parsed_attributes);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::MeshParsingError(Box::new(cause)));
+                    }
+                },
+                "material:" => match material::parse_material(tokens, materials) {
+                    Ok(mat) => {
+                        material = Some(mat);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::MeshParsingError(Box::new(cause)));
+                    }
+                },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::MeshParsingError(Box::new(cause)));
+                    }
+                },
+                "epsilon:" => match util::parse_number(tokens) {
+                    Ok(value) => {
+                        epsilon = Some(value);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::MeshParsingError(Box::new(cause)));
+                    }
+                },
+                "include_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Include(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::MeshParsingError(Box::new(cause)));
+                    }
+                },
+                "exclude_lights:" => match util::parse_names(tokens) {
+                    Ok(names) => {
+                        light_links = LightLinks::Exclude(names);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::MeshParsingError(Box::new(cause)));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "vertices:, faces:, material:, attributes:, shadow_bias:, epsilon:, include_lights:, exclude_lights:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        if material.is_none() {
+            return Err(ParsingError::MissingElement("material"));
+        }
+        if faces.is_empty() {
+            return Err(ParsingError::MissingElement("faces"));
+        }
+        if let Some(index) = faces.iter().flatten().find(|i| **i >= vertices.len()) {
+            return Err(ParsingError::MeshParsingError(Box::new(
+                ParsingError::UnsupportedElement(format!("vertex index {}", index)),
+            )));
+        }
+
+        let normals = faces
+            .iter()
+            .map(|[a, b, c]| {
+                let ab = (vertices[*b] - vertices[*a]) / T::one();
+                let ac = (vertices[*c] - vertices[*a]) / T::one();
+                Vector3::cross(ab, ac).normalized().as_normal()
+            })
+            .collect();
+        let faces = faces
+            .iter()
+            .enumerate()
+            .map(|(n, [a, b, c])| Face3::new(*a, *b, *c, n, n, n, 0, 0, 0))
+            .collect();
+        let uvs = vec![Point2::new(Zero::zero(), Zero::zero())];
+
+        let mut mesh_geometry = RenderableMesh::new(
+            Triangle3Mesh::new(vertices, normals, uvs, faces),
+            material::with_attributes(material.unwrap(), attributes),
+        );
+
+        if let Some(shadow_bias) = shadow_bias {
+            mesh_geometry = mesh_geometry.with_shadow_bias(shadow_bias);
+        }
+        if let Some(epsilon) = epsilon {
+            mesh_geometry = mesh_geometry.with_epsilon(epsilon);
+        }
+        mesh_geometry = mesh_geometry.with_light_links(light_links);
+
+        Ok(mesh_geometry)
+    }
+}
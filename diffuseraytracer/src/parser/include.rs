@@ -0,0 +1,261 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::parser::assets::AssetResolver;
+use crate::parser::location::{locate, Location, SceneTokens};
+use crate::parser::tokenizer::Tokenizer;
+use crate::parser::{is_toml, toml, ParsingError};
+
+// The directive that splices the tokens of another scene file into a scene, e.g. a library of
+// materials shared by several scenes:
+//
+//     include "materials.scene"
+//
+// The name is looked up like an asset, next to the including file first, then in the include
+// directories. The quotes are optional. The name has to be relative and must not leave the
+// directory it is looked up in, so a scene sent to a render server can not include other files
+// of the machine.
+pub const INCLUDE: &str = "include";
+
+// The tokens of a scene file with the names of its assets resolved and all included files
+// spliced in. TOML scenes are translated into the tokens of scene files. A file that includes
+// itself, directly or by other files, is an error. Errors are located in the file they are found
+// in.
+pub fn scene_tokens(
+    filename: &str,
+    content: &str,
+    include_dirs: &[PathBuf],
+) -> Result<SceneTokens, ParsingError> {
+    let mut scene = SceneTokens::new();
+    let mut including = vec![canonical(Path::new(filename))];
+    file_tokens(filename, content, include_dirs, &mut including, &mut scene)?;
+    Ok(scene)
+}
+
+// The name of a file after an include directive.
+pub fn included_name(token: &str) -> &str {
+    token
+        .strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+        .unwrap_or(token)
+}
+
+fn file_tokens(
+    filename: &str,
+    content: &str,
+    include_dirs: &[PathBuf],
+    including: &mut Vec<PathBuf>,
+    scene: &mut SceneTokens,
+) -> Result<(), ParsingError> {
+    let at = |(line, column)| Some(Location::new(filename, content, line, column));
+    let (tokens, positions): (Vec<String>, Vec<(usize, usize)>) = if is_toml(filename) {
+        match toml::tokens(content) {
+            Ok(tokens) => tokens.into_iter().unzip(),
+            Err(error) => {
+                return Err(locate(
+                    ParsingError::TomlParsingError(error.message),
+                    at((error.line, error.column)),
+                ))
+            }
+        }
+    } else {
+        let mut tokenizer = Tokenizer::new(content);
+        let tokens: Vec<_> = tokenizer.by_ref().collect();
+        if let Some(position) = tokenizer.unterminated_comment() {
+            return Err(locate(ParsingError::UnterminatedComment, at(position)));
+        }
+        tokens
+            .into_iter()
+            .map(|token| (token.text.to_string(), (token.line, token.column)))
+            .unzip()
+    };
+
+    let resolver = AssetResolver::new(filename, include_dirs);
+    let tokens = resolver.resolve_tokens(tokens.iter().map(String::as_str));
+    let file = scene.add_file(filename, content);
+
+    let mut tokens = tokens.into_iter().zip(positions);
+    while let Some((token, (line, column))) = tokens.next() {
+        if token != INCLUDE {
+            scene.push(token, file, line, column);
+            continue;
+        }
+
+        let Some((name, position)) = tokens.next() else {
+            return Err(locate(
+                ParsingError::UnexpectedEndOfTokens,
+                at((line, column)),
+            ));
+        };
+        let name = included_name(&name);
+        let confined = Path::new(name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !confined {
+            let cause = ParsingError::IncludeOutsideDirectory(name.to_string());
+            return Err(locate(cause, at(position)));
+        }
+        let Some(path) = resolver.resolve(name) else {
+            let cause = ParsingError::IncludeLoadingError(name.to_string());
+            return Err(locate(cause, at(position)));
+        };
+        let canonical_path = canonical(&path);
+        if including.contains(&canonical_path) {
+            return Err(locate(
+                ParsingError::IncludeCycle(name.to_string()),
+                at(position),
+            ));
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            let cause = ParsingError::IncludeLoadingError(name.to_string());
+            return Err(locate(cause, at(position)));
+        };
+
+        including.push(canonical_path);
+        file_tokens(
+            &path.to_string_lossy(),
+            &content,
+            include_dirs,
+            including,
+            scene,
+        )?;
+        including.pop();
+    }
+    Ok(())
+}
+
+// The same file can be named in different ways, e.g. with a relative and an absolute path.
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    fn located(error: ParsingError) -> (Location, ParsingError) {
+        match error {
+            ParsingError::LocatedParsingError(location, cause) => (location, *cause),
+            error => panic!("The error {:?} is not located.", error),
+        }
+    }
+
+    #[test]
+    fn splice_included_files() {
+        let root = env::temp_dir().join("splice_included_files");
+        let _ = fs::remove_dir_all(&root);
+        let library = root.join("library");
+        fs::create_dir_all(root.join("parts")).unwrap();
+        fs::create_dir_all(&library).unwrap();
+
+        fs::write(
+            library.join("materials.scene"),
+            "materials { red: unshaded_material { } }\n",
+        )
+        .unwrap();
+        fs::write(root.join("parts/sky.hdr"), "sky").unwrap();
+        fs::write(
+            root.join("parts/set.scene"),
+            "include materials.scene\nenvironment_light { image: sky.hdr }\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("parts/lights.toml"),
+            "[[point_light]]\nposition = [0, 5, 0]\n",
+        )
+        .unwrap();
+
+        let scene = root.join("test.scene");
+        let content = "include \"parts/set.scene\"\n\
+                       sphere { material: red }\n\
+                       include parts/lights.toml\n";
+        let tokens = scene_tokens(scene.to_str().unwrap(), content, &[library]).unwrap();
+
+        let sky = root.join("parts/sky.hdr");
+        let expected = format!(
+            "materials {{ red: unshaded_material {{ }} }} \
+             environment_light {{ image: {} }} \
+             sphere {{ material: red }} \
+             point_light {{ position: 0 5 0 }}",
+            sky.to_str().unwrap()
+        );
+        assert_eq!(tokens.tokens(), expected.split(' ').collect::<Vec<_>>());
+
+        // The tokens are located in the files they are written in.
+        let location = tokens.location(7).unwrap();
+        assert_eq!(
+            location.file,
+            root.join("parts/set.scene").to_str().unwrap()
+        );
+        assert_eq!((location.line, location.column), (2, 1));
+        assert_eq!(location.token, "environment_light");
+        let location = tokens.location(12).unwrap();
+        assert_eq!((location.line, location.column), (2, 1));
+        assert_eq!(location.snippet, "sphere { material: red }");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn reject_include_cycles_and_missing_files() {
+        let root = env::temp_dir().join("reject_include_cycles_and_missing_files");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let a = root.join("a.scene");
+        fs::write(&a, "include b.scene").unwrap();
+        fs::write(root.join("b.scene"), "include ./a.scene").unwrap();
+        fs::write(root.join("c.scene"), "include missing.scene").unwrap();
+
+        let error = scene_tokens(a.to_str().unwrap(), "include b.scene", &[]).unwrap_err();
+        let (location, cause) = located(error);
+        assert!(matches!(cause, ParsingError::IncludeCycle(name) if name == "./a.scene"));
+        assert_eq!(location.file, root.join("b.scene").to_str().unwrap());
+        assert_eq!((location.line, location.column), (1, 9));
+
+        let error = scene_tokens(a.to_str().unwrap(), "include c.scene", &[]).unwrap_err();
+        let (location, cause) = located(error);
+        assert!(
+            matches!(cause, ParsingError::IncludeLoadingError(name) if name == "missing.scene")
+        );
+        assert_eq!(location.token, "missing.scene");
+
+        let error = scene_tokens(a.to_str().unwrap(), "sphere { } include", &[]).unwrap_err();
+        let (location, cause) = located(error);
+        assert!(matches!(cause, ParsingError::UnexpectedEndOfTokens));
+        assert_eq!((location.line, location.column), (1, 12));
+
+        let error = scene_tokens(a.to_str().unwrap(), "sphere {\n/* }", &[]).unwrap_err();
+        let (location, cause) = located(error);
+        assert!(matches!(cause, ParsingError::UnterminatedComment));
+        assert_eq!((location.line, location.column), (2, 1));
+
+        let toml = root.join("e.toml");
+        let error = scene_tokens(toml.to_str().unwrap(), "a = [1 2]", &[]).unwrap_err();
+        let (location, cause) = located(error);
+        assert!(matches!(cause, ParsingError::TomlParsingError(_)));
+        assert_eq!(
+            (location.line, location.column, location.token.as_str()),
+            (1, 8, "2]")
+        );
+
+        // Included files stay in the directories they are looked up in.
+        for name in ["/etc/passwd", "../a.scene", "parts/../../a.scene"] {
+            let content = format!("include {}", name);
+            let error = scene_tokens(a.to_str().unwrap(), &content, &[]).unwrap_err();
+            let (location, cause) = located(error);
+            assert!(matches!(cause, ParsingError::IncludeOutsideDirectory(n) if n == name));
+            assert_eq!((location.line, location.column), (1, 9));
+        }
+
+        // The same file can be included more than once, if it does not include itself.
+        fs::write(root.join("d.scene"), "sphere { }").unwrap();
+        let tokens =
+            scene_tokens(a.to_str().unwrap(), "include d.scene include d.scene", &[]).unwrap();
+        assert_eq!(tokens.tokens(), ["sphere", "{", "}", "sphere", "{", "}"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,24 +1,35 @@
 use std::error::Error;
 use std::fmt::Debug;
+use std::fs;
 use std::str::FromStr;
 
-use cg_basics::light::{AmbientOcclusionLight, PointLight, SpotLight};
+use cg_basics::ies::{self, IesProfile};
+use cg_basics::light::{
+    AmbientOcclusionLight, AreaLight, EnvironmentLight, Falloff, LightIntensity, PointLight,
+    SphereLight, SpotLight,
+};
+use cg_basics::sky::PreethamSky;
 use colors::RGB;
+use image::ImageBuffer;
 use math::{Point3, Vector3};
 use traits::floating_point::ToRadians;
-use traits::{FloatingPoint, SignedNumber, Sqrt, Zero};
+use traits::{ConvenientNumber, Exp, FloatingPoint, SignedNumber, Sqrt, Zero};
 use units::angle::Degrees;
 use units::length::Length;
+use units::photometry::{Candela, Lumen};
+use units::radiometry::{Watt, WattPerSteradian};
 
+use crate::parser::texture;
 use crate::parser::util;
-use crate::parser::{FromTokens, ParsingError};
+use crate::parser::{FromTokens, ParsingError, TextureType};
 
 impl<T: Length> FromTokens for SpotLight<T, RGB<<T as Length>::ValueType>>
 where
     <T as Length>::AreaType: Sqrt<Output = T>,
-    <T as Length>::ValueType: FloatingPoint,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + From<f32> + 'static,
     <T as FromStr>::Err: Error + Debug,
     <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+    u16: Into<<T as Length>::ValueType>,
 {
     type Err = ParsingError;
 
@@ -31,6 +42,14 @@ where
         let mut position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
         let mut direction: Option<Vector3<<T as Length>::ValueType>> = None;
         let mut angle: Option<Degrees<<T as Length>::ValueType>> = None;
+        let mut inner_angle: Option<Degrees<<T as Length>::ValueType>> = None;
+        let mut falloff_exponent: Option<<T as Length>::ValueType> = None;
+        let mut intensity: Option<LightIntensity<<T as Length>::ValueType>> = None;
+        let mut profile: Option<IesProfile<<T as Length>::ValueType>> = None;
+        let mut gobo: Option<TextureType<<T as Length>::ValueType>> = None;
+        let mut shadow_bias: Option<<T as Length>::ValueType> = None;
+        let mut name: Option<String> = None;
+        let mut group: Option<String> = None;
 
         while let Some(token) = tokens.next() {
             match token {
@@ -60,36 +79,136 @@ where
                     }
                 },
                 "angle:" => match tokens.next() {
-                    Some(angle_string) => match angle_string.parse() {
-                        Ok(a) => angle = Some(a),
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse field of number.",
-                            ));
+                    Some(angle_string) => {
+                        match util::parse_token(angle_string, "Unable to parse field of number.") {
+                            Ok(a) => angle = Some(a),
+                            Err(cause) => {
+                                return Err(cause);
+                            }
                         }
-                    },
+                    }
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
                 },
+                "inner_angle:" => match util::parse_number(tokens) {
+                    Ok(a) => {
+                        inner_angle = Some(Degrees::new(a));
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SpotLightParsingError(Box::new(cause)));
+                    }
+                },
+                "falloff_exponent:" => match util::parse_number(tokens) {
+                    Ok(exponent) => {
+                        falloff_exponent = Some(exponent);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SpotLightParsingError(Box::new(cause)));
+                    }
+                },
+                "intensity:" => match LightIntensity::from_tokens(tokens) {
+                    Ok(i) => {
+                        intensity = Some(i);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SpotLightParsingError(Box::new(cause)));
+                    }
+                },
+                "profile:" => match load_ies_profile(tokens) {
+                    Ok(p) => {
+                        profile = Some(p);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SpotLightParsingError(Box::new(cause)));
+                    }
+                },
+                "gobo:" => match texture::parse_texture(tokens) {
+                    Ok(texture) => {
+                        gobo = Some(texture);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SpotLightParsingError(Box::new(cause)));
+                    }
+                },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SpotLightParsingError(Box::new(cause)));
+                    }
+                },
+                "name:" => match tokens.next() {
+                    Some(token) => {
+                        name = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::SpotLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
+                "group:" => match tokens.next() {
+                    Some(token) => {
+                        group = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::SpotLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
-                        expected: "color:, position:, }",
+                        expected: "color:, intensity:, position:, direction:, angle:, inner_angle:, falloff_exponent:, profile:, gobo:, shadow_bias:, name:, group:, }",
                         found: token.to_string(),
                     });
                 }
             }
         }
-        let spot_light = SpotLight::new(
+        let mut spot_light = SpotLight::new(
             color,
             position,
             direction.unwrap(),
             angle.unwrap().to_radians(),
         );
 
+        if let Some(intensity) = intensity {
+            spot_light = spot_light.with_intensity(intensity);
+        }
+
+        if let Some(inner_angle) = inner_angle {
+            spot_light = spot_light.with_inner_angle(inner_angle.to_radians());
+        }
+
+        if let Some(falloff_exponent) = falloff_exponent {
+            spot_light = spot_light.with_falloff_exponent(falloff_exponent);
+        }
+
+        if let Some(profile) = profile {
+            spot_light = spot_light.with_profile(profile);
+        }
+
+        if let Some(gobo) = gobo {
+            spot_light = spot_light.with_gobo(gobo);
+        }
+
+        if let Some(shadow_bias) = shadow_bias {
+            spot_light = spot_light.with_shadow_bias(shadow_bias);
+        }
+
+        if let Some(name) = name {
+            spot_light = spot_light.with_name(name);
+        }
+
+        if let Some(group) = group {
+            spot_light = spot_light.with_group(group);
+        }
+
         Ok(spot_light)
     }
 }
@@ -97,9 +216,10 @@ where
 impl<T: Length> FromTokens for PointLight<T, RGB<<T as Length>::ValueType>>
 where
     <T as Length>::AreaType: Sqrt<Output = T>,
-    <T as Length>::ValueType: SignedNumber,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber,
     <T as FromStr>::Err: Error + Debug,
     <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+    u16: Into<<T as Length>::ValueType>,
 {
     type Err = ParsingError;
 
@@ -110,6 +230,13 @@ where
 
         let mut color = RGB::new(Zero::zero(), Zero::zero(), Zero::zero());
         let mut position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut shadow_bias: Option<<T as Length>::ValueType> = None;
+        let mut name: Option<String> = None;
+        let mut group: Option<String> = None;
+        let mut falloff = Falloff::None;
+        let mut radius: Option<<T as Length>::ValueType> = None;
+        let mut intensity: Option<LightIntensity<<T as Length>::ValueType>> = None;
+        let mut profile: Option<IesProfile<<T as Length>::ValueType>> = None;
 
         while let Some(token) = tokens.next() {
             match token {
@@ -130,19 +257,571 @@ where
                         return Err(ParsingError::PointLightParsingError(Box::new(cause)));
                     }
                 },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PointLightParsingError(Box::new(cause)));
+                    }
+                },
+                "name:" => match tokens.next() {
+                    Some(token) => {
+                        name = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::PointLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
+                "group:" => match tokens.next() {
+                    Some(token) => {
+                        group = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::PointLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
+                "falloff:" => match Falloff::from_tokens(tokens) {
+                    Ok(f) => {
+                        falloff = f;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PointLightParsingError(Box::new(cause)));
+                    }
+                },
+                "radius:" => match util::parse_number(tokens) {
+                    Ok(r) => {
+                        radius = Some(r);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PointLightParsingError(Box::new(cause)));
+                    }
+                },
+                "intensity:" => match LightIntensity::from_tokens(tokens) {
+                    Ok(i) => {
+                        intensity = Some(i);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PointLightParsingError(Box::new(cause)));
+                    }
+                },
+                "profile:" => match load_ies_profile(tokens) {
+                    Ok(p) => {
+                        profile = Some(p);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PointLightParsingError(Box::new(cause)));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "color:, intensity:, position:, shadow_bias:, name:, group:, falloff:, radius:, profile:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        let mut point_light = PointLight::new(color, position).with_falloff(falloff);
+
+        if let Some(intensity) = intensity {
+            point_light = point_light.with_intensity(intensity);
+        }
+
+        if let Some(shadow_bias) = shadow_bias {
+            point_light = point_light.with_shadow_bias(shadow_bias);
+        }
+
+        if let Some(name) = name {
+            point_light = point_light.with_name(name);
+        }
+
+        if let Some(group) = group {
+            point_light = point_light.with_group(group);
+        }
+        if let Some(radius) = radius {
+            point_light = point_light.with_radius(radius);
+        }
+        if let Some(profile) = profile {
+            point_light = point_light.with_profile(profile);
+        }
+
+        Ok(point_light)
+    }
+}
+
+// falloff: none | inverse_square | smooth
+impl FromTokens for Falloff {
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        match tokens.next() {
+            Some("none") => Ok(Falloff::None),
+            Some("inverse_square") => Ok(Falloff::InverseSquare),
+            Some("smooth") => Ok(Falloff::Smooth),
+            Some(token) => Err(ParsingError::UnexpectedToken {
+                expected: "none, inverse_square, smooth",
+                found: token.to_string(),
+            }),
+            None => Err(ParsingError::UnexpectedEndOfTokens),
+        }
+    }
+}
+
+// intensity: <value> W | W/sr | lm | cd
+impl<V: FromStr + PartialOrd> FromTokens for LightIntensity<V> {
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        let value = util::parse_number(tokens)?;
+
+        match tokens.next() {
+            Some("W") => Ok(LightIntensity::Watt(Watt::new(value))),
+            Some("W/sr") => Ok(LightIntensity::WattPerSteradian(WattPerSteradian::new(
+                value,
+            ))),
+            Some("lm") => Ok(LightIntensity::Lumen(Lumen::new(value))),
+            Some("cd") => Ok(LightIntensity::Candela(Candela::new(value))),
+            Some(token) => Err(ParsingError::UnexpectedToken {
+                expected: "W, W/sr, lm, cd",
+                found: token.to_string(),
+            }),
+            None => Err(ParsingError::UnexpectedEndOfTokens),
+        }
+    }
+}
+
+impl<T: Length> FromTokens for AreaLight<T, RGB<<T as Length>::ValueType>>
+where
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    <T as Length>::ValueType: SignedNumber,
+    <T as FromStr>::Err: Error + Debug,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::AreaLightParsingError(Box::new(cause)));
+        }
+
+        let mut color = RGB::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut corner: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut a: Option<Vector3<T>> = None;
+        let mut b: Option<Vector3<T>> = None;
+        let mut shadow_bias: Option<<T as Length>::ValueType> = None;
+        let mut name: Option<String> = None;
+        let mut group: Option<String> = None;
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "color:" => match RGB::from_tokens(tokens) {
+                    Ok(col) => {
+                        color = col;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::AreaLightParsingError(Box::new(cause)));
+                    }
+                },
+                "corner:" => match Point3::from_tokens(tokens) {
+                    Ok(c) => {
+                        corner = c;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::AreaLightParsingError(Box::new(cause)));
+                    }
+                },
+                "a:" => match Vector3::from_tokens(tokens) {
+                    Ok(v) => {
+                        a = Some(v);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::AreaLightParsingError(Box::new(cause)));
+                    }
+                },
+                "b:" => match Vector3::from_tokens(tokens) {
+                    Ok(v) => {
+                        b = Some(v);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::AreaLightParsingError(Box::new(cause)));
+                    }
+                },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::AreaLightParsingError(Box::new(cause)));
+                    }
+                },
+                "name:" => match tokens.next() {
+                    Some(token) => {
+                        name = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::AreaLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
+                "group:" => match tokens.next() {
+                    Some(token) => {
+                        group = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::AreaLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "color:, corner:, a:, b:, shadow_bias:, name:, group:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        if a.is_none() {
+            return Err(ParsingError::MissingElement("a"));
+        }
+
+        if b.is_none() {
+            return Err(ParsingError::MissingElement("b"));
+        }
+
+        let mut area_light = AreaLight::new(color, corner, a.unwrap(), b.unwrap());
+
+        if let Some(shadow_bias) = shadow_bias {
+            area_light = area_light.with_shadow_bias(shadow_bias);
+        }
+
+        if let Some(name) = name {
+            area_light = area_light.with_name(name);
+        }
+
+        if let Some(group) = group {
+            area_light = area_light.with_group(group);
+        }
+
+        Ok(area_light)
+    }
+}
+
+impl<T: Length> FromTokens for SphereLight<T, RGB<<T as Length>::ValueType>>
+where
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    <T as Length>::ValueType: SignedNumber,
+    <T as FromStr>::Err: Error + Debug,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::SphereLightParsingError(Box::new(cause)));
+        }
+
+        let mut color = RGB::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut position: Point3<T> = Point3::new(Zero::zero(), Zero::zero(), Zero::zero());
+        let mut radius: Option<T> = None;
+        let mut shadow_bias: Option<<T as Length>::ValueType> = None;
+        let mut name: Option<String> = None;
+        let mut group: Option<String> = None;
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "color:" => match RGB::from_tokens(tokens) {
+                    Ok(col) => {
+                        color = col;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphereLightParsingError(Box::new(cause)));
+                    }
+                },
+                "position:" => match Point3::from_tokens(tokens) {
+                    Ok(pos) => {
+                        position = pos;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphereLightParsingError(Box::new(cause)));
+                    }
+                },
+                "radius:" => match util::parse_number(tokens) {
+                    Ok(r) => {
+                        radius = Some(r);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphereLightParsingError(Box::new(cause)));
+                    }
+                },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SphereLightParsingError(Box::new(cause)));
+                    }
+                },
+                "name:" => match tokens.next() {
+                    Some(token) => {
+                        name = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::SphereLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
+                "group:" => match tokens.next() {
+                    Some(token) => {
+                        group = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::SphereLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
-                        expected: "color:, position:, }",
+                        expected: "color:, position:, radius:, shadow_bias:, name:, group:, }",
                         found: token.to_string(),
                     });
                 }
             }
         }
 
-        Ok(PointLight::new(color, position))
+        if radius.is_none() {
+            return Err(ParsingError::MissingElement("radius"));
+        }
+
+        let mut sphere_light = SphereLight::new(color, position, radius.unwrap());
+
+        if let Some(shadow_bias) = shadow_bias {
+            sphere_light = sphere_light.with_shadow_bias(shadow_bias);
+        }
+
+        if let Some(name) = name {
+            sphere_light = sphere_light.with_name(name);
+        }
+
+        if let Some(group) = group {
+            sphere_light = sphere_light.with_group(group);
+        }
+
+        Ok(sphere_light)
+    }
+}
+
+impl<T: Length> FromTokens for EnvironmentLight<T>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + From<f32>,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+    u16: Into<<T as Length>::ValueType>,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::EnvironmentLightParsingError(Box::new(cause)));
+        }
+
+        let mut image: Option<ImageBuffer<RGB<<T as Length>::ValueType>>> = None;
+        let mut intensity: Option<<T as Length>::ValueType> = None;
+        let mut shadow_bias: Option<<T as Length>::ValueType> = None;
+        let mut name: Option<String> = None;
+        let mut group: Option<String> = None;
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "image:" => match tokens.next() {
+                    Some(filename) => match util::load_image(filename) {
+                        Ok(i) => {
+                            image = Some(i);
+                        }
+                        Err(cause) => {
+                            return Err(ParsingError::EnvironmentLightParsingError(Box::new(
+                                cause,
+                            )));
+                        }
+                    },
+                    None => {
+                        return Err(ParsingError::EnvironmentLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
+                "intensity:" => match util::parse_number(tokens) {
+                    Ok(i) => {
+                        intensity = Some(i);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::EnvironmentLightParsingError(Box::new(cause)));
+                    }
+                },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::EnvironmentLightParsingError(Box::new(cause)));
+                    }
+                },
+                "name:" => match tokens.next() {
+                    Some(token) => {
+                        name = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::EnvironmentLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
+                "group:" => match tokens.next() {
+                    Some(token) => {
+                        group = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::EnvironmentLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "image:, intensity:, shadow_bias:, name:, group:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        if image.is_none() {
+            return Err(ParsingError::MissingElement("image"));
+        }
+
+        let mut environment_light = EnvironmentLight::new(image.unwrap());
+
+        if let Some(intensity) = intensity {
+            environment_light = environment_light.with_intensity(intensity);
+        }
+
+        if let Some(shadow_bias) = shadow_bias {
+            environment_light = environment_light.with_shadow_bias(shadow_bias);
+        }
+
+        if let Some(name) = name {
+            environment_light = environment_light.with_name(name);
+        }
+
+        if let Some(group) = group {
+            environment_light = environment_light.with_group(group);
+        }
+
+        Ok(environment_light)
+    }
+}
+
+impl<V> FromTokens for PreethamSky<V>
+where
+    V: FloatingPoint + ConvenientNumber + Exp<Output = V> + From<f32>,
+    <V as FromStr>::Err: Error + Debug,
+    u16: Into<V>,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::SkyParsingError(Box::new(cause)));
+        }
+
+        let mut sun_direction: Option<Vector3<V>> = None;
+        let mut turbidity: V = 3.0.into();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "sun_direction:" => match Vector3::<V>::from_tokens(tokens) {
+                    Ok(vec) => {
+                        sun_direction = Some(vec.normalized());
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SkyParsingError(Box::new(cause)));
+                    }
+                },
+                "turbidity:" => match util::parse_number(tokens) {
+                    Ok(t) => {
+                        turbidity = t;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::SkyParsingError(Box::new(cause)));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "sun_direction:, turbidity:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        if sun_direction.is_none() {
+            return Err(ParsingError::MissingElement("sun_direction"));
+        }
+
+        Ok(PreethamSky::new(sun_direction.unwrap(), turbidity))
+    }
+}
+
+fn load_ies_profile<'a, T>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<IesProfile<T>, ParsingError>
+where
+    T: FloatingPoint + ConvenientNumber + FromStr,
+{
+    let filename = match tokens.next() {
+        Some(filename) => filename,
+        None => return Err(ParsingError::UnexpectedEndOfTokens),
+    };
+
+    let data = match fs::read_to_string(filename) {
+        Ok(data) => data,
+        Err(cause) => {
+            return Err(ParsingError::ProfileLoadingError(format!(
+                "{}: {}",
+                filename, cause
+            )))
+        }
+    };
+
+    match ies::decode(&data) {
+        Ok(profile) => Ok(profile),
+        Err(cause) => Err(ParsingError::ProfileLoadingError(format!(
+            "{}: {:?}",
+            filename, cause
+        ))),
     }
 }
 
@@ -165,6 +844,9 @@ where
         let mut color = RGB::new(Zero::zero(), Zero::zero(), Zero::zero());
         let mut e: T::ValueType = T::ValueType::zero();
         let mut distance: Option<T> = None;
+        let mut shadow_bias: Option<<T as Length>::ValueType> = None;
+        let mut name: Option<String> = None;
+        let mut group: Option<String> = None;
 
         while let Some(token) = tokens.next() {
             match token {
@@ -180,44 +862,89 @@ where
                 },
 
                 "distance:" => match tokens.next() {
-                    Some(distance_string) => match distance_string.parse() {
-                        Ok(d) => distance = Some(d),
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse field of number.",
-                            ));
+                    Some(distance_string) => {
+                        match util::parse_token(distance_string, "Unable to parse field of number.")
+                        {
+                            Ok(d) => distance = Some(d),
+                            Err(cause) => {
+                                return Err(cause);
+                            }
                         }
-                    },
+                    }
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
                 },
 
                 "e:" => match tokens.next() {
-                    Some(e_string) => match e_string.parse() {
-                        Ok(mp) => e = mp,
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse field of number.",
-                            ));
+                    Some(e_string) => {
+                        match util::parse_token(e_string, "Unable to parse field of number.") {
+                            Ok(mp) => e = mp,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
                         }
-                    },
+                    }
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
                 },
+                "shadow_bias:" => match util::parse_number(tokens) {
+                    Ok(bias) => {
+                        shadow_bias = Some(bias);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::AmbientOcclusionLightParsingError(Box::new(
+                            cause,
+                        )));
+                    }
+                },
+                "name:" => match tokens.next() {
+                    Some(token) => {
+                        name = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::AmbientOcclusionLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
+                "group:" => match tokens.next() {
+                    Some(token) => {
+                        group = Some(token.to_string());
+                    }
+                    None => {
+                        return Err(ParsingError::AmbientOcclusionLightParsingError(Box::new(
+                            ParsingError::UnexpectedEndOfTokens,
+                        )));
+                    }
+                },
                 "}" => {
                     break;
                 }
                 token => {
                     return Err(ParsingError::UnexpectedToken {
-                        expected: "color:, distance:, mapping_exponent, }",
+                        expected: "color:, distance:, e:, shadow_bias:, name:, group:, }",
                         found: token.to_string(),
                     });
                 }
             }
         }
 
-        Ok(AmbientOcclusionLight::new(color, e, distance.unwrap()))
+        let mut ambient_occlusion_light = AmbientOcclusionLight::new(color, e, distance.unwrap());
+
+        if let Some(shadow_bias) = shadow_bias {
+            ambient_occlusion_light = ambient_occlusion_light.with_shadow_bias(shadow_bias);
+        }
+
+        if let Some(name) = name {
+            ambient_occlusion_light = ambient_occlusion_light.with_name(name);
+        }
+
+        if let Some(group) = group {
+            ambient_occlusion_light = ambient_occlusion_light.with_group(group);
+        }
+
+        Ok(ambient_occlusion_light)
     }
 }
@@ -0,0 +1,206 @@
+use std::fmt;
+
+use crate::parser::ParsingError;
+
+// Where an error is found in a scene file. The line and the column are counted in characters from
+// 1. The token is the text at the column up to the next whitespace, as it is written in the file,
+// and the snippet is the whole line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub snippet: String,
+}
+
+impl Location {
+    pub fn new(file: &str, content: &str, line: usize, column: usize) -> Location {
+        let snippet = content.lines().nth(line - 1).unwrap_or_default();
+        let token = snippet
+            .chars()
+            .skip(column - 1)
+            .take_while(|c| !c.is_whitespace())
+            .collect();
+        Location {
+            file: file.to_string(),
+            line,
+            column,
+            token,
+            snippet: snippet.to_string(),
+        }
+    }
+}
+
+// Points at the token below the line, like a compiler does:
+//
+//      --> scene.scene:3:17
+//       |
+//     3 |     position: 0 x 1
+//       |                 ^
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let number = self.line.to_string();
+        let margin = " ".repeat(number.len());
+        let indent: String = self
+            .snippet
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        writeln!(
+            f,
+            "{}--> {}:{}:{}",
+            margin, self.file, self.line, self.column
+        )?;
+        writeln!(f, "{} |", margin)?;
+        writeln!(f, "{} | {}", number, self.snippet)?;
+        write!(
+            f,
+            "{} | {}{}",
+            margin,
+            indent,
+            "^".repeat(self.token.chars().count().max(1))
+        )
+    }
+}
+
+// Adds where an error is found to it, if that is known.
+pub fn locate(cause: ParsingError, location: Option<Location>) -> ParsingError {
+    match location {
+        Some(location) => ParsingError::LocatedParsingError(location, Box::new(cause)),
+        None => cause,
+    }
+}
+
+// The tokens of a scene together with the files, lines and columns they are written at, including
+// the tokens of included files.
+#[derive(Debug, Default)]
+pub struct SceneTokens {
+    tokens: Vec<String>,
+    positions: Vec<(usize, usize, usize)>,
+    files: Vec<(String, String)>,
+}
+
+impl SceneTokens {
+    pub fn new() -> SceneTokens {
+        SceneTokens::default()
+    }
+
+    // Adds a file the tokens are read from, returns its index.
+    pub fn add_file(&mut self, file: &str, content: &str) -> usize {
+        self.files.push((file.to_string(), content.to_string()));
+        self.files.len() - 1
+    }
+
+    pub fn push(&mut self, token: String, file: usize, line: usize, column: usize) {
+        self.tokens.push(token);
+        self.positions.push((file, line, column));
+    }
+
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    // The file and the line a token is written on.
+    pub fn line(&self, index: usize) -> Option<(usize, usize)> {
+        let (file, line, _) = *self.positions.get(index)?;
+        Some((file, line))
+    }
+
+    pub fn location(&self, index: usize) -> Option<Location> {
+        let (file, line, column) = *self.positions.get(index)?;
+        let (name, content) = &self.files[file];
+        Some(Location::new(name, content, line, column))
+    }
+
+    pub fn iter(&self) -> TrackedTokens<'_> {
+        TrackedTokens {
+            scene: self,
+            taken: 0,
+        }
+    }
+}
+
+// Iterates over the tokens of a scene and remembers how many were taken, so an error can be
+// located at the last token a parser took, which is usually the token the error is found at.
+pub struct TrackedTokens<'a> {
+    scene: &'a SceneTokens,
+    taken: usize,
+}
+
+impl<'a> TrackedTokens<'a> {
+    pub fn taken(&self) -> usize {
+        self.taken
+    }
+
+    pub fn location(&self, index: usize) -> Option<Location> {
+        self.scene.location(index)
+    }
+
+    pub fn locate(&self, cause: ParsingError) -> ParsingError {
+        let location = self
+            .taken
+            .checked_sub(1)
+            .and_then(|index| self.location(index));
+        locate(cause, location)
+    }
+}
+
+impl<'a> Iterator for TrackedTokens<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.scene.tokens.get(self.taken)?;
+        self.taken += 1;
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point_at_located_tokens() {
+        let content = "sphere {\n\tposition: 0 x 1\n}";
+        let mut scene = SceneTokens::new();
+        let file = scene.add_file("test.scene", content);
+        scene.push(String::from("sphere"), file, 1, 1);
+        scene.push(String::from("position:"), file, 2, 2);
+        scene.push(String::from("x"), file, 2, 14);
+
+        let mut tokens = scene.iter();
+        assert!(matches!(
+            tokens.locate(ParsingError::UnexpectedEndOfTokens),
+            ParsingError::UnexpectedEndOfTokens
+        ));
+        assert_eq!(tokens.by_ref().count(), 3);
+        assert_eq!(tokens.taken(), 3);
+
+        let ParsingError::LocatedParsingError(location, cause) =
+            tokens.locate(ParsingError::UnexpectedEndOfTokens)
+        else {
+            panic!("The error is not located.");
+        };
+        assert!(matches!(*cause, ParsingError::UnexpectedEndOfTokens));
+        assert_eq!(
+            location,
+            Location {
+                file: String::from("test.scene"),
+                line: 2,
+                column: 14,
+                token: String::from("x"),
+                snippet: String::from("\tposition: 0 x 1"),
+            }
+        );
+        assert_eq!(
+            location.to_string(),
+            " --> test.scene:2:14\n  |\n2 | \tposition: 0 x 1\n  | \t            ^"
+        );
+
+        let location = Location::new("test.scene", content, 3, 2);
+        assert_eq!(location.token, "");
+        assert!(location.to_string().ends_with("3 | }\n  |  ^"));
+    }
+}
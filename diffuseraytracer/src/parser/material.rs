@@ -1,49 +1,139 @@
+use std::collections::HashSet;
 use std::error::Error;
 use std::fmt::Debug;
 use std::str::FromStr;
+use std::sync::Arc;
 
-use cg_basics::material::{LambertMaterial, PhongMaterial, UnshadedMaterial};
+use cg_basics::material::{
+    DielectricMaterial, EmissiveMaterial, InstanceAttributes, InstanceMaterial, LambertMaterial,
+    MetalMaterial, PhongMaterial, PlasticMaterial, ReflectiveMaterial, UnshadedMaterial,
+};
 use colors::RGB;
 use image::Image;
 use math::Point2;
 use traits::{ConvenientNumber, FloatingPoint, Number, One, Sqrt};
 use units::length::Length;
 
-use crate::material::Material;
 use crate::parser::texture;
 use crate::parser::util;
-use crate::parser::{FromTokens, ParsingError};
+use crate::parser::{
+    FromTokens, FromTokensWithMaterials, MaterialLibrary, MaterialType, ParsingError, TextureType,
+};
 
-pub fn parse_material<'a, T: Length>(
+pub fn parse_material<'a, T: Length + 'static>(
     tokens: &mut impl Iterator<Item = &'a str>,
-) -> Result<Box<dyn Material<T, ColorType = RGB<<T as Length>::ValueType>>>, ParsingError>
+    materials: &MaterialLibrary<T>,
+) -> Result<MaterialType<T>, ParsingError>
 where
-    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + 'static,
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
     <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
     <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
 {
     match tokens.next() {
         Some("unshaded_material") => match UnshadedMaterial::from_tokens(tokens) {
-            Ok(material) => Ok(Box::new(material)),
+            Ok(material) => Ok(Arc::new(material)),
+            Err(cause) => Err(ParsingError::MaterialParsingError(Box::new(cause))),
+        },
+        Some("emissive_material") => match EmissiveMaterial::from_tokens(tokens) {
+            Ok(material) => Ok(Arc::new(material)),
             Err(cause) => Err(ParsingError::MaterialParsingError(Box::new(cause))),
         },
         Some("lambert_material") => match LambertMaterial::from_tokens(tokens) {
-            Ok(material) => Ok(Box::new(material)),
+            Ok(material) => Ok(Arc::new(material)),
             Err(cause) => Err(ParsingError::MaterialParsingError(Box::new(cause))),
         },
         Some("phong_material") => match PhongMaterial::from_tokens(tokens) {
-            Ok(material) => Ok(Box::new(material)),
+            Ok(material) => Ok(Arc::new(material)),
             Err(cause) => Err(ParsingError::MaterialParsingError(Box::new(cause))),
         },
-        Some(material) => Err(ParsingError::UnsupportedMaterial(material.to_string())),
+        Some("plastic_material") => match PlasticMaterial::from_tokens(tokens) {
+            Ok(material) => Ok(Arc::new(material)),
+            Err(cause) => Err(ParsingError::MaterialParsingError(Box::new(cause))),
+        },
+        Some("metal_material") => match MetalMaterial::from_tokens(tokens) {
+            Ok(material) => Ok(Arc::new(material)),
+            Err(cause) => Err(ParsingError::MaterialParsingError(Box::new(cause))),
+        },
+        Some("dielectric_material") => match DielectricMaterial::from_tokens(tokens) {
+            Ok(material) => Ok(Arc::new(material)),
+            Err(cause) => Err(ParsingError::MaterialParsingError(Box::new(cause))),
+        },
+        Some("reflective_material") => {
+            match ReflectiveMaterial::<MaterialType<T>, TextureType<T::ValueType>>::from_tokens(
+                tokens, materials,
+            ) {
+                Ok(material) => Ok(Arc::new(material)),
+                Err(cause) => Err(ParsingError::MaterialParsingError(Box::new(cause))),
+            }
+        }
+        // Materials named in the scene take precedence over plugins with the same name.
+        Some(name) => match materials.get(name) {
+            Some(material) => Ok(material),
+            None => match materials.plugin(name) {
+                Some(construct) => match construct(tokens) {
+                    Ok(material) => Ok(material),
+                    Err(cause) => Err(ParsingError::MaterialParsingError(Box::new(cause))),
+                },
+                None => Err(ParsingError::UnsupportedMaterial(name.to_string())),
+            },
+        },
         None => Err(ParsingError::UnexpectedEndOfTokens),
     }
 }
 
-impl<T: FromStr + Number + ConvenientNumber + 'static> FromTokens
+pub fn parse_material_library<'a, T: Length + 'static>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    materials: &mut MaterialLibrary<T>,
+    names: &mut HashSet<String>,
+) -> Result<(), ParsingError>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
+{
+    if let Err(cause) = util::check_next_token(tokens, "{") {
+        return Err(ParsingError::MaterialLibraryParsingError(Box::new(cause)));
+    }
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "}" => {
+                return Ok(());
+            }
+            name => match name.strip_suffix(':') {
+                Some(name) if !names.insert(name.to_string()) => {
+                    return Err(ParsingError::MaterialLibraryParsingError(Box::new(
+                        ParsingError::DuplicateMaterial(name.to_string()),
+                    )));
+                }
+                Some(name) => match parse_material(tokens, materials) {
+                    Ok(material) => {
+                        materials.insert(name.to_string(), material);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::MaterialLibraryParsingError(Box::new(cause)));
+                    }
+                },
+                None => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "<name>:, }",
+                        found: name.to_string(),
+                    });
+                }
+            },
+        }
+    }
+
+    Err(ParsingError::UnexpectedEndOfTokens)
+}
+
+impl<T: FromStr + FloatingPoint + ConvenientNumber + From<f32> + 'static> FromTokens
     for UnshadedMaterial<Box<dyn Image<ColorType = RGB<T>, PointType = Point2<T>>>>
 where
     <T as FromStr>::Err: Error + Debug,
+    u16: Into<T>,
 {
     type Err = ParsingError;
 
@@ -68,10 +158,39 @@ where
     }
 }
 
-impl<T: FromStr + Number + ConvenientNumber + 'static> FromTokens
+impl<T: FromStr + Number> FromTokens for EmissiveMaterial<RGB<T>>
+where
+    <T as FromStr>::Err: Error + Debug,
+    u16: Into<T>,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::EmissiveMaterialParsingError(Box::new(cause)));
+        }
+        if let Err(cause) = util::check_next_token(tokens, "color:") {
+            return Err(ParsingError::EmissiveMaterialParsingError(Box::new(cause)));
+        }
+
+        let color = RGB::from_tokens(tokens);
+        if let Err(cause) = color {
+            return Err(ParsingError::EmissiveMaterialParsingError(Box::new(cause)));
+        }
+
+        if let Err(cause) = util::check_next_token(tokens, "}") {
+            return Err(ParsingError::EmissiveMaterialParsingError(Box::new(cause)));
+        }
+
+        Ok(EmissiveMaterial::new(color.unwrap()))
+    }
+}
+
+impl<T: FromStr + FloatingPoint + ConvenientNumber + From<f32> + 'static> FromTokens
     for LambertMaterial<Box<dyn Image<ColorType = RGB<T>, PointType = Point2<T>>>>
 where
     <T as FromStr>::Err: Error + Debug,
+    u16: Into<T>,
 {
     type Err = ParsingError;
 
@@ -96,10 +215,11 @@ where
     }
 }
 
-impl<T: FromStr + Number + ConvenientNumber + 'static> FromTokens
+impl<T: FromStr + FloatingPoint + ConvenientNumber + From<f32> + 'static> FromTokens
     for PhongMaterial<Box<dyn Image<ColorType = RGB<T>, PointType = Point2<T>>>>
 where
     <T as FromStr>::Err: Error + Debug,
+    u16: Into<T>,
 {
     type Err = ParsingError;
 
@@ -134,14 +254,15 @@ where
                     }
                 },
                 "exponent:" => match tokens.next() {
-                    Some(exponent_string) => match exponent_string.parse() {
-                        Ok(exp) => exponent = exp,
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse field of number.",
-                            ));
+                    Some(exponent_string) => {
+                        match util::parse_token(exponent_string, "Unable to parse field of number.")
+                        {
+                            Ok(exp) => exponent = exp,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
                         }
-                    },
+                    }
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
@@ -173,3 +294,353 @@ where
         ))
     }
 }
+
+impl<T: FromStr + FloatingPoint + ConvenientNumber + From<f32> + 'static> FromTokens
+    for PlasticMaterial<Box<dyn Image<ColorType = RGB<T>, PointType = Point2<T>>>>
+where
+    <T as FromStr>::Err: Error + Debug,
+    u16: Into<T>,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::PlasticMaterialParsingError(Box::new(cause)));
+        }
+
+        let mut diffuse_texture: Option<Box<dyn Image<ColorType = RGB<T>, PointType = Point2<T>>>> =
+            None;
+        let mut specular_texture: Option<
+            Box<dyn Image<ColorType = RGB<T>, PointType = Point2<T>>>,
+        > = None;
+        let mut exponent = One::one();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "diffuse_texture:" => match texture::parse_texture(tokens) {
+                    Ok(texture) => {
+                        diffuse_texture = Some(texture);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PlasticMaterialParsingError(Box::new(cause)));
+                    }
+                },
+                "specular_texture:" => match texture::parse_texture(tokens) {
+                    Ok(texture) => {
+                        specular_texture = Some(texture);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::PlasticMaterialParsingError(Box::new(cause)));
+                    }
+                },
+                "exponent:" => match tokens.next() {
+                    Some(exponent_string) => {
+                        match util::parse_token(exponent_string, "Unable to parse field of number.")
+                        {
+                            Ok(exp) => exponent = exp,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
+                        }
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "diffuse_texture:, specular_texture:, exponent:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        if diffuse_texture.is_none() {
+            return Err(ParsingError::MissingElement("diffuse_texture"));
+        }
+
+        if specular_texture.is_none() {
+            return Err(ParsingError::MissingElement("specular_texture"));
+        }
+
+        Ok(PlasticMaterial::new(
+            diffuse_texture.unwrap(),
+            specular_texture.unwrap(),
+            exponent,
+        ))
+    }
+}
+
+impl<T: FromStr + FloatingPoint + ConvenientNumber + From<f32> + 'static> FromTokens
+    for MetalMaterial<Box<dyn Image<ColorType = RGB<T>, PointType = Point2<T>>>>
+where
+    <T as FromStr>::Err: Error + Debug,
+    u16: Into<T>,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::MetalMaterialParsingError(Box::new(cause)));
+        }
+
+        let mut texture: Option<Box<dyn Image<ColorType = RGB<T>, PointType = Point2<T>>>> = None;
+        let mut roughness = T::one().half();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "texture:" => match texture::parse_texture(tokens) {
+                    Ok(parsed_texture) => {
+                        texture = Some(parsed_texture);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::MetalMaterialParsingError(Box::new(cause)));
+                    }
+                },
+                "roughness:" => match tokens.next() {
+                    Some(roughness_string) => {
+                        match util::parse_token(roughness_string, "Unable to parse roughness.") {
+                            Ok(parsed_roughness) => roughness = parsed_roughness,
+                            Err(cause) => {
+                                return Err(cause);
+                            }
+                        }
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "texture:, roughness:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        if texture.is_none() {
+            return Err(ParsingError::MissingElement("texture"));
+        }
+
+        Ok(MetalMaterial::new(texture.unwrap(), roughness))
+    }
+}
+
+impl<T: FromStr + FloatingPoint + ConvenientNumber + From<f32> + 'static> FromTokens
+    for DielectricMaterial<RGB<T>>
+where
+    <T as FromStr>::Err: Error + Debug,
+    u16: Into<T>,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::DielectricMaterialParsingError(Box::new(
+                cause,
+            )));
+        }
+
+        let mut transmittance = RGB::new(One::one(), One::one(), One::one());
+        let mut index_of_refraction = T::one() + T::one().half();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "transmittance:" => match RGB::from_tokens(tokens) {
+                    Ok(color) => {
+                        transmittance = color;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::DielectricMaterialParsingError(Box::new(
+                            cause,
+                        )));
+                    }
+                },
+                "index_of_refraction:" => match tokens.next() {
+                    Some(index_string) => match util::parse_token(
+                        index_string,
+                        "Unable to parse index of refraction.",
+                    ) {
+                        Ok(parsed_index) => index_of_refraction = parsed_index,
+                        Err(cause) => {
+                            return Err(cause);
+                        }
+                    },
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "transmittance:, index_of_refraction:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(DielectricMaterial::new(transmittance, index_of_refraction))
+    }
+}
+
+// Lets an instance override attributes of its material without copying it.
+pub fn with_attributes<T: Length + 'static>(
+    material: MaterialType<T>,
+    attributes: Option<InstanceAttributes<RGB<T::ValueType>>>,
+) -> MaterialType<T> {
+    match attributes {
+        Some(attributes) => Arc::new(InstanceMaterial::new(material, attributes)),
+        None => material,
+    }
+}
+
+// attributes: {
+//     tint: 1.0 0.5 0.5
+//     emission_strength: 2.0
+// }
+impl<T: FromStr + FloatingPoint + 'static> FromTokens for InstanceAttributes<RGB<T>>
+where
+    <T as FromStr>::Err: Error + Debug,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::InstanceAttributesParsingError(Box::new(
+                cause,
+            )));
+        }
+
+        let mut tint = RGB::new(One::one(), One::one(), One::one());
+        let mut emission_strength = T::one();
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "tint:" => match RGB::from_tokens(tokens) {
+                    Ok(color) => {
+                        tint = color;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::InstanceAttributesParsingError(Box::new(
+                            cause,
+                        )));
+                    }
+                },
+                "emission_strength:" => match util::parse_number(tokens) {
+                    Ok(strength) => {
+                        emission_strength = strength;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::InstanceAttributesParsingError(Box::new(
+                            cause,
+                        )));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "tint:, emission_strength:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(InstanceAttributes::new(tint, emission_strength))
+    }
+}
+
+impl<T: Length + 'static> FromTokensWithMaterials<T>
+    for ReflectiveMaterial<MaterialType<T>, TextureType<T::ValueType>>
+where
+    <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
+    <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+    <T as Length>::AreaType: Sqrt<Output = T>,
+    u16: Into<<T as Length>::ValueType>,
+{
+    fn from_tokens<'a>(
+        tokens: &mut impl Iterator<Item = &'a str>,
+        materials: &MaterialLibrary<T>,
+    ) -> Result<Self, ParsingError> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::ReflectiveMaterialParsingError(Box::new(
+                cause,
+            )));
+        }
+
+        let mut material: Option<MaterialType<T>> = None;
+        let mut environment: Option<TextureType<T::ValueType>> = None;
+        let mut reflectance = RGB::new(One::one(), One::one(), One::one());
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "material:" => match parse_material(tokens, materials) {
+                    Ok(mat) => {
+                        material = Some(mat);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::ReflectiveMaterialParsingError(Box::new(
+                            cause,
+                        )));
+                    }
+                },
+                "environment:" => match texture::parse_texture(tokens) {
+                    Ok(texture) => {
+                        environment = Some(texture);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::ReflectiveMaterialParsingError(Box::new(
+                            cause,
+                        )));
+                    }
+                },
+                "reflectance:" => match RGB::from_tokens(tokens) {
+                    Ok(color) => {
+                        reflectance = color;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::ReflectiveMaterialParsingError(Box::new(
+                            cause,
+                        )));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "material:, environment:, reflectance:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        if material.is_none() {
+            return Err(ParsingError::MissingElement("material"));
+        }
+
+        if environment.is_none() {
+            return Err(ParsingError::MissingElement("environment"));
+        }
+
+        Ok(ReflectiveMaterial::new(
+            material.unwrap(),
+            environment.unwrap(),
+            reflectance,
+        ))
+    }
+}
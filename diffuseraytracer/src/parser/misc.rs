@@ -5,26 +5,24 @@ use std::str::FromStr;
 use colors::RGB;
 use math::{Normal3, Point2, Point3, Vector3};
 
+use crate::parser::util;
 use crate::parser::{FromTokens, ParsingError};
 
-pub fn parse_next<'a, T: FromStr>(
+pub fn parse_next<'a, T: FromStr + PartialOrd>(
     tokens: &mut impl Iterator<Item = &'a str>,
 ) -> Result<T, ParsingError>
 where
     <T as FromStr>::Err: Error,
 {
     match tokens.next() {
-        Some(token) => match token.parse::<T>() {
-            Ok(n) => Ok(n),
-            Err(_) => Err(ParsingError::NumberParsingError("Unable to parse number.")),
-        },
+        Some(token) => util::parse_token(token, "Unable to parse number."),
         None => Err(ParsingError::UnexpectedEndOfTokens),
     }
 }
 
 macro_rules! create_simple_token_parser {
     ($type: ident, $errorType: ident, $error: ident, [$($element: ident)+]) => {
-    impl<T: FromStr> FromTokens for $type<T> where
+    impl<T: FromStr + PartialOrd> FromTokens for $type<T> where
         <T as FromStr>::Err: Error + Debug,
         {
             type Err = ParsingError;
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use colors::RGB;
+use units::length::Length;
+
+use crate::light::Light;
+use crate::material::Material;
+use crate::parser::{FromTokens, FromTokensWithMaterials, MaterialLibrary, MaterialType};
+use crate::parser::{LightType, ParsingError, RenderableType};
+use crate::Renderable;
+
+// Custom elements that are not part of this crate. The parser constructs them by the name they
+// are registered with, e.g. "toon_material" in "material: toon_material { ... }". The built in
+// elements and the materials named in the scene take precedence if a plugin uses the same name.
+pub trait MaterialPlugin<T: Length>:
+    Material<T, ColorType = RGB<T::ValueType>> + FromTokens<Err = ParsingError> + 'static
+{
+    const NAME: &'static str;
+}
+
+pub trait LightPlugin<T: Length>:
+    Light<T, RGB<T::ValueType>> + FromTokens<Err = ParsingError> + 'static
+{
+    const NAME: &'static str;
+}
+
+pub trait GeometryPlugin<T: Length>:
+    Renderable<T, RGB<T::ValueType>> + FromTokensWithMaterials<T> + 'static
+{
+    const NAME: &'static str;
+}
+
+pub type MaterialFactory<T> =
+    for<'a, 'b> fn(&'b mut dyn Iterator<Item = &'a str>) -> Result<MaterialType<T>, ParsingError>;
+pub type LightFactory<T> =
+    for<'a, 'b> fn(&'b mut dyn Iterator<Item = &'a str>) -> Result<LightType<T>, ParsingError>;
+pub type GeometryFactory<T> = for<'a, 'b, 'c> fn(
+    &'b mut dyn Iterator<Item = &'a str>,
+    &'c MaterialLibrary<T>,
+) -> Result<RenderableType<T>, ParsingError>;
+
+pub struct PluginRegistry<T: Length> {
+    materials: HashMap<&'static str, MaterialFactory<T>>,
+    lights: HashMap<&'static str, LightFactory<T>>,
+    geometries: HashMap<&'static str, GeometryFactory<T>>,
+}
+
+impl<T: Length> PluginRegistry<T> {
+    pub fn new() -> PluginRegistry<T> {
+        PluginRegistry {
+            materials: HashMap::new(),
+            lights: HashMap::new(),
+            geometries: HashMap::new(),
+        }
+    }
+
+    pub fn register_material<M: MaterialPlugin<T>>(&mut self) {
+        self.materials.insert(M::NAME, construct_material::<T, M>);
+    }
+
+    pub fn register_light<L: LightPlugin<T>>(&mut self) {
+        self.lights.insert(L::NAME, construct_light::<T, L>);
+    }
+
+    pub fn register_geometry<G: GeometryPlugin<T>>(&mut self) {
+        self.geometries.insert(G::NAME, construct_geometry::<T, G>);
+    }
+
+    pub fn material(&self, name: &str) -> Option<MaterialFactory<T>> {
+        self.materials.get(name).copied()
+    }
+
+    pub fn materials(&self) -> HashMap<&'static str, MaterialFactory<T>> {
+        self.materials.clone()
+    }
+
+    pub fn light(&self, name: &str) -> Option<LightFactory<T>> {
+        self.lights.get(name).copied()
+    }
+
+    pub fn geometry(&self, name: &str) -> Option<GeometryFactory<T>> {
+        self.geometries.get(name).copied()
+    }
+}
+
+impl<T: Length> Default for PluginRegistry<T> {
+    fn default() -> Self {
+        PluginRegistry::new()
+    }
+}
+
+fn construct_material<T: Length, M: MaterialPlugin<T>>(
+    mut tokens: &mut dyn Iterator<Item = &str>,
+) -> Result<MaterialType<T>, ParsingError> {
+    match M::from_tokens(&mut tokens) {
+        Ok(material) => Ok(Arc::new(material)),
+        Err(cause) => Err(ParsingError::PluginParsingError(M::NAME, Box::new(cause))),
+    }
+}
+
+fn construct_light<T: Length, L: LightPlugin<T>>(
+    mut tokens: &mut dyn Iterator<Item = &str>,
+) -> Result<LightType<T>, ParsingError> {
+    match L::from_tokens(&mut tokens) {
+        Ok(light) => Ok(Box::new(light)),
+        Err(cause) => Err(ParsingError::PluginParsingError(L::NAME, Box::new(cause))),
+    }
+}
+
+fn construct_geometry<T: Length, G: GeometryPlugin<T>>(
+    mut tokens: &mut dyn Iterator<Item = &str>,
+    materials: &MaterialLibrary<T>,
+) -> Result<RenderableType<T>, ParsingError> {
+    match G::from_tokens(&mut tokens, materials) {
+        Ok(geometry) => Ok(Box::new(geometry)),
+        Err(cause) => Err(ParsingError::PluginParsingError(G::NAME, Box::new(cause))),
+    }
+}
+
+// Registers several plugins at once:
+//
+// register_plugins!(registry; material ToonMaterial<Meter<f64>>, geometry Torus<Meter<f64>>);
+#[macro_export]
+macro_rules! register_plugins {
+    ($registry: expr; $($kind: ident $plugin: ty),* $(,)?) => {
+        $( $crate::register_plugins!(@register $registry, $kind, $plugin); )*
+    };
+    (@register $registry: expr, material, $plugin: ty) => {
+        $registry.register_material::<$plugin>()
+    };
+    (@register $registry: expr, light, $plugin: ty) => {
+        $registry.register_light::<$plugin>()
+    };
+    (@register $registry: expr, geometry, $plugin: ty) => {
+        $registry.register_geometry::<$plugin>()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::error::Error;
+    use std::fmt::Debug;
+    use std::fs;
+    use std::str::FromStr;
+
+    use math::geometry::{ParametricLine, SurfacePoint};
+    use math::{Point3, Vector3};
+    use traits::{ConvenientNumber, FloatingPoint, Sqrt};
+    use units::length::Meter;
+
+    use crate::parser::{self, util, RenderableSphere};
+
+    struct ConstantMaterial<T: Length> {
+        color: RGB<T::ValueType>,
+    }
+
+    impl<T: Length> Material<T> for ConstantMaterial<T> {
+        type ColorType = RGB<T::ValueType>;
+
+        fn color_for(
+            &self,
+            _sp: SurfacePoint<T>,
+            _d: Vector3<T>,
+            _lights: Vec<&dyn Light<T, Self::ColorType>>,
+        ) -> Self::ColorType {
+            self.color
+        }
+    }
+
+    impl<T: Length> FromTokens for ConstantMaterial<T>
+    where
+        <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+    {
+        type Err = ParsingError;
+
+        fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+            util::check_next_token(tokens, "{")?;
+            util::check_next_token(tokens, "color:")?;
+            let color = RGB::from_tokens(tokens)?;
+            util::check_next_token(tokens, "}")?;
+
+            Ok(ConstantMaterial { color })
+        }
+    }
+
+    impl<T: Length + 'static> MaterialPlugin<T> for ConstantMaterial<T>
+    where
+        <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+    {
+        const NAME: &'static str = "constant_material";
+    }
+
+    struct UnitSphere<T: Length>(RenderableSphere<T>);
+
+    impl<T: Length> Renderable<T, RGB<T::ValueType>> for UnitSphere<T>
+    where
+        RenderableSphere<T>: Renderable<T, RGB<T::ValueType>>,
+    {
+        fn intersect(
+            &self,
+            ray: ParametricLine<Point3<T>, Vector3<T>>,
+        ) -> Vec<(
+            T::ValueType,
+            SurfacePoint<T>,
+            &dyn Material<T, ColorType = RGB<T::ValueType>>,
+        )> {
+            self.0.intersect(ray)
+        }
+    }
+
+    impl<T: Length + 'static> FromTokensWithMaterials<T> for UnitSphere<T>
+    where
+        <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
+        <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+        <T as Length>::AreaType: Sqrt<Output = T>,
+        u16: Into<<T as Length>::ValueType>,
+    {
+        fn from_tokens<'a>(
+            tokens: &mut impl Iterator<Item = &'a str>,
+            materials: &MaterialLibrary<T>,
+        ) -> Result<Self, ParsingError> {
+            Ok(UnitSphere(RenderableSphere::<T>::from_tokens(
+                tokens, materials,
+            )?))
+        }
+    }
+
+    impl<T: Length + 'static> GeometryPlugin<T> for UnitSphere<T>
+    where
+        RenderableSphere<T>: Renderable<T, RGB<T::ValueType>>,
+        <T as Length>::ValueType: FloatingPoint + ConvenientNumber + FromStr + From<f32> + 'static,
+        <<T as Length>::ValueType as FromStr>::Err: Error + Debug,
+        <T as Length>::AreaType: Sqrt<Output = T>,
+        u16: Into<<T as Length>::ValueType>,
+    {
+        const NAME: &'static str = "unit_sphere";
+    }
+
+    macro_rules! parse_scene_with_plugins {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let filename = env::temp_dir().join(concat!(stringify!($name), ".scene"));
+                fs::write(
+                    &filename,
+                    "unit_sphere {\n\
+                        material: constant_material { color: 1.0 0.5 0.0 }\n\
+                    }\n",
+                )
+                .unwrap();
+                let filename = filename.to_str().unwrap();
+
+                let mut plugins = PluginRegistry::<Meter<$type>>::new();
+                crate::register_plugins!(
+                    plugins;
+                    material ConstantMaterial<Meter<$type>>,
+                    geometry UnitSphere<Meter<$type>>,
+                );
+
+                let scene = parser::parse_scene_with_plugins(filename, &plugins).unwrap();
+                assert_eq!(scene.geometries.len(), 1);
+
+                let ray = ParametricLine::new(
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(5.0)),
+                    Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                );
+                let hits = scene.geometries[0].intersect(ray);
+                assert_eq!(hits.len(), 2);
+                let (_, sp, material) = hits[0];
+                assert_eq!(material.color_for(sp, ray.direction, vec![]), RGB::new(1.0, 0.5, 0.0));
+
+                assert!(parser::parse_scene::<Meter<$type>>(filename).is_err());
+
+                // A material of the scene shadows a plugin with the same name.
+                fs::write(
+                    filename,
+                    "materials {\n\
+                        constant_material: unshaded_material { texture: single_color_texture { color: 0 0 1 } }\n\
+                    }\n\
+                    unit_sphere { material: constant_material }\n",
+                )
+                .unwrap();
+                let scene = parser::parse_scene_with_plugins(filename, &plugins).unwrap();
+                let (_, sp, material) = scene.geometries[0].intersect(ray)[0];
+                assert_eq!(material.color_for(sp, ray.direction, vec![]), RGB::new(0.0, 0.0, 1.0));
+
+                fs::remove_file(filename).unwrap();
+            }
+        };
+    }
+
+    parse_scene_with_plugins! { f32, parse_scene_with_plugins_f32 }
+    parse_scene_with_plugins! { f64, parse_scene_with_plugins_f64 }
+}
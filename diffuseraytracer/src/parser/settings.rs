@@ -0,0 +1,125 @@
+use std::error::Error;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use traits::FloatingPoint;
+
+use crate::parser::{misc, util, ParsingError};
+use crate::settings::RenderSettings;
+
+// Parses a settings block into the settings. Only the options in the block are replaced, so a
+// later file can change single options of an earlier one.
+pub fn parse_settings<'a, V>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    settings: &mut RenderSettings<V>,
+) -> Result<(), ParsingError>
+where
+    V: FloatingPoint + FromStr,
+    <V as FromStr>::Err: Error + Debug,
+{
+    if let Err(cause) = util::check_next_token(tokens, "{") {
+        return Err(ParsingError::SettingsParsingError(Box::new(cause)));
+    }
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "}" => {
+                return Ok(());
+            }
+            "max_depth:" => match misc::parse_next(tokens) {
+                Ok(max_depth) => {
+                    settings.max_depth = Some(max_depth);
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SettingsParsingError(Box::new(cause)));
+                }
+            },
+            "shadow_bias:" => match misc::parse_next::<V>(tokens) {
+                Ok(shadow_bias) if shadow_bias >= V::zero() => {
+                    settings.shadow_bias = shadow_bias;
+                }
+                Ok(_) => {
+                    return Err(ParsingError::SettingsParsingError(Box::new(
+                        ParsingError::NumberParsingError("The shadow bias must not be negative."),
+                    )));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SettingsParsingError(Box::new(cause)));
+                }
+            },
+            "samples:" => match misc::parse_next(tokens) {
+                Ok(samples) if samples > 0 => {
+                    settings.samples = Some(samples);
+                }
+                Ok(_) => {
+                    return Err(ParsingError::SettingsParsingError(Box::new(
+                        ParsingError::NumberParsingError("The number of samples must be positive."),
+                    )));
+                }
+                Err(cause) => {
+                    return Err(ParsingError::SettingsParsingError(Box::new(cause)));
+                }
+            },
+            token => {
+                return Err(ParsingError::SettingsParsingError(Box::new(
+                    ParsingError::UnexpectedToken {
+                        expected: "max_depth:, shadow_bias:, samples:, }",
+                        found: token.to_string(),
+                    },
+                )));
+            }
+        }
+    }
+
+    Err(ParsingError::SettingsParsingError(Box::new(
+        ParsingError::UnexpectedEndOfTokens,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! parse_settings {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut settings = RenderSettings::<$type>::new();
+                parse_settings(
+                    &mut "{ max_depth: 3 samples: 16 }".split_whitespace(),
+                    &mut settings,
+                )
+                .unwrap();
+                assert_eq!(settings.max_depth, Some(3));
+                assert_eq!(settings.samples, Some(16));
+                assert_eq!(settings.shadow_bias, 0.0001);
+
+                // A later block only replaces the options it sets.
+                parse_settings(
+                    &mut "{ shadow_bias: 0.01 }".split_whitespace(),
+                    &mut settings,
+                )
+                .unwrap();
+                assert_eq!(settings.max_depth, Some(3));
+                assert_eq!(settings.shadow_bias, 0.01);
+
+                for block in [
+                    "{ samples: 0 }",
+                    "{ shadow_bias: -1.0 }",
+                    "{ max_depth: 2.5 }",
+                    "{ bounces: 2 }",
+                    "{ samples: 4",
+                ] {
+                    assert!(
+                        parse_settings(&mut block.split_whitespace(), &mut settings).is_err(),
+                        "{}",
+                        block
+                    );
+                }
+            }
+        };
+    }
+
+    parse_settings! { f32, parse_settings_f32 }
+    parse_settings! { f64, parse_settings_f64 }
+}
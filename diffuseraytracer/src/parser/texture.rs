@@ -3,19 +3,22 @@ use std::fmt::Debug;
 use std::str::FromStr;
 
 use colors::RGB;
-use image::generator::{Checkerboard, Grid};
-use image::{Image, SingleColorImage};
+use image::generator::{Checkerboard, Grid, NoisePattern};
+use image::texture::ImageTexture;
+use image::{Image, ImageBuffer, SingleColorImage};
+use math::noise::{Fbm, Noise, Perlin, Simplex, Turbulence, Worley};
 use math::{Point2, Vector2};
-use traits::{ConvenientNumber, Number, One};
+use traits::{ConvenientNumber, FloatingPoint, Number, One};
 
 use crate::parser::util;
 use crate::parser::{FromTokens, ParsingError};
 
-pub fn parse_texture<'a, T: FromStr + Number + ConvenientNumber + 'static>(
+pub fn parse_texture<'a, T: FromStr + FloatingPoint + ConvenientNumber + From<f32> + 'static>(
     tokens: &mut impl Iterator<Item = &'a str>,
 ) -> Result<Box<dyn Image<ColorType = RGB<T>, PointType = Point2<T>>>, ParsingError>
 where
     <T as FromStr>::Err: Error + Debug,
+    u16: Into<T>,
 {
     match tokens.next() {
         Some("single_color_texture") => match SingleColorImage::from_tokens(tokens) {
@@ -30,6 +33,14 @@ where
             Ok(tex) => Ok(Box::new(tex)),
             Err(cause) => Err(ParsingError::TextureParsingError(Box::new(cause))),
         },
+        Some("noise_texture") => match NoisePattern::from_tokens(tokens) {
+            Ok(tex) => Ok(Box::new(tex)),
+            Err(cause) => Err(ParsingError::TextureParsingError(Box::new(cause))),
+        },
+        Some("image_texture") => match ImageTexture::from_tokens(tokens) {
+            Ok(tex) => Ok(Box::new(tex)),
+            Err(cause) => Err(ParsingError::TextureParsingError(Box::new(cause))),
+        },
 
         Some(texture) => Err(ParsingError::UnsupportedTexture(texture.to_string())),
         None => Err(ParsingError::UnexpectedEndOfTokens),
@@ -169,14 +180,14 @@ where
                     }
                 },
                 "width:" => match tokens.next() {
-                    Some(width_string) => match width_string.parse() {
-                        Ok(w) => width = Some(w),
-                        Err(_) => {
-                            return Err(ParsingError::NumberParsingError(
-                                "Unable to parse field of number.",
-                            ));
+                    Some(width_string) => {
+                        match util::parse_token(width_string, "Unable to parse field of number.") {
+                            Ok(w) => width = Some(w),
+                            Err(cause) => {
+                                return Err(cause);
+                            }
                         }
-                    },
+                    }
                     None => {
                         return Err(ParsingError::UnexpectedEndOfTokens);
                     }
@@ -211,3 +222,193 @@ where
         ))
     }
 }
+
+pub(super) type NoiseContainer<T> = Box<dyn Noise<T> + Send + Sync>;
+
+impl<T> FromTokens for NoisePattern<RGB<T>, NoiseContainer<T>>
+where
+    T: FromStr + FloatingPoint + ConvenientNumber + 'static,
+    <T as FromStr>::Err: Error + Debug,
+    u16: Into<T>,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::NoiseTextureParsingError(Box::new(cause)));
+        }
+
+        let mut a: Option<RGB<T>> = None;
+        let mut b: Option<RGB<T>> = None;
+        let mut kind = "perlin";
+        let mut frequency = T::one();
+        let mut octaves = 1;
+        let mut turbulence = false;
+        let mut seed = 0;
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "a:" => match RGB::from_tokens(tokens) {
+                    Ok(color) => {
+                        a = Some(color);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::NoiseTextureParsingError(Box::new(cause)));
+                    }
+                },
+                "b:" => match RGB::from_tokens(tokens) {
+                    Ok(color) => {
+                        b = Some(color);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::NoiseTextureParsingError(Box::new(cause)));
+                    }
+                },
+                "kind:" => match tokens.next() {
+                    Some(k @ ("perlin" | "simplex" | "worley")) => {
+                        kind = k;
+                    }
+                    Some(k) => {
+                        return Err(ParsingError::UnexpectedToken {
+                            expected: "perlin, simplex, worley",
+                            found: k.to_string(),
+                        });
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "frequency:" => match util::parse_number(tokens) {
+                    Ok(f) => {
+                        frequency = f;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::NoiseTextureParsingError(Box::new(cause)));
+                    }
+                },
+                "octaves:" => match util::parse_number(tokens) {
+                    Ok(o) => {
+                        octaves = o;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::NoiseTextureParsingError(Box::new(cause)));
+                    }
+                },
+                "fractal:" => match tokens.next() {
+                    Some("fbm") => {
+                        turbulence = false;
+                    }
+                    Some("turbulence") => {
+                        turbulence = true;
+                    }
+                    Some(f) => {
+                        return Err(ParsingError::UnexpectedToken {
+                            expected: "fbm, turbulence",
+                            found: f.to_string(),
+                        });
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "seed:" => match util::parse_number(tokens) {
+                    Ok(s) => {
+                        seed = s;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::NoiseTextureParsingError(Box::new(cause)));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "a:, b:, kind:, frequency:, octaves:, fractal:, seed:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        let Some(a) = a else {
+            return Err(ParsingError::MissingElement("a"));
+        };
+        let Some(b) = b else {
+            return Err(ParsingError::MissingElement("b"));
+        };
+
+        Ok(NoisePattern::generate(
+            a,
+            b,
+            fractal_noise(kind, turbulence, octaves, seed),
+            frequency,
+        ))
+    }
+}
+
+// Sums octaves of a noise of a kind, either as fractal Brownian motion or as turbulence.
+pub(super) fn fractal_noise<T: FloatingPoint + ConvenientNumber + 'static>(
+    kind: &str,
+    turbulence: bool,
+    octaves: usize,
+    seed: u64,
+) -> NoiseContainer<T>
+where
+    u16: Into<T>,
+{
+    let noise: NoiseContainer<T> = match kind {
+        "simplex" => Box::new(Simplex::new(seed)),
+        "worley" => Box::new(Worley::new(seed)),
+        _ => Box::new(Perlin::new(seed)),
+    };
+    if turbulence {
+        Box::new(Turbulence::new(noise, octaves))
+    } else {
+        Box::new(Fbm::new(noise, octaves))
+    }
+}
+
+impl<T: FloatingPoint + From<f32>> FromTokens for ImageTexture<ImageBuffer<RGB<T>>> {
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::ImageTextureParsingError(Box::new(cause)));
+        }
+
+        let mut image: Option<ImageBuffer<RGB<T>>> = None;
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "image:" => match tokens.next() {
+                    Some(filename) => match util::load_image(filename) {
+                        Ok(loaded) => {
+                            image = Some(loaded);
+                        }
+                        Err(cause) => {
+                            return Err(ParsingError::ImageTextureParsingError(Box::new(cause)));
+                        }
+                    },
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "image:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        match image {
+            Some(image) => Ok(ImageTexture::new(image)),
+            None => Err(ParsingError::MissingElement("image")),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+// Splits a scene file into tokens at spaces, tabs and line breaks, together with where they are
+// written. Comments are skipped:
+//
+//     // A line comment, up to the end of the line.
+//     sphere {
+//         /* A block comment,
+//            which can span several lines. */
+//         radius: 2.0
+//     }
+//
+// Comments start at the beginning of a token, so a path like /textures//wood.ff stays a token.
+// A block comment without an end comments out the rest of the file, which is reported by
+// unterminated_comment with the line and the column where the comment starts.
+pub struct Tokenizer<'a> {
+    content: &'a str,
+    position: usize,
+    line: usize,
+    column: usize,
+    unterminated_comment: Option<(usize, usize)>,
+}
+
+// A token with its byte offset in the file, and the line and the column it starts at, counted
+// in characters from 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(content: &'a str) -> Tokenizer<'a> {
+        Tokenizer {
+            content,
+            position: 0,
+            line: 1,
+            column: 1,
+            unterminated_comment: None,
+        }
+    }
+
+    pub fn unterminated_comment(&self) -> Option<(usize, usize)> {
+        self.unterminated_comment
+    }
+
+    fn advance(&mut self, length: usize) {
+        for c in self.content[self.position..self.position + length].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.position += length;
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = &self.content[self.position..];
+            let token = rest.trim_start_matches([' ', '\t', '\n']);
+            self.advance(rest.len() - token.len());
+
+            if token.is_empty() {
+                return None;
+            } else if token.starts_with("//") {
+                self.advance(token.find('\n').unwrap_or(token.len()));
+            } else if let Some(comment) = token.strip_prefix("/*") {
+                match comment.find("*/") {
+                    Some(end) => self.advance(end + 4),
+                    None => {
+                        self.unterminated_comment = Some((self.line, self.column));
+                        self.advance(token.len());
+                    }
+                }
+            } else {
+                let length = token.find([' ', '\t', '\n']).unwrap_or(token.len());
+                let token = Token {
+                    text: &token[..length],
+                    offset: self.position,
+                    line: self.line,
+                    column: self.column,
+                };
+                self.advance(length);
+                return Some(token);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_comments() {
+        let content = "// A sphere.\n\
+                       sphere { /* at the origin */ radius: 2.0 // in meters\n\
+                       \t/* a comment\n\
+                       over // several */ lines */\n\
+                       image: /textures//wood.ff }\n\
+                       //";
+        let mut tokenizer = Tokenizer::new(content);
+        let tokens: Vec<Token> = tokenizer.by_ref().collect();
+
+        assert_eq!(
+            tokens.iter().map(|token| token.text).collect::<Vec<_>>(),
+            [
+                "sphere",
+                "{",
+                "radius:",
+                "2.0",
+                "lines",
+                "*/",
+                "image:",
+                "/textures//wood.ff",
+                "}"
+            ]
+        );
+        for token in &tokens {
+            assert_eq!(
+                &content[token.offset..token.offset + token.text.len()],
+                token.text
+            );
+        }
+        assert_eq!((tokens[2].line, tokens[2].column), (2, 30));
+        assert_eq!((tokens[4].line, tokens[4].column), (4, 20));
+        assert_eq!((tokens[6].line, tokens[6].column), (5, 1));
+        assert_eq!(tokenizer.unterminated_comment(), None);
+
+        let mut tokenizer = Tokenizer::new("sphere { }\n  /* the end");
+        assert_eq!(tokenizer.by_ref().count(), 3);
+        assert_eq!(tokenizer.unterminated_comment(), Some((2, 3)));
+    }
+}
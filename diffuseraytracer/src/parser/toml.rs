@@ -0,0 +1,685 @@
+// Scenes can be written in TOML, so they can be checked and formatted with the usual tools. A
+// TOML scene is translated into the tokens of a scene file, which are parsed like any other scene:
+//
+//     background_color = [0.0, 0.0, 0.0]
+//
+//     [[sphere]]
+//     position = [0.0, 1.0, 0.0]
+//     material.lambert_material.texture.single_color_texture.color = [1.0, 0.0, 0.0]
+//
+// is read as
+//
+//     background_color: 0.0 0.0 0.0
+//     sphere {
+//         position: 0.0 1.0 0.0
+//         material: lambert_material { texture: single_color_texture { color: 1.0 0.0 0.0 } }
+//     }
+//
+// Tables at the top level are elements, arrays of tables repeat them. Inside of an element, keys
+// with numbers, strings or arrays of them are properties with their values in order. A table with
+// a single table in it is a property with an element of that type, any other table is a block of
+// properties, e.g. the attributes of an instance. Arrays of tables repeat a property, e.g. the
+// keyframes of a focus pull. Multi-line strings and dates are not supported.
+//
+// Every token comes with the line and the column of the key or the value it is made of, so errors
+// in the scene can be located in the TOML file.
+pub fn tokens(content: &str) -> Result<Vec<(String, Position)>, SyntaxError> {
+    let document = Reader::new(content).document()?;
+
+    let mut tokens = Vec::new();
+    for (key, position, value) in &document.entries {
+        match value {
+            Value::Table(table) => element(key, table, &mut tokens),
+            Value::Array(values) if values.iter().all(Value::is_table) => {
+                for value in values {
+                    if let Value::Table(table) = value {
+                        element(key, table, &mut tokens);
+                    }
+                }
+            }
+            value => {
+                tokens.push((format!("{}:", key), *position));
+                value.flatten(&mut tokens);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// A line and a column, counted in characters from 1.
+pub type Position = (usize, usize);
+
+// An error in the syntax of a TOML file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    // A number, a string or a boolean, as the token it becomes.
+    Scalar(String, Position),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+impl Value {
+    fn is_table(&self) -> bool {
+        matches!(self, Value::Table(_))
+    }
+
+    fn flatten(&self, tokens: &mut Vec<(String, Position)>) {
+        match self {
+            Value::Scalar(token, position) => tokens.push((token.clone(), *position)),
+            Value::Array(values) => values.iter().for_each(|value| value.flatten(tokens)),
+            Value::Table(table) => properties(table, tokens),
+        }
+    }
+}
+
+// The entries of a table in the order they are written, with the positions of their keys. The
+// position of a table is where it is opened, by a header, a dotted key or an inline table.
+#[derive(Debug, Clone, PartialEq)]
+struct Table {
+    position: Position,
+    entries: Vec<(String, Position, Value)>,
+}
+
+impl Table {
+    fn new(position: Position) -> Table {
+        Table {
+            position,
+            entries: Vec::new(),
+        }
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entries
+            .iter_mut()
+            .find(|(k, _, _)| k == key)
+            .map(|(_, _, value)| value)
+    }
+
+    // The table of a key, created at the position if the key is missing. The last table of an
+    // array of tables stands for the array, like in headers of TOML.
+    fn table_mut(&mut self, key: &str, position: Position) -> Option<&mut Table> {
+        if self.get_mut(key).is_none() {
+            self.entries.push((
+                key.to_string(),
+                position,
+                Value::Table(Table::new(position)),
+            ));
+        }
+        match self.get_mut(key)? {
+            Value::Table(table) => Some(table),
+            Value::Array(values) => match values.last_mut() {
+                Some(Value::Table(table)) => Some(table),
+                _ => None,
+            },
+            Value::Scalar(..) => None,
+        }
+    }
+}
+
+fn element(name: &str, table: &Table, tokens: &mut Vec<(String, Position)>) {
+    tokens.push((name.to_string(), table.position));
+    tokens.push((String::from("{"), table.position));
+    properties(table, tokens);
+    tokens.push((String::from("}"), table.position));
+}
+
+fn properties(table: &Table, tokens: &mut Vec<(String, Position)>) {
+    for (key, position, value) in &table.entries {
+        match value {
+            Value::Table(table) => {
+                tokens.push((format!("{}:", key), *position));
+                typed_or_block(table, tokens);
+            }
+            Value::Array(values) if !values.is_empty() && values.iter().all(Value::is_table) => {
+                for value in values {
+                    if let Value::Table(table) = value {
+                        tokens.push((format!("{}:", key), table.position));
+                        typed_or_block(table, tokens);
+                    }
+                }
+            }
+            value => {
+                tokens.push((format!("{}:", key), *position));
+                value.flatten(tokens);
+            }
+        }
+    }
+}
+
+fn typed_or_block(table: &Table, tokens: &mut Vec<(String, Position)>) {
+    match table.entries.as_slice() {
+        [(name, _, Value::Table(element_table))] => element(name, element_table, tokens),
+        _ => {
+            tokens.push((String::from("{"), table.position));
+            properties(table, tokens);
+            tokens.push((String::from("}"), table.position));
+        }
+    }
+}
+
+struct Reader {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Reader {
+    fn new(content: &str) -> Reader {
+        Reader {
+            chars: content.chars().collect(),
+            position: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn document(mut self) -> Result<Table, SyntaxError> {
+        let mut root = Table::new((1, 1));
+        // The keys of the table of the last header.
+        let mut current: Vec<String> = Vec::new();
+
+        loop {
+            self.skip_whitespace_and_comments(true);
+            match self.peek() {
+                None => return Ok(root),
+                Some('[') => {
+                    let start = self.location();
+                    self.next();
+                    let array = self.peek() == Some('[');
+                    if array {
+                        self.next();
+                    }
+                    self.skip_whitespace_and_comments(false);
+                    let keys = self.keys()?;
+                    self.expect(']')?;
+                    if array {
+                        self.expect(']')?;
+                    }
+                    self.end_of_line()?;
+
+                    let (last, parents) = keys.split_last().unwrap();
+                    let mut table = &mut root;
+                    for key in parents {
+                        table = match table.table_mut(key, start) {
+                            Some(table) => table,
+                            None => return Err(error_at(start, "The key is not a table.")),
+                        };
+                    }
+                    if array {
+                        match table.get_mut(last) {
+                            None => table.entries.push((
+                                last.clone(),
+                                start,
+                                Value::Array(vec![Value::Table(Table::new(start))]),
+                            )),
+                            Some(Value::Array(values)) if values.iter().all(Value::is_table) => {
+                                values.push(Value::Table(Table::new(start)))
+                            }
+                            Some(_) => {
+                                return Err(error_at(start, "The key is not an array of tables."))
+                            }
+                        }
+                    } else if table.table_mut(last, start).is_none() {
+                        return Err(error_at(start, "The key is not a table."));
+                    }
+                    current = keys;
+                }
+                Some(_) => {
+                    let start = self.location();
+                    let keys = self.keys()?;
+                    self.expect('=')?;
+                    self.skip_whitespace_and_comments(false);
+                    let value = self.value()?;
+                    self.end_of_line()?;
+
+                    let mut table = &mut root;
+                    for key in &current {
+                        table = table.table_mut(key, start).unwrap();
+                    }
+                    insert(table, &keys, value, start)?;
+                }
+            }
+        }
+    }
+
+    fn keys(&mut self) -> Result<Vec<String>, SyntaxError> {
+        let mut keys = vec![self.key()?];
+        loop {
+            self.skip_whitespace_and_comments(false);
+            if self.peek() != Some('.') {
+                return Ok(keys);
+            }
+            self.next();
+            self.skip_whitespace_and_comments(false);
+            keys.push(self.key()?);
+        }
+    }
+
+    fn key(&mut self) -> Result<String, SyntaxError> {
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let key = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                if key.is_empty() {
+                    return Err(self.error("Expected a key."));
+                }
+                Ok(key)
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, SyntaxError> {
+        let start = self.location();
+        match self.peek() {
+            Some('"') => {
+                if self.starts_with("\"\"\"") {
+                    return Err(self.error("Multi-line strings are not supported."));
+                }
+                Ok(Value::Scalar(self.basic_string()?, start))
+            }
+            Some('\'') => {
+                if self.starts_with("'''") {
+                    return Err(self.error("Multi-line strings are not supported."));
+                }
+                Ok(Value::Scalar(self.literal_string()?, start))
+            }
+            Some('[') => {
+                self.next();
+                let mut values = Vec::new();
+                loop {
+                    self.skip_whitespace_and_comments(true);
+                    if self.peek() == Some(']') {
+                        self.next();
+                        return Ok(Value::Array(values));
+                    }
+                    values.push(self.value()?);
+                    self.skip_whitespace_and_comments(true);
+                    match self.peek() {
+                        Some(',') => {}
+                        Some(']') => {
+                            self.next();
+                            return Ok(Value::Array(values));
+                        }
+                        _ => return Err(self.error("Expected , or ] in the array.")),
+                    }
+                    self.next();
+                }
+            }
+            Some('{') => {
+                self.next();
+                let mut table = Table::new(start);
+                self.skip_whitespace_and_comments(false);
+                if self.peek() == Some('}') {
+                    self.next();
+                    return Ok(Value::Table(table));
+                }
+                loop {
+                    self.skip_whitespace_and_comments(false);
+                    let key_start = self.location();
+                    let keys = self.keys()?;
+                    self.expect('=')?;
+                    self.skip_whitespace_and_comments(false);
+                    let value = self.value()?;
+                    insert(&mut table, &keys, value, key_start)?;
+                    self.skip_whitespace_and_comments(false);
+                    match self.peek() {
+                        Some(',') => {}
+                        Some('}') => {
+                            self.next();
+                            return Ok(Value::Table(table));
+                        }
+                        _ => return Err(self.error("Expected , or } in the inline table.")),
+                    }
+                    self.next();
+                }
+            }
+            _ => {
+                let token = self.take_while(|c| {
+                    c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-' | '.')
+                });
+                match token.as_str() {
+                    "true" | "false" => Ok(Value::Scalar(token, start)),
+                    "inf" | "+inf" | "-inf" | "nan" | "+nan" | "-nan" => Ok(Value::Scalar(
+                        token.trim_start_matches('+').to_string(),
+                        start,
+                    )),
+                    _ => {
+                        let number = token.trim_start_matches('+').replace('_', "");
+                        if number.parse::<f64>().is_ok() && !token.contains("__") {
+                            Ok(Value::Scalar(number, start))
+                        } else {
+                            Err(self.error("Expected a value."))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, SyntaxError> {
+        let start = self.location();
+        self.next();
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('b') => '\u{8}',
+                        Some('t') => '\t',
+                        Some('n') => '\n',
+                        Some('f') => '\u{c}',
+                        Some('r') => '\r',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some(u @ ('u' | 'U')) => {
+                            let digits = if u == 'u' { 4 } else { 8 };
+                            let code: String = (0..digits).filter_map(|_| self.next()).collect();
+                            match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                                Some(c) => c,
+                                None => return Err(self.error("Invalid unicode escape.")),
+                            }
+                        }
+                        _ => return Err(self.error("Invalid escape sequence.")),
+                    };
+                    string.push(escaped);
+                }
+                Some('\n') | None => return Err(error_at(start, "Unterminated string.")),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, SyntaxError> {
+        let start = self.location();
+        self.next();
+        let string = self.take_while(|c| c != '\'' && c != '\n');
+        match self.next() {
+            Some('\'') => Ok(string),
+            _ => Err(error_at(start, "Unterminated string.")),
+        }
+    }
+
+    // Newlines are only skipped where values may continue on the next line, e.g. in arrays.
+    fn skip_whitespace_and_comments(&mut self, newlines: bool) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {
+                    self.next();
+                }
+                '\n' if newlines => {
+                    self.next();
+                }
+                '#' => {
+                    self.take_while(|c| c != '\n');
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), SyntaxError> {
+        self.skip_whitespace_and_comments(false);
+        match self.peek() {
+            Some('\n') => {
+                self.next();
+                Ok(())
+            }
+            None => Ok(()),
+            _ => Err(self.error("Expected the end of the line.")),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SyntaxError> {
+        self.skip_whitespace_and_comments(false);
+        if self.peek() == Some(expected) {
+            self.next();
+            Ok(())
+        } else {
+            Err(self.error(match expected {
+                ']' => "Expected ].",
+                '=' => "Expected =.",
+                _ => "Unexpected character.",
+            }))
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.peek().filter(|&c| predicate(c)) {
+            taken.push(c);
+            self.next();
+        }
+        taken
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        prefix
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.position + i) == Some(&c))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    // The line and the column of the next character.
+    fn location(&self) -> Position {
+        (self.line, self.column)
+    }
+
+    fn error(&self, message: &'static str) -> SyntaxError {
+        error_at(self.location(), message)
+    }
+}
+
+// Dotted keys create the tables in between. Errors are reported at the start of the keys.
+fn insert(
+    table: &mut Table,
+    keys: &[String],
+    value: Value,
+    start: Position,
+) -> Result<(), SyntaxError> {
+    let (last, parents) = keys.split_last().unwrap();
+    let mut table = table;
+    for key in parents {
+        table = match table.table_mut(key, start) {
+            Some(table) => table,
+            None => return Err(error_at(start, "The key is not a table.")),
+        };
+    }
+    if table.get_mut(last).is_some() {
+        return Err(error_at(start, "The key is defined twice."));
+    }
+    table.entries.push((last.clone(), start, value));
+    Ok(())
+}
+
+fn error_at((line, column): Position, message: &'static str) -> SyntaxError {
+    SyntaxError {
+        line,
+        column,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(scene: &str) -> Vec<String> {
+        scene.split_whitespace().map(String::from).collect()
+    }
+
+    fn texts(toml: &str) -> Vec<String> {
+        tokens(toml)
+            .unwrap()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect()
+    }
+
+    #[test]
+    fn translate_toml_to_tokens() {
+        let toml = r#"
+            # A plane lit by a spot light.
+            background_color = [0.0, 0.0, 0.0]
+            ambient_light = [0.05, 0.05, 0.05]
+
+            [[plane]]
+            position = [0.0, 0.0, 0.0]
+            material.lambert_material.texture.single_color_texture.color = [1, 1, 1]
+
+            [[pinhole_camera]]
+            id = "main"
+            eye_position = [
+                0.0, 3.0, 4.0, # The camera looks down.
+            ]
+            field_of_view = 90
+
+            [[sphere]]
+            attributes = { tint = [1, 0.5, 1] }
+
+            [sphere.material.phong_material]
+            exponent = 1_000
+
+            [[spot_light]]
+            angle = +30.0
+            gobo = { image_texture = { image = 'gobo.ff' } }
+
+            [[focus_pull]]
+            camera = "main"
+
+            [[focus_pull.keyframe]]
+            time = 0.0
+            focus_on = "near"
+
+            [[focus_pull.keyframe]]
+            time = 1e1
+            focus_on = "far"
+        "#;
+
+        let expected = "
+            background_color: 0.0 0.0 0.0
+            ambient_light: 0.05 0.05 0.05
+            plane {
+                position: 0.0 0.0 0.0
+                material: lambert_material { texture: single_color_texture { color: 1 1 1 } }
+            }
+            pinhole_camera { id: main eye_position: 0.0 3.0 4.0 field_of_view: 90 }
+            sphere {
+                attributes: { tint: 1 0.5 1 }
+                material: phong_material { exponent: 1000 }
+            }
+            spot_light { angle: 30.0 gobo: image_texture { image: gobo.ff } }
+            focus_pull {
+                camera: main
+                keyframe: { time: 0.0 focus_on: near }
+                keyframe: { time: 1e1 focus_on: far }
+            }
+        ";
+
+        assert_eq!(texts(toml), split(expected));
+    }
+
+    #[test]
+    fn translate_materials_and_settings() {
+        let toml = r#"
+            [settings]
+            max_depth = 4
+
+            [materials.red.lambert_material.texture.single_color_texture]
+            color = [0.8, 0.2, 0.2]
+
+            [[mesh]]
+            material = "red"
+            vertices = [3, [0, 0, 0], [1, 0, 0], [0, 1, 0]]
+        "#;
+
+        let expected = "
+            settings { max_depth: 4 }
+            materials { red: lambert_material { texture: single_color_texture { color: 0.8 0.2 0.2 } } }
+            mesh { material: red vertices: 3 0 0 0 1 0 0 0 1 0 }
+        ";
+
+        assert_eq!(texts(toml), split(expected));
+    }
+
+    #[test]
+    fn locate_tokens() {
+        let toml = "[[sphere]]\n\
+                    radius = 2.0\n\
+                    material.unshaded_material.texture = { color = [1, 0, 0] }\n\
+                    \n\
+                    [[sphere.keyframe]]\n";
+        let expected = [
+            ("sphere", (1, 1)),
+            ("{", (1, 1)),
+            ("radius:", (2, 1)),
+            ("2.0", (2, 10)),
+            ("material:", (3, 1)),
+            ("unshaded_material", (3, 1)),
+            ("{", (3, 1)),
+            ("texture:", (3, 1)),
+            ("{", (3, 38)),
+            ("color:", (3, 40)),
+            ("1", (3, 49)),
+            ("0", (3, 52)),
+            ("0", (3, 55)),
+            ("}", (3, 38)),
+            ("}", (3, 1)),
+            ("keyframe:", (5, 1)),
+            ("{", (5, 1)),
+            ("}", (5, 1)),
+            ("}", (1, 1)),
+        ];
+
+        let tokens = tokens(toml).unwrap();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|(token, position)| (token.as_str(), *position))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn report_position_of_errors() {
+        let error = |toml: &str| match tokens(toml) {
+            Err(SyntaxError { line, column, .. }) => (line, column),
+            result => panic!("Unexpected result {:?}", result),
+        };
+
+        assert_eq!(error("[[sphere]]\nposition = [0, 1 0]"), (2, 18));
+        assert_eq!(error("a = 1\na = 2"), (2, 1));
+        assert_eq!(error("a = \"text"), (1, 5));
+        assert_eq!(error("a = 1 b"), (1, 7));
+        assert_eq!(error("a = 1\n[a]"), (2, 1));
+        assert_eq!(error("a = 1\n[[a]]"), (2, 1));
+        assert_eq!(error("a.b = 1\na.b.c = 2"), (2, 1));
+        assert_eq!(error("a = '''\ntext'''"), (1, 5));
+    }
+}
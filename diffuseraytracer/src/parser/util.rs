@@ -1,3 +1,13 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use colors::{Color, RGB};
+use image::converter::srgb;
+use image::{farbfeld, hdr, jpeg, png, Image, ImageBuffer, WritableImage};
+use math::Point2;
+use traits::FloatingPoint;
+
 use crate::parser::ParsingError;
 
 pub fn check_next_token<'a, I: Iterator<Item = &'a str>>(
@@ -18,3 +28,150 @@ pub fn check_next_token<'a, I: Iterator<Item = &'a str>>(
         None => Err(ParsingError::UnexpectedEndOfTokens),
     }
 }
+
+// Skips a block in braces together with all blocks nested in it.
+pub fn skip_block<'a, I: Iterator<Item = &'a str>>(tokens: &mut I) -> Result<(), ParsingError> {
+    check_next_token(tokens, "{")?;
+
+    let mut depth = 1;
+    for token in tokens {
+        match token {
+            "{" => depth += 1,
+            "}" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Err(ParsingError::UnexpectedEndOfTokens)
+}
+
+pub fn parse_number<'a, I: Iterator<Item = &'a str>, T: FromStr + PartialOrd>(
+    tokens: &mut I,
+) -> Result<T, ParsingError> {
+    match tokens.next() {
+        Some(number_string) => parse_token(number_string, "Unable to parse field of number."),
+        None => Err(ParsingError::UnexpectedEndOfTokens),
+    }
+}
+
+// Parses a single number. NaN and infinite values are rejected, since they would spread through
+// every calculation they take part in and poison the whole render. This includes numbers that are
+// too large to be represented by the type, e.g. 1e39 for f32. NaN is the only value that can not
+// be compared with itself, and types without infinite values, like integers, fail to parse them.
+pub fn parse_token<T: FromStr + PartialOrd>(
+    token: &str,
+    message: &'static str,
+) -> Result<T, ParsingError> {
+    let number: T = match token.parse() {
+        Ok(number) => number,
+        Err(_) => return Err(ParsingError::NumberParsingError(message)),
+    };
+
+    let infinite = ["inf", "-inf"].iter().any(|infinity| {
+        infinity
+            .parse::<T>()
+            .is_ok_and(|infinity| number == infinity)
+    });
+    if number.partial_cmp(&number).is_none() || infinite {
+        return Err(ParsingError::NonFiniteNumber(token.to_string()));
+    }
+    Ok(number)
+}
+
+// Parses a count followed by as many names, e.g. the lights a geometry is lit by.
+pub fn parse_names<'a, I: Iterator<Item = &'a str>>(
+    tokens: &mut I,
+) -> Result<Vec<String>, ParsingError> {
+    let count: usize = parse_number(tokens)?;
+    let mut names = Vec::with_capacity(count);
+    for _ in 0..count {
+        match tokens.next() {
+            Some(name) => names.push(name.to_string()),
+            None => return Err(ParsingError::UnexpectedEndOfTokens),
+        }
+    }
+    Ok(names)
+}
+
+// Loads the image of a texture or a light. Radiance files hold linear colors, while farbfeld, PNG
+// and JPEG files hold colors encoded to sRGB like the images the renderer writes, which are
+// linearized. The decoder is chosen by the extension of the file.
+pub fn load_image<T>(filename: &str) -> Result<ImageBuffer<RGB<T>>, ParsingError>
+where
+    T: FloatingPoint + From<f32>,
+{
+    let data = match fs::read(filename) {
+        Ok(data) => data,
+        Err(cause) => {
+            return Err(ParsingError::ImageLoadingError(format!(
+                "{}: {}",
+                filename, cause
+            )))
+        }
+    };
+
+    let extension = Path::new(filename)
+        .extension()
+        .map(|extension| extension.to_ascii_lowercase());
+    let decoded = match extension.as_ref().and_then(|extension| extension.to_str()) {
+        Some("ff") => farbfeld::decode(&data)
+            .map(|image| linearize(&image))
+            .map_err(|cause| format!("{:?}", cause)),
+        Some("png") => png::decode(&data)
+            .map(|image| linearize(&image))
+            .map_err(|cause| format!("{:?}", cause)),
+        Some("jpg" | "jpeg") => jpeg::decode(&data)
+            .map(|image| linearize(&image))
+            .map_err(|cause| format!("{:?}", cause)),
+        _ => hdr::decode(&data).map_err(|cause| format!("{:?}", cause)),
+    };
+    let decoded = match decoded {
+        Ok(image) => image,
+        Err(cause) => {
+            return Err(ParsingError::ImageLoadingError(format!(
+                "{}: {}",
+                filename, cause
+            )))
+        }
+    };
+
+    let size = decoded.size();
+    let mut image = ImageBuffer::new(size, RGB::new(T::zero(), T::zero(), T::zero()));
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let p = Point2::new(x, y);
+            let color = decoded.get(p);
+            *image.get_mut(p) = RGB::new(color.red.into(), color.green.into(), color.blue.into());
+        }
+    }
+
+    Ok(image)
+}
+
+// The linear colors of an image with colors encoded to sRGB. Alpha is dropped.
+fn linearize<C>(image: &ImageBuffer<C>) -> ImageBuffer<RGB<f32>>
+where
+    C: Color,
+    RGB<C::ChannelType>: From<C>,
+    RGB<f32>: From<RGB<C::ChannelType>>,
+{
+    let size = image.size();
+    let mut linearized = ImageBuffer::new(size, RGB::new(0.0, 0.0, 0.0));
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let p = Point2::new(x, y);
+            let color = RGB::<f32>::from(RGB::from(image.get(p)));
+            *linearized.get_mut(p) = RGB::new(
+                srgb::decode(color.red),
+                srgb::decode(color.green),
+                srgb::decode(color.blue),
+            );
+        }
+    }
+    linearized
+}
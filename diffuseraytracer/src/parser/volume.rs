@@ -0,0 +1,178 @@
+use std::error::Error;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use cg_basics::volume::VolumeRegion;
+use colors::RGB;
+use math::Point3;
+use traits::{ConvenientNumber, FloatingPoint};
+
+use crate::parser::texture::fractal_noise;
+use crate::parser::util;
+use crate::parser::{FromTokens, ParsingError};
+
+impl<T> FromTokens for VolumeRegion<RGB<T>>
+where
+    T: FromStr + FloatingPoint + ConvenientNumber + 'static,
+    <T as FromStr>::Err: Error + Debug,
+    u16: Into<T>,
+{
+    type Err = ParsingError;
+
+    fn from_tokens<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<Self, Self::Err> {
+        if let Err(cause) = util::check_next_token(tokens, "{") {
+            return Err(ParsingError::VolumeParsingError(Box::new(cause)));
+        }
+
+        let mut a: Option<Point3<T>> = None;
+        let mut b: Option<Point3<T>> = None;
+        let mut density: Option<T> = None;
+        let mut albedo = RGB::new(T::one(), T::one(), T::one());
+        let mut anisotropy: Option<T> = None;
+        let mut step: Option<T> = None;
+        let mut noise: Option<&str> = None;
+        let mut frequency = T::one();
+        let mut octaves = 1;
+        let mut turbulence = false;
+        let mut seed = 0;
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "a:" => match Point3::from_tokens(tokens) {
+                    Ok(p) => {
+                        a = Some(p);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::VolumeParsingError(Box::new(cause)));
+                    }
+                },
+                "b:" => match Point3::from_tokens(tokens) {
+                    Ok(p) => {
+                        b = Some(p);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::VolumeParsingError(Box::new(cause)));
+                    }
+                },
+                "density:" => match util::parse_number(tokens) {
+                    Ok(d) => {
+                        density = Some(d);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::VolumeParsingError(Box::new(cause)));
+                    }
+                },
+                "albedo:" => match RGB::from_tokens(tokens) {
+                    Ok(color) => {
+                        albedo = color;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::VolumeParsingError(Box::new(cause)));
+                    }
+                },
+                "anisotropy:" => match util::parse_number(tokens) {
+                    Ok(g) => {
+                        anisotropy = Some(g);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::VolumeParsingError(Box::new(cause)));
+                    }
+                },
+                "step:" => match util::parse_number(tokens) {
+                    Ok(s) => {
+                        step = Some(s);
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::VolumeParsingError(Box::new(cause)));
+                    }
+                },
+                "noise:" => match tokens.next() {
+                    Some(k @ ("perlin" | "simplex" | "worley")) => {
+                        noise = Some(k);
+                    }
+                    Some(k) => {
+                        return Err(ParsingError::UnexpectedToken {
+                            expected: "perlin, simplex, worley",
+                            found: k.to_string(),
+                        });
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "frequency:" => match util::parse_number(tokens) {
+                    Ok(f) => {
+                        frequency = f;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::VolumeParsingError(Box::new(cause)));
+                    }
+                },
+                "octaves:" => match util::parse_number(tokens) {
+                    Ok(o) => {
+                        octaves = o;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::VolumeParsingError(Box::new(cause)));
+                    }
+                },
+                "fractal:" => match tokens.next() {
+                    Some("fbm") => {
+                        turbulence = false;
+                    }
+                    Some("turbulence") => {
+                        turbulence = true;
+                    }
+                    Some(f) => {
+                        return Err(ParsingError::UnexpectedToken {
+                            expected: "fbm, turbulence",
+                            found: f.to_string(),
+                        });
+                    }
+                    None => {
+                        return Err(ParsingError::UnexpectedEndOfTokens);
+                    }
+                },
+                "seed:" => match util::parse_number(tokens) {
+                    Ok(s) => {
+                        seed = s;
+                    }
+                    Err(cause) => {
+                        return Err(ParsingError::VolumeParsingError(Box::new(cause)));
+                    }
+                },
+                "}" => {
+                    break;
+                }
+                token => {
+                    return Err(ParsingError::UnexpectedToken {
+                        expected: "a:, b:, density:, albedo:, anisotropy:, step:, noise:, frequency:, octaves:, fractal:, seed:, }",
+                        found: token.to_string(),
+                    });
+                }
+            }
+        }
+
+        let Some(a) = a else {
+            return Err(ParsingError::MissingElement("a"));
+        };
+        let Some(b) = b else {
+            return Err(ParsingError::MissingElement("b"));
+        };
+        let Some(density) = density else {
+            return Err(ParsingError::MissingElement("density"));
+        };
+
+        let mut volume = VolumeRegion::new(a, b, density, albedo);
+        if let Some(anisotropy) = anisotropy {
+            volume = volume.with_anisotropy(anisotropy);
+        }
+        if let Some(step) = step {
+            volume = volume.with_step(step);
+        }
+        if let Some(kind) = noise {
+            volume = volume.with_noise(fractal_noise(kind, turbulence, octaves, seed), frequency);
+        }
+        Ok(volume)
+    }
+}
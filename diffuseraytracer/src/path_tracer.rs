@@ -0,0 +1,449 @@
+use std::sync::Arc;
+
+use crate::camera::RaytracingCamera;
+use crate::integrator::{self, Driver, Integrator};
+use crate::light::Light;
+use crate::metrics::Metrics;
+use crate::whitted_ray_tracer::{hit_distance, Tracer};
+use crate::Renderable;
+use cg_basics::scene_graph::Scene3;
+use colors::Color;
+use image::ImageBuffer;
+use math::geometry::ParametricLine;
+use math::{Point2, Point3, Vector2, Vector3};
+use random::{RandomNumberGenerator, WichmannHillPRNG};
+use sampling::{AdaptiveSampling, SamplingPatternSet};
+use traits::{ConvenientNumber, Exp, FloatingPoint, One, Sqrt, Zero};
+use units::length::Length;
+
+type SceneType<T, C> =
+    Scene3<C, Box<dyn Light<T, C>>, Box<dyn RaytracingCamera<T>>, Box<dyn Renderable<T, C>>>;
+
+// A unidirectional Monte Carlo path tracer. Every path starts at the camera and bounces through
+// the scene into directions drawn from the materials. At each bounce the lights are sampled
+// directly with shadow rays, so light sources found by the path itself only count after mirrors
+// and clear surfaces, which a light sample can not pass. Ambient lights stand in for the light
+// the paths gather themselves and are left out. Paths end when they are absorbed, after the
+// maximum depth, or by Russian roulette, which removes dark paths without biasing the image.
+pub struct PathTracer<T: Length> {
+    driver: Driver<T>,
+    max_depth: usize,
+    roulette_depth: usize,
+}
+
+impl<T: Length> PathTracer<T>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    u16: Into<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+{
+    pub fn new(
+        sampling_patterns: SamplingPatternSet<Point2<T::ValueType>>,
+        shadow_tolerance: T::ValueType,
+    ) -> PathTracer<T> {
+        PathTracer {
+            driver: Driver::new(sampling_patterns, shadow_tolerance),
+            max_depth: 8,
+            roulette_depth: 3,
+        }
+    }
+
+    pub fn with_threads(self, threads: usize) -> PathTracer<T> {
+        PathTracer {
+            driver: Driver {
+                threads: threads.max(1),
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    // The number of bounces after the camera ray. Zero renders the direct lighting only.
+    pub fn with_max_depth(self, max_depth: usize) -> PathTracer<T> {
+        PathTracer { max_depth, ..self }
+    }
+
+    // The number of bounces every path survives before Russian roulette may end it.
+    pub fn with_roulette_depth(self, roulette_depth: usize) -> PathTracer<T> {
+        PathTracer {
+            roulette_depth,
+            ..self
+        }
+    }
+
+    // Draws further patterns for a pixel until its noise is low enough.
+    pub fn with_adaptive_sampling(
+        self,
+        adaptive_sampling: AdaptiveSampling<T::ValueType>,
+    ) -> PathTracer<T> {
+        PathTracer {
+            driver: Driver {
+                adaptive_sampling: Some(adaptive_sampling),
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    pub fn with_metrics(self, metrics: Arc<Metrics>) -> PathTracer<T> {
+        PathTracer {
+            driver: Driver {
+                metrics,
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.driver.metrics)
+    }
+
+    pub fn render<C: Color<ChannelType = T::ValueType>>(
+        self,
+        scene: SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> ImageBuffer<C>
+    where
+        T::AreaType: Sqrt<Output = T>,
+    {
+        self.render_pass(&scene, camera_id, size, seed)
+    }
+
+    // Renders the image without consuming the renderer or the scene, e.g. for one of several
+    // passes with different seeds.
+    pub fn render_pass<C: Color<ChannelType = T::ValueType>>(
+        &self,
+        scene: &SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> ImageBuffer<C>
+    where
+        T::AreaType: Sqrt<Output = T>,
+    {
+        integrator::render_pass(self, scene, camera_id, size, seed)
+    }
+
+    // The light arriving along a camera ray, gathered by a single path. The procedural background
+    // of the scene is only seen by the camera ray.
+    fn trace_path<C: Color<ChannelType = T::ValueType>>(
+        &self,
+        tracer: &Tracer<T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        camera_background: Option<C>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C
+    where
+        T::AreaType: Sqrt<Output = T>,
+    {
+        let one = T::ValueType::one();
+        let max_survival = one - one / 20u16.into();
+
+        let mut color = C::default();
+        let mut throughput = C::uniform(one);
+        let mut r = r;
+        // The camera sees light sources directly, like the mirrors and clear surfaces do.
+        let mut specular = true;
+
+        for depth in 0..=self.max_depth {
+            let tolerance = if depth == 0 {
+                Zero::zero()
+            } else {
+                self.driver.shadow_tolerance
+            };
+            let hit = tracer.closest_hit(r, tolerance);
+            // The volumes in front of the hit scatter light into the path and dim what lies
+            // behind them.
+            if !tracer.scene.volumes.is_empty() {
+                let light_pattern = self.driver.sampling_patterns.draw_pattern(rnd);
+                let distance = hit_distance(r, hit.as_ref());
+                let (transmittance, inscattered) =
+                    tracer.through_volumes(r, distance, light_pattern, rnd);
+                color = color + throughput * inscattered;
+                throughput = throughput * transmittance;
+            }
+            let Some((sp, material, geometry)) = hit else {
+                match (depth, camera_background) {
+                    (0, Some(background)) => color = color + throughput * background,
+                    _ if specular => color = color + throughput * tracer.background(r),
+                    _ => {}
+                }
+                break;
+            };
+
+            if let Some(emission) = material.emission() {
+                if specular {
+                    color = color + throughput * emission;
+                }
+                break;
+            }
+
+            let lights = tracer
+                .scene
+                .lights
+                .iter()
+                .filter(|light| !light.is_indirect())
+                .filter(|light| geometry.illuminated_by(light.name()))
+                .filter(|light| {
+                    let light_pattern = self.driver.sampling_patterns.draw_pattern(rnd);
+                    tracer.illuminates(sp, geometry.as_ref(), light.as_ref(), light_pattern, rnd)
+                })
+                .map(|light| light.as_ref())
+                .collect();
+            let (diffuse, glossy) = material.diffuse_and_specular_for(sp, r.direction, lights);
+            color = color + throughput * (diffuse + glossy);
+
+            if depth == self.max_depth {
+                break;
+            }
+
+            let sample = *self
+                .driver
+                .sampling_patterns
+                .draw_pattern(rnd)
+                .draw_point(rnd);
+            let Some(scattering) = material.scatter(sp, r.direction, sample) else {
+                break;
+            };
+            throughput = throughput * scattering.weight;
+            specular = scattering.specular;
+
+            // Paths that carry little light are likely to end, the survivors carry their share.
+            if depth + 1 >= self.roulette_depth {
+                let survival = if throughput.max_channel() < max_survival {
+                    throughput.max_channel()
+                } else {
+                    max_survival
+                };
+                if self
+                    .driver
+                    .sampling_patterns
+                    .draw_pattern(rnd)
+                    .draw_point(rnd)
+                    .x
+                    >= survival
+                {
+                    break;
+                }
+                throughput = throughput * (one / survival);
+            }
+
+            r = ParametricLine::new(sp.p, scattering.direction * T::one());
+        }
+
+        color
+    }
+}
+
+impl<T: Length, C: Color<ChannelType = T::ValueType>> Integrator<T, C> for PathTracer<T>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    T::AreaType: Sqrt<Output = T>,
+    u16: Into<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+{
+    fn driver(&self) -> &Driver<T> {
+        &self.driver
+    }
+
+    fn trace_camera_ray(
+        &self,
+        tracer: &Tracer<T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        camera_background: Option<C>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C {
+        self.trace_path(tracer, r, camera_background, rnd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use cg_basics::camera::PinholeCamera;
+    use cg_basics::light::PointLight;
+    use cg_basics::material::LambertMaterial;
+    use cg_basics::scene_graph::{LightLinks, RenderableGeometry};
+    use colors::RGB;
+    use image::{Image, SingleColorImage};
+    use math::geometry::ImplicitPlane3;
+    use math::transform::Transform3;
+    use math::Normal3;
+    use sampling::JitteredPatternGenerator;
+    use traits::ToRadians;
+    use units::angle::Degrees;
+    use units::length::Meter;
+
+    macro_rules! path_tracer_indirect_light {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A gray floor under a gray ceiling with a lamp in between. The floor does not
+                // see the lamp and only receives the light the ceiling reflects.
+                let render = |max_depth: usize, roulette_depth: usize| {
+                    let floor = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let ceiling = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(2.0), Meter::new(0.0)),
+                        Normal3::new(0.0, -1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let gray = || {
+                        LambertMaterial::new(SingleColorImage::new(
+                            RGB::<$type>::new(0.5, 0.5, 0.5),
+                            Vector2::new(1.0, 1.0),
+                        ))
+                    };
+
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(
+                            RenderableGeometry::new(floor, gray(), Transform3::<$type>::ident())
+                                .with_light_links(LightLinks::Exclude(vec![String::from("lamp")])),
+                        ),
+                        Box::new(RenderableGeometry::new(
+                            ceiling,
+                            gray(),
+                            Transform3::<$type>::ident(),
+                        )),
+                    ];
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> = vec![Box::new(
+                        PointLight::new(
+                            RGB::new(1.0, 1.0, 1.0),
+                            Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                        )
+                        .with_name(String::from("lamp")),
+                    )];
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                            Degrees::<$type>::new(1.0).to_radians(),
+                        )),
+                    );
+
+                    let scene = Scene3::new(RGB::new(0.0, 0.0, 0.0), lights, cameras, geometries);
+
+                    let image = PathTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::jittered_patterns(
+                            4,
+                            32,
+                            32,
+                            &mut WichmannHillPRNG::from_seed(7),
+                        ),
+                        0.0001,
+                    )
+                    .with_max_depth(max_depth)
+                    .with_roulette_depth(roulette_depth)
+                    .render(scene, "main", Vector2::new(1, 1), 0);
+                    image.get(Point2::new(0, 0)).red
+                };
+
+                assert_eq!(render(0, 10), 0.0);
+
+                // The light reflected by the ceiling, integrated over the hemisphere of the
+                // floor.
+                let rendered = render(1, 10);
+                assert!((rendered - 0.1182).abs() < 0.005, "{}", rendered);
+
+                // Russian roulette ends paths early, but keeps the image on average.
+                let reference = render(8, 10);
+                let rendered = render(8, 1);
+                assert!(rendered > render(1, 10));
+                assert!(
+                    (rendered - reference).abs() < 0.01,
+                    "{} {}",
+                    rendered,
+                    reference
+                );
+            }
+        };
+    }
+
+    path_tracer_indirect_light! { f32, path_tracer_indirect_light_f32 }
+    path_tracer_indirect_light! { f64, path_tracer_indirect_light_f64 }
+
+    macro_rules! path_tracer_adaptive_sampling {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // Counts the camera rays of a pixel that looks at the sky and of one that looks
+                // at a lit floor, whose samples differ a little from each other.
+                let camera_rays = |floor: bool, threshold: $type| {
+                    let mut geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> = vec![];
+                    if floor {
+                        geometries.push(Box::new(RenderableGeometry::new(
+                            ImplicitPlane3::new(
+                                Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                                Normal3::new(0.0, 1.0, 0.0),
+                                Vector3::new(1.0, 0.0, 0.0),
+                            ),
+                            LambertMaterial::new(SingleColorImage::new(
+                                RGB::<$type>::new(0.5, 0.5, 0.5),
+                                Vector2::new(1.0, 1.0),
+                            )),
+                            Transform3::<$type>::ident(),
+                        )));
+                    }
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> =
+                        vec![Box::new(PointLight::new(
+                            RGB::new(1.0, 1.0, 1.0),
+                            Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                        ))];
+
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                            Degrees::<$type>::new(90.0).to_radians(),
+                        )),
+                    );
+
+                    let scene = Scene3::new(RGB::new(0.0, 0.0, 0.0), lights, cameras, geometries);
+
+                    let path_tracer = PathTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::jittered_patterns(
+                            4,
+                            2,
+                            2,
+                            &mut WichmannHillPRNG::from_seed(7),
+                        ),
+                        0.0001,
+                    )
+                    .with_max_depth(0)
+                    .with_adaptive_sampling(AdaptiveSampling::new(6, 16, threshold));
+                    let metrics = path_tracer.metrics();
+                    path_tracer.render(scene, "main", Vector2::new(1, 1), 0);
+                    metrics.snapshot().camera_rays
+                };
+
+                // The minimal number of samples is rounded up to whole patterns.
+                assert_eq!(camera_rays(false, 0.01), 8);
+                assert_eq!(camera_rays(true, 0.0), 16);
+                assert_eq!(camera_rays(true, 1.0), 8);
+            }
+        };
+    }
+
+    path_tracer_adaptive_sampling! { f32, path_tracer_adaptive_sampling_f32 }
+    path_tracer_adaptive_sampling! { f64, path_tracer_adaptive_sampling_f64 }
+}
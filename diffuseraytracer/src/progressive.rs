@@ -0,0 +1,270 @@
+use std::ops::{DivAssign, Sub};
+use std::time::{Duration, Instant};
+
+use colors::Color;
+use image::accumulation_buffer::AccumulationBuffer;
+use image::{Image, ImageBuffer};
+use math::{Point2, Vector2};
+
+use crate::metrics::Metrics;
+
+// Renders an image in passes and averages them, so a noisy preview is available long before the
+// render is done. Every pass is a complete render with its own seed.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Progressive {
+    pub passes: usize,
+    // The minimal time between two previews. Zero shows every pass.
+    pub update_interval: Duration,
+}
+
+impl Progressive {
+    pub fn new(passes: usize) -> Progressive {
+        Progressive {
+            passes: passes.max(1),
+            update_interval: Duration::ZERO,
+        }
+    }
+
+    pub fn with_update_interval(self, update_interval: Duration) -> Progressive {
+        Progressive {
+            update_interval,
+            ..self
+        }
+    }
+
+    // Calls render_pass with the seed of every pass and update with the number of finished passes
+    // and the average so far. The last pass is not shown, as the returned average is the final
+    // image.
+    pub fn render<C>(
+        &self,
+        size: Vector2<usize>,
+        seed: u128,
+        metrics: &Metrics,
+        render_pass: impl FnMut(u128) -> ImageBuffer<C>,
+        update: impl FnMut(usize, &AccumulationBuffer<C>),
+    ) -> AccumulationBuffer<C>
+    where
+        C: Color + Sub<Output = C> + DivAssign<C::ChannelType>,
+    {
+        self.resume(
+            AccumulationBuffer::new(size),
+            0,
+            seed,
+            metrics,
+            render_pass,
+            update,
+        )
+    }
+
+    // Continues a render whose first passes are already accumulated, e.g. from a checkpoint. The
+    // remaining passes draw the same samples as they would have without the interruption.
+    pub fn resume<C>(
+        &self,
+        mut accumulated: AccumulationBuffer<C>,
+        passes_done: usize,
+        seed: u128,
+        metrics: &Metrics,
+        mut render_pass: impl FnMut(u128) -> ImageBuffer<C>,
+        mut update: impl FnMut(usize, &AccumulationBuffer<C>),
+    ) -> AccumulationBuffer<C>
+    where
+        C: Color + Sub<Output = C> + DivAssign<C::ChannelType>,
+    {
+        let size = accumulated.size();
+        let pixels = (size.x * size.y) as u64;
+        let pixels_total = metrics.pixels_total.get();
+        let mut last_update = Instant::now();
+
+        // The finished passes count as rendered, so the progress covers the whole render.
+        let passes_done = passes_done.min(self.passes);
+        metrics.pixels.add(pixels * passes_done as u64);
+
+        for pass in passes_done..self.passes {
+            // Every pass adds its pixels to the total when it starts. The passes still to come are
+            // counted ahead.
+            metrics
+                .pixels_total
+                .set(pixels_total + pixels * (self.passes - 1) as u64);
+
+            let image = render_pass(random::pass_seed(seed, pass as u64));
+            // A cancelled pass misses tiles, so it is only kept if there is no other to show.
+            if metrics.is_cancelled() && pass > 0 {
+                break;
+            }
+            for y in 0..size.y {
+                for x in 0..size.x {
+                    let p = Point2::new(x, y);
+                    accumulated.add_sample(p, image.get(p));
+                }
+            }
+
+            if metrics.is_cancelled() {
+                break;
+            }
+
+            if pass + 1 < self.passes && last_update.elapsed() >= self.update_interval {
+                update(pass + 1, &accumulated);
+                last_update = Instant::now();
+            }
+        }
+
+        accumulated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::RGB;
+    use image::WritableImage;
+
+    macro_rules! progressive_render_averages_passes {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let size = Vector2::new(3, 2);
+                let metrics = Metrics::new();
+                let mut seeds = vec![];
+                let mut updates = vec![];
+
+                let image = Progressive::new(4).render(
+                    size,
+                    7,
+                    &metrics,
+                    |seed| {
+                        seeds.push(seed);
+                        metrics.pixels_total.add(6);
+                        metrics.pixels.add(6);
+                        let value = seeds.len() as $type;
+                        let mut image = ImageBuffer::new(size, RGB::<$type>::default());
+                        *image.get_mut(Point2::new(1, 1)) = RGB::new(value, 0.0, 1.0);
+                        image
+                    },
+                    |passes, image| updates.push((passes, image.get(Point2::new(1, 1)))),
+                );
+
+                assert_eq!(image.get(Point2::new(1, 1)), RGB::new(2.5, 0.0, 1.0));
+                assert_eq!(image.get(Point2::new(0, 0)), RGB::new(0.0, 0.0, 0.0));
+                assert_eq!(
+                    updates,
+                    vec![
+                        (1, RGB::new(1.0, 0.0, 1.0)),
+                        (2, RGB::new(1.5, 0.0, 1.0)),
+                        (3, RGB::new(2.0, 0.0, 1.0)),
+                    ]
+                );
+
+                // Every pass draws other samples.
+                seeds.sort();
+                seeds.dedup();
+                assert_eq!(seeds.len(), 4);
+
+                let statistics = metrics.snapshot();
+                assert_eq!(statistics.pixels_total, 24);
+                assert_eq!(statistics.progress(), 1.0);
+            }
+        };
+    }
+
+    progressive_render_averages_passes! { f32, progressive_render_averages_passes_f32 }
+    progressive_render_averages_passes! { f64, progressive_render_averages_passes_f64 }
+
+    macro_rules! progressive_render_stops_when_cancelled {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let size = Vector2::new(2, 1);
+                let metrics = Metrics::new();
+                let mut passes = 0;
+
+                // The third pass is cancelled while it renders and left out.
+                let image = Progressive::new(5).render(
+                    size,
+                    1,
+                    &metrics,
+                    |_| {
+                        passes += 1;
+                        if passes == 3 {
+                            metrics.cancel();
+                        }
+                        ImageBuffer::new(size, RGB::<$type>::new(passes as $type, 0.0, 0.0))
+                    },
+                    |_, _| {},
+                );
+
+                assert_eq!(passes, 3);
+                assert_eq!(image.get(Point2::new(0, 0)), RGB::new(1.5, 0.0, 0.0));
+
+                // A first pass is kept, even if it is cancelled.
+                let metrics = Metrics::new();
+                let image = Progressive::new(5).render(
+                    size,
+                    1,
+                    &metrics,
+                    |_| {
+                        metrics.cancel();
+                        ImageBuffer::new(size, RGB::<$type>::new(2.0, 0.0, 0.0))
+                    },
+                    |_, _| {},
+                );
+
+                assert_eq!(image.get(Point2::new(1, 0)), RGB::new(2.0, 0.0, 0.0));
+            }
+        };
+    }
+
+    progressive_render_stops_when_cancelled! { f32, progressive_render_stops_when_cancelled_f32 }
+    progressive_render_stops_when_cancelled! { f64, progressive_render_stops_when_cancelled_f64 }
+
+    macro_rules! progressive_resume_continues_render {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let size = Vector2::new(2, 1);
+                let render_pass = |seed: u128| {
+                    let value = (seed % 7) as $type;
+                    ImageBuffer::new(size, RGB::<$type>::new(value, 1.0, 0.0))
+                };
+
+                let full =
+                    Progressive::new(5).render(size, 3, &Metrics::new(), render_pass, |_, _| {});
+
+                let mut interrupted = None;
+                Progressive::new(5).render(
+                    size,
+                    3,
+                    &Metrics::new(),
+                    render_pass,
+                    |passes, image: &AccumulationBuffer<RGB<$type>>| {
+                        if passes == 2 {
+                            interrupted = Some(image.clone());
+                        }
+                    },
+                );
+
+                let metrics = Metrics::new();
+                let mut seeds = 0;
+                let resumed = Progressive::new(5).resume(
+                    interrupted.unwrap(),
+                    2,
+                    3,
+                    &metrics,
+                    |seed| {
+                        seeds += 1;
+                        render_pass(seed)
+                    },
+                    |_, _| {},
+                );
+
+                assert_eq!(seeds, 3);
+                assert_eq!(resumed.get(Point2::new(1, 0)), full.get(Point2::new(1, 0)));
+                assert_eq!(resumed.samples(Point2::new(1, 0)), 5.0);
+                assert_eq!(metrics.pixels.get(), 4);
+            }
+        };
+    }
+
+    progressive_resume_continues_render! { f32, progressive_resume_continues_render_f32 }
+    progressive_resume_continues_render! { f64, progressive_resume_continues_render_f64 }
+}
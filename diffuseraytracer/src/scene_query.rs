@@ -0,0 +1,149 @@
+use std::cmp::Ordering;
+
+use cg_basics::scene_graph::Scene3;
+use colors::Color;
+use math::{Normal3, Point3};
+use traits::{Sqrt, Zero};
+use units::length::Length;
+
+use crate::Renderable;
+
+// Queries the surfaces of a scene for the point that is the closest to a position, e.g. to march
+// through a volume or to place an object on the ground. There is no acceleration structure yet, so
+// each query checks every geometry.
+pub trait SceneQuery<T: Length> {
+    fn closest_point(&self, p: Point3<T>) -> Option<Point3<T>>;
+
+    // The distance to the closest surface, which is negative inside of a closed geometry. None if
+    // no geometry supports the query.
+    fn distance(&self, p: Point3<T>) -> Option<T>;
+}
+
+impl<T, C, L, CAM> SceneQuery<T> for Scene3<C, L, CAM, Box<dyn Renderable<T, C>>>
+where
+    T: Length,
+    T::ValueType: Sqrt<Output = T::ValueType>,
+    C: Color<ChannelType = T::ValueType>,
+{
+    fn closest_point(&self, p: Point3<T>) -> Option<Point3<T>> {
+        closest(&self.geometries, p).map(|(c, _, _)| c)
+    }
+
+    fn distance(&self, p: Point3<T>) -> Option<T> {
+        closest(&self.geometries, p).map(|(c, n, distance)| {
+            if ((p - c) / T::one()).dot(n.as_vector()) < Zero::zero() {
+                T::zero() - distance
+            } else {
+                distance
+            }
+        })
+    }
+}
+
+fn closest<T, C>(
+    geometries: &[Box<dyn Renderable<T, C>>],
+    p: Point3<T>,
+) -> Option<(Point3<T>, Normal3<T::ValueType>, T)>
+where
+    T: Length,
+    T::ValueType: Sqrt<Output = T::ValueType>,
+    C: Color<ChannelType = T::ValueType>,
+{
+    geometries
+        .iter()
+        .filter_map(|g| g.closest_point(p))
+        .map(|(c, n)| {
+            let offset = (p - c) / T::one();
+            (c, n, offset.dot(offset).sqrt() * T::one())
+        })
+        .min_by(|(_, _, a), (_, _, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use cg_basics::material::LambertMaterial;
+    use cg_basics::scene_graph::RenderableGeometry;
+    use colors::RGB;
+    use image::SingleColorImage;
+    use math::geometry::{ImplicitNSphere, ImplicitPlane3};
+    use math::transform::Transform3;
+    use math::{Vector2, Vector3};
+    use units::length::Meter;
+
+    use crate::camera::RaytracingCamera;
+    use crate::light::Light;
+
+    type SceneType<T, C> =
+        Scene3<C, Box<dyn Light<T, C>>, Box<dyn RaytracingCamera<T>>, Box<dyn Renderable<T, C>>>;
+
+    macro_rules! scene_query {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let material = || {
+                    LambertMaterial::new(SingleColorImage::new(
+                        RGB::<$type>::new(1.0, 1.0, 1.0),
+                        Vector2::new(1.0, 1.0),
+                    ))
+                };
+
+                let plane = ImplicitPlane3::new(
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    Normal3::new(0.0, 1.0, 0.0),
+                    Vector3::new(1.0, 0.0, 0.0),
+                );
+                let sphere = ImplicitNSphere::new(
+                    Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                    Meter::new(1.0),
+                );
+
+                let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> = vec![
+                    Box::new(RenderableGeometry::new(
+                        plane,
+                        material(),
+                        Transform3::<$type>::ident(),
+                    )),
+                    Box::new(RenderableGeometry::new(
+                        sphere,
+                        material(),
+                        Transform3::<$type>::ident().translate(0.0, 3.0, 0.0),
+                    )),
+                ];
+                let scene: SceneType<Meter<$type>, RGB<$type>> =
+                    Scene3::new(RGB::default(), vec![], HashMap::new(), geometries);
+
+                let p = Point3::new(Meter::new(0.0), Meter::new(5.0), Meter::new(0.0));
+                assert_eq!(
+                    scene.closest_point(p),
+                    Some(Point3::new(
+                        Meter::new(0.0),
+                        Meter::new(4.0),
+                        Meter::new(0.0)
+                    ))
+                );
+                assert_eq!(scene.distance(p), Some(Meter::new(1.0)));
+
+                let p = Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0));
+                assert_eq!(scene.distance(p), Some(Meter::new(1.0)));
+
+                let p = Point3::new(Meter::new(0.0), Meter::new(3.5), Meter::new(0.0));
+                assert_eq!(scene.distance(p), Some(Meter::new(-0.5)));
+
+                let p = Point3::new(Meter::new(4.0), Meter::new(-2.0), Meter::new(0.0));
+                assert_eq!(scene.distance(p), Some(Meter::new(-2.0)));
+
+                let empty: SceneType<Meter<$type>, RGB<$type>> =
+                    Scene3::new(RGB::default(), vec![], HashMap::new(), vec![]);
+                assert_eq!(empty.closest_point(p), None);
+                assert_eq!(empty.distance(p), None);
+            }
+        };
+    }
+
+    scene_query! { f32, scene_query_f32 }
+    scene_query! { f64, scene_query_f64 }
+}
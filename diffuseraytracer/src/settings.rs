@@ -0,0 +1,38 @@
+use traits::FloatingPoint;
+
+// How a scene is meant to be rendered, given by the settings block of the scene. Options left
+// out fall back to the defaults of the renderer, and arguments on the command line take
+// precedence.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RenderSettings<V> {
+    // How often the whitted and the path integrator follow a ray to the next surface.
+    pub max_depth: Option<usize>,
+    // How far shadow rays start off the surface, so they do not hit the surface they start on.
+    pub shadow_bias: V,
+    // The number of samples per pixel.
+    pub samples: Option<usize>,
+}
+
+impl<V> RenderSettings<V>
+where
+    V: FloatingPoint,
+    u16: Into<V>,
+{
+    pub fn new() -> RenderSettings<V> {
+        RenderSettings {
+            max_depth: None,
+            shadow_bias: V::one() / 10000u16.into(),
+            samples: None,
+        }
+    }
+}
+
+impl<V> Default for RenderSettings<V>
+where
+    V: FloatingPoint,
+    u16: Into<V>,
+{
+    fn default() -> RenderSettings<V> {
+        RenderSettings::new()
+    }
+}
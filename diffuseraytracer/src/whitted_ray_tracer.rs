@@ -0,0 +1,885 @@
+use std::cell::Cell;
+use std::sync::Arc;
+
+use crate::camera::RaytracingCamera;
+use crate::integrator::{self, Driver, Integrator};
+use crate::light::Light;
+use crate::material::{refraction, Material};
+use crate::metrics::Metrics;
+use crate::{Renderable, PACKET_WIDTH};
+use cg_basics::scene_graph::Scene3;
+use colors::Color;
+use image::ImageBuffer;
+use math::geometry::{ParametricLine, RayPacket, SurfacePoint};
+use math::{Normal3, Point2, Point3, Vector2, Vector3};
+use random::{RandomNumberGenerator, WichmannHillPRNG};
+use sampling::{AdaptiveSampling, SamplingPattern, SamplingPatternSet};
+use traits::{ConvenientNumber, Exp, FloatingPoint, Min, One, Sqrt, Zero};
+use units::length::Length;
+
+type SceneType<T, C> =
+    Scene3<C, Box<dyn Light<T, C>>, Box<dyn RaytracingCamera<T>>, Box<dyn Renderable<T, C>>>;
+
+// The closest intersection of a ray with the scene.
+pub(crate) type Hit<'a, T, C> = (
+    SurfacePoint<T>,
+    &'a dyn Material<T, ColorType = C>,
+    &'a Box<dyn Renderable<T, C>>,
+);
+
+// A recursive ray tracer after Whitted. Where a ray hits a mirror or a clear surface, reflection
+// and refraction rays are traced further into the scene, up to a maximum depth. Every ray carries
+// the share it contributes to the pixel, and branches of the ray tree whose share drops below a
+// threshold are not traced at all.
+pub struct WhittedRayTracer<T: Length> {
+    driver: Driver<T>,
+    max_depth: usize,
+    min_contribution: T::ValueType,
+    packets: bool,
+}
+
+impl<T: Length> WhittedRayTracer<T>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    u16: Into<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+{
+    pub fn new(
+        sampling_patterns: SamplingPatternSet<Point2<T::ValueType>>,
+        shadow_tolerance: T::ValueType,
+    ) -> WhittedRayTracer<T> {
+        WhittedRayTracer {
+            driver: Driver::new(sampling_patterns, shadow_tolerance),
+            max_depth: 5,
+            min_contribution: T::ValueType::one() / 256u16.into(),
+            packets: false,
+        }
+    }
+
+    pub fn with_threads(self, threads: usize) -> WhittedRayTracer<T> {
+        WhittedRayTracer {
+            driver: Driver {
+                threads: threads.max(1),
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    // The number of reflections and refractions along a path from the camera. Zero renders the
+    // same direct lighting as the diffuse ray tracer.
+    pub fn with_max_depth(self, max_depth: usize) -> WhittedRayTracer<T> {
+        WhittedRayTracer { max_depth, ..self }
+    }
+
+    // The share of the pixel below which a branch of the ray tree is not traced any further.
+    pub fn with_min_contribution(self, min_contribution: T::ValueType) -> WhittedRayTracer<T> {
+        WhittedRayTracer {
+            min_contribution,
+            ..self
+        }
+    }
+
+    // Draws further patterns for a pixel until its noise is low enough.
+    pub fn with_adaptive_sampling(
+        self,
+        adaptive_sampling: AdaptiveSampling<T::ValueType>,
+    ) -> WhittedRayTracer<T> {
+        WhittedRayTracer {
+            driver: Driver {
+                adaptive_sampling: Some(adaptive_sampling),
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    // Intersects the camera rays of a pattern with the scene in packets. The rays of a pattern are
+    // all drawn before the first one is shaded, so the random numbers are used in another order
+    // and the noise differs from a render without packets.
+    pub fn with_packets(self) -> WhittedRayTracer<T> {
+        WhittedRayTracer {
+            packets: true,
+            ..self
+        }
+    }
+
+    pub fn with_metrics(self, metrics: Arc<Metrics>) -> WhittedRayTracer<T> {
+        WhittedRayTracer {
+            driver: Driver {
+                metrics,
+                ..self.driver
+            },
+            ..self
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.driver.metrics)
+    }
+
+    pub fn render<C: Color<ChannelType = T::ValueType>>(
+        self,
+        scene: SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> ImageBuffer<C>
+    where
+        T::AreaType: Sqrt<Output = T>,
+    {
+        self.render_pass(&scene, camera_id, size, seed)
+    }
+
+    // Renders the image without consuming the renderer or the scene, e.g. for one of several
+    // passes with different seeds.
+    pub fn render_pass<C: Color<ChannelType = T::ValueType>>(
+        &self,
+        scene: &SceneType<T, C>,
+        camera_id: &str,
+        size: Vector2<usize>,
+        seed: u128,
+    ) -> ImageBuffer<C>
+    where
+        T::AreaType: Sqrt<Output = T>,
+    {
+        integrator::render_pass(self, scene, camera_id, size, seed)
+    }
+
+    // The light leaving a surface towards the origin of a ray. The contribution is the share of
+    // the pixel the ray stands for.
+    fn trace<C: Color<ChannelType = T::ValueType>>(
+        &self,
+        tracer: &Tracer<T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        depth: usize,
+        contribution: T::ValueType,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C
+    where
+        T::AreaType: Sqrt<Output = T>,
+    {
+        let hit = tracer.closest_hit(r, self.driver.shadow_tolerance);
+        let distance = hit_distance(r, hit.as_ref());
+        let color = match hit {
+            Some(hit) => self.shade(tracer, r, hit, depth, contribution, rnd),
+            None => tracer.background(r),
+        };
+        self.through_volumes(tracer, r, distance, color, rnd)
+    }
+
+    // The color at a distance along a ray, as seen through the volumes in front of it.
+    fn through_volumes<C: Color<ChannelType = T::ValueType>>(
+        &self,
+        tracer: &Tracer<T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        distance: T::ValueType,
+        color: C,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C
+    where
+        T::AreaType: Sqrt<Output = T>,
+    {
+        if tracer.scene.volumes.is_empty() {
+            return color;
+        }
+        let light_pattern = self.driver.sampling_patterns.draw_pattern(rnd);
+        let (transmittance, inscattered) = tracer.through_volumes(r, distance, light_pattern, rnd);
+        color * transmittance + inscattered
+    }
+
+    fn shade<C: Color<ChannelType = T::ValueType>>(
+        &self,
+        tracer: &Tracer<T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        (sp, material, geometry): Hit<T, C>,
+        depth: usize,
+        contribution: T::ValueType,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C
+    where
+        T::AreaType: Sqrt<Output = T>,
+    {
+        if let Some(emission) = material.emission() {
+            return emission;
+        }
+
+        let lights = tracer
+            .scene
+            .lights
+            .iter()
+            .filter(|light| geometry.illuminated_by(light.name()))
+            .filter(|light| {
+                let light_pattern = self.driver.sampling_patterns.draw_pattern(rnd);
+                tracer.illuminates(sp, geometry.as_ref(), light.as_ref(), light_pattern, rnd)
+            })
+            .map(|light| light.as_ref())
+            .collect();
+        let (diffuse, specular) = material.diffuse_and_specular_for(sp, r.direction, lights);
+        let mut color = diffuse + specular;
+
+        let one = T::ValueType::one();
+        let d = r.direction.normalized();
+        let n = sp.n.as_vector().normalized();
+        let traced = depth < self.max_depth;
+        let reflected = ParametricLine::new(sp.p, d.reflect_on(n.as_normal()) * T::one());
+
+        match (material.mirror_reflectance(sp), traced) {
+            (Some(reflectance), true) => {
+                let share = contribution * reflectance.max_channel();
+                if share >= self.min_contribution {
+                    color =
+                        color + self.trace(tracer, reflected, depth + 1, share, rnd) * reflectance;
+                }
+            }
+            _ => {
+                color = color + material.reflection_for(sp, r.direction);
+            }
+        }
+
+        if let (Some((transmittance, index_of_refraction)), true) =
+            (material.transmission(sp), traced)
+        {
+            let (_, refracted, fresnel) = refraction(d, n, index_of_refraction);
+
+            let share = contribution * fresnel;
+            if share >= self.min_contribution {
+                color = color + self.trace(tracer, reflected, depth + 1, share, rnd) * fresnel;
+            }
+            if let Some(refracted) = refracted {
+                let refracted = ParametricLine::new(sp.p, refracted * T::one());
+                let share = contribution * (one - fresnel) * transmittance.max_channel();
+                if share >= self.min_contribution {
+                    color = color
+                        + self.trace(tracer, refracted, depth + 1, share, rnd)
+                            * transmittance
+                            * (one - fresnel);
+                }
+            }
+        }
+
+        color
+    }
+}
+
+impl<T: Length, C: Color<ChannelType = T::ValueType>> Integrator<T, C> for WhittedRayTracer<T>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber + Exp<Output = T::ValueType>,
+    T::AreaType: Sqrt<Output = T>,
+    u16: Into<T::ValueType>,
+    WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+{
+    fn driver(&self) -> &Driver<T> {
+        &self.driver
+    }
+
+    fn trace_camera_ray(
+        &self,
+        tracer: &Tracer<T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        camera_background: Option<C>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C {
+        let hit = tracer.closest_hit(r, Zero::zero());
+        self.trace_camera_hit(tracer, r, hit, camera_background, rnd)
+    }
+
+    fn packets(&self) -> bool {
+        self.packets
+    }
+
+    // The color seen along a camera ray, shaded where the ray hits the scene.
+    fn trace_camera_hit<'a>(
+        &self,
+        tracer: &Tracer<'a, T, C>,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        hit: Option<Hit<'a, T, C>>,
+        camera_background: Option<C>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> C {
+        let distance = hit_distance(r, hit.as_ref());
+        let color = match hit {
+            Some(hit) => self.shade(tracer, r, hit, 0, One::one(), rnd),
+            None => camera_background.unwrap_or_else(|| tracer.background(r)),
+        };
+        self.through_volumes(tracer, r, distance, color, rnd)
+    }
+}
+
+// The ray parameter of a hit, or infinity for rays that leave the scene.
+pub(crate) fn hit_distance<T: Length, C: Color>(
+    r: ParametricLine<Point3<T>, Vector3<T>>,
+    hit: Option<&Hit<T, C>>,
+) -> T::ValueType
+where
+    T::ValueType: FloatingPoint,
+    T::AreaType: Sqrt<Output = T>,
+{
+    hit.map_or(T::ValueType::INFINITY, |(sp, _, _)| {
+        (sp.p - r.origin).magnitude() / r.direction.magnitude()
+    })
+}
+
+// The scene at the time of a camera ray, along with the counters of the rays cast for it.
+pub(crate) struct Tracer<'a, T: Length, C: Color> {
+    pub(crate) scene: &'a SceneType<T, C>,
+    pub(crate) time: T::ValueType,
+    pub(crate) shadow_tolerance: T::ValueType,
+    pub(crate) rays: &'a Cell<u64>,
+    pub(crate) shadow_rays: &'a Cell<u64>,
+}
+
+impl<'a, T: Length, C: Color<ChannelType = T::ValueType>> Tracer<'a, T, C>
+where
+    T::ValueType: FloatingPoint + ConvenientNumber,
+    T::AreaType: Sqrt<Output = T>,
+    u16: Into<T::ValueType>,
+{
+    pub(crate) fn closest_hit(
+        &self,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        tolerance: T::ValueType,
+    ) -> Option<Hit<'a, T, C>> {
+        self.rays.set(self.rays.get() + 1);
+        self.first_hit(r, self.time, tolerance)
+    }
+
+    // The closest intersections of up to a packet of rays, each traced at its own time. The packet
+    // finds the closest geometry along each ray, and only that one is intersected once more for
+    // the surface point.
+    pub(crate) fn closest_hits(
+        &self,
+        rays: &[ParametricLine<Point3<T>, Vector3<T>>],
+        times: &[T::ValueType],
+        tolerance: T::ValueType,
+    ) -> Vec<Option<Hit<'a, T, C>>> {
+        self.rays.set(self.rays.get() + rays.len() as u64);
+
+        // Lanes without a ray of their own repeat the first one.
+        let lanes: [_; PACKET_WIDTH] = std::array::from_fn(|lane| {
+            let lane = lane.min(rays.len() - 1);
+            (rays[lane], times[lane])
+        });
+        let packet = RayPacket::new(lanes.map(|(r, _)| r));
+        let times = lanes.map(|(_, time)| time);
+
+        let mut closest = [(T::ValueType::INFINITY, None); PACKET_WIDTH];
+        for (index, g) in self.scene.geometries.iter().enumerate() {
+            let epsilon = g.epsilon().unwrap_or(tolerance);
+            let distances = g
+                .intersect_packet(&packet, times, epsilon)
+                .unwrap_or_else(|| {
+                    lanes.map(|(r, time)| {
+                        g.intersect_at(r, time)
+                            .into_iter()
+                            .map(|(t, _, _)| t)
+                            .filter(|t| *t > epsilon)
+                            .fold(T::ValueType::INFINITY, |a, b| if b < a { b } else { a })
+                    })
+                });
+            for (closest, t) in closest.iter_mut().zip(distances) {
+                if t < closest.0 {
+                    *closest = (t, Some(index));
+                }
+            }
+        }
+
+        lanes
+            .iter()
+            .zip(closest)
+            .take(rays.len())
+            .map(|((r, time), (_, index))| {
+                let g = &self.scene.geometries[index?];
+                let epsilon = g.epsilon().unwrap_or(tolerance);
+                g.intersect_at(*r, *time)
+                    .into_iter()
+                    .filter(|(t, _, _)| *t > epsilon)
+                    .min_by(|(t1, _, _), (t2, _, _)| t1.partial_cmp(t2).unwrap())
+                    .map(|(_, sp, material)| (sp, material, g))
+                    // The test of a single ray can miss where the packet grazed an edge.
+                    .or_else(|| self.first_hit(*r, *time, tolerance))
+            })
+            .collect()
+    }
+
+    fn first_hit(
+        &self,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        time: T::ValueType,
+        tolerance: T::ValueType,
+    ) -> Option<Hit<'a, T, C>> {
+        self.scene
+            .geometries
+            .iter()
+            .flat_map(|g| {
+                let epsilon = g.epsilon().unwrap_or(tolerance);
+                g.intersect_at(r, time)
+                    .into_iter()
+                    .filter(move |(t, _, _)| *t > epsilon)
+                    .map(move |(t, sp, material)| (t, sp, material, g))
+            })
+            .min_by(|(t1, _, _, _), (t2, _, _, _)| t1.partial_cmp(t2).unwrap())
+            .map(|(_, sp, material, g)| (sp, material, g))
+    }
+
+    // Whether a light reaches a surface point, tested with a shadow ray.
+    pub(crate) fn illuminates(
+        &self,
+        sp: SurfacePoint<T>,
+        geometry: &dyn Renderable<T, C>,
+        light: &dyn Light<T, C>,
+        light_pattern: &SamplingPattern<Point2<T::ValueType>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> bool {
+        let bias = geometry.epsilon().unwrap_or(self.shadow_tolerance);
+        self.light_reaches(sp, bias, light, light_pattern, rnd)
+    }
+
+    // Whether a light reaches a point, with the bias of shadow rays for lights without their own.
+    fn light_reaches(
+        &self,
+        sp: SurfacePoint<T>,
+        bias: T::ValueType,
+        light: &dyn Light<T, C>,
+        light_pattern: &SamplingPattern<Point2<T::ValueType>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> bool {
+        let light_bias = light.shadow_bias().unwrap_or(bias);
+        light.illuminates(
+            sp,
+            &|shadow_ray, max_distance| {
+                self.shadow_rays.set(self.shadow_rays.get() + 1);
+                let t_max = max_distance.map_or(T::ValueType::INFINITY, |d| d / T::one());
+                self.scene
+                    .geometries
+                    .iter()
+                    .filter(|g| g.casts_shadows())
+                    .any(|g| {
+                        let bias = g.shadow_bias().unwrap_or(light_bias);
+                        g.intersects_any(shadow_ray, self.time, bias, t_max)
+                    })
+            },
+            light_pattern,
+            rnd,
+        )
+    }
+
+    // Whether nothing that casts shadows lies between two points, for renderers that connect
+    // paths.
+    pub(crate) fn connects(&self, from: Point3<T>, to: Point3<T>) -> bool {
+        self.shadow_rays.set(self.shadow_rays.get() + 1);
+        let distance = (to - from).magnitude() / T::one();
+        let r = ParametricLine::new(from, (to - from).normalized() * T::one());
+        !self
+            .scene
+            .geometries
+            .iter()
+            .filter(|g| g.casts_shadows())
+            .any(|g| {
+                let bias = g
+                    .shadow_bias()
+                    .unwrap_or(g.epsilon().unwrap_or(self.shadow_tolerance));
+                g.intersects_any(r, self.time, bias, distance - bias)
+            })
+    }
+
+    // Whether nothing that casts shadows lies between a point and each of several others. The
+    // shadow rays are intersected with the scene in packets.
+    pub(crate) fn connections(&self, from: Point3<T>, to: &[Point3<T>]) -> Vec<bool> {
+        to.chunks(PACKET_WIDTH)
+            .flat_map(|targets| {
+                self.shadow_rays
+                    .set(self.shadow_rays.get() + targets.len() as u64);
+
+                // Lanes without a target of their own repeat the first one.
+                let lanes: [_; PACKET_WIDTH] =
+                    std::array::from_fn(|lane| targets.get(lane).copied().unwrap_or(targets[0]));
+                let distances = lanes.map(|to| (to - from).magnitude() / T::one());
+                let rays =
+                    lanes.map(|to| ParametricLine::new(from, (to - from).normalized() * T::one()));
+                let packet = RayPacket::new(rays);
+
+                let mut occluded = [false; PACKET_WIDTH];
+                for g in self.scene.geometries.iter().filter(|g| g.casts_shadows()) {
+                    let bias = g
+                        .shadow_bias()
+                        .unwrap_or(g.epsilon().unwrap_or(self.shadow_tolerance));
+                    let hits = g
+                        .intersect_packet(&packet, [self.time; PACKET_WIDTH], bias)
+                        .unwrap_or_else(|| {
+                            rays.map(|r| {
+                                g.intersect_at(r, self.time)
+                                    .into_iter()
+                                    .map(|(t, _, _)| t)
+                                    .filter(|t| *t > bias)
+                                    .fold(T::ValueType::INFINITY, |a, b| if b < a { b } else { a })
+                            })
+                        });
+                    for lane in 0..PACKET_WIDTH {
+                        occluded[lane] |= hits[lane] < distances[lane] - bias;
+                    }
+                }
+
+                occluded[..targets.len()]
+                    .iter()
+                    .map(|occluded| !occluded)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    // The share of the light that passes the volumes along a ray up to a distance, together with
+    // the light the volumes scatter into the ray on the way. The ray marches through each volume
+    // with jittered steps and gathers the direct light at every step, i.e. the light is only
+    // scattered once. The light is not attenuated by the volumes on its way to the step.
+    pub(crate) fn through_volumes(
+        &self,
+        r: ParametricLine<Point3<T>, Vector3<T>>,
+        t_max: T::ValueType,
+        light_pattern: &SamplingPattern<Point2<T::ValueType>>,
+        rnd: &mut WichmannHillPRNG,
+    ) -> (T::ValueType, C)
+    where
+        T::ValueType: Exp<Output = T::ValueType>,
+        WichmannHillPRNG: RandomNumberGenerator<T::ValueType>,
+    {
+        let one = T::ValueType::one();
+        let mut transmittance = one;
+        let mut inscattered = C::default();
+        if self.scene.volumes.is_empty() {
+            return (transmittance, inscattered);
+        }
+
+        let unit = T::one();
+        let origin = Point3::new(r.origin.x / unit, r.origin.y / unit, r.origin.z / unit);
+        let direction = Vector3::new(
+            r.direction.x / unit,
+            r.direction.y / unit,
+            r.direction.z / unit,
+        );
+        let length = direction.magnitude();
+        let d = direction.normalized();
+
+        let mut segments: Vec<_> = self
+            .scene
+            .volumes
+            .iter()
+            .filter_map(|volume| {
+                volume
+                    .segment(origin, direction, t_max)
+                    .map(|segment| (segment, volume))
+            })
+            .collect();
+        segments.sort_by(|((a, _), _), ((b, _), _)| a.partial_cmp(b).unwrap());
+
+        for ((near, far), volume) in segments {
+            let step = volume.step / length;
+            let jitter = rnd.next_random();
+            let mut t = near;
+            while t < far {
+                let dt = step.min(far - t);
+                let sample = t + dt * jitter;
+                let density = volume.density_at(origin + direction * sample);
+                if density > Zero::zero() {
+                    let mut sp = SurfacePoint {
+                        p: r.at(sample),
+                        n: Normal3::new(Zero::zero(), one, Zero::zero()),
+                        uv: Point2::new(Zero::zero(), Zero::zero()),
+                    };
+                    let mut light = C::default();
+                    for l in self.scene.lights.iter().filter(|l| !l.is_indirect()) {
+                        let direction = l.direction_from(sp);
+                        sp.n = direction.as_normal();
+                        if self.light_reaches(
+                            sp,
+                            self.shadow_tolerance,
+                            l.as_ref(),
+                            light_pattern,
+                            rnd,
+                        ) {
+                            light = light + l.color_at(sp) * volume.phase(direction.dot(d));
+                        }
+                    }
+                    let extinction = (-density * dt * length).exp();
+                    inscattered =
+                        inscattered + light * volume.albedo * (transmittance * (one - extinction));
+                    transmittance *= extinction;
+                }
+                t += dt;
+            }
+        }
+
+        (transmittance, inscattered)
+    }
+
+    // Rays that leave the scene see an environment image of the background, the environment of a
+    // light or the background color. The procedural backgrounds are laid out on the image and only
+    // seen by camera rays.
+    pub(crate) fn background(&self, r: ParametricLine<Point3<T>, Vector3<T>>) -> C {
+        let direction = r.direction.normalized();
+        self.scene
+            .background
+            .as_ref()
+            .and_then(|background| background.environment(direction))
+            .or_else(|| {
+                self.scene
+                    .lights
+                    .iter()
+                    .find_map(|light| light.background(direction))
+            })
+            .unwrap_or(self.scene.bg_color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use cg_basics::camera::PinholeCamera;
+    use cg_basics::light::PointLight;
+    use cg_basics::material::{DielectricMaterial, UnshadedMaterial};
+    use cg_basics::scene_graph::RenderableGeometry;
+    use cg_basics::volume::VolumeRegion;
+    use colors::RGB;
+    use image::{Image, SingleColorImage};
+    use math::geometry::{AxisAlignedBox, ImplicitNSphere, ImplicitPlane3};
+    use math::transform::Transform3;
+    use math::Normal3;
+    use sampling::RegularPatternGenerator;
+    use traits::ToRadians;
+    use units::angle::Degrees;
+    use units::length::Meter;
+
+    macro_rules! whitted_ray_tracer_refraction {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A red floor seen from above through a glass ball, which does not bend the light
+                // but lets only half of it through each surface.
+                let render = |max_depth: usize, min_contribution: $type| {
+                    let floor = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let ball = ImplicitNSphere::new(
+                        Point3::new(Meter::new(0.0), Meter::new(1.5), Meter::new(0.0)),
+                        Meter::new(0.5),
+                    );
+
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(RenderableGeometry::new(
+                            floor,
+                            UnshadedMaterial::new(SingleColorImage::new(
+                                RGB::<$type>::new(1.0, 0.0, 0.0),
+                                Vector2::new(1.0, 1.0),
+                            )),
+                            Transform3::<$type>::ident(),
+                        )),
+                        Box::new(RenderableGeometry::new(
+                            ball,
+                            DielectricMaterial::new(RGB::<$type>::new(0.5, 0.5, 0.5), 1.0),
+                            Transform3::<$type>::ident(),
+                        )),
+                    ];
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> = vec![];
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(3.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                            Degrees::<$type>::new(1.0).to_radians(),
+                        )),
+                    );
+
+                    let scene = Scene3::new(RGB::new(0.0, 0.0, 0.0), lights, cameras, geometries);
+
+                    let image = WhittedRayTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1),
+                        0.0001,
+                    )
+                    .with_max_depth(max_depth)
+                    .with_min_contribution(min_contribution)
+                    .render(scene, "main", Vector2::new(1, 1), 0);
+                    image.get(Point2::new(0, 0))
+                };
+
+                // The ray has to enter and leave the ball to reach the floor.
+                assert_eq!(render(0, 0.0), RGB::new(0.0, 0.0, 0.0));
+                assert_eq!(render(1, 0.0), RGB::new(0.0, 0.0, 0.0));
+                assert_eq!(render(2, 0.0), RGB::new(0.25, 0.0, 0.0));
+                assert_eq!(render(5, 0.0), RGB::new(0.25, 0.0, 0.0));
+
+                // Behind both surfaces, the floor contributes too little to be traced.
+                assert_eq!(render(5, 0.3), RGB::new(0.0, 0.0, 0.0));
+            }
+        };
+    }
+
+    whitted_ray_tracer_refraction! { f32, whitted_ray_tracer_refraction_f32 }
+    whitted_ray_tracer_refraction! { f64, whitted_ray_tracer_refraction_f64 }
+
+    macro_rules! whitted_ray_tracer_packets {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A ball and a box on a floor, partly cut off by the image.
+                let render = |packets: bool| {
+                    let floor = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let ball = ImplicitNSphere::new(
+                        Point3::new(Meter::new(-0.5), Meter::new(0.5), Meter::new(0.0)),
+                        Meter::new(0.5),
+                    );
+                    let aab = AxisAlignedBox::new(
+                        Point3::new(Meter::new(0.2), Meter::new(0.0), Meter::new(-0.3)),
+                        Point3::new(Meter::new(0.8), Meter::new(0.6), Meter::new(0.3)),
+                    );
+                    let unshaded = |red: $type, green: $type, blue: $type| {
+                        UnshadedMaterial::new(SingleColorImage::new(
+                            RGB::<$type>::new(red, green, blue),
+                            Vector2::new(1.0, 1.0),
+                        ))
+                    };
+
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> = vec![
+                        Box::new(RenderableGeometry::new(
+                            floor,
+                            unshaded(1.0, 0.0, 0.0),
+                            Transform3::<$type>::ident(),
+                        )),
+                        Box::new(RenderableGeometry::new(
+                            ball,
+                            unshaded(0.0, 1.0, 0.0),
+                            Transform3::<$type>::ident(),
+                        )),
+                        Box::new(RenderableGeometry::new(
+                            aab,
+                            unshaded(0.0, 0.0, 1.0),
+                            Transform3::<$type>::ident().rotate_y(0.3 as $type),
+                        )),
+                    ];
+
+                    let lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> = vec![];
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(3.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-0.3), Meter::new(-1.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(1.0), Meter::new(0.0)),
+                            Degrees::<$type>::new(40.0).to_radians(),
+                        )),
+                    );
+
+                    let scene = Scene3::new(RGB::new(0.0, 0.0, 0.0), lights, cameras, geometries);
+
+                    // Nine samples per pixel fill one packet and start another.
+                    let renderer = WhittedRayTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::regular_pattern(3, 3),
+                        0.0001,
+                    );
+                    let renderer = if packets {
+                        renderer.with_packets()
+                    } else {
+                        renderer
+                    };
+                    renderer.render(scene, "main", Vector2::new(16, 12), 0)
+                };
+
+                let image = render(false);
+                let packed = render(true);
+                for y in 0..12 {
+                    for x in 0..16 {
+                        let p = Point2::new(x, y);
+                        assert_eq!(packed.get(p), image.get(p), "{:?}", p);
+                    }
+                }
+            }
+        };
+    }
+
+    whitted_ray_tracer_packets! { f32, whitted_ray_tracer_packets_f32 }
+    whitted_ray_tracer_packets! { f64, whitted_ray_tracer_packets_f64 }
+
+    macro_rules! whitted_ray_tracer_volumes {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A white floor seen from above through two meters of fog.
+                let render = |light: bool| {
+                    let floor = ImplicitPlane3::new(
+                        Point3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(0.0)),
+                        Normal3::new(0.0, 1.0, 0.0),
+                        Vector3::new(1.0, 0.0, 0.0),
+                    );
+                    let geometries: Vec<Box<dyn Renderable<Meter<$type>, RGB<$type>>>> =
+                        vec![Box::new(RenderableGeometry::new(
+                            floor,
+                            UnshadedMaterial::new(SingleColorImage::new(
+                                RGB::<$type>::new(1.0, 1.0, 1.0),
+                                Vector2::new(1.0, 1.0),
+                            )),
+                            Transform3::<$type>::ident(),
+                        ))];
+
+                    let mut lights: Vec<Box<dyn Light<Meter<$type>, RGB<$type>>>> = vec![];
+                    if light {
+                        lights.push(Box::new(PointLight::new(
+                            RGB::new(1.0, 1.0, 1.0),
+                            Point3::new(Meter::new(0.5), Meter::new(1.0), Meter::new(0.0)),
+                        )));
+                    }
+                    let mut cameras: HashMap<String, Box<dyn RaytracingCamera<Meter<$type>>>> =
+                        HashMap::new();
+                    cameras.insert(
+                        String::from("main"),
+                        Box::new(PinholeCamera::new(
+                            Point3::new(Meter::new(0.0), Meter::new(3.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(-1.0), Meter::new(0.0)),
+                            Vector3::new(Meter::new(0.0), Meter::new(0.0), Meter::new(-1.0)),
+                            Degrees::<$type>::new(1.0).to_radians(),
+                        )),
+                    );
+
+                    let mut scene =
+                        Scene3::new(RGB::new(0.0, 0.0, 0.0), lights, cameras, geometries);
+                    scene.volumes.push(VolumeRegion::new(
+                        Point3::new(-1.0, 0.0, -1.0),
+                        Point3::new(1.0, 2.0, 1.0),
+                        0.5,
+                        RGB::new(1.0, 1.0, 1.0),
+                    ));
+
+                    let image = WhittedRayTracer::<Meter<$type>>::new(
+                        SamplingPatternSet::<Point2<$type>>::regular_pattern(1, 1),
+                        0.0001,
+                    )
+                    .render(scene, "main", Vector2::new(1, 1), 0);
+                    image.get(Point2::new(0, 0))
+                };
+
+                // The fog lets through the share of the light its density allows for.
+                let dimmed = render(false);
+                assert!((dimmed.red - (-1.0 as $type).exp()).abs() < 0.0001);
+
+                // A light inside the fog scatters light towards the camera.
+                let lit = render(true);
+                assert!(lit.red > dimmed.red);
+            }
+        };
+    }
+
+    whitted_ray_tracer_volumes! { f32, whitted_ray_tracer_volumes_f32 }
+    whitted_ray_tracer_volumes! { f64, whitted_ray_tracer_volumes_f64 }
+}
@@ -0,0 +1,189 @@
+use std::ops::{DivAssign, Sub};
+
+use super::{Image, ImageBuffer, WritableImage};
+use colors::Color;
+use math::{Point, Point2};
+use traits::{One, Zero};
+
+// Kahan summation. The rounding error of every addition is kept in a separate compensation term
+// and fed back into the next addition, so the error does not grow with the number of summands.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct CompensatedSum<C> {
+    sum: C,
+    compensation: C,
+}
+
+impl<C: Color + Sub<Output = C>> CompensatedSum<C> {
+    pub fn new() -> CompensatedSum<C> {
+        CompensatedSum {
+            sum: C::default(),
+            compensation: C::default(),
+        }
+    }
+
+    pub fn add(&mut self, value: C) {
+        let y = value - self.compensation;
+        let t = self.sum + y;
+        self.compensation = (t - self.sum) - y;
+        self.sum = t;
+    }
+
+    pub fn add_sum(&mut self, other: CompensatedSum<C>) {
+        self.add(other.sum);
+        self.add(C::default() - other.compensation);
+    }
+
+    // A sum that was taken apart, e.g. to be stored in a file.
+    pub fn from_parts(sum: C, compensation: C) -> CompensatedSum<C> {
+        CompensatedSum { sum, compensation }
+    }
+
+    pub fn value(&self) -> C {
+        self.sum
+    }
+
+    pub fn compensation(&self) -> C {
+        self.compensation
+    }
+}
+
+// Collects the samples of a render that are added over several passes. Each pixel holds a
+// compensated sum and the number of samples; reading a pixel yields the mean.
+#[derive(Debug, Clone)]
+pub struct AccumulationBuffer<C: Color> {
+    sums: Vec<CompensatedSum<C>>,
+    samples: Vec<C::ChannelType>,
+    size: <Point2<usize> as Point>::VectorType,
+}
+
+impl<C: Color + Sub<Output = C>> AccumulationBuffer<C> {
+    pub fn new(size: <Point2<usize> as Point>::VectorType) -> AccumulationBuffer<C> {
+        AccumulationBuffer {
+            sums: vec![CompensatedSum::new(); size.x * size.y],
+            samples: vec![C::ChannelType::zero(); size.x * size.y],
+            size,
+        }
+    }
+
+    pub fn add_sample(&mut self, p: Point2<usize>, color: C) {
+        let index = p.y * self.size.x + p.x;
+        self.sums[index].add(color);
+        self.samples[index] += C::ChannelType::one();
+    }
+
+    pub fn add_buffer(&mut self, other: &AccumulationBuffer<C>) {
+        assert_eq!(self.size, other.size);
+        for (index, sum) in other.sums.iter().enumerate() {
+            self.sums[index].add_sum(*sum);
+            self.samples[index] += other.samples[index];
+        }
+    }
+
+    pub fn samples(&self, p: Point2<usize>) -> C::ChannelType {
+        self.samples[p.y * self.size.x + p.x]
+    }
+
+    pub fn sum(&self, p: Point2<usize>) -> CompensatedSum<C> {
+        self.sums[p.y * self.size.x + p.x]
+    }
+
+    // Replaces a pixel, e.g. to restore a buffer that was stored.
+    pub fn set(&mut self, p: Point2<usize>, sum: CompensatedSum<C>, samples: C::ChannelType) {
+        let index = p.y * self.size.x + p.x;
+        self.sums[index] = sum;
+        self.samples[index] = samples;
+    }
+
+    pub fn to_image_buffer(&self) -> ImageBuffer<C>
+    where
+        C: DivAssign<C::ChannelType>,
+    {
+        let mut image_buffer = ImageBuffer::new(self.size, C::default());
+        for y in 0..self.size.y {
+            for x in 0..self.size.x {
+                let p = Point2::new(x, y);
+                *image_buffer.get_mut(p) = self.get(p);
+            }
+        }
+        image_buffer
+    }
+}
+
+impl<C: Color + Sub<Output = C>> Image for AccumulationBuffer<C>
+where
+    C: DivAssign<C::ChannelType>,
+{
+    type ColorType = C;
+    type PointType = Point2<usize>;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.size
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        let index = p.y * self.size.x + p.x;
+        let mut color = self.sums[index].value();
+        if self.samples[index] != C::ChannelType::zero() {
+            color /= self.samples[index];
+        }
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::RGB;
+    use math::Vector2;
+
+    macro_rules! compensated_sum_is_exact_for_small_summands {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut naive = RGB::<$type>::new(1.0, 1.0, 1.0);
+                let mut compensated = CompensatedSum::new();
+                compensated.add(RGB::<$type>::new(1.0, 1.0, 1.0));
+
+                let small = RGB::<$type>::new(<$type>::EPSILON / 4.0, 0.0, 0.0);
+                for _ in 0..1000 {
+                    naive += small;
+                    compensated.add(small);
+                }
+
+                assert_eq!(naive.red, 1.0);
+                assert_eq!(compensated.value().red, 1.0 + 250.0 * <$type>::EPSILON);
+            }
+        };
+    }
+
+    compensated_sum_is_exact_for_small_summands! { f32, compensated_sum_is_exact_for_small_summands_f32 }
+    compensated_sum_is_exact_for_small_summands! { f64, compensated_sum_is_exact_for_small_summands_f64 }
+
+    macro_rules! accumulation_buffer_averages_samples {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let size = Vector2::new(2, 1);
+                let p = Point2::new(1, 0);
+
+                let mut a = AccumulationBuffer::<RGB<$type>>::new(size);
+                let mut b = AccumulationBuffer::<RGB<$type>>::new(size);
+                for i in 0..100 {
+                    a.add_sample(p, RGB::new(0.1 * i as $type, 1.0, 0.0));
+                    b.add_sample(p, RGB::new(1.0 / (i + 1) as $type, 3.0, 0.0));
+                }
+
+                a.add_buffer(&b);
+
+                assert_eq!(a.samples(p), 200.0);
+                assert_eq!(a.get(p).green, 2.0);
+                assert_eq!(a.get(Point2::new(0, 0)), RGB::new(0.0, 0.0, 0.0));
+                assert_eq!(a.to_image_buffer().get(p), a.get(p));
+            }
+        };
+    }
+
+    accumulation_buffer_averages_samples! { f32, accumulation_buffer_averages_samples_f32 }
+    accumulation_buffer_averages_samples! { f64, accumulation_buffer_averages_samples_f64 }
+}
@@ -0,0 +1,66 @@
+use crate::Image;
+
+use colors::RGB;
+use math::Point;
+use traits::Number;
+
+// Combines the images of the left and the right eye for red-cyan glasses. The red channel comes
+// from the left image, green and blue from the right one.
+pub struct Anaglyph<L, R> {
+    left: L,
+    right: R,
+}
+
+impl<L, R> Anaglyph<L, R> {
+    // Both images need to have the same size.
+    pub fn new(left: L, right: R) -> Anaglyph<L, R> {
+        Anaglyph { left, right }
+    }
+}
+
+impl<L, R, V> Image for Anaglyph<L, R>
+where
+    L: Image<ColorType = RGB<V>>,
+    R: Image<ColorType = RGB<V>, PointType = L::PointType>,
+    V: Number,
+{
+    type ColorType = RGB<V>;
+    type PointType = L::PointType;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.left.size()
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        let left = self.left.get(p);
+        let right = self.right.get(p);
+        RGB::new(left.red, right.green, right.blue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::SingleColorImage;
+    use math::{Point2, Vector2};
+
+    macro_rules! anaglyph_combines_eyes {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let size = Vector2::new(2.0, 2.0);
+                let left = SingleColorImage::new(RGB::<$type>::new(0.1, 0.2, 0.3), size);
+                let right = SingleColorImage::new(RGB::<$type>::new(0.4, 0.5, 0.6), size);
+
+                let anaglyph = Anaglyph::new(left, right);
+
+                assert_eq!(anaglyph.size(), size);
+                assert_eq!(anaglyph.get(Point2::new(0.5, 0.5)), RGB::new(0.1, 0.5, 0.6));
+            }
+        };
+    }
+
+    anaglyph_combines_eyes! { f32, anaglyph_combines_eyes_f32 }
+    anaglyph_combines_eyes! { f64, anaglyph_combines_eyes_f64 }
+}
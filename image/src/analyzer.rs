@@ -1,10 +1,10 @@
 use std::collections::HashMap;
 use std::hash::Hash;
-use std::ops::Index;
+use std::ops::{Index, Range};
 
 use crate::Image;
 
-use colors::Color;
+use colors::{Color, RGB};
 use math::Point2;
 
 pub struct Histogram<C: Color> {
@@ -82,6 +82,90 @@ where
     }
 }
 
+// The luminance of the pixels of an image, with the weights of Rec. 709, in bins of equal width
+// on a logarithmic scale. Pixels outside the range of stops, i.e. powers of two, are counted in the
+// first and last bin. Black pixels have no stop and are only counted separately.
+pub struct LuminanceHistogram {
+    stops: Range<f64>,
+    bins: Vec<usize>,
+    black: usize,
+}
+
+impl LuminanceHistogram {
+    pub fn from_image<T, I>(img: &I, stops: Range<f64>, bins: usize) -> LuminanceHistogram
+    where
+        T: Into<f64>,
+        I: Image<ColorType = RGB<T>, PointType = Point2<usize>>,
+    {
+        assert!(bins > 0 && stops.start < stops.end);
+        let mut histogram = LuminanceHistogram {
+            stops,
+            bins: vec![0; bins],
+            black: 0,
+        };
+
+        let size = img.size();
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let color = img.get(Point2::new(x, y));
+                let luminance = 0.2126 * color.red.into()
+                    + 0.7152 * color.green.into()
+                    + 0.0722 * color.blue.into();
+                if luminance > 0.0 {
+                    let bin = (luminance.log2() - histogram.stops.start) / histogram.stop_width();
+                    histogram.bins[(bin.max(0.0) as usize).min(bins - 1)] += 1;
+                } else {
+                    histogram.black += 1;
+                }
+            }
+        }
+        histogram
+    }
+
+    pub fn bins(&self) -> &[usize] {
+        &self.bins
+    }
+
+    pub fn black(&self) -> usize {
+        self.black
+    }
+
+    // The range of luminance of a bin.
+    pub fn bin_range(&self, bin: usize) -> Range<f64> {
+        let start = self.stops.start + bin as f64 * self.stop_width();
+        start.exp2()..(start + self.stop_width()).exp2()
+    }
+
+    // The luminance that the fraction of the pixels that are not black is darker than,
+    // interpolated within its bin. None if the image is black.
+    pub fn percentile(&self, fraction: f64) -> Option<f64> {
+        let lit: usize = self.bins.iter().sum();
+        if lit == 0 {
+            return None;
+        }
+
+        let rank = fraction.clamp(0.0, 1.0) * lit as f64;
+        let mut below = 0;
+        for (bin, &count) in self.bins.iter().enumerate() {
+            if count > 0 && (below + count) as f64 >= rank {
+                let f = (rank - below as f64) / count as f64;
+                let stop = self.stops.start + (bin as f64 + f) * self.stop_width();
+                return Some(stop.exp2());
+            }
+            below += count;
+        }
+        None
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        self.percentile(0.5)
+    }
+
+    fn stop_width(&self) -> f64 {
+        (self.stops.end - self.stops.start) / self.bins.len() as f64
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,4 +239,41 @@ mod tests {
         assert_eq!(histogram_all_tones.entropy(), 8.0);
         assert_eq!(histogram_white.entropy(), 0.0);
     }
+
+    macro_rules! luminance_histogram {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut image =
+                    ImageBuffer::new(Vector2::new(4, 2), RGB::<$type>::new(0.25, 0.25, 0.25));
+                *image.get_mut(Point2::new(0, 0)) = RGB::new(0.0, 0.0, 0.0);
+                *image.get_mut(Point2::new(1, 0)) = RGB::new(4.0, 4.0, 4.0);
+                *image.get_mut(Point2::new(2, 0)) = RGB::new(1000.0, 1000.0, 1000.0);
+                *image.get_mut(Point2::new(3, 0)) = RGB::new(0.0, 1.0, 0.0);
+
+                let histogram = LuminanceHistogram::from_image(&image, -4.0..4.0, 16);
+
+                assert_eq!(histogram.black(), 1);
+                assert_eq!(histogram.bins()[4], 4);
+                assert_eq!(histogram.bins()[7], 1);
+                assert_eq!(histogram.bins()[12], 1);
+                assert_eq!(histogram.bins()[15], 1);
+                assert_eq!(histogram.bins().iter().sum::<usize>(), 7);
+                assert_eq!(histogram.bin_range(4), 0.25..0.25f64 * 2.0f64.sqrt());
+
+                let median = histogram.median().unwrap();
+                assert!((0.25..0.36).contains(&median));
+                assert!(histogram.percentile(1.0).unwrap() <= 16.0);
+
+                let black = ImageBuffer::new(Vector2::new(2, 2), RGB::<$type>::new(0.0, 0.0, 0.0));
+                assert_eq!(
+                    LuminanceHistogram::from_image(&black, -4.0..4.0, 16).median(),
+                    None
+                );
+            }
+        };
+    }
+
+    luminance_histogram! { f32, luminance_histogram_f32 }
+    luminance_histogram! { f64, luminance_histogram_f64 }
 }
@@ -0,0 +1,187 @@
+use crate::{Image, ImageBuffer, WritableImage};
+
+use colors::RGB;
+use math::{Point2, Vector2};
+
+// Measures how much an image differs from a reference, e.g. a render from a reference render of
+// the same scene. The images must have the same size, and their channels range from zero to the
+// peak, e.g. 1 for linear colors or 255 for 8 bit colors.
+
+// The absolute difference of the channels of every pixel, which shows where the images differ.
+pub fn difference<T, A, B>(a: &A, b: &B) -> ImageBuffer<RGB<f64>>
+where
+    T: Into<f64>,
+    A: Image<ColorType = RGB<T>, PointType = Point2<usize>>,
+    B: Image<ColorType = RGB<T>, PointType = Point2<usize>>,
+{
+    let size = checked_size(a, b);
+    let mut difference = ImageBuffer::new(size, RGB::new(0.0, 0.0, 0.0));
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let p = Point2::new(x, y);
+            let (a, b) = (channels(a.get(p)), channels(b.get(p)));
+            *difference.get_mut(p) = RGB::new(
+                (a[0] - b[0]).abs(),
+                (a[1] - b[1]).abs(),
+                (a[2] - b[2]).abs(),
+            );
+        }
+    }
+    difference
+}
+
+// The peak signal-to-noise ratio in decibels, from the mean squared error of all channels. Equal
+// images have an infinite one.
+pub fn psnr<T, A, B>(a: &A, b: &B, peak: f64) -> f64
+where
+    T: Into<f64>,
+    A: Image<ColorType = RGB<T>, PointType = Point2<usize>>,
+    B: Image<ColorType = RGB<T>, PointType = Point2<usize>>,
+{
+    let size = checked_size(a, b);
+    let mut squared_error = 0.0;
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let p = Point2::new(x, y);
+            let (a, b) = (channels(a.get(p)), channels(b.get(p)));
+            squared_error += (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f64>();
+        }
+    }
+
+    let mean_squared_error = squared_error / (size.x * size.y * 3) as f64;
+    10.0 * (peak * peak / mean_squared_error).log10()
+}
+
+// The mean structural similarity of the luminance of the images, see Wang et al., "Image Quality
+// Assessment: From Error Visibility to Structural Similarity". The means, variances and the
+// covariance are weighted by an 11x11 Gaussian around every pixel, which is cut off at the edges.
+// 1 for equal images, lower the less similar they are.
+pub fn ssim<T, A, B>(a: &A, b: &B, peak: f64) -> f64
+where
+    T: Into<f64>,
+    A: Image<ColorType = RGB<T>, PointType = Point2<usize>>,
+    B: Image<ColorType = RGB<T>, PointType = Point2<usize>>,
+{
+    const RADIUS: isize = 5;
+    const SIGMA: f64 = 1.5;
+
+    let size = checked_size(a, b);
+    let (a, b) = (luminance(a), luminance(b));
+    let c1 = (0.01 * peak).powi(2);
+    let c2 = (0.03 * peak).powi(2);
+    let weights: Vec<f64> = (-RADIUS..=RADIUS)
+        .map(|i| (-((i * i) as f64) / (2.0 * SIGMA * SIGMA)).exp())
+        .collect();
+
+    let mut total = 0.0;
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let (mut weight_sum, mut mean_a, mut mean_b) = (0.0, 0.0, 0.0);
+            let (mut square_a, mut square_b, mut product) = (0.0, 0.0, 0.0);
+            for dy in -RADIUS..=RADIUS {
+                for dx in -RADIUS..=RADIUS {
+                    let (wx, wy) = (x as isize + dx, y as isize + dy);
+                    if wx < 0 || wy < 0 || wx >= size.x as isize || wy >= size.y as isize {
+                        continue;
+                    }
+                    let weight = weights[(dx + RADIUS) as usize] * weights[(dy + RADIUS) as usize];
+                    let index = wy as usize * size.x + wx as usize;
+                    let (a, b) = (a[index], b[index]);
+                    weight_sum += weight;
+                    mean_a += weight * a;
+                    mean_b += weight * b;
+                    square_a += weight * a * a;
+                    square_b += weight * b * b;
+                    product += weight * a * b;
+                }
+            }
+
+            let (mean_a, mean_b) = (mean_a / weight_sum, mean_b / weight_sum);
+            let variance_a = square_a / weight_sum - mean_a * mean_a;
+            let variance_b = square_b / weight_sum - mean_b * mean_b;
+            let covariance = product / weight_sum - mean_a * mean_b;
+            total += ((2.0 * mean_a * mean_b + c1) * (2.0 * covariance + c2))
+                / ((mean_a * mean_a + mean_b * mean_b + c1) * (variance_a + variance_b + c2));
+        }
+    }
+
+    total / (size.x * size.y) as f64
+}
+
+fn checked_size<A: Image<PointType = Point2<usize>>, B: Image<PointType = Point2<usize>>>(
+    a: &A,
+    b: &B,
+) -> Vector2<usize> {
+    assert_eq!(a.size(), b.size(), "The images have different sizes.");
+    a.size()
+}
+
+fn channels<T: Into<f64>>(color: RGB<T>) -> [f64; 3] {
+    [color.red.into(), color.green.into(), color.blue.into()]
+}
+
+// The luminance of every pixel with the weights of Rec. 709, row by row.
+fn luminance<T, I>(image: &I) -> Vec<f64>
+where
+    T: Into<f64>,
+    I: Image<ColorType = RGB<T>, PointType = Point2<usize>>,
+{
+    let size = image.size();
+    (0..size.y)
+        .flat_map(|y| (0..size.x).map(move |x| Point2::new(x, y)))
+        .map(|p| {
+            let [red, green, blue] = channels(image.get(p));
+            0.2126 * red + 0.7152 * green + 0.0722 * blue
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! compare_images {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let size = Vector2::new(16, 12);
+                let mut a = ImageBuffer::new(size, RGB::<$type>::new(0.5, 0.5, 0.5));
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let value = ((x + y) % 4) as $type / 4.0;
+                        *a.get_mut(Point2::new(x, y)) = RGB::new(value, 0.5, 1.0 - value);
+                    }
+                }
+                let mut b = a.clone();
+                *b.get_mut(Point2::new(3, 2)) = RGB::new(1.25, 0.5, 0.25);
+
+                assert_eq!(psnr(&a, &a, 1.0), f64::INFINITY);
+                assert_eq!(ssim(&a, &a, 1.0), 1.0);
+
+                let difference = difference(&a, &b);
+                assert_eq!(difference.get(Point2::new(3, 2)), RGB::new(1.0, 0.0, 0.5));
+                assert_eq!(difference.get(Point2::new(4, 2)), RGB::new(0.0, 0.0, 0.0));
+
+                // One channel is off by 1 and one by 0.5 in 16 * 12 * 3 channels.
+                let expected = 10.0 * (16.0 * 12.0 * 3.0 / 1.25f64).log10();
+                assert!((psnr(&a, &b, 1.0) - expected).abs() < 1e-9);
+
+                let similarity = ssim(&a, &b, 1.0);
+                assert!(similarity > 0.9 && similarity < 1.0);
+                let gray = ImageBuffer::new(size, RGB::<$type>::new(0.5, 0.5, 0.5));
+                assert!(ssim(&a, &gray, 1.0) < similarity);
+            }
+        };
+    }
+
+    compare_images! { f32, compare_images_f32 }
+    compare_images! { f64, compare_images_f64 }
+
+    #[test]
+    #[should_panic]
+    fn compare_images_of_different_sizes() {
+        let a = ImageBuffer::new(Vector2::new(2, 2), RGB::new(0u8, 0, 0));
+        let b = ImageBuffer::new(Vector2::new(2, 3), RGB::new(0u8, 0, 0));
+        psnr(&a, &b, 255.0);
+    }
+}
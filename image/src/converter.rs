@@ -1,17 +1,37 @@
+pub mod alpha;
 pub mod clamp;
 pub mod color;
 pub mod coordinate;
+pub mod crop;
+pub mod exposure;
+pub mod flip;
+pub mod luminance;
+pub mod quantize;
+pub mod resize;
 pub mod splitter;
+pub mod srgb;
+pub mod tone_map;
+pub mod upscale;
 
+pub use alpha::Alpha;
 pub use clamp::Clamp;
 pub use color::Color;
 pub use coordinate::Coordinate;
+pub use crop::Crop;
+pub use exposure::Exposure;
+pub use flip::{Flip, FlipDirection};
+pub use luminance::Luminance;
+pub use quantize::Quantize;
+pub use resize::{Resampling, Resize};
 pub use splitter::Splitter;
+pub use srgb::{SrgbDecode, SrgbEncode};
+pub use tone_map::{ToneMap, ToneMapping};
+pub use upscale::Upscale;
 
 use super::Image;
 
 use colors::Color as ColorTrait;
-use math::Point;
+use math::{Point, Point2, Vector2};
 
 pub trait Converter: Image {
     fn clamp_color(
@@ -25,11 +45,50 @@ pub trait Converter: Image {
     where
         Self: Sized;
     fn convert_coordinate<P: Point>(self) -> Coordinate<Self, P>
+    where
+        Self: Sized;
+    fn crop(self, origin: Point2<usize>, size: Vector2<usize>) -> Crop<Self>
+    where
+        Self: Sized + Image<PointType = Point2<usize>>;
+    fn expose(
+        self,
+        factor: <<Self as Image>::ColorType as ColorTrait>::ChannelType,
+    ) -> Exposure<Self>
+    where
+        Self: Sized;
+    fn flip(self, direction: FlipDirection) -> Flip<Self>
     where
         Self: Sized;
     fn split_channel<'a>(&'a self, channel: usize) -> Splitter<'a, Self>
     where
         Self: Sized;
+    fn encode_srgb(self) -> SrgbEncode<Self>
+    where
+        Self: Sized;
+    fn decode_srgb(self) -> SrgbDecode<Self>
+    where
+        Self: Sized;
+    fn tone_map(
+        self,
+        steps: Vec<ToneMapping<<<Self as Image>::ColorType as ColorTrait>::ChannelType>>,
+    ) -> ToneMap<Self>
+    where
+        Self: Sized;
+    fn upscale(self, factor: usize) -> Upscale<Self>
+    where
+        Self: Sized;
+    fn quantize(self, palette: Vec<<Self as Image>::ColorType>) -> Quantize<Self>
+    where
+        Self: Sized;
+    fn resize(self, size: Vector2<usize>, resampling: Resampling) -> Resize<Self>
+    where
+        Self: Sized + Image<PointType = Point2<usize>>;
+    fn with_alpha<A>(self, alpha: A) -> Alpha<Self, A>
+    where
+        Self: Sized;
+    fn luminance(self) -> Luminance<Self>
+    where
+        Self: Sized;
 }
 
 impl<T> Converter for T
@@ -61,10 +120,93 @@ where
         Coordinate::new(self)
     }
 
+    fn crop(self, origin: Point2<usize>, size: Vector2<usize>) -> Crop<Self>
+    where
+        Self: Sized + Image<PointType = Point2<usize>>,
+    {
+        Crop::new(self, origin, size)
+    }
+
+    fn expose(
+        self,
+        factor: <<Self as Image>::ColorType as ColorTrait>::ChannelType,
+    ) -> Exposure<Self>
+    where
+        Self: Sized,
+    {
+        Exposure::new(self, factor)
+    }
+
+    fn flip(self, direction: FlipDirection) -> Flip<Self>
+    where
+        Self: Sized,
+    {
+        Flip::new(self, direction)
+    }
+
     fn split_channel<'a>(&'a self, channel: usize) -> Splitter<'a, Self>
     where
         Self: Sized,
     {
         Splitter::new(&self, channel)
     }
+
+    fn encode_srgb(self) -> SrgbEncode<Self>
+    where
+        Self: Sized,
+    {
+        SrgbEncode::new(self)
+    }
+
+    fn decode_srgb(self) -> SrgbDecode<Self>
+    where
+        Self: Sized,
+    {
+        SrgbDecode::new(self)
+    }
+
+    fn tone_map(
+        self,
+        steps: Vec<ToneMapping<<<Self as Image>::ColorType as ColorTrait>::ChannelType>>,
+    ) -> ToneMap<Self>
+    where
+        Self: Sized,
+    {
+        ToneMap::new(self, steps)
+    }
+
+    fn upscale(self, factor: usize) -> Upscale<Self>
+    where
+        Self: Sized,
+    {
+        Upscale::new(self, factor)
+    }
+
+    fn quantize(self, palette: Vec<<Self as Image>::ColorType>) -> Quantize<Self>
+    where
+        Self: Sized,
+    {
+        Quantize::new(self, palette)
+    }
+
+    fn resize(self, size: Vector2<usize>, resampling: Resampling) -> Resize<Self>
+    where
+        Self: Sized + Image<PointType = Point2<usize>>,
+    {
+        Resize::new(self, size, resampling)
+    }
+
+    fn with_alpha<A>(self, alpha: A) -> Alpha<Self, A>
+    where
+        Self: Sized,
+    {
+        Alpha::new(self, alpha)
+    }
+
+    fn luminance(self) -> Luminance<Self>
+    where
+        Self: Sized,
+    {
+        Luminance::new(self)
+    }
 }
@@ -0,0 +1,72 @@
+use crate::Image;
+
+use colors::{Gray, RGB, RGBA};
+use math::Point;
+use traits::Number;
+
+// Adds an alpha channel to an image, taken from a gray image of the same size, e.g. the coverage
+// of a render. The colors are not premultiplied by the alpha.
+pub struct Alpha<T: Image, A> {
+    source: T,
+    alpha: A,
+}
+
+impl<T: Image, A> Alpha<T, A> {
+    // The alpha needs to have the size of the image.
+    pub fn new(source: T, alpha: A) -> Alpha<T, A> {
+        Alpha { source, alpha }
+    }
+}
+
+impl<T, A, V> Image for Alpha<T, A>
+where
+    T: Image<ColorType = RGB<V>>,
+    A: Image<ColorType = Gray<V>, PointType = T::PointType>,
+    V: Number,
+{
+    type ColorType = RGBA<V>;
+    type PointType = T::PointType;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.source.size()
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        let color = self.source.get(p);
+        RGBA::new(color.red, color.green, color.blue, self.alpha.get(p).value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ImageBuffer, WritableImage};
+    use math::{Point2, Vector2};
+
+    macro_rules! alpha_from_gray_image {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let image =
+                    ImageBuffer::new(Vector2::new(2, 2), RGB::<$type>::new(0.25, 0.5, 0.75));
+                let mut coverage = ImageBuffer::new(Vector2::new(2, 2), Gray::<$type>::new(1.0));
+                *coverage.get_mut(Point2::new(1, 0)) = Gray::new(0.0);
+                let alpha = Alpha::new(image, coverage);
+
+                assert_eq!(alpha.size(), Vector2::new(2, 2));
+                assert_eq!(
+                    alpha.get(Point2::new(0, 0)),
+                    RGBA::new(0.25, 0.5, 0.75, 1.0)
+                );
+                assert_eq!(
+                    alpha.get(Point2::new(1, 0)),
+                    RGBA::new(0.25, 0.5, 0.75, 0.0)
+                );
+            }
+        };
+    }
+
+    alpha_from_gray_image! { f32, alpha_from_gray_image_f32 }
+    alpha_from_gray_image! { f64, alpha_from_gray_image_f64 }
+}
@@ -0,0 +1,66 @@
+use crate::Image;
+
+use math::{Point, Point2, Vector2};
+
+// The part of an image with the given size whose top left pixel is at the origin.
+pub struct Crop<T: Image> {
+    source: T,
+    origin: Point2<usize>,
+    size: Vector2<usize>,
+}
+
+impl<T: Image<PointType = Point2<usize>>> Crop<T> {
+    pub fn new(source: T, origin: Point2<usize>, size: Vector2<usize>) -> Crop<T> {
+        let source_size = source.size();
+        assert!(
+            origin.x + size.x <= source_size.x && origin.y + size.y <= source_size.y,
+            "The cropped area exceeds the image."
+        );
+        Crop {
+            source,
+            origin,
+            size,
+        }
+    }
+}
+
+impl<T: Image<PointType = Point2<usize>>> Image for Crop<T> {
+    type ColorType = <T as Image>::ColorType;
+    type PointType = Point2<usize>;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.size
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        self.source
+            .get(Point2::new(self.origin.x + p.x, self.origin.y + p.y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::Gray;
+
+    use crate::{ImageBuffer, WritableImage};
+
+    #[test]
+    fn crop_moves_origin() {
+        let mut image = ImageBuffer::new(Vector2::new(4, 3), Gray::new(0.0));
+        *image.get_mut(Point2::new(2, 1)) = Gray::new(1.0);
+        let cropped = Crop::new(image, Point2::new(1, 1), Vector2::new(3, 2));
+
+        assert_eq!(cropped.size(), Vector2::new(3, 2));
+        assert_eq!(cropped.get(Point2::new(1, 0)), Gray::new(1.0));
+        assert_eq!(cropped.get(Point2::new(2, 1)), Gray::new(0.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn crop_outside_of_image() {
+        let image = ImageBuffer::new(Vector2::new(4, 3), Gray::new(0.0));
+        Crop::new(image, Point2::new(2, 0), Vector2::new(3, 1));
+    }
+}
@@ -0,0 +1,47 @@
+use crate::Image;
+
+use colors::Color;
+use math::Point;
+
+pub struct Exposure<T: Image> {
+    source: T,
+    factor: <<T as Image>::ColorType as Color>::ChannelType,
+}
+
+impl<T: Image> Exposure<T> {
+    pub fn new(source: T, factor: <<T as Image>::ColorType as Color>::ChannelType) -> Exposure<T> {
+        Exposure { source, factor }
+    }
+}
+
+impl<T: Image> Image for Exposure<T> {
+    type ColorType = <T as Image>::ColorType;
+    type PointType = <T as Image>::PointType;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.source.size()
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        self.source.get(p) * self.factor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::RGB;
+    use math::{Point2, Vector2};
+
+    use crate::SingleColorImage;
+
+    #[test]
+    fn exposure_scales_color() {
+        let image = SingleColorImage::new(RGB::new(0.5, 1.0, 2.0), Vector2::new(1.0, 1.0));
+        let exposed = Exposure::new(image, 0.5);
+
+        assert_eq!(exposed.get(Point2::new(0.0, 0.0)), RGB::new(0.25, 0.5, 1.0));
+        assert_eq!(exposed.size(), Vector2::new(1.0, 1.0));
+    }
+}
@@ -0,0 +1,63 @@
+use crate::Image;
+
+use math::{Point, Point2};
+
+// Horizontal flips mirror an image left to right, vertical ones top to bottom.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FlipDirection {
+    Horizontal,
+    Vertical,
+}
+
+pub struct Flip<T: Image> {
+    source: T,
+    direction: FlipDirection,
+}
+
+impl<T: Image> Flip<T> {
+    pub fn new(source: T, direction: FlipDirection) -> Flip<T> {
+        Flip { source, direction }
+    }
+}
+
+impl<T: Image<PointType = Point2<usize>>> Image for Flip<T> {
+    type ColorType = <T as Image>::ColorType;
+    type PointType = Point2<usize>;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.source.size()
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        let size = self.source.size();
+        let p = match self.direction {
+            FlipDirection::Horizontal => Point2::new(size.x - 1 - p.x, p.y),
+            FlipDirection::Vertical => Point2::new(p.x, size.y - 1 - p.y),
+        };
+        self.source.get(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::Gray;
+    use math::Vector2;
+
+    use crate::{ImageBuffer, WritableImage};
+
+    #[test]
+    fn flip_mirrors_pixels() {
+        let mut image = ImageBuffer::new(Vector2::new(3, 2), Gray::new(0.0));
+        *image.get_mut(Point2::new(0, 0)) = Gray::new(1.0);
+
+        let horizontal = Flip::new(image.clone(), FlipDirection::Horizontal);
+        assert_eq!(horizontal.get(Point2::new(2, 0)), Gray::new(1.0));
+        assert_eq!(horizontal.get(Point2::new(0, 0)), Gray::new(0.0));
+
+        let vertical = Flip::new(image, FlipDirection::Vertical);
+        assert_eq!(vertical.get(Point2::new(0, 1)), Gray::new(1.0));
+        assert_eq!(vertical.get(Point2::new(0, 0)), Gray::new(0.0));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::Image;
+
+use colors::{Gray, RGB};
+use math::Point;
+use traits::Number;
+
+// Collapses the colors of an image to their luminance with the weights of Rec. 709, e.g. to look
+// at an ambient occlusion pass or to analyze the brightness of a render. The colors need to be
+// linear.
+pub struct Luminance<T: Image> {
+    source: T,
+}
+
+impl<T: Image> Luminance<T> {
+    pub fn new(source: T) -> Luminance<T> {
+        Luminance { source }
+    }
+}
+
+impl<T, V> Image for Luminance<T>
+where
+    T: Image<ColorType = RGB<V>>,
+    V: Number,
+    f32: Into<V>,
+{
+    type ColorType = Gray<V>;
+    type PointType = <T as Image>::PointType;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.source.size()
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        let color = self.source.get(p);
+        Gray::new(
+            color.red * 0.2126.into() + color.green * 0.7152.into() + color.blue * 0.0722.into(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ImageBuffer, WritableImage};
+    use math::{Point2, Vector2};
+
+    macro_rules! luminance_of_colors {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut image =
+                    ImageBuffer::new(Vector2::new(2, 2), RGB::<$type>::new(1.0, 1.0, 1.0));
+                *image.get_mut(Point2::new(1, 0)) = RGB::new(1.0, 0.0, 0.0);
+                *image.get_mut(Point2::new(0, 1)) = RGB::new(0.0, 1.0, 0.0);
+                *image.get_mut(Point2::new(1, 1)) = RGB::new(0.0, 0.0, 2.0);
+                let luminance = Luminance::new(image);
+
+                assert_eq!(luminance.size(), Vector2::new(2, 2));
+                assert!((luminance.get(Point2::new(0, 0)).value - 1.0).abs() < 0.0001);
+                assert!((luminance.get(Point2::new(1, 0)).value - 0.2126).abs() < 0.0001);
+                assert!((luminance.get(Point2::new(0, 1)).value - 0.7152).abs() < 0.0001);
+                assert!((luminance.get(Point2::new(1, 1)).value - 0.1444).abs() < 0.0001);
+            }
+        };
+    }
+
+    luminance_of_colors! { f32, luminance_of_colors_f32 }
+    luminance_of_colors! { f64, luminance_of_colors_f64 }
+}
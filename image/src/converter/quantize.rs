@@ -0,0 +1,88 @@
+use crate::Image;
+
+use colors::RGB;
+use math::Point;
+use traits::Number;
+
+// Replaces every color by the closest color of a palette, e.g. to mimic the limited colors of old
+// hardware. The distance is the euclidean distance of the channels.
+pub struct Quantize<T: Image> {
+    source: T,
+    palette: Vec<<T as Image>::ColorType>,
+}
+
+impl<T: Image> Quantize<T> {
+    pub fn new(source: T, palette: Vec<<T as Image>::ColorType>) -> Quantize<T> {
+        assert!(!palette.is_empty());
+        Quantize { source, palette }
+    }
+}
+
+impl<T, V> Image for Quantize<T>
+where
+    T: Image<ColorType = RGB<V>>,
+    V: Number + PartialOrd,
+{
+    type ColorType = RGB<V>;
+    type PointType = <T as Image>::PointType;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.source.size()
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        let color = self.source.get(p);
+        let distance = |entry: &RGB<V>| {
+            let (red, green, blue) = (
+                entry.red - color.red,
+                entry.green - color.green,
+                entry.blue - color.blue,
+            );
+            red * red + green * green + blue * blue
+        };
+
+        let mut closest = self.palette[0];
+        let mut closest_distance = distance(&closest);
+        for entry in &self.palette[1..] {
+            let entry_distance = distance(entry);
+            if entry_distance < closest_distance {
+                closest = *entry;
+                closest_distance = entry_distance;
+            }
+        }
+
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use math::{Point2, Vector2};
+
+    use crate::SingleColorImage;
+
+    #[test]
+    fn quantize_picks_closest_color() {
+        let palette = vec![
+            RGB::new(0.0, 0.0, 0.0),
+            RGB::new(1.0, 0.0, 0.0),
+            RGB::new(1.0, 1.0, 1.0),
+        ];
+
+        let image = SingleColorImage::new(RGB::new(0.8, 0.3, 0.2), Vector2::new(1.0, 1.0));
+        let quantized = Quantize::new(image, palette.clone());
+        assert_eq!(
+            quantized.get(Point2::new(0.0, 0.0)),
+            RGB::new(1.0, 0.0, 0.0)
+        );
+
+        let image = SingleColorImage::new(RGB::new(0.7, 0.6, 0.8), Vector2::new(1.0, 1.0));
+        let quantized = Quantize::new(image, palette);
+        assert_eq!(
+            quantized.get(Point2::new(0.0, 0.0)),
+            RGB::new(1.0, 1.0, 1.0)
+        );
+    }
+}
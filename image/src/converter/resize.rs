@@ -0,0 +1,133 @@
+use crate::Image;
+
+use colors::Color;
+use math::{Point, Point2, Vector2};
+
+// How the pixels of a resized image are taken from the source. Box averages the source pixels
+// whose centers lie inside of a pixel, which suits shrinking, e.g. for thumbnails, and repeats
+// pixels when enlarging. Bilinear blends the four source pixels nearest to the center of a pixel.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Resampling {
+    Box,
+    Bilinear,
+}
+
+pub struct Resize<T: Image> {
+    source: T,
+    size: Vector2<usize>,
+    resampling: Resampling,
+}
+
+impl<T: Image<PointType = Point2<usize>>> Resize<T> {
+    pub fn new(source: T, size: Vector2<usize>, resampling: Resampling) -> Resize<T> {
+        let source_size = source.size();
+        assert!(
+            source_size.x > 0 && source_size.y > 0,
+            "The image is empty."
+        );
+        Resize {
+            source,
+            size,
+            resampling,
+        }
+    }
+
+    // The first and the last source pixel whose centers lie inside of the pixel along an axis,
+    // at least the one the pixel lies in.
+    fn covered(x: usize, size: usize, source_size: usize) -> (usize, usize) {
+        let first = x * source_size / size;
+        let end = ((x + 1) * source_size).div_ceil(size);
+        (first, end.max(first + 1))
+    }
+
+    // The two source pixels around the center of the pixel along an axis and the weight of the
+    // second one.
+    fn neighbors(x: usize, size: usize, source_size: usize) -> (usize, usize, f32) {
+        let center = (x as f32 + 0.5) * source_size as f32 / size as f32 - 0.5;
+        let center = center.clamp(0.0, (source_size - 1) as f32);
+        let first = center.floor() as usize;
+        let second = (first + 1).min(source_size - 1);
+        (first, second, center - first as f32)
+    }
+}
+
+impl<T: Image<PointType = Point2<usize>>> Image for Resize<T>
+where
+    f32: Into<<<T as Image>::ColorType as Color>::ChannelType>,
+{
+    type ColorType = <T as Image>::ColorType;
+    type PointType = Point2<usize>;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.size
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        let source_size = self.source.size();
+        match self.resampling {
+            Resampling::Box => {
+                let (x0, x1) = Self::covered(p.x, self.size.x, source_size.x);
+                let (y0, y1) = Self::covered(p.y, self.size.y, source_size.y);
+                let weight = (1.0 / ((x1 - x0) * (y1 - y0)) as f32).into();
+                (y0..y1)
+                    .flat_map(|y| (x0..x1).map(move |x| Point2::new(x, y)))
+                    .map(|p| self.source.get(p) * weight)
+                    .sum()
+            }
+            Resampling::Bilinear => {
+                let (x0, x1, tx) = Self::neighbors(p.x, self.size.x, source_size.x);
+                let (y0, y1, ty) = Self::neighbors(p.y, self.size.y, source_size.y);
+                let blend = |y: usize| {
+                    self.source.get(Point2::new(x0, y)) * (1.0 - tx).into()
+                        + self.source.get(Point2::new(x1, y)) * tx.into()
+                };
+                blend(y0) * (1.0 - ty).into() + blend(y1) * ty.into()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::Gray;
+
+    use crate::{ImageBuffer, WritableImage};
+
+    macro_rules! resize_image {
+        ($type: ty, $box_name: ident, $bilinear_name: ident) => {
+            #[test]
+            fn $box_name() {
+                let mut image = ImageBuffer::new(Vector2::new(4, 2), Gray::<$type>::new(0.0));
+                *image.get_mut(Point2::new(0, 0)) = Gray::new(1.0);
+                *image.get_mut(Point2::new(3, 1)) = Gray::new(2.0);
+
+                let shrunk = Resize::new(image.clone(), Vector2::new(2, 1), Resampling::Box);
+                assert_eq!(shrunk.size(), Vector2::new(2, 1));
+                assert_eq!(shrunk.get(Point2::new(0, 0)), Gray::new(0.25));
+                assert_eq!(shrunk.get(Point2::new(1, 0)), Gray::new(0.5));
+
+                let enlarged = Resize::new(image, Vector2::new(8, 4), Resampling::Box);
+                assert_eq!(enlarged.get(Point2::new(1, 1)), Gray::new(1.0));
+                assert_eq!(enlarged.get(Point2::new(2, 1)), Gray::new(0.0));
+                assert_eq!(enlarged.get(Point2::new(7, 3)), Gray::new(2.0));
+            }
+
+            #[test]
+            fn $bilinear_name() {
+                let mut image = ImageBuffer::new(Vector2::new(2, 1), Gray::<$type>::new(0.0));
+                *image.get_mut(Point2::new(1, 0)) = Gray::new(1.0);
+
+                let enlarged = Resize::new(image, Vector2::new(4, 1), Resampling::Bilinear);
+                assert_eq!(enlarged.get(Point2::new(0, 0)), Gray::new(0.0));
+                assert_eq!(enlarged.get(Point2::new(1, 0)), Gray::new(0.25));
+                assert_eq!(enlarged.get(Point2::new(2, 0)), Gray::new(0.75));
+                assert_eq!(enlarged.get(Point2::new(3, 0)), Gray::new(1.0));
+            }
+        };
+    }
+
+    resize_image! { f32, resize_box_f32, resize_bilinear_f32 }
+    resize_image! { f64, resize_box_f64, resize_bilinear_f64 }
+}
@@ -0,0 +1,134 @@
+use crate::Image;
+
+use colors::Color;
+use math::Point;
+use traits::{FloatingPoint, Zero};
+
+// The transfer function of sRGB, see IEC 61966-2-1. Image files store colors encoded, so the
+// steps of 8 or 16 bits are spread evenly for the eye, while the renderer computes with linear
+// light. Every channel is converted, so alpha must be split off first.
+pub fn encode<V>(value: V) -> V
+where
+    V: FloatingPoint,
+    u16: Into<V>,
+{
+    let value = value.max(Zero::zero());
+    if value <= ratio::<V>(31308, 10000) / 1000u16.into() {
+        value * ratio(1292, 100)
+    } else {
+        ratio::<V>(1055, 1000) * value.powf(ratio(10, 24)) - ratio(55, 1000)
+    }
+}
+
+// The inverse of encode, e.g. to linearize the colors of a texture.
+pub fn decode<V>(value: V) -> V
+where
+    V: FloatingPoint,
+    u16: Into<V>,
+{
+    let value = value.max(Zero::zero());
+    if value <= ratio::<V>(4045, 10000) / 10u16.into() {
+        value / ratio(1292, 100)
+    } else {
+        ((value + ratio(55, 1000)) / ratio(1055, 1000)).powf(ratio(24, 10))
+    }
+}
+
+fn ratio<V>(numerator: u16, denominator: u16) -> V
+where
+    V: FloatingPoint,
+    u16: Into<V>,
+{
+    numerator.into() / denominator.into()
+}
+
+pub struct SrgbEncode<T: Image> {
+    source: T,
+}
+
+impl<T: Image> SrgbEncode<T> {
+    pub fn new(source: T) -> SrgbEncode<T> {
+        SrgbEncode { source }
+    }
+}
+
+impl<T: Image> Image for SrgbEncode<T>
+where
+    <<T as Image>::ColorType as Color>::ChannelType: FloatingPoint,
+    u16: Into<<<T as Image>::ColorType as Color>::ChannelType>,
+{
+    type ColorType = <T as Image>::ColorType;
+    type PointType = <T as Image>::PointType;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.source.size()
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        self.source.get(p).map(encode)
+    }
+}
+
+pub struct SrgbDecode<T: Image> {
+    source: T,
+}
+
+impl<T: Image> SrgbDecode<T> {
+    pub fn new(source: T) -> SrgbDecode<T> {
+        SrgbDecode { source }
+    }
+}
+
+impl<T: Image> Image for SrgbDecode<T>
+where
+    <<T as Image>::ColorType as Color>::ChannelType: FloatingPoint,
+    u16: Into<<<T as Image>::ColorType as Color>::ChannelType>,
+{
+    type ColorType = <T as Image>::ColorType;
+    type PointType = <T as Image>::PointType;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.source.size()
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        self.source.get(p).map(decode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::RGB;
+    use math::{Point2, Vector2};
+
+    use crate::SingleColorImage;
+
+    macro_rules! srgb_round_trip {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // Middle gray is encoded to about 46 percent.
+                let image =
+                    SingleColorImage::new(RGB::<$type>::new(0.0, 0.18, 1.0), Vector2::new(1, 1));
+                let encoded = SrgbEncode::new(image).get(Point2::new(0, 0));
+                assert_eq!(encoded.red, 0.0);
+                assert!((encoded.green - 0.4614).abs() < 0.0001, "{:?}", encoded);
+                assert!((encoded.blue - 1.0).abs() < 0.0001, "{:?}", encoded);
+
+                let decoded = SrgbDecode::new(SingleColorImage::new(encoded, Vector2::new(1, 1)))
+                    .get(Point2::new(0, 0));
+                assert!((decoded.green - 0.18).abs() < 0.0001, "{:?}", decoded);
+                assert!((decoded.blue - 1.0).abs() < 0.0001, "{:?}", decoded);
+
+                // The linear segment near black.
+                assert!((encode::<$type>(0.001) - 0.01292).abs() < 0.00001);
+                assert!((decode::<$type>(0.01292) - 0.001).abs() < 0.00001);
+            }
+        };
+    }
+
+    srgb_round_trip! { f32, srgb_round_trip_f32 }
+    srgb_round_trip! { f64, srgb_round_trip_f64 }
+}
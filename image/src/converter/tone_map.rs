@@ -0,0 +1,121 @@
+use crate::Image;
+
+use colors::Color;
+use math::Point;
+use traits::{FloatingPoint, Zero};
+
+// Maps high dynamic range colors into the range of an image file. Clamping alone turns every
+// highlight brighter than white into a flat white area, the curves compress them instead. The
+// steps are applied one after the other, e.g. an exposure, a curve and a gamma.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ToneMapping<V> {
+    // Scales the colors, e.g. by 2 for one stop brighter.
+    Exposure(V),
+    // c / (1 + c), see Reinhard et al., "Photographic Tone Reproduction for Digital Images".
+    Reinhard,
+    // Narkowicz's fit of the filmic curve of the Academy Color Encoding System, with a toe in the
+    // shadows and a shoulder in the highlights.
+    Aces,
+    // Raises the colors to the power of 1 / gamma.
+    Gamma(V),
+}
+
+impl<V> ToneMapping<V>
+where
+    V: FloatingPoint,
+    u16: Into<V>,
+{
+    pub fn apply(&self, value: V) -> V {
+        let hundredths = |value: u16| Into::<V>::into(value) / 100u16.into();
+        match *self {
+            ToneMapping::Exposure(factor) => value * factor,
+            ToneMapping::Reinhard => {
+                let value = value.max(Zero::zero());
+                value / (value + V::one())
+            }
+            ToneMapping::Aces => {
+                let value = value.max(Zero::zero());
+                (value * (hundredths(251) * value + hundredths(3)))
+                    / (value * (hundredths(243) * value + hundredths(59)) + hundredths(14))
+            }
+            // Negative values, e.g. from the ringing of a filter, have no power.
+            ToneMapping::Gamma(gamma) => value.max(Zero::zero()).powf(V::one() / gamma),
+        }
+    }
+}
+
+pub struct ToneMap<T: Image> {
+    source: T,
+    steps: Vec<ToneMapping<<<T as Image>::ColorType as Color>::ChannelType>>,
+}
+
+impl<T: Image> ToneMap<T> {
+    pub fn new(
+        source: T,
+        steps: Vec<ToneMapping<<<T as Image>::ColorType as Color>::ChannelType>>,
+    ) -> ToneMap<T> {
+        ToneMap { source, steps }
+    }
+}
+
+impl<T: Image> Image for ToneMap<T>
+where
+    <<T as Image>::ColorType as Color>::ChannelType: FloatingPoint,
+    u16: Into<<<T as Image>::ColorType as Color>::ChannelType>,
+{
+    type ColorType = <T as Image>::ColorType;
+    type PointType = <T as Image>::PointType;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.source.size()
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        self.steps.iter().fold(self.source.get(p), |color, step| {
+            color.map(|value| step.apply(value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::RGB;
+    use math::{Point2, Vector2};
+
+    use crate::SingleColorImage;
+
+    macro_rules! tone_map_compresses_highlights {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let map = |steps: Vec<ToneMapping<$type>>| {
+                    let image =
+                        SingleColorImage::new(RGB::<$type>::new(0.0, 1.0, 3.0), Vector2::new(1, 1));
+                    ToneMap::new(image, steps).get(Point2::new(0, 0))
+                };
+
+                assert_eq!(map(vec![]), RGB::new(0.0, 1.0, 3.0));
+                assert_eq!(map(vec![ToneMapping::Reinhard]), RGB::new(0.0, 0.5, 0.75));
+
+                // The highlights stay below white and keep their order.
+                let aces = map(vec![ToneMapping::Aces]);
+                assert_eq!(aces.red, 0.0);
+                assert!((aces.green - 0.8038).abs() < 0.0001, "{:?}", aces);
+                assert!(aces.green < aces.blue && aces.blue < 1.0, "{:?}", aces);
+
+                // The steps are applied in order.
+                let exposed = map(vec![ToneMapping::Exposure(4.0), ToneMapping::Gamma(2.0)]);
+                assert!((exposed.green - 2.0).abs() < 0.0001, "{:?}", exposed);
+                let exposed = map(vec![ToneMapping::Gamma(2.0), ToneMapping::Exposure(4.0)]);
+                assert!((exposed.green - 4.0).abs() < 0.0001, "{:?}", exposed);
+
+                assert_eq!(ToneMapping::<$type>::Gamma(2.2).apply(-1.0), 0.0);
+            }
+        };
+    }
+
+    tone_map_compresses_highlights! { f32, tone_map_compresses_highlights_f32 }
+    tone_map_compresses_highlights! { f64, tone_map_compresses_highlights_f64 }
+}
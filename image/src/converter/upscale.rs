@@ -0,0 +1,53 @@
+use crate::Image;
+
+use math::{Point, Point2, Vector2};
+
+// Enlarges an image by an integer factor. Every pixel of the source becomes a square block of
+// pixels, so hard pixel edges stay hard, e.g. for pixel art.
+pub struct Upscale<T: Image> {
+    source: T,
+    factor: usize,
+}
+
+impl<T: Image> Upscale<T> {
+    pub fn new(source: T, factor: usize) -> Upscale<T> {
+        assert!(factor > 0);
+        Upscale { source, factor }
+    }
+}
+
+impl<T: Image<PointType = Point2<usize>>> Image for Upscale<T> {
+    type ColorType = <T as Image>::ColorType;
+    type PointType = Point2<usize>;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        let size = self.source.size();
+        Vector2::new(size.x * self.factor, size.y * self.factor)
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        self.source
+            .get(Point2::new(p.x / self.factor, p.y / self.factor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::Gray;
+
+    use crate::{ImageBuffer, WritableImage};
+
+    #[test]
+    fn upscale_repeats_pixels() {
+        let mut image = ImageBuffer::new(Vector2::new(2, 1), Gray::new(0.0));
+        *image.get_mut(Point2::new(1, 0)) = Gray::new(1.0);
+        let upscaled = Upscale::new(image, 3);
+
+        assert_eq!(upscaled.size(), Vector2::new(6, 3));
+        assert_eq!(upscaled.get(Point2::new(2, 2)), Gray::new(0.0));
+        assert_eq!(upscaled.get(Point2::new(3, 0)), Gray::new(1.0));
+        assert_eq!(upscaled.get(Point2::new(5, 2)), Gray::new(1.0));
+    }
+}
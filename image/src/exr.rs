@@ -0,0 +1,375 @@
+use std::io::{self, Write};
+use std::marker::PhantomData;
+
+use crate::{Image, ScanlineEncoder};
+
+use colors::{Color, Gray, RGB, RGBA};
+use math::{Point2, Vector2};
+use traits::F16;
+
+const MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+// The version of the format, with the flag for names of up to 255 instead of 31 bytes.
+const VERSION: u32 = 2;
+const LONG_NAMES: u32 = 0x400;
+
+// The colors that are stored in EXR files, with the names of their channels in the file.
+pub trait ExrColor {
+    const CHANNELS: &'static [&'static str];
+
+    fn channels(&self) -> impl Iterator<Item = f32>;
+}
+
+macro_rules! implement_exr_color {
+    ($color: ident, $type: ty, [$($channel: ident $name: literal)+]) => {
+        impl ExrColor for $color<$type> {
+            const CHANNELS: &'static [&'static str] = &[$($name),+];
+
+            fn channels(&self) -> impl Iterator<Item = f32> {
+                [$(self.$channel as f32),+].into_iter()
+            }
+        }
+    };
+}
+
+implement_exr_color! { Gray, f32, [value "Y"] }
+implement_exr_color! { Gray, f64, [value "Y"] }
+implement_exr_color! { RGB, f32, [red "R" green "G" blue "B"] }
+implement_exr_color! { RGB, f64, [red "R" green "G" blue "B"] }
+implement_exr_color! { RGBA, f32, [red "R" green "G" blue "B" alpha "A"] }
+implement_exr_color! { RGBA, f64, [red "R" green "G" blue "B" alpha "A"] }
+
+macro_rules! implement_half_float_exr_color {
+    ($color: ident, [$($channel: ident $name: literal)+]) => {
+        impl ExrColor for $color<F16> {
+            const CHANNELS: &'static [&'static str] = &[$($name),+];
+
+            fn channels(&self) -> impl Iterator<Item = f32> {
+                [$(self.$channel.to_f32()),+].into_iter()
+            }
+        }
+    };
+}
+
+implement_half_float_exr_color! { Gray, [value "Y"] }
+implement_half_float_exr_color! { RGB, [red "R" green "G" blue "B"] }
+implement_half_float_exr_color! { RGBA, [red "R" green "G" blue "B" alpha "A"] }
+
+// How the channels are stored, as 16 bit or as 32 bit floats.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PixelType {
+    Half,
+    Float,
+}
+
+impl PixelType {
+    fn id(self) -> u32 {
+        match self {
+            PixelType::Half => 1,
+            PixelType::Float => 2,
+        }
+    }
+
+    fn write(self, value: f32, data: &mut Vec<u8>) {
+        match self {
+            PixelType::Half => data.extend_from_slice(&F16::from_f32(value).to_le_bytes()),
+            PixelType::Float => data.extend_from_slice(&value.to_le_bytes()),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            PixelType::Half => 2,
+            PixelType::Float => 4,
+        }
+    }
+}
+
+// An EXR file of layers with the same size, e.g. the image and its auxiliary outputs. The channels
+// of a layer are named after the layer, like normal.R, except those of the layer without a name.
+// The file is written in scanlines without compression. The channels are kept as they are stored
+// in the file, so layers of 16 bit floats take half of the memory.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExrImage {
+    size: Vector2<usize>,
+    pixel_type: PixelType,
+    channels: Vec<(String, Vec<u8>)>,
+}
+
+impl ExrImage {
+    pub fn new(size: Vector2<usize>, pixel_type: PixelType) -> ExrImage {
+        ExrImage {
+            size,
+            pixel_type,
+            channels: vec![],
+        }
+    }
+
+    // Adds the image as a layer, which replaces the channels of a layer with the same name.
+    pub fn with_layer<T>(mut self, name: &str, image: &T) -> ExrImage
+    where
+        T: Image<PointType = Point2<usize>>,
+        T::ColorType: ExrColor,
+    {
+        assert_eq!(image.size(), self.size, "The layer has another size.");
+
+        let names = <T::ColorType as ExrColor>::CHANNELS.iter().map(|channel| {
+            if name.is_empty() {
+                channel.to_string()
+            } else {
+                format!("{}.{}", name, channel)
+            }
+        });
+        let channel_size = self.size.x * self.size.y * self.pixel_type.size();
+        let mut channels: Vec<(String, Vec<u8>)> = names
+            .map(|name| (name, Vec::with_capacity(channel_size)))
+            .collect();
+        for y in 0..self.size.y {
+            for x in 0..self.size.x {
+                let color = image.get(Point2::new(x, y));
+                for (channel, value) in channels.iter_mut().zip(color.channels()) {
+                    self.pixel_type.write(value, &mut channel.1);
+                }
+            }
+        }
+
+        self.channels
+            .retain(|(name, _)| channels.iter().all(|(other, _)| other != name));
+        self.channels.append(&mut channels);
+        self
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        // The channels are stored in the alphabetical order of their names.
+        let mut channels: Vec<&(String, Vec<u8>)> = self.channels.iter().collect();
+        channels.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let names: Vec<&str> = channels.iter().map(|(name, _)| name.as_str()).collect();
+        let mut result = header(self.size, self.pixel_type, &names);
+        let channel_row_size = self.size.x * self.pixel_type.size();
+        let row_size = channels.len() * channel_row_size;
+        for y in 0..self.size.y {
+            result.extend_from_slice(&(y as i32).to_le_bytes());
+            result.extend_from_slice(&(row_size as u32).to_le_bytes());
+            for (_, values) in &channels {
+                result.extend_from_slice(&values[y * channel_row_size..(y + 1) * channel_row_size]);
+            }
+        }
+
+        result
+    }
+}
+
+// Writes an EXR file of an image without further layers row by row.
+pub struct Writer<W: Write, C> {
+    writer: W,
+    pixel_type: PixelType,
+    // The channels of the color in the alphabetical order of their names, and the next row.
+    order: Vec<usize>,
+    y: usize,
+    _color: PhantomData<C>,
+}
+
+impl<W: Write, C: ExrColor> Writer<W, C> {
+    pub fn new(
+        mut writer: W,
+        size: Vector2<usize>,
+        pixel_type: PixelType,
+    ) -> io::Result<Writer<W, C>> {
+        let mut order: Vec<usize> = (0..C::CHANNELS.len()).collect();
+        order.sort_by_key(|&channel| C::CHANNELS[channel]);
+        let names: Vec<&str> = order.iter().map(|&channel| C::CHANNELS[channel]).collect();
+        writer.write_all(&header(size, pixel_type, &names))?;
+
+        Ok(Writer {
+            writer,
+            pixel_type,
+            order,
+            y: 0,
+            _color: PhantomData,
+        })
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write, C: Color + ExrColor> ScanlineEncoder for Writer<W, C> {
+    type ColorType = C;
+
+    fn write_scanline(&mut self, scanline: &[C]) -> io::Result<()> {
+        let row_size = self.order.len() * scanline.len() * self.pixel_type.size();
+        let mut data = Vec::with_capacity(row_size + 8);
+        data.extend_from_slice(&(self.y as i32).to_le_bytes());
+        data.extend_from_slice(&(row_size as u32).to_le_bytes());
+        for &channel in &self.order {
+            for color in scanline {
+                let value = color.channels().nth(channel).unwrap();
+                self.pixel_type.write(value, &mut data);
+            }
+        }
+        self.y += 1;
+        self.writer.write_all(&data)
+    }
+}
+
+// The header of a file with the channels, followed by the offsets of the scanlines.
+fn header(size: Vector2<usize>, pixel_type: PixelType, names: &[&str]) -> Vec<u8> {
+    let long_names = names.iter().any(|name| name.len() > 31);
+    let mut result = MAGIC.to_vec();
+    let version = if long_names {
+        VERSION | LONG_NAMES
+    } else {
+        VERSION
+    };
+    result.extend_from_slice(&version.to_le_bytes());
+
+    let mut channel_list = Vec::new();
+    for name in names {
+        channel_list.extend_from_slice(name.as_bytes());
+        channel_list.push(0);
+        channel_list.extend_from_slice(&pixel_type.id().to_le_bytes());
+        // Not linear in perception, reserved, and sampled at every pixel in x and y.
+        channel_list.extend_from_slice(&[0, 0, 0, 0]);
+        channel_list.extend_from_slice(&1i32.to_le_bytes());
+        channel_list.extend_from_slice(&1i32.to_le_bytes());
+    }
+    channel_list.push(0);
+
+    let mut window = Vec::with_capacity(16);
+    for corner in [0, 0, size.x as i32 - 1, size.y as i32 - 1] {
+        window.extend_from_slice(&corner.to_le_bytes());
+    }
+
+    write_attribute(&mut result, "channels", "chlist", &channel_list);
+    write_attribute(&mut result, "compression", "compression", &[0]);
+    write_attribute(&mut result, "dataWindow", "box2i", &window);
+    write_attribute(&mut result, "displayWindow", "box2i", &window);
+    write_attribute(&mut result, "lineOrder", "lineOrder", &[0]);
+    write_attribute(
+        &mut result,
+        "pixelAspectRatio",
+        "float",
+        &1.0f32.to_le_bytes(),
+    );
+    write_attribute(&mut result, "screenWindowCenter", "v2f", &[0; 8]);
+    write_attribute(
+        &mut result,
+        "screenWindowWidth",
+        "float",
+        &1.0f32.to_le_bytes(),
+    );
+    result.push(0);
+
+    // The offsets of the scanlines follow the header, each scanline starts with its y coordinate
+    // and its size, followed by the values of one channel after the other.
+    let row_size = names.len() * size.x * pixel_type.size();
+    let first_row = result.len() + size.y * 8;
+    for y in 0..size.y {
+        let offset = first_row + y * (row_size + 8);
+        result.extend_from_slice(&(offset as u64).to_le_bytes());
+    }
+
+    result
+}
+
+fn write_attribute(result: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    result.extend_from_slice(name.as_bytes());
+    result.push(0);
+    result.extend_from_slice(kind.as_bytes());
+    result.push(0);
+    result.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    result.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ImageBuffer, WritableImage};
+
+    macro_rules! encode_exr_layers {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut image = ImageBuffer::new(Vector2::new(2, 1), RGB::<$type>::default());
+                *image.get_mut(Point2::new(1, 0)) = RGB::new(1.0, 2.0, 3.0);
+                let depth = ImageBuffer::new(Vector2::new(2, 1), Gray::<$type>::new(0.5));
+
+                let data = ExrImage::new(Vector2::new(2, 1), PixelType::Float)
+                    .with_layer("", &image)
+                    .with_layer("depth", &depth)
+                    .encode();
+
+                assert_eq!(data[..8], [0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+                let header = String::from_utf8_lossy(&data);
+                let positions: Vec<usize> = ["B\0", "G\0", "R\0", "depth.Y\0"]
+                    .iter()
+                    .map(|name| header.find(name).unwrap())
+                    .collect();
+                assert!(positions.is_sorted());
+
+                // The only scanline holds the channels in the same order, after its offset.
+                let row_size = 4 * 2 * 4;
+                let start = data.len() - row_size - 8;
+                let offset = u64::from_le_bytes(data[start - 8..start].try_into().unwrap());
+                assert_eq!(offset as usize, start);
+                let row = &data[start..];
+                assert_eq!(row[..8], [0, 0, 0, 0, row_size as u8, 0, 0, 0]);
+                let values: Vec<f32> = row[8..]
+                    .chunks(4)
+                    .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+                    .collect();
+                assert_eq!(values, vec![0.0, 3.0, 0.0, 2.0, 0.0, 1.0, 0.5, 0.5]);
+            }
+        };
+    }
+
+    encode_exr_layers! { f32, encode_exr_layers_f32 }
+    encode_exr_layers! { f64, encode_exr_layers_f64 }
+
+    #[test]
+    fn write_exr_rows() {
+        let mut image = ImageBuffer::new(Vector2::new(2, 3), RGBA::new(0.0f32, 0.0, 0.0, 1.0));
+        *image.get_mut(Point2::new(1, 2)) = RGBA::new(0.25, 0.5, 0.75, 0.0);
+
+        let mut writer = Writer::new(Vec::new(), image.size(), PixelType::Float).unwrap();
+        writer.write_rows(&image, 0..2).unwrap();
+        writer.write_rows(&image, 2..3).unwrap();
+        let data = writer.finish().unwrap();
+
+        let encoded = ExrImage::new(image.size(), PixelType::Float)
+            .with_layer("", &image)
+            .encode();
+        assert_eq!(data, encoded);
+    }
+
+    #[test]
+    fn write_exr_rows_of_half_floats() {
+        let mut image = ImageBuffer::new(Vector2::new(2, 1), RGB::<F16>::default());
+        *image.get_mut(Point2::new(1, 0)) = RGB::from(RGB::new(0.5f32, 1.0, 65504.0));
+
+        let mut writer = Writer::new(Vec::new(), image.size(), PixelType::Half).unwrap();
+        writer.write_rows(&image, 0..1).unwrap();
+        let data = writer.finish().unwrap();
+
+        let row = [
+            0, 0, 0, 0, 12, 0, 0, 0, 0, 0, 0xff, 0x7b, 0, 0, 0, 0x3c, 0, 0, 0, 0x38,
+        ];
+        assert_eq!(data[data.len() - 20..], row);
+    }
+
+    #[test]
+    fn encode_exr_in_half_floats() {
+        let image = ImageBuffer::new(Vector2::new(3, 2), Gray::new(1.0f32));
+        let data = ExrImage::new(Vector2::new(3, 2), PixelType::Half)
+            .with_layer("mask", &image)
+            .encode();
+
+        // Both scanlines hold the three values of the only channel.
+        let row = [0, 0, 0, 0, 6, 0, 0, 0, 0x00, 0x3c, 0x00, 0x3c, 0x00, 0x3c];
+        assert_eq!(data[data.len() - 28..data.len() - 14], row);
+        assert_eq!(data[data.len() - 10..], row[4..]);
+    }
+}
@@ -1,7 +1,15 @@
-use crate::Image;
+use std::io::{self, Write};
+
+use crate::{Image, ImageBuffer, ScanlineEncoder, WritableImage};
 
 use colors::RGBA;
-use math::Point2;
+use math::{Point2, Vector2};
+
+#[derive(Debug, PartialEq)]
+pub enum DecodingError {
+    MissingSignature,
+    UnexpectedEndOfData,
+}
 
 pub trait Encoder {
     fn encode(&self) -> Vec<u8>;
@@ -10,26 +18,116 @@ pub trait Encoder {
 impl<T: Image<PointType = Point2<usize>, ColorType = RGBA<u16>>> Encoder for T {
     fn encode(&self) -> Vec<u8> {
         let size = self.size();
-        let num_pixel = size.x * size.y;
+        let data = Vec::with_capacity(8 + 4 + 4 + size.x * size.y * 8);
 
-        let mut result = Vec::with_capacity(8 + 4 + 4 + num_pixel * 8);
+        // Writing to memory does not fail.
+        let mut writer = Writer::new(data, size).unwrap();
+        writer.write_rows(self, 0..size.y).unwrap();
+        writer.finish().unwrap()
+    }
+}
+
+// Writes a farbfeld file row by row.
+pub struct Writer<W: Write> {
+    writer: W,
+}
 
-        result.append(&mut "farbfeld".as_bytes().to_vec());
+impl<W: Write> Writer<W> {
+    pub fn new(mut writer: W, size: Vector2<usize>) -> io::Result<Writer<W>> {
+        writer.write_all(b"farbfeld")?;
+        writer.write_all(&(size.x as u32).to_be_bytes())?;
+        writer.write_all(&(size.y as u32).to_be_bytes())?;
+        Ok(Writer { writer })
+    }
 
-        result.append(&mut (size.x as u32).to_be_bytes().to_vec());
-        result.append(&mut (size.y as u32).to_be_bytes().to_vec());
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
 
-        for y in 0..size.y {
-            for x in 0..size.x {
-                let color = self.get(Point2::new(x, y));
+impl<W: Write> ScanlineEncoder for Writer<W> {
+    type ColorType = RGBA<u16>;
 
-                result.append(&mut color.red.to_be_bytes().to_vec());
-                result.append(&mut color.green.to_be_bytes().to_vec());
-                result.append(&mut color.blue.to_be_bytes().to_vec());
-                result.append(&mut color.alpha.to_be_bytes().to_vec());
-            }
+    fn write_scanline(&mut self, scanline: &[RGBA<u16>]) -> io::Result<()> {
+        let mut data = Vec::with_capacity(scanline.len() * 8);
+        for color in scanline {
+            data.extend_from_slice(&color.red.to_be_bytes());
+            data.extend_from_slice(&color.green.to_be_bytes());
+            data.extend_from_slice(&color.blue.to_be_bytes());
+            data.extend_from_slice(&color.alpha.to_be_bytes());
         }
+        self.writer.write_all(&data)
+    }
+}
+
+// Decodes a farbfeld file, e.g. an image the renderer wrote before. Data after the last pixel is
+// ignored.
+pub fn decode(data: &[u8]) -> Result<ImageBuffer<RGBA<u16>>, DecodingError> {
+    if !data.starts_with(b"farbfeld") {
+        return Err(DecodingError::MissingSignature);
+    }
+    if data.len() < 16 {
+        return Err(DecodingError::UnexpectedEndOfData);
+    }
+
+    let width = u32::from_be_bytes([data[8], data[9], data[10], data[11]]) as usize;
+    let height = u32::from_be_bytes([data[12], data[13], data[14], data[15]]) as usize;
+    let pixels = &data[16..];
+    let size = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(8));
+    if size.is_none_or(|size| size > pixels.len()) {
+        return Err(DecodingError::UnexpectedEndOfData);
+    }
+
+    let mut image = ImageBuffer::new(Vector2::new(width, height), RGBA::new(0, 0, 0, 0));
+    let mut channels = pixels
+        .chunks_exact(2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]));
+    for y in 0..height {
+        for x in 0..width {
+            let mut channel = || channels.next().unwrap();
+            *image.get_mut(Point2::new(x, y)) =
+                RGBA::new(channel(), channel(), channel(), channel());
+        }
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_and_decode_farbfeld() {
+        let mut image = ImageBuffer::new(Vector2::new(3, 2), RGBA::new(0, 0, 0, u16::MAX));
+        *image.get_mut(Point2::new(2, 1)) = RGBA::new(1, 256, 65535, 7);
+
+        let data = image.encode();
+
+        assert_eq!(data[..16], *b"farbfeld\0\0\0\x03\0\0\0\x02");
+        assert_eq!(decode(&data), Ok(image));
+    }
 
-        result
+    #[test]
+    fn decode_rejects_invalid_data() {
+        assert_eq!(
+            decode(b"P6\n2 2\n255\n"),
+            Err(DecodingError::MissingSignature)
+        );
+        assert_eq!(
+            decode(b"farbfeld\0\0"),
+            Err(DecodingError::UnexpectedEndOfData)
+        );
+        assert_eq!(
+            decode(b"farbfeld\0\0\0\x01\0\0\0\x01\0\0\0\0"),
+            Err(DecodingError::UnexpectedEndOfData)
+        );
+        assert_eq!(
+            decode(b"farbfeld\xff\xff\xff\xff\xff\xff\xff\xff"),
+            Err(DecodingError::UnexpectedEndOfData)
+        );
     }
 }
@@ -0,0 +1,92 @@
+use math::Vector2;
+use traits::{ConvenientNumber, Exp, FloatingPoint, One, Zero};
+
+// Reconstructs pixels from samples that may lie anywhere around them. The box filter only sees the
+// samples inside of the pixel; the others spread each sample over the pixels within their radius,
+// which removes the stair steps the box filter leaves at high-contrast edges.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReconstructionFilter<V> {
+    Box,
+    // exp(-alpha x²), shifted down so it reaches zero at the radius.
+    Gaussian { radius: V, alpha: V },
+    // sinc(x) windowed by the central lobe of a wider sinc, the Lanczos filter. Keeps edges
+    // sharper than the Gaussian, but its negative lobes may ring at very bright edges.
+    WindowedSinc { radius: V },
+}
+
+impl<V> ReconstructionFilter<V>
+where
+    V: FloatingPoint + ConvenientNumber + Exp<Output = V>,
+{
+    pub fn radius(&self) -> V {
+        match self {
+            ReconstructionFilter::Box => V::one().half(),
+            ReconstructionFilter::Gaussian { radius, .. } => *radius,
+            ReconstructionFilter::WindowedSinc { radius } => *radius,
+        }
+    }
+
+    // The weight of a sample at the offset from the center of a pixel.
+    pub fn weight(&self, offset: Vector2<V>) -> V {
+        self.weight_1d(offset.x) * self.weight_1d(offset.y)
+    }
+
+    fn weight_1d(&self, x: V) -> V {
+        let x = x.abs();
+        if x > self.radius() {
+            return Zero::zero();
+        }
+
+        match self {
+            ReconstructionFilter::Box => One::one(),
+            ReconstructionFilter::Gaussian { radius, alpha } => {
+                let gaussian = |x: V| (-*alpha * x * x).exp();
+                gaussian(x) - gaussian(*radius)
+            }
+            ReconstructionFilter::WindowedSinc { radius } => sinc(x) * sinc(x / *radius),
+        }
+    }
+}
+
+fn sinc<V: FloatingPoint>(x: V) -> V {
+    if x.abs() < V::EPSILON {
+        V::one()
+    } else {
+        (V::PI * x).sin() / (V::PI * x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! reconstruction_filter_weights {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let box_filter = ReconstructionFilter::<$type>::Box;
+                assert_eq!(box_filter.weight(Vector2::new(0.4, -0.4)), 1.0);
+                assert_eq!(box_filter.weight(Vector2::new(0.6, 0.0)), 0.0);
+
+                let gaussian = ReconstructionFilter::<$type>::Gaussian {
+                    radius: 1.5,
+                    alpha: 2.0,
+                };
+                let center = gaussian.weight(Vector2::new(0.0, 0.0));
+                assert!((center - (1.0 - (-4.5 as $type).exp()).powi(2)).abs() < 0.0001);
+                assert!(gaussian.weight(Vector2::new(0.5, 0.0)) < center);
+                assert_eq!(gaussian.weight(Vector2::new(1.5, 0.0)), 0.0);
+                assert_eq!(gaussian.weight(Vector2::new(0.0, 2.0)), 0.0);
+
+                let sinc = ReconstructionFilter::<$type>::WindowedSinc { radius: 3.0 };
+                assert_eq!(sinc.weight(Vector2::new(0.0, 0.0)), 1.0);
+                assert!(sinc.weight(Vector2::new(1.0, 0.0)).abs() < 0.0001);
+                assert!(sinc.weight(Vector2::new(1.5, 0.0)) < 0.0);
+                assert_eq!(sinc.weight(Vector2::new(3.5, 0.0)), 0.0);
+            }
+        };
+    }
+
+    reconstruction_filter_weights! { f32, reconstruction_filter_weights_f32 }
+    reconstruction_filter_weights! { f64, reconstruction_filter_weights_f64 }
+}
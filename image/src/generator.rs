@@ -1,5 +1,7 @@
 pub mod checkerboard;
 pub mod grid;
+pub mod noise_pattern;
 
 pub use checkerboard::Checkerboard;
 pub use grid::Grid;
+pub use noise_pattern::NoisePattern;
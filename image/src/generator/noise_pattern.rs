@@ -0,0 +1,82 @@
+use crate::Image;
+use colors::Color;
+use math::noise::Noise;
+use math::{Point2, Point3, Vector2};
+use traits::{Clamp, ConvenientNumber, FloatingPoint, Half, One, Zero};
+
+// Blends two colors by a noise, e.g. for clouds, stains or rough surfaces. The noise is sampled on
+// the plane z = 0 with the frequency per unit of the texture coordinates. Values of -1 and below
+// give the first color, values of 1 and above the second one.
+pub struct NoisePattern<C: Color, N> {
+    a: C,
+    b: C,
+    noise: N,
+    frequency: C::ChannelType,
+}
+
+impl<C: Color, N> NoisePattern<C, N> {
+    pub fn generate(a: C, b: C, noise: N, frequency: C::ChannelType) -> NoisePattern<C, N> {
+        NoisePattern {
+            a,
+            b,
+            noise,
+            frequency,
+        }
+    }
+}
+
+impl<C: Color, N> Image for NoisePattern<C, N>
+where
+    C::ChannelType: FloatingPoint + ConvenientNumber,
+    N: Noise<C::ChannelType> + Send + Sync,
+{
+    type ColorType = C;
+    type PointType = Point2<C::ChannelType>;
+
+    fn size(&self) -> Vector2<C::ChannelType> {
+        Vector2::new(One::one(), One::one())
+    }
+
+    fn get(&self, p: Self::PointType) -> C {
+        let value = self.noise.value(Point3::new(
+            p.x * self.frequency,
+            p.y * self.frequency,
+            Zero::zero(),
+        ));
+        let one = C::ChannelType::one();
+        let t = (value + one).half().clamp(Zero::zero(), one);
+
+        self.a * (one - t) + self.b * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use colors::RGB;
+    use math::Vector3;
+
+    struct Ramp;
+
+    impl Noise<f64> for Ramp {
+        fn value_and_gradient(&self, p: Point3<f64>) -> (f64, Vector3<f64>) {
+            (p.x, Vector3::new(1.0, 0.0, 0.0))
+        }
+    }
+
+    #[test]
+    fn noise_pattern_blends_colors() {
+        let black = RGB::new(0.0, 0.0, 0.0);
+        let white = RGB::new(1.0, 1.0, 1.0);
+        let pattern = NoisePattern::generate(black, white, Ramp, 4.0);
+
+        assert_eq!(pattern.get(Point2::new(-0.5, 0.3)), black);
+        assert_eq!(pattern.get(Point2::new(0.0, 0.3)), RGB::new(0.5, 0.5, 0.5));
+        assert_eq!(
+            pattern.get(Point2::new(0.125, 0.7)),
+            RGB::new(0.75, 0.75, 0.75)
+        );
+        assert_eq!(pattern.get(Point2::new(0.5, 0.3)), white);
+    }
+}
@@ -0,0 +1,476 @@
+use std::io::{self, Write};
+use std::marker::PhantomData;
+
+use crate::{Image, ImageBuffer, ScanlineEncoder, WritableImage};
+
+use colors::{Color, RGB};
+use math::{Point2, Vector2};
+
+#[derive(Debug, PartialEq)]
+pub enum DecodingError {
+    MissingSignature,
+    UnsupportedFormat(String),
+    UnsupportedResolution(String),
+    UnexpectedEndOfData,
+    InvalidRunLength,
+}
+
+// Decodes a Radiance RGBE (.hdr) file. Only the common orientation with the first scanline at
+// the top and pixels running from left to right is supported.
+pub fn decode(data: &[u8]) -> Result<ImageBuffer<RGB<f32>>, DecodingError> {
+    let mut position = 0;
+
+    let signature = next_line(data, &mut position)?;
+    if signature != "#?RADIANCE" && signature != "#?RGBE" {
+        return Err(DecodingError::MissingSignature);
+    }
+
+    loop {
+        let line = next_line(data, &mut position)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(DecodingError::UnsupportedFormat(format.to_string()));
+            }
+        }
+    }
+
+    let resolution = next_line(data, &mut position)?;
+    let size: Vector2<usize> = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+        ["-Y", height, "+X", width] => match (width.parse(), height.parse()) {
+            (Ok(width), Ok(height)) => Vector2::new(width, height),
+            _ => return Err(DecodingError::UnsupportedResolution(resolution)),
+        },
+        _ => return Err(DecodingError::UnsupportedResolution(resolution)),
+    };
+
+    // Pixels take 4 bytes, unless they are repeated by runs. A run repeats a pixel at most 255
+    // times in 4 bytes, only old style runs that follow each other repeat it more often. Files
+    // whose size needs more than that are rejected before the image is allocated.
+    let Some(pixels) = size.x.checked_mul(size.y) else {
+        return Err(DecodingError::UnsupportedResolution(resolution));
+    };
+    if pixels.div_ceil(64) > data.len() - position {
+        return Err(DecodingError::UnexpectedEndOfData);
+    }
+
+    let mut image = ImageBuffer::new(size, RGB::new(0.0, 0.0, 0.0));
+    let mut scanline = vec![[0u8; 4]; size.x];
+
+    for y in 0..size.y {
+        read_scanline(data, &mut position, &mut scanline)?;
+        for (x, rgbe) in scanline.iter().enumerate() {
+            *image.get_mut(Point2::new(x, y)) = from_rgbe(*rgbe);
+        }
+    }
+
+    Ok(image)
+}
+
+// The colors that are stored in Radiance files, which share an exponent between their channels.
+pub trait HdrColor {
+    fn rgb(&self) -> [f32; 3];
+}
+
+impl HdrColor for RGB<f32> {
+    fn rgb(&self) -> [f32; 3] {
+        [self.red, self.green, self.blue]
+    }
+}
+
+impl HdrColor for RGB<f64> {
+    fn rgb(&self) -> [f32; 3] {
+        [self.red as f32, self.green as f32, self.blue as f32]
+    }
+}
+
+// Encodes an image as a Radiance RGBE (.hdr) file with run length encoded scanlines, where their
+// width allows it. Negative channels are written as zero.
+pub trait Encoder {
+    fn encode_hdr(&self) -> Vec<u8>;
+}
+
+impl<T> Encoder for T
+where
+    T: Image<PointType = Point2<usize>>,
+    T::ColorType: HdrColor,
+{
+    fn encode_hdr(&self) -> Vec<u8> {
+        let size = self.size();
+
+        // Writing to memory does not fail.
+        let mut writer = Writer::new(Vec::new(), size).unwrap();
+        writer.write_rows(self, 0..size.y).unwrap();
+        writer.finish().unwrap()
+    }
+}
+
+// Writes a Radiance file row by row.
+pub struct Writer<W: Write, C> {
+    writer: W,
+    _color: PhantomData<C>,
+}
+
+impl<W: Write, C> Writer<W, C> {
+    pub fn new(mut writer: W, size: Vector2<usize>) -> io::Result<Writer<W, C>> {
+        write!(
+            writer,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            size.y, size.x
+        )?;
+        Ok(Writer {
+            writer,
+            _color: PhantomData,
+        })
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write, C: Color + HdrColor> ScanlineEncoder for Writer<W, C> {
+    type ColorType = C;
+
+    fn write_scanline(&mut self, scanline: &[C]) -> io::Result<()> {
+        let scanline: Vec<[u8; 4]> = scanline.iter().map(|color| to_rgbe(color.rgb())).collect();
+        let mut data = Vec::with_capacity(scanline.len() * 4);
+        write_scanline(&scanline, &mut data);
+        self.writer.write_all(&data)
+    }
+}
+
+fn to_rgbe(rgb: [f32; 3]) -> [u8; 4] {
+    let rgb = rgb.map(|channel| channel.max(0.0));
+    let max = rgb[0].max(rgb[1]).max(rgb[2]);
+    if max < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    // The exponent makes the largest channel a mantissa from 128 to 255, the largest exponent
+    // clamps channels that can not be stored.
+    let exponent = (max.log2().floor() as i32 + 1).min(127);
+    let factor = 2.0f32.powi(8 - exponent);
+    let [red, green, blue] = rgb.map(|channel| (channel * factor).min(255.0) as u8);
+    [red, green, blue, (exponent + 128) as u8]
+}
+
+fn from_rgbe(rgbe: [u8; 4]) -> RGB<f32> {
+    if rgbe[3] == 0 {
+        return RGB::new(0.0, 0.0, 0.0);
+    }
+
+    let factor = 2.0f32.powi(rgbe[3] as i32 - (128 + 8));
+    RGB::new(
+        (rgbe[0] as f32 + 0.5) * factor,
+        (rgbe[1] as f32 + 0.5) * factor,
+        (rgbe[2] as f32 + 0.5) * factor,
+    )
+}
+
+fn next_line(data: &[u8], position: &mut usize) -> Result<String, DecodingError> {
+    let start = *position;
+    while *position < data.len() && data[*position] != b'\n' {
+        *position += 1;
+    }
+    if *position == data.len() {
+        return Err(DecodingError::UnexpectedEndOfData);
+    }
+
+    let line = String::from_utf8_lossy(&data[start..*position]).to_string();
+    *position += 1;
+    Ok(line)
+}
+
+fn next_byte(data: &[u8], position: &mut usize) -> Result<u8, DecodingError> {
+    match data.get(*position) {
+        Some(byte) => {
+            *position += 1;
+            Ok(*byte)
+        }
+        None => Err(DecodingError::UnexpectedEndOfData),
+    }
+}
+
+fn read_scanline(
+    data: &[u8],
+    position: &mut usize,
+    scanline: &mut [[u8; 4]],
+) -> Result<(), DecodingError> {
+    let width = scanline.len();
+
+    // Run length encoded scanlines start with two 2s followed by the width. Everything else is
+    // stored as flat or old style run length encoded pixels.
+    let is_rle = (8..0x8000).contains(&width)
+        && data.len() >= *position + 4
+        && data[*position] == 2
+        && data[*position + 1] == 2
+        && data[*position + 2] & 0x80 == 0;
+
+    if !is_rle {
+        return read_flat_scanline(data, position, scanline);
+    }
+
+    if ((data[*position + 2] as usize) << 8 | data[*position + 3] as usize) != width {
+        return Err(DecodingError::InvalidRunLength);
+    }
+    *position += 4;
+
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = next_byte(data, position)? as usize;
+            if count > 128 {
+                let count = count - 128;
+                if x + count > width {
+                    return Err(DecodingError::InvalidRunLength);
+                }
+                let value = next_byte(data, position)?;
+                for pixel in &mut scanline[x..x + count] {
+                    pixel[channel] = value;
+                }
+                x += count;
+            } else {
+                if count == 0 || x + count > width {
+                    return Err(DecodingError::InvalidRunLength);
+                }
+                for pixel in &mut scanline[x..x + count] {
+                    pixel[channel] = next_byte(data, position)?;
+                }
+                x += count;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_flat_scanline(
+    data: &[u8],
+    position: &mut usize,
+    scanline: &mut [[u8; 4]],
+) -> Result<(), DecodingError> {
+    let mut x = 0;
+    let mut shift = 0;
+
+    while x < scanline.len() {
+        let mut rgbe = [0u8; 4];
+        for byte in rgbe.iter_mut() {
+            *byte = next_byte(data, position)?;
+        }
+
+        // Old style runs repeat the previous pixel.
+        if rgbe[0] == 1 && rgbe[1] == 1 && rgbe[2] == 1 {
+            if x == 0 {
+                return Err(DecodingError::InvalidRunLength);
+            }
+            let count = (rgbe[3] as usize)
+                .checked_shl(shift)
+                .ok_or(DecodingError::InvalidRunLength)?;
+            if x + count > scanline.len() {
+                return Err(DecodingError::InvalidRunLength);
+            }
+            for i in x..x + count {
+                scanline[i] = scanline[x - 1];
+            }
+            x += count;
+            shift += 8;
+        } else {
+            scanline[x] = rgbe;
+            x += 1;
+            shift = 0;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_scanline(scanline: &[[u8; 4]], result: &mut Vec<u8>) {
+    let width = scanline.len();
+    if !(8..0x8000).contains(&width) {
+        for rgbe in scanline {
+            result.extend_from_slice(rgbe);
+        }
+        return;
+    }
+
+    result.extend_from_slice(&[2, 2, (width >> 8) as u8, width as u8]);
+    for channel in 0..4 {
+        let values: Vec<u8> = scanline.iter().map(|rgbe| rgbe[channel]).collect();
+        write_run_length_channel(&values, result);
+    }
+}
+
+// Repeated values are written as runs when they repeat at least four times, all others as
+// literal values, both in groups of at most 127 and 128 values.
+fn write_run_length_channel(values: &[u8], result: &mut Vec<u8>) {
+    const MIN_RUN: usize = 4;
+
+    let mut x = 0;
+    while x < values.len() {
+        let mut run_start = x;
+        let mut run_length = 0;
+        while run_start < values.len() {
+            run_length = values[run_start..]
+                .iter()
+                .take(127)
+                .take_while(|&&value| value == values[run_start])
+                .count();
+            if run_length >= MIN_RUN {
+                break;
+            }
+            run_start += run_length;
+        }
+
+        while x < run_start {
+            let count = (run_start - x).min(128);
+            result.push(count as u8);
+            result.extend_from_slice(&values[x..x + count]);
+            x += count;
+        }
+        if run_length >= MIN_RUN {
+            result.extend_from_slice(&[128 + run_length as u8, values[run_start]]);
+            x = run_start + run_length;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(width: usize, height: usize) -> Vec<u8> {
+        format!(
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            height, width
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn decode_flat_scanlines() {
+        let mut data = header(2, 1);
+        data.extend_from_slice(&[128, 64, 0, 129, 0, 0, 0, 0]);
+
+        let image = decode(&data).unwrap();
+
+        assert_eq!(image.size(), Vector2::new(2, 1));
+        assert_eq!(
+            image.get(Point2::new(0, 0)),
+            RGB::new(128.5 / 128.0, 64.5 / 128.0, 0.5 / 128.0)
+        );
+        assert_eq!(image.get(Point2::new(1, 0)), RGB::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn decode_run_length_encoded_scanlines() {
+        let mut data = header(8, 2);
+        for _ in 0..2 {
+            data.extend_from_slice(&[2, 2, 0, 8]);
+            // Red: a run of eight, green: eight literal values, blue and exponent: runs.
+            data.extend_from_slice(&[136, 127]);
+            data.extend_from_slice(&[8, 0, 1, 2, 3, 4, 5, 6, 7]);
+            data.extend_from_slice(&[136, 0]);
+            data.extend_from_slice(&[136, 128]);
+        }
+
+        let image = decode(&data).unwrap();
+
+        assert_eq!(image.size(), Vector2::new(8, 2));
+        for y in 0..2 {
+            for x in 0..8 {
+                assert_eq!(
+                    image.get(Point2::new(x, y)),
+                    RGB::new(127.5 / 256.0, (x as f32 + 0.5) / 256.0, 0.5 / 256.0)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn convert_to_rgbe() {
+        assert_eq!(to_rgbe([1.0, 0.5, 0.0]), [128, 64, 0, 129]);
+        assert_eq!(to_rgbe([0.0, 0.0, 0.0]), [0, 0, 0, 0]);
+        assert_eq!(to_rgbe([-1.0, 0.25, 0.0]), [0, 128, 0, 127]);
+        assert_eq!(to_rgbe([f32::MAX, 0.0, 0.0]), [255, 0, 0, 255]);
+    }
+
+    macro_rules! encode_and_decode_hdr {
+        ($type: ty, $width: expr, $name: ident) => {
+            #[test]
+            fn $name() {
+                let size = Vector2::new($width, 3);
+                let mut image = ImageBuffer::new(size, RGB::<$type>::new(0.25, 0.5, 4.0));
+                for x in 0..size.x / 2 {
+                    *image.get_mut(Point2::new(x, 1)) = RGB::new(x as $type * 10.0, 0.0, 1e-3);
+                }
+
+                let decoded = decode(&image.encode_hdr()).unwrap();
+
+                assert_eq!(decoded.size(), size);
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let expected = image.get(Point2::new(x, y));
+                        let color = decoded.get(Point2::new(x, y));
+                        let tolerance = expected.red.max(expected.green).max(expected.blue) / 128.0;
+                        assert!((color.red as $type - expected.red).abs() <= tolerance);
+                        assert!((color.green as $type - expected.green).abs() <= tolerance);
+                        assert!((color.blue as $type - expected.blue).abs() <= tolerance);
+                    }
+                }
+            }
+        };
+    }
+
+    encode_and_decode_hdr! { f32, 5, encode_and_decode_flat_hdr_f32 }
+    encode_and_decode_hdr! { f64, 5, encode_and_decode_flat_hdr_f64 }
+    encode_and_decode_hdr! { f32, 300, encode_and_decode_run_length_encoded_hdr_f32 }
+    encode_and_decode_hdr! { f64, 300, encode_and_decode_run_length_encoded_hdr_f64 }
+
+    #[test]
+    fn decode_rejects_invalid_data() {
+        assert_eq!(
+            decode(b"P6\n2 2\n255\n").err(),
+            Some(DecodingError::MissingSignature)
+        );
+
+        let mut data = header(2, 2);
+        data.extend_from_slice(&[128, 64, 0, 129]);
+        assert_eq!(
+            decode(&data).err(),
+            Some(DecodingError::UnexpectedEndOfData)
+        );
+
+        // The size of the image is checked against the data before it is allocated.
+        let mut data = header(100000, 100000);
+        data.extend_from_slice(&[128, 64, 0, 129]);
+        assert_eq!(
+            decode(&data).err(),
+            Some(DecodingError::UnexpectedEndOfData)
+        );
+        let data = header(usize::MAX, 2);
+        assert_eq!(
+            decode(&data).err(),
+            Some(DecodingError::UnsupportedResolution(format!(
+                "-Y 2 +X {}",
+                usize::MAX
+            )))
+        );
+
+        // Old style runs of no pixels shift the counts of the runs after them out of range.
+        let mut data = header(2, 1);
+        data.extend_from_slice(&[128, 64, 0, 129]);
+        data.extend_from_slice(&[1, 1, 1, 0].repeat(9));
+        assert_eq!(decode(&data).err(), Some(DecodingError::InvalidRunLength));
+
+        assert_eq!(
+            decode(b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n").err(),
+            Some(DecodingError::UnsupportedFormat(
+                "32-bit_rle_xyze".to_string()
+            ))
+        );
+    }
+}
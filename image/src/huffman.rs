@@ -0,0 +1,96 @@
+// Canonical Huffman codes as used by deflate and JPEG, where the codes of each length follow the
+// codes of the shorter lengths and the symbols of the same length are sorted.
+
+const MAX_LENGTH: usize = 16;
+
+// A source of the bits of codes, from the most significant bit of a code on.
+pub trait BitSource {
+    fn next_bit(&mut self) -> Option<u32>;
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Huffman {
+    // The number of codes of each length, from length 1 on.
+    counts: [u16; MAX_LENGTH],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    // The code of the symbols with the length of their codes, where 0 leaves a symbol out.
+    pub fn from_lengths(lengths: &[u8]) -> Huffman {
+        let mut counts = [0; MAX_LENGTH];
+        for &length in lengths.iter().filter(|&&length| length > 0) {
+            counts[length as usize - 1] += 1;
+        }
+
+        let mut symbols = Vec::with_capacity(lengths.len());
+        for length in 1..=MAX_LENGTH as u8 {
+            symbols.extend(
+                (0..lengths.len() as u16).filter(|&symbol| lengths[symbol as usize] == length),
+            );
+        }
+
+        Huffman { counts, symbols }
+    }
+
+    // The code of the symbols sorted by the length of their codes, with the number of codes of
+    // each length, as JPEG files store them.
+    pub fn from_counts(counts: [u16; MAX_LENGTH], symbols: Vec<u16>) -> Huffman {
+        Huffman { counts, symbols }
+    }
+
+    // Reads bits until they form a code. None if the bits end or form no code.
+    pub fn decode(&self, source: &mut impl BitSource) -> Option<u16> {
+        // The first code of each length is twice the code after the last one of the length
+        // before.
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts {
+            code |= source.next_bit()? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return self.symbols.get((index + code - first) as usize).copied();
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Bits(Vec<u32>);
+
+    impl BitSource for Bits {
+        fn next_bit(&mut self) -> Option<u32> {
+            (!self.0.is_empty()).then(|| self.0.remove(0))
+        }
+    }
+
+    #[test]
+    fn decode_canonical_codes() {
+        // The codes of the example of RFC 1951: F 00, A 010, B 011, C 100, D 101, E 110, G 1110
+        // and H 1111.
+        let huffman = Huffman::from_lengths(&[3, 3, 3, 3, 3, 2, 4, 4]);
+
+        let mut bits = Bits(vec![0, 0, 0, 1, 0, 1, 1, 0, 1, 1, 1, 1]);
+        assert_eq!(huffman.decode(&mut bits), Some(5));
+        assert_eq!(huffman.decode(&mut bits), Some(0));
+        assert_eq!(huffman.decode(&mut bits), Some(4));
+        assert_eq!(huffman.decode(&mut bits), Some(7));
+        assert_eq!(huffman.decode(&mut bits), None);
+    }
+
+    #[test]
+    fn decode_codes_given_by_counts() {
+        let mut counts = [0; MAX_LENGTH];
+        counts[1] = 2;
+        let huffman = Huffman::from_counts(counts, vec![0x10, 0x20]);
+
+        assert_eq!(huffman.decode(&mut Bits(vec![0, 1])), Some(0x20));
+        assert_eq!(huffman.decode(&mut Bits(vec![1, 1, 1])), None);
+    }
+}
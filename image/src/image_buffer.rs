@@ -3,6 +3,7 @@ use colors::Color;
 use math::geometry::{Circle, Rectangle2};
 use math::{Point, Point2};
 
+#[derive(Debug, PartialEq, Clone)]
 pub struct ImageBuffer<C: Color> {
     pixel_data: Vec<C>,
     size: <Point2<usize> as Point>::VectorType,
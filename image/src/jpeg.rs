@@ -0,0 +1,679 @@
+use std::f32::consts::PI;
+
+use crate::huffman::{BitSource, Huffman};
+use crate::{ImageBuffer, WritableImage};
+
+use colors::{YCbCr, RGB};
+use math::{Point2, Vector2};
+
+// The largest first coefficient of a block of 8 bit samples, before it is dequantized.
+const MAX_DC: i32 = 2047;
+
+// The most samples a frame may have in all its components, e.g. 16384 x 16384 pixels in
+// grayscale.
+const MAX_SAMPLES: usize = 1 << 28;
+
+// The order in which the coefficients of a block are stored, from the lowest frequencies to the
+// highest.
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+#[derive(Debug, PartialEq)]
+pub enum DecodingError {
+    MissingSignature,
+    UnexpectedEndOfData,
+    InvalidHuffmanCode,
+    InvalidCoefficient,
+    InvalidMarker,
+    MissingFrame,
+    MissingTable,
+    UnsupportedFormat(String),
+}
+
+// A component of the image, e.g. the luma, with the samples of all its blocks. Components with
+// fewer samples than others cover the same area with larger pixels.
+struct Component {
+    id: u8,
+    horizontal: usize,
+    vertical: usize,
+    quantization_table: usize,
+    dc_table: usize,
+    ac_table: usize,
+    prediction: i32,
+    samples: Vec<u8>,
+    stride: usize,
+}
+
+struct Frame {
+    size: Vector2<usize>,
+    components: Vec<Component>,
+    // The number of minimum coded units, the blocks of all components that cover the same area.
+    mcus: Vector2<usize>,
+    max_horizontal: usize,
+    max_vertical: usize,
+}
+
+#[derive(Default)]
+struct Tables {
+    quantization: [Option<[u16; 64]>; 4],
+    dc: [Option<Huffman>; 4],
+    ac: [Option<Huffman>; 4],
+    restart_interval: usize,
+}
+
+// Reads the bits of the entropy coded data from the most significant one on. A 0xff byte is
+// followed by a 0, which is skipped, or starts a marker, where only zeros are read.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl BitReader<'_> {
+    fn next_byte(&mut self) -> u8 {
+        match self.data.get(self.position..self.position + 2) {
+            Some([0xff, 0]) => {
+                self.position += 2;
+                0xff
+            }
+            Some([0xff, _]) => 0,
+            _ => match self.data.get(self.position) {
+                Some(&byte) if byte != 0xff => {
+                    self.position += 1;
+                    byte
+                }
+                _ => 0,
+            },
+        }
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        while self.count < count {
+            self.bits = (self.bits << 8) | self.next_byte() as u32;
+            self.count += 8;
+        }
+        self.count -= count;
+        let value = self.bits >> self.count;
+        self.bits &= (1 << self.count) - 1;
+        value
+    }
+
+    // Skips the rest of the current byte and the restart marker that follows.
+    fn restart(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+        while let Some([0xff, marker]) = self.data.get(self.position..self.position + 2) {
+            self.position += 2;
+            if (0xd0..=0xd7).contains(marker) {
+                break;
+            }
+        }
+    }
+}
+
+impl BitSource for BitReader<'_> {
+    fn next_bit(&mut self) -> Option<u32> {
+        Some(self.read(1))
+    }
+}
+
+// Decodes a baseline JPEG file in grayscale or YCbCr, with any subsampling of the components.
+// Progressive and arithmetic coded files are not supported.
+pub fn decode(data: &[u8]) -> Result<ImageBuffer<RGB<u8>>, DecodingError> {
+    if !data.starts_with(&[0xff, 0xd8]) {
+        return Err(DecodingError::MissingSignature);
+    }
+
+    let mut tables = Tables::default();
+    let mut frame = None;
+    let mut position = 2;
+    loop {
+        if data.get(position) != Some(&0xff) {
+            return Err(if position >= data.len() {
+                DecodingError::UnexpectedEndOfData
+            } else {
+                DecodingError::InvalidMarker
+            });
+        }
+        // Markers may be preceded by any number of 0xff bytes.
+        while data.get(position) == Some(&0xff) {
+            position += 1;
+        }
+        let marker = *data
+            .get(position)
+            .ok_or(DecodingError::UnexpectedEndOfData)?;
+        position += 1;
+        match marker {
+            0xd9 => break,
+            0x01 | 0xd0..=0xd7 => continue,
+            _ => {}
+        }
+
+        let length = data
+            .get(position..position + 2)
+            .ok_or(DecodingError::UnexpectedEndOfData)?;
+        let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+        let segment = data
+            .get(position + 2..position + length.max(2))
+            .ok_or(DecodingError::UnexpectedEndOfData)?;
+        position += length.max(2);
+
+        match marker {
+            0xdb => read_quantization_tables(segment, &mut tables)?,
+            0xc4 => read_huffman_tables(segment, &mut tables)?,
+            0xdd => {
+                let interval = segment.get(..2).ok_or(DecodingError::UnexpectedEndOfData)?;
+                tables.restart_interval = u16::from_be_bytes([interval[0], interval[1]]) as usize;
+            }
+            0xc0 | 0xc1 => frame = Some(read_frame(segment, data.len() - position)?),
+            0xc2 => {
+                return Err(DecodingError::UnsupportedFormat(String::from(
+                    "progressive",
+                )))
+            }
+            0xc3 | 0xc5..=0xc7 | 0xc9..=0xcb | 0xcd..=0xcf => {
+                return Err(DecodingError::UnsupportedFormat(String::from(
+                    "lossless, hierarchical or arithmetic coded",
+                )))
+            }
+            0xda => {
+                let frame = frame.as_mut().ok_or(DecodingError::MissingFrame)?;
+                position = decode_scan(segment, data, position, frame, &tables)?;
+            }
+            _ => {}
+        }
+    }
+
+    let frame = frame.ok_or(DecodingError::MissingFrame)?;
+    Ok(to_rgb(&frame))
+}
+
+fn read_quantization_tables(mut segment: &[u8], tables: &mut Tables) -> Result<(), DecodingError> {
+    while let Some((&info, rest)) = segment.split_first() {
+        let (precision, index) = ((info >> 4) as usize, (info & 0x0f) as usize);
+        let size = 64 * (precision + 1);
+        if index > 3 || precision > 1 || rest.len() < size {
+            return Err(DecodingError::UnexpectedEndOfData);
+        }
+
+        let mut table = [0; 64];
+        for (k, value) in table.iter_mut().enumerate() {
+            *value = match precision {
+                0 => rest[k] as u16,
+                _ => u16::from_be_bytes([rest[2 * k], rest[2 * k + 1]]),
+            };
+        }
+        tables.quantization[index] = Some(table);
+        segment = &rest[size..];
+    }
+    Ok(())
+}
+
+fn read_huffman_tables(mut segment: &[u8], tables: &mut Tables) -> Result<(), DecodingError> {
+    while segment.len() >= 17 {
+        let (class, index) = (segment[0] >> 4, (segment[0] & 0x0f) as usize);
+        let mut counts = [0; 16];
+        for (count, &byte) in counts.iter_mut().zip(&segment[1..17]) {
+            *count = byte as u16;
+        }
+        let total = counts.iter().sum::<u16>() as usize;
+        let symbols = segment
+            .get(17..17 + total)
+            .ok_or(DecodingError::UnexpectedEndOfData)?;
+        if index > 3 || class > 1 {
+            return Err(DecodingError::MissingTable);
+        }
+
+        let huffman = Huffman::from_counts(
+            counts,
+            symbols.iter().map(|&symbol| symbol as u16).collect(),
+        );
+        if class == 0 {
+            tables.dc[index] = Some(huffman);
+        } else {
+            tables.ac[index] = Some(huffman);
+        }
+        segment = &segment[17 + total..];
+    }
+    Ok(())
+}
+
+// The rest of the data after the frame has to hold the blocks of the frame, so a small file can
+// not make the decoder allocate a large image.
+fn read_frame(segment: &[u8], remaining: usize) -> Result<Frame, DecodingError> {
+    let header = segment.get(..6).ok_or(DecodingError::UnexpectedEndOfData)?;
+    let precision = header[0];
+    let height = u16::from_be_bytes([header[1], header[2]]) as usize;
+    let width = u16::from_be_bytes([header[3], header[4]]) as usize;
+    let count = header[5] as usize;
+    if precision != 8 {
+        return Err(DecodingError::UnsupportedFormat(format!(
+            "{} bits per sample",
+            precision
+        )));
+    }
+    if count != 1 && count != 3 {
+        return Err(DecodingError::UnsupportedFormat(format!(
+            "{} components",
+            count
+        )));
+    }
+    if width == 0 || height == 0 {
+        return Err(DecodingError::UnsupportedFormat(String::from(
+            "height given after the scan",
+        )));
+    }
+
+    let specifications = segment
+        .get(6..6 + 3 * count)
+        .ok_or(DecodingError::UnexpectedEndOfData)?;
+    let mut components: Vec<Component> = specifications
+        .chunks_exact(3)
+        .map(|specification| Component {
+            id: specification[0],
+            horizontal: (specification[1] >> 4) as usize,
+            vertical: (specification[1] & 0x0f) as usize,
+            quantization_table: (specification[2] & 0x03) as usize,
+            dc_table: 0,
+            ac_table: 0,
+            prediction: 0,
+            samples: vec![],
+            stride: 0,
+        })
+        .collect();
+    if components
+        .iter()
+        .any(|c| !(1..=4).contains(&c.horizontal) || !(1..=4).contains(&c.vertical))
+    {
+        return Err(DecodingError::UnsupportedFormat(String::from(
+            "sampling factor",
+        )));
+    }
+
+    let max_horizontal = components.iter().map(|c| c.horizontal).max().unwrap();
+    let max_vertical = components.iter().map(|c| c.vertical).max().unwrap();
+    let mcus = Vector2::new(
+        width.div_ceil(8 * max_horizontal),
+        height.div_ceil(8 * max_vertical),
+    );
+
+    // Each block needs a code for its first coefficient and one for the end of the block, which
+    // take at least a bit each.
+    let blocks: usize = components
+        .iter()
+        .map(|c| mcus.x * c.horizontal * mcus.y * c.vertical)
+        .sum();
+    if blocks.div_ceil(4) > remaining {
+        return Err(DecodingError::UnexpectedEndOfData);
+    }
+    if blocks * 64 > MAX_SAMPLES {
+        return Err(DecodingError::UnsupportedFormat(format!(
+            "{} x {} pixels",
+            width, height
+        )));
+    }
+
+    for component in &mut components {
+        component.stride = mcus.x * component.horizontal * 8;
+        component.samples = vec![0; component.stride * mcus.y * component.vertical * 8];
+    }
+
+    Ok(Frame {
+        size: Vector2::new(width, height),
+        components,
+        mcus,
+        max_horizontal,
+        max_vertical,
+    })
+}
+
+// Decodes the entropy coded data after the header of a scan, and returns the position of the
+// marker after it.
+fn decode_scan(
+    segment: &[u8],
+    data: &[u8],
+    position: usize,
+    frame: &mut Frame,
+    tables: &Tables,
+) -> Result<usize, DecodingError> {
+    let count = *segment.first().ok_or(DecodingError::UnexpectedEndOfData)? as usize;
+    let specifications = segment
+        .get(1..1 + 2 * count)
+        .ok_or(DecodingError::UnexpectedEndOfData)?;
+    let mut scanned = Vec::with_capacity(count);
+    for specification in specifications.chunks_exact(2) {
+        let index = frame
+            .components
+            .iter()
+            .position(|c| c.id == specification[0])
+            .ok_or(DecodingError::MissingFrame)?;
+        let component = &mut frame.components[index];
+        component.dc_table = (specification[1] >> 4) as usize & 0x03;
+        component.ac_table = (specification[1] & 0x0f) as usize & 0x03;
+        component.prediction = 0;
+        scanned.push(index);
+    }
+
+    // A scan of a single component covers its blocks one by one, otherwise the blocks of a
+    // minimum coded unit follow each other.
+    let mut units = Vec::new();
+    if let [index] = scanned[..] {
+        let component = &frame.components[index];
+        let width = (frame.size.x * component.horizontal).div_ceil(frame.max_horizontal);
+        let height = (frame.size.y * component.vertical).div_ceil(frame.max_vertical);
+        for y in 0..height.div_ceil(8) {
+            for x in 0..width.div_ceil(8) {
+                units.push(vec![(index, x, y)]);
+            }
+        }
+    } else {
+        for y in 0..frame.mcus.y {
+            for x in 0..frame.mcus.x {
+                let mut blocks = Vec::new();
+                for &index in &scanned {
+                    let component = &frame.components[index];
+                    for v in 0..component.vertical {
+                        for h in 0..component.horizontal {
+                            blocks.push((
+                                index,
+                                x * component.horizontal + h,
+                                y * component.vertical + v,
+                            ));
+                        }
+                    }
+                }
+                units.push(blocks);
+            }
+        }
+    }
+
+    let cosines = cosine_table();
+    let mut reader = BitReader {
+        data,
+        position,
+        bits: 0,
+        count: 0,
+    };
+    for (unit, blocks) in units.iter().enumerate() {
+        if tables.restart_interval > 0 && unit > 0 && unit % tables.restart_interval == 0 {
+            reader.restart();
+            for &index in &scanned {
+                frame.components[index].prediction = 0;
+            }
+        }
+        for &(index, x, y) in blocks {
+            let component = &mut frame.components[index];
+            let coefficients = decode_block(&mut reader, component, tables)?;
+            let samples = inverse_dct(&coefficients, &cosines);
+            for (row, values) in samples.chunks_exact(8).enumerate() {
+                let start = (y * 8 + row) * component.stride + x * 8;
+                component.samples[start..start + 8].copy_from_slice(values);
+            }
+        }
+    }
+
+    // The scan ends at the first marker that is no restart marker.
+    let mut position = reader.position;
+    while let Some(bytes) = data.get(position..position + 2) {
+        if bytes[0] == 0xff && bytes[1] != 0 && !(0xd0..=0xd7).contains(&bytes[1]) {
+            return Ok(position);
+        }
+        position += 1;
+    }
+    Err(DecodingError::UnexpectedEndOfData)
+}
+
+// The dequantized coefficients of a block, with the first one predicted from the block before.
+fn decode_block(
+    reader: &mut BitReader,
+    component: &mut Component,
+    tables: &Tables,
+) -> Result<[f32; 64], DecodingError> {
+    let quantization = tables.quantization[component.quantization_table]
+        .as_ref()
+        .ok_or(DecodingError::MissingTable)?;
+    let dc = tables.dc[component.dc_table]
+        .as_ref()
+        .ok_or(DecodingError::MissingTable)?;
+    let ac = tables.ac[component.ac_table]
+        .as_ref()
+        .ok_or(DecodingError::MissingTable)?;
+
+    let mut coefficients = [0.0; 64];
+    let size = dc.decode(reader).ok_or(DecodingError::InvalidHuffmanCode)? as u32;
+    if size > 16 {
+        return Err(DecodingError::InvalidHuffmanCode);
+    }
+    component.prediction += extend(reader.read(size), size);
+    if component.prediction.abs() > MAX_DC {
+        return Err(DecodingError::InvalidCoefficient);
+    }
+    coefficients[0] = component.prediction as f32 * quantization[0] as f32;
+
+    let mut k = 1;
+    while k < 64 {
+        let symbol = ac.decode(reader).ok_or(DecodingError::InvalidHuffmanCode)?;
+        let (zeros, size) = ((symbol >> 4) as usize, (symbol & 0x0f) as u32);
+        if size == 0 {
+            // Either sixteen zeros or the end of the block.
+            if zeros != 15 {
+                break;
+            }
+            k += 16;
+            continue;
+        }
+        k += zeros;
+        if k > 63 {
+            break;
+        }
+        let value = extend(reader.read(size), size);
+        coefficients[ZIGZAG[k]] = value as f32 * quantization[k] as f32;
+        k += 1;
+    }
+
+    Ok(coefficients)
+}
+
+// The value of the bits of a coefficient of the size, where small values are negative.
+fn extend(value: u32, size: u32) -> i32 {
+    if size == 0 {
+        0
+    } else if value < 1 << (size - 1) {
+        value as i32 - (1 << size) + 1
+    } else {
+        value as i32
+    }
+}
+
+// The weights of the frequencies for each position in a row or a column of a block.
+fn cosine_table() -> [[f32; 8]; 8] {
+    let mut table = [[0.0; 8]; 8];
+    for (x, row) in table.iter_mut().enumerate() {
+        for (u, weight) in row.iter_mut().enumerate() {
+            let scale = if u == 0 { 0.5 / 2.0f32.sqrt() } else { 0.5 };
+            *weight = scale * ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos();
+        }
+    }
+    table
+}
+
+// Turns the frequencies of a block back into samples, first along the rows, then along the
+// columns.
+fn inverse_dct(coefficients: &[f32; 64], cosines: &[[f32; 8]; 8]) -> [u8; 64] {
+    let mut rows = [0.0; 64];
+    for v in 0..8 {
+        for x in 0..8 {
+            rows[v * 8 + x] = (0..8)
+                .map(|u| cosines[x][u] * coefficients[v * 8 + u])
+                .sum();
+        }
+    }
+
+    let mut samples = [0; 64];
+    for y in 0..8 {
+        for x in 0..8 {
+            let value: f32 = (0..8).map(|v| cosines[y][v] * rows[v * 8 + x]).sum();
+            samples[y * 8 + x] = (value + 128.0).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    samples
+}
+
+fn to_rgb(frame: &Frame) -> ImageBuffer<RGB<u8>> {
+    let sample = |component: &Component, x: usize, y: usize| {
+        let x = x * component.horizontal / frame.max_horizontal;
+        let y = y * component.vertical / frame.max_vertical;
+        component.samples[y * component.stride + x]
+    };
+
+    let mut image = ImageBuffer::new(frame.size, RGB::new(0, 0, 0));
+    for y in 0..frame.size.y {
+        for x in 0..frame.size.x {
+            *image.get_mut(Point2::new(x, y)) = match &frame.components[..] {
+                [luma, cb, cr] => RGB::from(YCbCr::new(
+                    sample(luma, x, y),
+                    sample(cb, x, y),
+                    sample(cr, x, y),
+                )),
+                components => {
+                    let gray = sample(&components[0], x, y);
+                    RGB::new(gray, gray, gray)
+                }
+            };
+        }
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Image;
+
+    // A grayscale file of two blocks side by side, whose samples are stored without
+    // quantization. The first coefficient of a block has a code for each of the sizes 0, 7 and 10,
+    // the others only the code for the end of the block.
+    fn jpeg(restart_interval: Option<u8>, scan: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xff, 0xd8, 0xff, 0xdb, 0, 67, 0];
+        data.extend_from_slice(&[1; 64]);
+        data.extend_from_slice(&[0xff, 0xc0, 0, 11, 8, 0, 8, 0, 16, 1, 1, 0x11, 0]);
+        data.extend_from_slice(&[0xff, 0xc4, 0, 22, 0x00, 0, 3, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 7, 10]);
+        data.extend_from_slice(&[0xff, 0xc4, 0, 20, 0x10, 1, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        if let Some(interval) = restart_interval {
+            data.extend_from_slice(&[0xff, 0xdd, 0, 4, 0, interval]);
+        }
+        data.extend_from_slice(&[0xff, 0xda, 0, 8, 1, 1, 0x00, 0, 63, 0]);
+        data.extend_from_slice(scan);
+        data.extend_from_slice(&[0xff, 0xd9]);
+        data
+    }
+
+    fn assert_blocks(image: &ImageBuffer<RGB<u8>>, left: u8, right: u8) {
+        assert_eq!(image.size(), Vector2::new(16, 8));
+        for y in 0..8 {
+            for x in 0..16 {
+                let value = if x < 8 { left } else { right };
+                assert_eq!(image.get(Point2::new(x, y)), RGB::new(value, value, value));
+            }
+        }
+    }
+
+    #[test]
+    fn decode_predicted_blocks() {
+        // The first block is 576, an average of 200, the second 640 less.
+        let image = decode(&jpeg(None, &[0xa4, 0x04, 0xbf, 0xbf])).unwrap();
+
+        assert_blocks(&image, 200, 120);
+    }
+
+    #[test]
+    fn decode_restart_markers() {
+        // The prediction starts at 0 again after the marker, so the second block is -64.
+        let image = decode(&jpeg(Some(1), &[0xa4, 0x07, 0xff, 0xd0, 0x5f, 0xbf])).unwrap();
+
+        assert_blocks(&image, 200, 120);
+    }
+
+    #[test]
+    fn decode_rejects_unsupported_files() {
+        assert_eq!(
+            decode(b"\x89PNG").err(),
+            Some(DecodingError::MissingSignature)
+        );
+
+        let mut data = jpeg(None, &[0xa4, 0x04, 0xbf, 0xbf]);
+        let frame = data.iter().position(|&byte| byte == 0xc0).unwrap();
+        data[frame] = 0xc2;
+        assert_eq!(
+            decode(&data).err(),
+            Some(DecodingError::UnsupportedFormat(String::from(
+                "progressive"
+            )))
+        );
+
+        let data = jpeg(None, &[0xa4, 0x04, 0xbf, 0xbf]);
+        assert_eq!(
+            decode(&data[..data.len() - 6]).err(),
+            Some(DecodingError::UnexpectedEndOfData)
+        );
+    }
+
+    #[test]
+    fn decode_rejects_hostile_frames() {
+        // A frame of the largest size with four times four samples per pixel, in a few bytes.
+        let mut data = jpeg(None, &[0xa4, 0x04, 0xbf, 0xbf]);
+        let frame = data.windows(2).position(|w| w == [0xff, 0xc0]).unwrap();
+        data.splice(
+            frame + 5..frame + 13,
+            [0xff, 0xff, 0xff, 0xff, 1, 1, 0x44, 0],
+        );
+        assert!(data.len() < 200);
+        assert_eq!(
+            decode(&data).err(),
+            Some(DecodingError::UnexpectedEndOfData)
+        );
+
+        // Enough data for the blocks is not enough, if the frame has too many samples.
+        data.splice(
+            frame + 5..frame + 13,
+            [0x40, 0x10, 0x40, 0x10, 1, 1, 0x11, 0],
+        );
+        data.splice(data.len() - 2..data.len() - 2, vec![0; 1 << 21]);
+        assert_eq!(
+            decode(&data).err(),
+            Some(DecodingError::UnsupportedFormat(String::from(
+                "16400 x 16400 pixels"
+            )))
+        );
+    }
+
+    #[test]
+    fn decode_rejects_hostile_predictions() {
+        // Two first coefficients of the largest size, which add up beyond the samples, quantized
+        // by the largest factor of a table of 16 bits.
+        let mut data = jpeg(None, &[0xbf, 0xff, 0, 0xd7, 0xff, 0, 0xfb]);
+        let symbols = data.windows(3).position(|w| w == [0, 7, 10]).unwrap();
+        data[symbols + 2] = 16;
+        let mut table = vec![0xff, 0xdb, 0, 131, 0x10, 0xff, 0xff];
+        table.extend([0, 1].repeat(63));
+        data.splice(2..71, table);
+
+        assert_eq!(decode(&data).err(), Some(DecodingError::InvalidCoefficient));
+    }
+
+    #[test]
+    fn extend_sizes_to_values() {
+        assert_eq!(extend(0, 0), 0);
+        assert_eq!(extend(0, 1), -1);
+        assert_eq!(extend(1, 1), 1);
+        assert_eq!(extend(383, 10), -640);
+        assert_eq!(extend(576, 10), 576);
+    }
+}
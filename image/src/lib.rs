@@ -1,19 +1,34 @@
 use colors::Color;
-use math::{Point, Vector};
+use math::{Point, Point2, Vector};
 
-use std::ops::Deref;
+use std::io;
+use std::ops::{Deref, Range};
 
+pub mod accumulation_buffer;
+pub mod anaglyph;
 pub mod analyzer;
+pub mod comparison;
 pub mod converter;
+pub mod exr;
 pub mod farbfeld;
+pub mod filter;
 pub mod generator;
+pub mod hdr;
+mod huffman;
 pub mod image_buffer;
+pub mod jpeg;
+pub mod pfm;
+pub mod png;
+pub mod ppm;
 pub mod repeater;
 pub mod sampler;
+pub mod texture;
+pub mod tiled_buffer;
+mod zlib;
 
 pub use image_buffer::ImageBuffer;
 
-pub trait Image {
+pub trait Image: Send + Sync {
     type ColorType: Color;
     type PointType: Point;
 
@@ -34,10 +49,46 @@ impl<C: Color, P: Point> Image for Box<dyn Image<ColorType = C, PointType = P>>
     }
 }
 
+impl<T: Image> Image for &T {
+    type ColorType = T::ColorType;
+    type PointType = T::PointType;
+
+    fn size(&self) -> <<Self as Image>::PointType as Point>::VectorType {
+        (*self).size()
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        (*self).get(p)
+    }
+}
+
 pub trait WritableImage: Image {
     fn get_mut(&mut self, p: Self::PointType) -> &mut Self::ColorType;
 }
 
+// Writes an image row by row from the top, e.g. the rows of an image that are already rendered,
+// so the encoded file is never held in memory as a whole.
+pub trait ScanlineEncoder {
+    type ColorType: Color;
+
+    fn write_scanline(&mut self, scanline: &[Self::ColorType]) -> io::Result<()>;
+
+    fn write_rows(
+        &mut self,
+        image: &impl Image<ColorType = Self::ColorType, PointType = Point2<usize>>,
+        rows: Range<usize>,
+    ) -> io::Result<()> {
+        let width = image.size().x;
+        let mut scanline = Vec::with_capacity(width);
+        for y in rows {
+            scanline.clear();
+            scanline.extend((0..width).map(|x| image.get(Point2::new(x, y))));
+            self.write_scanline(&scanline)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct SingleColorImage<C: Color, S: Vector> {
     color: C,
@@ -50,7 +101,7 @@ impl<C: Color, S: Vector> SingleColorImage<C, S> {
     }
 }
 
-impl<C: Color, S: Vector + Copy + Clone> Image for SingleColorImage<C, S>
+impl<C: Color, S: Vector + Copy + Clone + Send + Sync> Image for SingleColorImage<C, S>
 where
     <S as Vector>::PointType: Point<VectorType = S>,
 {
@@ -0,0 +1,83 @@
+use crate::Image;
+
+use colors::RGB;
+use math::Point2;
+
+// Colors that are written to PFM files, which store each channel as a 32 bit float.
+pub trait PfmColor {
+    fn channels(&self) -> [f32; 3];
+}
+
+impl PfmColor for RGB<f32> {
+    fn channels(&self) -> [f32; 3] {
+        [self.red, self.green, self.blue]
+    }
+}
+
+impl PfmColor for RGB<f64> {
+    fn channels(&self) -> [f32; 3] {
+        [self.red as f32, self.green as f32, self.blue as f32]
+    }
+}
+
+// Encodes an image as a PFM file, which keeps the colors as floats without any clamping. The
+// negative scale in the header marks the floats as little endian, and the rows are stored from
+// the bottom to the top.
+pub trait Encoder {
+    fn encode_pfm(&self) -> Vec<u8>;
+}
+
+impl<T> Encoder for T
+where
+    T: Image<PointType = Point2<usize>>,
+    T::ColorType: PfmColor,
+{
+    fn encode_pfm(&self) -> Vec<u8> {
+        let size = self.size();
+        let header = format!("PF\n{} {}\n-1.0\n", size.x, size.y);
+
+        let mut result = Vec::with_capacity(header.len() + size.x * size.y * 12);
+        result.extend_from_slice(header.as_bytes());
+
+        for y in (0..size.y).rev() {
+            for x in 0..size.x {
+                for channel in self.get(Point2::new(x, y)).channels() {
+                    result.extend_from_slice(&channel.to_le_bytes());
+                }
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ImageBuffer, WritableImage};
+    use math::Vector2;
+
+    macro_rules! encode_pfm {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut image = ImageBuffer::new(Vector2::new(1, 2), RGB::<$type>::default());
+                *image.get_mut(Point2::new(0, 0)) = RGB::new(2.5, -1.0, 0.0);
+
+                let data = image.encode_pfm();
+                let header = b"PF\n1 2\n-1.0\n";
+                assert_eq!(data[..header.len()], *header);
+
+                let channels: Vec<f32> = data[header.len()..]
+                    .chunks(4)
+                    .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+                    .collect();
+                assert_eq!(channels, vec![0.0, 0.0, 0.0, 2.5, -1.0, 0.0]);
+            }
+        };
+    }
+
+    encode_pfm! { f32, encode_pfm_f32 }
+    encode_pfm! { f64, encode_pfm_f64 }
+}
@@ -0,0 +1,626 @@
+use crate::{zlib, Image, ImageBuffer, WritableImage};
+
+use colors::{RGB, RGBA};
+use math::{Point2, Vector2};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// The first pixel and the distance between the pixels of the seven passes of interlaced images,
+// in x and y.
+const ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum DecodingError {
+    MissingSignature,
+    UnexpectedEndOfData,
+    InvalidChecksum,
+    InvalidCompressedData,
+    InvalidFilter(u8),
+    InvalidPaletteIndex,
+    MissingHeader,
+    MissingPalette,
+    UnsupportedFormat(String),
+}
+
+// The colors that are stored in PNG files, with 8 or 16 bits per channel and with or without
+// alpha.
+pub trait PngColor {
+    const COLOR_TYPE: u8;
+    const BIT_DEPTH: u8;
+    const BYTES_PER_PIXEL: usize;
+
+    fn write_channels(&self, data: &mut Vec<u8>);
+}
+
+macro_rules! implement_png_color {
+    ($color: ident, $type: ty, $color_type: expr, [$($channel: ident)+]) => {
+        impl PngColor for $color<$type> {
+            const COLOR_TYPE: u8 = $color_type;
+            const BIT_DEPTH: u8 = <$type>::BITS as u8;
+            const BYTES_PER_PIXEL: usize = [$(stringify!($channel)),+].len() * size_of::<$type>();
+
+            fn write_channels(&self, data: &mut Vec<u8>) {
+                $(data.extend_from_slice(&self.$channel.to_be_bytes());)+
+            }
+        }
+    };
+}
+
+implement_png_color! { RGB, u8, 2, [red green blue] }
+implement_png_color! { RGB, u16, 2, [red green blue] }
+implement_png_color! { RGBA, u8, 6, [red green blue alpha] }
+implement_png_color! { RGBA, u16, 6, [red green blue alpha] }
+
+pub trait Encoder {
+    fn encode_png(&self) -> Vec<u8>;
+}
+
+impl<T> Encoder for T
+where
+    T: Image<PointType = Point2<usize>>,
+    T::ColorType: PngColor,
+{
+    fn encode_png(&self) -> Vec<u8> {
+        let size = self.size();
+        let bytes_per_pixel = <T::ColorType as PngColor>::BYTES_PER_PIXEL;
+        let row_length = size.x * bytes_per_pixel;
+
+        // Every row starts with the filter that predicts its bytes from the bytes before.
+        let mut filtered = Vec::with_capacity((row_length + 1) * size.y);
+        let mut previous = vec![0; row_length];
+        let mut row = Vec::with_capacity(row_length);
+        for y in 0..size.y {
+            row.clear();
+            for x in 0..size.x {
+                self.get(Point2::new(x, y)).write_channels(&mut row);
+            }
+            filter_row(&row, &previous, bytes_per_pixel, &mut filtered);
+            std::mem::swap(&mut row, &mut previous);
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(size.x as u32).to_be_bytes());
+        header.extend_from_slice(&(size.y as u32).to_be_bytes());
+        header.extend_from_slice(&[
+            <T::ColorType as PngColor>::BIT_DEPTH,
+            <T::ColorType as PngColor>::COLOR_TYPE,
+            0,
+            0,
+            0,
+        ]);
+
+        let mut result = SIGNATURE.to_vec();
+        write_chunk(&mut result, b"IHDR", &header);
+        write_chunk(&mut result, b"IDAT", &zlib::compress(&filtered));
+        write_chunk(&mut result, b"IEND", &[]);
+        result
+    }
+}
+
+// The header of a PNG file.
+struct Header {
+    size: Vector2<usize>,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    // The offsets and the steps of the pixels of each pass.
+    fn passes(&self) -> &'static [(usize, usize, usize, usize)] {
+        if self.interlaced {
+            &ADAM7_PASSES[..]
+        } else {
+            &[(0, 0, 1, 1)][..]
+        }
+    }
+
+    // The length of the decompressed pixels, each row of each pass starts with its filter.
+    fn filtered_length(&self) -> usize {
+        self.passes()
+            .iter()
+            .map(|&(x0, y0, dx, dy)| {
+                let width = self.size.x.saturating_sub(x0).div_ceil(dx);
+                let height = self.size.y.saturating_sub(y0).div_ceil(dy);
+                if width == 0 {
+                    return 0;
+                }
+                let row_length = width.saturating_mul(self.bits_per_pixel()).div_ceil(8);
+                height.saturating_mul(row_length.saturating_add(1))
+            })
+            .fold(0, usize::saturating_add)
+    }
+}
+
+// Decodes a PNG file of any color type and bit depth, interlaced or not. The colors are stored as
+// they are, 8 bits and less per channel are scaled to 16 bits. Chunks that do not describe the
+// pixels, e.g. the gamma, are ignored.
+pub fn decode(data: &[u8]) -> Result<ImageBuffer<RGBA<u16>>, DecodingError> {
+    if !data.starts_with(&SIGNATURE) {
+        return Err(DecodingError::MissingSignature);
+    }
+
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut compressed = Vec::new();
+    let mut position = SIGNATURE.len();
+    loop {
+        let length = data
+            .get(position..position + 4)
+            .ok_or(DecodingError::UnexpectedEndOfData)?;
+        let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+        let chunk = data
+            .get(position + 4..position + 12 + length)
+            .ok_or(DecodingError::UnexpectedEndOfData)?;
+        position += 12 + length;
+
+        let (kind, rest) = chunk.split_at(4);
+        let (content, crc) = rest.split_at(length);
+        if crc32(&chunk[..4 + length]).to_be_bytes() != crc {
+            return Err(DecodingError::InvalidChecksum);
+        }
+
+        match kind {
+            b"IHDR" => header = Some(read_header(content)?),
+            b"PLTE" => {
+                palette = content
+                    .chunks_exact(3)
+                    .map(|c| {
+                        RGBA::new(
+                            c[0] as u16 * 257,
+                            c[1] as u16 * 257,
+                            c[2] as u16 * 257,
+                            u16::MAX,
+                        )
+                    })
+                    .collect();
+            }
+            // The transparency of the colors of the palette.
+            b"tRNS" => {
+                for (color, &alpha) in palette.iter_mut().zip(content) {
+                    color.alpha = alpha as u16 * 257;
+                }
+            }
+            b"IDAT" => compressed.extend_from_slice(content),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+
+    let header = header.ok_or(DecodingError::MissingHeader)?;
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(DecodingError::MissingPalette);
+    }
+    let filtered = zlib::decompress(&compressed, header.filtered_length())
+        .ok_or(DecodingError::InvalidCompressedData)?;
+    // The pixels need at least as many bytes, which is checked before the image is allocated.
+    let pixels = header.size.x.saturating_mul(header.size.y);
+    if pixels.saturating_mul(header.bits_per_pixel()) / 8 > filtered.len() {
+        return Err(DecodingError::UnexpectedEndOfData);
+    }
+
+    let mut image = ImageBuffer::new(header.size, RGBA::new(0, 0, 0, u16::MAX));
+    let mut rows = filtered.as_slice();
+    for &(x0, y0, dx, dy) in header.passes() {
+        let width = header.size.x.saturating_sub(x0).div_ceil(dx);
+        let height = header.size.y.saturating_sub(y0).div_ceil(dy);
+        if width == 0 || height == 0 {
+            continue;
+        }
+
+        let row_length = (width * header.bits_per_pixel()).div_ceil(8);
+        let bytes_per_pixel = header.bits_per_pixel().div_ceil(8);
+        let mut previous = vec![0; row_length];
+        for y in 0..height {
+            if rows.len() < row_length + 1 {
+                return Err(DecodingError::UnexpectedEndOfData);
+            }
+            let (row, rest) = rows.split_at(row_length + 1);
+            rows = rest;
+            let row = unfilter_row(row[0], &row[1..], &previous, bytes_per_pixel)?;
+            for x in 0..width {
+                let color = read_pixel(&row, x, &header, &palette)?;
+                *image.get_mut(Point2::new(x0 + x * dx, y0 + y * dy)) = color;
+            }
+            previous = row;
+        }
+    }
+
+    Ok(image)
+}
+
+fn read_header(content: &[u8]) -> Result<Header, DecodingError> {
+    if content.len() != 13 {
+        return Err(DecodingError::UnexpectedEndOfData);
+    }
+    let width = u32::from_be_bytes([content[0], content[1], content[2], content[3]]);
+    let height = u32::from_be_bytes([content[4], content[5], content[6], content[7]]);
+    let (bit_depth, color_type) = (content[8], content[9]);
+
+    let bit_depths: &[u8] = match color_type {
+        0 => &[1, 2, 4, 8, 16],
+        3 => &[1, 2, 4, 8],
+        2 | 4 | 6 => &[8, 16],
+        _ => &[],
+    };
+    if !bit_depths.contains(&bit_depth) {
+        return Err(DecodingError::UnsupportedFormat(format!(
+            "color type {} with {} bits",
+            color_type, bit_depth
+        )));
+    }
+    if content[10] != 0 || content[11] != 0 || content[12] > 1 {
+        return Err(DecodingError::UnsupportedFormat(String::from(
+            "compression, filter or interlace method",
+        )));
+    }
+
+    Ok(Header {
+        size: Vector2::new(width as usize, height as usize),
+        bit_depth,
+        color_type,
+        interlaced: content[12] == 1,
+    })
+}
+
+// Reverses the filter of a row, which predicts each byte from the bytes before.
+fn unfilter_row(
+    filter: u8,
+    row: &[u8],
+    previous: &[u8],
+    bytes_per_pixel: usize,
+) -> Result<Vec<u8>, DecodingError> {
+    if filter > 4 {
+        return Err(DecodingError::InvalidFilter(filter));
+    }
+
+    let mut result: Vec<u8> = Vec::with_capacity(row.len());
+    for (i, &byte) in row.iter().enumerate() {
+        let left = if i >= bytes_per_pixel {
+            result[i - bytes_per_pixel]
+        } else {
+            0
+        };
+        let up = previous[i];
+        let up_left = if i >= bytes_per_pixel {
+            previous[i - bytes_per_pixel]
+        } else {
+            0
+        };
+        let prediction = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            _ => paeth(left, up, up_left),
+        };
+        result.push(byte.wrapping_add(prediction));
+    }
+    Ok(result)
+}
+
+fn read_pixel(
+    row: &[u8],
+    x: usize,
+    header: &Header,
+    palette: &[RGBA<u16>],
+) -> Result<RGBA<u16>, DecodingError> {
+    let depth = header.bit_depth as usize;
+    let sample = |index: usize| -> u16 {
+        match depth {
+            16 => u16::from_be_bytes([row[2 * index], row[2 * index + 1]]),
+            8 => row[index] as u16,
+            _ => {
+                let bit = index * depth;
+                (row[bit / 8] >> (8 - depth - bit % 8)) as u16 & ((1 << depth) - 1)
+            }
+        }
+    };
+    // Scales the samples to 16 bits, so the largest value stays the largest.
+    let scaled = |index: usize| -> u16 {
+        match depth {
+            16 => sample(index),
+            _ => sample(index) * (u16::MAX / ((1 << depth) - 1)),
+        }
+    };
+
+    let first = x * header.channels();
+    Ok(match header.color_type {
+        0 => {
+            let gray = scaled(first);
+            RGBA::new(gray, gray, gray, u16::MAX)
+        }
+        2 => RGBA::new(
+            scaled(first),
+            scaled(first + 1),
+            scaled(first + 2),
+            u16::MAX,
+        ),
+        3 => *palette
+            .get(sample(first) as usize)
+            .ok_or(DecodingError::InvalidPaletteIndex)?,
+        4 => {
+            let gray = scaled(first);
+            RGBA::new(gray, gray, gray, scaled(first + 1))
+        }
+        _ => RGBA::new(
+            scaled(first),
+            scaled(first + 1),
+            scaled(first + 2),
+            scaled(first + 3),
+        ),
+    })
+}
+
+// Filters a row with each of the five filters and keeps the one whose bytes are the smallest,
+// taken as signed numbers, which usually compresses best.
+fn filter_row(row: &[u8], previous: &[u8], bytes_per_pixel: usize, filtered: &mut Vec<u8>) {
+    let mut best: Option<(u64, u8, Vec<u8>)> = None;
+    for filter in 0..5 {
+        let bytes: Vec<u8> = (0..row.len())
+            .map(|i| {
+                let left = if i >= bytes_per_pixel {
+                    row[i - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let up = previous[i];
+                let up_left = if i >= bytes_per_pixel {
+                    previous[i - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let prediction = match filter {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((left as u16 + up as u16) / 2) as u8,
+                    _ => paeth(left, up, up_left),
+                };
+                row[i].wrapping_sub(prediction)
+            })
+            .collect();
+        let cost = bytes.iter().map(|&b| (b as i8).unsigned_abs() as u64).sum();
+        if best
+            .as_ref()
+            .is_none_or(|(best_cost, _, _)| cost < *best_cost)
+        {
+            best = Some((cost, filter, bytes));
+        }
+    }
+
+    let (_, filter, bytes) = best.unwrap();
+    filtered.push(filter);
+    filtered.extend_from_slice(&bytes);
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let distance_left = (estimate - left as i16).abs();
+    let distance_up = (estimate - up as i16).abs();
+    let distance_up_left = (estimate - up_left as i16).abs();
+    if distance_left <= distance_up && distance_left <= distance_up_left {
+        left
+    } else if distance_up <= distance_up_left {
+        up
+    } else {
+        up_left
+    }
+}
+
+fn write_chunk(result: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    result.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = result.len();
+    result.extend_from_slice(kind);
+    result.extend_from_slice(data);
+    let crc = crc32(&result[start..]);
+    result.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ImageBuffer;
+    use math::Vector2;
+
+    #[test]
+    fn crc32_of_text() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+    }
+
+    #[test]
+    fn paeth_predicts_the_closest_neighbor() {
+        assert_eq!(paeth(10, 20, 10), 20);
+        assert_eq!(paeth(20, 10, 10), 20);
+        assert_eq!(paeth(10, 10, 20), 10);
+        assert_eq!(paeth(0, 0, 0), 0);
+    }
+
+    macro_rules! encode_png_header {
+        ($color: ident, $type: ty, $depth: expr, $color_type: expr, $name: ident) => {
+            #[test]
+            fn $name() {
+                let image = ImageBuffer::new(Vector2::new(3, 2), $color::<$type>::default());
+                let data = image.encode_png();
+
+                assert_eq!(data[..8], SIGNATURE);
+                assert_eq!(data[8..16], [0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+                assert_eq!(data[16..24], [0, 0, 0, 3, 0, 0, 0, 2]);
+                assert_eq!(data[24..29], [$depth, $color_type, 0, 0, 0]);
+                assert_eq!(data[29..33], crc32(&data[12..29]).to_be_bytes());
+                assert_eq!(data[37..41], *b"IDAT");
+                assert_eq!(
+                    data[data.len() - 12..],
+                    [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]
+                );
+            }
+        };
+    }
+
+    encode_png_header! { RGB, u8, 8, 2, encode_png_header_rgb_u8 }
+    encode_png_header! { RGB, u16, 16, 2, encode_png_header_rgb_u16 }
+    encode_png_header! { RGBA, u8, 8, 6, encode_png_header_rgba_u8 }
+    encode_png_header! { RGBA, u16, 16, 6, encode_png_header_rgba_u16 }
+
+    // A PNG file of the chunks of a header, the rows without filters and further chunks.
+    fn png(header: [u8; 13], rows: &[u8], chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut data = SIGNATURE.to_vec();
+        write_chunk(&mut data, b"IHDR", &header);
+        for (kind, content) in chunks {
+            write_chunk(&mut data, kind, content);
+        }
+        write_chunk(&mut data, b"IDAT", &zlib::compress(rows));
+        write_chunk(&mut data, b"IEND", &[]);
+        data
+    }
+
+    macro_rules! encode_and_decode_png {
+        ($color: ident, $type: ty, $alpha: expr, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut image = ImageBuffer::new(Vector2::new(13, 7), $color::<$type>::default());
+                for y in 0..7 {
+                    for x in 0..13 {
+                        let value = (x * 31 + y * 17) as $type;
+                        let color = image.get_mut(Point2::new(x, y));
+                        color.red = value;
+                        color.blue = <$type>::MAX - value;
+                    }
+                }
+
+                let decoded = decode(&image.encode_png()).unwrap();
+
+                // Channels of 8 bits are scaled to 16 bits.
+                let scale = u16::MAX / <$type>::MAX as u16;
+                assert_eq!(decoded.size(), image.size());
+                for y in 0..7 {
+                    for x in 0..13 {
+                        let p = Point2::new(x, y);
+                        let expected = image.get(p);
+                        assert_eq!(
+                            decoded.get(p),
+                            RGBA::new(
+                                expected.red as u16 * scale,
+                                0,
+                                expected.blue as u16 * scale,
+                                $alpha
+                            )
+                        );
+                    }
+                }
+            }
+        };
+    }
+
+    encode_and_decode_png! { RGB, u8, u16::MAX, encode_and_decode_png_rgb_u8 }
+    encode_and_decode_png! { RGB, u16, u16::MAX, encode_and_decode_png_rgb_u16 }
+    encode_and_decode_png! { RGBA, u8, 0, encode_and_decode_png_rgba_u8 }
+    encode_and_decode_png! { RGBA, u16, 0, encode_and_decode_png_rgba_u16 }
+
+    #[test]
+    fn decode_palette_with_transparency() {
+        // Two pixels of two bits in each row.
+        let header = [0, 0, 0, 2, 0, 0, 0, 2, 2, 3, 0, 0, 0];
+        let rows = [0, 0b0001_0000, 0, 0b1000_0000];
+        let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255];
+        let data = png(header, &rows, &[(b"PLTE", &palette), (b"tRNS", &[128])]);
+
+        let image = decode(&data).unwrap();
+
+        assert_eq!(
+            image.get(Point2::new(0, 0)),
+            RGBA::new(65535, 0, 0, 128 * 257)
+        );
+        assert_eq!(image.get(Point2::new(1, 0)), RGBA::new(0, 65535, 0, 65535));
+        assert_eq!(image.get(Point2::new(0, 1)), RGBA::new(0, 0, 65535, 65535));
+        assert_eq!(
+            image.get(Point2::new(1, 1)),
+            RGBA::new(65535, 0, 0, 128 * 257)
+        );
+    }
+
+    #[test]
+    fn decode_interlaced_gray() {
+        // Of the seven passes of a 2x2 image, the first has the top left pixel, the sixth the
+        // top right one, and the seventh the bottom row.
+        let header = [0, 0, 0, 2, 0, 0, 0, 2, 8, 0, 0, 0, 1];
+        let rows = [0, 10, 0, 20, 0, 30, 40];
+        let image = decode(&png(header, &rows, &[])).unwrap();
+
+        let gray = |value: u16| RGBA::new(value * 257, value * 257, value * 257, 65535);
+        assert_eq!(image.get(Point2::new(0, 0)), gray(10));
+        assert_eq!(image.get(Point2::new(1, 0)), gray(20));
+        assert_eq!(image.get(Point2::new(0, 1)), gray(30));
+        assert_eq!(image.get(Point2::new(1, 1)), gray(40));
+    }
+
+    #[test]
+    fn decode_rejects_invalid_data() {
+        let image = ImageBuffer::new(Vector2::new(2, 2), RGB::<u8>::default());
+        let mut data = image.encode_png();
+
+        assert_eq!(
+            decode(&data[..data.len() - 12]),
+            Err(DecodingError::UnexpectedEndOfData)
+        );
+        data[20] ^= 1;
+        assert_eq!(decode(&data), Err(DecodingError::InvalidChecksum));
+        assert_eq!(decode(b"GIF89a"), Err(DecodingError::MissingSignature));
+
+        let header = [0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0];
+        assert_eq!(
+            decode(&png(header, &[5, 1, 2, 3], &[])),
+            Err(DecodingError::InvalidFilter(5))
+        );
+        let header = [0, 0, 0, 1, 0, 0, 0, 1, 4, 2, 0, 0, 0];
+        assert!(matches!(
+            decode(&png(header, &[0, 0], &[])),
+            Err(DecodingError::UnsupportedFormat(_))
+        ));
+
+        // The pixels are not decompressed beyond the size of the image.
+        let header = [0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0];
+        assert_eq!(
+            decode(&png(header, &[0; 100000], &[])),
+            Err(DecodingError::InvalidCompressedData)
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::io::{self, Write};
+
+use crate::{Image, ScanlineEncoder};
+
+use colors::RGB;
+use math::{Point2, Vector2};
+
+// Encodes an image as a binary PPM file with 8 bits per channel, which almost every image viewer
+// reads and whose header is plain text.
+pub trait Encoder {
+    fn encode_ppm(&self) -> Vec<u8>;
+}
+
+impl<T: Image<PointType = Point2<usize>, ColorType = RGB<u8>>> Encoder for T {
+    fn encode_ppm(&self) -> Vec<u8> {
+        let size = self.size();
+        let data = Vec::with_capacity(20 + size.x * size.y * 3);
+
+        // Writing to memory does not fail.
+        let mut writer = Writer::new(data, size).unwrap();
+        writer.write_rows(self, 0..size.y).unwrap();
+        writer.finish().unwrap()
+    }
+}
+
+// Writes a PPM file row by row.
+pub struct Writer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(mut writer: W, size: Vector2<usize>) -> io::Result<Writer<W>> {
+        write!(writer, "P6\n{} {}\n255\n", size.x, size.y)?;
+        Ok(Writer { writer })
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> ScanlineEncoder for Writer<W> {
+    type ColorType = RGB<u8>;
+
+    fn write_scanline(&mut self, scanline: &[RGB<u8>]) -> io::Result<()> {
+        let data: Vec<u8> = scanline
+            .iter()
+            .flat_map(|color| [color.red, color.green, color.blue])
+            .collect();
+        self.writer.write_all(&data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ImageBuffer, WritableImage};
+    use math::Vector2;
+
+    #[test]
+    fn encode_ppm() {
+        let mut image = ImageBuffer::new(Vector2::new(2, 1), RGB::new(0, 0, 0));
+        *image.get_mut(Point2::new(1, 0)) = RGB::new(255, 128, 1);
+
+        assert_eq!(
+            image.encode_ppm(),
+            b"P6\n2 1\n255\n\0\0\0\xff\x80\x01".to_vec()
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use crate::Image;
+
+use colors::Color;
+use math::{Point2, Vector2};
+use sampling::split;
+use traits::{ConvenientNumber, FloatingPoint, One, Zero};
+
+// Stretches an image over the unit square, so it can be used like the procedural textures, e.g.
+// on a material or as the gobo of a spot light. v grows upwards, the first row of the image is at
+// the top. Colors are interpolated bilinearly between the centers of the pixels, coordinates
+// outside of the unit square get the color of the closest edge.
+pub struct ImageTexture<I> {
+    source: I,
+}
+
+impl<I> ImageTexture<I> {
+    pub fn new(source: I) -> ImageTexture<I> {
+        ImageTexture { source }
+    }
+}
+
+impl<I, V> Image for ImageTexture<I>
+where
+    I: Image<PointType = Point2<usize>>,
+    I::ColorType: Color<ChannelType = V>,
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    type ColorType = I::ColorType;
+    type PointType = Point2<V>;
+
+    fn size(&self) -> Vector2<V> {
+        Vector2::new(One::one(), One::one())
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        let size = self.source.size();
+        let one = V::one();
+
+        let cell = |coordinate: V, cells: usize| {
+            let centered = coordinate - (one / to_value(cells)).half();
+            let (lower, f) = split(centered.clamp(Zero::zero(), one), cells);
+            (lower, (lower + 1).min(cells - 1), f)
+        };
+        let (x0, x1, fx) = cell(p.x, size.x);
+        let (y0, y1, fy) = cell(one - p.y, size.y);
+
+        let top = self.source.get(Point2::new(x0, y0)) * (one - fx)
+            + self.source.get(Point2::new(x1, y0)) * fx;
+        let bottom = self.source.get(Point2::new(x0, y1)) * (one - fx)
+            + self.source.get(Point2::new(x1, y1)) * fx;
+
+        top * (one - fy) + bottom * fy
+    }
+}
+
+fn to_value<V>(value: usize) -> V
+where
+    u16: Into<V>,
+{
+    (value as u16).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ImageBuffer, WritableImage};
+    use colors::RGB;
+
+    macro_rules! image_texture_interpolates_pixels {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut image = ImageBuffer::new(Vector2::new(2, 2), RGB::<$type>::default());
+                *image.get_mut(Point2::new(0, 0)) = RGB::new(1.0, 0.0, 0.0);
+                *image.get_mut(Point2::new(1, 0)) = RGB::new(0.0, 1.0, 0.0);
+                *image.get_mut(Point2::new(0, 1)) = RGB::new(0.0, 0.0, 1.0);
+                *image.get_mut(Point2::new(1, 1)) = RGB::new(1.0, 1.0, 1.0);
+
+                let texture = ImageTexture::new(image);
+
+                assert_eq!(texture.size(), Vector2::new(1.0, 1.0));
+                assert_eq!(
+                    texture.get(Point2::new(0.25, 0.75)),
+                    RGB::new(1.0, 0.0, 0.0)
+                );
+                assert_eq!(
+                    texture.get(Point2::new(0.75, 0.25)),
+                    RGB::new(1.0, 1.0, 1.0)
+                );
+                assert_eq!(texture.get(Point2::new(0.0, 1.0)), RGB::new(1.0, 0.0, 0.0));
+                assert_eq!(texture.get(Point2::new(-3.0, 0.0)), RGB::new(0.0, 0.0, 1.0));
+                assert_eq!(texture.get(Point2::new(0.5, 0.75)), RGB::new(0.5, 0.5, 0.0));
+                assert_eq!(texture.get(Point2::new(0.5, 0.5)), RGB::new(0.5, 0.5, 0.5));
+            }
+        };
+    }
+
+    image_texture_interpolates_pixels! { f32, image_texture_interpolates_pixels_f32 }
+    image_texture_interpolates_pixels! { f64, image_texture_interpolates_pixels_f64 }
+}
@@ -0,0 +1,268 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::{Image, WritableImage};
+use colors::{Color, Gray, RGB, RGBA};
+use math::{Point, Point2, Vector2};
+use traits::F16;
+
+// The colors that tiles are stored with in the backing file of a tiled buffer.
+pub trait TileColor: Color {
+    const SIZE: usize;
+
+    fn write(&self, data: &mut Vec<u8>);
+    fn read(data: &[u8]) -> Self;
+}
+
+macro_rules! implement_tile_color {
+    ($color: ident, $type: ty, [$($channel: ident)+]) => {
+        impl TileColor for $color<$type> {
+            const SIZE: usize = [$(stringify!($channel)),+].len() * size_of::<$type>();
+
+            fn write(&self, data: &mut Vec<u8>) {
+                $(data.extend_from_slice(&self.$channel.to_le_bytes());)+
+            }
+
+            fn read(data: &[u8]) -> Self {
+                let mut channels = data.chunks_exact(size_of::<$type>());
+                let mut next = || <$type>::from_le_bytes(channels.next().unwrap().try_into().unwrap());
+                $(let $channel = next();)+
+                $color::new($($channel),+)
+            }
+        }
+    };
+}
+
+implement_tile_color! { Gray, f32, [value] }
+implement_tile_color! { Gray, f64, [value] }
+implement_tile_color! { RGB, f32, [red green blue] }
+implement_tile_color! { RGB, f64, [red green blue] }
+implement_tile_color! { RGBA, f32, [red green blue alpha] }
+implement_tile_color! { RGBA, f64, [red green blue alpha] }
+implement_tile_color! { Gray, F16, [value] }
+implement_tile_color! { RGB, F16, [red green blue] }
+implement_tile_color! { RGBA, F16, [red green blue alpha] }
+
+// An image stored in square tiles, which are only allocated once a pixel of them is accessed. With
+// a backing file, at most a number of tiles stays in memory and the tile loaded first is written to
+// the file for the next one, so huge renders fit into little memory when they are assembled tile
+// by tile. The file is removed together with the buffer.
+pub struct TiledBuffer<C: TileColor> {
+    size: Vector2<usize>,
+    tile_size: usize,
+    tiles: Mutex<Tiles<C>>,
+}
+
+struct Tiles<C> {
+    color: C,
+    tiles: Vec<Option<Vec<C>>>,
+    backing: Option<Backing>,
+}
+
+struct Backing {
+    path: PathBuf,
+    file: File,
+    resident_tiles: usize,
+    // The tiles in memory in the order they were loaded, and the tiles stored in the file.
+    loaded: VecDeque<usize>,
+    stored: Vec<bool>,
+}
+
+impl<C: TileColor> TiledBuffer<C> {
+    pub fn new(size: Vector2<usize>, tile_size: usize, color: C) -> TiledBuffer<C> {
+        assert!(tile_size > 0);
+        let tile_count = size.x.div_ceil(tile_size) * size.y.div_ceil(tile_size);
+        TiledBuffer {
+            size,
+            tile_size,
+            tiles: Mutex::new(Tiles {
+                color,
+                tiles: vec![None; tile_count],
+                backing: None,
+            }),
+        }
+    }
+
+    // Keeps at most the number of tiles in memory and the others in the file, which is created or
+    // truncated.
+    pub fn backed_by(
+        self,
+        path: impl AsRef<Path>,
+        resident_tiles: usize,
+    ) -> io::Result<TiledBuffer<C>> {
+        assert!(resident_tiles > 0);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path.as_ref())?;
+
+        // Tiles that were accessed before are stored right away.
+        let tile_len = self.tile_len();
+        let mut tiles = self.tiles.into_inner().unwrap();
+        let loaded = (0..tiles.tiles.len())
+            .filter(|&index| tiles.tiles[index].is_some())
+            .collect();
+        tiles.backing = Some(Backing {
+            path: path.as_ref().to_path_buf(),
+            file,
+            resident_tiles,
+            loaded,
+            stored: vec![false; tiles.tiles.len()],
+        });
+        tiles.evict(tile_len)?;
+
+        Ok(TiledBuffer {
+            size: self.size,
+            tile_size: self.tile_size,
+            tiles: Mutex::new(tiles),
+        })
+    }
+
+    fn tile_len(&self) -> usize {
+        self.tile_size * self.tile_size
+    }
+
+    // The index of the tile of the pixel and the index of the pixel in the tile.
+    fn locate(&self, p: Point2<usize>) -> (usize, usize) {
+        assert!(p.x < self.size.x && p.y < self.size.y);
+        let tiles_per_row = self.size.x.div_ceil(self.tile_size);
+        let tile = (p.y / self.tile_size) * tiles_per_row + p.x / self.tile_size;
+        let pixel = (p.y % self.tile_size) * self.tile_size + p.x % self.tile_size;
+        (tile, pixel)
+    }
+}
+
+impl<C: TileColor> Tiles<C> {
+    fn load(&mut self, index: usize, tile_len: usize) -> io::Result<&mut Vec<C>> {
+        if self.tiles[index].is_none() {
+            let mut tile = vec![self.color; tile_len];
+            if let Some(backing) = &mut self.backing {
+                if backing.stored[index] {
+                    let mut data = vec![0; tile_len * C::SIZE];
+                    backing
+                        .file
+                        .seek(SeekFrom::Start((index * data.len()) as u64))?;
+                    backing.file.read_exact(&mut data)?;
+                    for (color, data) in tile.iter_mut().zip(data.chunks_exact(C::SIZE)) {
+                        *color = C::read(data);
+                    }
+                }
+                backing.loaded.push_back(index);
+            }
+            self.tiles[index] = Some(tile);
+            self.evict(tile_len)?;
+        }
+        Ok(self.tiles[index].as_mut().unwrap())
+    }
+
+    // Writes the tiles loaded first to the file until no more than allowed are in memory, except
+    // the one loaded last.
+    fn evict(&mut self, tile_len: usize) -> io::Result<()> {
+        let Some(backing) = &mut self.backing else {
+            return Ok(());
+        };
+        while backing.loaded.len() > backing.resident_tiles {
+            let index = backing.loaded.pop_front().unwrap();
+            let tile = self.tiles[index].take().unwrap();
+            let mut data = Vec::with_capacity(tile_len * C::SIZE);
+            for color in &tile {
+                color.write(&mut data);
+            }
+            backing
+                .file
+                .seek(SeekFrom::Start((index * data.len()) as u64))?;
+            backing.file.write_all(&data)?;
+            backing.stored[index] = true;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Backing {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl<C: TileColor> Image for TiledBuffer<C> {
+    type ColorType = C;
+    type PointType = Point2<usize>;
+
+    fn size(&self) -> <Self::PointType as Point>::VectorType {
+        self.size
+    }
+
+    fn get(&self, p: Self::PointType) -> Self::ColorType {
+        let (tile, pixel) = self.locate(p);
+        let mut tiles = self.tiles.lock().unwrap();
+        tiles
+            .load(tile, self.tile_len())
+            .expect("Unable to access the backing file of the tiled buffer.")[pixel]
+    }
+}
+
+impl<C: TileColor> WritableImage for TiledBuffer<C> {
+    fn get_mut(&mut self, p: Self::PointType) -> &mut Self::ColorType {
+        let (tile, pixel) = self.locate(p);
+        let tile_len = self.tile_len();
+        let tiles = self.tiles.get_mut().unwrap();
+        &mut tiles
+            .load(tile, tile_len)
+            .expect("Unable to access the backing file of the tiled buffer.")[pixel]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    macro_rules! tiled_buffer {
+        ($type: ty, $name: ident, $backed_name: ident) => {
+            #[test]
+            fn $name() {
+                let mut image = TiledBuffer::new(Vector2::new(5, 3), 2, RGB::<$type>::default());
+                *image.get_mut(Point2::new(4, 2)) = RGB::new(1.0, 2.0, 3.0);
+
+                assert_eq!(image.size(), Vector2::new(5, 3));
+                assert_eq!(image.get(Point2::new(4, 2)), RGB::new(1.0, 2.0, 3.0));
+                assert_eq!(image.get(Point2::new(3, 2)), RGB::default());
+            }
+
+            #[test]
+            fn $backed_name() {
+                let path = env::temp_dir().join(format!("{}.tiles", stringify!($backed_name)));
+                let mut image = TiledBuffer::new(Vector2::new(5, 3), 2, RGB::<$type>::default());
+                *image.get_mut(Point2::new(0, 0)) = RGB::new(0.5, 0.5, 0.5);
+                let mut image = image.backed_by(&path, 1).unwrap();
+                for y in 0..3 {
+                    for x in 0..5 {
+                        *image.get_mut(Point2::new(x, y)) += RGB::new(x as $type, y as $type, 1.0);
+                    }
+                }
+
+                assert!(path.exists());
+                for y in 0..3 {
+                    for x in 0..5 {
+                        let extra = if x == 0 && y == 0 { 0.5 } else { 0.0 };
+                        assert_eq!(
+                            image.get(Point2::new(x, y)),
+                            RGB::new(x as $type + extra, y as $type + extra, 1.0 + extra)
+                        );
+                    }
+                }
+                drop(image);
+                assert!(!path.exists());
+            }
+        };
+    }
+
+    tiled_buffer! { f32, tiled_buffer_f32, backed_tiled_buffer_f32 }
+    tiled_buffer! { f64, tiled_buffer_f64, backed_tiled_buffer_f64 }
+}
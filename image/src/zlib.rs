@@ -0,0 +1,453 @@
+// The zlib format of the compressed data of PNG files. The data is compressed with deflate in a
+// single block of the fixed Huffman codes, with repeated runs of bytes found in a window of the
+// last 32 KiB. Decompression reads blocks of every kind.
+
+use crate::huffman::{BitSource, Huffman};
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+// How many earlier positions with the same three bytes are compared at most, which bounds the
+// time spent on data with many repetitions.
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+// The first length of each length code from 257 on, and its number of extra bits.
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+// The first distance of each distance code, and its number of extra bits.
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+// Packs bits into bytes from the least significant bit of a byte on, as deflate stores them.
+struct BitWriter {
+    data: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new(data: Vec<u8>) -> BitWriter {
+        BitWriter {
+            data,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= (value as u64) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.data.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Huffman codes are packed from their most significant bit on.
+    fn write_code(&mut self, code: u32, length: u32) {
+        self.write(code.reverse_bits() >> (32 - length), length);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.data.push(self.bits as u8);
+        }
+        self.data
+    }
+}
+
+fn write_literal(writer: &mut BitWriter, symbol: u16) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol as u32, 8),
+        144..=255 => writer.write_code(0x190 + (symbol - 144) as u32, 9),
+        256..=279 => writer.write_code((symbol - 256) as u32, 7),
+        _ => writer.write_code(0xc0 + (symbol - 280) as u32, 8),
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASES.partition_point(|&base| base as usize <= length) - 1;
+    write_literal(writer, 257 + code as u16);
+    writer.write(
+        (length - LENGTH_BASES[code] as usize) as u32,
+        LENGTH_EXTRA_BITS[code] as u32,
+    );
+
+    let code = DISTANCE_BASES.partition_point(|&base| base as usize <= distance) - 1;
+    writer.write_code(code as u32, 5);
+    writer.write(
+        (distance - DISTANCE_BASES[code] as usize) as u32,
+        DISTANCE_EXTRA_BITS[code] as u32,
+    );
+}
+
+fn hash(data: &[u8]) -> usize {
+    let value = (data[0] as u32) << 16 | (data[1] as u32) << 8 | data[2] as u32;
+    (value.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+}
+
+// The longest earlier run of bytes that the data at the position repeats, as its length and its
+// distance back.
+fn longest_match(
+    data: &[u8],
+    position: usize,
+    head: &[usize],
+    previous: &[usize],
+) -> (usize, usize) {
+    let max_length = MAX_MATCH.min(data.len() - position);
+    let mut best = (0, 0);
+    let mut candidate = head[hash(&data[position..])];
+    for _ in 0..MAX_CHAIN {
+        if candidate == usize::MAX || position - candidate > WINDOW_SIZE {
+            break;
+        }
+        let length = data[candidate..]
+            .iter()
+            .zip(&data[position..position + max_length])
+            .take_while(|(a, b)| a == b)
+            .count();
+        if length > best.0 {
+            best = (length, position - candidate);
+            if length == max_length {
+                break;
+            }
+        }
+        candidate = previous[candidate % WINDOW_SIZE];
+    }
+    best
+}
+
+// Remembers the position as the latest one of its three bytes.
+fn insert(data: &[u8], position: usize, head: &mut [usize], previous: &mut [usize]) {
+    if position + MIN_MATCH <= data.len() {
+        let h = hash(&data[position..]);
+        previous[position % WINDOW_SIZE] = head[h];
+        head[h] = position;
+    }
+}
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    // Deflate with a window of 32 KiB and the default level.
+    let mut writer = BitWriter::new(vec![0x78, 0x9c]);
+    writer.write(1, 1);
+    writer.write(1, 2);
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut previous = vec![usize::MAX; WINDOW_SIZE];
+
+    let mut position = 0;
+    while position < data.len() {
+        let (length, distance) = if position + MIN_MATCH <= data.len() {
+            longest_match(data, position, &head, &previous)
+        } else {
+            (0, 0)
+        };
+
+        if length >= MIN_MATCH {
+            write_match(&mut writer, length, distance);
+            for p in position..position + length {
+                insert(data, p, &mut head, &mut previous);
+            }
+            position += length;
+        } else {
+            write_literal(&mut writer, data[position] as u16);
+            insert(data, position, &mut head, &mut previous);
+            position += 1;
+        }
+    }
+    write_literal(&mut writer, 256);
+
+    let mut compressed = writer.finish();
+    compressed.extend_from_slice(&adler32(data).to_be_bytes());
+    compressed
+}
+
+// Reads the bits the writer packs, from the least significant bit of a byte on. JPEG packs its
+// bits the other way round and has a reader of its own.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, count: u32) -> Option<u32> {
+        while self.count < count {
+            let byte = *self.data.get(self.position)?;
+            self.position += 1;
+            self.bits |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.bits & ((1 << count) - 1);
+        self.bits >>= count;
+        self.count -= count;
+        Some(value)
+    }
+
+    // Skips the rest of the current byte.
+    fn align(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+    }
+}
+
+impl BitSource for BitReader<'_> {
+    fn next_bit(&mut self) -> Option<u32> {
+        self.read(1)
+    }
+}
+
+// The order in which the lengths of the code length codes are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+// Decompresses zlib data. None if the data is malformed, its checksum does not match or it
+// decompresses to more bytes than the limit, which stops data that expands many times from
+// filling the memory.
+pub fn decompress(data: &[u8], limit: usize) -> Option<Vec<u8>> {
+    if data.len() < 6
+        || data[0] & 0x0f != 8
+        || !u16::from_be_bytes([data[0], data[1]]).is_multiple_of(31)
+        || data[1] & 0x20 != 0
+    {
+        return None;
+    }
+
+    let mut reader = BitReader {
+        data: &data[2..],
+        position: 0,
+        bits: 0,
+        count: 0,
+    };
+    let mut result = Vec::new();
+    loop {
+        let last = reader.read(1)? == 1;
+        match reader.read(2)? {
+            0 => {
+                reader.align();
+                let length = reader.read(16)?;
+                if length != !reader.read(16)? & 0xffff {
+                    return None;
+                }
+                let start = reader.position;
+                if result.len() + length as usize > limit {
+                    return None;
+                }
+                result.extend_from_slice(reader.data.get(start..start + length as usize)?);
+                reader.position += length as usize;
+            }
+            1 => {
+                let (literals, distances) = fixed_codes();
+                inflate_block(&mut reader, &literals, &distances, &mut result, limit)?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_codes(&mut reader)?;
+                inflate_block(&mut reader, &literals, &distances, &mut result, limit)?;
+            }
+            _ => return None,
+        }
+        if last {
+            break;
+        }
+    }
+
+    reader.align();
+    let checksum = reader.data.get(reader.position..reader.position + 4)?;
+    (checksum == adler32(&result).to_be_bytes()).then_some(result)
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [8; 288];
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    (
+        Huffman::from_lengths(&lengths),
+        Huffman::from_lengths(&[5; 30]),
+    )
+}
+
+fn read_dynamic_codes(reader: &mut BitReader) -> Option<(Huffman, Huffman)> {
+    let literal_count = reader.read(5)? as usize + 257;
+    let distance_count = reader.read(5)? as usize + 1;
+    let code_length_count = reader.read(4)? as usize + 4;
+
+    let mut code_lengths = [0; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[symbol] = reader.read(3)? as u8;
+    }
+    let code_lengths = Huffman::from_lengths(&code_lengths);
+
+    // The lengths of both codes are stored as one sequence, where runs of lengths repeat the
+    // previous length or zero.
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        match code_lengths.decode(reader)? {
+            length @ 0..=15 => lengths.push(length as u8),
+            16 => {
+                let previous = *lengths.last()?;
+                let repeat = 3 + reader.read(2)? as usize;
+                lengths.extend(std::iter::repeat_n(previous, repeat));
+            }
+            17 => {
+                let repeat = 3 + reader.read(3)? as usize;
+                lengths.extend(std::iter::repeat_n(0, repeat));
+            }
+            _ => {
+                let repeat = 11 + reader.read(7)? as usize;
+                lengths.extend(std::iter::repeat_n(0, repeat));
+            }
+        }
+    }
+    if lengths.len() > literal_count + distance_count {
+        return None;
+    }
+
+    Some((
+        Huffman::from_lengths(&lengths[..literal_count]),
+        Huffman::from_lengths(&lengths[literal_count..]),
+    ))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    literals: &Huffman,
+    distances: &Huffman,
+    result: &mut Vec<u8>,
+    limit: usize,
+) -> Option<()> {
+    loop {
+        if result.len() > limit {
+            return None;
+        }
+        match literals.decode(reader)? {
+            symbol @ 0..=255 => result.push(symbol as u8),
+            256 => return Some(()),
+            symbol => {
+                let code = symbol as usize - 257;
+                let length = *LENGTH_BASES.get(code)? as usize
+                    + reader.read(LENGTH_EXTRA_BITS[code] as u32)? as usize;
+                let code = distances.decode(reader)? as usize;
+                let distance = *DISTANCE_BASES.get(code)? as usize
+                    + reader.read(DISTANCE_EXTRA_BITS[code] as u32)? as usize;
+                if distance > result.len() {
+                    return None;
+                }
+                // The run may overlap the bytes it produces.
+                let start = result.len() - distance;
+                for index in start..start + length {
+                    result.push(result[index]);
+                }
+            }
+        }
+    }
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // The sums are reduced before they can overflow.
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adler32_of_text() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(&[]), 1);
+    }
+
+    #[test]
+    fn compress_empty_data() {
+        assert_eq!(
+            compress(&[]),
+            vec![0x78, 0x9c, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01]
+        );
+    }
+
+    #[test]
+    fn compress_repetitions() {
+        let data: Vec<u8> = (0..10000).map(|i| (i % 7) as u8).collect();
+        let compressed = compress(&data);
+
+        assert!(compressed.len() < 200);
+        assert_eq!(compressed[..2], [0x78, 0x9c]);
+        assert_eq!(
+            compressed[compressed.len() - 4..],
+            adler32(&data).to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn decompress_compressed_data() {
+        let data: Vec<u8> = (0..100000u64).map(|i| (i * i % 251) as u8).collect();
+
+        assert_eq!(decompress(&compress(&data), data.len()), Some(data));
+        assert_eq!(decompress(&compress(&[]), 0), Some(vec![]));
+    }
+
+    #[test]
+    fn decompress_stored_and_dynamic_blocks() {
+        let stored = [
+            0x78, 0x01, 0x01, 0x0b, 0x00, 0xf4, 0xff, b's', b't', b'o', b'r', b'e', b'd', b' ',
+            b'd', b'a', b't', b'a', 0x1a, 0xb2, 0x04, 0x4c,
+        ];
+        assert_eq!(decompress(&stored, 11), Some(b"stored data".to_vec()));
+
+        let dynamic = [
+            0x78, 0xda, 0x25, 0x8a, 0x81, 0x09, 0x00, 0x30, 0x0c, 0xc2, 0x6e, 0x4d, 0xf4, 0xff,
+            0x1b, 0xd6, 0x76, 0x20, 0x18, 0x8c, 0x4a, 0x91, 0x89, 0x64, 0x8b, 0x0f, 0x85, 0xd4,
+            0xae, 0xf1, 0xf6, 0xaa, 0xf3, 0x4c, 0x1f, 0xe7, 0x44, 0x13, 0x22,
+        ];
+        assert_eq!(
+            decompress(&dynamic, 50),
+            Some(b"bbadabaababacaabaaabacaadaacdbdbaabbcaabadbbbdabcd".to_vec())
+        );
+    }
+
+    #[test]
+    fn decompress_rejects_malformed_data() {
+        let mut data = compress(b"checksum");
+        *data.last_mut().unwrap() ^= 1;
+        assert_eq!(decompress(&data, 8), None);
+
+        let data = compress(b"truncated");
+        assert_eq!(decompress(&data[..data.len() - 5], 9), None);
+        assert_eq!(decompress(b"not zlib", 8), None);
+
+        // Data that expands beyond the limit is rejected, in repetitions and in stored blocks.
+        let data = compress(&[0; 100000]);
+        assert!(data.len() < 1000);
+        assert_eq!(decompress(&data, 99999), None);
+        let stored = [
+            0x78, 0x01, 0x01, 0x0b, 0x00, 0xf4, 0xff, b's', b't', b'o', b'r', b'e', b'd', b' ',
+            b'd', b'a', b't', b'a', 0x1a, 0xb2, 0x04, 0x4c,
+        ];
+        assert_eq!(decompress(&stored, 10), None);
+    }
+}
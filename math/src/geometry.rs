@@ -5,6 +5,7 @@ pub mod implicit_disc3;
 pub mod implicit_n_sphere;
 pub mod implicit_plane_3;
 pub mod parametric_line;
+pub mod ray_packet;
 pub mod rectangle;
 pub mod sphere;
 pub mod triangle;
@@ -16,6 +17,7 @@ pub use implicit_disc3::ImplicitDisc3;
 pub use implicit_n_sphere::ImplicitNSphere;
 pub use implicit_plane_3::ImplicitPlane3;
 pub use parametric_line::ParametricLine;
+pub use ray_packet::RayPacket;
 pub use rectangle::Rectangle2;
 pub use sphere::Sphere;
 pub use triangle::Triangle3;
@@ -26,9 +28,49 @@ pub trait Intersect<T> {
     fn intersect(self, other: T) -> Self::Output;
 }
 
-use crate::{Normal3, Point2, Point3};
+// Intersects all rays of a packet with a geometry at once. Each lane holds the ray parameter of the
+// closest intersection above the epsilon, or infinity if the ray misses. Geometries without a test
+// for packets return None, and their rays are intersected one at a time.
+pub trait IntersectPacket<V, const N: usize> {
+    fn intersect_packet(&self, _packet: &RayPacket<V, N>, _epsilon: V) -> Option<[V; N]> {
+        None
+    }
+}
+
+// Maps a point of the unit square uniformly onto a surface. The probability density is returned
+// along with the point. It is given with respect to the surface area, measured in square units of
+// the length type.
+pub trait SampleSurface<T: Div + Copy>
+where
+    <T as Div>::Output: Debug + Copy + PartialEq,
+{
+    fn sample_surface(
+        &self,
+        u: Point2<<T as Div>::Output>,
+    ) -> (SurfacePoint<T>, <T as Div>::Output);
+}
+
+// The point on the surface of a geometry that is the closest to a point, along with the normal of
+// the surface there. The normal of a closed surface points outwards, so the point is inside if its
+// offset from the closest point points against the normal.
+pub trait ClosestPoint<T: Div + Copy>
+where
+    <T as Div>::Output: Debug + Copy + PartialEq,
+{
+    fn closest_point(&self, p: Point3<T>) -> (Point3<T>, Normal3<<T as Div>::Output>);
+}
+
+// Scales a vector of plain values to a vector of lengths.
+fn scaled<T>(v: Vector3<<T as Div>::Output>, length: T) -> Vector3<T>
+where
+    T: Div + Mul<<T as Div>::Output, Output = T> + Copy,
+{
+    Vector3::new(length * v.x, length * v.y, length * v.z)
+}
+
+use crate::{Normal3, Point2, Point3, Vector3};
 use std::fmt::Debug;
-use std::ops::Div;
+use std::ops::{Div, Mul};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct SurfacePoint<T: Div + Copy>
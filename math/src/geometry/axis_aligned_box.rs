@@ -1,10 +1,13 @@
 use std::fmt::Debug;
 use std::ops::{Div, Mul, Sub};
 
-use super::{ImplicitPlane3, Intersect, ParametricLine, SurfacePoint};
+use super::{
+    scaled, ClosestPoint, ImplicitPlane3, Intersect, IntersectPacket, ParametricLine, RayPacket,
+    SurfacePoint,
+};
 
 use crate::{Normal3, Orthonormal3, Point3, Vector3};
-use traits::{FloatingPoint, Number, SelfMulNumber};
+use traits::{Abs, Clamp, FloatingPoint, Number, One, SelfMulNumber, Zero};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct AxisAlignedBox<P> {
@@ -109,6 +112,97 @@ where
     }
 }
 
+// The slabs between the opposite faces are intersected instead of each face, so the test runs
+// without branches.
+impl<T, const N: usize> IntersectPacket<<T as Div>::Output, N> for AxisAlignedBox<Point3<T>>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint,
+{
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket<<T as Div>::Output, N>,
+        epsilon: <T as Div>::Output,
+    ) -> Option<[<T as Div>::Output; N]> {
+        let one = T::one();
+        let a = [self.a.x / one, self.a.y / one, self.a.z / one];
+        let b = [self.b.x / one, self.b.y / one, self.b.z / one];
+
+        Some(std::array::from_fn(|lane| {
+            let mut near = <T as Div>::Output::NEG_INFINITY;
+            let mut far = <T as Div>::Output::INFINITY;
+            for axis in 0..3 {
+                let o = packet.origin[axis][lane];
+                let d = packet.direction[axis][lane];
+                let t1 = (a[axis] - o) / d;
+                let t2 = (b[axis] - o) / d;
+                let (entry, exit) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
+                if entry > near {
+                    near = entry;
+                }
+                if exit < far {
+                    far = exit;
+                }
+            }
+
+            if near > far {
+                <T as Div>::Output::INFINITY
+            } else if near > epsilon {
+                near
+            } else if far > epsilon {
+                far
+            } else {
+                <T as Div>::Output::INFINITY
+            }
+        }))
+    }
+}
+
+impl<T> ClosestPoint<T> for AxisAlignedBox<Point3<T>>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint,
+    <T as Mul>::Output: Number<<T as Div>::Output>,
+{
+    fn closest_point(&self, p: Point3<T>) -> (Point3<T>, Normal3<<T as Div>::Output>) {
+        let zero = <T as Div>::Output::zero();
+        let one = <T as Div>::Output::one();
+
+        let d = (p - self.a) / T::one();
+        let e = (self.b - self.a) / T::one();
+        let d = [d.x, d.y, d.z];
+        let e = [e.x, e.y, e.z];
+
+        let mut c = d;
+        let mut n = [zero; 3];
+
+        if (0..3).all(|i| d[i] >= zero && d[i] <= e[i]) {
+            // From the inside, the closest point is on the closest face.
+            let distance = |(i, upper): (usize, bool)| if upper { e[i] - d[i] } else { d[i] };
+            let (axis, upper) = (0..3)
+                .flat_map(|i| [(i, false), (i, true)])
+                .min_by(|a, b| distance(*a).partial_cmp(&distance(*b)).unwrap())
+                .unwrap();
+            c[axis] = if upper { e[axis] } else { zero };
+            n[axis] = if upper { one } else { -one };
+        } else {
+            for i in 0..3 {
+                c[i] = d[i].clamp(zero, e[i]);
+            }
+            let outside = |i: usize| (d[i] - c[i]).abs();
+            let axis = (0..3)
+                .max_by(|i, j| outside(*i).partial_cmp(&outside(*j)).unwrap())
+                .unwrap();
+            n[axis] = if d[axis] > c[axis] { one } else { -one };
+        }
+
+        (
+            self.a + scaled(Vector3::new(c[0], c[1], c[2]), T::one()),
+            Normal3::new(n[0], n[1], n[2]),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -216,4 +310,69 @@ mod tests {
 
     parametric_line_intersect_axis_aligned_box_3! { f32, parametric_line_intersect_axis_aligned_box_3_f32 }
     parametric_line_intersect_axis_aligned_box_3! { f64, parametric_line_intersect_axis_aligned_box_3_f64 }
+
+    macro_rules! axis_aligned_box_closest_point {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let aab = AxisAlignedBox::new(
+                    Point3::new(0 as $type, 0.0, 0.0),
+                    Point3::new(2.0, 2.0, 2.0),
+                );
+
+                let (p, n) = aab.closest_point(Point3::new(3.0, 1.0, 1.0));
+                assert_eq!(p, Point3::new(2.0, 1.0, 1.0));
+                assert_eq!(n, Normal3::new(1.0, 0.0, 0.0));
+
+                let (p, n) = aab.closest_point(Point3::new(1.0, 0.5, 1.0));
+                assert_eq!(p, Point3::new(1.0, 0.0, 1.0));
+                assert_eq!(n, Normal3::new(0.0, -1.0, 0.0));
+
+                let (p, _) = aab.closest_point(Point3::new(3.0, 3.0, -1.0));
+                assert_eq!(p, Point3::new(2.0, 2.0, 0.0));
+            }
+        };
+    }
+
+    axis_aligned_box_closest_point! { f32, axis_aligned_box_closest_point_f32 }
+    axis_aligned_box_closest_point! { f64, axis_aligned_box_closest_point_f64 }
+
+    macro_rules! axis_aligned_box_intersect_packet {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let aab = AxisAlignedBox::new(
+                    Point3::new(-1.0 as $type, -1.0, -6.0),
+                    Point3::new(1.0, 1.0, -4.0),
+                );
+
+                // The rays hit the front and the sides, miss the box and start inside.
+                let rays = [
+                    (0.0, 0.0, 0.0, 0.0, 0.0),
+                    (0.5, -0.5, 0.0, 0.0, 0.0),
+                    (3.0, 0.0, 0.0, -0.4, 0.0),
+                    (2.0, 2.0, 0.0, 0.0, 0.0),
+                    (0.0, 0.0, -5.2, 1.0, 0.3),
+                    (0.2, 0.1, -5.5, 0.0, 0.0),
+                ]
+                .map(|(x, y, z, dx, dy): ($type, $type, $type, $type, $type)| {
+                    ParametricLine::new(Point3::new(x, y, z), Vector3::new(dx, dy, -1.0))
+                });
+
+                let hits = aab.intersect_packet(&RayPacket::new(rays), 0.001).unwrap();
+                for (ray, t) in rays.iter().zip(hits) {
+                    let expected = ray
+                        .intersect(aab)
+                        .into_iter()
+                        .map(|(t, _)| t)
+                        .filter(|t| *t > 0.001)
+                        .fold(<$type>::INFINITY, <$type>::min);
+                    assert!(t == expected || (t - expected).abs() < 0.0001);
+                }
+            }
+        };
+    }
+
+    axis_aligned_box_intersect_packet! { f32, axis_aligned_box_intersect_packet_f32 }
+    axis_aligned_box_intersect_packet! { f64, axis_aligned_box_intersect_packet_f64 }
 }
@@ -1,12 +1,14 @@
 use std::fmt::Debug;
 use std::ops::{Add, Div, Mul, Sub};
 
-use super::{Intersect, ParametricLine, SurfacePoint};
+use super::{
+    scaled, ClosestPoint, Intersect, IntersectPacket, ParametricLine, RayPacket, SurfacePoint,
+};
 
-use crate::{Point2, Point3, Vector3};
+use crate::{Normal3, Point2, Point3, Vector3};
 use traits::{
-    Atan2, ConvenientNumber, FloatingPoint, Half, Number, One, Pi, SelfMulNumber, SignedNumber,
-    Sqrt, Zero,
+    Atan2, Clamp, ConvenientNumber, FloatingPoint, Half, Number, One, Pi, SelfMulNumber,
+    SignedNumber, Sqrt, Zero,
 };
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -136,6 +138,89 @@ where
     }
 }
 
+impl<T, const N: usize> IntersectPacket<<T as Div>::Output, N> for ImplicitCylinder<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+{
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket<<T as Div>::Output, N>,
+        epsilon: <T as Div>::Output,
+    ) -> Option<[<T as Div>::Output; N]> {
+        let zero = <T as Div>::Output::zero();
+        let one = T::one();
+        let center = Point3::new(
+            self.center.x / one,
+            self.center.y / one,
+            self.center.z / one,
+        );
+        let half_height = (self.height / one).half();
+        let radius = self.radius / one;
+
+        Some(std::array::from_fn(|lane| {
+            let v = packet.origin(lane) - center;
+            let d = packet.direction(lane);
+
+            let a = d.x * d.x + d.z * d.z;
+            let b = d.x * v.x + d.z * v.z;
+            let c = v.x * v.x + v.z * v.z - radius * radius;
+            let discriminant = b * b - a * c;
+
+            let root = if discriminant > zero {
+                discriminant.sqrt()
+            } else {
+                zero
+            };
+            let near = (-b - root) / a;
+            let far = (-b + root) / a;
+            let within = |t: <T as Div>::Output| {
+                let y = v.y + d.y * t;
+                t > epsilon && y >= -half_height && y <= half_height
+            };
+
+            if discriminant < zero {
+                <T as Div>::Output::INFINITY
+            } else if within(near) {
+                near
+            } else if within(far) {
+                far
+            } else {
+                <T as Div>::Output::INFINITY
+            }
+        }))
+    }
+}
+
+impl<T> ClosestPoint<T> for ImplicitCylinder<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    <T as Mul>::Output: Number<<T as Div>::Output>,
+{
+    // The cylinder is open, points beyond its ends are closest to its rims.
+    fn closest_point(&self, p: Point3<T>) -> (Point3<T>, Normal3<<T as Div>::Output>) {
+        let d = (p - self.center) / T::one();
+        let radial = Vector3::new(d.x, Zero::zero(), d.z);
+
+        let length = radial.magnitude();
+        let n = if length > Zero::zero() {
+            radial / length
+        } else {
+            Vector3::new(One::one(), Zero::zero(), Zero::zero())
+        };
+
+        let radius = self.radius / T::one();
+        let half_height = (self.height / T::one()).half();
+        let y = d.y.clamp(-half_height, half_height);
+
+        (
+            self.center + scaled(Vector3::new(n.x * radius, y, n.z * radius), T::one()),
+            n.as_normal(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -288,4 +373,63 @@ mod tests {
 
     parametric_line_intersect_implicit_cylinder! { f32, parametric_line_intersect_implicit_cylinder_f32 }
     parametric_line_intersect_implicit_cylinder! { f64, parametric_line_intersect_implicit_cylinder_f64 }
+
+    macro_rules! implicit_cylinder_closest_point {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let cylinder = ImplicitCylinder::new(Point3::new(0 as $type, 0.0, 0.0), 2.0, 1.0);
+
+                let (p, n) = cylinder.closest_point(Point3::new(3.0, 0.0, 0.0));
+                assert_eq!(p, Point3::new(1.0, 0.0, 0.0));
+                assert_eq!(n, Normal3::new(1.0, 0.0, 0.0));
+
+                let (p, n) = cylinder.closest_point(Point3::new(0.0, 5.0, 2.0));
+                assert_eq!(p, Point3::new(0.0, 1.0, 1.0));
+                assert_eq!(n, Normal3::new(0.0, 0.0, 1.0));
+            }
+        };
+    }
+
+    implicit_cylinder_closest_point! { f32, implicit_cylinder_closest_point_f32 }
+    implicit_cylinder_closest_point! { f64, implicit_cylinder_closest_point_f64 }
+
+    macro_rules! implicit_cylinder_intersect_packet {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let cylinder =
+                    ImplicitCylinder::new(Point3::new(0.0 as $type, 0.0, -5.0), 2.0, 1.0);
+
+                // The rays pass the tube, hit it, leave it through the open ends and start inside.
+                let rays = [
+                    (0.0, 0.0, 0.0),
+                    (0.5, 0.0, 0.0),
+                    (1.5, 0.0, 0.0),
+                    (0.0, 0.9, 0.0),
+                    (0.0, 0.0, 0.2),
+                    (0.0, 0.0, -5.0),
+                ]
+                .map(|(x, y, z): ($type, $type, $type)| {
+                    ParametricLine::new(Point3::new(x, y, z), Vector3::new(0.0, y, -1.0))
+                });
+
+                let hits = cylinder
+                    .intersect_packet(&RayPacket::new(rays), 0.001)
+                    .unwrap();
+                for (ray, t) in rays.iter().zip(hits) {
+                    let expected = ray
+                        .intersect(cylinder)
+                        .into_iter()
+                        .map(|(t, _)| t)
+                        .filter(|t| *t > 0.001)
+                        .fold(<$type>::INFINITY, <$type>::min);
+                    assert!(t == expected || (t - expected).abs() < 0.0001);
+                }
+            }
+        };
+    }
+
+    implicit_cylinder_intersect_packet! { f32, implicit_cylinder_intersect_packet_f32 }
+    implicit_cylinder_intersect_packet! { f64, implicit_cylinder_intersect_packet_f64 }
 }
@@ -1,10 +1,15 @@
 use std::fmt::Debug;
 use std::ops::{Add, Div, Mul, Sub};
 
-use super::{Intersect, ParametricLine, SurfacePoint};
+use super::{
+    scaled, ClosestPoint, Intersect, IntersectPacket, ParametricLine, RayPacket, SampleSurface,
+    SurfacePoint,
+};
 
 use crate::{Mat3x3, Normal3, Point2, Point3, Vector3};
-use traits::{Atan2, ConvenientNumber, FloatingPoint, Number, One, SelfMulNumber, Sqrt, Zero};
+use traits::{
+    Atan2, ConvenientNumber, Cos, FloatingPoint, Number, One, Pi, SelfMulNumber, Sin, Sqrt, Zero,
+};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct ImplicitDisc3<T>
@@ -105,6 +110,102 @@ where
     }
 }
 
+impl<T, const N: usize> IntersectPacket<<T as Div>::Output, N> for ImplicitDisc3<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint,
+{
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket<<T as Div>::Output, N>,
+        epsilon: <T as Div>::Output,
+    ) -> Option<[<T as Div>::Output; N]> {
+        let one = T::one();
+        let anchor = Point3::new(
+            self.anchor.x / one,
+            self.anchor.y / one,
+            self.anchor.z / one,
+        );
+        let n = self.normal.as_vector();
+        let radius = self.radius / one;
+
+        Some(std::array::from_fn(|lane| {
+            let o = packet.origin(lane);
+            let d = packet.direction(lane);
+            let denominator = d.dot(n);
+            let t = (anchor - o).dot(n) / denominator;
+            let offset = o + d * t - anchor;
+
+            if denominator != Zero::zero() && t > epsilon && offset.dot(offset) <= radius * radius {
+                t
+            } else {
+                <T as Div>::Output::INFINITY
+            }
+        }))
+    }
+}
+
+impl<T> SampleSurface<T> for ImplicitDisc3<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+{
+    fn sample_surface(
+        &self,
+        u: Point2<<T as Div>::Output>,
+    ) -> (SurfacePoint<T>, <T as Div>::Output) {
+        let one = <T as Div>::Output::one();
+        let pi = <T as Div>::Output::PI;
+
+        let radius = self.radius / T::one();
+        let s = u.x.sqrt();
+        let phi = u.y * (pi + pi);
+
+        // The same coordinate system as in the intersection, the texture coordinates match.
+        let w = Vector3::cross(self.normal.as_vector(), self.right);
+        let x = s * phi.cos();
+        let z = -(s * phi.sin());
+
+        let offset = self.right * x - w * z;
+        let p = self.anchor
+            + Vector3::new(
+                self.radius * offset.x,
+                self.radius * offset.y,
+                self.radius * offset.z,
+            );
+
+        let uv = Point2::new((x * x + z * z) * radius, (x.atan2(z) % one + one) % one);
+
+        (
+            SurfacePoint::new(p, self.normal, uv),
+            one / (pi * radius * radius),
+        )
+    }
+}
+
+impl<T> ClosestPoint<T> for ImplicitDisc3<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    <T as Mul>::Output: Number<<T as Div>::Output>,
+{
+    fn closest_point(&self, p: Point3<T>) -> (Point3<T>, Normal3<<T as Div>::Output>) {
+        let n = self.normal.as_vector();
+        let d = (p - self.anchor) / T::one();
+        let in_plane = d - n * d.dot(n);
+
+        let length = in_plane.magnitude();
+        let radius = self.radius / T::one();
+        let offset = if length > radius {
+            in_plane * (radius / length)
+        } else {
+            in_plane
+        };
+
+        (self.anchor + scaled(offset, T::one()), self.normal)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -229,4 +330,101 @@ mod tests {
 
     parametric_line_intersect_implicit_disc3! { f32, parametric_line_intersect_implicit_disc3_f32 }
     parametric_line_intersect_implicit_disc3! { f64, parametric_line_intersect_implicit_disc3_f64 }
+
+    macro_rules! sample_implicit_disc3_surface {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let normal = Normal3::new(0 as $type, 1 as $type, 0 as $type);
+                let disc = ImplicitDisc3::new(
+                    Point3::new(0 as $type, 1 as $type, 0 as $type),
+                    normal,
+                    Vector3::new(1 as $type, 0 as $type, 0 as $type),
+                    2 as $type,
+                );
+
+                for u in [
+                    Point2::new(0.1 as $type, 0.2 as $type),
+                    Point2::new(0.5 as $type, 0.9 as $type),
+                    Point2::new(0.8 as $type, 0.4 as $type),
+                ] {
+                    let (sp, pdf) = disc.sample_surface(u);
+
+                    assert!(disc.test(sp.p).abs() < 0.0001);
+                    assert!((sp.p - disc.anchor).magnitude() <= disc.radius);
+                    assert_eq!(sp.n, normal);
+                    assert!((pdf - 1.0 / (4.0 * <$type>::PI)).abs() < 0.0001);
+
+                    let ray = ParametricLine::new(
+                        sp.p + Vector3::new(0 as $type, 1 as $type, 0 as $type),
+                        Vector3::new(0 as $type, -1 as $type, 0 as $type),
+                    );
+                    let (_, hit) = ray.intersect(disc)[0];
+                    assert!((hit.uv - sp.uv).magnitude() < 0.0001);
+                }
+            }
+        };
+    }
+
+    sample_implicit_disc3_surface! { f32, sample_implicit_disc3_surface_f32 }
+    sample_implicit_disc3_surface! { f64, sample_implicit_disc3_surface_f64 }
+
+    macro_rules! implicit_disc3_closest_point {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let disc = ImplicitDisc3::new(
+                    Point3::new(0 as $type, 0.0, 0.0),
+                    Normal3::new(0.0, 1.0, 0.0),
+                    Vector3::new(1.0, 0.0, 0.0),
+                    2.0,
+                );
+
+                let (p, n) = disc.closest_point(Point3::new(1.0, 3.0, 0.0));
+                assert_eq!(p, Point3::new(1.0, 0.0, 0.0));
+                assert_eq!(n, Normal3::new(0.0, 1.0, 0.0));
+
+                let (p, _) = disc.closest_point(Point3::new(4.0, -1.0, 0.0));
+                assert_eq!(p, Point3::new(2.0, 0.0, 0.0));
+            }
+        };
+    }
+
+    implicit_disc3_closest_point! { f32, implicit_disc3_closest_point_f32 }
+    implicit_disc3_closest_point! { f64, implicit_disc3_closest_point_f64 }
+
+    macro_rules! implicit_disc3_intersect_packet {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let disc = ImplicitDisc3::new(
+                    Point3::new(0.0 as $type, -1.0, 0.0),
+                    Normal3::new(0.0, 1.0, 0.0),
+                    Vector3::new(1.0, 0.0, 0.0),
+                    1.0,
+                );
+
+                // The rays hit the disc and miss its rim, and the last one runs along it.
+                let mut rays = [-1.5, -0.9, 0.0, 0.5, 1.1, 2.0, 0.0].map(|x: $type| {
+                    ParametricLine::new(Point3::new(x, 0.0, 0.0), Vector3::new(0.0, -1.0, 0.0))
+                });
+                rays[6] =
+                    ParametricLine::new(Point3::new(0.0, -1.0, -2.0), Vector3::new(0.0, 0.0, 1.0));
+
+                let hits = disc.intersect_packet(&RayPacket::new(rays), 0.001).unwrap();
+                for (ray, t) in rays.iter().zip(hits) {
+                    let expected = ray
+                        .intersect(disc)
+                        .into_iter()
+                        .map(|(t, _)| t)
+                        .filter(|t| *t > 0.001)
+                        .fold(<$type>::INFINITY, <$type>::min);
+                    assert!(t == expected || (t - expected).abs() < 0.0001);
+                }
+            }
+        };
+    }
+
+    implicit_disc3_intersect_packet! { f32, implicit_disc3_intersect_packet_f32 }
+    implicit_disc3_intersect_packet! { f64, implicit_disc3_intersect_packet_f64 }
 }
@@ -1,7 +1,9 @@
 use std::fmt::Debug;
 use std::ops::{Add, Div, Mul, Sub};
 
-use super::{Intersect, ParametricLine, SurfacePoint};
+use super::{
+    scaled, ClosestPoint, Intersect, IntersectPacket, ParametricLine, RayPacket, SurfacePoint,
+};
 
 use crate::{Mat3x3, Normal3, Point2, Point3, Vector3};
 use traits::{FloatingPoint, Number, One, SelfMulNumber, Zero};
@@ -92,6 +94,48 @@ where
     }
 }
 
+impl<T, const N: usize> IntersectPacket<<T as Div>::Output, N> for ImplicitPlane3<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint,
+{
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket<<T as Div>::Output, N>,
+        epsilon: <T as Div>::Output,
+    ) -> Option<[<T as Div>::Output; N]> {
+        let one = T::one();
+        let anchor = Point3::new(
+            self.anchor.x / one,
+            self.anchor.y / one,
+            self.anchor.z / one,
+        );
+        let n = self.normal.as_vector();
+
+        Some(std::array::from_fn(|lane| {
+            let denominator = packet.direction(lane).dot(n);
+            let t = (anchor - packet.origin(lane)).dot(n) / denominator;
+
+            if denominator != Zero::zero() && t > epsilon {
+                t
+            } else {
+                <T as Div>::Output::INFINITY
+            }
+        }))
+    }
+}
+
+impl<T> ClosestPoint<T> for ImplicitPlane3<T>
+where
+    T: Number<<T as Div>::Output>,
+    <T as Div>::Output: Number,
+{
+    fn closest_point(&self, p: Point3<T>) -> (Point3<T>, Normal3<<T as Div>::Output>) {
+        let n = self.normal.as_vector();
+        (p - scaled(n, self.test(p)), self.normal)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -202,4 +246,58 @@ mod tests {
 
     parametric_line_intersect_implicit_plane3! { f32, parametric_line_intersect_implicit_plane3_f32 }
     parametric_line_intersect_implicit_plane3! { f64, parametric_line_intersect_implicit_plane3_f64 }
+
+    macro_rules! implicit_plane3_closest_point {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let plane = ImplicitPlane3::new(
+                    Point3::new(0 as $type, 1.0, 0.0),
+                    Normal3::new(0.0, 1.0, 0.0),
+                    Vector3::new(1.0, 0.0, 0.0),
+                );
+
+                let (p, n) = plane.closest_point(Point3::new(3.0, 5.0, -2.0));
+                assert_eq!(p, Point3::new(3.0, 1.0, -2.0));
+                assert_eq!(n, Normal3::new(0.0, 1.0, 0.0));
+            }
+        };
+    }
+
+    implicit_plane3_closest_point! { f32, implicit_plane3_closest_point_f32 }
+    implicit_plane3_closest_point! { f64, implicit_plane3_closest_point_f64 }
+
+    macro_rules! implicit_plane3_intersect_packet {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let plane = ImplicitPlane3::new(
+                    Point3::new(0.0 as $type, -1.0, 0.0),
+                    Normal3::new(0.0, 1.0, 0.0),
+                    Vector3::new(1.0, 0.0, 0.0),
+                );
+
+                // The rays point down, along the plane and away from it.
+                let rays = [-2.0, -1.0, -0.5, 0.0, 0.5, 1.0].map(|y: $type| {
+                    ParametricLine::new(Point3::new(1.0, 0.0, 2.0), Vector3::new(0.5, y, -1.0))
+                });
+
+                let hits = plane
+                    .intersect_packet(&RayPacket::new(rays), 0.001)
+                    .unwrap();
+                for (ray, t) in rays.iter().zip(hits) {
+                    let expected = ray
+                        .intersect(plane)
+                        .into_iter()
+                        .map(|(t, _)| t)
+                        .filter(|t| *t > 0.001)
+                        .fold(<$type>::INFINITY, <$type>::min);
+                    assert!(t == expected || (t - expected).abs() < 0.0001);
+                }
+            }
+        };
+    }
+
+    implicit_plane3_intersect_packet! { f32, implicit_plane3_intersect_packet_f32 }
+    implicit_plane3_intersect_packet! { f64, implicit_plane3_intersect_packet_f64 }
 }
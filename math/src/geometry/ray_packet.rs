@@ -0,0 +1,147 @@
+use std::ops::Div;
+
+use super::ParametricLine;
+
+use crate::{Mat4x4, Point3, Vector3};
+use traits::{FloatingPoint, One};
+
+// Rays that are intersected together. The coordinates are kept per axis with one lane per ray, so
+// a test runs the same instructions over all lanes and the compiler can map them onto SIMD
+// registers. Four lanes fill the registers of SSE and NEON, eight those of AVX. The rays are
+// plain values, i.e. lengths divided by their unit.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RayPacket<V, const N: usize> {
+    pub origin: [[V; N]; 3],
+    pub direction: [[V; N]; 3],
+}
+
+impl<V: FloatingPoint, const N: usize> RayPacket<V, N> {
+    pub fn new<T>(rays: [ParametricLine<Point3<T>, Vector3<T>>; N]) -> RayPacket<V, N>
+    where
+        T: Div<Output = V> + One + Copy,
+    {
+        let one = T::one();
+        RayPacket {
+            origin: [
+                rays.map(|r| r.origin.x / one),
+                rays.map(|r| r.origin.y / one),
+                rays.map(|r| r.origin.z / one),
+            ],
+            direction: [
+                rays.map(|r| r.direction.x / one),
+                rays.map(|r| r.direction.y / one),
+                rays.map(|r| r.direction.z / one),
+            ],
+        }
+    }
+
+    pub fn origin(&self, lane: usize) -> Point3<V> {
+        Point3::new(
+            self.origin[0][lane],
+            self.origin[1][lane],
+            self.origin[2][lane],
+        )
+    }
+
+    pub fn direction(&self, lane: usize) -> Vector3<V> {
+        Vector3::new(
+            self.direction[0][lane],
+            self.direction[1][lane],
+            self.direction[2][lane],
+        )
+    }
+
+    pub fn ray(&self, lane: usize) -> ParametricLine<Point3<V>, Vector3<V>> {
+        ParametricLine::new(self.origin(lane), self.direction(lane))
+    }
+
+    // Moves the origin of each ray by its own offset. The ray parameters stay the same.
+    pub fn translated(self, offsets: [Vector3<V>; N]) -> RayPacket<V, N> {
+        let mut packet = self;
+        for (lane, offset) in offsets.iter().enumerate() {
+            packet.origin[0][lane] = self.origin[0][lane] + offset.x;
+            packet.origin[1][lane] = self.origin[1][lane] + offset.y;
+            packet.origin[2][lane] = self.origin[2][lane] + offset.z;
+        }
+        packet
+    }
+
+    // The rays in another space, e.g. in the space of a geometry. The directions are transformed
+    // along with the origins, so the ray parameters stay the same.
+    pub fn transformed(self, m: Mat4x4<V>) -> RayPacket<V, N> {
+        let mut packet = self;
+        for lane in 0..N {
+            let o = m * self.origin(lane);
+            let d = m * self.direction(lane);
+            packet.origin[0][lane] = o.x;
+            packet.origin[1][lane] = o.y;
+            packet.origin[2][lane] = o.z;
+            packet.direction[0][lane] = d.x;
+            packet.direction[1][lane] = d.y;
+            packet.direction[2][lane] = d.z;
+        }
+        packet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::transform::Transform3;
+
+    macro_rules! new_ray_packet {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let rays = [0, 1, 2, 3].map(|i| {
+                    ParametricLine::new(
+                        Point3::new(i as $type, 2.0, 3.0),
+                        Vector3::new(0.0, i as $type, 1.0),
+                    )
+                });
+
+                let packet = RayPacket::<$type, 4>::new(rays);
+
+                assert_eq!(packet.origin[0], [0.0, 1.0, 2.0, 3.0]);
+                assert_eq!(packet.direction[1], [0.0, 1.0, 2.0, 3.0]);
+                for (lane, ray) in rays.iter().enumerate() {
+                    assert_eq!(packet.ray(lane), *ray);
+                }
+            }
+        };
+    }
+
+    new_ray_packet! { f32, new_ray_packet_f32 }
+    new_ray_packet! { f64, new_ray_packet_f64 }
+
+    macro_rules! ray_packet_transformed {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let rays = [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+                    ParametricLine::new(
+                        Point3::new(i as $type, 2.0, 3.0),
+                        Vector3::new(1.0, 0.0, i as $type),
+                    )
+                });
+                let transform = Transform3::<$type>::ident()
+                    .rotate_y(0.5 as $type)
+                    .translate(1.0, 2.0, 3.0);
+
+                let packet = RayPacket::<$type, 8>::new(rays)
+                    .transformed(transform.matrix)
+                    .translated([Vector3::new(0.0, 1.0, 0.0); 8]);
+
+                for (lane, ray) in rays.iter().enumerate() {
+                    let origin = transform.matrix * ray.origin + Vector3::new(0.0, 1.0, 0.0);
+                    assert_eq!(packet.ray(lane).origin, origin);
+                    assert_eq!(packet.ray(lane).direction, transform.matrix * ray.direction);
+                }
+            }
+        };
+    }
+
+    ray_packet_transformed! { f32, ray_packet_transformed_f32 }
+    ray_packet_transformed! { f64, ray_packet_transformed_f64 }
+}
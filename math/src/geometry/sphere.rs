@@ -1,12 +1,15 @@
 use std::ops::{Div, Mul};
 
-use super::{ImplicitNSphere, Intersect, ParametricLine, SurfacePoint};
+use super::{
+    scaled, ClosestPoint, ImplicitNSphere, Intersect, IntersectPacket, ParametricLine, RayPacket,
+    SampleSurface, SurfacePoint,
+};
 
-use crate::{Point2, Point3, Vector3};
+use crate::{Normal3, Point2, Point3, Vector3};
 use traits::floating_point::Pi;
 use traits::{
-    Acos, Atan2, ConvenientNumber, FloatingPoint, Half, Number, One, SelfMulNumber, SignedNumber,
-    Sqrt, Zero,
+    Acos, Atan2, Clamp, ConvenientNumber, Cos, FloatingPoint, Half, Number, One, SelfMulNumber,
+    SignedNumber, Sin, Sqrt, Zero,
 };
 
 pub type Sphere<T> = ImplicitNSphere<Point3<T>>;
@@ -93,6 +96,112 @@ where
         }
     }
 }
+
+impl<T, const N: usize> IntersectPacket<<T as Div>::Output, N> for Sphere<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint,
+    <T as Mul>::Output: Number<<T as Div>::Output>,
+{
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket<<T as Div>::Output, N>,
+        epsilon: <T as Div>::Output,
+    ) -> Option<[<T as Div>::Output; N]> {
+        let zero = <T as Div>::Output::zero();
+        let one = T::one();
+        let center = Point3::new(
+            self.center.x / one,
+            self.center.y / one,
+            self.center.z / one,
+        );
+        let radius = self.radius / one;
+
+        Some(std::array::from_fn(|lane| {
+            let oc = packet.origin(lane) - center;
+            let d = packet.direction(lane);
+
+            // The halved coefficient of the linear term saves the factors of two.
+            let a = d.dot(d);
+            let b = d.dot(oc);
+            let c = oc.dot(oc) - radius * radius;
+            let discriminant = b * b - a * c;
+
+            let root = if discriminant > zero {
+                discriminant.sqrt()
+            } else {
+                zero
+            };
+            let near = (-b - root) / a;
+            let far = (-b + root) / a;
+
+            if discriminant < zero {
+                <T as Div>::Output::INFINITY
+            } else if near > epsilon {
+                near
+            } else if far > epsilon {
+                far
+            } else {
+                <T as Div>::Output::INFINITY
+            }
+        }))
+    }
+}
+
+impl<T> SampleSurface<T> for Sphere<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber + Pi,
+    <T as Mul>::Output: Number<<T as Div>::Output>,
+{
+    fn sample_surface(
+        &self,
+        u: Point2<<T as Div>::Output>,
+    ) -> (SurfacePoint<T>, <T as Div>::Output) {
+        let one = <T as Div>::Output::one();
+        let pi = <T as Div>::Output::PI;
+
+        // Archimedes: slices of equal height cover equal areas of the sphere.
+        let y = one - u.x - u.x;
+        let r = (one - y * y).clamp(Zero::zero(), one).sqrt();
+        let phi = (u.y - one.half()) * (pi + pi);
+
+        let d = Vector3::new(r * phi.sin(), y, r * phi.cos());
+        let p = self.center + Vector3::new(self.radius * d.x, self.radius * d.y, self.radius * d.z);
+
+        let uv = Point2::new(
+            ((phi / pi).half() + one) % one,
+            one - y.clamp(-one, one).acos() / pi,
+        );
+
+        let radius = self.radius / T::one();
+        let area = (pi + pi + pi + pi) * radius * radius;
+
+        (SurfacePoint::new(p, d.as_normal(), uv), one / area)
+    }
+}
+
+impl<T> ClosestPoint<T> for Sphere<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    <T as Mul>::Output: Number<<T as Div>::Output>,
+{
+    fn closest_point(&self, p: Point3<T>) -> (Point3<T>, Normal3<<T as Div>::Output>) {
+        let d = (p - self.center) / T::one();
+        let length = d.magnitude();
+
+        // Every point of the surface is equally close to the center.
+        let n = if length > Zero::zero() {
+            d / length
+        } else {
+            Vector3::new(One::one(), Zero::zero(), Zero::zero())
+        };
+
+        (self.center + scaled(n, self.radius), n.as_normal())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,4 +346,87 @@ mod tests {
 
     parametric_line_intersect_sphere! { f32, parametric_line_intersect_sphere_f32 }
     parametric_line_intersect_sphere! { f64, parametric_line_intersect_sphere_f64 }
+
+    macro_rules! sample_sphere_surface {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let sphere =
+                    Sphere::new(Point3::new(1 as $type, 2 as $type, 3 as $type), 2 as $type);
+
+                for u in [
+                    Point2::new(0.1 as $type, 0.2 as $type),
+                    Point2::new(0.5 as $type, 0.9 as $type),
+                    Point2::new(0.8 as $type, 0.4 as $type),
+                ] {
+                    let (sp, pdf) = sphere.sample_surface(u);
+
+                    let d = sp.p - sphere.center;
+                    assert!((d.magnitude() - sphere.radius).abs() < 0.0001);
+                    assert!((d / sphere.radius - sp.n.as_vector()).magnitude() < 0.0001);
+                    assert!((pdf - 1.0 / (16.0 * <$type>::PI)).abs() < 0.0001);
+
+                    // A ray hitting the sampled point yields the same texture coordinates.
+                    let ray = ParametricLine::new(sp.p + d, -d);
+                    let (_, hit) = ray.intersect(sphere)[0];
+                    assert!((hit.p - sp.p).magnitude() < 0.0001);
+                    assert!((hit.uv - sp.uv).magnitude() < 0.0001);
+                }
+            }
+        };
+    }
+
+    sample_sphere_surface! { f32, sample_sphere_surface_f32 }
+    sample_sphere_surface! { f64, sample_sphere_surface_f64 }
+
+    macro_rules! sphere_closest_point {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let sphere = Sphere::new(Point3::new(1 as $type, 2.0, 3.0), 2.0);
+
+                let (p, n) = sphere.closest_point(Point3::new(1.0, 2.0, 8.0));
+                assert_eq!(p, Point3::new(1.0, 2.0, 5.0));
+                assert_eq!(n, Normal3::new(0.0, 0.0, 1.0));
+
+                let (p, n) = sphere.closest_point(Point3::new(1.0, 2.5, 3.0));
+                assert_eq!(p, Point3::new(1.0, 4.0, 3.0));
+                assert_eq!(n, Normal3::new(0.0, 1.0, 0.0));
+            }
+        };
+    }
+
+    sphere_closest_point! { f32, sphere_closest_point_f32 }
+    sphere_closest_point! { f64, sphere_closest_point_f64 }
+
+    macro_rules! sphere_intersect_packet {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let sphere = Sphere::new(Point3::new(0.0 as $type, 0.0, -5.0), 1.0);
+
+                // The rays pass the sphere, graze it and hit it, and the last one starts inside.
+                let mut rays = [-1.5, -1.0, -0.5, -0.1, 0.0, 0.4, 0.99, 0.0].map(|x: $type| {
+                    ParametricLine::new(Point3::new(x, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0))
+                });
+                rays[7].origin = Point3::new(0.0, 0.5, -5.0);
+
+                let hits = sphere
+                    .intersect_packet(&RayPacket::new(rays), 0.001)
+                    .unwrap();
+                for (ray, t) in rays.iter().zip(hits) {
+                    let expected = ray
+                        .intersect(sphere)
+                        .into_iter()
+                        .map(|(t, _)| t)
+                        .filter(|t| *t > 0.001)
+                        .fold(<$type>::INFINITY, <$type>::min);
+                    assert!(t == expected || (t - expected).abs() < 0.0001);
+                }
+            }
+        };
+    }
+
+    sphere_intersect_packet! { f32, sphere_intersect_packet_f32 }
+    sphere_intersect_packet! { f64, sphere_intersect_packet_f64 }
 }
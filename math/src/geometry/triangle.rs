@@ -1,10 +1,13 @@
 use std::fmt::Debug;
 use std::ops::{Div, Mul};
 
-use super::{Intersect, ParametricLine, SurfacePoint};
+use super::{
+    scaled, ClosestPoint, Intersect, IntersectPacket, ParametricLine, RayPacket, SampleSurface,
+    SurfacePoint,
+};
 
 use crate::{Mat3x3, Normal3, Point2, Point3, Vector3};
-use traits::{ConvenientNumber, FloatingPoint, Number, One, SelfMulNumber, Zero};
+use traits::{ConvenientNumber, FloatingPoint, Half, Number, One, SelfMulNumber, Sqrt, Zero};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Triangle3<T: Div>
@@ -112,6 +115,73 @@ where
     }
 }
 
+impl<T, const N: usize> IntersectPacket<<T as Div>::Output, N> for Triangle3<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint,
+{
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket<<T as Div>::Output, N>,
+        epsilon: <T as Div>::Output,
+    ) -> Option<[<T as Div>::Output; N]> {
+        Some(intersect_triangle_packet(
+            plain(self.a),
+            plain(self.b),
+            plain(self.c),
+            packet,
+            epsilon,
+        ))
+    }
+}
+
+impl<T> Triangle3<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+{
+    // The area in square units of the length type.
+    pub fn area(&self) -> <T as Div>::Output {
+        let ab = (self.b - self.a) / T::one();
+        let ac = (self.c - self.a) / T::one();
+        Vector3::cross(ab, ac).magnitude().half()
+    }
+
+    pub fn centroid(&self) -> Point3<T> {
+        let one = <T as Div>::Output::one();
+        self.a + ((self.b - self.a) + (self.c - self.a)) * (one / (one + one + one))
+    }
+}
+
+impl<T> SampleSurface<T> for Triangle3<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+{
+    fn sample_surface(
+        &self,
+        u: Point2<<T as Div>::Output>,
+    ) -> (SurfacePoint<T>, <T as Div>::Output) {
+        let one = <T as Div>::Output::one();
+
+        // Folding the unit square with a square root spreads the points evenly over the triangle.
+        let s = u.x.sqrt();
+        let beta = u.y * s;
+        let gamma = one - s;
+        let alpha = one - beta - gamma;
+
+        let p = self.a + (self.b - self.a) * beta + (self.c - self.a) * gamma;
+        let n = (self.na * alpha + self.nb * beta + self.nc * gamma)
+            .normalized()
+            .as_normal();
+        let uv = self.uva.as_vector() * alpha
+            + self.uvb.as_vector() * beta
+            + self.uvc.as_vector() * gamma;
+
+        (SurfacePoint::new(p, n, uv.as_point()), one / self.area())
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Face3 {
     a: usize,
@@ -158,6 +228,20 @@ pub struct Triangle3Mesh<T: Div> {
     faces: Vec<Face3>,
 }
 
+impl<T: Div + Clone> Clone for Triangle3Mesh<T>
+where
+    <T as Div>::Output: Clone,
+{
+    fn clone(&self) -> Self {
+        Triangle3Mesh {
+            vertices: self.vertices.clone(),
+            normals: self.normals.clone(),
+            uvs: self.uvs.clone(),
+            faces: self.faces.clone(),
+        }
+    }
+}
+
 impl<T: Div> Triangle3Mesh<T> {
     pub fn new(
         vertices: Vec<Point3<T>>,
@@ -172,6 +256,32 @@ impl<T: Div> Triangle3Mesh<T> {
             faces,
         }
     }
+
+    pub fn faces(&self) -> &[Face3] {
+        &self.faces
+    }
+}
+
+impl<T: Div> Triangle3Mesh<T>
+where
+    <T as Div>::Output: Copy + Debug + PartialEq,
+{
+    pub fn triangle(&self, face: &Face3) -> Triangle3<T>
+    where
+        T: Copy,
+    {
+        Triangle3::new(
+            self.vertices[face.a],
+            self.vertices[face.b],
+            self.vertices[face.c],
+            self.normals[face.na],
+            self.normals[face.nb],
+            self.normals[face.nc],
+            self.uvs[face.uva],
+            self.uvs[face.uvb],
+            self.uvs[face.uvc],
+        )
+    }
 }
 
 impl<T: Div> Intersect<&Triangle3Mesh<T>> for ParametricLine<Point3<T>, Vector3<T>>
@@ -188,24 +298,191 @@ where
         triangle_mesh
             .faces
             .iter()
-            .map(|face| {
-                Triangle3::new(
-                    triangle_mesh.vertices[face.a],
-                    triangle_mesh.vertices[face.b],
-                    triangle_mesh.vertices[face.c],
-                    triangle_mesh.normals[face.na],
-                    triangle_mesh.normals[face.nb],
-                    triangle_mesh.normals[face.nc],
-                    triangle_mesh.uvs[face.uva],
-                    triangle_mesh.uvs[face.uvb],
-                    triangle_mesh.uvs[face.uvc],
-                )
-            })
+            .map(|face| triangle_mesh.triangle(face))
             .flat_map(|triangle| self.intersect(triangle))
             .collect()
     }
 }
 
+impl<T, const N: usize> IntersectPacket<<T as Div>::Output, N> for Triangle3Mesh<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint,
+{
+    fn intersect_packet(
+        &self,
+        packet: &RayPacket<<T as Div>::Output, N>,
+        epsilon: <T as Div>::Output,
+    ) -> Option<[<T as Div>::Output; N]> {
+        let mut closest = [<T as Div>::Output::INFINITY; N];
+        for face in &self.faces {
+            let hits = intersect_triangle_packet(
+                plain(self.vertices[face.a]),
+                plain(self.vertices[face.b]),
+                plain(self.vertices[face.c]),
+                packet,
+                epsilon,
+            );
+            for (t, hit) in closest.iter_mut().zip(hits) {
+                if hit < *t {
+                    *t = hit;
+                }
+            }
+        }
+        Some(closest)
+    }
+}
+
+impl<T> Triangle3Mesh<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint,
+{
+    // Whether a ray hits any face between two ray parameters. The faces are tested until the first
+    // hit, without computing the surface point, as shadow rays only need to know whether they are
+    // blocked.
+    pub fn intersects_any(
+        &self,
+        ray: ParametricLine<Point3<T>, Vector3<T>>,
+        t_min: <T as Div>::Output,
+        t_max: <T as Div>::Output,
+    ) -> bool {
+        let packet = RayPacket::new([ray]);
+        self.faces.iter().any(|face| {
+            let [t] = intersect_triangle_packet(
+                plain(self.vertices[face.a]),
+                plain(self.vertices[face.b]),
+                plain(self.vertices[face.c]),
+                &packet,
+                t_min,
+            );
+            t < t_max
+        })
+    }
+}
+
+// The point in plain values, for packets of rays.
+fn plain<T>(p: Point3<T>) -> Point3<<T as Div>::Output>
+where
+    T: Div + One + Copy,
+{
+    let one = T::one();
+    Point3::new(p.x / one, p.y / one, p.z / one)
+}
+
+// Solves for the barycentric coordinates and the ray parameter with Cramer's rule, like the test of
+// a single ray. The determinants are the triple products of their columns.
+fn intersect_triangle_packet<V, const N: usize>(
+    a: Point3<V>,
+    b: Point3<V>,
+    c: Point3<V>,
+    packet: &RayPacket<V, N>,
+    epsilon: V,
+) -> [V; N]
+where
+    V: FloatingPoint,
+{
+    let zero = V::zero();
+    let one = V::one();
+    let ab = a - b;
+    let ac = a - c;
+
+    std::array::from_fn(|lane| {
+        let d = packet.direction(lane);
+        let v = a - packet.origin(lane);
+
+        let ac_d = Vector3::cross(ac, d);
+        let determinant = ab.dot(ac_d);
+        let beta = v.dot(ac_d) / determinant;
+        let gamma = ab.dot(Vector3::cross(v, d)) / determinant;
+        let t = ab.dot(Vector3::cross(ac, v)) / determinant;
+
+        if determinant != zero
+            && beta >= zero
+            && gamma >= zero
+            && beta + gamma <= one
+            && t > epsilon
+        {
+            t
+        } else {
+            V::INFINITY
+        }
+    })
+}
+
+impl<T> ClosestPoint<T> for Triangle3<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+{
+    // The normal is the one of the plane of the triangle, on the side of the vertex normals.
+    fn closest_point(&self, p: Point3<T>) -> (Point3<T>, Normal3<<T as Div>::Output>) {
+        let ab = (self.b - self.a) / T::one();
+        let ac = (self.c - self.a) / T::one();
+        let ap = (p - self.a) / T::one();
+
+        let n = Vector3::cross(ab, ac).normalized();
+        let vertex_normals = self.na.as_vector() + self.nb.as_vector() + self.nc.as_vector();
+        let n = if n.dot(vertex_normals) < Zero::zero() {
+            -n
+        } else {
+            n
+        };
+
+        (
+            self.a + scaled(closest_offset(ab, ac, ap), T::one()),
+            n.as_normal(),
+        )
+    }
+}
+
+// Ericson, "Real-Time Collision Detection", 5.1.5. Finds the region of the triangle (a vertex, an
+// edge, or the face) the projection of p falls into. All vectors are relative to the vertex a.
+fn closest_offset<V>(ab: Vector3<V>, ac: Vector3<V>, ap: Vector3<V>) -> Vector3<V>
+where
+    V: FloatingPoint,
+{
+    let zero = V::zero();
+
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= zero && d2 <= zero {
+        return Vector3::new(zero, zero, zero);
+    }
+
+    let bp = ap - ab;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= zero && d4 <= d3 {
+        return ab;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= zero && d1 >= zero && d3 <= zero {
+        return ab * (d1 / (d1 - d3));
+    }
+
+    let cp = ap - ac;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= zero && d5 <= d6 {
+        return ac;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= zero && d2 >= zero && d6 <= zero {
+        return ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= zero && d4 - d3 >= zero && d5 - d6 >= zero {
+        return ab + (ac - ab) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denominator = V::one() / (va + vb + vc);
+    ab * (vb * denominator) + ac * (vc * denominator)
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -517,4 +794,206 @@ pub mod tests {
 
     parametric_line_intersect_triangle_3_mesh! { f32, parametric_line_intersect_triangle_3_mesh_f32 }
     parametric_line_intersect_triangle_3_mesh! { f64, parametric_line_intersect_triangle_3_mesh_f64 }
+
+    macro_rules! sample_triangle_surface {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let n = Normal3::new(0 as $type, 0 as $type, 1 as $type);
+                let triangle = Triangle3::new(
+                    Point3::new(0 as $type, 0 as $type, 0 as $type),
+                    Point3::new(2 as $type, 0 as $type, 0 as $type),
+                    Point3::new(0 as $type, 2 as $type, 0 as $type),
+                    n,
+                    n,
+                    n,
+                    Point2::new(0 as $type, 0 as $type),
+                    Point2::new(1 as $type, 0 as $type),
+                    Point2::new(0 as $type, 1 as $type),
+                );
+
+                assert_eq!(triangle.area(), 2 as $type);
+
+                let (a, pdf) = triangle.sample_surface(Point2::new(1 as $type, 0 as $type));
+                let (b, _) = triangle.sample_surface(Point2::new(1 as $type, 1 as $type));
+                let (c, _) = triangle.sample_surface(Point2::new(0 as $type, 0.5 as $type));
+                let (p, _) = triangle.sample_surface(Point2::new(0.25 as $type, 0.5 as $type));
+
+                assert_eq!(pdf, 0.5 as $type);
+                assert_eq!(a, SurfacePoint::new(triangle.a, n, triangle.uva));
+                assert_eq!(b, SurfacePoint::new(triangle.b, n, triangle.uvb));
+                assert_eq!(c, SurfacePoint::new(triangle.c, n, triangle.uvc));
+                assert_eq!(
+                    p,
+                    SurfacePoint::new(
+                        Point3::new(0.5 as $type, 1 as $type, 0 as $type),
+                        n,
+                        Point2::new(0.25 as $type, 0.5 as $type)
+                    )
+                );
+            }
+        };
+    }
+
+    sample_triangle_surface! { f32, sample_triangle_surface_f32 }
+    sample_triangle_surface! { f64, sample_triangle_surface_f64 }
+
+    macro_rules! triangle_closest_point {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let triangle = |normal: Normal3<$type>| {
+                    Triangle3::new(
+                        Point3::new(0 as $type, 0.0, 0.0),
+                        Point3::new(1.0, 0.0, 0.0),
+                        Point3::new(0.0, 1.0, 0.0),
+                        normal,
+                        normal,
+                        normal,
+                        Point2::new(0.0, 0.0),
+                        Point2::new(1.0, 0.0),
+                        Point2::new(0.0, 1.0),
+                    )
+                };
+                let front = triangle(Normal3::new(0.0, 0.0, 1.0));
+
+                let (p, n) = front.closest_point(Point3::new(0.25, 0.25, 2.0));
+                assert_eq!(p, Point3::new(0.25, 0.25, 0.0));
+                assert_eq!(n, Normal3::new(0.0, 0.0, 1.0));
+
+                // The vertices and edges.
+                assert_eq!(
+                    front.closest_point(Point3::new(2.0, -1.0, 0.0)).0,
+                    Point3::new(1.0, 0.0, 0.0)
+                );
+                assert_eq!(
+                    front.closest_point(Point3::new(1.0, 1.0, 0.0)).0,
+                    Point3::new(0.5, 0.5, 0.0)
+                );
+                assert_eq!(
+                    front.closest_point(Point3::new(-1.0, 0.5, 0.0)).0,
+                    Point3::new(0.0, 0.5, 0.0)
+                );
+                assert_eq!(
+                    front.closest_point(Point3::new(-1.0, -1.0, 1.0)).0,
+                    Point3::new(0.0, 0.0, 0.0)
+                );
+
+                let back = triangle(Normal3::new(0.0, 0.0, -1.0));
+                assert_eq!(
+                    back.closest_point(Point3::new(0.25, 0.25, 2.0)).1,
+                    Normal3::new(0.0, 0.0, -1.0)
+                );
+            }
+        };
+    }
+
+    triangle_closest_point! { f32, triangle_closest_point_f32 }
+    triangle_closest_point! { f64, triangle_closest_point_f64 }
+
+    macro_rules! triangle_intersect_packet {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let vertices = vec![
+                    Point3::new(-1.0 as $type, -1.0, -2.0),
+                    Point3::new(1.0, -1.0, -2.0),
+                    Point3::new(1.0, 1.0, -2.0),
+                    Point3::new(-1.0, 1.0, -3.0),
+                ];
+                let normals = vec![Normal3::z_axis()];
+                let uvs = vec![Point2::new(0.0, 0.0)];
+                let faces = vec![
+                    Face3::new(0, 1, 2, 0, 0, 0, 0, 0, 0),
+                    Face3::new(0, 2, 3, 0, 0, 0, 0, 0, 0),
+                ];
+                let mesh = Triangle3Mesh::new(vertices, normals, uvs, faces);
+                let triangle = mesh.triangle(&mesh.faces()[0]);
+
+                // The rays hit one triangle, the other, both edges and neither.
+                let rays = [
+                    (0.5, -0.5),
+                    (-0.5, 0.5),
+                    (0.0, 0.0),
+                    (0.99, 0.99),
+                    (1.5, 0.0),
+                    (-0.9, -0.95),
+                    (0.0, 2.0),
+                    (-2.0, -2.0),
+                ]
+                .map(|(x, y): ($type, $type)| {
+                    ParametricLine::new(Point3::new(x, y, 0.0), Vector3::new(0.0, 0.0, -1.0))
+                });
+
+                let hits = triangle
+                    .intersect_packet(&RayPacket::new(rays), 0.001)
+                    .unwrap();
+                for (ray, t) in rays.iter().zip(hits) {
+                    let expected = ray
+                        .intersect(triangle)
+                        .into_iter()
+                        .map(|(t, _)| t)
+                        .filter(|t| *t > 0.001)
+                        .fold(<$type>::INFINITY, <$type>::min);
+                    assert!(t == expected || (t - expected).abs() < 0.0001);
+                }
+
+                let hits = mesh.intersect_packet(&RayPacket::new(rays), 0.001).unwrap();
+                for (ray, t) in rays.iter().zip(hits) {
+                    let expected = ray
+                        .intersect(&mesh)
+                        .into_iter()
+                        .map(|(t, _)| t)
+                        .filter(|t| *t > 0.001)
+                        .fold(<$type>::INFINITY, <$type>::min);
+                    assert!(t == expected || (t - expected).abs() < 0.0001);
+                }
+            }
+        };
+    }
+
+    triangle_intersect_packet! { f32, triangle_intersect_packet_f32 }
+    triangle_intersect_packet! { f64, triangle_intersect_packet_f64 }
+
+    macro_rules! triangle_mesh_intersects_any {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let vertices = vec![
+                    Point3::new(-1.0 as $type, -1.0, -2.0),
+                    Point3::new(1.0, -1.0, -2.0),
+                    Point3::new(0.0, 1.0, -2.0),
+                    Point3::new(-1.0, -1.0, -4.0),
+                    Point3::new(1.0, -1.0, -4.0),
+                    Point3::new(0.0, 1.0, -4.0),
+                ];
+                let normals = vec![Normal3::z_axis()];
+                let uvs = vec![Point2::new(0.0, 0.0)];
+                let faces = vec![
+                    Face3::new(0, 1, 2, 0, 0, 0, 0, 0, 0),
+                    Face3::new(3, 4, 5, 0, 0, 0, 0, 0, 0),
+                ];
+                let mesh = Triangle3Mesh::new(vertices, normals, uvs, faces);
+
+                let ray = ParametricLine::new(
+                    Point3::new(0.0 as $type, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, -1.0),
+                );
+                assert!(mesh.intersects_any(ray, 0.001, <$type>::INFINITY));
+                assert!(mesh.intersects_any(ray, 0.001, 3.0));
+                assert!(mesh.intersects_any(ray, 3.0, 5.0));
+                assert!(!mesh.intersects_any(ray, 0.001, 1.0));
+                assert!(!mesh.intersects_any(ray, 4.5, <$type>::INFINITY));
+
+                let miss = ParametricLine::new(
+                    Point3::new(2.0 as $type, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, -1.0),
+                );
+                assert!(!mesh.intersects_any(miss, 0.001, <$type>::INFINITY));
+            }
+        };
+    }
+
+    triangle_mesh_intersects_any! { f32, triangle_mesh_intersects_any_f32 }
+    triangle_mesh_intersects_any! { f64, triangle_mesh_intersects_any_f64 }
 }
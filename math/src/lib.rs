@@ -1,5 +1,6 @@
 pub mod geometry;
 mod mat;
+pub mod noise;
 pub mod normal;
 mod point;
 pub mod transform;
@@ -0,0 +1,161 @@
+pub mod curl;
+pub mod fractal;
+pub mod perlin;
+pub mod simplex;
+pub mod worley;
+
+pub use curl::Curl;
+pub use fractal::{Fbm, Turbulence};
+pub use perlin::Perlin;
+pub use simplex::Simplex;
+pub use worley::Worley;
+
+use traits::FloatingPoint;
+
+use crate::{Point3, Vector3};
+
+// A smooth pseudo-random function of space. The same seed always gives the same noise. The
+// gradient is returned along with the value, so bumps can be derived from the noise without
+// finite differences.
+pub trait Noise<V> {
+    fn value(&self, p: Point3<V>) -> V {
+        self.value_and_gradient(p).0
+    }
+
+    fn value_and_gradient(&self, p: Point3<V>) -> (V, Vector3<V>);
+}
+
+impl<V, N: Noise<V> + ?Sized> Noise<V> for Box<N> {
+    fn value_and_gradient(&self, p: Point3<V>) -> (V, Vector3<V>) {
+        (**self).value_and_gradient(p)
+    }
+}
+
+// A shuffled table of the numbers up to 255, which hashes the cells of the integer lattice the
+// noise functions are built on. The noise repeats every 256 units.
+#[derive(Debug, PartialEq, Clone)]
+struct Permutation {
+    table: [u8; 256],
+}
+
+impl Permutation {
+    fn new(seed: u64) -> Permutation {
+        let mut table = [0; 256];
+        for (index, entry) in table.iter_mut().enumerate() {
+            *entry = index as u8;
+        }
+
+        let mut state = seed;
+        for index in (1..table.len()).rev() {
+            let other = (split_mix(&mut state) % (index as u64 + 1)) as usize;
+            table.swap(index, other);
+        }
+
+        Permutation { table }
+    }
+
+    fn hash(&self, x: usize, y: usize, z: usize) -> usize {
+        let h = self.table[x & 255] as usize;
+        let h = self.table[(h + y) & 255] as usize;
+        self.table[(h + z) & 255] as usize
+    }
+
+    // A pseudo-random number of the unit interval for a hash. Each index gives another number.
+    fn unit<V>(&self, hash: usize, index: usize) -> V
+    where
+        V: FloatingPoint,
+        u16: Into<V>,
+    {
+        let high = self.table[(hash + 85 * index) & 255] as u16;
+        let low = self.table[(high as usize + index + 1) & 255] as u16;
+        ((high << 8) | low).into() / u16::MAX.into()
+    }
+}
+
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// The cell of the integer lattice x lies in, wrapped to the size of the permutation table, and
+// the position of x within the cell.
+fn lattice<V>(x: V) -> (usize, V)
+where
+    V: FloatingPoint,
+    u16: Into<V>,
+{
+    let cell = x.floor();
+    let size: V = 256u16.into();
+    let wrapped = cell - (cell / size).floor() * size;
+
+    let mut index = 0;
+    for step in [128, 64, 32, 16, 8, 4, 2, 1] {
+        if to_value::<V>(index + step) <= wrapped {
+            index += step;
+        }
+    }
+
+    (index, x - cell)
+}
+
+// One of the twelve directions towards the edges of a cube, as proposed by Perlin in "Improving
+// Noise".
+fn gradient<V: FloatingPoint>(hash: usize) -> Vector3<V> {
+    let one = V::one();
+    let zero = V::zero();
+    match hash % 12 {
+        0 => Vector3::new(one, one, zero),
+        1 => Vector3::new(-one, one, zero),
+        2 => Vector3::new(one, -one, zero),
+        3 => Vector3::new(-one, -one, zero),
+        4 => Vector3::new(one, zero, one),
+        5 => Vector3::new(-one, zero, one),
+        6 => Vector3::new(one, zero, -one),
+        7 => Vector3::new(-one, zero, -one),
+        8 => Vector3::new(zero, one, one),
+        9 => Vector3::new(zero, -one, one),
+        10 => Vector3::new(zero, one, -one),
+        _ => Vector3::new(zero, -one, -one),
+    }
+}
+
+fn to_value<V>(value: usize) -> V
+where
+    u16: Into<V>,
+{
+    (value as u16).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! lattice_wraps_cells {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                assert_eq!(lattice::<$type>(3.25), (3, 0.25));
+                assert_eq!(lattice::<$type>(-0.75), (255, 0.25));
+                assert_eq!(lattice::<$type>(-0.0), (0, 0.0));
+                assert_eq!(lattice::<$type>(513.5), (1, 0.5));
+
+                let permutation = Permutation::new(7);
+                assert_eq!(permutation, Permutation::new(7));
+                assert_ne!(permutation, Permutation::new(8));
+
+                let mut sorted = permutation.table;
+                sorted.sort();
+                assert!(sorted.iter().enumerate().all(|(i, e)| i == *e as usize));
+
+                let unit: $type = permutation.unit(17, 2);
+                assert!((0.0..=1.0).contains(&unit));
+            }
+        };
+    }
+
+    lattice_wraps_cells! { f32, lattice_wraps_cells_f32 }
+    lattice_wraps_cells! { f64, lattice_wraps_cells_f64 }
+}
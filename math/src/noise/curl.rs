@@ -0,0 +1,70 @@
+use traits::FloatingPoint;
+
+use super::{to_value, Noise};
+use crate::{Point3, Vector3};
+
+// A flow field without sources and sinks, after Bridson et al., "Curl-Noise for Procedural Fluid
+// Flow". It is the curl of a vector potential made of three copies of the noise, shifted far
+// apart, so particles advected by it swirl around without bunching up.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Curl<N> {
+    pub noise: N,
+}
+
+impl<N> Curl<N> {
+    pub fn new(noise: N) -> Curl<N> {
+        Curl { noise }
+    }
+
+    pub fn velocity<V>(&self, p: Point3<V>) -> Vector3<V>
+    where
+        N: Noise<V>,
+        V: FloatingPoint,
+        u16: Into<V>,
+    {
+        let shifted = |x: usize, y: usize, z: usize| {
+            let offset = Vector3::new(to_value(x), to_value(y), to_value(z));
+            self.noise.value_and_gradient(p + offset).1
+        };
+        let a = shifted(0, 0, 0);
+        let b = shifted(31, 67, 113);
+        let c = shifted(149, 17, 79);
+
+        Vector3::new(c.y - b.z, a.z - c.x, b.x - a.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::noise::Perlin;
+
+    macro_rules! curl_noise_is_divergence_free {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let curl = Curl::new(Perlin::new(7));
+
+                let h = 0.001;
+                for i in 0..20 {
+                    let t = i as $type * 0.41;
+                    let p = Point3::<$type>::new(t, 2.0 - t * 0.6, t * 1.7 - 1.0);
+                    assert!(curl.velocity(p).magnitude() > 0.0);
+
+                    let divergence = (curl.velocity(Point3::new(p.x + h, p.y, p.z)).x
+                        - curl.velocity(Point3::new(p.x - h, p.y, p.z)).x
+                        + curl.velocity(Point3::new(p.x, p.y + h, p.z)).y
+                        - curl.velocity(Point3::new(p.x, p.y - h, p.z)).y
+                        + curl.velocity(Point3::new(p.x, p.y, p.z + h)).z
+                        - curl.velocity(Point3::new(p.x, p.y, p.z - h)).z)
+                        / (2.0 * h);
+                    assert!(divergence.abs() < 0.05);
+                }
+            }
+        };
+    }
+
+    curl_noise_is_divergence_free! { f32, curl_noise_is_divergence_free_f32 }
+    curl_noise_is_divergence_free! { f64, curl_noise_is_divergence_free_f64 }
+}
@@ -0,0 +1,186 @@
+use traits::{ConvenientNumber, FloatingPoint};
+
+use super::Noise;
+use crate::{Point3, Vector3};
+
+// Fractional Brownian motion sums octaves of a noise. Each octave has the lacunarity times the
+// frequency and the gain times the amplitude of the one before. The sum is divided by the sum of
+// the amplitudes, so it stays in the range of the noise.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Fbm<N, V> {
+    pub noise: N,
+    pub octaves: usize,
+    pub lacunarity: V,
+    pub gain: V,
+}
+
+impl<N, V: FloatingPoint + ConvenientNumber> Fbm<N, V> {
+    pub fn new(noise: N, octaves: usize) -> Fbm<N, V> {
+        Fbm {
+            noise,
+            octaves,
+            lacunarity: V::one() + V::one(),
+            gain: V::one().half(),
+        }
+    }
+
+    pub fn with_lacunarity(self, lacunarity: V) -> Fbm<N, V> {
+        Fbm { lacunarity, ..self }
+    }
+
+    pub fn with_gain(self, gain: V) -> Fbm<N, V> {
+        Fbm { gain, ..self }
+    }
+}
+
+impl<N, V> Noise<V> for Fbm<N, V>
+where
+    N: Noise<V>,
+    V: FloatingPoint + ConvenientNumber,
+{
+    fn value_and_gradient(&self, p: Point3<V>) -> (V, Vector3<V>) {
+        octaves(
+            &self.noise,
+            self.octaves,
+            self.lacunarity,
+            self.gain,
+            p,
+            false,
+        )
+    }
+}
+
+// Like fractional Brownian motion, but sums the absolute values of the octaves. The creases where
+// the noise changes its sign give it the look of fire, smoke or marble veins. The values of
+// gradient noises lie in [0, 1].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Turbulence<N, V> {
+    pub noise: N,
+    pub octaves: usize,
+    pub lacunarity: V,
+    pub gain: V,
+}
+
+impl<N, V: FloatingPoint + ConvenientNumber> Turbulence<N, V> {
+    pub fn new(noise: N, octaves: usize) -> Turbulence<N, V> {
+        Turbulence {
+            noise,
+            octaves,
+            lacunarity: V::one() + V::one(),
+            gain: V::one().half(),
+        }
+    }
+
+    pub fn with_lacunarity(self, lacunarity: V) -> Turbulence<N, V> {
+        Turbulence { lacunarity, ..self }
+    }
+
+    pub fn with_gain(self, gain: V) -> Turbulence<N, V> {
+        Turbulence { gain, ..self }
+    }
+}
+
+impl<N, V> Noise<V> for Turbulence<N, V>
+where
+    N: Noise<V>,
+    V: FloatingPoint + ConvenientNumber,
+{
+    fn value_and_gradient(&self, p: Point3<V>) -> (V, Vector3<V>) {
+        octaves(
+            &self.noise,
+            self.octaves,
+            self.lacunarity,
+            self.gain,
+            p,
+            true,
+        )
+    }
+}
+
+fn octaves<N, V>(
+    noise: &N,
+    octaves: usize,
+    lacunarity: V,
+    gain: V,
+    p: Point3<V>,
+    absolute: bool,
+) -> (V, Vector3<V>)
+where
+    N: Noise<V>,
+    V: FloatingPoint + ConvenientNumber,
+{
+    let zero = V::zero();
+    let mut value = zero;
+    let mut gradient = Vector3::new(zero, zero, zero);
+    let mut amplitude = V::one();
+    let mut frequency = V::one();
+    let mut amplitudes = zero;
+
+    for _ in 0..octaves {
+        let q = Point3::new(p.x * frequency, p.y * frequency, p.z * frequency);
+        let (octave, octave_gradient) = noise.value_and_gradient(q);
+        let (octave, octave_gradient) = if absolute && octave < zero {
+            (-octave, -octave_gradient)
+        } else {
+            (octave, octave_gradient)
+        };
+
+        value += octave * amplitude;
+        gradient += octave_gradient * (amplitude * frequency);
+        amplitudes += amplitude;
+
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+
+    if amplitudes == zero {
+        return (value, gradient);
+    }
+
+    (value / amplitudes, gradient / amplitudes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::noise::{Perlin, Simplex};
+
+    macro_rules! fractal_noise {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let p = Point3::<$type>::new(1.3, -4.7, 0.45);
+
+                // A single octave is the noise itself.
+                assert_eq!(
+                    Fbm::new(Perlin::new(3), 1).value_and_gradient(p),
+                    Perlin::new(3).value_and_gradient(p)
+                );
+                assert_eq!(Fbm::new(Perlin::new(3), 0).value(p), 0.0);
+
+                let fbm = Fbm::new(Simplex::new(3), 5).with_gain(0.6);
+                let turbulence = Turbulence::new(Simplex::new(3), 5).with_lacunarity(2.5);
+
+                let h = 0.0005;
+                for i in 0..50 {
+                    let t = i as $type * 0.37;
+                    let p = Point3::<$type>::new(t, t * 0.71 - 3.0, 5.0 - t * 1.3);
+
+                    let (value, gradient) = fbm.value_and_gradient(p);
+                    assert!(value.abs() <= 1.1);
+                    let dx = (fbm.value(Point3::new(p.x + h, p.y, p.z))
+                        - fbm.value(Point3::new(p.x - h, p.y, p.z)))
+                        / (2.0 * h);
+                    assert!((gradient.x - dx).abs() < 0.05);
+
+                    let value = turbulence.value(p);
+                    assert!((0.0..=1.1).contains(&value));
+                }
+            }
+        };
+    }
+
+    fractal_noise! { f32, fractal_noise_f32 }
+    fractal_noise! { f64, fractal_noise_f64 }
+}
@@ -0,0 +1,134 @@
+use traits::{ConvenientNumber, FloatingPoint};
+
+use super::{gradient, lattice, to_value, Noise, Permutation};
+use crate::{Point3, Vector3};
+
+// Gradient noise after Perlin, "Improving Noise". Each point of the integer lattice gets a
+// gradient, the noise interpolates between the planes they span with a quintic curve. The values
+// lie roughly in [-1, 1] and are zero on the lattice points.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Perlin {
+    permutation: Permutation,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        Perlin {
+            permutation: Permutation::new(seed),
+        }
+    }
+}
+
+impl<V> Noise<V> for Perlin
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    fn value_and_gradient(&self, p: Point3<V>) -> (V, Vector3<V>) {
+        let (x, fx) = lattice(p.x);
+        let (y, fy) = lattice(p.y);
+        let (z, fz) = lattice(p.z);
+
+        // The value of the plane of a corner at p and the gradient of the corner.
+        let corner = |i: usize, j: usize, k: usize| {
+            let g: Vector3<V> = gradient(self.permutation.hash(x + i, y + j, z + k));
+            let d = Vector3::new(fx - to_value(i), fy - to_value(j), fz - to_value(k));
+            (g.dot(d), g)
+        };
+        let (n000, g000) = corner(0, 0, 0);
+        let (n100, g100) = corner(1, 0, 0);
+        let (n010, g010) = corner(0, 1, 0);
+        let (n110, g110) = corner(1, 1, 0);
+        let (n001, g001) = corner(0, 0, 1);
+        let (n101, g101) = corner(1, 0, 1);
+        let (n011, g011) = corner(0, 1, 1);
+        let (n111, g111) = corner(1, 1, 1);
+
+        let (u, du) = fade(fx);
+        let (v, dv) = fade(fy);
+        let (w, dw) = fade(fz);
+
+        // The trilinear interpolation, expanded into a polynomial of u, v and w.
+        let k1 = n100 - n000;
+        let k2 = n010 - n000;
+        let k3 = n001 - n000;
+        let k4 = n000 - n100 - n010 + n110;
+        let k5 = n000 - n010 - n001 + n011;
+        let k6 = n000 - n100 - n001 + n101;
+        let k7 = n100 + n010 + n001 + n111 - n000 - n110 - n101 - n011;
+
+        let value =
+            n000 + k1 * u + k2 * v + k3 * w + k4 * u * v + k5 * v * w + k6 * w * u + k7 * u * v * w;
+
+        let interpolated = g000
+            + (g100 - g000) * u
+            + (g010 - g000) * v
+            + (g001 - g000) * w
+            + (g000 - g100 - g010 + g110) * (u * v)
+            + (g000 - g010 - g001 + g011) * (v * w)
+            + (g000 - g100 - g001 + g101) * (w * u)
+            + (g100 + g010 + g001 + g111 - g000 - g110 - g101 - g011) * (u * v * w);
+        let gradient = interpolated
+            + Vector3::new(
+                du * (k1 + k4 * v + k6 * w + k7 * v * w),
+                dv * (k2 + k5 * w + k4 * u + k7 * w * u),
+                dw * (k3 + k6 * u + k5 * v + k7 * u * v),
+            );
+
+        (value, gradient)
+    }
+}
+
+// 6t⁵ - 15t⁴ + 10t³ and its derivative.
+fn fade<V>(t: V) -> (V, V)
+where
+    V: FloatingPoint,
+    u16: Into<V>,
+{
+    let t2 = t * t;
+    let value = t2 * t * (t * (t * to_value(6) - to_value(15)) + to_value(10));
+    let derivative = to_value::<V>(30) * t2 * (t - V::one()) * (t - V::one());
+    (value, derivative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! perlin_noise {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let noise = Perlin::new(42);
+
+                assert_eq!(noise.value(Point3::<$type>::new(3.0, -2.0, 7.0)), 0.0);
+
+                let p = Point3::<$type>::new(1.3, -4.7, 0.45);
+                assert_eq!(noise.value(p), Perlin::new(42).value(p));
+                assert_ne!(noise.value(p), Perlin::new(43).value(p));
+
+                let h = 0.001;
+                for i in 0..50 {
+                    let t = i as $type * 0.37;
+                    let p = Point3::<$type>::new(t, t * 0.71 - 3.0, 5.0 - t * 1.3);
+                    let (value, gradient) = noise.value_and_gradient(p);
+                    assert!(value.abs() <= 1.1);
+
+                    let dx = (noise.value(Point3::new(p.x + h, p.y, p.z))
+                        - noise.value(Point3::new(p.x - h, p.y, p.z)))
+                        / (2.0 * h);
+                    let dy = (noise.value(Point3::new(p.x, p.y + h, p.z))
+                        - noise.value(Point3::new(p.x, p.y - h, p.z)))
+                        / (2.0 * h);
+                    let dz = (noise.value(Point3::new(p.x, p.y, p.z + h))
+                        - noise.value(Point3::new(p.x, p.y, p.z - h)))
+                        / (2.0 * h);
+                    assert!((gradient - Vector3::new(dx, dy, dz)).magnitude() < 0.01);
+                }
+            }
+        };
+    }
+
+    perlin_noise! { f32, perlin_noise_f32 }
+    perlin_noise! { f64, perlin_noise_f64 }
+}
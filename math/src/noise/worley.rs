@@ -0,0 +1,103 @@
+use traits::{ConvenientNumber, FloatingPoint};
+
+use super::{lattice, to_value, Noise, Permutation};
+use crate::{Point3, Vector3};
+
+// Cellular noise after Worley, "A Cellular Texture Basis Function". Each cell of the integer
+// lattice holds a random feature point, the value is the distance to the closest one. It is zero
+// at the feature points and rarely exceeds one. The gradient points away from the closest feature
+// point.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Worley {
+    permutation: Permutation,
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Worley {
+        Worley {
+            permutation: Permutation::new(seed),
+        }
+    }
+}
+
+impl<V> Noise<V> for Worley
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    fn value_and_gradient(&self, p: Point3<V>) -> (V, Vector3<V>) {
+        let (x, fx) = lattice(p.x);
+        let (y, fy) = lattice(p.y);
+        let (z, fz) = lattice(p.z);
+
+        // The vector from the closest feature point to p. The closest one lies in the cell of p or
+        // one of its neighbors.
+        let mut closest: Option<(V, Vector3<V>)> = None;
+        for k in 0..3 {
+            for j in 0..3 {
+                for i in 0..3 {
+                    // Wrapping around by 255 is one cell back.
+                    let hash = self.permutation.hash(x + 255 + i, y + 255 + j, z + 255 + k);
+                    let one = V::one();
+                    let feature = Vector3::new(
+                        to_value::<V>(i) - one + self.permutation.unit(hash, 0),
+                        to_value::<V>(j) - one + self.permutation.unit(hash, 1),
+                        to_value::<V>(k) - one + self.permutation.unit(hash, 2),
+                    );
+                    let d = Vector3::new(fx, fy, fz) - feature;
+                    let distance = d.dot(d);
+                    if closest.is_none_or(|(closest, _)| distance < closest) {
+                        closest = Some((distance, d));
+                    }
+                }
+            }
+        }
+
+        let zero = V::zero();
+        match closest {
+            Some((distance, d)) if distance > zero => {
+                let distance = distance.sqrt();
+                (distance, d / distance)
+            }
+            _ => (zero, Vector3::new(zero, zero, zero)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! worley_noise {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let noise = Worley::new(42);
+
+                let p = Point3::<$type>::new(1.3, -4.7, 0.45);
+                assert_eq!(noise.value(p), Worley::new(42).value(p));
+                assert_ne!(noise.value(p), Worley::new(43).value(p));
+
+                for i in 0..50 {
+                    let t = i as $type * 0.37;
+                    let p = Point3::<$type>::new(t, t * 0.71 - 3.0, 5.0 - t * 1.3);
+                    let (value, gradient) = noise.value_and_gradient(p);
+                    assert!((0.0..2.0).contains(&value));
+                    assert!((gradient.magnitude() - 1.0).abs() < 0.001);
+
+                    // Stepping towards the closest feature point gets closer to it.
+                    let step = 0.5 * value;
+                    let closer = noise.value(Point3::new(
+                        p.x - gradient.x * step,
+                        p.y - gradient.y * step,
+                        p.z - gradient.z * step,
+                    ));
+                    assert!((closer - 0.5 * value).abs() < 0.001);
+                }
+            }
+        };
+    }
+
+    worley_noise! { f32, worley_noise_f32 }
+    worley_noise! { f64, worley_noise_f64 }
+}
@@ -24,6 +24,18 @@ impl WichmannHillPRNG {
         WichmannHillPRNG::new(s1 as u32, s2 as u32, s3 as u32)
     }
 
+    // The state only depends on the seed and the index, so every pixel or sample can get its own
+    // generator without caring about the order in which they are evaluated.
+    pub fn for_index(seed: u128, index: u128) -> WichmannHillPRNG {
+        let h = hash(seed, index);
+
+        WichmannHillPRNG::new(
+            (h % 30268) as u32 + 1,
+            ((h >> 32) % 30306) as u32 + 1,
+            ((h >> 64) % 30322) as u32 + 1,
+        )
+    }
+
     pub fn new_random() -> WichmannHillPRNG {
         let current_time = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -34,6 +46,36 @@ impl WichmannHillPRNG {
     }
 }
 
+// The seed for a frame of an animation. Every frame gets a different, decorrelated seed, so noise
+// does not stay fixed on the screen while the camera or the objects move.
+pub fn frame_seed(seed: u128, frame: u64) -> u128 {
+    hash(seed, frame as u128)
+}
+
+// The seed for a pass of a progressive render. Every pass draws new samples, so the passes add
+// up instead of repeating the same noise. The index is complemented, so a pass does not get the
+// seed of the frame with the same number.
+pub fn pass_seed(seed: u128, pass: u64) -> u128 {
+    hash(seed, !(pass as u128))
+}
+
+// The seed for a tile of an image that is rendered on its own, e.g. on another machine. Pixels
+// draw their samples by their index in the tile, so tiles need seeds of their own to not repeat
+// the same noise. The index lies in the upper half, apart from those of frames and passes.
+pub fn tile_seed(seed: u128, tile: u32) -> u128 {
+    hash(seed, (tile as u128 + 1) << 64)
+}
+
+fn hash(seed: u128, index: u128) -> u128 {
+    let mut h = seed ^ index.wrapping_mul(0x9e3779b97f4a7c15f39cc0605cedc835);
+    h ^= h >> 67;
+    h = h.wrapping_mul(0xff51afd7ed558ccdc4ceb9fe1a85ec53);
+    h ^= h >> 59;
+    h = h.wrapping_mul(0xc4ceb9fe1a85ec53ff51afd7ed558ccd);
+    h ^= h >> 61;
+    h
+}
+
 impl RandomNumberGenerator<f32> for WichmannHillPRNG {
     fn next_random(&mut self) -> f32 {
         let s1 = self.s1 as f32;
@@ -65,9 +107,11 @@ impl RandomNumberGenerator<f64> for WichmannHillPRNG {
 }
 
 impl RandomNumberGenerator<usize> for WichmannHillPRNG {
+    // Scaled to the range of u32 only. Scaled to the range of usize, the lower bits are beyond the
+    // precision of f64 and always zero, so the numbers were all even.
     fn next_random(&mut self) -> usize {
         let v = (<WichmannHillPRNG as RandomNumberGenerator<f64>>::next_random(self)
-            * (usize::MAX as f64)) as usize;
+            * (u32::MAX as f64)) as usize;
         v
     }
 }
@@ -0,0 +1,152 @@
+use traits::{FloatingPoint, Zero};
+
+// Decides when a pixel has enough samples. Every pixel takes at least the minimal number of
+// samples and stops once the standard error of its mean falls below the threshold, or at the
+// maximal number of samples. Flat pixels, e.g. of the background, stop early, while noisy ones
+// get more samples.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AdaptiveSampling<V> {
+    pub min_samples: usize,
+    pub max_samples: usize,
+    pub threshold: V,
+}
+
+impl<V: FloatingPoint> AdaptiveSampling<V> {
+    pub fn new(min_samples: usize, max_samples: usize, threshold: V) -> AdaptiveSampling<V> {
+        // The variance needs at least two samples.
+        let min_samples = min_samples.max(2);
+        AdaptiveSampling {
+            min_samples,
+            max_samples: max_samples.max(min_samples),
+            threshold,
+        }
+    }
+
+    pub fn is_done(&self, statistics: &SampleStatistics<V>) -> bool {
+        statistics.samples >= self.max_samples
+            || (statistics.samples >= self.min_samples
+                && statistics.variance_of_mean() <= self.threshold * self.threshold)
+    }
+}
+
+// The running mean and variance of a series of samples, see Welford, "Note on a Method for
+// Calculating Corrected Sums of Squares and Products". It stays accurate for many samples with a
+// large mean, where the difference of the sum of squares and the squared sum cancels out.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SampleStatistics<V> {
+    samples: usize,
+    count: V,
+    mean: V,
+    squares: V,
+}
+
+impl<V: FloatingPoint> SampleStatistics<V> {
+    pub fn new() -> SampleStatistics<V> {
+        SampleStatistics {
+            samples: 0,
+            count: Zero::zero(),
+            mean: Zero::zero(),
+            squares: Zero::zero(),
+        }
+    }
+
+    pub fn add(&mut self, value: V) {
+        self.samples += 1;
+        self.count += V::one();
+        let delta = value - self.mean;
+        self.mean += delta / self.count;
+        self.squares += delta * (value - self.mean);
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn mean(&self) -> V {
+        self.mean
+    }
+
+    // The unbiased variance of the samples.
+    pub fn variance(&self) -> V {
+        if self.samples > 1 {
+            self.squares / (self.count - V::one())
+        } else {
+            Zero::zero()
+        }
+    }
+
+    // The variance of the mean, which shrinks with the number of samples.
+    pub fn variance_of_mean(&self) -> V {
+        if self.samples > 0 {
+            self.variance() / self.count
+        } else {
+            Zero::zero()
+        }
+    }
+}
+
+impl<V: FloatingPoint> Default for SampleStatistics<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! sample_statistics {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let mut statistics = SampleStatistics::<$type>::new();
+                assert_eq!(statistics.variance(), 0.0);
+
+                for value in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+                    statistics.add(value);
+                }
+
+                assert_eq!(statistics.samples(), 8);
+                assert!((statistics.mean() - 5.0).abs() < 0.0001);
+                assert!((statistics.variance() - 32.0 / 7.0).abs() < 0.0001);
+                assert!((statistics.variance_of_mean() - 4.0 / 7.0).abs() < 0.0001);
+            }
+        };
+    }
+
+    sample_statistics! { f32, sample_statistics_f32 }
+    sample_statistics! { f64, sample_statistics_f64 }
+
+    macro_rules! adaptive_sampling_stops {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let adaptive = AdaptiveSampling::<$type>::new(4, 16, 0.1);
+
+                // A flat pixel stops after the minimal number of samples.
+                let mut flat = SampleStatistics::new();
+                for _ in 0..3 {
+                    flat.add(0.5);
+                }
+                assert!(!adaptive.is_done(&flat));
+                flat.add(0.5);
+                assert!(adaptive.is_done(&flat));
+
+                // A noisy one takes all samples.
+                let mut noisy = SampleStatistics::new();
+                for i in 0..15 {
+                    noisy.add((i % 2) as $type);
+                }
+                assert!(!adaptive.is_done(&noisy));
+                noisy.add(0.0);
+                assert!(adaptive.is_done(&noisy));
+
+                assert_eq!(AdaptiveSampling::<$type>::new(0, 1, 0.1).min_samples, 2);
+                assert_eq!(AdaptiveSampling::<$type>::new(8, 4, 0.1).max_samples, 8);
+            }
+        };
+    }
+
+    adaptive_sampling_stops! { f32, adaptive_sampling_stops_f32 }
+    adaptive_sampling_stops! { f64, adaptive_sampling_stops_f64 }
+}
@@ -0,0 +1,167 @@
+use math::Point2;
+use traits::{ConvenientNumber, FloatingPoint};
+
+use crate::split;
+
+// The opening of a camera lens. Out-of-focus highlights, the bokeh, take its shape. The blades of
+// a diaphragm form a regular polygon instead of a disc.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Aperture<V> {
+    Disc,
+    // The rotation is the angle of the first corner towards the x axis in radians.
+    Polygon { blades: usize, rotation: V },
+}
+
+impl<V> Aperture<V>
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    // Maps a point of the unit square uniformly onto the aperture, which fits into the unit
+    // circle.
+    pub fn map(&self, p: Point2<V>) -> Point2<V> {
+        match self {
+            Aperture::Disc => map_to_disc(p),
+            Aperture::Polygon { blades, rotation } => map_to_polygon(p, *blades, *rotation),
+        }
+    }
+}
+
+// The concentric mapping of Shirley and Chiu, "A Low Distortion Map Between Disk and Square".
+// Neighboring points stay close to each other, so stratified patterns remain stratified.
+pub fn map_to_disc<V>(p: Point2<V>) -> Point2<V>
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    let one = V::one();
+    let two: V = 2u16.into();
+    let x = two * p.x - one;
+    let y = two * p.y - one;
+
+    let (r, phi) = if x > -y {
+        if x > y {
+            (x, y / x)
+        } else {
+            (y, two - x / y)
+        }
+    } else if x < y {
+        (-x, 4u16.into() + y / x)
+    } else if y != V::zero() {
+        (-y, 6u16.into() - x / y)
+    } else {
+        (-y, V::zero())
+    };
+
+    let phi = phi * V::PI / 4u16.into();
+
+    Point2::new(r * phi.cos(), r * phi.sin())
+}
+
+// Maps the x coordinate to one of the triangles between the center and two neighboring corners of
+// a regular polygon and the remainder uniformly into that triangle.
+pub fn map_to_polygon<V>(p: Point2<V>, corners: usize, rotation: V) -> Point2<V>
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    assert!(corners >= 3);
+
+    let corner = |index: usize| {
+        let angle = rotation + (V::PI + V::PI) * (index as u16).into() / (corners as u16).into();
+        Point2::new(angle.cos(), angle.sin())
+    };
+
+    let (triangle, x) = split(p.x, corners);
+    let a = corner(triangle);
+    let b = corner(triangle + 1);
+
+    let s = x.sqrt();
+    let wa = s * (V::one() - p.y);
+    let wb = s * p.y;
+
+    Point2::new(a.x * wa + b.x * wb, a.y * wa + b.y * wb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! map_to_disc_matches_pattern_mapping {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                use crate::{PatternMapping, SamplingPattern};
+
+                let points: Vec<Point2<$type>> = (0..=8)
+                    .flat_map(|y| {
+                        (0..=8).map(move |x| Point2::new(x as $type / 8.0, y as $type / 8.0))
+                    })
+                    .collect();
+                let pattern = SamplingPattern::new(points.clone()).mapped_to_disc();
+
+                for (i, point) in points.iter().enumerate() {
+                    let mapped = map_to_disc(*point);
+                    assert!((mapped.x - pattern[i].x).abs() < 0.00001);
+                    assert!((mapped.y - pattern[i].y).abs() < 0.00001);
+                    assert!(mapped.x * mapped.x + mapped.y * mapped.y <= 1.00001);
+                }
+            }
+        };
+    }
+
+    map_to_disc_matches_pattern_mapping! { f32, map_to_disc_matches_pattern_mapping_f32 }
+    map_to_disc_matches_pattern_mapping! { f64, map_to_disc_matches_pattern_mapping_f64 }
+
+    macro_rules! map_to_polygon {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let hexagon = Aperture::<$type>::Polygon {
+                    blades: 6,
+                    rotation: 0.0,
+                };
+
+                // The apothem of a regular hexagon with a circumradius of one.
+                let apothem = (3.0 as $type).sqrt() / 2.0;
+
+                let mut sum_x: $type = 0.0;
+                let mut sum_y: $type = 0.0;
+                let n = 32;
+                for y in 0..n {
+                    for x in 0..n {
+                        let p = hexagon.map(Point2::new(
+                            (x as $type + 0.5) / n as $type,
+                            (y as $type + 0.5) / n as $type,
+                        ));
+
+                        // Inside of all six edges.
+                        for edge in 0..6 {
+                            let angle = std::f64::consts::PI as $type / 3.0 * (edge as $type + 0.5);
+                            assert!(p.x * angle.cos() + p.y * angle.sin() <= apothem + 0.00001);
+                        }
+                        sum_x += p.x;
+                        sum_y += p.y;
+                    }
+                }
+
+                // Uniform, so the samples are centered around the origin.
+                let count = (n * n) as $type;
+                assert!((sum_x / count).abs() < 0.01);
+                assert!((sum_y / count).abs() < 0.01);
+
+                // The first corner is rotated by the rotation of the aperture.
+                let corner = Aperture::<$type>::Polygon {
+                    blades: 5,
+                    rotation: 1.0,
+                }
+                .map(Point2::new(0.19999, 0.0));
+                assert!((corner.x - (1.0 as $type).cos()).abs() < 0.001);
+                assert!((corner.y - (1.0 as $type).sin()).abs() < 0.001);
+            }
+        };
+    }
+
+    map_to_polygon! { f32, map_to_polygon_f32 }
+    map_to_polygon! { f64, map_to_polygon_f64 }
+}
@@ -0,0 +1,331 @@
+use math::{Point2, Vector2};
+use traits::{ConvenientNumber, FloatingPoint, One, Zero};
+
+// A piecewise constant distribution over a number of entries. Entries are drawn with a
+// probability proportional to their weight, either by a binary search over the cumulative
+// weights or, after building an alias table, in constant time. If all weights are zero, every
+// entry is equally likely.
+pub struct Distribution1D<V> {
+    weights: Vec<V>,
+    cdf: Vec<V>,
+    total: V,
+    alias_table: Option<Vec<(V, usize)>>,
+}
+
+impl<V> Distribution1D<V>
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    pub fn new(weights: Vec<V>) -> Distribution1D<V> {
+        assert!(!weights.is_empty());
+
+        let total = weights.iter().fold(V::zero(), |a, b| a + *b);
+        let mut sum = V::zero();
+        let cdf = weights
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| {
+                if total > V::zero() {
+                    sum += *weight;
+                    sum / total
+                } else {
+                    to_value::<V>(i + 1) / to_value(weights.len())
+                }
+            })
+            .collect();
+
+        Distribution1D {
+            weights,
+            cdf,
+            total,
+            alias_table: None,
+        }
+    }
+
+    // Builds an alias table with the method of Vose, afterwards sampling no longer depends on
+    // the number of entries.
+    pub fn with_alias_table(self) -> Distribution1D<V> {
+        let n = self.weights.len();
+        let one = V::one();
+
+        let mut table: Vec<(V, usize)> = (0..n)
+            .map(|i| (self.probability(i) * to_value(n), i))
+            .collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|i| table[*i].0 < one);
+
+        while let (Some(s), Some(l)) = (small.pop(), large.pop()) {
+            table[s].1 = l;
+            table[l].0 = table[l].0 + table[s].0 - one;
+            if table[l].0 < one {
+                small.push(l);
+            } else {
+                large.push(l);
+            }
+        }
+
+        // Whatever is left only misses one because of rounding errors.
+        for i in small.into_iter().chain(large) {
+            table[i] = (one, i);
+        }
+
+        Distribution1D {
+            alias_table: Some(table),
+            ..self
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    // The sum of all weights.
+    pub fn total(&self) -> V {
+        self.total
+    }
+
+    pub fn probability(&self, index: usize) -> V {
+        if self.total > V::zero() {
+            self.weights[index] / self.total
+        } else {
+            V::one() / to_value(self.weights.len())
+        }
+    }
+
+    // Draws an entry for a uniform sample in [0, 1]. Returns the index, its probability and the
+    // sample remapped to [0, 1], so it can be reused for further decisions.
+    pub fn sample_discrete(&self, u: V) -> (usize, V, V) {
+        let (index, remapped) = match &self.alias_table {
+            Some(table) => {
+                let one = V::one();
+                let (i, f) = split(u, table.len());
+                let (threshold, alias) = table[i];
+                if f < threshold || threshold >= one {
+                    (i, (f / threshold).clamp(V::zero(), one))
+                } else {
+                    (
+                        alias,
+                        ((f - threshold) / (one - threshold)).clamp(V::zero(), one),
+                    )
+                }
+            }
+            None => self.invert_cdf(u),
+        };
+
+        (index, self.probability(index), remapped)
+    }
+
+    // Draws a position in [0, 1] for a uniform sample, each entry covers an interval of equal
+    // width. Returns the position, the probability density there and the index of the entry.
+    pub fn sample_continuous(&self, u: V) -> (V, V, usize) {
+        let (index, _, offset) = self.sample_discrete(u);
+        let n = to_value::<V>(self.weights.len());
+
+        (
+            (to_value::<V>(index) + offset) / n,
+            self.probability(index) * n,
+            index,
+        )
+    }
+
+    // The probability density of a position in [0, 1].
+    pub fn density(&self, x: V) -> V {
+        let (index, _) = split(x, self.weights.len());
+        self.probability(index) * to_value(self.weights.len())
+    }
+
+    fn invert_cdf(&self, u: V) -> (usize, V) {
+        let index = self
+            .cdf
+            .partition_point(|c| *c <= u)
+            .min(self.cdf.len() - 1);
+        let lower = if index == 0 {
+            V::zero()
+        } else {
+            self.cdf[index - 1]
+        };
+        let width = self.cdf[index] - lower;
+
+        if width > V::zero() {
+            (index, ((u - lower) / width).clamp(V::zero(), V::one()))
+        } else {
+            (index, V::one().half())
+        }
+    }
+}
+
+// A piecewise constant distribution over the unit square, given by a grid of weights stored row
+// by row, e.g. the luminance of an image. A row is chosen first and then a column within it.
+pub struct Distribution2D<V> {
+    rows: Distribution1D<V>,
+    columns: Vec<Distribution1D<V>>,
+}
+
+impl<V> Distribution2D<V>
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    pub fn new(weights: &[V], size: Vector2<usize>) -> Distribution2D<V> {
+        assert_eq!(weights.len(), size.x * size.y);
+
+        let columns: Vec<Distribution1D<V>> = weights
+            .chunks(size.x)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let rows = Distribution1D::new(columns.iter().map(|c| c.total()).collect());
+
+        Distribution2D { rows, columns }
+    }
+
+    pub fn with_alias_tables(self) -> Distribution2D<V> {
+        Distribution2D {
+            rows: self.rows.with_alias_table(),
+            columns: self
+                .columns
+                .into_iter()
+                .map(|c| c.with_alias_table())
+                .collect(),
+        }
+    }
+
+    pub fn size(&self) -> Vector2<usize> {
+        Vector2::new(self.columns[0].len(), self.rows.len())
+    }
+
+    // Draws a position in the unit square, y grows with the row index. Returns the position and
+    // the probability density there.
+    pub fn sample_continuous(&self, u: Point2<V>) -> (Point2<V>, V) {
+        let (y, row_density, row) = self.rows.sample_continuous(u.y);
+        let (x, column_density, _) = self.columns[row].sample_continuous(u.x);
+
+        (Point2::new(x, y), row_density * column_density)
+    }
+
+    pub fn density(&self, p: Point2<V>) -> V {
+        let (row, _) = split(p.y, self.rows.len());
+        self.rows.density(p.y) * self.columns[row].density(p.x)
+    }
+}
+
+// Splits a coordinate in [0, 1] into the index of the cell it falls into and the position inside
+// of the cell.
+pub fn split<V>(coordinate: V, cells: usize) -> (usize, V)
+where
+    V: FloatingPoint + ConvenientNumber,
+    u16: Into<V>,
+{
+    let x = coordinate * to_value(cells);
+
+    let mut lower = 0;
+    let mut upper = cells - 1;
+    while lower < upper {
+        let middle = (lower + upper).div_ceil(2);
+        if to_value::<V>(middle) <= x {
+            lower = middle;
+        } else {
+            upper = middle - 1;
+        }
+    }
+
+    (lower, (x - to_value(lower)).clamp(Zero::zero(), One::one()))
+}
+
+fn to_value<V>(value: usize) -> V
+where
+    u16: Into<V>,
+{
+    (value as u16).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! distribution_1d_sample_discrete {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let weights: Vec<$type> = vec![1.0, 3.0, 0.0, 4.0];
+
+                for distribution in [
+                    Distribution1D::new(weights.clone()),
+                    Distribution1D::new(weights.clone()).with_alias_table(),
+                ] {
+                    assert_eq!(distribution.total(), 8.0);
+                    assert_eq!(distribution.probability(1), 0.375);
+
+                    let mut counts = [0; 4];
+                    for i in 0..800 {
+                        let u = (i as $type + 0.5) / 800.0;
+                        let (index, probability, remapped) = distribution.sample_discrete(u);
+                        assert_eq!(probability, distribution.probability(index));
+                        assert!((0.0..=1.0).contains(&remapped));
+                        counts[index] += 1;
+                    }
+                    assert_eq!(counts, [100, 300, 0, 400]);
+                }
+            }
+        };
+    }
+
+    distribution_1d_sample_discrete! { f32, distribution_1d_sample_discrete_f32 }
+    distribution_1d_sample_discrete! { f64, distribution_1d_sample_discrete_f64 }
+
+    macro_rules! distribution_1d_without_weights {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let distribution = Distribution1D::new(vec![0.0 as $type; 4]);
+
+                assert_eq!(distribution.probability(3), 0.25);
+                assert_eq!(distribution.sample_discrete(0.6).0, 2);
+                assert_eq!(distribution.density(0.9), 1.0);
+            }
+        };
+    }
+
+    distribution_1d_without_weights! { f32, distribution_1d_without_weights_f32 }
+    distribution_1d_without_weights! { f64, distribution_1d_without_weights_f64 }
+
+    macro_rules! distribution_2d_sample_continuous {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                let weights: Vec<$type> = vec![1.0, 0.0, 2.0, 1.0, 3.0, 1.0];
+                let size = Vector2::new(3, 2);
+
+                for distribution in [
+                    Distribution2D::new(&weights, size),
+                    Distribution2D::new(&weights, size).with_alias_tables(),
+                ] {
+                    assert_eq!(distribution.size(), size);
+
+                    let mut mean_density = 0.0;
+                    for y in 0..6 {
+                        for x in 0..6 {
+                            let u = Point2::new((x as $type + 0.5) / 6.0, (y as $type + 0.5) / 6.0);
+                            let (p, density) = distribution.sample_continuous(u);
+                            assert!((density - distribution.density(p)).abs() < 0.0001);
+                            mean_density += distribution.density(u) / 36.0;
+                        }
+                    }
+                    assert!((mean_density - 1.0).abs() < 0.0001);
+
+                    // The empty cell is never drawn.
+                    let (p, _) = distribution.sample_continuous(Point2::new(0.3, 0.1));
+                    assert!(p.x < 1.0 / 3.0 || p.x > 2.0 / 3.0);
+                    assert_eq!(distribution.density(Point2::new(0.5, 0.25)), 0.0);
+                }
+            }
+        };
+    }
+
+    distribution_2d_sample_continuous! { f32, distribution_2d_sample_continuous_f32 }
+    distribution_2d_sample_continuous! { f64, distribution_2d_sample_continuous_f64 }
+}
@@ -1,5 +1,15 @@
+pub mod adaptive;
+pub mod aperture;
+pub mod distribution;
+pub mod multiple_importance;
 pub mod sampling_pattern;
 pub mod sampling_pattern_set;
+pub mod triangle_mesh_sampler;
 
+pub use adaptive::*;
+pub use aperture::*;
+pub use distribution::*;
+pub use multiple_importance::*;
 pub use sampling_pattern::*;
 pub use sampling_pattern_set::*;
+pub use triangle_mesh_sampler::*;
@@ -0,0 +1,56 @@
+use traits::{FloatingPoint, Zero};
+
+// Weights for combining the estimates of several sampling strategies, see Veach and Guibas,
+// "Optimally Combining Sampling Techniques for Monte Carlo Rendering". A sample drawn with one
+// strategy is weighted by its density relative to the densities the other strategies would have
+// drawn it with, each multiplied by the number of samples the strategy takes.
+
+// The balance heuristic for a sample of strategy a, which takes count_a samples, against strategy
+// b. The weights of both strategies add up to one.
+pub fn balance_heuristic<V: FloatingPoint>(count_a: V, pdf_a: V, count_b: V, pdf_b: V) -> V {
+    let a = count_a * pdf_a;
+    let b = count_b * pdf_b;
+
+    if a + b > V::zero() {
+        a / (a + b)
+    } else {
+        Zero::zero()
+    }
+}
+
+// The power heuristic with an exponent of two, which reduces the variance further when one of the
+// strategies is a lot better than the other.
+pub fn power_heuristic<V: FloatingPoint>(count_a: V, pdf_a: V, count_b: V, pdf_b: V) -> V {
+    let a = count_a * pdf_a;
+    let b = count_b * pdf_b;
+
+    if a * a + b * b > V::zero() {
+        a * a / (a * a + b * b)
+    } else {
+        Zero::zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! heuristics {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                assert_eq!(balance_heuristic::<$type>(1.0, 3.0, 1.0, 1.0), 0.75);
+                assert_eq!(balance_heuristic::<$type>(2.0, 1.0, 1.0, 2.0), 0.5);
+                assert_eq!(power_heuristic::<$type>(1.0, 3.0, 1.0, 1.0), 0.9);
+                assert_eq!(power_heuristic::<$type>(1.0, 0.0, 1.0, 0.0), 0.0);
+
+                let a = balance_heuristic::<$type>(1.0, 0.3, 4.0, 0.7);
+                let b = balance_heuristic::<$type>(4.0, 0.7, 1.0, 0.3);
+                assert!((a + b - 1.0).abs() < 0.0001);
+            }
+        };
+    }
+
+    heuristics! { f32, heuristics_f32 }
+    heuristics! { f64, heuristics_f64 }
+}
@@ -3,6 +3,7 @@ use std::ops::Index;
 use math::{Point2, Point3};
 use random::RandomNumberGenerator;
 
+#[derive(Clone)]
 pub struct SamplingPattern<T> {
     points: Vec<T>,
 }
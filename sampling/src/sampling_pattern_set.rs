@@ -7,6 +7,7 @@ use traits::RadicalInverse;
 
 use super::{PatternMapping, SamplingPattern};
 
+#[derive(Clone)]
 pub struct SamplingPatternSet<T> {
     patterns: Vec<SamplingPattern<T>>,
 }
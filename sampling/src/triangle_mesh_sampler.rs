@@ -0,0 +1,128 @@
+use std::ops::Div;
+
+use math::geometry::triangle::Triangle3Mesh;
+use math::geometry::{SampleSurface, SurfacePoint};
+use math::{Point2, Point3, Vector3};
+use traits::{ConvenientNumber, FloatingPoint, One, SelfMulNumber};
+
+use super::Distribution1D;
+
+// Samples the surface of a mesh uniformly. A triangle is chosen with a probability proportional
+// to its area, the distribution of the areas is computed once when the sampler is created.
+pub struct Triangle3MeshSampler<T: Div> {
+    mesh: Triangle3Mesh<T>,
+    distribution: Distribution1D<<T as Div>::Output>,
+    center: Point3<T>,
+}
+
+impl<T> Triangle3MeshSampler<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    u16: Into<<T as Div>::Output>,
+{
+    pub fn new(mesh: Triangle3Mesh<T>) -> Triangle3MeshSampler<T> {
+        let mut areas = Vec::with_capacity(mesh.faces().len());
+        let mut moment = Vector3::new(T::zero(), T::zero(), T::zero());
+        let origin = mesh.triangle(&mesh.faces()[0]).centroid();
+
+        for face in mesh.faces() {
+            let triangle = mesh.triangle(face);
+            let area = triangle.area();
+
+            moment += (triangle.centroid() - origin) * area;
+            areas.push(area);
+        }
+
+        let distribution = Distribution1D::new(areas);
+        let center = origin + moment / distribution.total();
+
+        Triangle3MeshSampler {
+            mesh,
+            distribution,
+            center,
+        }
+    }
+
+    pub fn mesh(&self) -> &Triangle3Mesh<T> {
+        &self.mesh
+    }
+
+    pub fn area(&self) -> <T as Div>::Output {
+        self.distribution.total()
+    }
+
+    // The center of the surface, each triangle is weighted by its area.
+    pub fn center(&self) -> Point3<T> {
+        self.center
+    }
+}
+
+impl<T> SampleSurface<T> for Triangle3MeshSampler<T>
+where
+    T: SelfMulNumber<<T as Div>::Output>,
+    <T as Div>::Output: FloatingPoint + ConvenientNumber,
+    u16: Into<<T as Div>::Output>,
+{
+    fn sample_surface(
+        &self,
+        u: Point2<<T as Div>::Output>,
+    ) -> (SurfacePoint<T>, <T as Div>::Output) {
+        // The remapped sample is reused for sampling the chosen triangle.
+        let (index, _, ux) = self.distribution.sample_discrete(u.x);
+        let triangle = self.mesh.triangle(&self.mesh.faces()[index]);
+        let (sp, _) = triangle.sample_surface(Point2::new(ux, u.y));
+
+        (sp, <T as Div>::Output::one() / self.area())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use math::geometry::triangle::Face3;
+    use math::Normal3;
+
+    macro_rules! sample_triangle_3_mesh_surface {
+        ($type: ty, $name: ident) => {
+            #[test]
+            fn $name() {
+                // A triangle with area 1 next to one with area 3.
+                let vertices = vec![
+                    Point3::new(0 as $type, 0 as $type, 0 as $type),
+                    Point3::new(2 as $type, 0 as $type, 0 as $type),
+                    Point3::new(0 as $type, 1 as $type, 0 as $type),
+                    Point3::new(10 as $type, 0 as $type, 0 as $type),
+                    Point3::new(13 as $type, 0 as $type, 0 as $type),
+                    Point3::new(10 as $type, 2 as $type, 0 as $type),
+                ];
+                let normals = vec![Normal3::new(0 as $type, 0 as $type, 1 as $type)];
+                let uvs = vec![Point2::new(0 as $type, 0 as $type)];
+                let faces = vec![
+                    Face3::new(0, 1, 2, 0, 0, 0, 0, 0, 0),
+                    Face3::new(3, 4, 5, 0, 0, 0, 0, 0, 0),
+                ];
+                let triangle_mesh = Triangle3Mesh::new(vertices, normals, uvs, faces);
+
+                let sampler = Triangle3MeshSampler::new(triangle_mesh);
+                assert_eq!(sampler.area(), 4 as $type);
+                let center = sampler.center();
+                assert!((center.x - 101.0 / 12.0).abs() < 0.0001);
+                assert!((center.y - 7.0 / 12.0).abs() < 0.0001);
+
+                let (first, pdf) = sampler.sample_surface(Point2::new(0.2 as $type, 0.5 as $type));
+                let (second, _) = sampler.sample_surface(Point2::new(0.3 as $type, 0.5 as $type));
+                let (last, _) = sampler.sample_surface(Point2::new(1 as $type, 1 as $type));
+
+                assert_eq!(pdf, 0.25 as $type);
+                assert!(first.p.x < 2 as $type);
+                assert!(second.p.x >= 10 as $type);
+                assert_eq!(last.p, Point3::new(13 as $type, 0 as $type, 0 as $type));
+            }
+        };
+    }
+
+    sample_triangle_3_mesh_surface! { f32, sample_triangle_3_mesh_surface_f32 }
+    sample_triangle_3_mesh_surface! { f64, sample_triangle_3_mesh_surface_f64 }
+}
@@ -0,0 +1,294 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display, LowerExp, UpperExp};
+use std::iter::Sum;
+use std::num::ParseFloatError;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, RemAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+use super::{DivEuclid, Number, One, RemEuclid, Zero};
+
+// A 16 bit float, e.g. for the channels of images that need half of the memory of 32 bit floats.
+// Arithmetic is done in 32 bit floats and rounded to the nearest 16 bit float, so it is not faster
+// than with 32 bit floats and loses precision with every operation.
+#[derive(Clone, Copy, Default)]
+pub struct F16(u16);
+
+impl F16 {
+    pub const MAX: F16 = F16(0x7bff);
+    pub const MIN: F16 = F16(0xfbff);
+
+    pub fn from_bits(bits: u16) -> F16 {
+        F16(bits)
+    }
+
+    pub fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    pub fn from_le_bytes(bytes: [u8; 2]) -> F16 {
+        F16(u16::from_le_bytes(bytes))
+    }
+
+    pub fn to_le_bytes(self) -> [u8; 2] {
+        self.0.to_le_bytes()
+    }
+
+    // The nearest 16 bit float, with ties rounded to the even one. Values beyond the largest one
+    // become infinite, the smallest ones subnormal or zero.
+    pub fn from_f32(value: f32) -> F16 {
+        let bits = value.to_bits();
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exponent = ((bits >> 23) & 0xff) as i32;
+        let mantissa = bits & 0x7f_ffff;
+
+        if exponent == 0xff {
+            let nan = if mantissa != 0 { 0x200 } else { 0 };
+            return F16(sign | 0x7c00 | nan);
+        }
+
+        let exponent = exponent - 127 + 15;
+        if exponent >= 0x1f {
+            return F16(sign | 0x7c00);
+        }
+
+        let (half, shift, mantissa) = if exponent > 0 {
+            (((exponent as u32) << 10) | (mantissa >> 13), 13, mantissa)
+        } else if exponent >= -10 {
+            let mantissa = mantissa | 0x80_0000;
+            let shift = (14 - exponent) as u32;
+            (mantissa >> shift, shift, mantissa)
+        } else {
+            return F16(sign);
+        };
+        let rest = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let round_up = rest > halfway || (rest == halfway && half & 1 == 1);
+        // A carry out of the mantissa correctly increases the exponent.
+        F16(sign | (half + round_up as u32) as u16)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        let sign = ((self.0 & 0x8000) as u32) << 16;
+        let exponent = ((self.0 >> 10) & 0x1f) as u32;
+        let mantissa = (self.0 & 0x3ff) as u32;
+
+        let bits = match exponent {
+            0 if mantissa == 0 => sign,
+            // Subnormal values are normal ones as 32 bit floats.
+            0 => {
+                let shift = mantissa.leading_zeros() - 21;
+                let mantissa = (mantissa << shift) & 0x3ff;
+                sign | ((127 - 15 + 1 - shift) << 23) | (mantissa << 13)
+            }
+            0x1f => sign | 0x7f80_0000 | (mantissa << 13),
+            _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+        };
+        f32::from_bits(bits)
+    }
+}
+
+impl From<f32> for F16 {
+    fn from(value: f32) -> F16 {
+        F16::from_f32(value)
+    }
+}
+
+impl From<F16> for f32 {
+    fn from(value: F16) -> f32 {
+        value.to_f32()
+    }
+}
+
+impl From<F16> for f64 {
+    fn from(value: F16) -> f64 {
+        value.to_f32() as f64
+    }
+}
+
+impl From<bool> for F16 {
+    fn from(value: bool) -> F16 {
+        F16::from_f32(value as u8 as f32)
+    }
+}
+
+impl PartialEq for F16 {
+    fn eq(&self, other: &F16) -> bool {
+        self.to_f32() == other.to_f32()
+    }
+}
+
+impl PartialOrd for F16 {
+    fn partial_cmp(&self, other: &F16) -> Option<Ordering> {
+        self.to_f32().partial_cmp(&other.to_f32())
+    }
+}
+
+impl Debug for F16 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(&self.to_f32(), f)
+    }
+}
+
+impl Display for F16 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&self.to_f32(), f)
+    }
+}
+
+impl LowerExp for F16 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        LowerExp::fmt(&self.to_f32(), f)
+    }
+}
+
+impl UpperExp for F16 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        UpperExp::fmt(&self.to_f32(), f)
+    }
+}
+
+impl FromStr for F16 {
+    type Err = ParseFloatError;
+
+    fn from_str(s: &str) -> Result<F16, ParseFloatError> {
+        s.parse::<f32>().map(F16::from_f32)
+    }
+}
+
+macro_rules! implement_operators_for_f16 {
+    ($($trait: ident $function: ident $assign_trait: ident $assign_function: ident $operator: tt)*) => {
+        $(
+        impl $trait for F16 {
+            type Output = F16;
+
+            fn $function(self, rhs: F16) -> F16 {
+                F16::from_f32(self.to_f32() $operator rhs.to_f32())
+            }
+        }
+
+        impl<'a> $trait<&'a F16> for F16 {
+            type Output = F16;
+
+            fn $function(self, rhs: &'a F16) -> F16 {
+                self $operator *rhs
+            }
+        }
+
+        impl $assign_trait for F16 {
+            fn $assign_function(&mut self, rhs: F16) {
+                *self = *self $operator rhs;
+            }
+        }
+
+        impl<'a> $assign_trait<&'a F16> for F16 {
+            fn $assign_function(&mut self, rhs: &'a F16) {
+                *self = *self $operator *rhs;
+            }
+        }
+        )*
+    }
+}
+
+implement_operators_for_f16! {
+    Add add AddAssign add_assign +
+    Sub sub SubAssign sub_assign -
+    Mul mul MulAssign mul_assign *
+    Div div DivAssign div_assign /
+    Rem rem RemAssign rem_assign %
+}
+
+impl DivEuclid for F16 {
+    type Output = F16;
+
+    fn div_euclid(self, rhs: F16) -> F16 {
+        F16::from_f32(self.to_f32().div_euclid(rhs.to_f32()))
+    }
+}
+
+impl RemEuclid for F16 {
+    type Output = F16;
+
+    fn rem_euclid(self, rhs: F16) -> F16 {
+        F16::from_f32(self.to_f32().rem_euclid(rhs.to_f32()))
+    }
+}
+
+impl Sum for F16 {
+    fn sum<I: Iterator<Item = F16>>(iter: I) -> F16 {
+        iter.fold(F16::zero(), |a, b| a + b)
+    }
+}
+
+impl<'a> Sum<&'a F16> for F16 {
+    fn sum<I: Iterator<Item = &'a F16>>(iter: I) -> F16 {
+        iter.fold(F16::zero(), |a, b| a + b)
+    }
+}
+
+impl Zero for F16 {
+    fn zero() -> F16 {
+        F16(0)
+    }
+}
+
+impl One for F16 {
+    fn one() -> F16 {
+        F16(0x3c00)
+    }
+}
+
+impl Number for F16 {
+    const MAX: F16 = F16::MAX;
+    const MIN: F16 = F16::MIN;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_from_f32() {
+        let bits = |value: f32| F16::from_f32(value).to_bits();
+        assert_eq!(bits(0.0), 0x0000);
+        assert_eq!(bits(-0.0), 0x8000);
+        assert_eq!(bits(1.0), 0x3c00);
+        assert_eq!(bits(0.5), 0x3800);
+        assert_eq!(bits(-2.0), 0xc000);
+        assert_eq!(bits(65504.0), 0x7bff);
+        assert_eq!(bits(1e6), 0x7c00);
+        assert_eq!(bits(f32::INFINITY), 0x7c00);
+        assert_eq!(bits(f32::NAN) & 0x7e00, 0x7e00);
+        assert_eq!(bits(2.0f32.powi(-24)), 0x0001);
+        assert_eq!(bits(2.0f32.powi(-26)), 0x0000);
+        assert_eq!(bits(1.0 + 2.0f32.powi(-11)), 0x3c00);
+        assert_eq!(bits(1.0 + 3.0 * 2.0f32.powi(-11)), 0x3c02);
+    }
+
+    #[test]
+    fn convert_to_f32() {
+        for bits in (0..=u16::MAX).filter(|bits| bits & 0x7c00 != 0x7c00) {
+            let value = F16::from_bits(bits);
+            assert_eq!(F16::from_f32(value.to_f32()).to_bits(), bits);
+        }
+        assert_eq!(F16::from_bits(0x0001).to_f32(), 2.0f32.powi(-24));
+        assert_eq!(F16::from_bits(0xfc00).to_f32(), f32::NEG_INFINITY);
+        assert!(F16::from_bits(0x7e00).to_f32().is_nan());
+        assert_eq!(F16::MAX.to_f32(), 65504.0);
+        assert_eq!(F16::MIN.to_f32(), -65504.0);
+    }
+
+    #[test]
+    fn calculate_with_f16() {
+        let a = F16::from_f32(1.5);
+        let b = F16::from_f32(0.25);
+
+        assert_eq!(a + b, F16::from_f32(1.75));
+        assert_eq!(a - b, F16::from_f32(1.25));
+        assert_eq!(a * b, F16::from_f32(0.375));
+        assert_eq!(a / b, F16::from_f32(6.0));
+        assert_eq!(F16::from_f32(-0.0), F16::zero());
+        assert!(b < a);
+        assert_eq!([a, b, F16::one()].iter().sum::<F16>(), F16::from_f32(2.75));
+        assert_eq!("0.5".parse::<F16>(), Ok(F16::from_f32(0.5)));
+    }
+}
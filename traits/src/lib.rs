@@ -91,6 +91,7 @@ macro_rules! create_and_implement_proxy_trait {
 
 pub mod convenient_number;
 pub mod floating_point;
+pub mod half_float;
 pub mod number;
 pub mod number_with_size;
 pub mod signed_number;
@@ -99,6 +100,7 @@ use std::num::NonZeroI32;
 
 pub use convenient_number::*;
 pub use floating_point::*;
+pub use half_float::F16;
 pub use number::*;
 pub use number_with_size::*;
 pub use signed_number::*;
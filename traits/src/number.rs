@@ -98,6 +98,8 @@ pub trait Number<N=Self>: DivEuclid
                 + for<'a> Sum<&'a Self>
                 + UpperExp
                 + Zero
+                + Send
+                + Sync
                 + Sized  {
     const MAX: Self;
     const MIN: Self;
@@ -13,13 +13,15 @@ use traits::{
 pub mod angle;
 pub mod area;
 pub mod length;
+pub mod photometry;
 pub mod prefix;
+pub mod radiometry;
 pub mod second_moment_of_area;
 pub mod volume;
 
 use prefix::Prefix;
 
-pub trait Unit: Debug + PartialEq + PartialOrd + Copy + Clone {
+pub trait Unit: Debug + PartialEq + PartialOrd + Copy + Clone + Send + Sync {
     const UNIT: &'static str;
 }
 
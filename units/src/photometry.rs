@@ -0,0 +1,58 @@
+use std::ops::Div;
+
+use super::prefix::None;
+use super::radiometry::{Watt, WattPerSteradian};
+use super::ValueWithPrefixAndUnit;
+
+// Lumen per watt of monochromatic light at 555 nm, where the eye is most sensitive. Radiometric
+// and photometric quantities are converted with it, as if all light was emitted at this
+// wavelength.
+pub const LUMINOUS_EFFICACY: u16 = 683;
+
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct LumenUnit;
+
+impl super::Unit for LumenUnit {
+    const UNIT: &'static str = "lm";
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct CandelaUnit;
+
+impl super::Unit for CandelaUnit {
+    const UNIT: &'static str = "cd";
+}
+
+// Luminous flux, the power emitted by a light weighted by the sensitivity of the eye.
+pub type Lumen<T> = ValueWithPrefixAndUnit<T, None, LumenUnit>;
+
+// Luminous intensity, the luminous flux emitted into a solid angle.
+pub type Candela<T> = ValueWithPrefixAndUnit<T, None, CandelaUnit>;
+
+impl<T> Lumen<T> {
+    // The intensity of a light that spreads its flux evenly over a solid angle in steradian.
+    pub fn per_solid_angle(self, solid_angle: T) -> Candela<<T as Div>::Output>
+    where
+        T: Div,
+    {
+        Candela::new(self.value / solid_angle)
+    }
+
+    pub fn to_watt(self) -> Watt<<T as Div>::Output>
+    where
+        T: Div,
+        u16: Into<T>,
+    {
+        Watt::new(self.value / LUMINOUS_EFFICACY.into())
+    }
+}
+
+impl<T> Candela<T> {
+    pub fn to_watt_per_steradian(self) -> WattPerSteradian<<T as Div>::Output>
+    where
+        T: Div,
+        u16: Into<T>,
+    {
+        WattPerSteradian::new(self.value / LUMINOUS_EFFICACY.into())
+    }
+}
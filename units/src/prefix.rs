@@ -1,6 +1,6 @@
 use std::fmt::Debug;
 
-pub trait Prefix: Debug + PartialEq + PartialOrd + Clone + Copy {
+pub trait Prefix: Debug + PartialEq + PartialOrd + Clone + Copy + Send + Sync {
     const NUMERATOR: u64;
     const DENOMINATOR: u64;
     const PREFIX: &'static str;
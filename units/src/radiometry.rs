@@ -0,0 +1,53 @@
+use std::ops::{Div, Mul};
+
+use super::photometry::{Candela, Lumen, LUMINOUS_EFFICACY};
+use super::prefix::None;
+use super::ValueWithPrefixAndUnit;
+
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct WattUnit;
+
+impl super::Unit for WattUnit {
+    const UNIT: &'static str = "W";
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct WattPerSteradianUnit;
+
+impl super::Unit for WattPerSteradianUnit {
+    const UNIT: &'static str = "W/sr";
+}
+
+// Radiant flux, the power emitted by a light.
+pub type Watt<T> = ValueWithPrefixAndUnit<T, None, WattUnit>;
+
+// Radiant intensity, the power emitted into a solid angle.
+pub type WattPerSteradian<T> = ValueWithPrefixAndUnit<T, None, WattPerSteradianUnit>;
+
+impl<T> Watt<T> {
+    // The intensity of a light that spreads its power evenly over a solid angle in steradian.
+    pub fn per_solid_angle(self, solid_angle: T) -> WattPerSteradian<<T as Div>::Output>
+    where
+        T: Div,
+    {
+        WattPerSteradian::new(self.value / solid_angle)
+    }
+
+    pub fn to_lumen(self) -> Lumen<<T as Mul>::Output>
+    where
+        T: Mul,
+        u16: Into<T>,
+    {
+        Lumen::new(self.value * LUMINOUS_EFFICACY.into())
+    }
+}
+
+impl<T> WattPerSteradian<T> {
+    pub fn to_candela(self) -> Candela<<T as Mul>::Output>
+    where
+        T: Mul,
+        u16: Into<T>,
+    {
+        Candela::new(self.value * LUMINOUS_EFFICACY.into())
+    }
+}